@@ -0,0 +1,65 @@
+//! Run with `cargo bench --features bench`, or `cargo bench --features bench -- <name>` for one
+//! benchmark. Criterion keeps the last results in `target/criterion` and reports the change against
+//! them, so run it before and after a change to show what it gained.
+
+use std::{hint::black_box, path::Path};
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use miro_pdf::bench::{BenchDocument, dark_mode_shader};
+
+/// A page of mostly text and one with links on several pages
+const DOCUMENTS: [&str; 2] = ["assets/text-copy-test.pdf", "assets/links.pdf"];
+
+fn open(path: &str) -> BenchDocument {
+    BenchDocument::open(Path::new(path)).unwrap()
+}
+
+fn draw(c: &mut Criterion) {
+    let mut group = c.benchmark_group("draw_page");
+    for path in DOCUMENTS {
+        let mut doc = open(path);
+        // The whole page fitting in the window, and zoomed in so that only part of it is drawn
+        for scale in [1.0, 3.0] {
+            doc.set_view(scale, false, 1920.0, 1080.0);
+            group.bench_with_input(BenchmarkId::new(path, scale), &scale, |b, _| {
+                b.iter(|| black_box(doc.draw_first_page().unwrap()))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn dark_mode(c: &mut Criterion) {
+    let mut doc = open(DOCUMENTS[0]);
+    let colors = doc.dark_mode_colors();
+    doc.set_view(2.0, false, 1920.0, 1080.0);
+    let drawn = doc.draw_first_page().unwrap();
+    c.bench_function("dark_mode_shader", |b| {
+        b.iter_batched_ref(
+            || drawn.clone(),
+            |pixels| dark_mode_shader(black_box(pixels), &colors),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+}
+
+fn extract_text(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract_text_in_window");
+    for path in DOCUMENTS {
+        let doc = open(path);
+        group.bench_function(path, |b| b.iter(|| doc.extract_text_in_window()));
+    }
+    group.finish();
+}
+
+fn extract_links(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract_links");
+    for path in DOCUMENTS {
+        let doc = open(path);
+        group.bench_function(path, |b| b.iter(|| doc.extract_links().unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, draw, dark_mode, extract_text, extract_links);
+criterion_main!(benches);
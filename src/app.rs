@@ -1,6 +1,8 @@
 use std::{
+    collections::VecDeque,
     fs::canonicalize,
-    path::{PathBuf},
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
 use iced::{
@@ -24,63 +26,268 @@ use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 use strum::EnumString;
 use tokio::sync::mpsc;
-use tracing::error;
+use tracing::{error, info};
 
 use crate::{
     CONFIG,
     bookmarks::{BookmarkMessage, BookmarkStore},
-    config::{MouseAction, MouseButton, MouseInput, MouseModifiers},
+    build::{BuildNotification, BuildState, BuildWatch, build_watcher},
+    check,
+    closed_tabs::{ClosedTab, ClosedTabs},
+    download::{self, DownloadProgress, Downloads},
+    error::MiroError,
+    global_search::{self, GlobalMatch, GlobalSearch},
+    lookup::{self, LookupTarget},
+    config::{
+        BindableMessage, Config, LastTabClosed, MouseAction, MouseButton, MouseInput,
+        MouseModifiers, SIDEBAR_RATIO_RANGE, SidebarSide,
+    },
     geometry::Vector,
+    hooks::{self, HookContext, HookEvent},
     icons,
     jumplist::{JumpLocation, Jumplist},
+    metadata, paths,
     pdf::{
-        PdfMessage, SearchMethod,
+        PdfMessage, SearchFlags, SearchMethod,
+        archive::{self, DocumentKind},
+        export,
+        pointer::PresentationPointer,
         widget::{OutlineItem, PdfViewer},
     },
     platform_specific,
+    preferences::{EntryKind, Preferences},
+    quick_switcher::{QuickSwitcher, SwitchTarget},
+    reading_stats::ReadingStats,
     recent_files::RecentFiles,
     rpc::rpc_server,
+    scrubber::{self, PageScrubber},
+    session::SessionStore,
+    tab_color::{self, TabColor},
+    tabs::{Tab, Tabs},
     watch::{WatchMessage, WatchNotification, file_watcher},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 enum PaneType {
+    #[default]
     Pdf,
     Sidebar,
 }
 
+/// Id of the scrollable in each sidebar tab, scrolled with the movement keys while the sidebar is
+/// the active pane
+pub const SIDEBAR_SCROLLABLE: &str = "sidebar_scrollable";
+/// How far the movement keys scroll the sidebar
+const SIDEBAR_SCROLL_STEP: f32 = 40.0;
+/// How many of the most recently viewed tabs keep their rendered pages while hidden, switching
+/// back to one of them doesn't have to wait for anything to render
+const CACHED_TABS: usize = 4;
+
 #[derive(Debug)]
 struct Pane {
     pane_type: PaneType,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, EnumString, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Default)]
 pub enum SidebarTab {
     #[default]
     Outline,
     Bookmark,
+    Annotations,
+    Layers,
+    Stats,
+}
+
+impl SidebarTab {
+    const ALL: [SidebarTab; 5] = [
+        SidebarTab::Outline,
+        SidebarTab::Bookmark,
+        SidebarTab::Annotations,
+        SidebarTab::Layers,
+        SidebarTab::Stats,
+    ];
+
+    /// The tab `offset` steps to the right, wrapping around at the ends.
+    fn cycle(self, offset: isize) -> Self {
+        let idx = Self::ALL.iter().position(|tab| *tab == self).unwrap_or(0) as isize;
+        Self::ALL[(idx + offset).rem_euclid(Self::ALL.len() as isize) as usize]
+    }
+}
+
+/// Decides who receives key presses. In [`InputMode::Insert`] a text input is being edited and
+/// only `Escape` reaches the app, so single letter bindings can't fire while typing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputMode {
+    #[default]
+    Normal,
+    Insert,
+}
+
+/// Single line prompts shown at the top of the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    OpenUrl,
+    SaveWorkspace,
+    /// Lists the named destinations of the active document matching the input
+    GoToDestination,
+}
+
+/// The selected text sent to the `TranslateCommand` and its answer once it arrives.
+#[derive(Debug)]
+struct Translation {
+    /// Answers to earlier translations that are still running carry a different id
+    id: u64,
+    source: String,
+    result: Option<Result<String, String>>,
+}
+
+/// Runs of the Tools menu kept for the tool output panel
+const TOOL_LOG_LENGTH: usize = 20;
+
+/// A run of a tool from the Tools menu and its output once it finished.
+#[derive(Debug)]
+struct ToolRun {
+    id: u64,
+    name: String,
+    /// With the placeholders filled in
+    command: String,
+    result: Option<Result<String, String>>,
+}
+
+/// What is known about the active document, shown by [AppMessage::ShowProperties].
+#[derive(Debug)]
+struct Properties {
+    path: PathBuf,
+    name: String,
+    in_memory: bool,
+    page_count: usize,
+    /// Metadata like the title and author, only what the document sets
+    info: Vec<(&'static str, String)>,
+    signatures: Vec<metadata::Signature>,
+    /// `None` while the document is still being checked
+    check: Option<check::Report>,
+}
+
+/// What was being closed when documents with unsaved edits were found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PendingClose {
+    Tab(usize),
+    Exit,
+}
+
+/// The answers to the prompt about closing documents with unsaved edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsavedChoice {
+    /// Write the edits back to the files, then close
+    Save,
+    /// Close and throw the edits away
+    Discard,
+    /// Keep everything open
+    Cancel,
+}
+
+/// How long an error stays on screen unless it is clicked away earlier
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(6);
+/// How close to the top of the window the mouse has to get to bring back the menu bar and tabs
+/// in distraction-free mode
+const CHROME_REVEAL_DISTANCE: f32 = 8.0;
+/// They are hidden again once the mouse moves further down than this, leaving room for the menus
+const CHROME_HIDE_DISTANCE: f32 = 160.0;
+/// More destinations than this only show up in the prompt once the input narrows them down
+const MAX_LISTED_DESTINATIONS: usize = 50;
+/// How often the page grid is redrawn while thumbnails are being rendered
+const THUMBNAIL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+struct Notification {
+    id: u64,
+    text: String,
 }
 
+/// The window and everything in it. The documents in the tabs are [PdfViewer]s, only tests put
+/// other [Tab]s in them.
 #[derive(Debug)]
-pub struct App {
+pub struct App<T: Tab = PdfViewer> {
     mac_menu: Option<platform_specific::macos::Menu>,
-    pub pdfs: Vec<PdfViewer>,
-    pub pdf_idx: usize,
+    pub tabs: Tabs<T>,
     pub file_watcher: Option<mpsc::Sender<WatchMessage>>,
     pub dark_mode: bool,
     pub invert_pdf: bool,
     pub draw_page_borders: bool,
     presentation_mode: bool,
+    /// Shown by the documents while in presentation mode
+    presentation_pointer: PresentationPointer,
+    /// Hides the menu bar and tabs until the mouse gets close to the top edge of the window
+    distraction_free: bool,
+    chrome_revealed: bool,
     search_open: bool,
     search_hover: bool,
+    /// The page the scrubber under the active tab is being dragged to
+    scrub_preview: Option<usize>,
+    scrubber_hover: bool,
     bookmark_store: BookmarkStore,
     recent_files: RecentFiles,
+    reading_stats: ReadingStats,
+    closed_tabs: ClosedTabs,
     pane_state: pane_grid::State<Pane>,
+    /// The pane keybindings go to, only ever the sidebar while it is open
+    active_pane: PaneType,
     sidebar_tab: SidebarTab,
     shift_pressed: bool,
     ctrl_pressed: bool,
     scale_factor: f64,
     jumplist: Jumplist,
+    jumplist_open: bool,
+    /// The search across all open tabs, `Some` while its popup is open
+    global_search: Option<GlobalSearch>,
+    /// `Some` while the popup switching between documents by name is open
+    quick_switcher: Option<QuickSwitcher>,
+    /// Fraction of the window taken up by the sidebar
+    sidebar_ratio: f32,
+    session_store: SessionStore,
+    /// Incremented whenever the pending key sequence changes to cancel stale timeout tasks.
+    key_sequence_generation: u64,
+    /// Incremented on every window resize so only the last one of a burst triggers autofit
+    resize_generation: u64,
+    /// Set while the window is minimized, pages aren't rendered since nobody can see them
+    window_hidden: bool,
+    /// Cleared while another window has focus, the time spent there isn't reading
+    window_focused: bool,
+    /// Trackpad scrolling since the last frame. Trackpads report many small deltas per frame,
+    /// they are applied together by [AppMessage::ApplyPendingMove] so the pages are only moved
+    /// and rendered once. Kept together with the tab they were scrolled in, which a tab switch
+    /// before the next frame mustn't change.
+    pending_move: Option<(usize, Vector<f32>)>,
+    /// List the recent files instead of an empty window while no document is open, set when the
+    /// last tab is closed with `LastTabClosed RecentFiles`
+    recent_files_screen: bool,
+    input_mode: InputMode,
+    /// Used to detect page changes for [HookEvent::PageChanged]
+    last_focused_page: Option<(PathBuf, usize)>,
+    /// Set when a build command is watched with `--watch-build`
+    build: Option<BuildState>,
+    /// Directory given with `--watch-dir`, new pdfs in it are opened in new tabs
+    watched_directory: Option<PathBuf>,
+    downloads: Downloads,
+    /// The open prompt and the text typed into it so far
+    prompt: Option<(Prompt, String)>,
+    /// Index into the destinations listed below the prompt, see [App::listed_destinations]
+    prompt_selected: usize,
+    translation: Option<Translation>,
+    /// The latest runs of tools, oldest first
+    tool_log: VecDeque<ToolRun>,
+    next_tool_run_id: u64,
+    next_translation_id: u64,
+    tool_log_open: bool,
+    /// The tab whose color menu was opened by right clicking it
+    tab_menu: Option<usize>,
+    properties: Option<Properties>,
+    preferences: Option<Preferences>,
+    /// Asks what to do with unsaved edits before closing a tab or exiting
+    unsaved_prompt: Option<PendingClose>,
+    /// Errors shown in the corner until they time out or are clicked away
+    notifications: Vec<Notification>,
+    next_notification_id: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, EnumString, Default)]
@@ -90,25 +297,127 @@ pub enum AppMessage {
     #[strum(disabled)]
     #[serde(skip)]
     OpenTempFile(PathBuf),
+    /// Opens a pdf that only exists in memory under the given name
+    #[strum(disabled)]
+    #[serde(skip)]
+    OpenInMemory(String, Vec<u8>),
     CloseFile(PathBuf),
     OpenNewFileFinder,
+    /// Downloads a pdf into the cache and opens it
+    OpenUrl(String),
+    OpenUrlPrompt,
+    #[strum(disabled)]
+    #[serde(skip)]
+    PromptInput(String),
+    #[strum(disabled)]
+    #[serde(skip)]
+    Download(DownloadProgress),
+    /// Downloads the active document again if it was opened from a url
+    RefreshDownload,
+    /// Saves the open tabs as a named workspace
+    SaveWorkspace(String),
+    SaveWorkspacePrompt,
+    /// Jumps to a named destination of the active document
+    GoToDestination(String),
+    GoToDestinationPrompt,
+    /// Opens every document of a saved workspace
+    OpenWorkspace(String),
+    /// Sends the selected text to the configured `TranslateCommand` and shows the answer
+    TranslateSelection,
+    #[strum(disabled)]
+    #[serde(skip)]
+    TranslationReady(u64, Result<String, String>),
+    CloseTranslation,
+    /// Runs the n-th tool of the Tools menu on the active document
+    RunTool(usize),
+    #[strum(disabled)]
+    #[serde(skip)]
+    ToolFinished(u64, Result<String, String>),
+    ShowToolLog,
+    CloseToolLog,
+    /// Opens the color menu of the n-th tab
+    ShowTabMenu(usize),
+    CloseTabMenu,
+    /// Labels the n-th tab with a color, or removes its label
+    SetTabColor(usize, Option<TabColor>),
+    /// Shows the metadata of the active document and checks it for problems
+    ShowProperties,
+    #[strum(disabled)]
+    #[serde(skip)]
+    PropertiesChecked(PathBuf, check::Report),
+    CloseProperties,
+    /// Lists the settings and key bindings of the config file for editing
+    OpenPreferences,
+    #[strum(disabled)]
+    #[serde(skip)]
+    EditPreference(usize, String),
+    #[strum(disabled)]
+    #[serde(skip)]
+    FilterPreferences(String),
+    /// Writes the edited preferences to the config file and applies them
+    SavePreferences,
+    ClosePreferences,
     #[strum(disabled)]
     #[serde(skip)]
     FileDialogResult(Option<PathBuf>),
+    OpenSaveAsDialog,
+    #[strum(disabled)]
+    #[serde(skip)]
+    SaveAsDialogResult(Option<PathBuf>),
+    /// Asks for a directory to write every chapter of the active document to
+    SplitByChapter,
+    #[strum(disabled)]
+    #[serde(skip)]
+    SplitByChapterDirectory(Option<PathBuf>),
+    /// Asks where to save a copy of the active document in the dark mode colors
+    ExportDarkCopy,
+    #[strum(disabled)]
+    #[serde(skip)]
+    ExportDarkCopyPath(Option<PathBuf>),
+    /// Asks where to save the text of the active document
+    ExportText,
+    #[strum(disabled)]
+    #[serde(skip)]
+    ExportTextPath(Option<PathBuf>),
     Debug(String),
     PdfMessage(PdfMessage),
     OpenTab(usize),
     CloseTab(usize),
+    /// Reopens the n-th most recently closed tab
+    ReopenClosedTab(usize),
+    #[strum(disabled)]
+    #[serde(skip)]
+    RestoreClosedTab(ClosedTab),
     CloseActiveTab,
     PreviousTab,
     NextTab,
+    /// Steps through the tabs in most recently used order
+    NextRecentTab,
+    PreviousRecentTab,
+    /// Opens a popup listing the open tabs and recent files, filtered by typing part of a name
+    OpenQuickSwitcher,
+    #[strum(disabled)]
+    #[serde(skip)]
+    QuickSwitcherInput(String),
+    /// Switches to the n-th entry of the quick switcher, or the highlighted one
+    #[strum(disabled)]
+    #[serde(skip)]
+    SwitchToEntry(Option<usize>),
     #[strum(disabled)]
     #[serde(skip)]
     FileWatcher(WatchNotification),
     ToggleDarkModeUi,
     ToggleDarkModePdf,
+    /// The OS switched between light and dark, only listened to with `Theme Auto`
+    #[strum(disabled)]
+    #[serde(skip)]
+    SystemThemeChanged(iced::theme::Mode),
     TogglePageBorders,
     MouseMoved(Vector<f32>),
+    /// The mouse moved over a widget which handled the move itself, like an open menu
+    #[strum(disabled)]
+    #[serde(skip)]
+    CapturedMouseMoved(Vector<f32>),
     #[strum(disabled)]
     #[serde(skip)]
     MouseButtonDown(MouseButton),
@@ -126,14 +435,40 @@ pub enum AppMessage {
     #[strum(disabled)]
     #[serde(skip)]
     SearchHover(bool),
+    /// Previews a page while the scrubber under the active tab is dragged
+    #[strum(disabled)]
+    #[serde(skip)]
+    ScrubPage(usize),
+    /// Jumps to the previewed page once the scrubber is released
+    #[strum(disabled)]
+    #[serde(skip)]
+    FinishScrub,
+    #[strum(disabled)]
+    #[serde(skip)]
+    ScrubberHover(bool),
     BookmarkMessage(BookmarkMessage),
     #[strum(disabled)]
     #[serde(skip)]
     PaneResize(pane_grid::ResizeEvent),
+    #[strum(disabled)]
+    #[serde(skip)]
+    PaneClicked(pane_grid::Pane),
+    /// Moves keyboard focus between the pdf and the sidebar
+    FocusNextPane,
+    /// A keybinding, handled by the active pane when it has a use for it
+    #[strum(disabled)]
+    #[serde(skip)]
+    KeyAction(BindableMessage),
     ToggleSidebar,
+    /// Grows the sidebar by the given fraction of the window, shrinks it if negative
+    ResizeSidebar(f32),
     SetSidebar(SidebarTab),
     OutlineGoToPage(usize),
+    /// Quits, after asking what to do with unsaved edits if there are any
     Exit,
+    #[strum(disabled)]
+    #[serde(skip)]
+    UnsavedChanges(UnsavedChoice),
     #[default]
     None,
     #[strum(disabled)]
@@ -143,68 +478,385 @@ pub enum AppMessage {
     JumpTo(JumpLocation),
     JumpBack,
     JumpForward,
+    ToggleJumplist,
+    /// Jumps directly to the n-th entry of the jumplist
+    JumpToEntry(usize),
     ToggleFullscreen,
     TogglePresentationMode,
+    /// Cycles the mouse in presentation mode between the cursor, a laser pointer and a spotlight
+    CyclePresentationPointer,
+    ToggleDistractionFree,
     OpenSearch,
     CloseSearch,
     ToggleSearchMethod,
+    /// Opens a popup searching through every open tab at once
+    OpenGlobalSearch,
+    #[strum(disabled)]
+    #[serde(skip)]
+    GlobalSearchInput(String),
+    #[strum(disabled)]
+    #[serde(skip)]
+    GlobalSearchMethod(SearchMethod),
+    #[strum(disabled)]
+    #[serde(skip)]
+    GlobalSearchFlags(SearchFlags),
+    /// The matches in one document for the search of the given generation
+    #[strum(disabled)]
+    #[serde(skip)]
+    GlobalSearchResults(u64, PathBuf, Vec<GlobalMatch>),
+    /// Moves the highlighted result of the search across all tabs up or down
+    #[strum(disabled)]
+    #[serde(skip)]
+    MoveGlobalSearchSelection(i32),
+    /// Jumps to the n-th result of the search across all tabs, or the highlighted one
+    #[strum(disabled)]
+    #[serde(skip)]
+    OpenGlobalSearchResult(Option<usize>),
+    /// Moves the highlighted entry of whichever list popup is open up or down
+    #[strum(disabled)]
+    #[serde(skip)]
+    MovePopupSelection(i32),
+    /// A key was pressed that is the prefix of a bound key sequence
+    KeySequencePending,
+    /// The pending key sequence was completed or broken
+    KeySequenceCancel,
+    #[strum(disabled)]
+    #[serde(skip)]
+    KeySequenceTimeout(u64),
+    #[strum(disabled)]
+    #[serde(skip)]
+    WindowResized,
+    /// The window lost focus, which is all most platforms report when it is minimized
+    #[strum(disabled)]
+    #[serde(skip)]
+    WindowUnfocused,
+    #[strum(disabled)]
+    #[serde(skip)]
+    WindowFocused,
+    #[strum(disabled)]
+    #[serde(skip)]
+    WindowHidden(bool),
+    #[strum(disabled)]
+    #[serde(skip)]
+    ApplyPendingMove,
+    #[strum(disabled)]
+    #[serde(skip)]
+    ResizeSettled(u64),
+    #[strum(disabled)]
+    #[serde(skip)]
+    EnterInsertMode,
+    /// Leaves the focused text input and hands key presses back to the keybindings
+    ExitInsertMode,
+    #[strum(disabled)]
+    #[serde(skip)]
+    WatchDirectory(PathBuf),
+    #[strum(disabled)]
+    #[serde(skip)]
+    StartBuildWatch(BuildWatch),
+    #[strum(disabled)]
+    #[serde(skip)]
+    BuildWatcher(BuildNotification),
+    ToggleBuildPanel,
+    /// Logs the error and shows it as a notification
+    #[strum(disabled)]
+    #[serde(skip)]
+    ShowError(MiroError),
+    #[strum(disabled)]
+    #[serde(skip)]
+    DismissNotification(u64),
 }
 
-impl App {
-    fn get_mouse_action(&self, button: MouseButton) -> Option<MouseAction> {
-        let input = MouseInput {
-            button,
-            modifiers: MouseModifiers {
-                ctrl: self.ctrl_pressed,
-                shift: self.shift_pressed,
-            },
-        };
-        CONFIG.read().unwrap().get_mouse_action(input)
-    }
-
-    pub fn new(bookmark_store: BookmarkStore, recent_files: RecentFiles) -> Self {
+/// The handling of tabs and the jumplist, which only needs to know the paths and locations of the
+/// documents.
+impl<T: Tab> App<T> {
+    pub fn new(
+        bookmark_store: BookmarkStore,
+        recent_files: RecentFiles,
+        reading_stats: ReadingStats,
+        session_store: SessionStore,
+    ) -> Self {
         let cfg = CONFIG.read().unwrap();
         let (mut ps, pdf_id) = pane_grid::State::new(Pane {
             pane_type: PaneType::Pdf,
         });
+        let sidebar_ratio = session_store
+            .sidebar_ratio()
+            .unwrap_or(cfg.sidebar_ratio)
+            .clamp(*SIDEBAR_RATIO_RANGE.start(), *SIDEBAR_RATIO_RANGE.end());
         if cfg.open_sidebar {
-            Self::open_sidebar(&mut ps, pdf_id);
+            Self::open_sidebar(&mut ps, pdf_id, sidebar_ratio, cfg.sidebar_side);
         }
 
         Self {
             mac_menu: None,
-            pdfs: vec![],
-            pdf_idx: 0,
+            tabs: Tabs::new(),
             file_watcher: None,
             dark_mode: CONFIG.read().unwrap().dark_mode,
             invert_pdf: CONFIG.read().unwrap().invert_pdf,
             draw_page_borders: CONFIG.read().unwrap().page_borders,
             presentation_mode: false,
+            presentation_pointer: PresentationPointer::Off,
+            distraction_free: false,
+            chrome_revealed: false,
             search_open: false,
             search_hover: false,
+            scrub_preview: None,
+            scrubber_hover: false,
             bookmark_store,
             recent_files,
+            reading_stats,
+            closed_tabs: ClosedTabs::new(),
             pane_state: ps,
+            active_pane: PaneType::Pdf,
             sidebar_tab: SidebarTab::Outline,
             shift_pressed: false,
             ctrl_pressed: false,
             scale_factor: 1.0,
             jumplist: Jumplist::new(),
+            jumplist_open: false,
+            global_search: None,
+            quick_switcher: None,
+            sidebar_ratio,
+            session_store,
+            key_sequence_generation: 0,
+            resize_generation: 0,
+            window_hidden: false,
+            window_focused: true,
+            pending_move: None,
+            recent_files_screen: false,
+            input_mode: InputMode::Normal,
+            last_focused_page: None,
+            build: None,
+            watched_directory: None,
+            downloads: Downloads::new(),
+            prompt: None,
+            prompt_selected: 0,
+            translation: None,
+            tool_log: VecDeque::new(),
+            next_tool_run_id: 0,
+            next_translation_id: 0,
+            tool_log_open: false,
+            tab_menu: None,
+            properties: None,
+            preferences: None,
+            unsaved_prompt: None,
+            notifications: vec![],
+            next_notification_id: 0,
         }
     }
 
-    fn open_pdf(&mut self, path_buf: PathBuf) -> iced::Task<AppMessage> {
-        let out = match PdfViewer::from_path(path_buf.clone()) {
-            Ok(mut viewer) => {
-                viewer.set_scale_factor(self.scale_factor);
-                viewer.set_pdf_dark_mode(self.invert_pdf);
-                self.pdfs.push(viewer);
-                iced::Task::done(AppMessage::OpenTab(self.pdfs.len() - 1))
+    fn open_sidebar(
+        pane_state: &mut pane_grid::State<Pane>,
+        pdf_id: pane_grid::Pane,
+        sidebar_ratio: f32,
+        side: SidebarSide,
+    ) {
+        if let Some((sidebar_id, split)) = pane_state.split(
+            pane_grid::Axis::Vertical,
+            pdf_id,
+            Pane {
+                pane_type: PaneType::Sidebar,
+            },
+        ) {
+            // New panes are always placed to the right of the one being split
+            if side == SidebarSide::Left {
+                pane_state.swap(pdf_id, sidebar_id);
             }
-            Err(e) => {
-                error!("Couldn't create pdf viewer or {path_buf:?} {e}");
-                iced::Task::none()
+            pane_state.resize(split, side.split_ratio(sidebar_ratio));
+        }
+    }
+
+    /// Adds `tab` after the others and returns its index.
+    fn add_tab(&mut self, tab: T) -> usize {
+        let idx = self.tabs.push(tab);
+        self.restore_jumplist();
+        idx
+    }
+
+    /// Picks up the back/forward history from the last time exactly these documents were open.
+    fn restore_jumplist(&mut self) {
+        if !self.jumplist.is_empty() {
+            return;
+        }
+        if let Some(jumplist) = self
+            .session_store
+            .take_jumplist(self.tabs.pdfs.iter().map(|pdf| pdf.path()))
+        {
+            self.jumplist = jumplist;
+        }
+    }
+
+    fn record_location(&mut self) {
+        if let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) {
+            self.jumplist.push(pdf.location());
+        }
+    }
+
+    /// The indices of the tabs in the order they are shown in, see [tab_color::grouped_order].
+    fn tab_order(&self) -> Vec<usize> {
+        if !CONFIG.read().unwrap().group_tabs_by_color {
+            return (0..self.tabs.pdfs.len()).collect();
+        }
+        let colors: Vec<Option<TabColor>> = self
+            .tabs
+            .pdfs
+            .iter()
+            .map(|pdf| self.session_store.tab_color(pdf.path()))
+            .collect();
+        tab_color::grouped_order(&colors)
+    }
+
+    /// Moves `step` tabs to the right in the order they are shown in, stopping at the ends.
+    fn step_tab(&mut self, step: isize) {
+        let order = self.tab_order();
+        self.tabs.step(&order, step);
+    }
+
+    /// Scrolls to `location`, opening its document first if it isn't open anymore.
+    fn jump_to(&mut self, location: JumpLocation) -> iced::Task<AppMessage> {
+        match self.tabs.position(&location.pdf_path) {
+            Some(i) => {
+                self.tabs.active = i;
+                self.tabs.pdfs[i]
+                    .set_location(&location)
+                    .map(AppMessage::PdfMessage)
+            }
+            None => iced::Task::done(AppMessage::OpenFile(location.pdf_path.clone()))
+                .chain(iced::Task::done(AppMessage::JumpTo(location))),
+        }
+    }
+
+    fn jump_back(&mut self) -> iced::Task<AppMessage> {
+        match self.jumplist.jump_back() {
+            Some(location) => self.jump_to(location.clone()),
+            None => iced::Task::none(),
+        }
+    }
+
+    fn jump_forward(&mut self) -> iced::Task<AppMessage> {
+        match self.jumplist.jump_forward() {
+            Some(location) => self.jump_to(location.clone()),
+            None => iced::Task::none(),
+        }
+    }
+}
+
+impl App {
+    fn get_mouse_action(&self, button: MouseButton) -> Option<MouseAction> {
+        let input = MouseInput {
+            button,
+            modifiers: MouseModifiers {
+                ctrl: self.ctrl_pressed,
+                shift: self.shift_pressed,
+            },
+        };
+        CONFIG.read().unwrap().get_mouse_action(input)
+    }
+
+    fn add_viewer(&mut self, mut viewer: PdfViewer) -> iced::Task<AppMessage> {
+        viewer.set_scale_factor(self.scale_factor);
+        viewer.set_pdf_dark_mode(self.invert_pdf);
+        viewer.presentation_pointer = self.shown_presentation_pointer();
+        if !viewer.in_memory
+            && let Some(hidden) = self.session_store.hidden_layers(&viewer.path)
+            && let Err(e) = viewer.set_hidden_layers(hidden.to_vec())
+        {
+            error!("Couldn't restore the layers of {}: {e}", viewer.name);
+        }
+        let hook_task = run_hooks(HookEvent::FileOpened, &viewer);
+        let idx = self.add_tab(viewer);
+        iced::Task::batch([iced::Task::done(AppMessage::OpenTab(idx)), hook_task])
+    }
+
+    /// Closes tab `i`, every way of closing a tab ends up here. Closing a tab while none are open
+    /// quits, closing the last one does what `LastTabClosed` says. A document with unsaved edits
+    /// asks what to do with them first.
+    fn close_tab(&mut self, i: usize) -> iced::Task<AppMessage> {
+        if self.tabs.pdfs.is_empty() {
+            return self.exit();
+        }
+        if i >= self.tabs.pdfs.len() {
+            return iced::Task::none();
+        }
+        if self.tabs.pdfs[i].modified {
+            self.unsaved_prompt = Some(PendingClose::Tab(i));
+            return iced::Task::none();
+        }
+        self.remove_tab(i)
+    }
+
+    fn remove_tab(&mut self, i: usize) -> iced::Task<AppMessage> {
+        let closed = self.tabs.remove(i);
+        // Documents from memory are gone for good once closed
+        if !closed.in_memory {
+            if let Some(sender) = &self.file_watcher {
+                // We should never fill this up from here
+                let _ = sender.blocking_send(WatchMessage::StopWatch(closed.path.clone()));
+            }
+            self.closed_tabs.push(ClosedTab {
+                path: closed.path.clone(),
+                page: closed.current_page(),
+                scale: closed.scale,
+            });
+        }
+        if let Some(m) = &self.mac_menu {
+            m.update_closed_tabs(self.closed_tabs.get_closed());
+        }
+        // An open prompt about unsaved edits keeps asking about the same document, and scrolling
+        // that wasn't applied yet still goes to it
+        match self.unsaved_prompt {
+            Some(PendingClose::Tab(j)) if j == i => self.unsaved_prompt = None,
+            Some(PendingClose::Tab(j)) if j > i => {
+                self.unsaved_prompt = Some(PendingClose::Tab(j - 1))
             }
+            _ => {}
+        }
+        self.pending_move = match self.pending_move {
+            Some((j, _)) if j == i => None,
+            Some((j, pending)) if j > i => Some((j - 1, pending)),
+            pending => pending,
+        };
+        let hook_task = run_hooks(HookEvent::FileClosed, &closed);
+        if !self.tabs.pdfs.is_empty() {
+            return hook_task;
+        }
+        let last_tab_closed = CONFIG.read().unwrap().last_tab_closed;
+        self.recent_files_screen = last_tab_closed == LastTabClosed::RecentFiles;
+        if last_tab_closed == LastTabClosed::Exit {
+            // The hook still runs before the window closes
+            hook_task.chain(exit())
+        } else {
+            hook_task
+        }
+    }
+
+    /// Quits unless a document has unsaved edits, which asks what to do with them first.
+    fn exit(&mut self) -> iced::Task<AppMessage> {
+        if self.tabs.pdfs.iter().any(|pdf| pdf.modified) {
+            self.unsaved_prompt = Some(PendingClose::Exit);
+            iced::Task::none()
+        } else {
+            exit()
+        }
+    }
+
+    /// The tabs with unsaved edits that `pending` would close.
+    fn unsaved_tabs(&self, pending: PendingClose) -> Vec<usize> {
+        self.tabs
+            .pdfs
+            .iter()
+            .enumerate()
+            .filter(|(i, pdf)| {
+                pdf.modified && (pending == PendingClose::Exit || pending == PendingClose::Tab(*i))
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn open_pdf(&mut self, path_buf: PathBuf) -> iced::Task<AppMessage> {
+        let out = match PdfViewer::from_path(path_buf.clone()) {
+            Ok(viewer) => self.add_viewer(viewer),
+            Err(e) => return self.notify(MiroError::file(path_buf, e)),
         };
         if let Some(sender) = self.file_watcher.as_ref() {
             // We should never fill this up from here, thus blocking is alright
@@ -213,6 +865,30 @@ impl App {
         out
     }
 
+    fn save_active_pdf_as(&mut self, path_buf: PathBuf) -> Result<(), MiroError> {
+        let Some(pdf) = self.tabs.pdfs.get_mut(self.tabs.active) else {
+            return Ok(());
+        };
+        let old_path = pdf.path.clone();
+        let was_in_memory = pdf.in_memory;
+        pdf.save_as(path_buf.clone())
+            .map_err(|e| MiroError::file(&path_buf, e))?;
+        let new_path = pdf.path.clone();
+        if let Some(sender) = self.file_watcher.as_ref() {
+            // We should never fill this up from here, thus blocking is alright
+            if !was_in_memory {
+                let _ = sender.blocking_send(WatchMessage::StopWatch(old_path));
+            }
+            let _ = sender.blocking_send(WatchMessage::StartWatch(new_path.clone()));
+        }
+        self.recent_files.add_recent(new_path);
+        if let Some(m) = &self.mac_menu {
+            let recent_files = self.recent_files.get_recent();
+            m.update_recent_files(recent_files);
+        }
+        Ok(())
+    }
+
     fn has_sidebar_pane(&self) -> bool {
         self.pane_state
             .panes
@@ -236,30 +912,112 @@ impl App {
             .map(|(id, _)| *id)
     }
 
-    fn open_sidebar(pane_state: &mut pane_grid::State<Pane>, pdf_id: pane_grid::Pane) {
-        if let Some((_, split)) = pane_state.split(
-            pane_grid::Axis::Vertical,
-            pdf_id,
-            Pane {
-                pane_type: PaneType::Sidebar,
-            },
-        ) {
-            pane_state.resize(split, 0.7);
+    /// What a keybinding does while the sidebar is the active pane. Movement scrolls the sidebar
+    /// and moving sideways switches its tab, everything else is left to the pdf.
+    fn sidebar_key_action(&mut self, action: BindableMessage) -> Option<iced::Task<AppMessage>> {
+        let scroll = |y: f32| {
+            widget::operation::scroll_by(
+                widget::Id::new(SIDEBAR_SCROLLABLE),
+                scrollable::AbsoluteOffset { x: 0.0, y },
+            )
+        };
+        match action {
+            BindableMessage::MoveUp => Some(scroll(-SIDEBAR_SCROLL_STEP)),
+            BindableMessage::MoveDown => Some(scroll(SIDEBAR_SCROLL_STEP)),
+            BindableMessage::HalfPageUp => Some(scroll(-SIDEBAR_SCROLL_STEP * 5.0)),
+            BindableMessage::HalfPageDown => Some(scroll(SIDEBAR_SCROLL_STEP * 5.0)),
+            BindableMessage::PageUp => Some(scroll(-SIDEBAR_SCROLL_STEP * 10.0)),
+            BindableMessage::PageDown => Some(scroll(SIDEBAR_SCROLL_STEP * 10.0)),
+            BindableMessage::MoveLeft => {
+                self.sidebar_tab = self.sidebar_tab.cycle(-1);
+                Some(iced::Task::none())
+            }
+            BindableMessage::MoveRight => {
+                self.sidebar_tab = self.sidebar_tab.cycle(1);
+                Some(iced::Task::none())
+            }
+            _ => None,
+        }
+    }
+
+    /// The split between the pdf and the sidebar, if the sidebar is open
+    fn sidebar_split(&self) -> Option<pane_grid::Split> {
+        match self.pane_state.layout() {
+            pane_grid::Node::Split { id, .. } => Some(*id),
+            pane_grid::Node::Pane(_) => None,
+        }
+    }
+
+    /// While Ctrl is held the highlighted tab is only opened once it's released, which lets the
+    /// user step further back through the history first.
+    fn step_recent_tab(&mut self, steps: isize) {
+        if self.tabs.history.step(steps).is_some() && !self.ctrl_pressed {
+            self.tabs.finish_switch();
         }
     }
 
     pub fn update(&mut self, message: AppMessage) -> iced::Task<AppMessage> {
         let _span = tracy_client::span!("App update");
+        let tabs_before = (self.tabs.active, self.tabs.pdfs.len());
+        let task = self.handle_message(message);
+        // Whichever tab ended up active has had its changes seen by now
+        if let Some(pdf) = self.tabs.pdfs.get_mut(self.tabs.active) {
+            pdf.unseen_changes = false;
+            self.tabs.history.activate(self.tabs.active);
+        }
+        // Tabs only fall out of the recently used ones when another tab is switched to or opened
+        if (self.tabs.active, self.tabs.pdfs.len()) != tabs_before {
+            for (i, pdf) in self.tabs.pdfs.iter_mut().enumerate() {
+                if i != self.tabs.active && !self.tabs.history.is_recent(i, CACHED_TABS) {
+                    pdf.release_render_caches();
+                }
+            }
+        }
+        let focused = self
+            .tabs
+            .pdfs
+            .get(self.tabs.active)
+            .map(|pdf| (pdf, pdf.current_page()));
+        // Nobody is reading while the window is in the background or minimized
+        let reading = self.window_focused && !self.window_hidden;
+        self.reading_stats.observe(
+            focused
+                .filter(|(pdf, _)| reading && !pdf.in_memory)
+                .map(|(pdf, page)| (pdf.path.as_path(), page)),
+        );
+
+        let mut hook_task = iced::Task::none();
+        if let Some((pdf, page)) = focused {
+            if let Some((path, last_page)) = &self.last_focused_page
+                && *path == pdf.path
+                && *last_page != page
+            {
+                hook_task = run_hooks(HookEvent::PageChanged, pdf);
+            }
+            self.last_focused_page = Some((pdf.path.clone(), page));
+        } else {
+            self.last_focused_page = None;
+        }
+        iced::Task::batch([task, hook_task])
+    }
+
+    fn handle_message(&mut self, message: AppMessage) -> iced::Task<AppMessage> {
         match message {
             AppMessage::InitializeMacMenu => {
                 let recent_files = self.recent_files.get_recent();
-                let m = platform_specific::macos::Menu::new(recent_files);
+                let m = platform_specific::macos::Menu::new(
+                    recent_files,
+                    &self.session_store.workspace_names(),
+                );
                 m.init();
                 self.mac_menu = Some(m);
                 iced::Task::none()
             }
             AppMessage::OpenFile(path_buf) => {
-                let path_buf = canonicalize(path_buf).unwrap();
+                let path_buf = match canonicalize(&path_buf) {
+                    Ok(path_buf) => path_buf,
+                    Err(e) => return self.notify(MiroError::file(path_buf, e)),
+                };
                 self.recent_files.add_recent(path_buf.clone());
                 if let Some(m) = &self.mac_menu {
                     let recent_files = self.recent_files.get_recent();
@@ -268,13 +1026,25 @@ impl App {
                 self.open_pdf(path_buf)
             }
             AppMessage::OpenTempFile(path_buf) => {
-                let path_buf = canonicalize(path_buf).unwrap();
+                let path_buf = match canonicalize(&path_buf) {
+                    Ok(path_buf) => path_buf,
+                    Err(e) => return self.notify(MiroError::file(path_buf, e)),
+                };
                 self.open_pdf(path_buf)
             }
+            AppMessage::OpenInMemory(name, bytes) => match PdfViewer::from_bytes(name, &bytes) {
+                Ok(viewer) => self.add_viewer(viewer),
+                Err(e) => self.notify(MiroError::document(format!(
+                    "Couldn't open the pdf from memory: {e}"
+                ))),
+            },
             AppMessage::CloseFile(path_buf) => {
-                let path_buf = canonicalize(path_buf).unwrap();
-                if let Some(idx) = self.pdfs.iter().position(|p| p.path == path_buf) {
-                    iced::Task::done(AppMessage::CloseTab(idx))
+                let path_buf = match canonicalize(&path_buf) {
+                    Ok(path_buf) => path_buf,
+                    Err(e) => return self.notify(MiroError::file(path_buf, e)),
+                };
+                if let Some(idx) = self.tabs.pdfs.iter().position(|p| p.path == path_buf) {
+                    self.close_tab(idx)
                 } else {
                     iced::Task::none()
                 }
@@ -283,17 +1053,46 @@ impl App {
                 println!("[DEBUG] {s}");
                 iced::Task::none()
             }
-            AppMessage::PdfMessage(msg) => {
-                if !self.pdfs.is_empty() {
-                    if self.pdfs[self.pdf_idx].is_jumpable_action(&msg) {
-                        self.record_location();
-                        let pdf_msg = self.pdfs[self.pdf_idx]
-                            .update(msg)
-                            .map(AppMessage::PdfMessage);
+            AppMessage::PdfMessage(PdfMessage::Error(e)) | AppMessage::ShowError(e) => {
+                self.notify(e)
+            }
+            AppMessage::DismissNotification(id) => {
+                self.notifications.retain(|n| n.id != id);
+                iced::Task::none()
+            }
+            AppMessage::PdfMessage(PdfMessage::DeleteAnnotation(_)) if paths::read_only() => {
+                iced::Task::none()
+            }
+            AppMessage::PdfMessage(PdfMessage::ToggleLayer(idx)) => {
+                let Some(pdf) = self.tabs.pdfs.get_mut(self.tabs.active) else {
+                    return iced::Task::none();
+                };
+                let task = pdf
+                    .update(PdfMessage::ToggleLayer(idx))
+                    .map(AppMessage::PdfMessage);
+                // The layers chosen for a document are shown again the next time it is opened
+                if !pdf.in_memory
+                    && let Some(hidden) = pdf.hidden_layers()
+                {
+                    self.session_store
+                        .set_hidden_layers(&pdf.path, hidden.to_vec());
+                }
+                task
+            }
+            AppMessage::PdfMessage(msg) => {
+                if matches!(msg, PdfMessage::UpdateSearchNeedle(_)) {
+                    self.enter_insert_mode();
+                }
+                if !self.tabs.pdfs.is_empty() {
+                    if self.tabs.pdfs[self.tabs.active].is_jumpable_action(&msg) {
+                        self.record_location();
+                        let pdf_msg = self.tabs.pdfs[self.tabs.active]
+                            .update(msg)
+                            .map(AppMessage::PdfMessage);
                         self.record_location();
                         pdf_msg
                     } else {
-                        self.pdfs[self.pdf_idx]
+                        self.tabs.pdfs[self.tabs.active]
                             .update(msg)
                             .map(AppMessage::PdfMessage)
                     }
@@ -304,112 +1103,689 @@ impl App {
             AppMessage::OpenNewFileFinder => iced::Task::perform(
                 async {
                     AsyncFileDialog::new()
-                        .add_filter("Pdf", &["pdf"])
+                        .add_filter("Documents", archive::OPENABLE_EXTENSIONS)
                         .pick_file()
                         .await
                         .map(|file_handle| file_handle.path().to_path_buf())
                 },
                 AppMessage::FileDialogResult,
             ),
+            AppMessage::OpenUrl(url) => {
+                self.prompt = None;
+                let mode_task = self.exit_insert_mode();
+                if !self.downloads.start(&url) {
+                    return mode_task;
+                }
+                iced::Task::batch([
+                    mode_task,
+                    iced::Task::run(download::download(url), AppMessage::Download),
+                ])
+            }
+            AppMessage::OpenUrlPrompt => self.open_prompt(Prompt::OpenUrl),
+            AppMessage::SaveWorkspacePrompt if paths::read_only() => iced::Task::none(),
+            AppMessage::SaveWorkspacePrompt => self.open_prompt(Prompt::SaveWorkspace),
+            AppMessage::GoToDestinationPrompt => self.open_prompt(Prompt::GoToDestination),
+            AppMessage::GoToDestination(name) => {
+                self.prompt = None;
+                let mode_task = self.exit_insert_mode();
+                let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) else {
+                    return mode_task;
+                };
+                match pdf.destinations().get(&name) {
+                    Some(page) => iced::Task::batch([
+                        mode_task,
+                        iced::Task::done(AppMessage::OutlineGoToPage(page)),
+                    ]),
+                    None => {
+                        let e = MiroError::document(format!(
+                            "{} has no destination named {name}",
+                            pdf.name
+                        ));
+                        iced::Task::batch([mode_task, self.notify(e)])
+                    }
+                }
+            }
+            AppMessage::PromptInput(input) => {
+                self.enter_insert_mode();
+                if let Some((_, text)) = &mut self.prompt {
+                    *text = input;
+                }
+                self.prompt_selected = 0;
+                iced::Task::none()
+            }
+            AppMessage::SaveWorkspace(name) => {
+                self.prompt = None;
+                self.session_store.save_workspace(
+                    name.trim(),
+                    self.tabs
+                        .pdfs
+                        .iter()
+                        .filter(|pdf| !pdf.in_memory)
+                        .map(|pdf| pdf.path.as_path()),
+                );
+                if let Err(e) = self.session_store.save() {
+                    error!("Error while saving the session: {}", e)
+                }
+                if let Some(m) = &self.mac_menu {
+                    m.update_workspaces(&self.session_store.workspace_names());
+                }
+                self.exit_insert_mode()
+            }
+            AppMessage::OpenWorkspace(name) => {
+                let Some(documents) = self.session_store.workspace(&name) else {
+                    error!("There is no workspace called {name}");
+                    return iced::Task::none();
+                };
+                let tasks: Vec<_> = documents
+                    .iter()
+                    .filter(|path| {
+                        if !path.exists() {
+                            error!("{path:?} from the workspace {name} no longer exists");
+                        }
+                        path.exists() && !self.tabs.pdfs.iter().any(|pdf| &pdf.path == *path)
+                    })
+                    .map(|path| iced::Task::done(AppMessage::OpenFile(path.clone())))
+                    .collect();
+                iced::Task::batch(tasks)
+            }
+            AppMessage::Download(progress) => {
+                self.downloads.update(&progress);
+                match progress {
+                    DownloadProgress::Finished { path, .. } => {
+                        match self.tabs.pdfs.iter_mut().find(|pdf| pdf.path == path) {
+                            // Refreshed, the tab showing the old copy only has to reload
+                            Some(pdf) => pdf
+                                .update(PdfMessage::FileChanged)
+                                .map(AppMessage::PdfMessage),
+                            None => iced::Task::done(AppMessage::OpenFile(path)),
+                        }
+                    }
+                    DownloadProgress::Failed { url, error } => {
+                        self.notify(MiroError::Download { url, reason: error })
+                    }
+                    DownloadProgress::Progress { .. } => iced::Task::none(),
+                }
+            }
+            AppMessage::RefreshDownload => match self
+                .tabs
+                .pdfs
+                .get(self.tabs.active)
+                .and_then(|pdf| self.downloads.source(&pdf.path))
+            {
+                Some(url) => iced::Task::done(AppMessage::OpenUrl(url.to_string())),
+                None => iced::Task::none(),
+            },
+            AppMessage::TranslateSelection => {
+                let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) else {
+                    return iced::Task::none();
+                };
+                let source = pdf.selection_text();
+                let template = CONFIG.read().unwrap().translate_command.clone();
+                if source.trim().is_empty() {
+                    return iced::Task::none();
+                }
+                if template.is_empty() {
+                    error!("Translating requires a TranslateCommand in the config file");
+                    return iced::Task::none();
+                }
+                let target = lookup::lookup_target(&template, source.trim());
+                let id = self.next_translation_id;
+                self.next_translation_id += 1;
+                self.translation = Some(Translation {
+                    id,
+                    source,
+                    result: None,
+                });
+                iced::Task::perform(lookup::output(target), move |result| {
+                    AppMessage::TranslationReady(id, result.map_err(|e| e.to_string()))
+                })
+            }
+            AppMessage::TranslationReady(id, result) => {
+                if let Some(translation) = &mut self.translation
+                    && translation.id == id
+                {
+                    translation.result = Some(result);
+                }
+                iced::Task::none()
+            }
+            AppMessage::CloseTranslation => {
+                self.translation = None;
+                iced::Task::none()
+            }
+            AppMessage::RunTool(idx) => {
+                let Some((name, template)) = CONFIG.read().unwrap().tools.get(idx).cloned() else {
+                    return iced::Task::none();
+                };
+                let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) else {
+                    return self.notify(MiroError::document(format!(
+                        "{name} needs an open document"
+                    )));
+                };
+                let command = hooks::expand_placeholders(
+                    &template,
+                    &HookContext {
+                        path: &pdf.path,
+                        page: pdf.current_page(),
+                        page_count: pdf.page_count().unwrap_or(0) as usize,
+                    },
+                );
+                let id = self.next_tool_run_id;
+                self.next_tool_run_id += 1;
+                self.tool_log.push_back(ToolRun {
+                    id,
+                    name,
+                    command: command.clone(),
+                    result: None,
+                });
+                if self.tool_log.len() > TOOL_LOG_LENGTH {
+                    self.tool_log.pop_front();
+                }
+                iced::Task::perform(
+                    lookup::output(LookupTarget::Command(command)),
+                    move |result| AppMessage::ToolFinished(id, result.map_err(|e| e.to_string())),
+                )
+            }
+            AppMessage::ToolFinished(id, result) => {
+                // Tools opening another program print nothing, there is no need to show those
+                if !matches!(&result, Ok(output) if output.is_empty()) {
+                    self.tool_log_open = true;
+                }
+                if let Some(run) = self.tool_log.iter_mut().find(|run| run.id == id) {
+                    run.result = Some(result);
+                }
+                iced::Task::none()
+            }
+            AppMessage::ShowToolLog => {
+                self.tool_log_open = true;
+                iced::Task::none()
+            }
+            AppMessage::CloseToolLog => {
+                self.tool_log_open = false;
+                iced::Task::none()
+            }
+            AppMessage::ShowTabMenu(idx) => {
+                // The label is stored per file, documents from memory have none
+                if self.tabs.pdfs.get(idx).is_some_and(|pdf| !pdf.in_memory) {
+                    self.tab_menu = Some(idx);
+                }
+                iced::Task::none()
+            }
+            AppMessage::CloseTabMenu => {
+                self.tab_menu = None;
+                iced::Task::none()
+            }
+            AppMessage::SetTabColor(idx, color) => {
+                self.tab_menu = None;
+                if let Some(pdf) = self.tabs.pdfs.get(idx).filter(|pdf| !pdf.in_memory) {
+                    self.session_store.set_tab_color(&pdf.path, color);
+                }
+                iced::Task::none()
+            }
+            AppMessage::ShowProperties => {
+                let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) else {
+                    return iced::Task::none();
+                };
+                // Documents only in memory are checked as they would be saved
+                let bytes = pdf.in_memory.then(|| pdf.pdf_bytes());
+                let path = pdf.path.clone();
+                self.properties = Some(Properties {
+                    path: path.clone(),
+                    name: pdf.name.clone(),
+                    in_memory: pdf.in_memory,
+                    page_count: pdf.page_count().unwrap_or(0) as usize,
+                    info: pdf.document_info(),
+                    signatures: pdf.signatures().to_vec(),
+                    check: None,
+                });
+                iced::Task::perform(
+                    tokio::task::spawn_blocking({
+                        let path = path.clone();
+                        move || match bytes {
+                            Some(Ok(bytes)) => check::check_bytes(&bytes),
+                            Some(Err(e)) => check::Report::failed(e),
+                            None => check::check_file(&path),
+                        }
+                    }),
+                    move |report| {
+                        AppMessage::PropertiesChecked(
+                            path,
+                            report.unwrap_or_else(check::Report::failed),
+                        )
+                    },
+                )
+            }
+            AppMessage::PropertiesChecked(path, report) => {
+                if let Some(properties) = &mut self.properties
+                    && properties.path == path
+                {
+                    properties.check = Some(report);
+                }
+                iced::Task::none()
+            }
+            AppMessage::CloseProperties => {
+                self.properties = None;
+                iced::Task::none()
+            }
+            AppMessage::OpenPreferences => match Preferences::load() {
+                Ok(preferences) => {
+                    self.preferences = Some(preferences);
+                    self.enter_insert_mode();
+                    widget::operation::focus(widget::Id::new("preferences_filter"))
+                        .map(|_: ()| AppMessage::None)
+                }
+                Err(e) => self.notify(MiroError::document(e)),
+            },
+            AppMessage::EditPreference(idx, value) => {
+                self.enter_insert_mode();
+                if let Some(preferences) = &mut self.preferences {
+                    preferences.edit(idx, value);
+                }
+                iced::Task::none()
+            }
+            AppMessage::FilterPreferences(filter) => {
+                self.enter_insert_mode();
+                if let Some(preferences) = &mut self.preferences {
+                    preferences.filter = filter;
+                }
+                iced::Task::none()
+            }
+            AppMessage::SavePreferences => {
+                let Some(preferences) = &self.preferences else {
+                    return iced::Task::none();
+                };
+                if let Err(e) = preferences.save() {
+                    return self.notify(MiroError::file(&preferences.path, e));
+                }
+                // Settings only read at startup, like the initial zoom, apply from the next start
+                let style = match Config::system_config() {
+                    Ok(config) => {
+                        let style = config.pdf_dark_mode_style;
+                        *CONFIG.write().unwrap() = config;
+                        style
+                    }
+                    Err(e) => return self.notify(MiroError::file(&preferences.path, e)),
+                };
+                for pdf in &mut self.tabs.pdfs {
+                    pdf.set_pdf_dark_mode_style(style);
+                }
+                self.preferences = None;
+                self.exit_insert_mode()
+            }
+            AppMessage::ClosePreferences => {
+                self.preferences = None;
+                self.exit_insert_mode()
+            }
             AppMessage::FileDialogResult(path_buf_opt) => path_buf_opt
                 .map_or(iced::Task::none(), |path_buf| {
                     iced::Task::done(AppMessage::OpenFile(path_buf))
                 }),
-            AppMessage::CloseTab(i) => {
-                if self.pdfs.is_empty() {
-                    exit()
+            AppMessage::OpenSaveAsDialog => {
+                if let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) {
+                    let file_name = pdf.name.clone();
+                    let directory = pdf.path.parent().map(|p| p.to_path_buf());
+                    // Documents other than pdfs are saved by copying the file
+                    let (filter, extension) = match DocumentKind::of(&pdf.path) {
+                        DocumentKind::ImageArchive => (
+                            "Archive",
+                            pdf.path
+                                .extension()
+                                .map_or("cbz".to_string(), |ext| ext.to_string_lossy().to_string()),
+                        ),
+                        _ => ("Pdf", "pdf".to_string()),
+                    };
+                    iced::Task::perform(
+                        async move {
+                            let mut dialog = AsyncFileDialog::new()
+                                .add_filter(filter, &[extension])
+                                .set_file_name(file_name);
+                            if let Some(directory) = directory {
+                                dialog = dialog.set_directory(directory);
+                            }
+                            dialog
+                                .save_file()
+                                .await
+                                .map(|file_handle| file_handle.path().to_path_buf())
+                        },
+                        AppMessage::SaveAsDialogResult,
+                    )
                 } else {
-                    if let Some(sender) = &self.file_watcher {
-                        // We should never fill this up from here
-                        let _ = sender.blocking_send(WatchMessage::StopWatch(
-                            self.pdfs[self.pdf_idx].path.clone(),
-                        ));
-                    }
-                    self.pdfs.remove(i);
-                    if self.pdf_idx >= self.pdfs.len() {
-                        if self.pdfs.is_empty() {
-                            self.pdf_idx = 0;
-                        } else {
-                            self.pdf_idx = self.pdfs.len() - 1;
-                        }
-                    }
                     iced::Task::none()
                 }
             }
-            AppMessage::PreviousTab => {
-                self.pdf_idx = if self.pdf_idx == 0 {
-                    0
-                } else {
-                    self.pdf_idx - 1
+            AppMessage::SaveAsDialogResult(path_buf_opt) => {
+                if let Some(path_buf) = path_buf_opt
+                    && let Err(e) = self.save_active_pdf_as(path_buf)
+                {
+                    return self.notify(e);
+                }
+                iced::Task::none()
+            }
+            AppMessage::SplitByChapter => {
+                let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) else {
+                    return iced::Task::none();
+                };
+                let directory = pdf.path.parent().map(|p| p.to_path_buf());
+                iced::Task::perform(
+                    async move {
+                        let mut dialog = AsyncFileDialog::new();
+                        if let Some(directory) = directory {
+                            dialog = dialog.set_directory(directory);
+                        }
+                        dialog
+                            .pick_folder()
+                            .await
+                            .map(|file_handle| file_handle.path().to_path_buf())
+                    },
+                    AppMessage::SplitByChapterDirectory,
+                )
+            }
+            AppMessage::SplitByChapterDirectory(directory) => {
+                let (Some(directory), Some(pdf)) =
+                    (directory, self.tabs.pdfs.get(self.tabs.active))
+                else {
+                    return iced::Task::none();
+                };
+                let chapters =
+                    export::chapters(pdf.get_outline(), pdf.page_count().unwrap_or(0) as usize);
+                if chapters.len() < 2 {
+                    return self.notify(MiroError::document(format!(
+                        "{} has no chapters in its outline to split it at",
+                        pdf.name
+                    )));
+                }
+                let bytes = match pdf.pdf_bytes() {
+                    Ok(bytes) => bytes,
+                    Err(e) => return self.notify(MiroError::document(e)),
+                };
+                iced::Task::perform(
+                    tokio::task::spawn_blocking(move || {
+                        export::split_by_chapter(&bytes, &chapters, &directory)
+                            .map_err(|e| MiroError::file(&directory, e))
+                    }),
+                    |result| match result {
+                        Ok(Ok(written)) => {
+                            info!("Wrote {} chapters", written.len());
+                            AppMessage::None
+                        }
+                        Ok(Err(e)) => AppMessage::ShowError(e),
+                        Err(e) => AppMessage::ShowError(MiroError::document(e)),
+                    },
+                )
+            }
+            AppMessage::ExportDarkCopy => {
+                let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) else {
+                    return iced::Task::none();
+                };
+                let stem = Path::new(&pdf.name)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "document".to_string());
+                let directory = pdf.path.parent().map(|p| p.to_path_buf());
+                iced::Task::perform(
+                    async move {
+                        let mut dialog = AsyncFileDialog::new()
+                            .add_filter("Pdf", &["pdf"])
+                            .set_file_name(format!("{stem}-dark.pdf"));
+                        if let Some(directory) = directory {
+                            dialog = dialog.set_directory(directory);
+                        }
+                        dialog
+                            .save_file()
+                            .await
+                            .map(|file_handle| file_handle.path().to_path_buf())
+                    },
+                    AppMessage::ExportDarkCopyPath,
+                )
+            }
+            AppMessage::ExportDarkCopyPath(path) => {
+                let (Some(path), Some(pdf)) = (path, self.tabs.pdfs.get(self.tabs.active)) else {
+                    return iced::Task::none();
+                };
+                let bytes = match pdf.pdf_bytes() {
+                    Ok(bytes) => bytes,
+                    Err(e) => return self.notify(MiroError::document(e)),
+                };
+                let colors = pdf.dark_mode_colors();
+                iced::Task::perform(
+                    tokio::task::spawn_blocking(move || {
+                        export::dark_copy(&bytes, &colors, export::DARK_COPY_SCALE, &path)
+                            .map_err(|e| MiroError::file(&path, e))
+                    }),
+                    |result| match result {
+                        Ok(Ok(())) => AppMessage::None,
+                        Ok(Err(e)) => AppMessage::ShowError(e),
+                        Err(e) => AppMessage::ShowError(MiroError::document(e)),
+                    },
+                )
+            }
+            AppMessage::ExportText => {
+                let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) else {
+                    return iced::Task::none();
                 };
+                let stem = Path::new(&pdf.name)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "document".to_string());
+                let directory = pdf.path.parent().map(|p| p.to_path_buf());
+                iced::Task::perform(
+                    async move {
+                        let mut dialog = AsyncFileDialog::new()
+                            .add_filter("Text", &["txt"])
+                            .set_file_name(format!("{stem}.txt"));
+                        if let Some(directory) = directory {
+                            dialog = dialog.set_directory(directory);
+                        }
+                        dialog
+                            .save_file()
+                            .await
+                            .map(|file_handle| file_handle.path().to_path_buf())
+                    },
+                    AppMessage::ExportTextPath,
+                )
+            }
+            AppMessage::ExportTextPath(path) => {
+                let (Some(path), Some(pdf)) = (path, self.tabs.pdfs.get(self.tabs.active)) else {
+                    return iced::Task::none();
+                };
+                let text = pdf.document_text();
+                iced::Task::perform(
+                    tokio::task::spawn_blocking(move || {
+                        paths::ensure_writable().map_err(|e| MiroError::file(&path, e))?;
+                        std::fs::write(&path, text).map_err(|e| MiroError::file(&path, e))
+                    }),
+                    |result| match result {
+                        Ok(Ok(())) => AppMessage::None,
+                        Ok(Err(e)) => AppMessage::ShowError(e),
+                        Err(e) => AppMessage::ShowError(MiroError::document(e)),
+                    },
+                )
+            }
+            AppMessage::CloseTab(i) => self.close_tab(i),
+            AppMessage::PreviousTab => {
+                self.step_tab(-1);
                 iced::Task::none()
             }
             AppMessage::NextTab => {
-                if !self.pdfs.is_empty() {
-                    self.pdf_idx = (self.pdf_idx + 1).min(self.pdfs.len() - 1);
-                }
+                self.step_tab(1);
                 iced::Task::none()
             }
-            AppMessage::OpenTab(i) => {
-                self.pdf_idx = i;
+            AppMessage::OpenQuickSwitcher => {
+                self.quick_switcher = Some(QuickSwitcher::new(
+                    self.tabs
+                        .pdfs
+                        .iter()
+                        .map(|pdf| (pdf.path.clone(), pdf.name.clone()))
+                        .collect(),
+                    self.recent_files.get_recent(),
+                ));
+                self.enter_insert_mode();
+                widget::operation::focus(widget::Id::new("quick_switcher_input"))
+                    .map(|_: ()| AppMessage::None)
+            }
+            AppMessage::QuickSwitcherInput(query) => {
+                if let Some(switcher) = self.quick_switcher.as_mut() {
+                    switcher.set_query(query);
+                }
                 iced::Task::none()
             }
-            AppMessage::FileWatcher(watch_notification) => {
-                match watch_notification {
-                    WatchNotification::Ready(sender) => {
-                        self.file_watcher = Some(sender);
+            AppMessage::SwitchToEntry(idx) => {
+                let Some(switcher) = self.quick_switcher.take() else {
+                    return iced::Task::none();
+                };
+                let mode_task = self.exit_insert_mode();
+                match switcher.get(idx.unwrap_or(switcher.selected)) {
+                    Some(SwitchTarget::Tab(i)) => {
+                        iced::Task::batch([mode_task, iced::Task::done(AppMessage::OpenTab(i))])
                     }
-                    WatchNotification::Changed(path) => {
-                        self.pdfs
-                            .iter_mut()
-                            .find(|pdf| pdf.path == path)
-                            .map(|viewer| viewer.update(PdfMessage::FileChanged));
+                    Some(SwitchTarget::Recent(path)) => {
+                        iced::Task::batch([mode_task, iced::Task::done(AppMessage::OpenFile(path))])
                     }
+                    None => mode_task,
                 }
+            }
+            AppMessage::NextRecentTab => {
+                self.step_recent_tab(1);
                 iced::Task::none()
             }
+            AppMessage::PreviousRecentTab => {
+                self.step_recent_tab(-1);
+                iced::Task::none()
+            }
+            AppMessage::OpenTab(i) => {
+                self.tabs.open(i);
+                iced::Task::none()
+            }
+            AppMessage::FileWatcher(watch_notification) => match watch_notification {
+                WatchNotification::Ready(sender) => {
+                    if let Some(directory) = &self.watched_directory {
+                        // We should never fill this up from here, thus blocking is alright
+                        let _ = sender
+                            .blocking_send(WatchMessage::StartWatchDirectory(directory.clone()));
+                    }
+                    self.file_watcher = Some(sender);
+                    iced::Task::none()
+                }
+                // Replacing a pdf that is already open shows up as a new one
+                WatchNotification::Created(path)
+                    if self.tabs.pdfs.iter().any(|pdf| pdf.path == path) =>
+                {
+                    iced::Task::none()
+                }
+                WatchNotification::Created(path) => {
+                    info!("Opening {path:?} which was added to the watched directory");
+                    iced::Task::done(AppMessage::OpenFile(path))
+                }
+                WatchNotification::Changed(path) => {
+                    let Some((i, viewer)) = self
+                        .tabs
+                        .pdfs
+                        .iter_mut()
+                        .enumerate()
+                        .find(|(_, pdf)| pdf.path == path)
+                    else {
+                        return iced::Task::none();
+                    };
+                    let _ = viewer.update(PdfMessage::FileChanged);
+                    let hook_task = run_hooks(HookEvent::FileChanged, viewer);
+                    if i != self.tabs.active {
+                        viewer.unseen_changes = true;
+                        if CONFIG.read().unwrap().flash_on_change {
+                            return iced::Task::batch([
+                                hook_task,
+                                iced::window::latest().and_then(|id| {
+                                    iced::window::request_user_attention(
+                                        id,
+                                        Some(window::UserAttention::Informational),
+                                    )
+                                }),
+                            ]);
+                        }
+                    }
+                    hook_task
+                }
+                WatchNotification::Error(e) => self.notify(e),
+            },
             AppMessage::ToggleDarkModeUi => {
                 self.dark_mode = !self.dark_mode;
-                for pdf in &mut self.pdfs {
+                for pdf in &mut self.tabs.pdfs {
                     pdf.set_interface_dark_mode(self.invert_pdf);
                 }
                 iced::Task::none()
             }
+            AppMessage::SystemThemeChanged(mode) => {
+                let dark = match mode {
+                    iced::theme::Mode::Dark => true,
+                    iced::theme::Mode::Light => false,
+                    iced::theme::Mode::None => return iced::Task::none(),
+                };
+                self.dark_mode = dark;
+                for pdf in &mut self.tabs.pdfs {
+                    pdf.set_interface_dark_mode(self.dark_mode);
+                }
+                if CONFIG.read().unwrap().follow_system_theme_pdf {
+                    self.invert_pdf = dark;
+                    for pdf in &mut self.tabs.pdfs {
+                        pdf.set_pdf_dark_mode(self.invert_pdf);
+                    }
+                }
+                iced::Task::none()
+            }
             AppMessage::ToggleDarkModePdf => {
                 self.invert_pdf = !self.invert_pdf;
-                for pdf in &mut self.pdfs {
+                for pdf in &mut self.tabs.pdfs {
                     pdf.set_pdf_dark_mode(self.invert_pdf);
                 }
                 iced::Task::none()
             }
             AppMessage::TogglePageBorders => {
                 self.draw_page_borders = !self.draw_page_borders;
-                for pdf in &mut self.pdfs {
+                for pdf in &mut self.tabs.pdfs {
                     pdf.draw_page_borders = self.draw_page_borders;
                 }
                 iced::Task::none()
             }
             AppMessage::None => iced::Task::none(),
             AppMessage::MouseMoved(vector) => {
-                if !self.pdfs.is_empty() {
-                    self.pdfs[self.pdf_idx]
-                        .update(PdfMessage::MouseMoved(vector))
-                        .map(AppMessage::PdfMessage)
-                } else {
-                    iced::Task::none()
+                if self.distraction_free {
+                    if vector.y <= CHROME_REVEAL_DISTANCE {
+                        self.set_chrome_revealed(true);
+                    } else if vector.y > CHROME_HIDE_DISTANCE {
+                        self.set_chrome_revealed(false);
+                    }
                 }
+                self.move_mouse(vector)
             }
+            // Open menus can reach further down than CHROME_HIDE_DISTANCE, the menu bar stays
+            // while the mouse is on them
+            AppMessage::CapturedMouseMoved(vector) => self.move_mouse(vector),
             AppMessage::SearchHover(hover) => {
                 self.search_hover = hover;
                 iced::Task::none()
             }
+            AppMessage::ScrubPage(page) => {
+                self.scrub_preview = Some(page);
+                iced::Task::none()
+            }
+            AppMessage::FinishScrub => match self.scrub_preview.take() {
+                Some(page) => iced::Task::done(AppMessage::OutlineGoToPage(page)),
+                None => iced::Task::none(),
+            },
+            AppMessage::ScrubberHover(hover) => {
+                self.scrubber_hover = hover;
+                iced::Task::none()
+            }
             AppMessage::MouseButtonDown(button) => {
-                if self.search_open && self.search_hover {
+                // Text inputs lose focus when clicking elsewhere, typing into one again re-enters
+                // insert mode through its on_input message.
+                if !(self.search_open && self.search_hover) {
+                    self.input_mode = InputMode::Normal;
+                }
+                if (self.search_open && self.search_hover) || self.scrubber_hover {
                     iced::Task::none()
-                } else if !self.pdfs.is_empty()
+                } else if !self.tabs.pdfs.is_empty()
                     && let Some(action) = self.get_mouse_action(button)
                 {
-                    self.pdfs[self.pdf_idx]
+                    self.tabs.pdfs[self.tabs.active]
                         .update(PdfMessage::MouseAction(action, true))
                         .map(AppMessage::PdfMessage)
                 } else {
@@ -419,10 +1795,10 @@ impl App {
             AppMessage::MouseButtonUp(button) => {
                 if self.search_open && self.search_hover {
                     iced::Task::none()
-                } else if !self.pdfs.is_empty()
+                } else if !self.tabs.pdfs.is_empty()
                     && let Some(action) = self.get_mouse_action(button)
                 {
-                    self.pdfs[self.pdf_idx]
+                    self.tabs.pdfs[self.tabs.active]
                         .update(PdfMessage::MouseAction(action, false))
                         .map(AppMessage::PdfMessage)
                 } else {
@@ -435,29 +1811,53 @@ impl App {
             }
             AppMessage::CtrlPressed(pressed) => {
                 self.ctrl_pressed = pressed;
+                if !pressed {
+                    self.tabs.finish_switch();
+                }
                 iced::Task::none()
             }
             AppMessage::ModifiersChanged(modifiers) => {
                 self.shift_pressed = modifiers.shift();
                 self.ctrl_pressed = modifiers.control();
+                if !self.ctrl_pressed {
+                    self.tabs.finish_switch();
+                }
                 iced::Task::none()
             }
             AppMessage::BookmarkMessage(BookmarkMessage::RequestNewBookmark { name }) => {
-                let path = self.pdfs.get(self.pdf_idx).map(|pdf| pdf.path.clone());
-                let page = self.pdfs.get(self.pdf_idx).map(|pdf| pdf.current_page());
+                let mode_task = self.exit_insert_mode();
+                let path = self
+                    .tabs
+                    .pdfs
+                    .get(self.tabs.active)
+                    .map(|pdf| pdf.path.clone());
+                let page = self
+                    .tabs
+                    .pdfs
+                    .get(self.tabs.active)
+                    .map(|pdf| pdf.current_page());
                 if let (Some(path), Some(page)) = (path, page) {
-                    self.bookmark_store
-                        .update(BookmarkMessage::CreateBookmark { path, name, page })
-                        .map(AppMessage::BookmarkMessage)
+                    iced::Task::batch([
+                        mode_task,
+                        self.bookmark_store
+                            .update(BookmarkMessage::CreateBookmark { path, name, page })
+                            .map(AppMessage::BookmarkMessage),
+                    ])
                 } else {
-                    iced::Task::none()
+                    mode_task
                 }
             }
+            AppMessage::BookmarkMessage(BookmarkMessage::PendingName(name)) => {
+                self.enter_insert_mode();
+                self.bookmark_store
+                    .update(BookmarkMessage::PendingName(name))
+                    .map(AppMessage::BookmarkMessage)
+            }
             AppMessage::BookmarkMessage(BookmarkMessage::GoTo { path, page }) => {
-                if let Some(pdf_index) = self.pdfs.iter().position(|pdf| pdf.path == path) {
+                if let Some(pdf_index) = self.tabs.position(&path) {
                     self.record_location();
-                    self.pdf_idx = pdf_index;
-                    let pdf_msg = self.pdfs[pdf_index]
+                    self.tabs.active = pdf_index;
+                    let pdf_msg = self.tabs.pdfs[pdf_index]
                         .update(PdfMessage::SetPage(page))
                         .map(AppMessage::PdfMessage);
                     self.record_location();
@@ -475,16 +1875,66 @@ impl App {
                 .update(bookmark_message)
                 .map(AppMessage::BookmarkMessage),
             AppMessage::PaneResize(pane_grid::ResizeEvent { split, ratio }) => {
-                self.pane_state.resize(split, ratio);
+                let side = CONFIG.read().unwrap().sidebar_side;
+                self.sidebar_ratio = side
+                    .split_ratio(ratio)
+                    .clamp(*SIDEBAR_RATIO_RANGE.start(), *SIDEBAR_RATIO_RANGE.end());
+                self.pane_state
+                    .resize(split, side.split_ratio(self.sidebar_ratio));
+                iced::Task::none()
+            }
+            AppMessage::ResizeSidebar(delta) => {
+                if let Some(split) = self.sidebar_split() {
+                    self.sidebar_ratio = (self.sidebar_ratio + delta)
+                        .clamp(*SIDEBAR_RATIO_RANGE.start(), *SIDEBAR_RATIO_RANGE.end());
+                    let side = CONFIG.read().unwrap().sidebar_side;
+                    self.pane_state
+                        .resize(split, side.split_ratio(self.sidebar_ratio));
+                }
+                iced::Task::none()
+            }
+            AppMessage::PaneClicked(pane) => {
+                if let Some(clicked) = self.pane_state.get(pane) {
+                    self.active_pane = clicked.pane_type;
+                }
+                iced::Task::none()
+            }
+            AppMessage::FocusNextPane => {
+                self.active_pane = match self.active_pane {
+                    PaneType::Pdf if self.has_sidebar_pane() => PaneType::Sidebar,
+                    _ => PaneType::Pdf,
+                };
                 iced::Task::none()
             }
+            AppMessage::KeyAction(action) => {
+                // The sidebar is hidden in presentation mode, so keys go to the document even if
+                // the sidebar had focus before
+                let pane = if self.presentation_mode {
+                    PaneType::Pdf
+                } else {
+                    self.active_pane
+                };
+                match pane {
+                    PaneType::Sidebar => match self.sidebar_key_action(action) {
+                        Some(task) => task,
+                        None => self.handle_message(action.into()),
+                    },
+                    PaneType::Pdf => self.handle_message(action.into()),
+                }
+            }
             AppMessage::ToggleSidebar => {
                 if self.has_sidebar_pane() {
                     if let Some(sidebar_id) = self.get_sidebar_pane_id() {
                         self.pane_state.close(sidebar_id);
                     }
+                    self.active_pane = PaneType::Pdf;
                 } else if let Some(pdf_id) = self.get_pdf_pane_id() {
-                    Self::open_sidebar(&mut self.pane_state, pdf_id);
+                    Self::open_sidebar(
+                        &mut self.pane_state,
+                        pdf_id,
+                        self.sidebar_ratio,
+                        CONFIG.read().unwrap().sidebar_side,
+                    );
                 }
                 iced::Task::none()
             }
@@ -493,9 +1943,9 @@ impl App {
                 iced::Task::none()
             }
             AppMessage::OutlineGoToPage(page) => {
-                if !self.pdfs.is_empty() {
+                if !self.tabs.pdfs.is_empty() {
                     self.record_location();
-                    let pdf_msg = self.pdfs[self.pdf_idx]
+                    let pdf_msg = self.tabs.pdfs[self.tabs.active]
                         .update(PdfMessage::SetPage(page))
                         .map(AppMessage::PdfMessage);
                     self.record_location();
@@ -504,9 +1954,39 @@ impl App {
                     iced::Task::none()
                 }
             }
-            AppMessage::CloseActiveTab => iced::Task::done(AppMessage::CloseTab(self.pdf_idx)),
+            AppMessage::CloseActiveTab => self.close_tab(self.tabs.active),
+            AppMessage::ReopenClosedTab(idx) => match self.closed_tabs.take(idx) {
+                Some(tab) => {
+                    if let Some(m) = &self.mac_menu {
+                        m.update_closed_tabs(self.closed_tabs.get_closed());
+                    }
+                    if tab.path.exists() {
+                        iced::Task::done(AppMessage::OpenFile(tab.path.clone()))
+                            .chain(iced::Task::done(AppMessage::RestoreClosedTab(tab)))
+                    } else {
+                        self.notify(MiroError::file(tab.path, "The file no longer exists"))
+                    }
+                }
+                None => iced::Task::none(),
+            },
+            AppMessage::RestoreClosedTab(tab) => {
+                match self
+                    .tabs
+                    .pdfs
+                    .iter_mut()
+                    .rev()
+                    .find(|pdf| pdf.path == tab.path)
+                {
+                    Some(pdf) => {
+                        pdf.scale = tab.scale;
+                        pdf.update(PdfMessage::SetPage(tab.page))
+                            .map(AppMessage::PdfMessage)
+                    }
+                    None => iced::Task::none(),
+                }
+            }
             AppMessage::Scroll(delta) => {
-                if !self.pdfs.is_empty() {
+                if !self.tabs.pdfs.is_empty() {
                     match delta {
                         iced::mouse::ScrollDelta::Lines { y, .. } => {
                             let button = if y > 0.0 {
@@ -517,26 +1997,62 @@ impl App {
                                 return iced::Task::none();
                             };
                             if let Some(action) = self.get_mouse_action(button) {
-                                self.pdfs[self.pdf_idx]
+                                self.tabs.pdfs[self.tabs.active]
                                     .update(PdfMessage::MouseAction(action, true))
                                     .map(AppMessage::PdfMessage)
                             } else {
                                 iced::Task::none()
                             }
                         }
+                        // Trackpads report pinches as scrolling with Ctrl held
+                        iced::mouse::ScrollDelta::Pixels { y, .. } if self.ctrl_pressed => {
+                            self.tabs.pdfs[self.tabs.active]
+                                .update(PdfMessage::Pinch(y))
+                                .map(AppMessage::PdfMessage)
+                        }
                         iced::mouse::ScrollDelta::Pixels { x, y } => {
                             let sensitivity = CONFIG.read().unwrap().trackpad_sensitivity;
-                            let move_vec = Vector::new(-x * sensitivity, y * sensitivity);
-                            self.pdfs[self.pdf_idx]
-                                .update(PdfMessage::Move(move_vec))
-                                .map(AppMessage::PdfMessage)
+                            let delta = Vector::new(-x * sensitivity, y * sensitivity);
+                            self.pending_move = match self.pending_move {
+                                Some((idx, pending)) if idx == self.tabs.active => {
+                                    Some((idx, pending + delta))
+                                }
+                                _ => Some((self.tabs.active, delta)),
+                            };
+                            iced::Task::none()
                         }
                     }
                 } else {
                     iced::Task::none()
                 }
             }
-            AppMessage::Exit => exit(),
+            AppMessage::ApplyPendingMove => match self.pending_move.take() {
+                Some((idx, pending)) if idx < self.tabs.pdfs.len() => self.tabs.pdfs[idx]
+                    .update(PdfMessage::Move(pending))
+                    .map(AppMessage::PdfMessage),
+                _ => iced::Task::none(),
+            },
+            AppMessage::Exit => self.exit(),
+            AppMessage::UnsavedChanges(choice) => {
+                let Some(pending) = self.unsaved_prompt.take() else {
+                    return iced::Task::none();
+                };
+                if choice == UnsavedChoice::Cancel {
+                    return iced::Task::none();
+                }
+                for i in self.unsaved_tabs(pending) {
+                    if choice == UnsavedChoice::Save
+                        && let Err(e) = self.tabs.pdfs[i].save_before_close()
+                    {
+                        return self.notify(MiroError::file(self.tabs.pdfs[i].path.clone(), e));
+                    }
+                    self.tabs.pdfs[i].modified = false;
+                }
+                match pending {
+                    PendingClose::Tab(i) => self.remove_tab(i),
+                    PendingClose::Exit => exit(),
+                }
+            }
             AppMessage::FoundWindowId(id) => match id {
                 Some(id) => iced::window::scale_factor(id)
                     .map(AppMessage::FoundScaleFactor)
@@ -545,51 +2061,166 @@ impl App {
             },
             AppMessage::FoundScaleFactor(scale) => {
                 self.scale_factor = scale as f64;
-                for viewer in &mut self.pdfs {
+                for viewer in &mut self.tabs.pdfs {
                     viewer.set_scale_factor(self.scale_factor);
                 }
                 iced::Task::none()
             }
-            AppMessage::JumpTo(location) => {
-                match self
-                    .pdfs
-                    .iter_mut()
-                    .enumerate()
-                    .find(|(_, pdf)| pdf.path == location.pdf_path)
-                {
-                    Some((i, pdf)) => {
-                        self.pdf_idx = i;
-                        pdf.update(PdfMessage::SetLocation(
-                            location.translation,
-                            location.scale,
-                        ))
-                        .map(AppMessage::PdfMessage)
-                    }
-                    None => iced::Task::done(AppMessage::OpenFile(location.pdf_path.clone()))
-                        .chain(iced::Task::done(AppMessage::JumpTo(location))),
+            AppMessage::JumpTo(location) => self.jump_to(location),
+            AppMessage::JumpBack => self.jump_back(),
+            AppMessage::JumpForward => self.jump_forward(),
+            AppMessage::ToggleJumplist => {
+                self.jumplist_open = !self.jumplist_open;
+                iced::Task::none()
+            }
+            AppMessage::JumpToEntry(idx) => {
+                self.jumplist_open = false;
+                match self.jumplist.jump_to(idx) {
+                    Some(location) => self.jump_to(location.clone()),
+                    None => iced::Task::none(),
                 }
             }
-            AppMessage::JumpBack => {
-                if let Some(location) = self.jumplist.jump_back() {
-                    return iced::Task::done(AppMessage::JumpTo(location.clone()));
+            AppMessage::OpenGlobalSearch => {
+                if self.global_search.is_none() {
+                    self.global_search = Some(GlobalSearch::new(
+                        CONFIG.read().unwrap().default_search_method,
+                    ));
                 }
-                iced::Task::none()
+                self.enter_insert_mode();
+                widget::operation::focus(widget::Id::new("global_search_input"))
+                    .map(|_: ()| AppMessage::None)
             }
-            AppMessage::JumpForward => {
-                if let Some(location) = self.jumplist.jump_forward() {
-                    return iced::Task::done(AppMessage::JumpTo(location.clone()));
+            AppMessage::GlobalSearchInput(needle) => {
+                self.enter_insert_mode();
+                let Some(search) = self.global_search.as_mut() else {
+                    return iced::Task::none();
+                };
+                search.needle = needle;
+                if search.needle.is_empty() {
+                    search.restart(vec![]);
+                    return iced::Task::none();
                 }
-                iced::Task::none()
+                let generation = search.restart(
+                    self.tabs
+                        .pdfs
+                        .iter()
+                        .map(|pdf| (pdf.path.clone(), pdf.name.clone()))
+                        .collect(),
+                );
+                let (method, flags) = (search.method, search.flags);
+                iced::Task::batch(self.tabs.pdfs.iter().map(|pdf| {
+                    let (text, char_bboxes) = pdf.search_source();
+                    let needle = search.needle.clone();
+                    let path = pdf.path.clone();
+                    iced::Task::perform(
+                        tokio::task::spawn_blocking(move || {
+                            global_search::search_document(
+                                text,
+                                char_bboxes,
+                                &needle,
+                                method,
+                                flags,
+                            )
+                        }),
+                        move |result| {
+                            AppMessage::GlobalSearchResults(
+                                generation,
+                                path,
+                                result.unwrap_or_default(),
+                            )
+                        },
+                    )
+                }))
             }
-            AppMessage::ToggleFullscreen => toggle_fullscreen(),
-            AppMessage::TogglePresentationMode => {
-                self.presentation_mode = !self.presentation_mode;
+            AppMessage::GlobalSearchMethod(method) => {
+                let Some(search) = self.global_search.as_mut() else {
+                    return iced::Task::none();
+                };
+                search.method = method;
+                iced::Task::done(AppMessage::GlobalSearchInput(search.needle.clone()))
+            }
+            AppMessage::GlobalSearchFlags(flags) => {
+                let Some(search) = self.global_search.as_mut() else {
+                    return iced::Task::none();
+                };
+                search.flags = flags;
+                iced::Task::done(AppMessage::GlobalSearchInput(search.needle.clone()))
+            }
+            AppMessage::GlobalSearchResults(generation, path, matches) => {
+                if let Some(search) = self.global_search.as_mut() {
+                    search.finish(generation, &path, matches);
+                }
                 iced::Task::none()
             }
-            AppMessage::OpenSearch => {
-                self.search_open = true;
-                let search_task = if !self.pdfs.is_empty() {
-                    self.pdfs[self.pdf_idx]
+            AppMessage::MovePopupSelection(delta) => {
+                if let Some(switcher) = self.quick_switcher.as_mut() {
+                    switcher.move_selection(delta);
+                    return iced::Task::none();
+                }
+                if let Some((Prompt::GoToDestination, input)) = &self.prompt {
+                    let listed = self.listed_destinations(input).len();
+                    if listed > 0 {
+                        self.prompt_selected = (self.prompt_selected as i64 + delta as i64)
+                            .clamp(0, listed as i64 - 1)
+                            as usize;
+                    }
+                    return iced::Task::none();
+                }
+                iced::Task::done(AppMessage::MoveGlobalSearchSelection(delta))
+            }
+            AppMessage::MoveGlobalSearchSelection(delta) => {
+                if let Some(search) = self.global_search.as_mut() {
+                    search.move_selection(delta);
+                }
+                iced::Task::none()
+            }
+            AppMessage::OpenGlobalSearchResult(idx) => {
+                let Some(search) = self.global_search.take() else {
+                    return iced::Task::none();
+                };
+                let mode_task = self.exit_insert_mode();
+                let Some((path, found)) = search.get(idx.unwrap_or(search.selected)) else {
+                    return mode_task;
+                };
+                let Some(i) = self.tabs.pdfs.iter().position(|pdf| pdf.path == path) else {
+                    return mode_task;
+                };
+                self.record_location();
+                self.tabs.active = i;
+                // Opened so the highlighted matches can be stepped through and dismissed
+                self.search_open = true;
+                let jump_task = self.tabs.pdfs[i]
+                    .update(PdfMessage::ShowSearchMatch(
+                        search.needle.clone(),
+                        search.method,
+                        search.flags,
+                        found.found.clone(),
+                    ))
+                    .map(AppMessage::PdfMessage);
+                self.record_location();
+                iced::Task::batch([mode_task, jump_task])
+            }
+            AppMessage::ToggleFullscreen => toggle_fullscreen(),
+            AppMessage::TogglePresentationMode => {
+                self.presentation_mode = !self.presentation_mode;
+                self.sync_presentation_pointer();
+                iced::Task::none()
+            }
+            AppMessage::CyclePresentationPointer => {
+                self.presentation_pointer = self.presentation_pointer.next();
+                self.sync_presentation_pointer();
+                iced::Task::none()
+            }
+            AppMessage::ToggleDistractionFree => {
+                self.distraction_free = !self.distraction_free;
+                self.set_chrome_revealed(false);
+                iced::Task::none()
+            }
+            AppMessage::OpenSearch => {
+                self.search_open = true;
+                self.enter_insert_mode();
+                let search_task = if !self.tabs.pdfs.is_empty() {
+                    self.tabs.pdfs[self.tabs.active]
                         .update(PdfMessage::HighlightSearchResults)
                         .map(AppMessage::PdfMessage)
                 } else {
@@ -606,14 +2237,32 @@ impl App {
                 }
             }
             AppMessage::CloseSearch => {
+                // Escape is bound to CloseSearch by default and should dismiss any popup
+                self.jumplist_open = false;
+                self.global_search = None;
+                self.quick_switcher = None;
+                self.tabs.history.cancel();
+                self.prompt = None;
+                self.translation = None;
+                self.tool_log_open = false;
+                self.tab_menu = None;
+                self.properties = None;
+                self.preferences = None;
+                if let Some(pdf) = self.tabs.pdfs.get_mut(self.tabs.active) {
+                    let _ = pdf.update(PdfMessage::CloseOverview);
+                }
                 if self.search_open {
                     self.search_open = false;
-                    if !self.pdfs.is_empty() {
-                        self.pdfs[self.pdf_idx]
-                            .update(PdfMessage::HideSearchResults)
-                            .map(AppMessage::PdfMessage)
+                    let mode_task = self.exit_insert_mode();
+                    if !self.tabs.pdfs.is_empty() {
+                        iced::Task::batch([
+                            mode_task,
+                            self.tabs.pdfs[self.tabs.active]
+                                .update(PdfMessage::HideSearchResults)
+                                .map(AppMessage::PdfMessage),
+                        ])
                     } else {
-                        iced::Task::none()
+                        mode_task
                     }
                 } else {
                     iced::Task::none()
@@ -621,7 +2270,7 @@ impl App {
             }
             AppMessage::ToggleSearchMethod => {
                 if self.search_open {
-                    if let Some(viewer) = self.pdfs.get_mut(self.pdf_idx) {
+                    if let Some(viewer) = self.tabs.pdfs.get_mut(self.tabs.active) {
                         viewer
                             .update(PdfMessage::ToggleSearchMethod)
                             .map(AppMessage::PdfMessage)
@@ -632,43 +2281,293 @@ impl App {
                     iced::Task::none()
                 }
             }
+            AppMessage::KeySequencePending => {
+                self.key_sequence_generation = self.key_sequence_generation.wrapping_add(1);
+                let generation = self.key_sequence_generation;
+                let timeout = CONFIG.read().unwrap().keyboard.timeout();
+                iced::Task::perform(tokio::time::sleep(timeout), move |_| {
+                    AppMessage::KeySequenceTimeout(generation)
+                })
+            }
+            AppMessage::KeySequenceCancel => {
+                self.key_sequence_generation = self.key_sequence_generation.wrapping_add(1);
+                iced::Task::none()
+            }
+            AppMessage::KeySequenceTimeout(generation) => {
+                if generation == self.key_sequence_generation {
+                    CONFIG.write().unwrap().keyboard.reset();
+                }
+                iced::Task::none()
+            }
+            AppMessage::WindowResized => {
+                self.resize_generation = self.resize_generation.wrapping_add(1);
+                let generation = self.resize_generation;
+                let debounce = CONFIG.read().unwrap().autofit_debounce;
+                iced::Task::perform(tokio::time::sleep(debounce), move |_| {
+                    AppMessage::ResizeSettled(generation)
+                })
+            }
+            AppMessage::WindowUnfocused => {
+                self.window_focused = false;
+                iced::window::latest()
+                    .and_then(iced::window::is_minimized)
+                    .map(|minimized| AppMessage::WindowHidden(minimized == Some(true)))
+            }
+            AppMessage::WindowFocused => {
+                self.window_focused = true;
+                self.window_hidden = false;
+                iced::Task::none()
+            }
+            AppMessage::WindowHidden(hidden) => {
+                self.window_hidden = hidden;
+                iced::Task::none()
+            }
+            AppMessage::ResizeSettled(generation) => {
+                if generation == self.resize_generation
+                    && let Some(pdf) = self.tabs.pdfs.get_mut(self.tabs.active)
+                {
+                    pdf.update(PdfMessage::ZoomFit).map(AppMessage::PdfMessage)
+                } else {
+                    iced::Task::none()
+                }
+            }
+            AppMessage::EnterInsertMode => {
+                self.enter_insert_mode();
+                iced::Task::none()
+            }
+            AppMessage::ExitInsertMode => {
+                self.prompt = None;
+                self.global_search = None;
+                self.quick_switcher = None;
+                self.preferences = None;
+                self.exit_insert_mode()
+            }
+            AppMessage::WatchDirectory(directory) => {
+                if let Some(sender) = &self.file_watcher {
+                    // We should never fill this up from here, thus blocking is alright
+                    let _ =
+                        sender.blocking_send(WatchMessage::StartWatchDirectory(directory.clone()));
+                }
+                self.watched_directory = Some(directory);
+                iced::Task::none()
+            }
+            AppMessage::StartBuildWatch(watch) => {
+                self.build = Some(BuildState::new(watch));
+                iced::Task::none()
+            }
+            AppMessage::BuildWatcher(notification) => {
+                let Some(build) = self.build.as_mut() else {
+                    return iced::Task::none();
+                };
+                if build.update(notification) {
+                    // The file watcher might not notice if the build rewrote the file in place
+                    for pdf in self
+                        .tabs
+                        .pdfs
+                        .iter_mut()
+                        .filter(|pdf| pdf.path.starts_with(&build.watch.dir))
+                    {
+                        let _ = pdf.update(PdfMessage::FileChanged);
+                    }
+                }
+                iced::Task::none()
+            }
+            AppMessage::ToggleBuildPanel => {
+                if let Some(build) = self.build.as_mut() {
+                    build.expanded = !build.expanded;
+                }
+                iced::Task::none()
+            }
         }
     }
 
-    fn record_location(&mut self) {
-        if let Some(pdf) = self.pdfs.get(self.pdf_idx) {
-            self.jumplist.push(JumpLocation {
-                pdf_path: pdf.path.clone(),
-                translation: pdf.translation,
-                scale: pdf.scale,
-            })
-        };
+    /// The pointer is remembered outside of presentation mode but only drawn in it.
+    fn shown_presentation_pointer(&self) -> PresentationPointer {
+        if self.presentation_mode {
+            self.presentation_pointer
+        } else {
+            PresentationPointer::Off
+        }
+    }
+
+    fn sync_presentation_pointer(&mut self) {
+        let pointer = self.shown_presentation_pointer();
+        for pdf in &mut self.tabs.pdfs {
+            pdf.presentation_pointer = pointer;
+        }
+    }
+
+    fn enter_insert_mode(&mut self) {
+        if self.input_mode != InputMode::Insert {
+            self.input_mode = InputMode::Insert;
+            // A half typed sequence like "Z" must not complete once we return to normal mode
+            CONFIG.write().unwrap().keyboard.reset();
+            self.key_sequence_generation = self.key_sequence_generation.wrapping_add(1);
+        }
+    }
+
+    fn notify(&mut self, error: MiroError) -> iced::Task<AppMessage> {
+        error!("{error}");
+        let id = self.next_notification_id;
+        self.next_notification_id = self.next_notification_id.wrapping_add(1);
+        self.notifications.push(Notification {
+            id,
+            text: error.to_string(),
+        });
+        iced::Task::perform(tokio::time::sleep(NOTIFICATION_DURATION), move |_| {
+            AppMessage::DismissNotification(id)
+        })
+    }
+
+    fn notifications_view(&self) -> Element<'_, AppMessage> {
+        let mut col = widget::column![].spacing(4.0);
+        for notification in &self.notifications {
+            col = col.push(
+                widget::mouse_area(
+                    container(
+                        text(notification.text.as_str())
+                            .size(13.0)
+                            .shaping(text::Shaping::Advanced),
+                    )
+                    .width(Length::Fixed(360.0))
+                    .padding(8.0)
+                    .style(|theme: &Theme| container::Style {
+                        background: Some(theme.extended_palette().background.weak.color.into()),
+                        border: Border {
+                            color: theme.extended_palette().danger.base.color,
+                            width: 2.0,
+                            radius: Radius::from(8.0),
+                        },
+                        ..Default::default()
+                    }),
+                )
+                .on_press(AppMessage::DismissNotification(notification.id)),
+            );
+        }
+        col.into()
+    }
+
+    fn set_chrome_revealed(&mut self, revealed: bool) {
+        if self.chrome_revealed && !revealed {
+            // The scrubber disappears with the tabs before it can notice the mouse leaving it
+            self.scrubber_hover = false;
+        }
+        self.chrome_revealed = revealed;
+    }
+
+    fn move_mouse(&mut self, position: Vector<f32>) -> iced::Task<AppMessage> {
+        match self.tabs.pdfs.get_mut(self.tabs.active) {
+            Some(pdf) => pdf
+                .update(PdfMessage::MouseMoved(position))
+                .map(AppMessage::PdfMessage),
+            None => iced::Task::none(),
+        }
+    }
+
+    fn open_prompt(&mut self, prompt: Prompt) -> iced::Task<AppMessage> {
+        self.prompt = Some((prompt, String::new()));
+        self.prompt_selected = 0;
+        self.enter_insert_mode();
+        widget::operation::focus(widget::Id::new("prompt_input")).map(|_: ()| AppMessage::None)
+    }
+
+    fn exit_insert_mode(&mut self) -> iced::Task<AppMessage> {
+        if self.input_mode == InputMode::Insert {
+            self.input_mode = InputMode::Normal;
+            iced::advanced::widget::operate(
+                iced::advanced::widget::operation::focusable::unfocus::<()>(),
+            )
+            .discard()
+        } else {
+            iced::Task::none()
+        }
     }
 
     fn create_tabs(&self) -> Element<'_, AppMessage> {
         let mut command_bar = widget::Row::new();
-        for (i, pdf) in self.pdfs.iter().enumerate() {
+        for i in self.tab_order() {
+            let pdf = &self.tabs.pdfs[i];
+            let is_open = i == self.tabs.active;
+            let page_progress = match self.scrub_preview.filter(|_| is_open) {
+                Some(page) => format!("({} / {})", page + 1, pdf.page_count().unwrap_or(0)),
+                None => pdf.page_progress(),
+            };
+            let scrubber = is_open.then(|| {
+                widget::mouse_area(
+                    widget::canvas(PageScrubber {
+                        current_page: pdf.current_page(),
+                        page_count: pdf.page_count().unwrap_or(0).max(0) as usize,
+                        preview: self.scrub_preview,
+                    })
+                    .width(Length::Fill)
+                    .height(Length::Fixed(scrubber::HEIGHT)),
+                )
+                .on_enter(AppMessage::ScrubberHover(true))
+                .on_exit(AppMessage::ScrubberHover(false))
+                .into()
+            });
             command_bar = command_bar.push(file_tab(
-                &pdf.name,
-                pdf.page_progress(),
+                middle_ellipsis(&pdf.name, MAX_TAB_TITLE_CHARS),
+                pdf.path.display().to_string(),
+                page_progress,
+                pdf.unseen_changes,
+                is_open.then(|| signature_badge(pdf.signatures())).flatten(),
+                self.session_store.tab_color(&pdf.path).map(TabColor::color),
+                scrubber,
                 AppMessage::OpenTab(i),
                 AppMessage::CloseTab(i),
-                i == self.pdf_idx,
+                AppMessage::ShowTabMenu(i),
+                is_open,
             ));
         }
         command_bar = command_bar.spacing(4.0).height(Length::Shrink);
-        scrollable(command_bar)
+        let tabs = scrollable(command_bar)
             .direction(Direction::Horizontal(
                 Scrollbar::default().scroller_width(0.0).width(0.0),
             ))
-            .into()
+            .width(Length::Fill);
+        if self.tabs.pdfs.len() < 2 {
+            return tabs.into();
+        }
+        // Tabs scrolled out of view can still be picked from the list of all of them
+        let choices: Vec<TabChoice> = self
+            .tabs
+            .pdfs
+            .iter()
+            .enumerate()
+            .map(|(idx, pdf)| TabChoice {
+                idx,
+                name: pdf.name.clone(),
+            })
+            .collect();
+        widget::row![
+            tabs,
+            widget::pick_list(choices, None::<TabChoice>, |choice| {
+                AppMessage::OpenTab(choice.idx)
+            })
+            .placeholder(format!("{} Tabs", self.tabs.pdfs.len())),
+        ]
+        .spacing(4.0)
+        .align_y(alignment::Vertical::Center)
+        .into()
     }
 
     fn search_view(&self) -> Element<'_, AppMessage> {
-        let search_method = self.pdfs.get(self.pdf_idx).map(|x| x.search_method);
+        let search_method = self
+            .tabs
+            .pdfs
+            .get(self.tabs.active)
+            .map(|x| x.search_method);
+        let search_flags = self
+            .tabs
+            .pdfs
+            .get(self.tabs.active)
+            .map(|x| x.search_flags)
+            .unwrap_or_default();
         let search_progress = self
+            .tabs
             .pdfs
-            .get(self.pdf_idx)
+            .get(self.tabs.active)
             .map(|x| x.search_progress())
             .unwrap_or_default();
         widget::row![
@@ -678,30 +2577,22 @@ impl App {
                     widget::column![
                         widget::text_input(
                             "Search",
-                            self.pdfs
-                                .get(self.pdf_idx)
+                            self.tabs
+                                .pdfs
+                                .get(self.tabs.active)
                                 .map(|x| x.needle.as_str())
                                 .unwrap_or("")
                         )
                         .id(widget::Id::new("search_input"))
-                        .on_input(|x| AppMessage::PdfMessage(PdfMessage::UpdateSearchNeedle(x))),
+                        .on_input(|x| AppMessage::PdfMessage(PdfMessage::UpdateSearchNeedle(x)))
+                        .on_submit(AppMessage::ExitInsertMode),
                         widget::row![
-                            widget::button("Plain text")
-                                .style(move |theme, status| Self::search_method_button_style(
-                                    theme,
-                                    status,
-                                    search_method == Some(SearchMethod::PlainText)
-                                ))
-                                .on_press(
-                                    PdfMessage::SetSearchMethod(SearchMethod::PlainText).into()
-                                ),
-                            widget::button("Regex")
-                                .style(move |theme, status| Self::search_method_button_style(
-                                    theme,
-                                    status,
-                                    search_method == Some(SearchMethod::Regex)
-                                ))
-                                .on_press(PdfMessage::SetSearchMethod(SearchMethod::Regex).into()),
+                            Self::search_options(
+                                search_method,
+                                search_flags,
+                                |method| PdfMessage::SetSearchMethod(method).into(),
+                                |flags| PdfMessage::SetSearchFlags(flags).into(),
+                            ),
                             widget::space::horizontal().width(Length::Fill),
                             widget::text(search_progress),
                         ]
@@ -733,6 +2624,863 @@ impl App {
         .into()
     }
 
+    /// Shows the keys of a partially typed key sequence, such as the first `Z` of `Z Z`.
+    fn key_sequence_view(&self) -> Option<Element<'_, AppMessage>> {
+        let config = CONFIG.read().unwrap();
+        let pending = config.keyboard.ongoing_inputs();
+        if pending.is_empty() {
+            return None;
+        }
+        let keys = pending
+            .iter()
+            .map(|input| input.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(
+            container(text(keys).shaping(text::Shaping::Advanced))
+                .padding([4.0, 8.0])
+                .style(|theme: &Theme| widget::container::Style {
+                    background: Some(theme.extended_palette().background.weak.color.into()),
+                    border: Border {
+                        color: theme.extended_palette().primary.base.color,
+                        width: 2.0,
+                        radius: Radius::from(8.0),
+                    },
+                    ..Default::default()
+                })
+                .into(),
+        )
+    }
+
+    /// How many occurrences of the selected text there are while they are highlighted.
+    fn occurrences_view(&self) -> Option<Element<'_, AppMessage>> {
+        let (on_screen, total) = self.tabs.pdfs.get(self.tabs.active)?.occurrence_counts()?;
+        let label = if total == 1 {
+            "1 occurrence".to_string()
+        } else {
+            format!("{total} occurrences, {on_screen} on screen")
+        };
+        Some(
+            container(text(label).size(13.0))
+                .padding([4.0, 8.0])
+                .style(|theme: &Theme| widget::container::Style {
+                    background: Some(theme.extended_palette().background.weak.color.into()),
+                    border: Border {
+                        color: theme.extended_palette().primary.base.color,
+                        width: 2.0,
+                        radius: Radius::from(8.0),
+                    },
+                    ..Default::default()
+                })
+                .into(),
+        )
+    }
+
+    fn jumplist_view(&self) -> Element<'_, AppMessage> {
+        let mut entries = widget::column![].spacing(2.0);
+        for (i, location) in self.jumplist.entries.iter().enumerate().rev() {
+            let name = location
+                .pdf_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let mut label = format!("{name} — p.{}", location.page + 1);
+            if let Some(section) = &location.section {
+                label.push_str(&format!(" — {section}"));
+            }
+            let current = i == self.jumplist.current_index;
+            entries = entries.push(
+                button(text(label).shaping(text::Shaping::Advanced))
+                    .width(Length::Fill)
+                    .on_press(AppMessage::JumpToEntry(i))
+                    .style(move |theme: &Theme, status| {
+                        let palette = theme.extended_palette();
+                        let mut style = button::text(theme, status);
+                        if current {
+                            style.background = Some(palette.primary.weak.color.into());
+                            style.text_color = palette.primary.weak.text;
+                        } else if status == button::Status::Hovered {
+                            style.background = Some(palette.background.strong.color.into());
+                        }
+                        style.border.radius = Radius::from(4.0);
+                        style
+                    }),
+            );
+        }
+        if self.jumplist.is_empty() {
+            entries = entries.push(text("The jumplist is empty"));
+        }
+
+        container(
+            widget::column![
+                text("Jumplist").size(18.0),
+                scrollable(entries).height(Length::Shrink),
+            ]
+            .spacing(8.0),
+        )
+        .max_width(500.0)
+        .max_height(400.0)
+        .padding(8.0)
+        .style(|theme: &Theme| widget::container::Style {
+            background: Some(theme.extended_palette().background.weak.color.into()),
+            border: Border {
+                color: theme.extended_palette().primary.base.color,
+                width: 2.0,
+                radius: Radius::from(8.0),
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn translation_view<'a>(&self, translation: &'a Translation) -> Element<'a, AppMessage> {
+        let result: Element<'a, AppMessage> = match &translation.result {
+            None => text("Translating…").into(),
+            Some(Ok(translated)) => text(translated).shaping(text::Shaping::Advanced).into(),
+            Some(Err(e)) => text(format!("Translation failed: {e}"))
+                .style(text::danger)
+                .into(),
+        };
+        container(
+            widget::column![
+                text("Translation").size(18.0),
+                text(&translation.source)
+                    .size(13.0)
+                    .shaping(text::Shaping::Advanced)
+                    .style(text::secondary),
+                scrollable(result).height(Length::Shrink),
+            ]
+            .spacing(8.0),
+        )
+        .max_width(500.0)
+        .max_height(400.0)
+        .padding(8.0)
+        .style(|theme: &Theme| widget::container::Style {
+            background: Some(theme.extended_palette().background.weak.color.into()),
+            border: Border {
+                color: theme.extended_palette().primary.base.color,
+                width: 2.0,
+                radius: Radius::from(8.0),
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn tool_log_view(&self) -> Element<'_, AppMessage> {
+        let mut runs = widget::column![].spacing(8.0);
+        for run in self.tool_log.iter().rev() {
+            let result: Element<'_, AppMessage> = match &run.result {
+                None => text("Running…").style(text::secondary).into(),
+                Some(Ok(output)) if output.is_empty() => text("Finished without output")
+                    .style(text::secondary)
+                    .into(),
+                Some(Ok(output)) => text(output)
+                    .font(iced::Font::MONOSPACE)
+                    .shaping(text::Shaping::Advanced)
+                    .into(),
+                Some(Err(e)) => text(e).style(text::danger).into(),
+            };
+            runs = runs.push(widget::column![
+                text(&run.name),
+                text(&run.command).size(13.0).style(text::secondary),
+                result,
+            ]);
+        }
+        if self.tool_log.is_empty() {
+            runs = runs.push(text("No tools have been run yet").style(text::secondary));
+        }
+        container(
+            widget::column![
+                text("Tool Output").size(18.0),
+                scrollable(runs).height(Length::Shrink),
+            ]
+            .spacing(8.0),
+        )
+        .max_width(600.0)
+        .max_height(500.0)
+        .padding(8.0)
+        .style(|theme: &Theme| widget::container::Style {
+            background: Some(theme.extended_palette().background.weak.color.into()),
+            border: Border {
+                color: theme.extended_palette().primary.base.color,
+                width: 2.0,
+                radius: Radius::from(8.0),
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn tab_menu_view(&self, idx: usize) -> Element<'_, AppMessage> {
+        let current = self.session_store.tab_color(&self.tabs.pdfs[idx].path);
+        let mut colors = widget::column![].spacing(2.0);
+        for color in TabColor::ALL {
+            let swatch = text("●").style(move |_: &Theme| text::Style {
+                color: Some(color.color()),
+            });
+            let label = if current == Some(color) {
+                format!("{} ✓", color.label())
+            } else {
+                color.label().to_string()
+            };
+            colors = colors.push(
+                button(widget::row![swatch, text(label).size(13.0)].spacing(8.0))
+                    .width(Length::Fill)
+                    .style(button::text)
+                    .on_press(AppMessage::SetTabColor(idx, Some(color))),
+            );
+        }
+        colors = colors.push(
+            button(text("No Color").size(13.0))
+                .width(Length::Fill)
+                .style(button::text)
+                .on_press_maybe(current.map(|_| AppMessage::SetTabColor(idx, None))),
+        );
+        container(
+            widget::column![
+                text("Tab Color").size(18.0),
+                text(self.tabs.pdfs[idx].name.clone())
+                    .size(13.0)
+                    .shaping(text::Shaping::Advanced)
+                    .style(text::secondary),
+                colors,
+            ]
+            .spacing(8.0),
+        )
+        .max_width(300.0)
+        .padding(8.0)
+        .style(|theme: &Theme| widget::container::Style {
+            background: Some(theme.extended_palette().background.weak.color.into()),
+            border: Border {
+                color: theme.extended_palette().primary.base.color,
+                width: 2.0,
+                radius: Radius::from(8.0),
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn unsaved_view(&self, pending: PendingClose) -> Element<'_, AppMessage> {
+        let unsaved = self.unsaved_tabs(pending);
+        let mut col = widget::column![
+            text("Unsaved Changes").size(18.0),
+            text(match pending {
+                PendingClose::Tab(_) => "This document has edits that haven't been saved:",
+                PendingClose::Exit => "These documents have edits that haven't been saved:",
+            })
+            .size(13.0),
+        ]
+        .spacing(8.0);
+        for &i in &unsaved {
+            col = col.push(
+                text(self.tabs.pdfs[i].name.clone())
+                    .size(13.0)
+                    .shaping(text::Shaping::Advanced),
+            );
+        }
+        // Documents from memory have no file to write the edits back to
+        let can_save = unsaved.iter().all(|&i| !self.tabs.pdfs[i].in_memory);
+        if !can_save {
+            col = col.push(
+                text("Documents opened from memory have to be saved with Save As")
+                    .size(12.0)
+                    .style(text::secondary),
+            );
+        }
+        let save = button(text("Save").size(13.0)).style(button::primary);
+        col = col.push(
+            widget::row![
+                widget::space::horizontal().width(Length::Fill),
+                button(text("Cancel").size(13.0))
+                    .style(button::secondary)
+                    .on_press(AppMessage::UnsavedChanges(UnsavedChoice::Cancel)),
+                button(text("Discard").size(13.0))
+                    .style(button::danger)
+                    .on_press(AppMessage::UnsavedChanges(UnsavedChoice::Discard)),
+                if can_save {
+                    save.on_press(AppMessage::UnsavedChanges(UnsavedChoice::Save))
+                } else {
+                    save
+                },
+            ]
+            .spacing(8.0),
+        );
+        container(col)
+            .max_width(500.0)
+            .padding(8.0)
+            .style(|theme: &Theme| widget::container::Style {
+                background: Some(theme.extended_palette().background.weak.color.into()),
+                border: Border {
+                    color: theme.extended_palette().primary.base.color,
+                    width: 2.0,
+                    radius: Radius::from(8.0),
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn properties_view<'a>(&self, properties: &'a Properties) -> Element<'a, AppMessage> {
+        let row = |label: &'a str, value: String| {
+            widget::row![
+                text(label)
+                    .size(13.0)
+                    .style(text::secondary)
+                    .width(Length::Fixed(80.0)),
+                text(value).size(13.0).shaping(text::Shaping::Advanced),
+            ]
+            .spacing(8.0)
+        };
+        let mut details = widget::column![row("Name", properties.name.clone())].spacing(4.0);
+        if !properties.in_memory {
+            details = details.push(row("Path", properties.path.display().to_string()));
+        }
+        details = details.push(row("Pages", properties.page_count.to_string()));
+        for (label, value) in &properties.info {
+            details = details.push(row(label, value.clone()));
+        }
+
+        let mut signatures = widget::column![].spacing(4.0);
+        if properties.signatures.is_empty() {
+            signatures = signatures.push(text("The document has no signature fields").size(13.0));
+        } else {
+            for signature in &properties.signatures {
+                signatures = signatures.push(
+                    widget::row![
+                        text(signature.field.clone())
+                            .size(13.0)
+                            .shaping(text::Shaping::Advanced)
+                            .width(Length::Fill),
+                        text(signature.summary())
+                            .size(13.0)
+                            .shaping(text::Shaping::Advanced)
+                            .style(text::secondary),
+                    ]
+                    .spacing(8.0),
+                );
+            }
+            signatures = signatures.push(
+                text("Signatures are listed as the document describes them, they aren't verified")
+                    .size(12.0)
+                    .style(text::secondary),
+            );
+        }
+
+        let mut problems = widget::column![].spacing(4.0);
+        match &properties.check {
+            None => problems = problems.push(text("Checking…").size(13.0)),
+            Some(report) if report.problems.is_empty() => {
+                problems = problems.push(text("No problems found").size(13.0))
+            }
+            Some(report) => {
+                for problem in &report.problems {
+                    problems = problems.push(
+                        text(problem.to_string())
+                            .size(13.0)
+                            .shaping(text::Shaping::Advanced)
+                            .style(text::danger),
+                    );
+                }
+            }
+        }
+
+        let mut fonts = widget::column![].spacing(4.0);
+        match &properties.check {
+            None => fonts = fonts.push(text("Checking…").size(13.0)),
+            Some(report) if report.fonts.is_empty() => {
+                fonts = fonts.push(text("The document uses no fonts").size(13.0))
+            }
+            Some(report) => {
+                for font in &report.fonts {
+                    let embedding = match (font.embedded, font.subset) {
+                        (true, true) => "embedded subset",
+                        (true, false) => "embedded",
+                        (false, _) if font.is_standard() => "standard font",
+                        (false, _) => "not embedded",
+                    };
+                    fonts = fonts.push(
+                        widget::row![
+                            text(font.name.clone())
+                                .size(13.0)
+                                .shaping(text::Shaping::Advanced)
+                                .width(Length::Fill),
+                            text(format!(
+                                "{}, {embedding}, page {}",
+                                font.kind,
+                                font.page + 1
+                            ))
+                            .size(13.0)
+                            .style(if font.is_missing() {
+                                text::danger
+                            } else {
+                                text::secondary
+                            }),
+                        ]
+                        .spacing(8.0),
+                    );
+                }
+            }
+        }
+
+        container(scrollable(
+            widget::column![
+                text("Properties").size(18.0),
+                details,
+                text("Fonts").size(15.0),
+                fonts,
+                text("Signatures").size(15.0),
+                signatures,
+                text("Integrity").size(15.0),
+                problems,
+            ]
+            .spacing(8.0),
+        ))
+        .max_width(600.0)
+        .max_height(500.0)
+        .padding(8.0)
+        .style(|theme: &Theme| widget::container::Style {
+            background: Some(theme.extended_palette().background.weak.color.into()),
+            border: Border {
+                color: theme.extended_palette().primary.base.color,
+                width: 2.0,
+                radius: Radius::from(8.0),
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn preferences_view<'a>(&self, preferences: &'a Preferences) -> Element<'a, AppMessage> {
+        let mut entries = widget::column![].spacing(4.0);
+        let mut last_kind = None;
+        for (idx, entry) in preferences.visible() {
+            if last_kind != Some(entry.kind) {
+                last_kind = Some(entry.kind);
+                entries = entries.push(
+                    text(match entry.kind {
+                        EntryKind::Set => "Settings",
+                        EntryKind::Bind => "Key bindings",
+                    })
+                    .size(15.0),
+                );
+            }
+            entries = entries.push(
+                widget::row![
+                    text(entry.name.as_str())
+                        .size(13.0)
+                        .width(Length::FillPortion(2)),
+                    widget::text_input("", &entry.value)
+                        .size(13.0)
+                        .width(Length::FillPortion(3))
+                        .on_input(move |value| AppMessage::EditPreference(idx, value))
+                        .on_submit(AppMessage::SavePreferences),
+                ]
+                .spacing(8.0)
+                .align_y(alignment::Vertical::Center),
+            );
+            if let Some(error) = &entry.error {
+                entries = entries.push(
+                    text(error.as_str())
+                        .size(12.0)
+                        .shaping(text::Shaping::Advanced)
+                        .style(text::danger),
+                );
+            }
+        }
+
+        let save = button(text("Save").size(13.0)).style(button::primary);
+        container(
+            widget::column![
+                text("Preferences").size(18.0),
+                text(preferences.path.display().to_string())
+                    .size(12.0)
+                    .style(text::secondary),
+                widget::text_input("Filter", &preferences.filter)
+                    .id(widget::Id::new("preferences_filter"))
+                    .on_input(AppMessage::FilterPreferences),
+                scrollable(entries).height(Length::Shrink),
+                widget::row![
+                    widget::space::horizontal().width(Length::Fill),
+                    button(text("Cancel").size(13.0))
+                        .style(button::secondary)
+                        .on_press(AppMessage::ClosePreferences),
+                    if preferences.has_changes() && !preferences.has_errors() {
+                        save.on_press(AppMessage::SavePreferences)
+                    } else {
+                        save
+                    },
+                ]
+                .spacing(8.0),
+            ]
+            .spacing(8.0),
+        )
+        .max_width(600.0)
+        .max_height(500.0)
+        .padding(8.0)
+        .style(|theme: &Theme| widget::container::Style {
+            background: Some(theme.extended_palette().background.weak.color.into()),
+            border: Border {
+                color: theme.extended_palette().primary.base.color,
+                width: 2.0,
+                radius: Radius::from(8.0),
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn global_search_view<'a>(&self, search: &'a GlobalSearch) -> Element<'a, AppMessage> {
+        let mut results = widget::column![].spacing(2.0);
+        let mut idx = 0;
+        for document in &search.documents {
+            let summary = match &document.matches {
+                None => "searching…".to_string(),
+                Some(matches) if matches.len() < document.total => {
+                    format!("{} of {} matches", matches.len(), document.total)
+                }
+                Some(_) => format!("{} matches", document.total),
+            };
+            results = results.push(
+                widget::row![
+                    text(document.name.as_str())
+                        .size(14.0)
+                        .shaping(text::Shaping::Advanced),
+                    widget::space::horizontal().width(Length::Fill),
+                    text(summary).size(12.0),
+                ]
+                .padding([4.0, 0.0]),
+            );
+            for found in document.matches.iter().flatten() {
+                let selected = idx == search.selected;
+                results = results.push(
+                    button(
+                        text(format!("p.{} {}", found.page + 1, found.snippet))
+                            .size(13.0)
+                            .shaping(text::Shaping::Advanced),
+                    )
+                    .width(Length::Fill)
+                    .on_press(AppMessage::OpenGlobalSearchResult(Some(idx)))
+                    .style(move |theme: &Theme, status| {
+                        let palette = theme.extended_palette();
+                        let mut style = button::text(theme, status);
+                        if selected {
+                            style.background = Some(palette.primary.weak.color.into());
+                            style.text_color = palette.primary.weak.text;
+                        } else if status == button::Status::Hovered {
+                            style.background = Some(palette.background.strong.color.into());
+                        }
+                        style.border.radius = Radius::from(4.0);
+                        style
+                    }),
+                );
+                idx += 1;
+            }
+        }
+        if !search.needle.is_empty() && search.documents.is_empty() {
+            results = results.push(text("No documents are open"));
+        }
+
+        container(
+            widget::column![
+                widget::text_input("Search all tabs", &search.needle)
+                    .id(widget::Id::new("global_search_input"))
+                    .on_input(AppMessage::GlobalSearchInput)
+                    .on_submit(AppMessage::OpenGlobalSearchResult(None)),
+                Self::search_options(
+                    Some(search.method),
+                    search.flags,
+                    AppMessage::GlobalSearchMethod,
+                    AppMessage::GlobalSearchFlags,
+                ),
+                scrollable(results).height(Length::Shrink),
+            ]
+            .spacing(8.0),
+        )
+        .max_width(600.0)
+        .max_height(500.0)
+        .padding(8.0)
+        .style(|theme: &Theme| widget::container::Style {
+            background: Some(theme.extended_palette().background.weak.color.into()),
+            border: Border {
+                color: theme.extended_palette().primary.base.color,
+                width: 2.0,
+                radius: Radius::from(8.0),
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn quick_switcher_view<'a>(&self, switcher: &'a QuickSwitcher) -> Element<'a, AppMessage> {
+        let mut entries = widget::column![].spacing(2.0);
+        let matches = switcher.matches();
+        for (idx, candidate) in matches.iter().enumerate() {
+            let selected = idx == switcher.selected;
+            let kind = match candidate.target {
+                SwitchTarget::Tab(_) => "open",
+                SwitchTarget::Recent(_) => "recent",
+            };
+            entries = entries.push(
+                button(
+                    widget::row![
+                        text(candidate.name.as_str())
+                            .size(14.0)
+                            .shaping(text::Shaping::Advanced),
+                        text(candidate.location.as_str())
+                            .size(12.0)
+                            .shaping(text::Shaping::Advanced)
+                            .style(text::secondary),
+                        widget::space::horizontal().width(Length::Fill),
+                        text(kind).size(12.0),
+                    ]
+                    .spacing(8.0)
+                    .align_y(alignment::Vertical::Center),
+                )
+                .width(Length::Fill)
+                .on_press(AppMessage::SwitchToEntry(Some(idx)))
+                .style(move |theme: &Theme, status| {
+                    let palette = theme.extended_palette();
+                    let mut style = button::text(theme, status);
+                    if selected {
+                        style.background = Some(palette.primary.weak.color.into());
+                        style.text_color = palette.primary.weak.text;
+                    } else if status == button::Status::Hovered {
+                        style.background = Some(palette.background.strong.color.into());
+                    }
+                    style.border.radius = Radius::from(4.0);
+                    style
+                }),
+            );
+        }
+        if matches.is_empty() {
+            entries = entries.push(text("No matching documents").size(13.0));
+        }
+
+        container(
+            widget::column![
+                widget::text_input("Switch to document", &switcher.query)
+                    .id(widget::Id::new("quick_switcher_input"))
+                    .on_input(AppMessage::QuickSwitcherInput)
+                    .on_submit(AppMessage::SwitchToEntry(None)),
+                scrollable(entries).height(Length::Shrink),
+            ]
+            .spacing(8.0),
+        )
+        .max_width(600.0)
+        .max_height(500.0)
+        .padding(8.0)
+        .style(|theme: &Theme| widget::container::Style {
+            background: Some(theme.extended_palette().background.weak.color.into()),
+            border: Border {
+                color: theme.extended_palette().primary.base.color,
+                width: 2.0,
+                radius: Radius::from(8.0),
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    /// The named destinations of the active document listed below the prompt for `input`.
+    fn listed_destinations(&self, input: &str) -> Vec<(String, usize)> {
+        self.tabs
+            .pdfs
+            .get(self.tabs.active)
+            .map(|pdf| {
+                pdf.destinations()
+                    .matching(input)
+                    .take(MAX_LISTED_DESTINATIONS)
+                    .map(|(name, page)| (name.to_string(), page))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn prompt_view<'a>(&self, prompt: Prompt, input: &'a str) -> Element<'a, AppMessage> {
+        let destinations = match prompt {
+            Prompt::GoToDestination => self.listed_destinations(input),
+            _ => vec![],
+        };
+        let (placeholder, label, submit) = match prompt {
+            Prompt::OpenUrl => (
+                "https://example.com/paper.pdf",
+                "Open",
+                download::is_url(input).then(|| AppMessage::OpenUrl(input.to_string())),
+            ),
+            Prompt::SaveWorkspace => (
+                "Workspace name",
+                "Save",
+                (!input.trim().is_empty()).then(|| AppMessage::SaveWorkspace(input.to_string())),
+            ),
+            Prompt::GoToDestination => (
+                "Destination name",
+                "Go",
+                destinations
+                    .get(self.prompt_selected)
+                    .map(|(name, _)| AppMessage::GoToDestination(name.clone())),
+            ),
+        };
+        let mut content = widget::column![
+            widget::row![
+                widget::text_input(placeholder, input)
+                    .id(widget::Id::new("prompt_input"))
+                    .on_input(AppMessage::PromptInput)
+                    .on_submit_maybe(submit.clone()),
+                button(label).on_press_maybe(submit),
+            ]
+            .spacing(4.0),
+        ]
+        .spacing(8.0);
+        if prompt == Prompt::GoToDestination {
+            let mut list = widget::column![].spacing(2.0);
+            for (idx, (name, page)) in destinations.into_iter().enumerate() {
+                let selected = idx == self.prompt_selected;
+                list = list.push(
+                    button(
+                        widget::row![
+                            text(name.clone())
+                                .size(13.0)
+                                .shaping(text::Shaping::Advanced),
+                            widget::space::horizontal().width(Length::Fill),
+                            text(format!("p.{}", page + 1)).size(12.0),
+                        ]
+                        .spacing(8.0),
+                    )
+                    .width(Length::Fill)
+                    .on_press(AppMessage::GoToDestination(name))
+                    .style(move |theme: &Theme, status| {
+                        let palette = theme.extended_palette();
+                        let mut style = button::text(theme, status);
+                        if selected {
+                            style.background = Some(palette.primary.weak.color.into());
+                            style.text_color = palette.primary.weak.text;
+                        } else if status == button::Status::Hovered {
+                            style.background = Some(palette.background.strong.color.into());
+                        }
+                        style.border.radius = Radius::from(4.0);
+                        style
+                    }),
+                );
+            }
+            if self
+                .tabs
+                .pdfs
+                .get(self.tabs.active)
+                .is_none_or(|pdf| pdf.destinations().is_empty())
+            {
+                list = list.push(text("The document has no named destinations").size(13.0));
+            }
+            content = content.push(scrollable(list).height(Length::Shrink));
+        }
+        container(content)
+            .max_width(500.0)
+            .max_height(400.0)
+            .padding(8.0)
+            .style(|theme: &Theme| widget::container::Style {
+                background: Some(theme.extended_palette().background.weak.color.into()),
+                border: Border {
+                    color: theme.extended_palette().primary.base.color,
+                    width: 2.0,
+                    radius: Radius::from(8.0),
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn tab_switcher_view(&self) -> Element<'_, AppMessage> {
+        let mut entries = widget::column![].spacing(2.0);
+        for (idx, highlighted) in self.tabs.history.entries() {
+            let Some(pdf) = self.tabs.pdfs.get(idx) else {
+                continue;
+            };
+            entries = entries.push(
+                container(text(pdf.name.clone()).shaping(text::Shaping::Advanced))
+                    .width(Length::Fill)
+                    .padding([4.0, 8.0])
+                    .style(move |theme: &Theme| {
+                        let palette = theme.extended_palette();
+                        if highlighted {
+                            widget::container::Style {
+                                background: Some(palette.primary.weak.color.into()),
+                                text_color: Some(palette.primary.weak.text),
+                                border: Border {
+                                    radius: Radius::from(4.0),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            }
+                        } else {
+                            widget::container::Style::default()
+                        }
+                    }),
+            );
+        }
+
+        container(entries)
+            .max_width(500.0)
+            .padding(8.0)
+            .style(|theme: &Theme| widget::container::Style {
+                background: Some(theme.extended_palette().background.weak.color.into()),
+                border: Border {
+                    color: theme.extended_palette().primary.base.color,
+                    width: 2.0,
+                    radius: Radius::from(8.0),
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    /// Buttons picking the search method and flags, shared by the search bar and the search across
+    /// all tabs.
+    fn search_options<'a>(
+        method: Option<SearchMethod>,
+        flags: SearchFlags,
+        on_method: impl Fn(SearchMethod) -> AppMessage,
+        on_flags: impl Fn(SearchFlags) -> AppMessage,
+    ) -> Element<'a, AppMessage> {
+        let option = |label: &'static str, selected: bool, message: AppMessage| {
+            widget::button(label)
+                .style(move |theme, status| {
+                    Self::search_method_button_style(theme, status, selected)
+                })
+                .on_press(message)
+        };
+        widget::row![
+            option(
+                "Plain text",
+                method == Some(SearchMethod::PlainText),
+                on_method(SearchMethod::PlainText),
+            ),
+            option(
+                "Regex",
+                method == Some(SearchMethod::Regex),
+                on_method(SearchMethod::Regex),
+            ),
+            option(
+                "Ignore case",
+                flags.ignore_case,
+                on_flags(SearchFlags {
+                    ignore_case: !flags.ignore_case,
+                    ..flags
+                }),
+            ),
+            option(
+                "Whole word",
+                flags.whole_word,
+                on_flags(SearchFlags {
+                    whole_word: !flags.whole_word,
+                    ..flags
+                }),
+            ),
+        ]
+        .spacing(4.0)
+        .into()
+    }
+
     fn search_method_button_style(
         theme: &Theme,
         _status: widget::button::Status,
@@ -762,29 +3510,229 @@ impl App {
         }
     }
 
+    /// Names the active document and page so the window can be told apart in taskbars and alt-tab
+    /// switchers. Re-evaluated by iced after every update.
+    pub fn title(&self) -> String {
+        let app_name = if paths::read_only() {
+            "Miro (read-only)"
+        } else {
+            "Miro"
+        };
+        match self.tabs.pdfs.get(self.tabs.active) {
+            Some(pdf) => format!(
+                "{} — p.{}/{} — {app_name}",
+                pdf.name,
+                pdf.current_page() + 1,
+                pdf.page_count().unwrap_or(0)
+            ),
+            None => String::from(app_name),
+        }
+    }
+
     pub fn view(&self) -> iced::Element<'_, AppMessage> {
+        let show_active_pane = self.has_sidebar_pane() && !self.presentation_mode;
         let pg = PaneGrid::new(&self.pane_state, |_id, pane, _is_maximized| {
+            let is_active = show_active_pane && pane.pane_type == self.active_pane;
             pane_grid::Content::new(match pane.pane_type {
                 PaneType::Sidebar => self.view_sidebar(),
                 PaneType::Pdf => {
-                    let pdf_content: iced::Element<'_, AppMessage> = if self.pdfs.is_empty() {
+                    // Nothing is rendered for a minimized window, the message that restores it
+                    // leads to a fresh render
+                    let pdf_content: iced::Element<'_, AppMessage> = if self.window_hidden {
                         widget::space::vertical().into()
+                    } else if self.tabs.pdfs.is_empty() {
+                        if self.recent_files_screen {
+                            self.view_recent_files()
+                        } else {
+                            widget::space::vertical().into()
+                        }
                     } else {
-                        self.pdfs[self.pdf_idx].view().map(AppMessage::PdfMessage)
+                        self.tabs.pdfs[self.tabs.active]
+                            .view()
+                            .map(AppMessage::PdfMessage)
                     };
                     let tabs = self.create_tabs();
                     if self.presentation_mode {
                         widget::column![stack![pdf_content,]].into()
                     } else {
-                        let mut stack_children: Vec<Element<'_, AppMessage>> = vec![
-                            pdf_content,
-                            container(tabs)
-                                .align_y(alignment::Vertical::Bottom)
-                                .width(Length::Fill)
-                                .height(Length::Fill)
-                                .padding(8.0)
+                        let mut stack_children: Vec<Element<'_, AppMessage>> = vec![pdf_content];
+                        if !self.distraction_free {
+                            stack_children.push(
+                                container(tabs)
+                                    .align_y(alignment::Vertical::Bottom)
+                                    .width(Length::Fill)
+                                    .height(Length::Fill)
+                                    .padding(8.0)
+                                    .into(),
+                            );
+                        }
+                        if let Some(pending) = self.key_sequence_view() {
+                            stack_children.push(
+                                container(pending)
+                                    .align_x(alignment::Horizontal::Right)
+                                    .align_y(alignment::Vertical::Bottom)
+                                    .width(Length::Fill)
+                                    .height(Length::Fill)
+                                    .padding(8.0)
+                                    .into(),
+                            );
+                        }
+                        if let Some(panel) = self
+                            .build
+                            .as_ref()
+                            .and_then(|build| build.view(AppMessage::ToggleBuildPanel))
+                        {
+                            stack_children.push(
+                                container(panel)
+                                    .align_y(alignment::Vertical::Bottom)
+                                    .width(Length::Fill)
+                                    .height(Length::Fill)
+                                    .padding(Padding::default().left(8.0).right(8.0).bottom(56.0))
+                                    .into(),
+                            );
+                        }
+                        let mut corner: Vec<Element<'_, AppMessage>> = vec![];
+                        if !self.notifications.is_empty() {
+                            corner.push(self.notifications_view());
+                        }
+                        if let Some(progress) = self.downloads.view() {
+                            corner.push(progress);
+                        }
+                        if let Some(occurrences) = self.occurrences_view() {
+                            corner.push(occurrences);
+                        }
+                        if !corner.is_empty() {
+                            stack_children.push(
+                                container(widget::Column::with_children(corner).spacing(8.0))
+                                    .align_x(alignment::Horizontal::Right)
+                                    .align_y(alignment::Vertical::Bottom)
+                                    .width(Length::Fill)
+                                    .height(Length::Fill)
+                                    .padding(Padding::default().right(8.0).bottom(56.0))
+                                    .into(),
+                            );
+                        }
+                        if let Some((prompt, input)) = &self.prompt {
+                            stack_children.push(
+                                container(self.prompt_view(*prompt, input))
+                                    .align_x(alignment::Horizontal::Center)
+                                    .align_y(alignment::Vertical::Top)
+                                    .width(Length::Fill)
+                                    .padding(8.0)
+                                    .into(),
+                            );
+                        }
+                        if self.jumplist_open {
+                            stack_children.push(
+                                widget::mouse_area(
+                                    container(self.jumplist_view())
+                                        .center(Length::Fill)
+                                        .padding(8.0),
+                                )
+                                .on_press(AppMessage::ToggleJumplist)
+                                .into(),
+                            );
+                        }
+                        if let Some(search) = &self.global_search {
+                            stack_children.push(
+                                widget::mouse_area(
+                                    container(self.global_search_view(search))
+                                        .center(Length::Fill)
+                                        .padding(8.0),
+                                )
+                                .on_press(AppMessage::CloseSearch)
+                                .into(),
+                            );
+                        }
+                        if let Some(switcher) = &self.quick_switcher {
+                            stack_children.push(
+                                widget::mouse_area(
+                                    container(self.quick_switcher_view(switcher))
+                                        .center(Length::Fill)
+                                        .padding(8.0),
+                                )
+                                .on_press(AppMessage::CloseSearch)
+                                .into(),
+                            );
+                        }
+                        if let Some(properties) = &self.properties {
+                            stack_children.push(
+                                widget::mouse_area(
+                                    container(self.properties_view(properties))
+                                        .center(Length::Fill)
+                                        .padding(8.0),
+                                )
+                                .on_press(AppMessage::CloseProperties)
+                                .into(),
+                            );
+                        }
+                        if let Some(preferences) = &self.preferences {
+                            stack_children.push(
+                                widget::mouse_area(
+                                    container(
+                                        // Clicking between the fields shouldn't throw the edits away
+                                        widget::mouse_area(self.preferences_view(preferences))
+                                            .on_press(AppMessage::None),
+                                    )
+                                    .center(Length::Fill)
+                                    .padding(8.0),
+                                )
+                                .on_press(AppMessage::ClosePreferences)
+                                .into(),
+                            );
+                        }
+                        if let Some(pending) = self.unsaved_prompt {
+                            stack_children.push(
+                                widget::mouse_area(
+                                    container(self.unsaved_view(pending))
+                                        .center(Length::Fill)
+                                        .padding(8.0),
+                                )
+                                .on_press(AppMessage::UnsavedChanges(UnsavedChoice::Cancel))
+                                .into(),
+                            );
+                        }
+                        if let Some(translation) = &self.translation {
+                            stack_children.push(
+                                widget::mouse_area(
+                                    container(self.translation_view(translation))
+                                        .center(Length::Fill)
+                                        .padding(8.0),
+                                )
+                                .on_press(AppMessage::CloseTranslation)
                                 .into(),
-                        ];
+                            );
+                        }
+                        if let Some(idx) = self.tab_menu.filter(|&idx| idx < self.tabs.pdfs.len()) {
+                            stack_children.push(
+                                widget::mouse_area(
+                                    container(self.tab_menu_view(idx))
+                                        .center(Length::Fill)
+                                        .padding(8.0),
+                                )
+                                .on_press(AppMessage::CloseTabMenu)
+                                .into(),
+                            );
+                        }
+                        if self.tool_log_open {
+                            stack_children.push(
+                                widget::mouse_area(
+                                    container(self.tool_log_view())
+                                        .center(Length::Fill)
+                                        .padding(8.0),
+                                )
+                                .on_press(AppMessage::CloseToolLog)
+                                .into(),
+                            );
+                        }
+                        if self.tabs.history.is_switching() {
+                            stack_children.push(
+                                container(self.tab_switcher_view())
+                                    .center(Length::Fill)
+                                    .padding(8.0)
+                                    .into(),
+                            );
+                        }
                         if self.search_open {
                             stack_children.push(
                                 container(self.search_view())
@@ -795,111 +3743,108 @@ impl App {
                                     .into(),
                             );
                         }
-                        if self.mac_menu.is_none() {
-                            let menu_bar = platform_specific::iced_aw::create_menu_bar(
-                                self.pdf_idx,
+                        let menu_bar = self.mac_menu.is_none().then(|| {
+                            platform_specific::iced_aw::create_menu_bar(
+                                self.tabs.active,
                                 &self.recent_files.get_recent(),
-                            );
-                            widget::column![menu_bar, stack(stack_children)].into()
-                        } else {
+                                self.closed_tabs.get_closed(),
+                                &self.session_store.workspace_names(),
+                            )
+                        });
+                        if self.distraction_free {
+                            // Both come back together over the top of the document, so
+                            // nothing moves while reading
+                            if self.chrome_revealed {
+                                stack_children.push(
+                                    widget::column![menu_bar, container(tabs).padding(8.0)].into(),
+                                );
+                            }
                             widget::column![stack(stack_children)].into()
+                        } else {
+                            widget::column![menu_bar, stack(stack_children)].into()
                         }
                     }
                 }
             })
-            .style(|_theme: &Theme| Default::default())
+            .style(move |theme: &Theme| {
+                if is_active {
+                    container::Style {
+                        border: Border {
+                            color: theme.extended_palette().primary.base.color,
+                            width: 2.0,
+                            radius: Radius::from(0.0),
+                        },
+                        ..Default::default()
+                    }
+                } else {
+                    Default::default()
+                }
+            })
         })
+        .on_click(AppMessage::PaneClicked)
         .on_resize(10, AppMessage::PaneResize);
 
         pg.into()
     }
 
     fn view_sidebar(&self) -> Element<'_, AppMessage> {
-        let create_expanded_outline_button = || {
-            button(
-                widget::row![
-                    widget::svg(icons::table_of_contents())
-                        .width(18.0)
-                        .height(18.0)
-                        .style(|theme: &Theme, _| {
-                            let palette = theme.extended_palette();
-                            widget::svg::Style {
-                                color: Some(palette.primary.base.text),
-                            }
-                        }),
-                    widget::space::horizontal().width(8.0),
-                    text("Outline")
-                ]
-                .align_y(alignment::Vertical::Center),
-            )
-            .width(Length::Fill)
-            .height(30.0)
-            .padding(6.0)
-            .style(|theme: &Theme, _status| {
-                let palette = theme.extended_palette();
-                button::Style {
-                    background: Some(palette.primary.base.color.into()),
-                    text_color: palette.primary.base.text,
-                    border: Border {
-                        radius: Radius::from(4.0),
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                }
-            })
-            .on_press(AppMessage::SetSidebar(SidebarTab::Outline))
-        };
+        let sidebar_picker = widget::row![
+            self.sidebar_tab_button(SidebarTab::Outline, icons::table_of_contents(), "Outline"),
+            self.sidebar_tab_button(SidebarTab::Bookmark, icons::bookmark(), "Bookmarks"),
+            self.sidebar_tab_button(
+                SidebarTab::Annotations,
+                icons::message_square(),
+                "Annotations"
+            ),
+            self.sidebar_tab_button(SidebarTab::Layers, icons::layers(), "Layers"),
+            self.sidebar_tab_button(SidebarTab::Stats, icons::chart_column(), "Statistics"),
+        ]
+        .height(Length::Shrink)
+        .spacing(4.0)
+        .padding(Padding::default().top(4.0).bottom(4.0));
 
-        let create_collapsed_outline_button = || {
-            button(
-                widget::svg(icons::table_of_contents())
-                    .width(18.0)
-                    .height(18.0)
-                    .style(|theme: &Theme, _| {
-                        let palette = theme.extended_palette();
-                        widget::svg::Style {
-                            color: Some(palette.primary.base.text),
-                        }
-                    }),
-            )
-            .width(Length::Shrink)
-            .height(30.0)
-            .padding(6.0)
-            .style(|theme: &Theme, status| {
-                let palette = theme.extended_palette();
-                widget::button::Style {
-                    background: match status {
-                        widget::button::Status::Hovered => Some(palette.primary.weak.color.into()),
-                        widget::button::Status::Pressed => {
-                            Some(palette.primary.strong.color.into())
-                        }
-                        _ => Some(palette.primary.base.color.into()),
-                    },
-                    border: Border {
-                        radius: 4.0.into(),
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                }
-            })
-            .on_press(AppMessage::SetSidebar(SidebarTab::Outline))
+        let contents: Element<'_, AppMessage> = match self.sidebar_tab {
+            SidebarTab::Outline => self.view_outline(),
+            SidebarTab::Bookmark => self.bookmark_store.view().map(AppMessage::BookmarkMessage),
+            SidebarTab::Annotations => self.view_annotations(),
+            SidebarTab::Layers => self.view_layers(),
+            SidebarTab::Stats => self.reading_stats.view(
+                self.tabs
+                    .pdfs
+                    .get(self.tabs.active)
+                    .map(|pdf| pdf.path.as_path()),
+            ),
         };
 
-        let create_expanded_bookmark_button = || {
+        widget::column![
+            sidebar_picker,
+            widget::space::vertical().height(8.0),
+            contents,
+        ]
+        .padding(8.0)
+        .into()
+    }
+
+    /// The selected tab is expanded to show its label, the others only show their icon.
+    fn sidebar_tab_button(
+        &self,
+        tab: SidebarTab,
+        icon: widget::svg::Handle,
+        label: &'static str,
+    ) -> Element<'static, AppMessage> {
+        let icon = widget::svg(icon)
+            .width(18.0)
+            .height(18.0)
+            .style(|theme: &Theme, _| {
+                let palette = theme.extended_palette();
+                widget::svg::Style {
+                    color: Some(palette.primary.base.text),
+                }
+            });
+        if self.sidebar_tab == tab {
             button(
-                widget::row![
-                    text("Bookmarks").width(Length::Fill),
-                    widget::svg(icons::bookmark())
-                        .width(18.0)
-                        .height(18.0)
-                        .style(|theme: &Theme, _| {
-                            let palette = theme.extended_palette();
-                            widget::svg::Style {
-                                color: Some(palette.primary.base.text),
-                            }
-                        })
-                ]
-                .align_y(alignment::Vertical::Center),
+                widget::row![icon, widget::space::horizontal().width(8.0), text(label)]
+                    .align_y(alignment::Vertical::Center),
             )
             .width(Length::Fill)
             .height(30.0)
@@ -916,72 +3861,73 @@ impl App {
                     ..Default::default()
                 }
             })
-            .on_press(AppMessage::SetSidebar(SidebarTab::Bookmark))
-        };
-
-        let create_collapsed_bookmark_button = || {
-            button(
-                widget::svg(icons::bookmark())
-                    .width(18.0)
-                    .height(18.0)
-                    .style(|theme: &Theme, _| {
-                        let palette = theme.extended_palette();
-                        widget::svg::Style {
-                            color: Some(palette.primary.base.text),
-                        }
-                    }),
-            )
-            .width(Length::Shrink)
-            .height(30.0)
-            .padding(6.0)
-            .style(|theme: &Theme, status| {
-                let palette = theme.extended_palette();
-                widget::button::Style {
-                    background: match status {
-                        widget::button::Status::Hovered => Some(palette.primary.weak.color.into()),
-                        widget::button::Status::Pressed => {
-                            Some(palette.primary.strong.color.into())
-                        }
-                        _ => Some(palette.primary.base.color.into()),
-                    },
-                    border: Border {
-                        radius: 4.0.into(),
+            .on_press(AppMessage::SetSidebar(tab))
+            .into()
+        } else {
+            button(icon)
+                .width(Length::Shrink)
+                .height(30.0)
+                .padding(6.0)
+                .style(|theme: &Theme, status| {
+                    let palette = theme.extended_palette();
+                    widget::button::Style {
+                        background: match status {
+                            widget::button::Status::Hovered => {
+                                Some(palette.primary.weak.color.into())
+                            }
+                            widget::button::Status::Pressed => {
+                                Some(palette.primary.strong.color.into())
+                            }
+                            _ => Some(palette.primary.base.color.into()),
+                        },
+                        border: Border {
+                            radius: 4.0.into(),
+                            ..Default::default()
+                        },
                         ..Default::default()
-                    },
-                    ..Default::default()
-                }
-            })
-            .on_press(AppMessage::SetSidebar(SidebarTab::Bookmark))
-        };
-
-        let (outline_button, bookmark_button) = match self.sidebar_tab {
-            SidebarTab::Outline => (
-                create_expanded_outline_button(),
-                create_collapsed_bookmark_button(),
-            ),
-            SidebarTab::Bookmark => (
-                create_collapsed_outline_button(),
-                create_expanded_bookmark_button(),
-            ),
-        };
-
-        let sidebar_picker = widget::row![outline_button, bookmark_button]
-            .height(Length::Shrink)
-            .spacing(4.0)
-            .padding(Padding::default().top(4.0).bottom(4.0));
-
-        let contents: Element<'_, AppMessage> = match self.sidebar_tab {
-            SidebarTab::Outline => self.view_outline(),
-            SidebarTab::Bookmark => self.bookmark_store.view().map(AppMessage::BookmarkMessage),
-        };
+                    }
+                })
+                .on_press(AppMessage::SetSidebar(tab))
+                .into()
+        }
+    }
 
-        widget::column![
-            sidebar_picker,
+    /// Shown instead of a document after the last tab is closed with `LastTabClosed RecentFiles`.
+    fn view_recent_files(&self) -> Element<'_, AppMessage> {
+        let mut col = widget::column![
+            text("Recent Files").size(18.0),
             widget::space::vertical().height(8.0),
-            contents,
-        ]
-        .padding(8.0)
-        .into()
+        ];
+        let recent = self.recent_files.get_recent();
+        if recent.is_empty() {
+            col = col.push(text("No recent files").style(|theme: &Theme| {
+                let palette = theme.extended_palette();
+                text::Style {
+                    color: Some(palette.background.weak.color),
+                }
+            }));
+        }
+        for path in recent {
+            let name = path
+                .file_name()
+                .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy());
+            col = col.push(
+                button(widget::column![
+                    text(name).shaping(text::Shaping::Advanced),
+                    text(path.to_string_lossy())
+                        .size(12.0)
+                        .shaping(text::Shaping::Advanced),
+                ])
+                .style(button::text)
+                .width(Length::Fill)
+                .on_press(AppMessage::OpenFile(path.clone())),
+            );
+        }
+        container(widget::scrollable(col.spacing(4.0)))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(32.0)
+            .into()
     }
 
     fn view_outline(&self) -> Element<'_, AppMessage> {
@@ -990,7 +3936,7 @@ impl App {
             widget::space::vertical().height(8.0),
         ];
 
-        if self.pdfs.is_empty() {
+        if self.tabs.pdfs.is_empty() {
             col = col.push(text("No document loaded").style(|theme: &Theme| {
                 let palette = theme.extended_palette();
                 text::Style {
@@ -998,7 +3944,7 @@ impl App {
                 }
             }));
         } else {
-            let outline = self.pdfs[self.pdf_idx].get_outline();
+            let outline = self.tabs.pdfs[self.tabs.active].get_outline();
             if outline.is_empty() {
                 col = col.push(text("No outline available").style(|theme: &Theme| {
                     let palette = theme.extended_palette();
@@ -1008,66 +3954,168 @@ impl App {
                 }));
             } else {
                 let outline_content = view_outline_items(outline, 0);
-                col = col.push(widget::scrollable(outline_content));
+                col = col.push(
+                    widget::scrollable(outline_content).id(widget::Id::new(SIDEBAR_SCROLLABLE)),
+                );
             }
         }
         container(col).height(Length::Fill).into()
     }
 
-    pub fn subscription(&self) -> Subscription<AppMessage> {
-        let keys = listen_with(|event, status, _| match event {
-            Event::Keyboard(keyboard_event) => match keyboard_event {
-                iced::keyboard::Event::ModifiersChanged(modifiers) => {
-                    Some(AppMessage::ModifiersChanged(modifiers))
+    fn view_layers(&self) -> Element<'_, AppMessage> {
+        let mut col = widget::column![
+            text("Layers").size(18.0),
+            widget::space::vertical().height(8.0),
+        ];
+        let placeholder = |msg| {
+            text(msg).style(|theme: &Theme| {
+                let palette = theme.extended_palette();
+                text::Style {
+                    color: Some(palette.background.weak.color),
                 }
-                iced::keyboard::Event::KeyPressed {
-                    key: _,
-                    modified_key: iced::keyboard::Key::Character(ref modified),
-                    physical_key: _,
-                    location: _,
-                    modifiers: _,
-                    text: _,
-                    repeat: _,
-                } => {
-                    let e = if modified == "+" {
-                        iced::keyboard::Event::KeyPressed {
-                            key: iced::keyboard::Key::Character(SmolStr::new_static("+")),
-                            modified_key: iced::keyboard::Key::Character(SmolStr::new_static("+")),
-                            physical_key: iced::keyboard::key::Physical::Code(
-                                iced::keyboard::key::Code::Minus,
-                            ),
-                            location: iced::keyboard::Location::Standard,
-                            modifiers: Modifiers::empty(),
-                            text: Some(SmolStr::new_static("+")),
-                            repeat: false,
-                        }
-                    } else {
-                        keyboard_event
-                    };
-                    let mut config = CONFIG.write().unwrap();
-                    match status {
-                        iced::event::Status::Ignored => {
-                            config.keyboard.dispatch(e).map(|x| (*x).into())
-                        }
-                        iced::event::Status::Captured => None,
-                    }
+            })
+        };
+        let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) else {
+            return container(col.push(placeholder("No document loaded")))
+                .height(Length::Fill)
+                .into();
+        };
+        if pdf.layers().is_empty() {
+            return container(col.push(placeholder("No layers in this document")))
+                .height(Length::Fill)
+                .into();
+        }
+        let mut list = widget::column![].spacing(6.0);
+        for (i, layer) in pdf.layers().iter().enumerate() {
+            list = list.push(
+                widget::checkbox(layer.visible)
+                    .label(layer.name.as_str())
+                    .text_shaping(text::Shaping::Advanced)
+                    .on_toggle(move |_| PdfMessage::ToggleLayer(i).into()),
+            );
+        }
+        col = col.push(widget::scrollable(list).id(widget::Id::new(SIDEBAR_SCROLLABLE)));
+        container(col).height(Length::Fill).into()
+    }
+
+    fn view_annotations(&self) -> Element<'_, AppMessage> {
+        let mut col = widget::column![
+            text("Annotations").size(18.0),
+            widget::space::vertical().height(8.0),
+        ];
+
+        let placeholder = |msg| {
+            text(msg).style(|theme: &Theme| {
+                let palette = theme.extended_palette();
+                text::Style {
+                    color: Some(palette.background.weak.color),
                 }
-                _ => {
-                    // Handle other keyboard events for keybinds
-                    let mut config = CONFIG.write().unwrap();
-                    match status {
-                        iced::event::Status::Ignored => config
-                            .keyboard
-                            .dispatch(keyboard_event)
-                            .map(|x| (*x).into()),
-                        iced::event::Status::Captured => None,
+            })
+        };
+        let Some(pdf) = self.tabs.pdfs.get(self.tabs.active) else {
+            return container(col.push(placeholder("No document loaded")))
+                .height(Length::Fill)
+                .into();
+        };
+        if pdf.annotations().is_empty() {
+            return container(col.push(placeholder("No annotations in this document")))
+                .height(Length::Fill)
+                .into();
+        }
+
+        let mut list = widget::column![].spacing(4.0);
+        let annotations = pdf.annotations();
+        let mut start = 0;
+        while start < annotations.len() {
+            let page = annotations[start].page_idx;
+            let end = annotations[start..]
+                .iter()
+                .position(|a| a.page_idx != page)
+                .map_or(annotations.len(), |len| start + len);
+            let collapsed = pdf.is_annotation_page_collapsed(page);
+
+            list = list.push(
+                button(text(format!(
+                    "{} Page {} ({})",
+                    if collapsed { "▸" } else { "▾" },
+                    page + 1,
+                    end - start
+                )))
+                .style(button::text)
+                .padding(0.0)
+                .on_press(PdfMessage::ToggleAnnotationPage(page).into()),
+            );
+            if !collapsed {
+                for (i, entry) in annotations.iter().enumerate().take(end).skip(start) {
+                    let mut details = widget::column![
+                        text(entry.kind_label()).size(12.0).style(|theme: &Theme| {
+                            let palette = theme.extended_palette();
+                            text::Style {
+                                color: Some(palette.primary.base.color),
+                            }
+                        }),
+                        text(pdf.annotation_snippet(entry)).shaping(text::Shaping::Advanced),
+                    ];
+                    if let Some(author) = &entry.author {
+                        details = details.push(text(author.clone()).size(12.0));
                     }
+                    let mut row = widget::row![details.width(Length::Fill)]
+                        .align_y(alignment::Vertical::Center);
+                    if !paths::read_only() {
+                        row = row.push(
+                            icons::icon_button(icons::delete(), icons::ButtonVariant::Danger)
+                                .on_press(PdfMessage::DeleteAnnotation(i).into()),
+                        );
+                    }
+                    list = list.push(
+                        button(row)
+                            .style(|_: &Theme, _| widget::button::Style {
+                                background: None,
+                                ..Default::default()
+                            })
+                            .width(Length::Fill)
+                            .padding(Padding::default().left(8.0).right(8.0))
+                            .on_press(AppMessage::OutlineGoToPage(page)),
+                    );
                 }
-            },
+            }
+            start = end;
+        }
+        col = col.push(widget::scrollable(list).id(widget::Id::new(SIDEBAR_SCROLLABLE)));
+
+        container(col).height(Length::Fill).into()
+    }
+
+    /// Whether a popup with a list of results to pick from has the keyboard.
+    fn list_popup_open(&self) -> bool {
+        self.quick_switcher.is_some()
+            || self.global_search.is_some()
+            || matches!(self.prompt, Some((Prompt::GoToDestination, _)))
+    }
+
+    pub fn subscription(&self) -> Subscription<AppMessage> {
+        let keys = match self.input_mode {
+            InputMode::Normal => listen_with(|event, status, _| match event {
+                Event::Keyboard(keyboard_event) => normal_mode_key_event(keyboard_event, status),
+                _ => None,
+            }),
+            InputMode::Insert if self.list_popup_open() => listen_with(|event, _, _| match event {
+                Event::Keyboard(keyboard_event) => list_popup_key_event(keyboard_event),
+                _ => None,
+            }),
+            InputMode::Insert => listen_with(|event, _, _| match event {
+                Event::Keyboard(keyboard_event) => insert_mode_key_event(keyboard_event),
+                _ => None,
+            }),
+        };
+        let mouse = listen_with(|event, status, _| match event {
             Event::Mouse(e) => match e {
-                iced::mouse::Event::CursorMoved { position } => {
-                    Some(AppMessage::MouseMoved(position.into()))
-                }
+                iced::mouse::Event::CursorMoved { position } => match status {
+                    iced::event::Status::Ignored => Some(AppMessage::MouseMoved(position.into())),
+                    iced::event::Status::Captured => {
+                        Some(AppMessage::CapturedMouseMoved(position.into()))
+                    }
+                },
                 iced::mouse::Event::ButtonPressed(button) => {
                     iced_to_config_mouse_button(button).map(AppMessage::MouseButtonDown)
                 }
@@ -1092,6 +4140,7 @@ impl App {
 
         let mut subs = vec![
             keys,
+            mouse,
             Subscription::run(file_watcher).map(AppMessage::FileWatcher),
         ];
         subs.append(&mut platform_specific::listeners());
@@ -1100,12 +4149,51 @@ impl App {
         if config.rpc_enabled {
             subs.push(Subscription::run(rpc_server));
         }
+        if config.follow_system_theme {
+            subs.push(iced::system::theme_changes().map(AppMessage::SystemThemeChanged));
+        }
+        // The window only closes once unsaved edits are dealt with
+        subs.push(iced::window::close_requests().map(|_| AppMessage::Exit));
+        if self.pending_move.is_some() {
+            subs.push(iced::window::frames().map(|_| AppMessage::ApplyPendingMove));
+        }
+        subs.push(listen_with(|event, _, _| match event {
+            Event::Window(window::Event::Unfocused) => Some(AppMessage::WindowUnfocused),
+            Event::Window(window::Event::Focused) => Some(AppMessage::WindowFocused),
+            // Windows shrinks minimized windows to nothing instead
+            Event::Window(window::Event::Resized(size)) => Some(AppMessage::WindowHidden(
+                size.width == 0.0 || size.height == 0.0,
+            )),
+            _ => None,
+        }));
+        if config.autofit {
+            subs.push(listen_with(|event, _, _| match event {
+                Event::Window(window::Event::Resized(_)) => Some(AppMessage::WindowResized),
+                _ => None,
+            }));
+        }
+        // Thumbnails rendered in the background only show up once the page grid is drawn again
+        if !self.window_hidden
+            && self
+                .tabs
+                .pdfs
+                .get(self.tabs.active)
+                .is_some_and(PdfViewer::is_loading_thumbnails)
+        {
+            subs.push(iced::time::every(THUMBNAIL_POLL_INTERVAL).map(|_| AppMessage::None));
+        }
+        if let Some(build) = &self.build {
+            subs.push(
+                Subscription::run_with(build.watch.clone(), build_watcher)
+                    .map(AppMessage::BuildWatcher),
+            );
+        }
 
         Subscription::batch(subs)
     }
 }
 
-impl Drop for App {
+impl<T: Tab> Drop for App<T> {
     fn drop(&mut self) {
         match self.bookmark_store.save() {
             Ok(_) => {}
@@ -1119,6 +4207,27 @@ impl Drop for App {
                 error!("Error while saving recent files: {}", e)
             }
         }
+        match self.reading_stats.save() {
+            Ok(_) => {}
+            Err(e) => {
+                error!("Error while saving reading statistics: {}", e)
+            }
+        }
+        self.session_store.set_sidebar_ratio(self.sidebar_ratio);
+        self.session_store.store_jumplist(
+            self.tabs
+                .pdfs
+                .iter()
+                .filter(|pdf| !pdf.in_memory())
+                .map(|pdf| pdf.path()),
+            std::mem::take(&mut self.jumplist),
+        );
+        match self.session_store.save() {
+            Ok(_) => {}
+            Err(e) => {
+                error!("Error while saving the session: {}", e)
+            }
+        }
     }
 }
 
@@ -1218,66 +4327,157 @@ fn debug_button_s(label: &str) -> button::Button<'_, AppMessage, iced::Theme, ic
         })
 }
 
+/// Longer file names are shortened in the middle so the tabs stay narrow enough to see several
+const MAX_TAB_TITLE_CHARS: usize = 32;
+
+/// An entry of the list of all tabs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TabChoice {
+    idx: usize,
+    name: String,
+}
+
+impl std::fmt::Display for TabChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}. {}", self.idx + 1, self.name)
+    }
+}
+
+/// Shortens `name` to `max` characters by replacing its middle with an ellipsis, keeping the end
+/// since names often only differ in a version or date right before the extension.
+fn middle_ellipsis(name: &str, max: usize) -> String {
+    let len = name.chars().count();
+    if len <= max {
+        return name.to_string();
+    }
+    let end = (max - 1) / 2;
+    let start = max - 1 - end;
+    let head: String = name.chars().take(start).collect();
+    let tail: String = name.chars().skip(len - end).collect();
+    format!("{head}…{tail}")
+}
+
+/// "Signed" when any signature field of the document holds a signature, "Unsigned" when it only
+/// has empty ones.
+fn signature_badge(signatures: &[metadata::Signature]) -> Option<&'static str> {
+    if signatures.iter().any(|s| s.signed) {
+        Some("Signed")
+    } else if !signatures.is_empty() {
+        Some("Unsigned")
+    } else {
+        None
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn file_tab<'a>(
-    file_name: &'a str,
+    title: String,
+    path: String,
     page_progress: String,
+    has_unseen_changes: bool,
+    signature_badge: Option<&'static str>,
+    color: Option<iced::Color>,
+    scrubber: Option<Element<'a, AppMessage>>,
     on_press: AppMessage,
     on_close: AppMessage,
+    on_right_press: AppMessage,
     is_open: bool,
 ) -> Element<'a, AppMessage> {
-    container(
-        widget::row![
-            base_button(
-                widget::row![
-                    text(file_name)
-                        .font(Font {
-                            family: iced::font::Family::Name("Geist"),
-                            weight: Weight::Semibold,
+    let mut label = widget::row![
+        text(title)
+            .font(Font {
+                family: iced::font::Family::Name("Geist"),
+                weight: Weight::Semibold,
+                ..Default::default()
+            })
+            .shaping(text::Shaping::Advanced),
+        text(page_progress).shaping(text::Shaping::Advanced),
+    ]
+    .spacing(8.0);
+    if let Some(badge) = signature_badge {
+        label = label.push(container(text(badge).size(12.0)).padding([0.0, 4.0]).style(
+            move |theme: &Theme| container::Style {
+                border: Border {
+                    // Missing signatures aren't a problem in themselves, only signed is highlighted
+                    color: if badge == "Signed" {
+                        theme.extended_palette().success.base.color
+                    } else {
+                        theme.extended_palette().background.strong.color
+                    },
+                    width: 1.0,
+                    radius: Radius::from(4.0),
+                },
+                ..Default::default()
+            },
+        ));
+    }
+    if has_unseen_changes {
+        // The file was reloaded while the tab was in the background
+        label = label.push(text("●").style(|theme: &Theme| text::Style {
+            color: Some(theme.extended_palette().primary.base.color),
+        }));
+    }
+    let tab = container(
+        widget::column![
+            widget::row![
+                widget::tooltip(
+                    base_button(label, on_press).style(file_tab_style),
+                    container(text(path).size(13.0).shaping(text::Shaping::Advanced))
+                        .padding([4.0, 8.0])
+                        .style(|theme: &Theme| widget::container::Style {
+                            background: Some(theme.extended_palette().background.weak.color.into()),
+                            border: Border {
+                                color: theme.extended_palette().primary.base.color,
+                                width: 2.0,
+                                radius: Radius::from(8.0),
+                            },
                             ..Default::default()
-                        })
-                        .shaping(text::Shaping::Advanced),
-                    text(page_progress).shaping(text::Shaping::Advanced)
-                ]
-                .spacing(8.0),
-                on_press
-            )
-            .style(file_tab_style),
-            // TODO: Svg X
-            base_button(
-                text("×").align_y(alignment::Vertical::Bottom).size(24.0),
-                on_close
-            )
-            .padding(0.0)
-            .style(file_tab_style),
+                        }),
+                    widget::tooltip::Position::Bottom,
+                ),
+                // TODO: Svg X
+                base_button(
+                    text("×").align_y(alignment::Vertical::Bottom).size(24.0),
+                    on_close
+                )
+                .padding(0.0)
+                .style(file_tab_style),
+            ]
+            .align_y(alignment::Vertical::Center)
+            .spacing(2.0),
         ]
-        .align_y(alignment::Vertical::Center)
-        .spacing(2.0),
+        .push(scrubber)
+        .spacing(4.0),
     )
     .padding(6.0)
     .style(move |theme| {
         let palette = theme.extended_palette();
-        let border_pair = if is_open {
-            palette.primary.base
-        } else {
-            palette.primary.weak
+        // Color labels replace the primary color, faded the same way for tabs in the background
+        let border_color = match (color, is_open) {
+            (Some(color), true) => color,
+            (Some(color), false) => color.scale_alpha(0.5),
+            (None, true) => palette.primary.base.color,
+            (None, false) => palette.primary.weak.color,
         };
         container::Style {
             text_color: None,
             background: Some(palette.background.weak.color.into()),
             border: Border {
-                color: border_pair.color,
+                color: border_color,
                 width: 2.0,
                 radius: Radius::from(8.0),
             },
             shadow: Shadow {
-                color: border_pair.color,
+                color: border_color,
                 offset: iced::Vector { x: 0.0, y: 2.0 },
                 blur_radius: 4.0,
             },
             snap: true,
         }
-    })
-    .into()
+    });
+    widget::mouse_area(tab)
+        .on_right_press(on_right_press)
+        .into()
 }
 
 pub fn file_tab_style(theme: &Theme, status: button::Status) -> button::Style {
@@ -1315,6 +4515,30 @@ fn disabled(style: &button::Style) -> button::Style {
     }
 }
 
+/// Starts every command configured for `event` in the background.
+fn run_hooks(event: HookEvent, pdf: &PdfViewer) -> iced::Task<AppMessage> {
+    let config = CONFIG.read().unwrap();
+    let mut commands = config
+        .hooks
+        .iter()
+        .filter(|(hook_event, _)| *hook_event == event)
+        .peekable();
+    if commands.peek().is_none() {
+        return iced::Task::none();
+    }
+    let ctx = HookContext {
+        path: &pdf.path,
+        page: pdf.current_page(),
+        page_count: pdf.page_count().unwrap_or(0) as usize,
+    };
+    iced::Task::batch(commands.map(|(_, command)| {
+        iced::Task::perform(
+            hooks::run(hooks::expand_placeholders(command, &ctx)),
+            |_| AppMessage::None,
+        )
+    }))
+}
+
 fn toggle_fullscreen() -> iced::Task<AppMessage> {
     iced::window::latest()
         .and_then(move |id| iced::window::mode(id).map(move |mode| (id, mode)))
@@ -1324,6 +4548,102 @@ fn toggle_fullscreen() -> iced::Task<AppMessage> {
         })
 }
 
+fn normal_mode_key_event(
+    keyboard_event: iced::keyboard::Event,
+    status: iced::event::Status,
+) -> Option<AppMessage> {
+    match keyboard_event {
+        iced::keyboard::Event::ModifiersChanged(modifiers) => {
+            Some(AppMessage::ModifiersChanged(modifiers))
+        }
+        iced::keyboard::Event::KeyPressed {
+            key: _,
+            modified_key: iced::keyboard::Key::Character(ref modified),
+            physical_key: _,
+            location: _,
+            modifiers: _,
+            text: _,
+            repeat: _,
+        } => {
+            let e = if modified == "+" {
+                iced::keyboard::Event::KeyPressed {
+                    key: iced::keyboard::Key::Character(SmolStr::new_static("+")),
+                    modified_key: iced::keyboard::Key::Character(SmolStr::new_static("+")),
+                    physical_key: iced::keyboard::key::Physical::Code(
+                        iced::keyboard::key::Code::Minus,
+                    ),
+                    location: iced::keyboard::Location::Standard,
+                    modifiers: Modifiers::empty(),
+                    text: Some(SmolStr::new_static("+")),
+                    repeat: false,
+                }
+            } else {
+                keyboard_event
+            };
+            match status {
+                iced::event::Status::Ignored => dispatch_key_event(e),
+                iced::event::Status::Captured => None,
+            }
+        }
+        _ => {
+            // Handle other keyboard events for keybinds
+            match status {
+                iced::event::Status::Ignored => dispatch_key_event(keyboard_event),
+                iced::event::Status::Captured => None,
+            }
+        }
+    }
+}
+
+/// While a text input is focused the keybindings are bypassed entirely, `Escape` is the only key
+/// that is handled and returns to normal mode.
+fn insert_mode_key_event(keyboard_event: iced::keyboard::Event) -> Option<AppMessage> {
+    match keyboard_event {
+        iced::keyboard::Event::ModifiersChanged(modifiers) => {
+            Some(AppMessage::ModifiersChanged(modifiers))
+        }
+        iced::keyboard::Event::KeyPressed {
+            key: iced::keyboard::Key::Named(iced::keyboard::key::Named::Escape),
+            ..
+        } => Some(AppMessage::ExitInsertMode),
+        _ => None,
+    }
+}
+
+/// Like [insert_mode_key_event], but the arrow keys move the selection of the list below the
+/// text input instead of the cursor in it.
+fn list_popup_key_event(keyboard_event: iced::keyboard::Event) -> Option<AppMessage> {
+    match keyboard_event {
+        iced::keyboard::Event::KeyPressed {
+            key: iced::keyboard::Key::Named(iced::keyboard::key::Named::ArrowUp),
+            ..
+        } => Some(AppMessage::MovePopupSelection(-1)),
+        iced::keyboard::Event::KeyPressed {
+            key: iced::keyboard::Key::Named(iced::keyboard::key::Named::ArrowDown),
+            ..
+        } => Some(AppMessage::MovePopupSelection(1)),
+        _ => insert_mode_key_event(keyboard_event),
+    }
+}
+
+/// Feeds a key event to the configured keybindings. Besides bound actions this also reports when a
+/// multi-key sequence starts or stops being pending so the UI can show and time out the prefix.
+fn dispatch_key_event(event: iced::keyboard::Event) -> Option<AppMessage> {
+    let mut config = CONFIG.write().unwrap();
+    let pending_before = config.keyboard.ongoing_inputs().len();
+    if let Some(action) = config.keyboard.dispatch(event) {
+        return Some(AppMessage::KeyAction(*action));
+    }
+    let pending_after = config.keyboard.ongoing_inputs().len();
+    if pending_after > 0 && pending_after != pending_before {
+        Some(AppMessage::KeySequencePending)
+    } else if pending_after == 0 && pending_before > 0 {
+        Some(AppMessage::KeySequenceCancel)
+    } else {
+        None
+    }
+}
+
 fn iced_to_config_mouse_button(button: iced::mouse::Button) -> Option<MouseButton> {
     match button {
         iced::mouse::Button::Left => Some(MouseButton::Left),
@@ -1334,3 +4654,331 @@ fn iced_to_config_mouse_button(button: iced::mouse::Button) -> Option<MouseButto
         iced::mouse::Button::Other(_) => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tabs::FakeTab;
+
+    /// An app without any state from disk with `paths` opened in tabs, the last one active.
+    /// Tasks returned by [App::update] aren't run, so tests send the messages they would.
+    fn app_with(paths: &[&str]) -> App {
+        let mut app: App = App::new(
+            BookmarkStore::new(),
+            RecentFiles::new(),
+            ReadingStats::default(),
+            SessionStore::default(),
+        );
+        for path in paths {
+            let viewer = PdfViewer::from_path(PathBuf::from(path)).unwrap();
+            let _ = app.add_viewer(viewer);
+            let _ = app.update(AppMessage::OpenTab(app.tabs.pdfs.len() - 1));
+        }
+        app
+    }
+
+    /// An app showing fake documents at `paths`, the last one active like after opening them one
+    /// by one.
+    fn fake_app(paths: &[&str]) -> App<FakeTab> {
+        let mut app = App::new(
+            BookmarkStore::new(),
+            RecentFiles::new(),
+            ReadingStats::default(),
+            SessionStore::default(),
+        );
+        for path in paths {
+            let idx = app.add_tab(FakeTab::new(path));
+            app.tabs.open(idx);
+            app.tabs.history.activate(idx);
+        }
+        app
+    }
+
+    fn active<T: Tab>(app: &App<T>) -> &Path {
+        app.tabs.pdfs[app.tabs.active].path()
+    }
+
+    #[test]
+    fn long_tab_titles_keep_their_start_and_end() {
+        assert_eq!(middle_ellipsis("paper.pdf", 12), "paper.pdf");
+        let title = middle_ellipsis("a-very-long-conference-paper-final-v3.pdf", 20);
+        assert_eq!(title, "a-very-lon…al-v3.pdf");
+        assert_eq!(title.chars().count(), 20);
+        assert_eq!(middle_ellipsis("åäöåäöåäö", 5), "åä…äö");
+    }
+
+    #[test]
+    fn trackpad_scrolling_is_applied_once_per_frame() {
+        let mut app = app_with(&["assets/links.pdf"]);
+        let start = app.tabs.pdfs[0].translation;
+        let sensitivity = CONFIG.read().unwrap().trackpad_sensitivity;
+        for _ in 0..3 {
+            let _ = app.update(AppMessage::Scroll(iced::mouse::ScrollDelta::Pixels {
+                x: 0.0,
+                y: 2.0,
+            }));
+        }
+        assert_eq!(app.tabs.pdfs[0].translation, start);
+        let _ = app.update(AppMessage::ApplyPendingMove);
+        assert_eq!(
+            app.tabs.pdfs[0].translation,
+            start + Vector::new(0.0, 6.0 * sensitivity)
+        );
+        assert_eq!(app.pending_move, None);
+    }
+
+    #[test]
+    fn trackpad_scrolling_stays_in_its_tab() {
+        let mut app = app_with(&["assets/links.pdf", "assets/coordinate_points.pdf"]);
+        let start = app.tabs.pdfs[1].translation;
+        let other = app.tabs.pdfs[0].translation;
+        let _ = app.update(AppMessage::Scroll(iced::mouse::ScrollDelta::Pixels {
+            x: 0.0,
+            y: 2.0,
+        }));
+        let _ = app.update(AppMessage::OpenTab(0));
+        let _ = app.update(AppMessage::ApplyPendingMove);
+        assert_ne!(app.tabs.pdfs[1].translation, start);
+        assert_eq!(app.tabs.pdfs[0].translation, other);
+    }
+
+    #[test]
+    fn reading_time_pauses_while_the_window_is_in_the_background() {
+        let mut app = app_with(&["assets/links.pdf"]);
+        let path = active(&app).to_path_buf();
+        assert!(app.reading_stats.is_reading(&path));
+        let _ = app.update(AppMessage::WindowUnfocused);
+        assert!(!app.reading_stats.is_reading(&path));
+        let _ = app.update(AppMessage::WindowFocused);
+        assert!(app.reading_stats.is_reading(&path));
+    }
+
+    #[test]
+    fn switching_tabs_follows_the_tab_order() {
+        let mut app = fake_app(&["a.pdf", "b.pdf", "c.pdf"]);
+        app.step_tab(-1);
+        assert_eq!(active(&app), Path::new("b.pdf"));
+        app.step_tab(5);
+        assert_eq!(active(&app), Path::new("c.pdf"));
+    }
+
+    #[test]
+    fn jumping_between_documents_can_be_undone() {
+        let mut app = fake_app(&["a.pdf", "b.pdf"]);
+        app.record_location();
+        let mut target = app.tabs.pdfs[0].location();
+        target.translation = Vector::new(0.0, 300.0);
+        let _ = app.jump_to(target.clone());
+        app.record_location();
+        assert_eq!(active(&app), Path::new("a.pdf"));
+        assert_eq!(app.jumplist.len(), 2);
+
+        let _ = app.jump_back();
+        assert_eq!(active(&app), Path::new("b.pdf"));
+        let _ = app.jump_forward();
+        assert_eq!(active(&app), Path::new("a.pdf"));
+        assert_eq!(app.tabs.pdfs[0].location.translation, target.translation);
+
+        // The document jumped back to was closed in between, it is opened again first
+        app.tabs.remove(1);
+        let _ = app.jump_back();
+        assert_eq!(active(&app), Path::new("a.pdf"));
+        assert_eq!(app.jumplist.current_index, 0);
+    }
+
+    #[test]
+    fn closing_the_last_tab_leaves_an_empty_window() {
+        CONFIG.write().unwrap().last_tab_closed = LastTabClosed::StartScreen;
+        let mut app = app_with(&["assets/links.pdf", "assets/coordinate_points.pdf"]);
+        let _ = app.update(AppMessage::CloseActiveTab);
+        let _ = app.update(AppMessage::CloseActiveTab);
+        assert!(app.tabs.pdfs.is_empty());
+        assert_eq!(app.tabs.active, 0);
+        assert!(!app.recent_files_screen);
+        // Opening a document from the empty window works as usual
+        let _ = app.add_viewer(PdfViewer::from_path(PathBuf::from("assets/links.pdf")).unwrap());
+        assert_eq!(active(&app), Path::new("assets/links.pdf"));
+    }
+
+    #[test]
+    fn closing_unsaved_edits_asks_first() {
+        let mut app = app_with(&["assets/links.pdf", "assets/links_commented.pdf"]);
+        let _ = app.update(PdfMessage::DeleteAnnotation(0).into());
+        assert!(app.tabs.pdfs[1].modified);
+
+        let _ = app.update(AppMessage::CloseActiveTab);
+        assert_eq!(app.unsaved_prompt, Some(PendingClose::Tab(1)));
+        assert_eq!(app.tabs.pdfs.len(), 2);
+        let _ = app.update(AppMessage::UnsavedChanges(UnsavedChoice::Cancel));
+        assert_eq!(app.unsaved_prompt, None);
+        assert_eq!(app.tabs.pdfs.len(), 2);
+
+        let _ = app.update(AppMessage::Exit);
+        assert_eq!(app.unsaved_prompt, Some(PendingClose::Exit));
+        let _ = app.update(AppMessage::UnsavedChanges(UnsavedChoice::Cancel));
+
+        // Closing a tab before it keeps the prompt about the same document
+        let _ = app.update(AppMessage::CloseTab(1));
+        let _ = app.update(AppMessage::CloseTab(0));
+        assert_eq!(app.unsaved_prompt, Some(PendingClose::Tab(0)));
+        let _ = app.update(AppMessage::UnsavedChanges(UnsavedChoice::Discard));
+        assert!(app.tabs.pdfs.is_empty());
+        assert_eq!(app.unsaved_prompt, None);
+    }
+
+    #[test]
+    fn the_quick_switcher_switches_to_the_typed_tab() {
+        let mut app = app_with(&["assets/links.pdf", "assets/coordinate_points.pdf"]);
+        let _ = app.update(AppMessage::OpenQuickSwitcher);
+        assert_eq!(app.input_mode, InputMode::Insert);
+        let _ = app.update(AppMessage::QuickSwitcherInput("lnk".to_string()));
+        let _ = app.update(AppMessage::MovePopupSelection(1));
+        assert_eq!(app.quick_switcher.as_ref().unwrap().selected, 0);
+        // Returns the OpenTab the tests have to send themselves
+        let _ = app.update(AppMessage::SwitchToEntry(None));
+        assert!(app.quick_switcher.is_none());
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let _ = app.update(AppMessage::OpenTab(0));
+        assert_eq!(active(&app), Path::new("assets/links.pdf"));
+    }
+
+    #[test]
+    fn arrow_keys_only_move_the_selection_of_lists() {
+        let arrow_down = iced::keyboard::Event::KeyPressed {
+            key: iced::keyboard::Key::Named(iced::keyboard::key::Named::ArrowDown),
+            modified_key: iced::keyboard::Key::Named(iced::keyboard::key::Named::ArrowDown),
+            physical_key: iced::keyboard::key::Physical::Code(iced::keyboard::key::Code::ArrowDown),
+            location: iced::keyboard::Location::Standard,
+            modifiers: Modifiers::empty(),
+            text: None,
+            repeat: false,
+        };
+        assert!(insert_mode_key_event(arrow_down.clone()).is_none());
+        assert!(matches!(
+            list_popup_key_event(arrow_down),
+            Some(AppMessage::MovePopupSelection(1))
+        ));
+
+        let mut app = app_with(&["assets/links.pdf"]);
+        assert!(!app.list_popup_open());
+        let _ = app.update(AppMessage::OpenQuickSwitcher);
+        assert!(app.list_popup_open());
+    }
+
+    #[test]
+    fn the_arrow_keys_pick_a_destination() {
+        let mut app = app_with(&["assets/links.pdf"]);
+        let _ = app.update(AppMessage::GoToDestinationPrompt);
+        assert!(app.list_popup_open());
+        let _ = app.update(AppMessage::PromptInput("t".to_string()));
+        assert_eq!(
+            app.listed_destinations("t"),
+            [
+                ("links-in-tables".to_string(), 1),
+                ("section-text".to_string(), 1)
+            ]
+        );
+        let _ = app.update(AppMessage::MovePopupSelection(5));
+        assert_eq!(app.prompt_selected, 1);
+        let _ = app.update(AppMessage::PromptInput("te".to_string()));
+        assert_eq!(app.prompt_selected, 0);
+    }
+
+    #[test]
+    fn open_menus_keep_the_menu_bar_revealed() {
+        let mut app = app_with(&["assets/links.pdf"]);
+        let _ = app.update(AppMessage::ToggleDistractionFree);
+        let _ = app.update(AppMessage::MouseMoved(Vector::new(100.0, 0.0)));
+        assert!(app.chrome_revealed);
+        let below = Vector::new(100.0, CHROME_HIDE_DISTANCE + 100.0);
+        let _ = app.update(AppMessage::CapturedMouseMoved(below));
+        assert!(app.chrome_revealed);
+        let _ = app.update(AppMessage::MouseMoved(below));
+        assert!(!app.chrome_revealed);
+    }
+
+    #[test]
+    fn typing_into_the_preferences_bypasses_the_keybindings() {
+        let mut app = app_with(&["assets/links.pdf"]);
+        app.preferences = Some(Preferences::new(PathBuf::from("miro.conf"), String::new()));
+        app.input_mode = InputMode::Normal;
+        let _ = app.update(AppMessage::FilterPreferences("zoom".to_string()));
+        assert_eq!(app.input_mode, InputMode::Insert);
+        app.input_mode = InputMode::Normal;
+        let _ = app.update(AppMessage::EditPreference(0, "1".to_string()));
+        assert_eq!(app.input_mode, InputMode::Insert);
+    }
+
+    #[test]
+    fn the_pointer_only_shows_in_presentation_mode() {
+        let mut app = app_with(&["assets/links.pdf"]);
+        let _ = app.update(AppMessage::CyclePresentationPointer);
+        assert_eq!(
+            app.tabs.pdfs[0].presentation_pointer,
+            PresentationPointer::Off
+        );
+        let _ = app.update(AppMessage::TogglePresentationMode);
+        assert_eq!(
+            app.tabs.pdfs[0].presentation_pointer,
+            PresentationPointer::Laser
+        );
+        let _ = app.update(AppMessage::CyclePresentationPointer);
+        assert_eq!(
+            app.tabs.pdfs[0].presentation_pointer,
+            PresentationPointer::Spotlight
+        );
+        let _ = app.update(AppMessage::TogglePresentationMode);
+        assert_eq!(
+            app.tabs.pdfs[0].presentation_pointer,
+            PresentationPointer::Off
+        );
+    }
+
+    #[test]
+    fn slow_translations_dont_replace_newer_ones() {
+        let mut app = app_with(&[]);
+        app.translation = Some(Translation {
+            id: 1,
+            source: "Hallo".to_string(),
+            result: None,
+        });
+        let _ = app.update(AppMessage::TranslationReady(0, Ok("Goodbye".to_string())));
+        assert_eq!(app.translation.as_ref().unwrap().result, None);
+        let _ = app.update(AppMessage::TranslationReady(1, Ok("Hello".to_string())));
+        assert_eq!(
+            app.translation.as_ref().unwrap().result,
+            Some(Ok("Hello".to_string()))
+        );
+    }
+
+    #[test]
+    fn keys_reach_the_document_in_presentation_mode() {
+        let mut app = app_with(&["assets/multiple-page-layouts.pdf"]);
+        app.active_pane = PaneType::Sidebar;
+        let _ = app.update(AppMessage::TogglePresentationMode);
+        let start = app.tabs.pdfs[0].translation;
+        // Would scroll the sidebar if it had focus
+        let _ = app.update(AppMessage::KeyAction(BindableMessage::MoveDown));
+        assert_ne!(app.tabs.pdfs[0].translation, start);
+    }
+
+    #[test]
+    fn tabs_are_labeled_from_their_menu() {
+        let mut app = app_with(&["assets/links.pdf", "assets/coordinate_points.pdf"]);
+        let _ = app.update(AppMessage::ShowTabMenu(0));
+        assert_eq!(app.tab_menu, Some(0));
+        let _ = app.update(AppMessage::SetTabColor(0, Some(TabColor::Green)));
+        assert_eq!(app.tab_menu, None);
+        assert_eq!(
+            app.session_store.tab_color(Path::new("assets/links.pdf")),
+            Some(TabColor::Green)
+        );
+        let _ = app.update(AppMessage::SetTabColor(0, None));
+        assert_eq!(
+            app.session_store.tab_color(Path::new("assets/links.pdf")),
+            None
+        );
+        // Labels don't reorder the tabs unless they are grouped
+        assert_eq!(app.tab_order(), [0, 1]);
+    }
+}
@@ -0,0 +1,82 @@
+//! The hot paths measured by the benchmarks in `benches/`. Benchmarks only see the public items of
+//! the library, so this is the one public module and the rest of miro stays private to the crate.
+//! Only built with the `bench` feature, so none of this ends up in release builds.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::{
+    geometry::{Rect, Vector},
+    pdf::{
+        backend::{self, DocumentBackend},
+        widget::{PdfViewer, cpu_pdf_dark_mode_shader},
+    },
+};
+
+/// A document opened both the way the viewer opens it and through its backend.
+#[derive(Debug)]
+pub struct BenchDocument {
+    viewer: PdfViewer,
+    backend: Box<dyn DocumentBackend>,
+    viewport: iced::Size,
+}
+
+impl BenchDocument {
+    /// Opens `path` in a full HD window.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut doc = Self {
+            viewer: PdfViewer::from_path(path.to_path_buf())?,
+            backend: backend::open(path)?,
+            viewport: iced::Size::new(1920.0, 1080.0),
+        };
+        doc.viewer.set_viewport(doc.viewport);
+        Ok(doc)
+    }
+
+    pub fn page_count(&self) -> Result<usize> {
+        self.backend.page_count()
+    }
+
+    /// Shows the document at `scale` in a `width` by `height` window, in the colors of the dark
+    /// mode if `dark_mode` is set.
+    pub fn set_view(&mut self, scale: f32, dark_mode: bool, width: f32, height: f32) {
+        self.viewport = iced::Size::new(width, height);
+        self.viewer.set_viewport(self.viewport);
+        self.viewer.set_pdf_dark_mode(dark_mode);
+        self.viewer.scale = scale;
+    }
+
+    /// Draws the part of the first page in the window to rgba pixels, the same way the viewer
+    /// draws pages that it doesn't have cached. Pages fitting in the window are drawn entirely.
+    pub fn draw_first_page(&self) -> Result<Vec<u8>> {
+        Ok(self.viewer.draw_visible_page(0, self.viewport)?.2)
+    }
+
+    pub fn dark_mode_colors(&self) -> [[u8; 4]; 256] {
+        self.viewer.dark_mode_colors()
+    }
+
+    /// Copies the text under a selection covering the whole window, the same as dragging a
+    /// selection over the whole screen.
+    pub fn extract_text_in_window(&self) -> String {
+        self.viewer.extract_text_from_rect(Rect::from_points(
+            Vector::zero(),
+            Vector::new(self.viewport.width, self.viewport.height),
+        ))
+    }
+
+    /// Reads the links of every page, which happens for the whole document when it is opened.
+    pub fn extract_links(&self) -> Result<usize> {
+        let mut count = 0;
+        for page_idx in 0..self.backend.page_count()? {
+            count += self.backend.page_links(page_idx)?.len();
+        }
+        Ok(count)
+    }
+}
+
+/// Recolors rgba `samples` with dark mode `colors`, see [BenchDocument::dark_mode_colors].
+pub fn dark_mode_shader(samples: &mut [u8], colors: &[[u8; 4]; 256]) {
+    cpu_pdf_dark_mode_shader(samples, colors);
+}
@@ -10,9 +10,14 @@ use iced::{
 };
 use serde::{Deserialize, Serialize};
 use strum::EnumString;
+use tracing::error;
 use twox_hash::XxHash64;
 
-use crate::icons::{self, ButtonVariant, icon_button};
+use crate::{
+    app::SIDEBAR_SCROLLABLE,
+    icons::{self, ButtonVariant, icon_button},
+    paths,
+};
 
 // This does not need to be cryptographically sound in the slightest. It is just used for
 // fingerprinting files to detect updates.
@@ -72,27 +77,45 @@ impl BookmarkStore {
     }
 
     fn system_store_path() -> Result<PathBuf> {
-        Ok(home::home_dir()
-            .ok_or(anyhow!("No home directory could be determined"))?
-            .join("./.config/miro-pdf/bookmarks.json"))
+        paths::data_file("bookmarks.json")
     }
 
     pub fn save(&self) -> Result<()> {
-        fs::write(
-            Self::system_store_path()?,
+        paths::write(
+            &Self::system_store_path()?,
             serde_json::to_string(self).map_err(|e| anyhow!("{}", e))?,
         )
-        .map_err(|e| anyhow!("{}", e))
+    }
+
+    /// Saved on every change so `miro ctl bookmark list` sees the same bookmarks as the viewer
+    fn save_or_log(&self) {
+        if let Err(e) = self.save() {
+            error!("Error while saving bookmarks: {e}");
+        }
+    }
+
+    /// Every bookmark together with the file it belongs to.
+    pub fn bookmarks(&self) -> impl Iterator<Item = (&Path, &Bookmark)> {
+        self.sets
+            .iter()
+            .flat_map(|set| set.marks.iter().map(|mark| (set.path.as_path(), mark)))
     }
 
     pub fn update(&mut self, message: BookmarkMessage) -> iced::Task<BookmarkMessage> {
         match message {
+            BookmarkMessage::CreateBookmark { .. } | BookmarkMessage::DeleteBookmark { .. }
+                if paths::read_only() =>
+            {
+                iced::Task::none()
+            }
             BookmarkMessage::CreateBookmark { path, name, page } => {
                 self.create_bookmark(path, name, page);
+                self.save_or_log();
                 iced::Task::none()
             }
             BookmarkMessage::DeleteBookmark { path, name } => {
                 self.delete_bookmark(path, name);
+                self.save_or_log();
                 iced::Task::none()
             }
             BookmarkMessage::PendingName(s) => {
@@ -109,15 +132,20 @@ impl BookmarkStore {
         let mut col = widget::column![
             text("Bookmarks").size(18.0),
             widget::space::vertical().height(8.0),
-            text_input("New bookmark", &self.pending_name)
-                .on_input(BookmarkMessage::PendingName)
-                .on_submit(BookmarkMessage::RequestNewBookmark {
-                    name: self.pending_name.clone()
-                }),
-            widget::space::vertical().height(8.0),
-            rule::horizontal(2.0),
-            widget::space::vertical().height(8.0),
         ];
+        if !paths::read_only() {
+            col = col.push(
+                text_input("New bookmark", &self.pending_name)
+                    .on_input(BookmarkMessage::PendingName)
+                    .on_submit(BookmarkMessage::RequestNewBookmark {
+                        name: self.pending_name.clone(),
+                    }),
+            );
+            col = col.push(widget::space::vertical().height(8.0));
+        }
+        col = col
+            .push(rule::horizontal(2.0))
+            .push(widget::space::vertical().height(8.0));
         for set in &self.sets {
             col = col.push(self.view_bookmark_set(set));
         }
@@ -131,45 +159,50 @@ impl BookmarkStore {
             widget::space::vertical().height(4.0)
         ];
         for mark in &set.marks {
-            marks = marks.push(
-                button(widget::row![
-                    hover(
-                        text(&mark.name)
-                            .shaping(text::Shaping::Advanced)
-                            .style(|_: &Theme| widget::text::Style {
-                                color: Some(iced::Color::from_rgb(0.5, 0.5, 0.5)),
-                            })
-                            .width(Length::Fill),
-                        text(&mark.name)
-                            .shaping(text::Shaping::Advanced)
-                            .style(|theme: &Theme| {
-                                let palette = theme.extended_palette();
-                                widget::text::Style {
-                                    color: Some(palette.primary.base.color),
-                                }
-                            })
-                            .width(Length::Fill),
-                    ),
+            let mut row = widget::row![hover(
+                text(&mark.name)
+                    .shaping(text::Shaping::Advanced)
+                    .style(|_: &Theme| widget::text::Style {
+                        color: Some(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+                    })
+                    .width(Length::Fill),
+                text(&mark.name)
+                    .shaping(text::Shaping::Advanced)
+                    .style(|theme: &Theme| {
+                        let palette = theme.extended_palette();
+                        widget::text::Style {
+                            color: Some(palette.primary.base.color),
+                        }
+                    })
+                    .width(Length::Fill),
+            )];
+            if !paths::read_only() {
+                row = row.push(
                     icon_button(icons::delete(), ButtonVariant::Danger).on_press(
                         BookmarkMessage::DeleteBookmark {
                             path: set.path.clone(),
-                            name: mark.name.clone()
-                        }
-                    )
-                ])
-                .style(|_: &Theme, _| widget::button::Style {
-                    background: None,
-                    ..Default::default()
-                })
-                .width(Length::Fill)
-                .padding(Padding::default().left(8.0).right(8.0))
-                .on_press(BookmarkMessage::GoTo {
-                    path: set.path.clone(),
-                    page: mark.page,
-                }),
+                            name: mark.name.clone(),
+                        },
+                    ),
+                );
+            }
+            marks = marks.push(
+                button(row)
+                    .style(|_: &Theme, _| widget::button::Style {
+                        background: None,
+                        ..Default::default()
+                    })
+                    .width(Length::Fill)
+                    .padding(Padding::default().left(8.0).right(8.0))
+                    .on_press(BookmarkMessage::GoTo {
+                        path: set.path.clone(),
+                        page: mark.page,
+                    }),
             );
         }
-        widget::scrollable(marks).into()
+        widget::scrollable(marks)
+            .id(widget::Id::new(SIDEBAR_SCROLLABLE))
+            .into()
     }
 
     /// Requires canonical path
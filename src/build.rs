@@ -0,0 +1,234 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use async_watcher::{AsyncDebouncer, notify::RecursiveMode};
+use iced::{
+    Border, Element, Font, Length, Theme,
+    border::Radius,
+    futures::{SinkExt, Stream, channel::mpsc},
+    stream,
+    widget::{self, button, container, scrollable, text},
+};
+use tracing::error;
+
+use crate::hooks;
+
+/// Files produced by typical LaTeX and Typst builds. Changes to these must not trigger another
+/// build or we would end up in a loop.
+const IGNORED_EXTENSIONS: &[&str] = &[
+    "pdf",
+    "aux",
+    "log",
+    "fls",
+    "fdb_latexmk",
+    "synctex",
+    "gz",
+    "out",
+    "toc",
+    "bbl",
+    "blg",
+    "bcf",
+    "xdv",
+    "run",
+    "xml",
+    "nav",
+    "snm",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BuildWatch {
+    pub command: String,
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub enum BuildNotification {
+    Started,
+    Finished { success: bool, output: String },
+}
+
+/// The `--watch-build` command and the outcome of its latest run.
+#[derive(Debug)]
+pub struct BuildState {
+    pub watch: BuildWatch,
+    pub running: bool,
+    /// Whether the last finished build succeeded along with its combined output
+    pub last: Option<(bool, String)>,
+    pub expanded: bool,
+}
+
+impl BuildState {
+    pub fn new(watch: BuildWatch) -> Self {
+        Self {
+            watch,
+            running: false,
+            last: None,
+            expanded: false,
+        }
+    }
+
+    /// Returns true if the build finished successfully and the output should be reloaded.
+    pub fn update(&mut self, notification: BuildNotification) -> bool {
+        match notification {
+            BuildNotification::Started => {
+                self.running = true;
+                false
+            }
+            BuildNotification::Finished { success, output } => {
+                self.running = false;
+                // Errors are what the panel is for, a successful build doesn't need attention
+                self.expanded = !success;
+                self.last = Some((success, output));
+                success
+            }
+        }
+    }
+
+    pub fn view<'a, Message: Clone + 'a>(
+        &'a self,
+        on_toggle: Message,
+    ) -> Option<Element<'a, Message>> {
+        let status = match (&self.last, self.running) {
+            (_, true) => "Building…",
+            (Some((true, _)), false) => "Build succeeded",
+            (Some((false, _)), false) => "Build failed",
+            (None, false) => return None,
+        };
+        let failed = matches!(self.last, Some((false, _))) && !self.running;
+
+        let header = widget::row![
+            text(status)
+                .width(Length::Fill)
+                .style(move |theme: &Theme| {
+                    let palette = theme.extended_palette();
+                    text::Style {
+                        color: failed.then_some(palette.danger.base.color),
+                    }
+                }),
+            button(text(if self.expanded { "Hide" } else { "Show output" }))
+                .on_press(on_toggle)
+                .style(button::text),
+        ]
+        .align_y(iced::alignment::Vertical::Center);
+
+        let mut col = widget::column![header].spacing(4.0);
+        if self.expanded
+            && let Some((_, output)) = &self.last
+        {
+            col = col.push(
+                scrollable(text(output.as_str()).font(Font::MONOSPACE).size(12.0))
+                    .width(Length::Fill)
+                    .height(Length::Fixed(200.0)),
+            );
+        }
+
+        Some(
+            container(col)
+                .padding([4.0, 8.0])
+                .width(Length::Fill)
+                .style(|theme: &Theme| widget::container::Style {
+                    background: Some(theme.extended_palette().background.weak.color.into()),
+                    border: Border {
+                        color: theme.extended_palette().background.strong.color,
+                        width: 1.0,
+                        radius: Radius::from(8.0),
+                    },
+                    ..Default::default()
+                })
+                .into(),
+        )
+    }
+}
+
+fn is_source_change(path: &Path) -> bool {
+    let hidden = path.components().any(|c| {
+        c.as_os_str()
+            .to_str()
+            .is_some_and(|s| s.starts_with('.') && s != "." && s != "..")
+    });
+    let ignored_extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IGNORED_EXTENSIONS.contains(&ext));
+    !hidden && !ignored_extension
+}
+
+async fn run_build(command: &str, dir: &Path) -> (bool, String) {
+    match hooks::shell_command(command)
+        .current_dir(dir)
+        .output()
+        .await
+    {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            (output.status.success(), text)
+        }
+        Err(e) => (false, format!("Couldn't run '{command}': {e}")),
+    }
+}
+
+/// Runs the build command whenever a source file below the watched directory changes.
+pub fn build_watcher(watch: &BuildWatch) -> impl Stream<Item = BuildNotification> + use<> {
+    let watch = watch.clone();
+    stream::channel(
+        10,
+        |mut output: mpsc::Sender<BuildNotification>| async move {
+            let (mut debouncer, mut file_events) = match AsyncDebouncer::new_with_channel(
+                Duration::from_millis(300),
+                Some(Duration::from_millis(300)),
+            )
+            .await
+            {
+                Ok(debouncer) => debouncer,
+                Err(e) => {
+                    error!("Couldn't start the build watcher: {e}");
+                    return;
+                }
+            };
+            if let Err(e) = debouncer
+                .watcher()
+                .watch(&watch.dir, RecursiveMode::Recursive)
+            {
+                error!("Couldn't watch {:?} for changes: {e}", watch.dir);
+                return;
+            }
+
+            while let Some(events) = file_events.recv().await {
+                let Ok(events) = events else {
+                    continue;
+                };
+                if !events
+                    .iter()
+                    .any(|e| e.event.paths.iter().any(|p| is_source_change(p)))
+                {
+                    continue;
+                }
+                let _ = output.send(BuildNotification::Started).await;
+                let (success, log) = run_build(&watch.command, &watch.dir).await;
+                let _ = output
+                    .send(BuildNotification::Finished {
+                        success,
+                        output: log,
+                    })
+                    .await;
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_artifacts_do_not_trigger_builds() {
+        assert!(is_source_change(Path::new("/thesis/main.tex")));
+        assert!(is_source_change(Path::new("/thesis/chapters/intro.typ")));
+        assert!(!is_source_change(Path::new("/thesis/main.pdf")));
+        assert!(!is_source_change(Path::new("/thesis/main.aux")));
+        assert!(!is_source_change(Path::new("/thesis/.git/index")));
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use mupdf::{Document, TextPageFlags, pdf::PdfPage};
+
+use crate::metadata::{self, Font};
+
+/// Something about a document that makes it render differently between readers or not at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// The page the problem is on, `None` for problems with the file itself
+    pub page: Option<usize>,
+    pub description: String,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.page {
+            Some(page) => write!(f, "Page {}: {}", page + 1, self.description),
+            None => write!(f, "{}", self.description),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub page_count: usize,
+    pub problems: Vec<Problem>,
+    /// Every font used by the document, found while checking the pages
+    pub fonts: Vec<Font>,
+}
+
+impl Report {
+    /// A report for a document that couldn't be checked at all.
+    pub fn failed(reason: impl Display) -> Self {
+        let mut report = Report::default();
+        report.problem(None, reason);
+        report
+    }
+
+    fn problem(&mut self, page: Option<usize>, description: impl Display) {
+        self.problems.push(Problem {
+            page,
+            description: description.to_string(),
+        });
+    }
+}
+
+/// Looks for problems in the structure of the pdf in `bytes` and in every one of its pages.
+pub fn check_bytes(bytes: &[u8]) -> Report {
+    let mut report = Report::default();
+    for problem in structure_problems(bytes) {
+        report.problem(None, problem);
+    }
+
+    let doc = match Document::from_bytes(bytes, "application/pdf") {
+        Ok(doc) => doc,
+        Err(e) => {
+            report.problem(None, format!("Couldn't open the document: {e}"));
+            return report;
+        }
+    };
+    if doc.needs_password().unwrap_or(false) {
+        report.problem(
+            None,
+            "The document is encrypted, its pages can't be checked",
+        );
+        return report;
+    }
+    report.page_count = match doc.page_count() {
+        Ok(count) => count.max(0) as usize,
+        Err(e) => {
+            report.problem(None, format!("Couldn't count the pages: {e}"));
+            return report;
+        }
+    };
+
+    // Fonts are usually shared between pages and only reported on the first one using them
+    let mut seen_fonts = HashSet::new();
+    for page_idx in 0..report.page_count {
+        let page = match doc.load_page(page_idx as i32) {
+            Ok(page) => page,
+            Err(e) => {
+                report.problem(Some(page_idx), format!("Couldn't load the page: {e}"));
+                continue;
+            }
+        };
+        match page.to_display_list(false) {
+            Ok(list) => {
+                if let Err(e) = list.to_text_page(TextPageFlags::empty()) {
+                    report.problem(Some(page_idx), format!("Couldn't extract the text: {e}"));
+                }
+            }
+            Err(e) => report.problem(Some(page_idx), format!("Couldn't draw the page: {e}")),
+        }
+        if let Ok(pdf_page) = PdfPage::try_from(page) {
+            for font in metadata::page_fonts(&pdf_page, page_idx, &mut seen_fonts) {
+                if font.is_missing() {
+                    report.problem(
+                        Some(page_idx),
+                        format!("The font {} isn't embedded", font.name),
+                    );
+                }
+                report.fonts.push(font);
+            }
+        }
+    }
+    report
+}
+
+pub fn check_file(path: &Path) -> Report {
+    match std::fs::read(path) {
+        Ok(bytes) => check_bytes(&bytes),
+        Err(e) => Report::failed(format!("Couldn't read the file: {e}")),
+    }
+}
+
+/// Problems with the header, trailer and cross-reference table that MuPDF silently repairs
+/// while opening the file, but other readers might not.
+fn structure_problems(bytes: &[u8]) -> Vec<&'static str> {
+    let mut problems = vec![];
+    let head = &bytes[..bytes.len().min(1024)];
+    if find(head, b"%PDF-").is_none() {
+        problems.push("The file doesn't start with a %PDF- header");
+    }
+    let tail = &bytes[bytes.len().saturating_sub(1024)..];
+    if find(tail, b"%%EOF").is_none() {
+        problems.push("The file doesn't end with %%EOF, it might be truncated");
+    }
+
+    let Some(startxref) = rfind(bytes, b"startxref") else {
+        problems.push("The file has no startxref, its cross-reference table can't be found");
+        return problems;
+    };
+    let offset = bytes[startxref + b"startxref".len()..]
+        .iter()
+        .skip_while(|b| b.is_ascii_whitespace())
+        .take_while(|b| b.is_ascii_digit())
+        .try_fold(0usize, |acc, b| {
+            acc.checked_mul(10)?.checked_add((b - b'0') as usize)
+        });
+    let points_at_xref = offset
+        .and_then(|offset| bytes.get(offset..))
+        .is_some_and(|rest| {
+            let rest = rest.trim_ascii_start();
+            // Either a classic table or a cross-reference stream object like "12 0 obj"
+            rest.starts_with(b"xref") || rest.first().is_some_and(u8::is_ascii_digit)
+        });
+    if !points_at_xref {
+        problems.push("startxref doesn't point at the cross-reference table");
+    }
+    problems
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+/// Checks every file and prints what is wrong with them. Returns whether all of them are fine.
+pub fn cli(paths: &[PathBuf]) -> bool {
+    let mut all_fine = true;
+    for path in paths {
+        let report = check_file(path);
+        if report.problems.is_empty() {
+            println!(
+                "{}: {} pages, no problems found",
+                path.display(),
+                report.page_count
+            );
+        } else {
+            all_fine = false;
+            println!(
+                "{}: {} pages, {} problems",
+                path.display(),
+                report.page_count,
+                report.problems.len()
+            );
+            for problem in &report.problems {
+                println!("  {problem}");
+            }
+        }
+    }
+    all_fine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn startxref_has_to_point_at_the_table() {
+        let body = "%PDF-1.4\n1 0 obj\n<<>>\nendobj\n";
+        let valid = format!(
+            "{body}xref\n0 1\ntrailer\n<<>>\nstartxref\n{}\n%%EOF\n",
+            body.len()
+        );
+        assert!(structure_problems(valid.as_bytes()).is_empty());
+
+        let moved = format!("{body}xref\n0 1\ntrailer\n<<>>\nstartxref\n3\n%%EOF\n");
+        assert_eq!(
+            structure_problems(moved.as_bytes()),
+            ["startxref doesn't point at the cross-reference table"]
+        );
+        assert_eq!(
+            structure_problems(&valid.as_bytes()[..valid.len() / 2]),
+            [
+                "The file doesn't end with %%EOF, it might be truncated",
+                "The file has no startxref, its cross-reference table can't be found"
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_files_are_reported() {
+        let bytes = std::fs::read("assets/links.pdf").unwrap();
+        let report = check_bytes(&bytes[..bytes.len() / 2]);
+        assert!(!report.problems.is_empty());
+    }
+}
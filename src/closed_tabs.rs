@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+const MAX_CLOSED_TABS: usize = 10;
+
+/// Enough information to bring a closed tab back roughly where the user left it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedTab {
+    pub path: PathBuf,
+    pub page: usize,
+    pub scale: f32,
+}
+
+/// Recently closed tabs, most recent first. Only kept in memory for the current session.
+#[derive(Debug, Default)]
+pub struct ClosedTabs {
+    tabs: Vec<ClosedTab>,
+}
+
+impl ClosedTabs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, tab: ClosedTab) {
+        self.tabs.retain(|t| t.path != tab.path);
+        self.tabs.insert(0, tab);
+        self.tabs.truncate(MAX_CLOSED_TABS);
+    }
+
+    /// Removes and returns the tab at `idx`, where 0 is the most recently closed one.
+    pub fn take(&mut self, idx: usize) -> Option<ClosedTab> {
+        if idx < self.tabs.len() {
+            Some(self.tabs.remove(idx))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_closed(&self) -> &[ClosedTab] {
+        &self.tabs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn closed(path: &str) -> ClosedTab {
+        ClosedTab {
+            path: PathBuf::from(path),
+            page: 0,
+            scale: 1.0,
+        }
+    }
+
+    #[test]
+    fn most_recently_closed_tabs_come_first() {
+        let mut tabs = ClosedTabs::new();
+        tabs.push(closed("/a.pdf"));
+        tabs.push(closed("/b.pdf"));
+        // Closing the same document again moves it to the front instead of listing it twice
+        tabs.push(closed("/a.pdf"));
+        assert_eq!(tabs.get_closed(), [closed("/a.pdf"), closed("/b.pdf")]);
+
+        assert_eq!(tabs.take(1), Some(closed("/b.pdf")));
+        assert_eq!(tabs.take(1), None);
+        assert_eq!(tabs.take(0), Some(closed("/a.pdf")));
+        assert!(tabs.get_closed().is_empty());
+    }
+
+    #[test]
+    fn only_the_latest_closed_tabs_are_kept() {
+        let mut tabs = ClosedTabs::new();
+        for i in 0..MAX_CLOSED_TABS + 3 {
+            tabs.push(closed(&format!("/{i}.pdf")));
+        }
+        assert_eq!(tabs.get_closed().len(), MAX_CLOSED_TABS);
+        assert_eq!(
+            tabs.get_closed()[0].path,
+            PathBuf::from(format!("/{}.pdf", MAX_CLOSED_TABS + 2))
+        );
+        assert_eq!(
+            tabs.get_closed()[MAX_CLOSED_TABS - 1].path,
+            PathBuf::from("/3.pdf")
+        );
+    }
+}
@@ -3,6 +3,10 @@ use crate::config::BindableMessage;
 pub enum CommonMenuItem {
     Button(BindableMessage),
     RecentFiles,
+    RecentlyClosed,
+    Workspaces,
+    /// The tools added with `Tool` in the config
+    Tools,
     Separator,
 }
 pub fn items() -> Vec<(String, Vec<CommonMenuItem>)> {
@@ -11,11 +15,26 @@ pub fn items() -> Vec<(String, Vec<CommonMenuItem>)> {
             String::from("File"),
             vec![
                 CommonMenuItem::Button(BindableMessage::OpenFileFinder),
+                CommonMenuItem::Button(BindableMessage::OpenUrl),
+                CommonMenuItem::Button(BindableMessage::RefreshDownload),
+                CommonMenuItem::Button(BindableMessage::SaveAs),
                 CommonMenuItem::Button(BindableMessage::PrintPdf),
+                CommonMenuItem::Button(BindableMessage::SharePage),
+                CommonMenuItem::Button(BindableMessage::ShowProperties),
+                CommonMenuItem::Button(BindableMessage::CopyCitation),
+                CommonMenuItem::Button(BindableMessage::OpenIdentifier),
+                CommonMenuItem::Button(BindableMessage::CopyPageReference),
+                CommonMenuItem::Button(BindableMessage::OpenPreferences),
                 CommonMenuItem::Separator,
                 CommonMenuItem::RecentFiles,
+                CommonMenuItem::RecentlyClosed,
                 CommonMenuItem::Separator,
+                CommonMenuItem::Button(BindableMessage::SaveWorkspace),
+                CommonMenuItem::Workspaces,
+                CommonMenuItem::Separator,
+                CommonMenuItem::Button(BindableMessage::OpenQuickSwitcher),
                 CommonMenuItem::Button(BindableMessage::CloseTab),
+                CommonMenuItem::Button(BindableMessage::ReopenClosedTab),
             ],
         ),
         (
@@ -24,17 +43,39 @@ pub fn items() -> Vec<(String, Vec<CommonMenuItem>)> {
                 CommonMenuItem::Button(BindableMessage::ToggleDarkModeUi),
                 CommonMenuItem::Button(BindableMessage::ToggleDarkModePdf),
                 CommonMenuItem::Button(BindableMessage::TogglePageBorders),
+                CommonMenuItem::Button(BindableMessage::TogglePageSlicing),
+                CommonMenuItem::Button(BindableMessage::TogglePageBoxes),
                 CommonMenuItem::Button(BindableMessage::ToggleSidebar),
+                CommonMenuItem::Button(BindableMessage::FocusNextPane),
+                CommonMenuItem::Button(BindableMessage::ToggleOverview),
+                CommonMenuItem::Button(BindableMessage::GoToDestination),
                 CommonMenuItem::Separator,
                 CommonMenuItem::Button(BindableMessage::ZoomIn),
                 CommonMenuItem::Button(BindableMessage::ZoomOut),
                 CommonMenuItem::Button(BindableMessage::ZoomHome),
                 CommonMenuItem::Button(BindableMessage::ZoomFit),
+                CommonMenuItem::Button(BindableMessage::ZoomFitColumn),
+                CommonMenuItem::Button(BindableMessage::ZoomToSelection),
+                CommonMenuItem::Button(BindableMessage::CycleZoomPreset),
                 CommonMenuItem::Separator,
                 CommonMenuItem::Button(BindableMessage::TogglePresentationMode),
+                CommonMenuItem::Button(BindableMessage::CyclePresentationPointer),
+                CommonMenuItem::Button(BindableMessage::ToggleDistractionFree),
                 CommonMenuItem::Button(BindableMessage::ToggleFullscreen),
             ],
         ),
+        (
+            String::from("Export"),
+            vec![
+                CommonMenuItem::Button(BindableMessage::SplitByChapter),
+                CommonMenuItem::Button(BindableMessage::ExportDarkCopy),
+                CommonMenuItem::Button(BindableMessage::ExportText),
+                CommonMenuItem::Separator,
+                CommonMenuItem::Button(BindableMessage::CopyTableAsTsv),
+                CommonMenuItem::Button(BindableMessage::CopyTableAsCsv),
+                CommonMenuItem::Button(BindableMessage::CopyFormula),
+            ],
+        ),
         (
             String::from("Layout"),
             vec![
@@ -44,5 +85,13 @@ pub fn items() -> Vec<(String, Vec<CommonMenuItem>)> {
                 CommonMenuItem::Button(BindableMessage::PresentationLayout),
             ],
         ),
+        (
+            String::from("Tools"),
+            vec![
+                CommonMenuItem::Tools,
+                CommonMenuItem::Separator,
+                CommonMenuItem::Button(BindableMessage::ShowToolLog),
+            ],
+        ),
     ]
 }
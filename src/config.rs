@@ -1,5 +1,5 @@
 use anyhow::{Result, anyhow};
-use std::{fs, path::PathBuf, str::FromStr, fmt};
+use std::{fs, path::PathBuf, str::FromStr, fmt, time::Duration};
 
 use colored::Colorize;
 use keybinds2::{KeyInput, KeySeq, Keybind, Keybinds};
@@ -8,10 +8,21 @@ use strum::{Display, EnumString};
 use crate::{
     app::AppMessage,
     geometry::Vector,
-    pdf::{PdfMessage, SearchMethod, page_layout::PageLayout},
+    hooks::HookEvent,
+    paths,
+    pdf::{
+        PdfMessage, SearchMethod, ZoomPreset, page_layout::PageLayout, text_layout::TableFormat,
+    },
 };
 
 pub const MOVE_STEP: f32 = 40.0;
+/// Fraction of the window the sidebar grows or shrinks by per key press
+pub const SIDEBAR_RESIZE_STEP: f32 = 0.05;
+/// The sidebar or the document can't be made smaller than this fraction of the window, a sidebar
+/// collapsed to nothing can't be dragged open again
+pub const SIDEBAR_RATIO_RANGE: std::ops::RangeInclusive<f32> = 0.1..=0.9;
+/// `MailHandler` value that copies the address of email links instead of opening them
+pub const MAIL_HANDLER_COPY: &str = "Copy";
 
 #[derive(Debug, Clone)]
 pub struct ConfigError {
@@ -157,6 +168,48 @@ impl FromStr for MouseInput {
 // That does inherently mean that each menu button needs to be able to be key-bound
 // If that isn't desirable, each menu button could have an Option<BindableMessage> instead
 
+/// Which side of the pdf the sidebar is docked to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Default)]
+pub enum SidebarSide {
+    Left,
+    #[default]
+    Right,
+}
+
+impl SidebarSide {
+    /// Converts the fraction taken up by the sidebar into the ratio of the pane grid split, which
+    /// is the fraction taken up by the left pane. Converts the other way as well.
+    pub fn split_ratio(self, ratio: f32) -> f32 {
+        match self {
+            SidebarSide::Left => ratio,
+            SidebarSide::Right => 1.0 - ratio,
+        }
+    }
+}
+
+/// How the dark mode of pdfs recolors pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Default)]
+pub enum PdfDarkModeStyle {
+    /// Inverts the brightness of the rendered page, photos included
+    #[default]
+    Invert,
+    /// Draws text and vector graphics in the dark mode colors and keeps images as they are. Scanned
+    /// pages are still inverted
+    Recolor,
+}
+
+/// What happens once the last open tab is closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Default)]
+pub enum LastTabClosed {
+    /// Quit miro
+    Exit,
+    /// Keep the window open without a document, like starting miro without a file
+    #[default]
+    StartScreen,
+    /// Keep the window open and list the recently opened files
+    RecentFiles,
+}
+
 #[derive(Debug, EnumString, Display, Clone, Copy, PartialEq, Eq)]
 pub enum BindableMessage {
     MoveUp,
@@ -165,31 +218,100 @@ pub enum BindableMessage {
     MoveRight,
     NextPage,
     PreviousPage,
+    FirstPage,
+    LastPage,
+    NextBlock,
+    PreviousBlock,
     PageUp,
     PageDown,
     HalfPageUp,
     HalfPageDown,
     ZoomHome,
     ZoomFit,
+    ZoomFitColumn,
+    /// Zoom in until the region selected with the mouse fills the screen
+    ZoomToSelection,
+    /// Step through the zoom levels of `ZoomPresets`
+    CycleZoomPreset,
     ZoomIn,
     ZoomOut,
     NextTab,
     PreviousTab,
+    NextRecentTab,
+    PreviousRecentTab,
+    /// Switch to an open tab or recent file by typing part of its name
+    OpenQuickSwitcher,
     ToggleDarkModePdf,
     ToggleDarkModeUi,
     TogglePageBorders,
+    /// Outline the media, crop, bleed, trim and art boxes of the pages with labels
+    TogglePageBoxes,
+    /// Read pages too large for the screen one screen-sized segment at a time with NextPage
+    TogglePageSlicing,
     ToggleSidebar,
+    GrowSidebar,
+    ShrinkSidebar,
+    FocusNextPane,
+    /// Show a grid of every page, clicking one jumps to it
+    ToggleOverview,
+    /// Pick one of the named destinations of the document to jump to
+    GoToDestination,
     ToggleLinkHitboxes,
+    ToggleWordHints,
+    ToggleCaretMode,
+    CopySelection,
+    /// Copy all text of the current page
+    SelectAllPage,
+    /// Copy the table under the mouse selection with tabs between its columns
+    CopyTableAsTsv,
+    /// Copy the table under the mouse selection as comma separated values
+    CopyTableAsCsv,
+    /// Copy the formula under the mouse selection as LaTeX, through the `FormulaCommand`
+    CopyFormula,
+    LookupSelection,
+    HighlightOccurrences,
+    TranslateSelection,
+    /// Show what the tools of the Tools menu printed
+    ShowToolLog,
     OpenFileFinder,
+    OpenUrl,
+    RefreshDownload,
+    SaveWorkspace,
+    SaveAs,
+    /// Show the metadata of the active document and check it for problems
+    ShowProperties,
+    /// Copy the DOI or arXiv id printed on the first pages along with the title
+    CopyCitation,
+    /// Open the DOI or arXiv id printed on the first pages on doi.org or arxiv.org
+    OpenIdentifier,
+    /// Copy "file.pdf — page 42 (Section)" for notes about the current page
+    CopyPageReference,
+    /// Write one pdf per top-level outline entry into a directory
+    SplitByChapter,
+    /// Save a copy of the document rendered in the dark mode colors for reading elsewhere
+    ExportDarkCopy,
+    /// Edit the settings and key bindings of the config file
+    OpenPreferences,
+    /// Save the text of every page to a text file
+    ExportText,
     #[strum(serialize = "CloseActiveTab", serialize = "CloseTab")]
     CloseTab,
+    ReopenClosedTab,
     PrintPdf,
+    SharePage,
     Exit,
     JumpBack,
     JumpForward,
+    ShowJumplist,
     ToggleFullscreen,
     TogglePresentationMode,
+    /// Cycle the mouse in presentation mode between the cursor, a laser pointer and a spotlight
+    CyclePresentationPointer,
+    /// Hide the menu bar and tabs until the mouse reaches the top of the window
+    ToggleDistractionFree,
     OpenSearch,
+    /// Search through every open tab at once
+    OpenGlobalSearch,
     CloseSearch,
     ToggleSearchMethod,
     NextSearchResult,
@@ -204,17 +326,46 @@ impl BindableMessage {
     pub fn default_menu_label(&self) -> Option<&'static str> {
         match self {
             BindableMessage::OpenFileFinder => Some("Open File"),
+            BindableMessage::OpenUrl => Some("Open URL…"),
+            BindableMessage::RefreshDownload => Some("Download Again"),
+            BindableMessage::SaveWorkspace => Some("Save Workspace…"),
+            BindableMessage::SaveAs => Some("Save As"),
+            BindableMessage::ShowProperties => Some("Properties…"),
+            BindableMessage::CopyCitation => Some("Copy Citation"),
+            BindableMessage::OpenIdentifier => Some("Open on doi.org or arXiv"),
+            BindableMessage::CopyPageReference => Some("Copy Page Reference"),
+            BindableMessage::SplitByChapter => Some("Split by Chapter…"),
+            BindableMessage::ExportDarkCopy => Some("Dark Copy…"),
+            BindableMessage::OpenPreferences => Some("Preferences…"),
+            BindableMessage::ExportText => Some("Text…"),
+            BindableMessage::CopyTableAsTsv => Some("Copy Table as TSV"),
+            BindableMessage::CopyTableAsCsv => Some("Copy Table as CSV"),
+            BindableMessage::CopyFormula => Some("Copy Formula as LaTeX"),
+            BindableMessage::ShowToolLog => Some("Show Tool Output"),
             BindableMessage::PrintPdf => Some("Print"),
+            BindableMessage::SharePage => Some("Share Page as PNG"),
             BindableMessage::CloseTab => Some("Close"),
+            BindableMessage::ReopenClosedTab => Some("Reopen Closed Tab"),
+            BindableMessage::OpenQuickSwitcher => Some("Switch Document…"),
             BindableMessage::ToggleDarkModeUi => Some("Toggle Interface Dark Mode"),
             BindableMessage::ToggleDarkModePdf => Some("Toggle PDF Dark Mode"),
             BindableMessage::TogglePageBorders => Some("Toggle Page Borders"),
+            BindableMessage::TogglePageSlicing => Some("Toggle Page Slicing"),
+            BindableMessage::TogglePageBoxes => Some("Toggle Page Boxes"),
+            BindableMessage::ToggleOverview => Some("Page Overview"),
+            BindableMessage::GoToDestination => Some("Go to Destination…"),
             BindableMessage::ZoomIn => Some("Zoom In"),
             BindableMessage::ZoomOut => Some("Zoom Out"),
             BindableMessage::ZoomHome => Some("Zoom 100%"),
             BindableMessage::ZoomFit => Some("Fit To Screen"),
+            BindableMessage::ZoomFitColumn => Some("Fit Column"),
+            BindableMessage::ZoomToSelection => Some("Zoom to Selection"),
+            BindableMessage::CycleZoomPreset => Some("Next Zoom Preset"),
             BindableMessage::ToggleSidebar => Some("Toggle Sidebar"),
+            BindableMessage::FocusNextPane => Some("Focus Next Pane"),
             BindableMessage::TogglePresentationMode => Some("Presentation Mode"),
+            BindableMessage::CyclePresentationPointer => Some("Laser Pointer / Spotlight"),
+            BindableMessage::ToggleDistractionFree => Some("Distraction-Free Mode"),
             BindableMessage::ToggleFullscreen => Some("Toggle Fullscreen"),
             BindableMessage::SinglePageLayout => Some("Single Page"),
             BindableMessage::DoublePageLayout => Some("Double Page"),
@@ -242,28 +393,84 @@ impl From<BindableMessage> for AppMessage {
             }
             BindableMessage::NextPage => AppMessage::PdfMessage(PdfMessage::NextPage),
             BindableMessage::PreviousPage => AppMessage::PdfMessage(PdfMessage::PreviousPage),
+            BindableMessage::FirstPage => AppMessage::PdfMessage(PdfMessage::FirstPage),
+            BindableMessage::LastPage => AppMessage::PdfMessage(PdfMessage::LastPage),
+            BindableMessage::NextBlock => AppMessage::PdfMessage(PdfMessage::NextBlock),
+            BindableMessage::PreviousBlock => AppMessage::PdfMessage(PdfMessage::PreviousBlock),
             BindableMessage::ZoomHome => AppMessage::PdfMessage(PdfMessage::ZoomHome),
             BindableMessage::ZoomFit => AppMessage::PdfMessage(PdfMessage::ZoomFit),
+            BindableMessage::ZoomFitColumn => AppMessage::PdfMessage(PdfMessage::ZoomFitColumn),
+            BindableMessage::ZoomToSelection => AppMessage::PdfMessage(PdfMessage::ZoomToSelection),
+            BindableMessage::CycleZoomPreset => AppMessage::PdfMessage(PdfMessage::CycleZoomPreset),
             BindableMessage::ZoomIn => AppMessage::PdfMessage(PdfMessage::ZoomIn),
             BindableMessage::ZoomOut => AppMessage::PdfMessage(PdfMessage::ZoomOut),
             BindableMessage::NextTab => AppMessage::NextTab,
             BindableMessage::PreviousTab => AppMessage::PreviousTab,
+            BindableMessage::NextRecentTab => AppMessage::NextRecentTab,
+            BindableMessage::PreviousRecentTab => AppMessage::PreviousRecentTab,
+            BindableMessage::OpenQuickSwitcher => AppMessage::OpenQuickSwitcher,
             BindableMessage::ToggleDarkModePdf => AppMessage::ToggleDarkModePdf,
             BindableMessage::ToggleDarkModeUi => AppMessage::ToggleDarkModeUi,
             BindableMessage::TogglePageBorders => AppMessage::TogglePageBorders,
+            BindableMessage::TogglePageBoxes => AppMessage::PdfMessage(PdfMessage::TogglePageBoxes),
+            BindableMessage::TogglePageSlicing => {
+                AppMessage::PdfMessage(PdfMessage::TogglePageSlicing)
+            }
             BindableMessage::ToggleSidebar => AppMessage::ToggleSidebar,
+            BindableMessage::GrowSidebar => AppMessage::ResizeSidebar(SIDEBAR_RESIZE_STEP),
+            BindableMessage::ShrinkSidebar => AppMessage::ResizeSidebar(-SIDEBAR_RESIZE_STEP),
+            BindableMessage::FocusNextPane => AppMessage::FocusNextPane,
+            BindableMessage::ToggleOverview => AppMessage::PdfMessage(PdfMessage::ToggleOverview),
+            BindableMessage::GoToDestination => AppMessage::GoToDestinationPrompt,
             BindableMessage::ToggleLinkHitboxes => {
                 AppMessage::PdfMessage(PdfMessage::ToggleLinkHitboxes)
             }
+            BindableMessage::ToggleWordHints => AppMessage::PdfMessage(PdfMessage::ToggleWordHints),
+            BindableMessage::ToggleCaretMode => AppMessage::PdfMessage(PdfMessage::ToggleCaretMode),
+            BindableMessage::CopySelection => AppMessage::PdfMessage(PdfMessage::CopySelection),
+            BindableMessage::SelectAllPage => AppMessage::PdfMessage(PdfMessage::SelectAllPage),
+            BindableMessage::CopyTableAsTsv => {
+                AppMessage::PdfMessage(PdfMessage::CopyTable(TableFormat::Tsv))
+            }
+            BindableMessage::CopyTableAsCsv => {
+                AppMessage::PdfMessage(PdfMessage::CopyTable(TableFormat::Csv))
+            }
+            BindableMessage::CopyFormula => AppMessage::PdfMessage(PdfMessage::CopyFormula),
+            BindableMessage::LookupSelection => AppMessage::PdfMessage(PdfMessage::LookupSelection),
+            BindableMessage::HighlightOccurrences => {
+                AppMessage::PdfMessage(PdfMessage::HighlightOccurrences)
+            }
+            BindableMessage::TranslateSelection => AppMessage::TranslateSelection,
+            BindableMessage::ShowToolLog => AppMessage::ShowToolLog,
             BindableMessage::OpenFileFinder => AppMessage::OpenNewFileFinder,
+            BindableMessage::OpenUrl => AppMessage::OpenUrlPrompt,
+            BindableMessage::RefreshDownload => AppMessage::RefreshDownload,
+            BindableMessage::SaveWorkspace => AppMessage::SaveWorkspacePrompt,
+            BindableMessage::SaveAs => AppMessage::OpenSaveAsDialog,
+            BindableMessage::ShowProperties => AppMessage::ShowProperties,
+            BindableMessage::CopyCitation => AppMessage::PdfMessage(PdfMessage::CopyCitation),
+            BindableMessage::OpenIdentifier => AppMessage::PdfMessage(PdfMessage::OpenIdentifier),
+            BindableMessage::CopyPageReference => {
+                AppMessage::PdfMessage(PdfMessage::CopyPageReference)
+            }
+            BindableMessage::SplitByChapter => AppMessage::SplitByChapter,
+            BindableMessage::ExportDarkCopy => AppMessage::ExportDarkCopy,
+            BindableMessage::OpenPreferences => AppMessage::OpenPreferences,
+            BindableMessage::ExportText => AppMessage::ExportText,
             BindableMessage::CloseTab => AppMessage::CloseActiveTab,
+            BindableMessage::ReopenClosedTab => AppMessage::ReopenClosedTab(0),
             BindableMessage::PrintPdf => AppMessage::PdfMessage(PdfMessage::PrintPdf),
+            BindableMessage::SharePage => AppMessage::PdfMessage(PdfMessage::SharePage),
             BindableMessage::Exit => AppMessage::Exit,
             BindableMessage::JumpBack => AppMessage::JumpBack,
             BindableMessage::JumpForward => AppMessage::JumpForward,
+            BindableMessage::ShowJumplist => AppMessage::ToggleJumplist,
             BindableMessage::ToggleFullscreen => AppMessage::ToggleFullscreen,
             BindableMessage::TogglePresentationMode => AppMessage::TogglePresentationMode,
+            BindableMessage::CyclePresentationPointer => AppMessage::CyclePresentationPointer,
+            BindableMessage::ToggleDistractionFree => AppMessage::ToggleDistractionFree,
             BindableMessage::OpenSearch => AppMessage::OpenSearch,
+            BindableMessage::OpenGlobalSearch => AppMessage::OpenGlobalSearch,
             BindableMessage::CloseSearch => AppMessage::CloseSearch,
             BindableMessage::ToggleSearchMethod => AppMessage::ToggleSearchMethod,
             BindableMessage::NextSearchResult => {
@@ -300,13 +507,68 @@ pub struct Config {
     pub rpc_allow_lan: bool,
     pub rpc_port: u32,
     pub trackpad_sensitivity: f32,
+    /// Multiplier on zooming with a trackpad pinch, which doesn't affect panning
+    pub pinch_sensitivity: f32,
     pub page_borders: bool,
+    /// Color of the line around pages when page borders are shown, in light and dark mode
+    pub page_border_color: iced::Color,
+    pub page_border_color_dark: iced::Color,
+    /// Width of the line around pages in pixels, no line is drawn when zero
+    pub page_border_width: f32,
+    /// Draw a drop shadow behind pages when page borders are shown
+    pub page_shadow: bool,
+    /// Show scrollbars while the view moves
+    pub scrollbars: bool,
+    /// The zoom levels `CycleZoomPreset` steps through
+    pub zoom_presets: Vec<ZoomPreset>,
     pub dark_mode: bool,
     pub invert_pdf: bool,
+    pub pdf_dark_mode_style: PdfDarkModeStyle,
+    /// Follow the light/dark preference of the OS instead of `dark_mode`, set by `Theme Auto`
+    pub follow_system_theme: bool,
+    /// Switch the pdf dark mode along with the OS preference as well while following it
+    pub follow_system_theme_pdf: bool,
     pub open_sidebar: bool,
+    /// Fraction of the window taken up by the sidebar when it is first opened
+    pub sidebar_ratio: f32,
+    pub sidebar_side: SidebarSide,
+    /// Show tabs with the same color label next to each other
+    pub group_tabs_by_color: bool,
     pub default_search_method: SearchMethod,
     pub open_fullscreen_default: bool,
     pub open_presentation_default: bool,
+    pub last_tab_closed: LastTabClosed,
+    pub flash_on_change: bool,
+    /// Show a toast along with the flash at the edge when paging past the first or last page
+    pub end_of_document_toast: bool,
+    /// Highlight every occurrence of the selected text as soon as something is selected
+    pub highlight_occurrences: bool,
+    /// Join wrapped lines and undo hyphenation and typographic characters in copied text
+    pub copy_cleanup: bool,
+    /// Fit the current page to the window whenever the window is resized
+    pub autofit: bool,
+    /// How long the window size has to stay the same before autofit kicks in
+    pub autofit_debounce: Duration,
+    /// The characters link and word hints are made of, in the order they are handed out
+    pub hint_chars: Vec<char>,
+    /// Url or shell command the selected text is looked up with, `%s` is replaced by the text
+    pub lookup_command: String,
+    /// Url or shell command answering with a translation of `%s`, translating is disabled when
+    /// this is empty
+    pub translate_command: String,
+    /// Shell command turning an image of a formula into LaTeX for `CopyFormula`, empty if unset
+    pub formula_command: String,
+    /// Shell command web links are opened with, `%s` is replaced by the url. Links go to the
+    /// default browser when this is empty
+    pub url_handler: String,
+    /// Shell command `mailto:` links are opened with, or [MAIL_HANDLER_COPY] to copy the address.
+    /// They go to the default mail client when this is empty
+    pub mail_handler: String,
+    /// Shell commands run when an event happens, see [HookEvent]
+    pub hooks: Vec<(HookEvent, String)>,
+    /// The entries of the Tools menu, a name and a shell command, added with
+    /// `Tool <name> <command>`
+    pub tools: Vec<(String, String)>,
 }
 
 impl Config {
@@ -418,54 +680,36 @@ impl Config {
                     );
                 }
 
-                let setting = &parts[1];
-                let value = &parts[2];
+                let setting = SETTINGS
+                    .iter()
+                    .find(|setting| setting.name == parts[1])
+                    .ok_or_else(|| format!("Unknown setting: {}", parts[1]))?;
+                (setting.apply)(config, &parts[2])?;
+            }
+            Command::On => {
+                if parts.len() != 3 {
+                    return Err(
+                        "On command requires exactly 2 arguments: <event> <command>".to_string()
+                    );
+                }
 
-                match setting.as_str() {
-                    "DarkModePdf" => {
-                        config.invert_pdf = Self::parse_boolean("DarkModePdf", value)?;
-                    }
-                    "DarkModeUi" => {
-                        config.dark_mode = Self::parse_boolean("DarkModeUi", value)?;
-                    }
-                    "OpenSidebar" => {
-                        config.open_sidebar = Self::parse_boolean("OpenSidebar", value)?;
-                    }
-                    "PageBorders" => {
-                        config.page_borders = Self::parse_boolean("PageBorders", value)?;
-                    }
-                    "Rpc" => {
-                        config.rpc_enabled = Self::parse_boolean("Rpc", value)?;
-                    }
-                    "RpcPort" => {
-                        config.rpc_port = value.parse::<u32>().map_err(|_| {
-                            format!("Invalid port number: '{value}'. Must be a valid integer")
-                        })?;
-                    }
-                    "RpcAllowLan" => {
-                        config.rpc_allow_lan = Self::parse_boolean("RpcAllowLan", value)?;
-                    }
-                    "TrackpadSensitivity" => {
-                        config.trackpad_sensitivity = value.parse::<f32>().map_err(|_| {
-                            format!("Invalid float value for TrackpadSensitivity: '{value}'. Must be a valid number")
-                        })?;
-                    }
-                    "DefaultSearchMethod" => {
-                        config.default_search_method =
-                            SearchMethod::from_str(value).map_err(|_| {
-                                format!("Unknown search method: '{value}'. Use PlainText or Regex")
-                            })?;
-                    }
-                    "OpenFullscreen" => {
-                        config.open_fullscreen_default =
-                            Self::parse_boolean("OpenFullscreen", value)?;
-                    }
-                    "OpenPresentation" => {
-                        config.open_presentation_default =
-                            Self::parse_boolean("OpenPresentation", value)?;
-                    }
-                    _ => return Err(format!("Unknown setting: {setting}")),
+                let event_str = &parts[1];
+                let event = HookEvent::from_str(event_str)
+                    .map_err(|_| format!("Unknown event: {event_str}"))?;
+
+                config.hooks.push((event, parts[2].clone()));
+            }
+            Command::Tool => {
+                if parts.len() != 3 {
+                    return Err(
+                        "Tool command requires exactly 2 arguments: <name> <command>".to_string(),
+                    );
+                }
+                if parts[1].trim().is_empty() {
+                    return Err("Tools need a name to be shown in the menu".to_string());
                 }
+
+                config.tools.push((parts[1].clone(), parts[2].clone()));
             }
         }
 
@@ -482,21 +726,31 @@ impl Config {
         }
     }
 
-    fn parse_line_parts(line: &str) -> Result<Vec<String>, String> {
+    fn parse_color(value_name: &'static str, value: &str) -> Result<iced::Color, String> {
+        iced::Color::from_str(value).map_err(|_| {
+            format!("Invalid color for {value_name}: '{value}'. Use hex notation like #bbb8bb")
+        })
+    }
+
+    pub(crate) fn parse_line_parts(line: &str) -> Result<Vec<String>, String> {
         let mut parts = Vec::new();
         let mut current_part = String::new();
         let mut in_quotes = false;
+        // Set by quotes so `""` is an empty part instead of nothing, used to unset text settings
+        let mut quoted = false;
         let chars = line.chars();
 
         for ch in chars {
             match ch {
                 '"' => {
                     in_quotes = !in_quotes;
+                    quoted = true;
                 }
                 ' ' | '\t' if !in_quotes => {
-                    if !current_part.is_empty() {
+                    if !current_part.is_empty() || quoted {
                         parts.push(current_part.clone());
                         current_part.clear();
+                        quoted = false;
                     }
                 }
                 _ => {
@@ -509,7 +763,7 @@ impl Config {
             return Err("Unterminated quoted string".to_string());
         }
 
-        if !current_part.is_empty() {
+        if !current_part.is_empty() || quoted {
             parts.push(current_part);
         }
 
@@ -517,15 +771,14 @@ impl Config {
     }
 
     pub fn system_config_path() -> Result<PathBuf> {
-        Ok(home::home_dir()
-            .ok_or(anyhow!("No home directory could be determined"))?
-            .join("./.config/miro-pdf/miro.conf"))
+        paths::config_file()
     }
 
     fn merge_configs(mut base: Config, overrider: &Config) -> Config {
         for binding in overrider.keyboard.as_slice() {
             base.keyboard.push(binding.clone());
         }
+        base.keyboard.set_timeout(overrider.keyboard.timeout());
         for binding in &overrider.mouse {
             println!("{binding:?}");
             base.mouse.push(*binding);
@@ -534,11 +787,39 @@ impl Config {
         base.rpc_port = overrider.rpc_port;
         base.rpc_allow_lan = overrider.rpc_allow_lan;
         base.trackpad_sensitivity = overrider.trackpad_sensitivity;
+        base.pinch_sensitivity = overrider.pinch_sensitivity;
         base.page_borders = overrider.page_borders;
+        base.page_border_color = overrider.page_border_color;
+        base.page_border_color_dark = overrider.page_border_color_dark;
+        base.page_border_width = overrider.page_border_width;
+        base.page_shadow = overrider.page_shadow;
+        base.scrollbars = overrider.scrollbars;
+        base.zoom_presets = overrider.zoom_presets.clone();
         base.dark_mode = overrider.dark_mode;
         base.invert_pdf = overrider.invert_pdf;
+        base.pdf_dark_mode_style = overrider.pdf_dark_mode_style;
+        base.follow_system_theme = overrider.follow_system_theme;
+        base.follow_system_theme_pdf = overrider.follow_system_theme_pdf;
         base.open_sidebar = overrider.open_sidebar;
+        base.sidebar_ratio = overrider.sidebar_ratio;
+        base.sidebar_side = overrider.sidebar_side;
+        base.group_tabs_by_color = overrider.group_tabs_by_color;
         base.default_search_method = overrider.default_search_method;
+        base.last_tab_closed = overrider.last_tab_closed;
+        base.flash_on_change = overrider.flash_on_change;
+        base.end_of_document_toast = overrider.end_of_document_toast;
+        base.highlight_occurrences = overrider.highlight_occurrences;
+        base.copy_cleanup = overrider.copy_cleanup;
+        base.autofit = overrider.autofit;
+        base.autofit_debounce = overrider.autofit_debounce;
+        base.hint_chars = overrider.hint_chars.clone();
+        base.lookup_command = overrider.lookup_command.clone();
+        base.translate_command = overrider.translate_command.clone();
+        base.formula_command = overrider.formula_command.clone();
+        base.url_handler = overrider.url_handler.clone();
+        base.mail_handler = overrider.mail_handler.clone();
+        base.hooks.extend(overrider.hooks.iter().cloned());
+        base.tools.extend(overrider.tools.iter().cloned());
         base
     }
 }
@@ -556,6 +837,16 @@ impl Default for Config {
                     KeyInput::from_str("K").unwrap(),
                     BindableMessage::PreviousPage,
                 ),
+                Keybind::new(KeySeq::from_str("g g").unwrap(), BindableMessage::FirstPage),
+                Keybind::new(KeyInput::from_str("G").unwrap(), BindableMessage::LastPage),
+                Keybind::new(
+                    KeyInput::from_str("Space").unwrap(),
+                    BindableMessage::NextBlock,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("Shift+Space").unwrap(),
+                    BindableMessage::PreviousBlock,
+                ),
                 Keybind::new(
                     KeyInput::from_str("H").unwrap(),
                     BindableMessage::PreviousTab,
@@ -608,8 +899,16 @@ impl Default for Config {
                     KeyInput::from_str("Alt+Right").unwrap(),
                     BindableMessage::JumpForward,
                 ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+j").unwrap(),
+                    BindableMessage::ShowJumplist,
+                ),
                 Keybind::new(KeyInput::from_str("0").unwrap(), BindableMessage::ZoomHome),
                 Keybind::new(KeyInput::from_str("_").unwrap(), BindableMessage::ZoomFit),
+                Keybind::new(
+                    KeyInput::from_str("z").unwrap(),
+                    BindableMessage::CycleZoomPreset,
+                ),
                 Keybind::new(KeyInput::from_str("-").unwrap(), BindableMessage::ZoomOut),
                 Keybind::new(KeyInput::from_str("Plus").unwrap(), BindableMessage::ZoomIn),
                 // Standard zoom controls
@@ -637,10 +936,53 @@ impl Default for Config {
                     KeyInput::from_str("Ctrl+B").unwrap(),
                     BindableMessage::ToggleSidebar,
                 ),
+                Keybind::new(
+                    KeyInput::from_str("Alt+Plus").unwrap(),
+                    BindableMessage::GrowSidebar,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("Alt+-").unwrap(),
+                    BindableMessage::ShrinkSidebar,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("F6").unwrap(),
+                    BindableMessage::FocusNextPane,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+g").unwrap(),
+                    BindableMessage::ToggleOverview,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+G").unwrap(),
+                    BindableMessage::GoToDestination,
+                ),
                 Keybind::new(
                     KeyInput::from_str("Ctrl+l").unwrap(),
                     BindableMessage::ToggleLinkHitboxes,
                 ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+h").unwrap(),
+                    BindableMessage::ToggleWordHints,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("F7").unwrap(),
+                    BindableMessage::ToggleCaretMode,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+c").unwrap(),
+                    BindableMessage::CopySelection,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+a").unwrap(),
+                    BindableMessage::SelectAllPage,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+D").unwrap(),
+                    BindableMessage::LookupSelection,
+                ),
+                Keybind::new('*', BindableMessage::HighlightOccurrences),
+                Keybind::new('=', BindableMessage::ZoomToSelection),
+                Keybind::new('y', BindableMessage::CopyPageReference),
                 Keybind::new(
                     KeyInput::from_str("Ctrl+k").unwrap(),
                     BindableMessage::TogglePageBorders,
@@ -653,6 +995,14 @@ impl Default for Config {
                     KeyInput::from_str("F10").unwrap(),
                     BindableMessage::TogglePresentationMode,
                 ),
+                Keybind::new(
+                    KeyInput::from_str("F8").unwrap(),
+                    BindableMessage::CyclePresentationPointer,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("F9").unwrap(),
+                    BindableMessage::ToggleDistractionFree,
+                ),
                 Keybind::new(
                     KeyInput::from_str("F1").unwrap(),
                     BindableMessage::SinglePageLayout,
@@ -673,21 +1023,41 @@ impl Default for Config {
                     KeyInput::from_str("Ctrl+o").unwrap(),
                     BindableMessage::OpenFileFinder,
                 ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+O").unwrap(),
+                    BindableMessage::OpenUrl,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+S").unwrap(),
+                    BindableMessage::SaveAs,
+                ),
                 Keybind::new(
                     KeyInput::from_str("Ctrl+p").unwrap(),
                     BindableMessage::PrintPdf,
                 ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+e").unwrap(),
+                    BindableMessage::SharePage,
+                ),
                 Keybind::new(KeySeq::from_str("Z Z").unwrap(), BindableMessage::CloseTab),
                 Keybind::new(KeySeq::from_str("q").unwrap(), BindableMessage::Exit),
                 Keybind::new(
                     KeyInput::from_str("Ctrl+w").unwrap(),
                     BindableMessage::CloseTab,
                 ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+T").unwrap(),
+                    BindableMessage::ReopenClosedTab,
+                ),
                 // Search
                 Keybind::new(
                     KeyInput::from_str("/").unwrap(),
                     BindableMessage::OpenSearch,
                 ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+F").unwrap(),
+                    BindableMessage::OpenGlobalSearch,
+                ),
                 Keybind::new(
                     KeyInput::from_str("Escape").unwrap(),
                     BindableMessage::CloseSearch,
@@ -705,6 +1075,18 @@ impl Default for Config {
                     KeyInput::from_str("Shift+Tab").unwrap(),
                     BindableMessage::PreviousTab,
                 ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+Tab").unwrap(),
+                    BindableMessage::NextRecentTab,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+Shift+Tab").unwrap(),
+                    BindableMessage::PreviousRecentTab,
+                ),
+                Keybind::new(
+                    KeyInput::from_str("Ctrl+K").unwrap(),
+                    BindableMessage::OpenQuickSwitcher,
+                ),
             ]),
             mouse: vec![
                 (
@@ -826,13 +1208,49 @@ impl Default for Config {
             rpc_port: 7890,
             rpc_allow_lan: false,
             trackpad_sensitivity: 1.0,
+            pinch_sensitivity: 1.0,
             page_borders: true,
+            page_border_color: iced::Color::from_rgb8(187, 184, 187),
+            page_border_color_dark: iced::Color::from_rgb8(51, 56, 71),
+            page_border_width: 0.0,
+            page_shadow: false,
+            scrollbars: true,
+            zoom_presets: vec![
+                ZoomPreset::Scale(0.5),
+                ZoomPreset::Scale(1.0),
+                ZoomPreset::Scale(1.5),
+                ZoomPreset::Scale(2.0),
+                ZoomPreset::Fit,
+                ZoomPreset::FitWidth,
+            ],
             dark_mode: true,
             invert_pdf: false,
+            pdf_dark_mode_style: PdfDarkModeStyle::Invert,
+            follow_system_theme: false,
+            follow_system_theme_pdf: false,
             open_sidebar: false,
+            sidebar_ratio: 0.3,
+            sidebar_side: SidebarSide::Right,
+            group_tabs_by_color: false,
             default_search_method: SearchMethod::PlainText,
             open_fullscreen_default: false,
             open_presentation_default: false,
+            last_tab_closed: LastTabClosed::StartScreen,
+            flash_on_change: false,
+            end_of_document_toast: true,
+            highlight_occurrences: false,
+            copy_cleanup: false,
+            autofit: false,
+            autofit_debounce: Duration::from_millis(100),
+            // Easily distinguishable characters, without confusing ones like 'i', 'l' and 'o'
+            hint_chars: "abcdefghjkmnpqrstuvwxyz".chars().collect(),
+            lookup_command: "https://en.wiktionary.org/wiki/%s".to_string(),
+            translate_command: String::new(),
+            formula_command: String::new(),
+            url_handler: String::new(),
+            mail_handler: String::new(),
+            hooks: vec![],
+            tools: vec![],
         }
     }
 }
@@ -856,11 +1274,521 @@ enum Command {
     Bind,
     MouseBind,
     Set,
+    On,
+    Tool,
+}
+
+/// A setting changed with `Set <name> <value>`.
+pub struct Setting {
+    pub name: &'static str,
+    /// The values it takes, listed by `miro config --list`
+    pub kind: &'static str,
+    /// Parses `value` into the config, or explains what is wrong with it
+    apply: fn(&mut Config, &str) -> Result<(), String>,
+    /// The value as it would be written in the config file
+    show: fn(&Config) -> String,
+}
+
+impl Setting {
+    pub fn value(&self, config: &Config) -> String {
+        (self.show)(config)
+    }
+}
+
+fn show_bool(value: bool) -> String {
+    if value { "True" } else { "False" }.to_string()
+}
+
+fn show_color(color: iced::Color) -> String {
+    let [r, g, b, a] = color.into_rgba8();
+    if a == 255 {
+        format!("#{r:02x}{g:02x}{b:02x}")
+    } else {
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+}
+
+/// Quotes text the way the config file needs it when it contains spaces or is empty.
+fn show_text(text: &str) -> String {
+    if text.is_empty() || text.contains(char::is_whitespace) {
+        format!("\"{text}\"")
+    } else {
+        text.to_string()
+    }
+}
+
+fn parse_float(value_name: &'static str, value: &str) -> Result<f32, String> {
+    // "NaN" and "inf" parse as floats too, but no setting has a use for them
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| {
+            format!("Invalid float value for {value_name}: '{value}'. Must be a valid number")
+        })
+}
+
+/// Every setting the config file accepts. `Set` lines are parsed through this table as well, so a
+/// new setting only has to be added here.
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        name: "DarkModePdf",
+        kind: "boolean",
+        apply: |config, value| {
+            config.invert_pdf = Config::parse_boolean("DarkModePdf", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.invert_pdf),
+    },
+    Setting {
+        name: "DarkModePdfStyle",
+        kind: "Invert or Recolor",
+        apply: |config, value| {
+            config.pdf_dark_mode_style = PdfDarkModeStyle::from_str(value).map_err(|_| {
+                format!("Unknown pdf dark mode style: '{value}'. Use Invert or Recolor")
+            })?;
+            Ok(())
+        },
+        show: |config| format!("{:?}", config.pdf_dark_mode_style),
+    },
+    Setting {
+        name: "DarkModeUi",
+        kind: "boolean",
+        apply: |config, value| {
+            config.dark_mode = Config::parse_boolean("DarkModeUi", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.dark_mode),
+    },
+    Setting {
+        name: "Theme",
+        kind: "Light, Dark or Auto",
+        apply: |config, value| {
+            match value {
+                "Light" | "Dark" => {
+                    config.dark_mode = value == "Dark";
+                    config.follow_system_theme = false;
+                }
+                "Auto" => config.follow_system_theme = true,
+                _ => {
+                    return Err(format!(
+                        "Invalid value for Theme: '{value}'. Use Light, Dark or Auto"
+                    ));
+                }
+            }
+            Ok(())
+        },
+        show: |config| {
+            match (config.follow_system_theme, config.dark_mode) {
+                (true, _) => "Auto",
+                (false, true) => "Dark",
+                (false, false) => "Light",
+            }
+            .to_string()
+        },
+    },
+    Setting {
+        name: "AutoThemePdf",
+        kind: "boolean",
+        apply: |config, value| {
+            config.follow_system_theme_pdf = Config::parse_boolean("AutoThemePdf", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.follow_system_theme_pdf),
+    },
+    Setting {
+        name: "OpenSidebar",
+        kind: "boolean",
+        apply: |config, value| {
+            config.open_sidebar = Config::parse_boolean("OpenSidebar", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.open_sidebar),
+    },
+    Setting {
+        name: "PageBorders",
+        kind: "boolean",
+        apply: |config, value| {
+            config.page_borders = Config::parse_boolean("PageBorders", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.page_borders),
+    },
+    Setting {
+        name: "PageBorderColor",
+        kind: "color",
+        apply: |config, value| {
+            config.page_border_color = Config::parse_color("PageBorderColor", value)?;
+            Ok(())
+        },
+        show: |config| show_color(config.page_border_color),
+    },
+    Setting {
+        name: "PageBorderColorDark",
+        kind: "color",
+        apply: |config, value| {
+            config.page_border_color_dark = Config::parse_color("PageBorderColorDark", value)?;
+            Ok(())
+        },
+        show: |config| show_color(config.page_border_color_dark),
+    },
+    Setting {
+        name: "PageBorderWidth",
+        kind: "number of pixels",
+        apply: |config, value| {
+            let width = parse_float("PageBorderWidth", value)?;
+            if width < 0.0 {
+                return Err(format!(
+                    "Invalid PageBorderWidth: '{value}'. Can't be negative"
+                ));
+            }
+            config.page_border_width = width;
+            Ok(())
+        },
+        show: |config| config.page_border_width.to_string(),
+    },
+    Setting {
+        name: "PageShadow",
+        kind: "boolean",
+        apply: |config, value| {
+            config.page_shadow = Config::parse_boolean("PageShadow", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.page_shadow),
+    },
+    Setting {
+        name: "Scrollbars",
+        kind: "boolean",
+        apply: |config, value| {
+            config.scrollbars = Config::parse_boolean("Scrollbars", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.scrollbars),
+    },
+    Setting {
+        name: "ZoomPresets",
+        kind: "list of Fit, FitWidth, FitColumn and percentages",
+        apply: |config, value| {
+            config.zoom_presets = value
+                .split(',')
+                .map(|preset| preset.trim().parse())
+                .collect::<Result<_, _>>()?;
+            Ok(())
+        },
+        show: |config| {
+            config
+                .zoom_presets
+                .iter()
+                .map(|preset| match preset {
+                    ZoomPreset::Scale(scale) => format!("{}%", scale * 100.0),
+                    ZoomPreset::Fit => "Fit".to_string(),
+                    ZoomPreset::FitWidth => "FitWidth".to_string(),
+                    ZoomPreset::FitColumn => "FitColumn".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        },
+    },
+    Setting {
+        name: "Rpc",
+        kind: "boolean",
+        apply: |config, value| {
+            config.rpc_enabled = Config::parse_boolean("Rpc", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.rpc_enabled),
+    },
+    Setting {
+        name: "RpcPort",
+        kind: "port",
+        apply: |config, value| {
+            config.rpc_port = value
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid port number: '{value}'. Must be a valid integer"))?;
+            Ok(())
+        },
+        show: |config| config.rpc_port.to_string(),
+    },
+    Setting {
+        name: "RpcAllowLan",
+        kind: "boolean",
+        apply: |config, value| {
+            config.rpc_allow_lan = Config::parse_boolean("RpcAllowLan", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.rpc_allow_lan),
+    },
+    Setting {
+        name: "TrackpadSensitivity",
+        kind: "number",
+        apply: |config, value| {
+            config.trackpad_sensitivity = parse_float("TrackpadSensitivity", value)?;
+            Ok(())
+        },
+        show: |config| config.trackpad_sensitivity.to_string(),
+    },
+    Setting {
+        name: "PinchSensitivity",
+        kind: "number",
+        apply: |config, value| {
+            config.pinch_sensitivity = parse_float("PinchSensitivity", value)?;
+            Ok(())
+        },
+        show: |config| config.pinch_sensitivity.to_string(),
+    },
+    Setting {
+        name: "SidebarRatio",
+        kind: "number between 0 and 1",
+        apply: |config, value| {
+            let ratio = parse_float("SidebarRatio", value)?;
+            if !(0.0..1.0).contains(&ratio) {
+                return Err(format!(
+                    "Invalid SidebarRatio: '{value}'. Must be between 0 and 1"
+                ));
+            }
+            config.sidebar_ratio =
+                ratio.clamp(*SIDEBAR_RATIO_RANGE.start(), *SIDEBAR_RATIO_RANGE.end());
+            Ok(())
+        },
+        show: |config| config.sidebar_ratio.to_string(),
+    },
+    Setting {
+        name: "SidebarSide",
+        kind: "Left or Right",
+        apply: |config, value| {
+            config.sidebar_side = SidebarSide::from_str(value)
+                .map_err(|_| format!("Unknown sidebar side: '{value}'. Use Left or Right"))?;
+            Ok(())
+        },
+        show: |config| format!("{:?}", config.sidebar_side),
+    },
+    Setting {
+        name: "GroupTabsByColor",
+        kind: "boolean",
+        apply: |config, value| {
+            config.group_tabs_by_color = Config::parse_boolean("GroupTabsByColor", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.group_tabs_by_color),
+    },
+    Setting {
+        name: "DefaultSearchMethod",
+        kind: "PlainText or Regex",
+        apply: |config, value| {
+            config.default_search_method = SearchMethod::from_str(value)
+                .map_err(|_| format!("Unknown search method: '{value}'. Use PlainText or Regex"))?;
+            Ok(())
+        },
+        show: |config| format!("{:?}", config.default_search_method),
+    },
+    Setting {
+        name: "OpenFullscreen",
+        kind: "boolean",
+        apply: |config, value| {
+            config.open_fullscreen_default = Config::parse_boolean("OpenFullscreen", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.open_fullscreen_default),
+    },
+    Setting {
+        name: "OpenPresentation",
+        kind: "boolean",
+        apply: |config, value| {
+            config.open_presentation_default = Config::parse_boolean("OpenPresentation", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.open_presentation_default),
+    },
+    Setting {
+        name: "LastTabClosed",
+        kind: "Exit, StartScreen or RecentFiles",
+        apply: |config, value| {
+            config.last_tab_closed = LastTabClosed::from_str(value).map_err(|_| {
+                format!(
+                    "Unknown last tab behavior: '{value}'. Use Exit, StartScreen or RecentFiles"
+                )
+            })?;
+            Ok(())
+        },
+        show: |config| format!("{:?}", config.last_tab_closed),
+    },
+    Setting {
+        name: "FlashOnChange",
+        kind: "boolean",
+        apply: |config, value| {
+            config.flash_on_change = Config::parse_boolean("FlashOnChange", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.flash_on_change),
+    },
+    Setting {
+        name: "EndOfDocumentToast",
+        kind: "boolean",
+        apply: |config, value| {
+            config.end_of_document_toast = Config::parse_boolean("EndOfDocumentToast", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.end_of_document_toast),
+    },
+    Setting {
+        name: "HighlightOccurrences",
+        kind: "boolean",
+        apply: |config, value| {
+            config.highlight_occurrences = Config::parse_boolean("HighlightOccurrences", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.highlight_occurrences),
+    },
+    Setting {
+        name: "CopyCleanup",
+        kind: "boolean",
+        apply: |config, value| {
+            config.copy_cleanup = Config::parse_boolean("CopyCleanup", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.copy_cleanup),
+    },
+    Setting {
+        name: "SequenceTimeout",
+        kind: "milliseconds",
+        apply: |config, value| {
+            let millis = value.parse::<u64>().map_err(|_| {
+                format!("Invalid timeout: '{value}'. Must be a whole number of milliseconds")
+            })?;
+            config.keyboard.set_timeout(Duration::from_millis(millis));
+            Ok(())
+        },
+        show: |config| config.keyboard.timeout().as_millis().to_string(),
+    },
+    Setting {
+        name: "HintChars",
+        kind: "at least two different characters",
+        apply: |config, value| {
+            let chars: Vec<char> = value.to_lowercase().chars().collect();
+            if chars.len() < 2 {
+                return Err(format!(
+                    "Invalid HintChars: '{value}'. At least two characters are needed"
+                ));
+            }
+            if chars.iter().any(|c| c.is_whitespace())
+                || (1..chars.len()).any(|i| chars[..i].contains(&chars[i]))
+            {
+                return Err(format!(
+                    "Invalid HintChars: '{value}'. Every character may only be used once"
+                ));
+            }
+            config.hint_chars = chars;
+            Ok(())
+        },
+        show: |config| config.hint_chars.iter().collect(),
+    },
+    Setting {
+        name: "LookupCommand",
+        kind: "url or shell command",
+        apply: |config, value| {
+            config.lookup_command = value.to_string();
+            Ok(())
+        },
+        show: |config| show_text(&config.lookup_command),
+    },
+    Setting {
+        name: "TranslateCommand",
+        kind: "url or shell command",
+        apply: |config, value| {
+            config.translate_command = value.to_string();
+            Ok(())
+        },
+        show: |config| show_text(&config.translate_command),
+    },
+    Setting {
+        name: "FormulaCommand",
+        kind: "shell command",
+        apply: |config, value| {
+            config.formula_command = value.to_string();
+            Ok(())
+        },
+        show: |config| show_text(&config.formula_command),
+    },
+    Setting {
+        name: "UrlHandler",
+        kind: "shell command",
+        apply: |config, value| {
+            config.url_handler = value.to_string();
+            Ok(())
+        },
+        show: |config| show_text(&config.url_handler),
+    },
+    Setting {
+        name: "MailHandler",
+        kind: "shell command or Copy",
+        apply: |config, value| {
+            config.mail_handler = value.to_string();
+            Ok(())
+        },
+        show: |config| show_text(&config.mail_handler),
+    },
+    Setting {
+        name: "Autofit",
+        kind: "boolean",
+        apply: |config, value| {
+            config.autofit = Config::parse_boolean("Autofit", value)?;
+            Ok(())
+        },
+        show: |config| show_bool(config.autofit),
+    },
+    Setting {
+        name: "AutofitDebounce",
+        kind: "milliseconds",
+        apply: |config, value| {
+            let millis = value.parse::<u64>().map_err(|_| {
+                format!("Invalid debounce: '{value}'. Must be a whole number of milliseconds")
+            })?;
+            config.autofit_debounce = Duration::from_millis(millis);
+            Ok(())
+        },
+        show: |config| config.autofit_debounce.as_millis().to_string(),
+    },
+];
+
+/// A table of every setting with what it takes, its default and its value in `current`.
+pub fn list_settings(current: &Config) -> String {
+    let default = Config::default();
+    let rows: Vec<[String; 4]> = SETTINGS
+        .iter()
+        .map(|setting| {
+            [
+                setting.name.to_string(),
+                setting.kind.to_string(),
+                setting.value(&default),
+                setting.value(current),
+            ]
+        })
+        .collect();
+    let header = ["Setting", "Type", "Default", "Current"].map(str::to_string);
+    let mut widths = [0; 4];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
 }
 
 #[cfg(test)]
 mod tests {
     use keybinds2::{KeyInput, Keybind};
+    use proptest::prelude::*;
 
     use super::*;
 
@@ -897,8 +1825,9 @@ mod tests {
     pub fn can_parse_config_file() {
         let contents = include_str!("../assets/default.conf");
         let config = Config::from_str(contents).unwrap();
-        let binds = config.keyboard.into_vec();
         let default_cfg = Config::default();
+        assert_eq!(config.keyboard.timeout(), default_cfg.keyboard.timeout());
+        let binds = config.keyboard.into_vec();
         let default_binds = default_cfg.keyboard.into_vec();
         assert_eq!(binds.len(), default_binds.len());
         for (b1, b2) in binds.iter().zip(default_binds) {
@@ -921,10 +1850,40 @@ mod tests {
             config.trackpad_sensitivity,
             default_cfg.trackpad_sensitivity
         );
+        assert_eq!(config.pinch_sensitivity, default_cfg.pinch_sensitivity);
         assert_eq!(config.page_borders, default_cfg.page_borders);
+        assert_eq!(config.page_border_color, default_cfg.page_border_color);
+        assert_eq!(
+            config.page_border_color_dark,
+            default_cfg.page_border_color_dark
+        );
+        assert_eq!(config.page_border_width, default_cfg.page_border_width);
+        assert_eq!(config.page_shadow, default_cfg.page_shadow);
+        assert_eq!(config.scrollbars, default_cfg.scrollbars);
+        assert_eq!(config.zoom_presets, default_cfg.zoom_presets);
         assert_eq!(config.dark_mode, default_cfg.dark_mode);
         assert_eq!(config.invert_pdf, default_cfg.invert_pdf);
+        assert_eq!(config.group_tabs_by_color, default_cfg.group_tabs_by_color);
+        assert_eq!(config.pdf_dark_mode_style, default_cfg.pdf_dark_mode_style);
+        assert_eq!(config.follow_system_theme, default_cfg.follow_system_theme);
+        assert_eq!(
+            config.follow_system_theme_pdf,
+            default_cfg.follow_system_theme_pdf
+        );
         assert_eq!(config.open_sidebar, default_cfg.open_sidebar);
+        assert_eq!(
+            config.end_of_document_toast,
+            default_cfg.end_of_document_toast
+        );
+        assert_eq!(
+            config.highlight_occurrences,
+            default_cfg.highlight_occurrences
+        );
+        assert_eq!(config.copy_cleanup, default_cfg.copy_cleanup);
+        assert_eq!(config.autofit, default_cfg.autofit);
+        assert_eq!(config.autofit_debounce, default_cfg.autofit_debounce);
+        assert_eq!(config.hint_chars, default_cfg.hint_chars);
+        assert_eq!(config.lookup_command, default_cfg.lookup_command);
         assert_eq!(
             config.default_search_method,
             default_cfg.default_search_method
@@ -1161,6 +2120,16 @@ Set RpcPort invalid_port
         assert_eq!(result.config.trackpad_sensitivity, 0.5);
     }
 
+    #[test]
+    pub fn pinch_sensitivity_is_separate_from_trackpad_sensitivity() {
+        let config_str = "Set PinchSensitivity 2.5";
+        let result = Config::parse_with_errors(config_str);
+
+        assert!(!result.has_errors());
+        assert_eq!(result.config.pinch_sensitivity, 2.5);
+        assert_eq!(result.config.trackpad_sensitivity, 1.0);
+    }
+
     #[test]
     pub fn error_handling_invalid_trackpad_sensitivity() {
         let config_str = "Set TrackpadSensitivity invalid";
@@ -1198,6 +2167,240 @@ Set RpcPort invalid_port
         );
     }
 
+    #[test]
+    pub fn can_parse_sidebar_ratio() {
+        let result = Config::parse_with_errors("Set SidebarRatio 0.25");
+        assert!(!result.has_errors());
+        assert_eq!(result.config.sidebar_ratio, 0.25);
+
+        let result = Config::parse_with_errors("Set SidebarRatio 1.5");
+        assert!(result.has_errors());
+
+        let result = Config::parse_with_errors("Set SidebarRatio 0.0");
+        assert!(!result.has_errors());
+        assert_eq!(result.config.sidebar_ratio, *SIDEBAR_RATIO_RANGE.start());
+    }
+
+    #[test]
+    pub fn can_parse_sidebar_side() {
+        let result = Config::parse_with_errors("Set SidebarSide Left");
+        assert!(!result.has_errors());
+        assert_eq!(result.config.sidebar_side, SidebarSide::Left);
+
+        let result = Config::parse_with_errors("Set SidebarSide Top");
+        assert!(result.has_errors());
+        assert!(result.errors[0].message.contains("Unknown sidebar side"));
+    }
+
+    #[test]
+    pub fn can_parse_pdf_dark_mode_style() {
+        let result = Config::parse_with_errors("Set DarkModePdfStyle Recolor");
+        assert!(!result.has_errors());
+        assert_eq!(result.config.pdf_dark_mode_style, PdfDarkModeStyle::Recolor);
+
+        let result = Config::parse_with_errors("Set DarkModePdfStyle Sepia");
+        assert!(result.has_errors());
+        assert!(
+            result.errors[0]
+                .message
+                .contains("Unknown pdf dark mode style")
+        );
+    }
+
+    #[test]
+    pub fn can_parse_last_tab_closed() {
+        let result = Config::parse_with_errors("Set LastTabClosed RecentFiles");
+        assert!(!result.has_errors());
+        assert_eq!(result.config.last_tab_closed, LastTabClosed::RecentFiles);
+
+        let result = Config::parse_with_errors("Set LastTabClosed Minimize");
+        assert!(result.has_errors());
+    }
+
+    #[test]
+    pub fn can_parse_sequence_timeout() {
+        let config_str = "Set SequenceTimeout 250";
+        let result = Config::parse_with_errors(config_str);
+
+        assert!(!result.has_errors());
+        assert_eq!(result.config.keyboard.timeout(), Duration::from_millis(250));
+    }
+
+    #[test]
+    pub fn error_handling_invalid_sequence_timeout() {
+        let config_str = "Set SequenceTimeout -5";
+        let result = Config::parse_with_errors(config_str);
+
+        assert!(result.has_errors());
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].message.contains("Invalid timeout: '-5'"));
+    }
+
+    #[test]
+    pub fn can_parse_autofit() {
+        let config_str = "Set Autofit True\nSet AutofitDebounce 250";
+        let result = Config::parse_with_errors(config_str);
+
+        assert!(!result.has_errors());
+        assert!(result.config.autofit);
+        assert_eq!(result.config.autofit_debounce, Duration::from_millis(250));
+    }
+
+    #[test]
+    pub fn can_parse_zoom_presets() {
+        let result = Config::parse_with_errors("Set ZoomPresets 75%,FitWidth");
+        assert!(!result.has_errors());
+        assert_eq!(
+            result.config.zoom_presets,
+            [ZoomPreset::Scale(0.75), ZoomPreset::FitWidth]
+        );
+
+        let result = Config::parse_with_errors("Set ZoomPresets 100%,Huge");
+        assert!(
+            result.errors[0]
+                .message
+                .contains("Invalid zoom preset: 'Huge'")
+        );
+    }
+
+    #[test]
+    pub fn can_parse_page_style() {
+        let config_str =
+            "Set PageBorderColor #ff0000\nSet PageBorderWidth 1.5\nSet PageShadow True";
+        let result = Config::parse_with_errors(config_str);
+
+        assert!(!result.has_errors());
+        assert_eq!(
+            result.config.page_border_color,
+            iced::Color::from_rgb8(255, 0, 0)
+        );
+        assert_eq!(result.config.page_border_width, 1.5);
+        assert!(result.config.page_shadow);
+
+        let result = Config::parse_with_errors("Set PageBorderColorDark red");
+        assert!(result.errors[0].message.contains("Invalid color"));
+    }
+
+    #[test]
+    pub fn can_parse_theme() {
+        let result = Config::parse_with_errors("Set Theme Auto\nSet AutoThemePdf True");
+        assert!(!result.has_errors());
+        assert!(result.config.follow_system_theme);
+        assert!(result.config.follow_system_theme_pdf);
+
+        let result = Config::parse_with_errors("Set Theme Auto\nSet Theme Light");
+        assert!(!result.has_errors());
+        assert!(!result.config.follow_system_theme);
+        assert!(!result.config.dark_mode);
+
+        let result = Config::parse_with_errors("Set Theme Dim");
+        assert!(result.errors[0].message.contains("Invalid value for Theme"));
+    }
+
+    #[test]
+    pub fn can_parse_url_handler() {
+        let result = Config::parse_with_errors(r#"Set UrlHandler "firefox --private-window %s""#);
+        assert!(!result.has_errors());
+        assert_eq!(result.config.url_handler, "firefox --private-window %s");
+
+        // Quotes around nothing unset it again
+        let result =
+            Config::parse_with_errors("Set UrlHandler \"firefox %s\"\nSet UrlHandler \"\"");
+        assert!(!result.has_errors());
+        assert_eq!(result.config.url_handler, "");
+        assert_eq!(Config::default().url_handler, "");
+    }
+
+    #[test]
+    pub fn can_parse_mail_handler() {
+        let result = Config::parse_with_errors("Set MailHandler Copy");
+        assert!(!result.has_errors());
+        assert_eq!(result.config.mail_handler, MAIL_HANDLER_COPY);
+
+        let result = Config::parse_with_errors(r#"Set MailHandler "thunderbird -compose %s""#);
+        assert_eq!(result.config.mail_handler, "thunderbird -compose %s");
+    }
+
+    #[test]
+    pub fn can_parse_hint_chars() {
+        let result = Config::parse_with_errors("Set HintChars ASDFghjkl");
+        assert!(!result.has_errors());
+        assert_eq!(
+            result.config.hint_chars,
+            vec!['a', 's', 'd', 'f', 'g', 'h', 'j', 'k', 'l']
+        );
+
+        let result = Config::parse_with_errors("Set HintChars asdfa");
+        assert!(result.errors[0].message.contains("only be used once"));
+    }
+
+    #[test]
+    pub fn can_parse_lookup_command() {
+        let result = Config::parse_with_errors(
+            "Set LookupCommand \"dict %s\"\nSet TranslateCommand \"trans -b :en %s\"",
+        );
+
+        assert!(!result.has_errors());
+        assert_eq!(result.config.lookup_command, "dict %s");
+        assert_eq!(result.config.translate_command, "trans -b :en %s");
+    }
+
+    #[test]
+    pub fn can_parse_formula_command() {
+        let result = Config::parse_with_errors("Set FormulaCommand \"pix2tex %s\"");
+        assert!(!result.has_errors());
+        assert_eq!(result.config.formula_command, "pix2tex %s");
+        assert!(Config::default().formula_command.is_empty());
+    }
+
+    #[test]
+    pub fn can_parse_hooks() {
+        let config_str = r#"On PageChanged "notify-send 'page %p'""#;
+        let result = Config::parse_with_errors(config_str);
+
+        assert!(!result.has_errors());
+        assert_eq!(
+            result.config.hooks,
+            vec![(HookEvent::PageChanged, "notify-send 'page %p'".to_string())]
+        );
+    }
+
+    #[test]
+    pub fn can_parse_tools() {
+        let config_str = "Tool \"Open in Okular\" \"okular %f\"\n\
+            Tool \"Extract refs\" \"scholarly %f --page %p\"";
+        let result = Config::parse_with_errors(config_str);
+
+        assert!(!result.has_errors());
+        assert_eq!(
+            result.config.tools,
+            vec![
+                ("Open in Okular".to_string(), "okular %f".to_string()),
+                (
+                    "Extract refs".to_string(),
+                    "scholarly %f --page %p".to_string()
+                ),
+            ]
+        );
+
+        let result = Config::parse_with_errors("Tool \"\" \"okular %f\"");
+        assert!(result.has_errors());
+    }
+
+    #[test]
+    pub fn error_handling_unknown_hook_event() {
+        let config_str = r#"On PageTurned "echo""#;
+        let result = Config::parse_with_errors(config_str);
+
+        assert!(result.has_errors());
+        assert_eq!(result.errors.len(), 1);
+        assert!(
+            result.errors[0]
+                .message
+                .contains("Unknown event: PageTurned")
+        );
+    }
+
     #[test]
     pub fn test_config_file_with_errors() {
         use std::fs;
@@ -1215,4 +2418,129 @@ Set RpcPort invalid_port
             assert!(!result.config.keyboard.as_slice().is_empty());
         }
     }
+
+    #[test]
+    pub fn settings_round_trip_through_their_listed_values() {
+        let default = Config::default();
+        let example = include_str!("../assets/default.conf");
+        for setting in SETTINGS {
+            let line = format!("Set {} {}", setting.name, setting.value(&default));
+            let result = Config::parse_with_errors(&line);
+            assert!(!result.has_errors(), "{}", result.format_errors());
+            assert_eq!(
+                setting.value(&result.config),
+                setting.value(&default),
+                "{line}"
+            );
+            assert!(
+                example.contains(&format!("Set {} ", setting.name)),
+                "{} is missing from the example config",
+                setting.name
+            );
+        }
+    }
+
+    #[test]
+    pub fn list_shows_defaults_and_current_values() {
+        let current = Config::from_str("Set PageBorderWidth 1.5\nSet Theme Auto").unwrap();
+        let table = list_settings(&current);
+        // The type can contain spaces, the default and current values are the last two columns
+        let values = |name: &str| {
+            let line = table
+                .lines()
+                .find(|line| line.starts_with(&format!("{name} ")))
+                .unwrap();
+            let mut columns = line.split_whitespace().rev();
+            let current = columns.next().unwrap();
+            (columns.next().unwrap(), current)
+        };
+        assert!(table.starts_with("Setting"));
+        assert_eq!(table.lines().count(), SETTINGS.len() + 1);
+        assert_eq!(values("PageBorderWidth"), ("0", "1.5"));
+        assert_eq!(values("Theme"), ("Dark", "Auto"));
+        assert_eq!(values("PageBorderColor"), ("#bbb8bb", "#bbb8bb"));
+    }
+
+    /// A word of a config line: a command, a setting name, random text or text in quotes that
+    /// may be missing its closing quote
+    fn config_word() -> impl Strategy<Value = String> {
+        prop_oneof![
+            prop::sample::select(vec!["Set", "Bind", "MouseBind", "On"]).prop_map(String::from),
+            prop::sample::select(SETTINGS.iter().map(|s| s.name).collect::<Vec<_>>())
+                .prop_map(String::from),
+            "[^\\s\"]{0,12}",
+            "\"[^\"\\n\\r]{0,12}\"?",
+        ]
+    }
+
+    fn config_line() -> impl Strategy<Value = String> {
+        prop_oneof![
+            4 => (
+                prop::collection::vec(config_word(), 0..5),
+                prop::sample::select(vec![" ", "\t", "   "]),
+            )
+                .prop_map(|(words, separator)| words.join(separator)),
+            1 => "#\\PC{0,40}",
+            1 => "\\PC{200,2000}",
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn parsing_never_panics_and_errors_point_at_their_line(
+            lines in prop::collection::vec(config_line(), 0..20),
+        ) {
+            let result = Config::parse_with_errors(&lines.join("\n"));
+            let mut previous = 0;
+            for error in &result.errors {
+                prop_assert!(error.line_number > previous);
+                prop_assert!(error.line_number <= lines.len());
+                let line = lines[error.line_number - 1].trim();
+                prop_assert!(!line.is_empty() && !line.starts_with('#'));
+                previous = error.line_number;
+            }
+        }
+
+        #[test]
+        fn unquoted_lines_split_on_whitespace(line in "[^\"]{0,80}") {
+            let parts = Config::parse_line_parts(&line).unwrap();
+            prop_assert_eq!(
+                parts,
+                line.split([' ', '\t'])
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        #[test]
+        fn mouse_inputs_never_panic(input in "\\PC{0,40}") {
+            let _ = MouseInput::from_str(&input);
+        }
+
+        #[test]
+        fn mouse_inputs_parse_with_any_modifiers(
+            ctrl: bool,
+            shift: bool,
+            button in prop::sample::select(vec!["Left", "MouseMiddle", "Right", "Back", "ScrollUp"]),
+        ) {
+            let mut input = String::new();
+            if ctrl {
+                input.push_str("Ctrl+");
+            }
+            if shift {
+                input.push_str("Shift+");
+            }
+            input.push_str(button);
+            let parsed = MouseInput::from_str(&input).unwrap();
+            prop_assert_eq!(parsed.modifiers, MouseModifiers { ctrl, shift });
+        }
+    }
+
+    #[test]
+    fn float_settings_have_to_be_finite() {
+        for value in ["NaN", "inf", "-inf"] {
+            let result = Config::parse_with_errors(&format!("Set TrackpadSensitivity {value}"));
+            assert!(result.has_errors(), "{value} was accepted");
+        }
+    }
 }
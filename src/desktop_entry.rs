@@ -0,0 +1,426 @@
+//! `miro install-desktop-entry`, registers miro with the file manager so pdfs can be opened with
+//! it by double-clicking them. Nothing is registered unless asked for and `--uninstall` removes
+//! all of it again.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+
+use crate::paths;
+
+/// The name of the desktop entry as well as the Windows program id
+const APP_ID: &str = "miro-pdf";
+/// The file types miro opens, see [crate::pdf::archive::DocumentKind]
+const MIME_TYPES: &[&str] = &["application/pdf", "application/vnd.comicbook+zip"];
+const EXTENSIONS: &[&str] = &[".pdf", ".cbz"];
+const DEFAULTS_SECTION: &str = "[Default Applications]";
+
+fn desktop_file_name() -> String {
+    format!("{APP_ID}.desktop")
+}
+
+/// The contents of the `.desktop` file launching `exe`.
+fn desktop_entry(exe: &Path) -> String {
+    format!(
+        "[Desktop Entry]\n\
+        Type=Application\n\
+        Name=Miro\n\
+        GenericName=PDF Viewer\n\
+        Comment={}\n\
+        Exec={} %f\n\
+        Icon={APP_ID}\n\
+        Terminal=false\n\
+        Categories=Office;Viewer;\n\
+        MimeType={};\n",
+        env!("CARGO_PKG_DESCRIPTION"),
+        exec_arg(&exe.to_string_lossy()),
+        MIME_TYPES.join(";"),
+    )
+}
+
+/// Quotes `arg` for the `Exec` key when it contains characters the desktop entry spec reserves,
+/// like the spaces separating arguments. Inside the quotes `"`, `` ` ``, `$` and `\` are escaped
+/// with a backslash, which the string escaping of the file then doubles.
+fn exec_arg(arg: &str) -> String {
+    const RESERVED: &[char] = &[
+        ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(',
+        ')', '`',
+    ];
+    let arg = arg.replace('%', "%%");
+    if !arg.contains(RESERVED) {
+        return arg;
+    }
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        match c {
+            '"' | '`' | '$' => {
+                quoted.push_str("\\\\");
+                quoted.push(c);
+            }
+            '\\' => quoted.push_str("\\\\\\\\"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Makes `desktop_file` the preferred application for `mime_types` in the contents of a
+/// `mimeapps.list`. The applications that were the default before stay listed after it, so
+/// [remove_defaults] brings them back.
+fn set_defaults(mimeapps: &str, desktop_file: &str, mime_types: &[&str]) -> String {
+    let mut lines: Vec<String> = mimeapps.lines().map(str::to_string).collect();
+    let section = match lines.iter().position(|l| l.trim() == DEFAULTS_SECTION) {
+        Some(i) => i,
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(DEFAULTS_SECTION.to_string());
+            lines.len() - 1
+        }
+    };
+    for mime in mime_types {
+        let section_end = lines[section + 1..]
+            .iter()
+            .position(|l| l.trim_start().starts_with('['))
+            .map_or(lines.len(), |i| section + 1 + i);
+        let existing = (section + 1..section_end).find(|&i| {
+            lines[i]
+                .split_once('=')
+                .is_some_and(|(key, _)| key.trim() == *mime)
+        });
+        match existing {
+            Some(i) => {
+                let (key, apps) = lines[i].split_once('=').unwrap();
+                if apps.split(';').next().map(str::trim) == Some(desktop_file) {
+                    continue;
+                }
+                // The other applications are kept exactly as they were written, so that
+                // [remove_defaults] gives back the original line
+                let others = without_app(apps, desktop_file);
+                lines[i] = if others.trim().is_empty() {
+                    format!("{key}={desktop_file}")
+                } else {
+                    format!("{key}={desktop_file};{others}")
+                };
+            }
+            None => {
+                // Before the blank lines separating the section from the next one
+                let end = (section + 1..section_end)
+                    .rev()
+                    .find(|&i| !lines[i].trim().is_empty())
+                    .map_or(section + 1, |i| i + 1);
+                lines.insert(end, format!("{mime}={desktop_file}"));
+            }
+        }
+    }
+    let mut out = lines.join("\n");
+    if mimeapps.is_empty() || mimeapps.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// `apps`, a `;` separated list of desktop files, without `desktop_file`.
+fn without_app(apps: &str, desktop_file: &str) -> String {
+    apps.split(';')
+        .filter(|app| app.trim() != desktop_file)
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Removes `desktop_file` from every association in the contents of a `mimeapps.list`, dropping
+/// the associations left without any application and the section [set_defaults] added if nothing
+/// else is in it. Lines not mentioning `desktop_file` are kept byte for byte, so uninstalling
+/// gives back the file as it was before installing.
+fn remove_defaults(mimeapps: &str, desktop_file: &str) -> String {
+    let mut out: Vec<String> = vec![];
+    // Where the current section starts in `out`, and whether associations were dropped from it
+    let mut section: Option<(usize, bool)> = None;
+    for line in mimeapps.split_inclusive('\n') {
+        if line.trim_start().starts_with('[') {
+            drop_emptied_section(&mut out, section, false);
+            section = Some((out.len(), false));
+            out.push(line.to_string());
+            continue;
+        }
+        let Some((key, apps)) = line.split_once('=') else {
+            out.push(line.to_string());
+            continue;
+        };
+        let (apps, ending) = match apps.strip_suffix("\r\n") {
+            Some(apps) => (apps, "\r\n"),
+            None => apps
+                .strip_suffix('\n')
+                .map_or((apps, ""), |apps| (apps, "\n")),
+        };
+        if !apps.split(';').any(|app| app.trim() == desktop_file) {
+            out.push(line.to_string());
+            continue;
+        }
+        let rest = without_app(apps, desktop_file);
+        if rest.split(';').all(|app| app.trim().is_empty()) {
+            if let Some((_, dropped)) = &mut section {
+                *dropped = true;
+            }
+        } else {
+            out.push(format!("{key}={rest}{ending}"));
+        }
+    }
+    drop_emptied_section(&mut out, section, true);
+    out.concat()
+}
+
+/// Drops the section starting at `section` from `out` when all of its associations were removed,
+/// along with the blank line [set_defaults] puts before a section it adds at the end.
+fn drop_emptied_section(out: &mut Vec<String>, section: Option<(usize, bool)>, last: bool) {
+    let Some((start, true)) = section else {
+        return;
+    };
+    if out[start + 1..].iter().any(|l| !l.trim().is_empty()) {
+        return;
+    }
+    out.truncate(start);
+    if last && out.last().is_some_and(|l| l.trim().is_empty()) {
+        out.pop();
+    }
+}
+
+fn base_dirs() -> Result<directories::BaseDirs> {
+    directories::BaseDirs::new().ok_or(anyhow!("No home directory could be determined"))
+}
+
+fn linux_paths() -> Result<(PathBuf, PathBuf, PathBuf)> {
+    let dirs = base_dirs()?;
+    let data = dirs.data_dir();
+    Ok((
+        data.join("applications").join(desktop_file_name()),
+        data.join("icons/hicolor/256x256/apps")
+            .join(format!("{APP_ID}.png")),
+        dirs.config_dir().join("mimeapps.list"),
+    ))
+}
+
+fn install_linux() -> Result<()> {
+    let exe = std::env::current_exe()?;
+    let (desktop, icon, mimeapps) = linux_paths()?;
+    for path in [&desktop, &icon, &mimeapps] {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    std::fs::write(&desktop, desktop_entry(&exe))?;
+    std::fs::write(&icon, include_bytes!("../assets/logo.png"))?;
+    let current = std::fs::read_to_string(&mimeapps).unwrap_or_default();
+    std::fs::write(
+        &mimeapps,
+        set_defaults(&current, &desktop_file_name(), MIME_TYPES),
+    )?;
+    refresh_desktop_database(&desktop);
+    println!("Wrote {}", desktop.display());
+    println!(
+        "Made miro the default application for {} in {}",
+        MIME_TYPES.join(", "),
+        mimeapps.display()
+    );
+    Ok(())
+}
+
+fn uninstall_linux() -> Result<()> {
+    let (desktop, icon, mimeapps) = linux_paths()?;
+    for path in [&desktop, &icon] {
+        match std::fs::remove_file(path) {
+            Ok(()) => println!("Removed {}", path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(anyhow!("Couldn't remove {}: {e}", path.display())),
+        }
+    }
+    if let Ok(current) = std::fs::read_to_string(&mimeapps) {
+        let cleaned = remove_defaults(&current, &desktop_file_name());
+        if cleaned != current {
+            std::fs::write(&mimeapps, cleaned)?;
+            println!("Removed miro from {}", mimeapps.display());
+        }
+    }
+    refresh_desktop_database(&desktop);
+    Ok(())
+}
+
+/// File managers pick up the change on their own eventually, this only makes it immediate.
+fn refresh_desktop_database(desktop: &Path) {
+    if let Some(dir) = desktop.parent() {
+        let _ = std::process::Command::new("update-desktop-database")
+            .arg(dir)
+            .status();
+    }
+}
+
+/// The `reg` invocations registering miro under the current user. Windows doesn't let programs
+/// make themselves the default, so miro only shows up in "Open with".
+fn windows_install_commands(exe: &Path) -> Vec<Vec<String>> {
+    let class = format!(r"HKCU\Software\Classes\{APP_ID}.Document");
+    let mut commands = vec![
+        vec![
+            "add".to_string(),
+            class.clone(),
+            "/ve".to_string(),
+            "/d".to_string(),
+            "Miro document".to_string(),
+            "/f".to_string(),
+        ],
+        vec![
+            "add".to_string(),
+            format!(r"{class}\DefaultIcon"),
+            "/ve".to_string(),
+            "/d".to_string(),
+            format!("\"{}\",0", exe.display()),
+            "/f".to_string(),
+        ],
+        vec![
+            "add".to_string(),
+            format!(r"{class}\shell\open\command"),
+            "/ve".to_string(),
+            "/d".to_string(),
+            format!("\"{}\" \"%1\"", exe.display()),
+            "/f".to_string(),
+        ],
+    ];
+    for ext in EXTENSIONS {
+        commands.push(vec![
+            "add".to_string(),
+            format!(r"HKCU\Software\Classes\{ext}\OpenWithProgids"),
+            "/v".to_string(),
+            format!("{APP_ID}.Document"),
+            "/t".to_string(),
+            "REG_NONE".to_string(),
+            "/f".to_string(),
+        ]);
+    }
+    commands
+}
+
+fn windows_uninstall_commands() -> Vec<Vec<String>> {
+    let mut commands = vec![vec![
+        "delete".to_string(),
+        format!(r"HKCU\Software\Classes\{APP_ID}.Document"),
+        "/f".to_string(),
+    ]];
+    for ext in EXTENSIONS {
+        commands.push(vec![
+            "delete".to_string(),
+            format!(r"HKCU\Software\Classes\{ext}\OpenWithProgids"),
+            "/v".to_string(),
+            format!("{APP_ID}.Document"),
+            "/f".to_string(),
+        ]);
+    }
+    commands
+}
+
+/// Runs `reg` with each of `commands`. Missing keys are fine when uninstalling.
+fn run_reg(commands: &[Vec<String>], ignore_failures: bool) -> Result<()> {
+    for args in commands {
+        let status = std::process::Command::new("reg").args(args).status()?;
+        if !status.success() && !ignore_failures {
+            return Err(anyhow!("reg {} failed with {status}", args.join(" ")));
+        }
+    }
+    Ok(())
+}
+
+/// `miro install-desktop-entry`, or `miro install-desktop-entry --uninstall`.
+pub fn cli(uninstall: bool) -> Result<()> {
+    paths::ensure_writable()?;
+    match (std::env::consts::OS, uninstall) {
+        ("windows", false) => {
+            run_reg(&windows_install_commands(&std::env::current_exe()?), false)?;
+            println!(
+                "Miro now shows up in \"Open with\" for {}",
+                EXTENSIONS.join(", ")
+            );
+            Ok(())
+        }
+        ("windows", true) => {
+            run_reg(&windows_uninstall_commands(), true)?;
+            println!("Removed miro from \"Open with\"");
+            Ok(())
+        }
+        ("macos", _) => Err(anyhow!(
+            "Use \"Get Info\" on a pdf in Finder to open it with miro by default"
+        )),
+        (_, false) => install_linux(),
+        (_, true) => uninstall_linux(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn associations_are_added_and_removed_again() {
+        let before = "[Added Associations]\n\
+            application/pdf=org.gnome.Evince.desktop;\n\
+            \n\
+            [Default Applications]\n\
+            application/pdf=org.gnome.Evince.desktop\n\
+            text/html=firefox.desktop\n\
+            \n\
+            [Removed Associations]\n";
+        let installed = set_defaults(before, "miro-pdf.desktop", MIME_TYPES);
+        assert_eq!(
+            installed,
+            "[Added Associations]\n\
+            application/pdf=org.gnome.Evince.desktop;\n\
+            \n\
+            [Default Applications]\n\
+            application/pdf=miro-pdf.desktop;org.gnome.Evince.desktop\n\
+            text/html=firefox.desktop\n\
+            application/vnd.comicbook+zip=miro-pdf.desktop\n\
+            \n\
+            [Removed Associations]\n"
+        );
+        // Installing twice changes nothing
+        assert_eq!(
+            set_defaults(&installed, "miro-pdf.desktop", MIME_TYPES),
+            installed
+        );
+        // Uninstalling gives back the file as it was
+        assert_eq!(remove_defaults(&installed, "miro-pdf.desktop"), before);
+        let unrelated = "[Default Applications]\ntext/html=\nimage/png=a.desktop;;b.desktop\r\n";
+        assert_eq!(remove_defaults(unrelated, "miro-pdf.desktop"), unrelated);
+
+        assert_eq!(
+            set_defaults("", "miro-pdf.desktop", &["application/pdf"]),
+            "[Default Applications]\napplication/pdf=miro-pdf.desktop\n"
+        );
+        // The section added by installing goes away again
+        let without_section = "[Added Associations]\ntext/html=firefox.desktop\n";
+        let installed = set_defaults(without_section, "miro-pdf.desktop", MIME_TYPES);
+        assert_eq!(
+            remove_defaults(&installed, "miro-pdf.desktop"),
+            without_section
+        );
+        assert_eq!(
+            remove_defaults(
+                "[Added Associations]\napplication/pdf=a.desktop;miro-pdf.desktop;\n",
+                "miro-pdf.desktop"
+            ),
+            "[Added Associations]\napplication/pdf=a.desktop;\n"
+        );
+    }
+
+    #[test]
+    fn exec_paths_are_quoted_when_needed() {
+        assert_eq!(exec_arg("/usr/bin/miro"), "/usr/bin/miro");
+        assert_eq!(
+            exec_arg("/home/me/My Apps/miro"),
+            "\"/home/me/My Apps/miro\""
+        );
+        assert_eq!(
+            exec_arg("/opt/$x/100%/a\\b"),
+            "\"/opt/\\\\$x/100%%/a\\\\\\\\b\""
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use iced::{
+    Border, Element, Length, Theme,
+    border::Radius,
+    futures::{SinkExt, Stream, channel::mpsc},
+    stream,
+    widget::{self, container, progress_bar, text},
+};
+use twox_hash::XxHash64;
+
+use crate::paths;
+
+#[derive(Debug, Clone)]
+pub enum DownloadProgress {
+    Progress {
+        url: String,
+        received: u64,
+        total: Option<u64>,
+    },
+    Finished {
+        url: String,
+        path: PathBuf,
+    },
+    Failed {
+        url: String,
+        error: String,
+    },
+}
+
+pub fn is_url(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://")
+}
+
+/// Where the document at `url` is cached. The same url always ends up in the same file so
+/// refreshing a download replaces the old copy, which the file watcher then reloads.
+pub fn cache_path(url: &str) -> Result<PathBuf> {
+    let name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|u| u.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("download");
+    let name = if name.ends_with(".pdf") {
+        name.to_string()
+    } else {
+        format!("{name}.pdf")
+    };
+    let hash = XxHash64::oneshot(0, url.as_bytes());
+    Ok(paths::cache_dir()?
+        .join("downloads")
+        .join(format!("{hash:016x}-{name}")))
+}
+
+/// Downloads `url` into the cache, reporting the progress along the way.
+pub fn download(url: String) -> impl Stream<Item = DownloadProgress> {
+    stream::channel(
+        16,
+        |mut output: mpsc::Sender<DownloadProgress>| async move {
+            let message = match download_to_cache(&url, &mut output).await {
+                Ok(path) => DownloadProgress::Finished { url, path },
+                Err(e) => DownloadProgress::Failed {
+                    url,
+                    error: e.to_string(),
+                },
+            };
+            let _ = output.send(message).await;
+        },
+    )
+}
+
+async fn download_to_cache(
+    url: &str,
+    output: &mut mpsc::Sender<DownloadProgress>,
+) -> Result<PathBuf> {
+    paths::ensure_writable()?;
+    let path = cache_path(url)?;
+    let mut response = reqwest::get(url).await?.error_for_status()?;
+    let total = response.content_length();
+    let mut bytes = Vec::with_capacity(total.unwrap_or(0) as usize);
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        let _ = output
+            .send(DownloadProgress::Progress {
+                url: url.to_string(),
+                received: bytes.len() as u64,
+                total,
+            })
+            .await;
+    }
+    if !bytes.starts_with(b"%PDF") {
+        return Err(anyhow!("{url} is not a pdf"));
+    }
+
+    let dir = path
+        .parent()
+        .ok_or(anyhow!("The cache path has no parent directory"))?;
+    tokio::fs::create_dir_all(dir).await?;
+    // Renamed over the old copy so an open tab never reads a partial download, the file watcher
+    // picks up the replaced file like it does for build tools
+    let target = path.clone();
+    tokio::task::spawn_blocking(move || paths::write_atomically(&target, bytes)).await??;
+    Ok(tokio::fs::canonicalize(&path).await?)
+}
+
+/// Downloads in progress and where finished ones came from.
+#[derive(Debug, Default)]
+pub struct Downloads {
+    /// Bytes received so far and the expected total, by url
+    active: BTreeMap<String, (u64, Option<u64>)>,
+    /// The url each cached file was downloaded from
+    sources: HashMap<PathBuf, String>,
+}
+
+impl Downloads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns false if `url` is already being downloaded.
+    pub fn start(&mut self, url: &str) -> bool {
+        if self.active.contains_key(url) {
+            return false;
+        }
+        self.active.insert(url.to_string(), (0, None));
+        true
+    }
+
+    pub fn update(&mut self, progress: &DownloadProgress) {
+        match progress {
+            DownloadProgress::Progress {
+                url,
+                received,
+                total,
+            } => {
+                self.active.insert(url.clone(), (*received, *total));
+            }
+            DownloadProgress::Finished { url, path } => {
+                self.active.remove(url);
+                self.sources.insert(path.clone(), url.clone());
+            }
+            DownloadProgress::Failed { url, .. } => {
+                self.active.remove(url);
+            }
+        }
+    }
+
+    /// The url the cached file at `path` was downloaded from, if it was.
+    pub fn source(&self, path: &Path) -> Option<&str> {
+        self.sources.get(path).map(String::as_str)
+    }
+
+    pub fn view<'a, Message: 'a>(&'a self) -> Option<Element<'a, Message>> {
+        if self.active.is_empty() {
+            return None;
+        }
+        let mut rows = widget::column![].spacing(4.0);
+        for (url, (received, total)) in &self.active {
+            let label = match total {
+                Some(total) => format!(
+                    "Downloading {url} ({} / {} kB)",
+                    received / 1000,
+                    total / 1000
+                ),
+                None => format!("Downloading {url} ({} kB)", received / 1000),
+            };
+            let fraction = match total {
+                Some(total) if *total > 0 => *received as f32 / *total as f32,
+                _ => 0.0,
+            };
+            rows = rows.push(text(label).size(13.0));
+            rows = rows.push(progress_bar(0.0..=1.0, fraction).girth(6.0));
+        }
+        Some(
+            container(rows)
+                .width(Length::Fixed(360.0))
+                .padding(8.0)
+                .style(|theme: &Theme| widget::container::Style {
+                    background: Some(theme.extended_palette().background.weak.color.into()),
+                    border: Border {
+                        color: theme.extended_palette().primary.base.color,
+                        width: 2.0,
+                        radius: Radius::from(8.0),
+                    },
+                    ..Default::default()
+                })
+                .into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_path_keeps_the_file_name() {
+        let path = cache_path("https://example.com/papers/attention.pdf?download=1").unwrap();
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert!(name.ends_with("-attention.pdf"));
+        assert_eq!(
+            path,
+            cache_path("https://example.com/papers/attention.pdf?download=1").unwrap()
+        );
+        assert_ne!(
+            path,
+            cache_path("https://example.org/papers/attention.pdf").unwrap()
+        );
+    }
+
+    #[test]
+    fn cache_path_without_a_file_name() {
+        let path = cache_path("https://arxiv.org/pdf/1706.03762").unwrap();
+        assert!(path.to_string_lossy().ends_with("-1706.03762.pdf"));
+        let path = cache_path("https://example.com/").unwrap();
+        assert!(path.to_string_lossy().ends_with("-download.pdf"));
+    }
+}
@@ -0,0 +1,101 @@
+use std::{fmt::Display, path::PathBuf};
+
+/// Errors that happen while handling a message. They are shown to the user as a notification
+/// instead of taking down the whole viewer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MiroError {
+    /// A file couldn't be found, read or written
+    File {
+        path: PathBuf,
+        reason: String,
+    },
+    /// A document couldn't be downloaded from a url
+    Download {
+        url: String,
+        reason: String,
+    },
+    /// MuPDF failed to read, edit or render the document
+    Document(String),
+    Clipboard(String),
+    /// Changed files won't be reloaded
+    Watch(String),
+    /// Another program couldn't be started to open a link, file or the print dialog
+    External(String),
+}
+
+impl MiroError {
+    pub fn file(path: impl Into<PathBuf>, reason: impl Display) -> Self {
+        MiroError::File {
+            path: path.into(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn document(reason: impl Display) -> Self {
+        MiroError::Document(reason.to_string())
+    }
+
+    pub fn external(reason: impl Display) -> Self {
+        MiroError::External(reason.to_string())
+    }
+}
+
+impl Display for MiroError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MiroError::File { path, reason } => write!(f, "{}: {reason}", path.display()),
+            MiroError::Download { url, reason } => write!(f, "Couldn't download {url}: {reason}"),
+            MiroError::Document(reason) => write!(f, "{reason}"),
+            MiroError::Clipboard(reason) => write!(f, "Couldn't use the clipboard: {reason}"),
+            MiroError::Watch(reason) => write!(f, "{reason}"),
+            MiroError::External(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl std::error::Error for MiroError {}
+
+impl From<arboard::Error> for MiroError {
+    fn from(value: arboard::Error) -> Self {
+        MiroError::Clipboard(value.to_string())
+    }
+}
+
+impl From<mupdf::Error> for MiroError {
+    fn from(value: mupdf::Error) -> Self {
+        MiroError::document(value)
+    }
+}
+
+/// Runs `f` on a thread meant for blocking work. Creating a clipboard or starting another program
+/// can take hundreds of milliseconds on some desktops, which mustn't hold up the interface.
+pub async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, MiroError> + Send + 'static,
+) -> Result<T, MiroError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .unwrap_or_else(|e| Err(MiroError::external(e)))
+}
+
+/// Copies `text` to the system clipboard.
+pub async fn copy_to_clipboard(text: String) -> Result<(), MiroError> {
+    run_blocking(move || {
+        arboard::Clipboard::new()?.set_text(text)?;
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_errors_name_the_file() {
+        let error = MiroError::file("/tmp/missing.pdf", "No such file or directory");
+        assert_eq!(
+            error.to_string(),
+            "/tmp/missing.pdf: No such file or directory"
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Copying formulas as LaTeX. Miro can't read formulas itself, so the selection is rendered to an
+//! image and handed to an external converter such as pix2tex, set with `FormulaCommand`.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    error::{MiroError, copy_to_clipboard},
+    lookup::{self, LookupTarget},
+};
+
+/// The shell command converting `image`. `%s` in `template` is replaced by its quoted path, which
+/// is appended when the template doesn't mention it.
+pub fn command(template: &str, image: &Path) -> String {
+    let path = lookup::shell_quote(&image.to_string_lossy());
+    if template.contains("%s") {
+        lookup::fill_template(template, &path)
+    } else {
+        format!("{template} {path}")
+    }
+}
+
+/// The LaTeX in the output of a converter. Converters tend to log progress before the result, so
+/// only the last line is used, and pix2tex starts it with the name of the file it read.
+pub fn latex_from_output(output: &str, image: &Path) -> Option<String> {
+    let line = output.lines().map(str::trim).rfind(|l| !l.is_empty())?;
+    let line = line
+        .strip_prefix(&*image.to_string_lossy())
+        .and_then(|rest| rest.strip_prefix(':'))
+        .unwrap_or(line)
+        .trim();
+    (!line.is_empty()).then(|| line.to_string())
+}
+
+/// Runs the converter in `template` on `image`, which is deleted afterwards, and copies the LaTeX
+/// it returns.
+pub async fn copy_as_latex(template: String, image: PathBuf) -> Result<(), MiroError> {
+    let output = lookup::output(LookupTarget::Command(command(&template, &image))).await;
+    let _ = std::fs::remove_file(&image);
+    let output =
+        output.map_err(|e| MiroError::external(format!("The formula converter failed: {e}")))?;
+    let latex = latex_from_output(&output, &image)
+        .ok_or_else(|| MiroError::external("The formula converter didn't print any LaTeX"))?;
+    copy_to_clipboard(latex).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn the_image_is_passed_to_the_converter() {
+        let image = Path::new("/tmp/miro-paper-p3.png");
+        assert_eq!(
+            command("pix2tex", image),
+            "pix2tex '/tmp/miro-paper-p3.png'"
+        );
+        assert_eq!(
+            command("convert-formula --in %s --out -", image),
+            "convert-formula --in '/tmp/miro-paper-p3.png' --out -"
+        );
+    }
+
+    #[test]
+    fn latex_is_taken_from_the_last_line() {
+        let image = Path::new("/tmp/miro-paper-p3.png");
+        assert_eq!(
+            latex_from_output(
+                "Loading model\n/tmp/miro-paper-p3.png: e^{i\\pi}+1=0\n\n",
+                image
+            ),
+            Some("e^{i\\pi}+1=0".to_string())
+        );
+        assert_eq!(
+            latex_from_output("\\frac{a}{b}", image),
+            Some("\\frac{a}{b}".to_string())
+        );
+        assert_eq!(latex_from_output(" \n", image), None);
+    }
+}
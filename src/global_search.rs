@@ -0,0 +1,208 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    geometry::Rect,
+    pdf::{SearchFlags, SearchMatch, SearchMethod, find_search_matches},
+};
+
+/// Characters of context shown on either side of a match
+const SNIPPET_CONTEXT: usize = 40;
+/// Matches listed per document, the rest are only counted
+pub const MAX_LISTED_MATCHES: usize = 200;
+
+#[derive(Debug, Clone)]
+pub struct GlobalMatch {
+    pub page: usize,
+    /// The matched text with some of the text around it, on a single line
+    pub snippet: String,
+    pub found: SearchMatch,
+}
+
+/// The matches in one of the open documents.
+#[derive(Debug, Clone)]
+pub struct DocumentMatches {
+    pub path: PathBuf,
+    pub name: String,
+    /// `None` while the document is still being searched
+    pub matches: Option<Vec<GlobalMatch>>,
+    /// Every match, including the ones that aren't listed
+    pub total: usize,
+}
+
+/// Searches through every open tab at once.
+#[derive(Debug, Default)]
+pub struct GlobalSearch {
+    pub needle: String,
+    pub method: SearchMethod,
+    pub flags: SearchFlags,
+    /// Incremented on every search so results of an older needle are dropped
+    generation: u64,
+    pub documents: Vec<DocumentMatches>,
+    /// Index into all listed matches, counted across documents
+    pub selected: usize,
+}
+
+impl GlobalSearch {
+    pub fn new(method: SearchMethod) -> Self {
+        Self {
+            method,
+            ..Default::default()
+        }
+    }
+
+    /// Forgets the previous results. Returns the generation the results of the new search have
+    /// to be reported with.
+    pub fn restart(&mut self, documents: Vec<(PathBuf, String)>) -> u64 {
+        self.generation = self.generation.wrapping_add(1);
+        self.selected = 0;
+        self.documents = documents
+            .into_iter()
+            .map(|(path, name)| DocumentMatches {
+                path,
+                name,
+                matches: None,
+                total: 0,
+            })
+            .collect();
+        self.generation
+    }
+
+    pub fn finish(&mut self, generation: u64, path: &Path, mut matches: Vec<GlobalMatch>) {
+        if generation != self.generation {
+            return;
+        }
+        if let Some(document) = self.documents.iter_mut().find(|d| d.path == path) {
+            document.total = matches.len();
+            matches.truncate(MAX_LISTED_MATCHES);
+            document.matches = Some(matches);
+        }
+        self.selected = self.selected.min(self.listed().saturating_sub(1));
+    }
+
+    /// Matches listed so far, counted across documents.
+    pub fn listed(&self) -> usize {
+        self.documents
+            .iter()
+            .filter_map(|d| d.matches.as_ref())
+            .map(Vec::len)
+            .sum()
+    }
+
+    pub fn move_selection(&mut self, delta: i32) {
+        let listed = self.listed();
+        if listed == 0 {
+            return;
+        }
+        self.selected = (self.selected as i64 + delta as i64).clamp(0, listed as i64 - 1) as usize;
+    }
+
+    /// The document and match of the `idx`-th listed match.
+    pub fn get(&self, idx: usize) -> Option<(&Path, &GlobalMatch)> {
+        self.documents
+            .iter()
+            .flat_map(|d| {
+                d.matches
+                    .iter()
+                    .flatten()
+                    .map(move |m| (d.path.as_path(), m))
+            })
+            .nth(idx)
+    }
+}
+
+/// Finds `needle` in the text of a document, meant to be run on a blocking thread.
+pub fn search_document(
+    text: Arc<str>,
+    char_bboxes: Arc<[(usize, usize, Rect<f32>)]>,
+    needle: &str,
+    method: SearchMethod,
+    flags: SearchFlags,
+) -> Vec<GlobalMatch> {
+    find_search_matches(&text, needle, method, flags, &char_bboxes)
+        .into_iter()
+        .map(|found| GlobalMatch {
+            page: found.pages.start,
+            snippet: snippet(&text, found.start_byte, found.end_byte),
+            found,
+        })
+        .collect()
+}
+
+/// The text from `start` to `end` with up to [SNIPPET_CONTEXT] characters on either side and all
+/// whitespace collapsed into single spaces.
+fn snippet(text: &str, start: usize, end: usize) -> String {
+    let before_start = text[..start]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map(|(i, _)| i);
+    let after_end = text[end..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map(|(i, _)| end + i);
+
+    let mut out = String::new();
+    if before_start.is_some() {
+        out.push('…');
+    }
+    let excerpt = &text[before_start.unwrap_or(0)..after_end.unwrap_or(text.len())];
+    out.push_str(&excerpt.split_whitespace().collect::<Vec<_>>().join(" "));
+    if after_end.is_some() {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_are_shortened_on_one_line() {
+        let text = format!(
+            "{}needle\nin a\n\nhaystack{}",
+            "a".repeat(60),
+            "b".repeat(60)
+        );
+        let start = text.find("needle").unwrap();
+        let snippet = snippet(&text, start, start + "needle".len());
+        assert_eq!(
+            snippet,
+            format!("…{}needle in a haystack{}…", "a".repeat(40), "b".repeat(25))
+        );
+        assert_eq!(super::snippet("short one", 0, 5), "short one");
+    }
+
+    #[test]
+    fn selection_spans_documents_and_ignores_stale_results() {
+        let found = |page| GlobalMatch {
+            page,
+            snippet: String::new(),
+            found: SearchMatch {
+                start_byte: 0,
+                end_byte: 0,
+                pages: page..page + 1,
+                rects: vec![],
+            },
+        };
+        let mut search = GlobalSearch::new(SearchMethod::PlainText);
+        let old = search.restart(vec![("a.pdf".into(), "a".into())]);
+        let generation = search.restart(vec![
+            ("a.pdf".into(), "a".into()),
+            ("b.pdf".into(), "b".into()),
+        ]);
+        search.finish(old, Path::new("a.pdf"), vec![found(9)]);
+        assert_eq!(search.listed(), 0);
+
+        search.finish(generation, Path::new("b.pdf"), vec![found(3)]);
+        search.finish(generation, Path::new("a.pdf"), vec![found(1), found(2)]);
+        search.move_selection(5);
+        let (path, m) = search.get(search.selected).unwrap();
+        assert_eq!((path, m.page), (Path::new("b.pdf"), 3));
+        search.move_selection(-1);
+        assert_eq!(search.get(search.selected).unwrap().1.page, 2);
+    }
+}
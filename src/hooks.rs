@@ -0,0 +1,121 @@
+use std::path::Path;
+
+use strum::{Display, EnumString};
+use tracing::error;
+
+use crate::lookup;
+
+/// Events which can trigger a command configured with `On <Event> "<command>"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+pub enum HookEvent {
+    FileOpened,
+    FileClosed,
+    /// The file was modified on disk and reloaded
+    FileChanged,
+    PageChanged,
+}
+
+/// Values substituted into a hook command before it is run.
+#[derive(Debug, Clone, Copy)]
+pub struct HookContext<'a> {
+    pub path: &'a Path,
+    /// 0-indexed, shown 1-indexed to the command
+    pub page: usize,
+    pub page_count: usize,
+}
+
+/// Replaces `%f` with the file path, `%p` with the page number, `%n` with the page count and `%%`
+/// with a literal `%`. Unknown placeholders are left untouched. The path is quoted for the shell
+/// since file names can contain anything, like spaces or `;`.
+pub fn expand_placeholders(command: &str, ctx: &HookContext) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut chars = command.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            out.push(ch);
+            continue;
+        }
+        match chars.peek() {
+            Some('f') => out.push_str(&lookup::shell_quote(&ctx.path.to_string_lossy())),
+            Some('p') => out.push_str(&(ctx.page + 1).to_string()),
+            Some('n') => out.push_str(&ctx.page_count.to_string()),
+            Some('%') => out.push('%'),
+            _ => {
+                out.push('%');
+                continue;
+            }
+        }
+        chars.next();
+    }
+    out
+}
+
+/// `command` wrapped in the platform shell.
+pub fn shell_command(command: &str) -> tokio::process::Command {
+    #[cfg(windows)]
+    let process = {
+        let mut p = tokio::process::Command::new("cmd");
+        p.arg("/C").arg(command);
+        p
+    };
+    #[cfg(not(windows))]
+    let process = {
+        let mut p = tokio::process::Command::new("sh");
+        p.arg("-c").arg(command);
+        p
+    };
+    process
+}
+
+/// Runs `command` through the platform shell without waiting on it in the ui thread.
+pub async fn run(command: String) {
+    match shell_command(&command).status().await {
+        Ok(status) if !status.success() => {
+            error!("Hook command '{command}' exited with {status}");
+        }
+        Ok(_) => {}
+        Err(e) => error!("Couldn't run hook command '{command}': {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_all_placeholders() {
+        let ctx = HookContext {
+            path: Path::new("/docs/paper.pdf"),
+            page: 4,
+            page_count: 12,
+        };
+        assert_eq!(
+            expand_placeholders("notify-send 'page %p/%n' 100%%", &ctx),
+            "notify-send 'page 5/12' 100%"
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn paths_are_quoted() {
+        let ctx = HookContext {
+            path: Path::new("/docs/my notes; rm -rf ~/it's $(id).pdf"),
+            page: 0,
+            page_count: 1,
+        };
+        assert_eq!(
+            expand_placeholders("open %f", &ctx),
+            r"open '/docs/my notes; rm -rf ~/it'\''s $(id).pdf'"
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_placeholders_alone() {
+        let ctx = HookContext {
+            path: Path::new("a.pdf"),
+            page: 0,
+            page_count: 1,
+        };
+        assert_eq!(expand_placeholders("echo %x %", &ctx), "echo %x %");
+    }
+}
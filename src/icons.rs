@@ -1,22 +1,107 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+
 use iced::{
     Length, Theme,
     widget::{self, svg},
 };
 
+use crate::paths;
+
 const SVG_DELETE: &[u8] = include_bytes!("../assets/icons/delete.svg");
 const SVG_TABLE_OF_CONTENTS: &[u8] = include_bytes!("../assets/icons/table_of_contents.svg");
 const SVG_BOOKMARK: &[u8] = include_bytes!("../assets/icons/bookmark.svg");
+const SVG_CHART_COLUMN: &[u8] = include_bytes!("../assets/icons/chart_column.svg");
+const SVG_MESSAGE_SQUARE: &[u8] = include_bytes!("../assets/icons/message_square.svg");
+const SVG_LAYERS: &[u8] = include_bytes!("../assets/icons/layers.svg");
+
+/// Handles are created the first time an icon is shown and reused after that, so neither the
+/// bundled svg nor a replacement from the icon directory is looked at before it is needed.
+static HANDLES: LazyLock<Mutex<HashMap<Icon, svg::Handle>>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Icon {
+    Delete,
+    TableOfContents,
+    Bookmark,
+    ChartColumn,
+    MessageSquare,
+    Layers,
+}
+
+impl Icon {
+    /// The name of the file replacing the icon in the icon directory
+    fn file_name(self) -> &'static str {
+        match self {
+            Icon::Delete => "delete.svg",
+            Icon::TableOfContents => "table_of_contents.svg",
+            Icon::Bookmark => "bookmark.svg",
+            Icon::ChartColumn => "chart_column.svg",
+            Icon::MessageSquare => "message_square.svg",
+            Icon::Layers => "layers.svg",
+        }
+    }
+
+    fn bundled(self) -> &'static [u8] {
+        match self {
+            Icon::Delete => SVG_DELETE,
+            Icon::TableOfContents => SVG_TABLE_OF_CONTENTS,
+            Icon::Bookmark => SVG_BOOKMARK,
+            Icon::ChartColumn => SVG_CHART_COLUMN,
+            Icon::MessageSquare => SVG_MESSAGE_SQUARE,
+            Icon::Layers => SVG_LAYERS,
+        }
+    }
+}
+
+/// Icons in this directory (`icons` next to the config file) replace the bundled ones with the
+/// same file name.
+fn icon_dir() -> Option<PathBuf> {
+    paths::config_dir().ok().map(|dir| dir.join("icons"))
+}
+
+/// The svg for `icon`, taken from `dir` if it has a replacement for it.
+fn load(icon: Icon, dir: Option<&Path>) -> svg::Handle {
+    match dir.map(|dir| dir.join(icon.file_name())) {
+        Some(path) if path.is_file() => svg::Handle::from_path(path),
+        _ => svg::Handle::from_memory(icon.bundled()),
+    }
+}
+
+pub fn handle(icon: Icon) -> svg::Handle {
+    HANDLES
+        .lock()
+        .unwrap()
+        .entry(icon)
+        .or_insert_with(|| load(icon, icon_dir().as_deref()))
+        .clone()
+}
 
 pub fn delete() -> svg::Handle {
-    svg::Handle::from_memory(SVG_DELETE)
+    handle(Icon::Delete)
 }
 
 pub fn table_of_contents() -> svg::Handle {
-    svg::Handle::from_memory(SVG_TABLE_OF_CONTENTS)
+    handle(Icon::TableOfContents)
 }
 
 pub fn bookmark() -> svg::Handle {
-    svg::Handle::from_memory(SVG_BOOKMARK)
+    handle(Icon::Bookmark)
+}
+
+pub fn chart_column() -> svg::Handle {
+    handle(Icon::ChartColumn)
+}
+
+pub fn message_square() -> svg::Handle {
+    handle(Icon::MessageSquare)
+}
+
+pub fn layers() -> svg::Handle {
+    handle(Icon::Layers)
 }
 
 #[allow(unused)]
@@ -86,3 +171,29 @@ pub fn icon_button<'a, T>(
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icons_from_the_icon_directory_replace_bundled_ones() {
+        let dir = std::env::temp_dir().join(format!("miro-icons-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("bookmark.svg"), "<svg/>").unwrap();
+
+        assert_eq!(
+            load(Icon::Bookmark, Some(&dir)),
+            svg::Handle::from_path(dir.join("bookmark.svg"))
+        );
+        assert_eq!(
+            load(Icon::Delete, Some(&dir)),
+            svg::Handle::from_memory(SVG_DELETE)
+        );
+        assert_eq!(
+            load(Icon::Delete, None),
+            svg::Handle::from_memory(SVG_DELETE)
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -14,6 +14,12 @@ pub struct JumpLocation {
     pub pdf_path: PathBuf,
     pub translation: Vector<f32>,
     pub scale: f32,
+    /// Only used for display, 0-indexed
+    #[serde(default)]
+    pub page: usize,
+    /// Title of the outline entry containing [JumpLocation::page], only used for display
+    #[serde(default)]
+    pub section: Option<String>,
 }
 
 impl JumpLocation {
@@ -32,7 +38,7 @@ impl JumpLocation {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Jumplist {
     pub entries: VecDeque<JumpLocation>,
     pub current_index: usize,
@@ -83,6 +89,15 @@ impl Jumplist {
         self.entries.get(self.current_index)
     }
 
+    /// Moves directly to entry `idx` as if jumping back or forward several times.
+    pub fn jump_to(&mut self, idx: usize) -> Option<&JumpLocation> {
+        if idx >= self.len() {
+            return None;
+        }
+        self.current_index = idx;
+        self.entries.get(self.current_index)
+    }
+
     pub fn jump_forward(&mut self) -> Option<&JumpLocation> {
         if self.len() <= 1 || self.current_index >= self.len() - 1 {
             return None;
@@ -97,3 +112,31 @@ impl Default for Jumplist {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(y: f32) -> JumpLocation {
+        JumpLocation {
+            pdf_path: PathBuf::from("/a.pdf"),
+            translation: Vector::new(0.0, y),
+            scale: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn jumping_to_an_entry_keeps_the_history() {
+        let mut jumplist = Jumplist::new();
+        for y in [0.0, 100.0, 200.0, 300.0] {
+            jumplist.push(location(y));
+        }
+
+        assert_eq!(jumplist.jump_to(1).unwrap().translation.y, 100.0);
+        assert_eq!(jumplist.len(), 4);
+        assert_eq!(jumplist.jump_forward().unwrap().translation.y, 200.0);
+        assert!(jumplist.jump_to(4).is_none());
+        assert_eq!(jumplist.current_index, 2);
+    }
+}
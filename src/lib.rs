@@ -0,0 +1,597 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Read},
+    path::{Path, PathBuf},
+    sync::{LazyLock, RwLock},
+    time::SystemTime,
+};
+
+use anyhow::anyhow;
+use app::App;
+use bookmarks::BookmarkStore;
+use build::BuildWatch;
+use clap::{Parser, Subcommand};
+use reading_stats::ReadingStats;
+use recent_files::RecentFiles;
+use session::SessionStore;
+use config::Config;
+use iced::{window::icon::from_file_data, Color, Font, Theme};
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+use crate::app::AppMessage;
+
+mod app;
+#[cfg(any(test, feature = "bench"))]
+pub mod bench;
+mod bookmarks;
+mod build;
+mod check;
+mod closed_tabs;
+mod common_menu;
+mod config;
+mod desktop_entry;
+mod download;
+mod error;
+mod formula;
+mod geometry;
+mod global_search;
+mod hooks;
+mod icons;
+mod jumplist;
+mod lookup;
+mod metadata;
+mod paths;
+mod pdf;
+mod platform_specific;
+mod preferences;
+mod quick_switcher;
+mod reading_stats;
+mod recent_files;
+mod rpc;
+mod screenshot;
+mod scrubber;
+mod session;
+mod tab_color;
+mod tab_history;
+mod tabs;
+mod watch;
+
+// of the screen
+// TODO: Figure out why hovering over a menu disables all other inputs/buttons in the program (write
+// my own menu??)
+
+const DARK_THEME: Theme = Theme::TokyoNight;
+
+static CONFIG: LazyLock<RwLock<Config>> = LazyLock::new(|| RwLock::new(Config::default()));
+
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    name = "miro",
+    about = "A pdf viewer",
+    args_conflicts_with_subcommands = true
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(
+        value_name = "PATH",
+        help = "The pdf to open, or - to read it from stdin"
+    )]
+    path: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        help = "Launch the program in fullscreen mode (can be combined with --presentation)"
+    )]
+    fullscreen: bool,
+    #[arg(
+        short,
+        long,
+        help = "Launch the program in presentation mode (can be combined with --fullscreen)"
+    )]
+    presentation: bool,
+    #[arg(
+        long,
+        value_name = "URL",
+        conflicts_with = "path",
+        help = "Download a pdf from the specified URL into the cache and open it, the same as passing the URL as PATH"
+    )]
+    url: Option<String>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Open the documents of the workspace NAME saved from the File menu"
+    )]
+    workspace: Option<String>,
+    #[arg(long, value_name = "FILE", help = "Read the configuration from FILE")]
+    config: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Keep the configuration, bookmarks and other state in a directory next to the executable"
+    )]
+    portable: bool,
+    #[arg(
+        long,
+        help = "Don't write bookmarks, sessions, annotations or any other state, for presenting or read-only file systems"
+    )]
+    read_only: bool,
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        help = "Run COMMAND whenever a source file in the build directory changes and reload the pdf when it succeeds"
+    )]
+    watch_build: Option<String>,
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "The directory watched by --watch-build, defaults to the current directory"
+    )]
+    build_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Open pdfs added to DIR in new tabs, for example downloaded papers"
+    )]
+    watch_dir: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Control a running instance through its RPC server (requires `Set Rpc True`)
+    #[command(subcommand)]
+    Ctl(rpc::CtlCommand),
+    /// Report broken cross-reference tables, pages that fail to load and fonts that aren't
+    /// embedded. Exits with 1 if any file has problems
+    Check {
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Render a page the way the viewer shows it to a PNG file without opening a window, to
+    /// attach to bug reports
+    Screenshot {
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+        /// The page to render, counted from 1
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+        #[arg(long, value_name = "PNG")]
+        out: PathBuf,
+        /// Pixels per point of the page, 1 is the size of the page at 100% zoom
+        #[arg(long, default_value_t = 1.0)]
+        scale: f32,
+        /// Recolor the page like the dark mode of the viewer
+        #[arg(long)]
+        dark: bool,
+    },
+    /// Register miro with the file manager so pdfs can be opened with it, as a desktop entry and
+    /// default application on Linux or in "Open with" on Windows
+    InstallDesktopEntry {
+        /// Remove the registration again
+        #[arg(long)]
+        uninstall: bool,
+    },
+    /// Print where the config file is read from
+    Config {
+        /// Print every setting with the values it takes, its default and its current value
+        #[arg(long)]
+        list: bool,
+    },
+}
+
+fn bytes_to_tmp(bytes: &[u8], file_prefix: &str) -> anyhow::Result<PathBuf> {
+    let ts = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let tmp = std::env::temp_dir().join(format!("miro-{file_prefix}-{ts}.pdf"));
+    match fs::write(&tmp, bytes) {
+        Ok(_) => Ok(tmp),
+        Err(e) => Err(anyhow!("{e}")),
+    }
+}
+
+/// Parses the command line and runs the viewer, or the subcommand it names.
+pub fn run() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_writer(io::stdout)
+        .with_env_filter(EnvFilter::new("miro"))
+        .init();
+
+    let mut args = Args::parse();
+    paths::init(args.config.clone(), args.portable, args.read_only);
+
+    // NOTE: Used to automatically delete the file when exiting the program (normally or when
+    // crashing)
+    let mut tmp_file = None;
+    // Read explicitly with `-`, kept in memory only
+    let mut stdin_pdf = None;
+    if args.path.as_deref() == Some(Path::new("-")) {
+        let mut bytes = Vec::new();
+        match io::stdin().read_to_end(&mut bytes) {
+            Ok(_) => stdin_pdf = Some(bytes),
+            Err(e) => eprintln!("Failed to read from stdin: {e}"),
+        }
+        args.path = None;
+    } else if !io::stdin().is_terminal()
+        && args.path.is_none()
+        && args.url.is_none()
+        && args.command.is_none()
+    {
+        let mut bytes = Vec::new();
+        match io::stdin().read_to_end(&mut bytes) {
+            // Nothing may be written, not even a temporary file
+            Ok(_) if paths::read_only() => stdin_pdf = Some(bytes),
+            Ok(_) => match bytes_to_tmp(&bytes, "stdin") {
+                Ok(tmp) => {
+                    args.path = Some(tmp.clone());
+                    tmp_file = Some(TempFile(tmp.clone()));
+                }
+                Err(e) => {
+                    eprintln!("Failed to write to temporary file: {e}");
+                }
+            },
+            Err(e) => {
+                eprintln!("Failed to read from stdin: {e}");
+            }
+        }
+    }
+
+    if let Some(path) = args.path.as_ref().and_then(|p| p.to_str())
+        && download::is_url(path)
+    {
+        args.url = Some(path.to_string());
+        args.path = None;
+    }
+
+    if let Err(e) = paths::create_dirs() {
+        eprintln!("Couldn't create the required config directories: {e}");
+    }
+
+    match Config::system_config() {
+        Ok(cfg) => {
+            let mut config = CONFIG.write().unwrap();
+            *config = cfg;
+            info!(
+                "Using system config file located at {}",
+                Config::system_config_path()
+                    .expect(
+                        "Managed to load a config file without being able to determine its location"
+                    )
+                    .canonicalize()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+            );
+        }
+        Err(e) if args.config.is_some() => {
+            eprintln!("Couldn't read the config file: {e}");
+        }
+        Err(_) => {}
+    }
+
+    match args.command.take() {
+        Some(Command::Ctl(command)) => return rpc::ctl(command),
+        Some(Command::Check { files }) => {
+            if !check::cli(&files) {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Screenshot {
+            file,
+            page,
+            out,
+            scale,
+            dark,
+        }) => return screenshot::cli(&file, page, &out, scale, dark),
+        Some(Command::InstallDesktopEntry { uninstall }) => return desktop_entry::cli(uninstall),
+        Some(Command::Config { list }) => {
+            if list {
+                print!("{}", config::list_settings(&CONFIG.read().unwrap()));
+            } else {
+                println!("{}", Config::system_config_path()?.display());
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+
+    let build_watch = match args.watch_build.clone() {
+        Some(command) => {
+            let dir = match args.build_dir.clone() {
+                Some(dir) => dir,
+                None => std::env::current_dir()?,
+            };
+            Some(BuildWatch {
+                command,
+                dir: dir.canonicalize()?,
+            })
+        }
+        None => None,
+    };
+
+    let cfg_fullscreen;
+    let cfg_presentation;
+    let cfg_follow_system_theme;
+    {
+        let config = CONFIG.read().unwrap();
+        cfg_presentation = config.open_presentation_default;
+        cfg_fullscreen = config.open_fullscreen_default;
+        cfg_follow_system_theme = config.follow_system_theme;
+    }
+    Ok(iced::application(
+        move || {
+            let path = args.path.clone();
+            let state: App = App::new(
+                BookmarkStore::system_store().unwrap_or_default(),
+                RecentFiles::system_store().unwrap_or_default(),
+                ReadingStats::system_store().unwrap_or_default(),
+                SessionStore::system_store().unwrap_or_default(),
+            );
+            let startup_tasks = match (path, stdin_pdf.clone()) {
+                (_, Some(bytes)) => {
+                    iced::Task::done(app::AppMessage::OpenInMemory("stdin".to_string(), bytes))
+                }
+                (Some(p), None) if tmp_file.is_some() => {
+                    iced::Task::done(app::AppMessage::OpenTempFile(p))
+                }
+                (Some(p), None) => iced::Task::done(app::AppMessage::OpenFile(p)),
+                (None, None) => match args.url.clone() {
+                    Some(url) => iced::Task::done(app::AppMessage::OpenUrl(url)),
+                    None => iced::Task::none(),
+                },
+            };
+            let mut startup_tasks =
+                startup_tasks.chain(iced::window::latest().map(app::AppMessage::FoundWindowId));
+
+            for task in platform_specific::startup_tasks().into_iter() {
+                startup_tasks = startup_tasks.chain(task);
+            }
+
+            if cfg_follow_system_theme {
+                startup_tasks =
+                    startup_tasks.chain(iced::system::theme().map(AppMessage::SystemThemeChanged));
+            }
+
+            if let Some(name) = args.workspace.clone() {
+                startup_tasks =
+                    startup_tasks.chain(iced::Task::done(AppMessage::OpenWorkspace(name)));
+            }
+
+            if let Some(dir) = args.watch_dir.clone() {
+                startup_tasks =
+                    startup_tasks.chain(iced::Task::done(AppMessage::WatchDirectory(dir)));
+            }
+
+            if let Some(watch) = build_watch.clone() {
+                startup_tasks =
+                    startup_tasks.chain(iced::Task::done(AppMessage::StartBuildWatch(watch)));
+            }
+
+            // NOTE: The default state is in windowed, non presentation mode. Using the toggles is
+            // thus deterministic.
+            if args.fullscreen || cfg_fullscreen {
+                startup_tasks = startup_tasks.chain(iced::Task::done(AppMessage::ToggleFullscreen));
+            }
+            if args.presentation || cfg_presentation {
+                startup_tasks =
+                    startup_tasks.chain(iced::Task::done(AppMessage::TogglePresentationMode));
+            }
+
+            (state, startup_tasks)
+        },
+        App::update,
+        App::view,
+    )
+    .title(App::title)
+    .antialiasing(true)
+    .theme(theme)
+    .subscription(App::subscription)
+    .window(settings())
+    .font(include_bytes!("../assets/font/Geist-VariableFont_wght.ttf").as_slice())
+    .default_font(Font::with_name("Geist"))
+    .run()?)
+}
+
+fn theme(app: &App) -> Theme {
+    use iced::theme::palette::{*};
+
+    // TODO: Custom themes for UI, and maybe custom pdf background color (perhaps in json files?).
+    let miro_light = Theme::custom_with_fn(
+        "Miro Light".to_string(),
+        iced::theme::Palette {
+            background: Color::from_rgb8(240, 239, 238),
+            text: Color::from_rgb8(30, 30, 30),
+            primary: Color::from_rgb8(167, 143, 135),
+            success: Color::from_rgb8(0, 255, 0),
+            warning: Color::from_rgb8(255, 165, 0),
+            danger: Color::from_rgb8(255, 0, 0),
+        },
+        |palette: Palette| {
+            let bg = Background::new(palette.background, palette.text);
+            let secondary = Secondary::generate(palette.background, palette.text);
+            Extended {
+                background: Background {
+                    base: Pair {
+                        color: Color::from_rgb8(240, 239, 238),
+                        text: Color::from_rgb8(30, 30, 30),
+                    },
+                    weak: Pair {
+                        color: Color::from_rgb8(255, 255, 255),
+                        text: Color::from_rgb8(30, 30, 30),
+                    },
+                    strong: Pair {
+                        color: Color::from_rgb8(187, 184, 187),
+                        text: Color::from_rgb8(255, 255, 255),
+                    },
+                    weakest: bg.weakest,
+                    weaker: bg.weaker,
+                    neutral: bg.neutral,
+                    stronger: bg.stronger,
+                    strongest: bg.strongest,
+                },
+                primary: Primary {
+                    base: Pair {
+                        color: Color::from_rgb8(167, 143, 135),
+                        text: Color::from_rgb8(255, 255, 255),
+                    },
+                    weak: Pair {
+                        color: Color::from_rgb8(228, 226, 226),
+                        text: Color::from_rgb8(255, 255, 255),
+                    },
+                    strong: Pair {
+                        color: Color::from_rgb8(147, 123, 115),
+                        text: Color::from_rgb8(255, 255, 255),
+                    },
+                },
+                secondary: Secondary {
+                    base: Pair {
+                        color: Color::from_rgb8(217, 217, 217),
+                        text: Color::from_rgb8(122, 122, 122),
+                    },
+                    weak: secondary.weak,
+                    strong: secondary.strong,
+                },
+                success: Success::generate(palette.success, palette.background, palette.text),
+                warning: Warning::generate(palette.warning, palette.background, palette.text),
+                danger: Danger {
+                    base: Pair {
+                        color: Color::from_rgb8(167, 143, 135),
+                        text: Color::from_rgb8(255, 255, 255),
+                    },
+                    weak: Pair {
+                        color: Color::from_rgb8(228, 226, 226),
+                        text: Color::from_rgb8(30, 30, 30),
+                    },
+                    strong: Pair {
+                        color: Color::from_rgb8(147, 123, 115),
+                        text: Color::from_rgb8(255, 255, 255),
+                    },
+                },
+                is_dark: false,
+            }
+        },
+    );
+    let miro_dark = Theme::custom_with_fn(
+        "Miro Dark".to_string(),
+        iced::theme::Palette {
+            background: Color::from_rgb8(26, 27, 38),
+            text: Color::from_rgb8(154, 165, 206),
+            primary: Color::from_rgb8(42, 195, 222),
+            success: Color::from_rgb8(158, 206, 106),
+            warning: Color::from_rgb8(255, 165, 0),
+            danger: Color::from_rgb8(247, 118, 142),
+        },
+        |palette: Palette| {
+            let bg = Background::new(palette.background, palette.text);
+            Extended {
+                background: Background {
+                    base: Pair {
+                        color: Color::from_rgb8(26, 27, 38),
+                        text: Color::from_rgb8(154, 165, 206),
+                    },
+                    weak: Pair {
+                        color: Color::from_rgb8(36, 40, 59),
+                        text: Color::from_rgb8(154, 165, 206),
+                    },
+                    strong: Pair {
+                        color: Color::from_rgb8(51, 56, 71),
+                        text: Color::from_rgb8(154, 165, 206),
+                    },
+                    weakest: bg.weakest,
+                    weaker: bg.weaker,
+                    neutral: bg.neutral,
+                    stronger: bg.stronger,
+                    strongest: bg.strongest,
+                },
+                primary: Primary {
+                    base: Pair {
+                        color: Color::from_rgb8(42, 195, 222),
+                        text: Color::from_rgb8(26, 27, 38),
+                    },
+                    weak: Pair {
+                        color: Color::from_rgb8(73, 219, 240),
+                        text: Color::from_rgb8(26, 27, 38),
+                    },
+                    strong: Pair {
+                        color: Color::from_rgb8(21, 171, 204),
+                        text: Color::from_rgb8(255, 255, 255),
+                    },
+                },
+                secondary: Secondary {
+                    base: Pair {
+                        color: Color::from_rgb8(51, 56, 71),
+                        text: Color::from_rgb8(154, 165, 206),
+                    },
+                    weak: Pair {
+                        color: Color::from_rgb8(68, 75, 95),
+                        text: Color::from_rgb8(154, 165, 206),
+                    },
+                    strong: Pair {
+                        color: Color::from_rgb8(34, 39, 47),
+                        text: Color::from_rgb8(154, 165, 206),
+                    },
+                },
+                success: Success {
+                    base: Pair {
+                        color: Color::from_rgb8(158, 206, 106),
+                        text: Color::from_rgb8(26, 27, 38),
+                    },
+                    weak: Pair {
+                        color: Color::from_rgb8(180, 220, 140),
+                        text: Color::from_rgb8(26, 27, 38),
+                    },
+                    strong: Pair {
+                        color: Color::from_rgb8(136, 192, 72),
+                        text: Color::from_rgb8(255, 255, 255),
+                    },
+                },
+                warning: Warning::generate(palette.warning, palette.background, palette.text),
+                danger: Danger {
+                    base: Pair {
+                        color: Color::from_rgb8(247, 118, 142),
+                        text: Color::from_rgb8(26, 27, 38),
+                    },
+                    weak: Pair {
+                        color: Color::from_rgb8(250, 150, 170),
+                        text: Color::from_rgb8(26, 27, 38),
+                    },
+                    strong: Pair {
+                        color: Color::from_rgb8(244, 86, 114),
+                        text: Color::from_rgb8(255, 255, 255),
+                    },
+                },
+                is_dark: true,
+            }
+        },
+    );
+    match app.dark_mode {
+        true => miro_dark,
+        false => miro_light,
+    }
+}
+
+//#[cfg(target_os = "windows")]
+pub fn settings() -> iced::window::Settings {
+    use iced::window::Settings;
+
+    let icon_img = include_bytes!("../assets/logo.png");
+    let icon = from_file_data(icon_img, None).ok();
+
+    Settings {
+        icon,
+        // Closing asks about unsaved edits first, see `AppMessage::Exit`
+        exit_on_close_request: false,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::{Result, anyhow};
+use tracing::error;
+
+use crate::{
+    error::{MiroError, run_blocking},
+    hooks,
+};
+
+/// Where a lookup of the selected text goes, decided by the configured `LookupCommand`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LookupTarget {
+    /// Opened in the default browser
+    Url(String),
+    /// Run through the platform shell like a hook
+    Command(String),
+}
+
+/// Replaces `%s` in `template` with `text` and `%%` with a literal `%`. Templates starting with
+/// `http://` or `https://` are urls and get the text percent encoded, anything else is a shell
+/// command and gets it quoted.
+pub fn lookup_target(template: &str, text: &str) -> LookupTarget {
+    if template.starts_with("http://") || template.starts_with("https://") {
+        LookupTarget::Url(fill_template(template, &percent_encode(text)))
+    } else {
+        LookupTarget::Command(fill_template(template, &shell_quote(text)))
+    }
+}
+
+/// Replaces `%s` in `template` with `replacement` and `%%` with a literal `%`.
+pub fn fill_template(template: &str, replacement: &str) -> String {
+    let mut out = String::with_capacity(template.len() + replacement.len());
+    let mut chars = template.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            out.push(ch);
+            continue;
+        }
+        match chars.peek() {
+            Some('s') => out.push_str(replacement),
+            Some('%') => out.push('%'),
+            _ => {
+                out.push('%');
+                continue;
+            }
+        }
+        chars.next();
+    }
+    out
+}
+
+fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+#[cfg(not(windows))]
+pub fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+#[cfg(windows)]
+pub fn shell_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', ""))
+}
+
+pub async fn run(target: LookupTarget) {
+    if let Err(e) = open(target).await {
+        error!("{e}");
+    }
+}
+
+/// Opens the url in the default browser or runs the command, failing when the browser or command
+/// can't be started or the command exits with an error.
+pub async fn open(target: LookupTarget) -> Result<(), MiroError> {
+    match target {
+        LookupTarget::Url(url) => {
+            run_blocking(move || {
+                webbrowser::open(&url).map_err(|e| {
+                    MiroError::external(format!("Failed to open {url} in the default browser: {e}"))
+                })
+            })
+            .await
+        }
+        LookupTarget::Command(command) => match hooks::shell_command(&command).status().await {
+            Ok(status) if !status.success() => Err(MiroError::external(format!(
+                "'{command}' exited with {status}"
+            ))),
+            Ok(_) => Ok(()),
+            Err(e) => Err(MiroError::external(format!(
+                "Couldn't run '{command}': {e}"
+            ))),
+        },
+    }
+}
+
+/// Fetches the url or runs the command and returns what it answered with, used to show
+/// translations inside the viewer.
+pub async fn output(target: LookupTarget) -> Result<String> {
+    let output = match target {
+        LookupTarget::Url(url) => reqwest::get(url).await?.error_for_status()?.text().await?,
+        LookupTarget::Command(command) => {
+            let output = hooks::shell_command(&command).output().await?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "'{command}' exited with {}: {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            String::from_utf8_lossy(&output.stdout).to_string()
+        }
+    };
+    Ok(output.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_get_the_text_percent_encoded() {
+        assert_eq!(
+            lookup_target("https://en.wiktionary.org/wiki/%s", "Straße frei"),
+            LookupTarget::Url("https://en.wiktionary.org/wiki/Stra%C3%9Fe%20frei".to_string())
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn commands_get_the_text_quoted() {
+        assert_eq!(
+            lookup_target("dict %s | less 100%%", "l'amour"),
+            LookupTarget::Command(r"dict 'l'\''amour' | less 100%".to_string())
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::HashSet;
+
+use mupdf::pdf::{PdfObject, PdfPage};
+
+use crate::pdf::forms::{Fields, text_entry};
+
+/// Fonts every reader has to provide, so they don't have to be embedded outside of PDF/A
+const STANDARD_FONTS: [&str; 14] = [
+    "Times-Roman",
+    "Times-Bold",
+    "Times-Italic",
+    "Times-BoldItalic",
+    "Helvetica",
+    "Helvetica-Bold",
+    "Helvetica-Oblique",
+    "Helvetica-BoldOblique",
+    "Courier",
+    "Courier-Bold",
+    "Courier-Oblique",
+    "Courier-BoldOblique",
+    "Symbol",
+    "ZapfDingbats",
+];
+
+/// A font used by a page of the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Font {
+    /// The name without the subset prefix
+    pub name: String,
+    /// Type1, TrueType, Type0, Type3 and so on
+    pub kind: String,
+    /// Type 3 fonts are drawn by the document itself and always count as embedded
+    pub embedded: bool,
+    /// Only the glyphs used in the document are embedded, the name had a prefix like "ABCDEF+"
+    pub subset: bool,
+    /// The first page using the font
+    pub page: usize,
+}
+
+impl Font {
+    pub fn is_standard(&self) -> bool {
+        STANDARD_FONTS.contains(&self.name.as_str())
+    }
+
+    /// Fonts that aren't embedded are substituted by every reader in its own way.
+    pub fn is_missing(&self) -> bool {
+        !self.embedded && !self.is_standard()
+    }
+}
+
+/// The fonts used directly by the page on `page_idx`. Fonts in `seen` are skipped and the new
+/// ones are added to it, since most fonts are shared between pages.
+pub fn page_fonts(page: &PdfPage, page_idx: usize, seen: &mut HashSet<i32>) -> Vec<Font> {
+    let mut fonts = vec![];
+    let Ok(Some(dict)) = page
+        .object()
+        .get_dict_inheritable("Resources")
+        .and_then(|resources| match resources {
+            Some(resources) => resources.get_dict("Font"),
+            None => Ok(None),
+        })
+    else {
+        return fonts;
+    };
+    for i in 0..dict.dict_len().unwrap_or(0) as i32 {
+        let Ok(Some(font)) = dict.get_dict_val(i) else {
+            continue;
+        };
+        if font.is_indirect().unwrap_or(false)
+            && let Ok(num) = font.as_indirect()
+            && !seen.insert(num)
+        {
+            continue;
+        }
+        if let Some(font) = describe_font(&font, page_idx) {
+            fonts.push(font);
+        }
+    }
+    fonts
+}
+
+fn describe_font(font: &PdfObject, page: usize) -> Option<Font> {
+    let name_of = |key: &str, obj: &PdfObject| -> Option<String> {
+        let name = obj.get_dict(key).ok()??;
+        Some(String::from_utf8_lossy(name.as_name().ok()?).to_string())
+    };
+    let kind = name_of("Subtype", font)?;
+    let base_font = name_of("BaseFont", font).unwrap_or_else(|| "(unnamed)".to_string());
+    let (subset, name) = split_subset(&base_font);
+    let described = if kind == "Type0" {
+        font.get_dict("DescendantFonts")
+            .ok()??
+            .get_array(0)
+            .ok()??
+    } else {
+        font.try_clone().ok()?
+    };
+    let embedded = kind == "Type3"
+        || described
+            .get_dict("FontDescriptor")
+            .ok()
+            .flatten()
+            .is_some_and(|descriptor| {
+                ["FontFile", "FontFile2", "FontFile3"]
+                    .iter()
+                    .any(|key| descriptor.get_dict(*key).ok().flatten().is_some())
+            });
+    Some(Font {
+        name: name.to_string(),
+        kind,
+        embedded,
+        subset,
+        page,
+    })
+}
+
+/// Splits the prefix of six capital letters off subset fonts like "ABCDEF+Helvetica".
+fn split_subset(base_font: &str) -> (bool, &str) {
+    match base_font.split_once('+') {
+        Some((tag, name)) if tag.len() == 6 && tag.bytes().all(|b| b.is_ascii_uppercase()) => {
+            (true, name)
+        }
+        _ => (false, base_font),
+    }
+}
+
+/// The boundaries a pdf page can define, from the whole sheet to the part meant to be seen
+const PAGE_BOXES: [&str; 5] = ["MediaBox", "CropBox", "BleedBox", "TrimBox", "ArtBox"];
+
+/// A boundary of a page in page space, labeled with the names of every box with these bounds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageBox {
+    pub names: String,
+    pub rect: mupdf::Rect,
+}
+
+/// The boxes `page` defines. Boxes with the same bounds, like a crop box that is the media box,
+/// are listed once.
+pub fn page_boxes(page: &PdfPage) -> Vec<PageBox> {
+    let Ok(ctm) = page.ctm() else {
+        return vec![];
+    };
+    let mut boxes: Vec<PageBox> = vec![];
+    for name in PAGE_BOXES {
+        // Only the media and crop box are inherited from the page tree
+        let obj = if name == "MediaBox" || name == "CropBox" {
+            page.object().get_dict_inheritable(name)
+        } else {
+            page.object().get_dict(name)
+        };
+        let Some(rect) = obj.ok().flatten().as_ref().and_then(box_rect) else {
+            continue;
+        };
+        let rect = rect.transform(&ctm);
+        match boxes.iter_mut().find(|b| b.rect == rect) {
+            Some(existing) => {
+                existing.names.push_str(" / ");
+                existing.names.push_str(name);
+            }
+            None => boxes.push(PageBox {
+                names: name.to_string(),
+                rect,
+            }),
+        }
+    }
+    boxes
+}
+
+/// A rectangle written as `[x0 y0 x1 y1]` in pdf space, where any two opposite corners can be
+/// given.
+fn box_rect(array: &PdfObject) -> Option<mupdf::Rect> {
+    let mut values = [0.0; 4];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = array.get_array(i as i32).ok()??.as_float().ok()?;
+    }
+    let [x0, y0, x1, y1] = values;
+    Some(mupdf::Rect::new(
+        x0.min(x1),
+        y0.min(y1),
+        x0.max(x1),
+        y0.max(y1),
+    ))
+}
+
+/// A signature field of the document's form. Only what the signature claims about itself is read,
+/// nothing is verified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// The full name of the field, like "part3.signature"
+    pub field: String,
+    /// Empty fields are waiting to be signed
+    pub signed: bool,
+    pub signer: Option<String>,
+    /// Formatted like "2024-01-31 12:00"
+    pub date: Option<String>,
+    pub reason: Option<String>,
+    pub location: Option<String>,
+}
+
+impl Signature {
+    /// "Signed by Jane Doe on 2024-01-31 12:00 (Approval, Berlin)" or "Not signed"
+    pub fn summary(&self) -> String {
+        if !self.signed {
+            return "Not signed".to_string();
+        }
+        let mut summary = String::from("Signed");
+        if let Some(signer) = &self.signer {
+            summary.push_str(&format!(" by {signer}"));
+        }
+        if let Some(date) = &self.date {
+            summary.push_str(&format!(" on {date}"));
+        }
+        let details: Vec<&str> = [&self.reason, &self.location]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if !details.is_empty() {
+            summary.push_str(&format!(" ({})", details.join(", ")));
+        }
+        summary
+    }
+}
+
+/// A pdf date like "D:20240131120000+01'00'" as "2024-01-31 12:00", other text as it is.
+fn format_date(date: &str) -> String {
+    let digits = date.strip_prefix("D:").unwrap_or(date);
+    let part = |range: std::ops::Range<usize>| {
+        digits
+            .get(range)
+            .filter(|p| p.bytes().all(|b| b.is_ascii_digit()))
+    };
+    match (
+        part(0..4),
+        part(4..6),
+        part(6..8),
+        part(8..10),
+        part(10..12),
+    ) {
+        (Some(y), Some(mo), Some(d), Some(h), Some(mi)) => format!("{y}-{mo}-{d} {h}:{mi}"),
+        (Some(y), Some(mo), Some(d), _, _) => format!("{y}-{mo}-{d}"),
+        _ => date.to_string(),
+    }
+}
+
+/// The signature fields of `doc`, signed or not, in the order of the form.
+pub fn signatures(doc: &mupdf::Document) -> Vec<Signature> {
+    Fields::of(doc)
+        .filter(|field| {
+            !field.has_field_kids && field.field_type.as_deref() == Some(b"Sig".as_slice())
+        })
+        .map(|field| {
+            let value = field.obj.get_dict("V").ok().flatten();
+            Signature {
+                field: field.name,
+                signed: value.is_some(),
+                signer: value.as_ref().and_then(|v| text_entry(v, "Name")),
+                date: value
+                    .as_ref()
+                    .and_then(|v| text_entry(v, "M"))
+                    .map(|date| format_date(&date)),
+                reason: value.as_ref().and_then(|v| text_entry(v, "Reason")),
+                location: value.as_ref().and_then(|v| text_entry(v, "Location")),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use mupdf::pdf::PdfDocument;
+
+    use super::*;
+
+    #[test]
+    fn subset_prefixes_are_split_off() {
+        assert_eq!(split_subset("ABCDEF+Helvetica"), (true, "Helvetica"));
+        assert_eq!(split_subset("Helvetica"), (false, "Helvetica"));
+        assert_eq!(split_subset("C++Mono"), (false, "C++Mono"));
+    }
+
+    #[test]
+    fn fonts_are_listed_once_with_their_subset_prefix_removed() {
+        let doc = mupdf::Document::open("assets/multiple-page-layouts.pdf").unwrap();
+        let mut seen_fonts = HashSet::new();
+        let mut fonts = vec![];
+        for page_idx in 0..doc.page_count().unwrap() {
+            let page = PdfPage::try_from(doc.load_page(page_idx).unwrap()).unwrap();
+            fonts.extend(page_fonts(&page, page_idx as usize, &mut seen_fonts));
+        }
+        assert!(fonts.iter().any(|f| f.name == "LiberationSerif"));
+        assert!(
+            fonts
+                .iter()
+                .all(|f| f.subset && f.embedded && !f.is_missing())
+        );
+        let mut seen = HashSet::new();
+        assert!(fonts.iter().all(|f| seen.insert((&f.name, &f.kind))));
+    }
+
+    #[test]
+    fn page_boxes_are_in_page_space() {
+        let doc = mupdf::Document::open("assets/multiple-page-layouts.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let bounds = page.bounds().unwrap();
+        let boxes = page_boxes(&PdfPage::try_from(page).unwrap());
+        assert!(boxes[0].names.starts_with("MediaBox"));
+        assert_eq!(boxes[0].rect, bounds);
+    }
+
+    #[test]
+    fn signature_fields_are_listed() {
+        let mut pdf = PdfDocument::new();
+        pdf.new_page((100.0, 100.0)).unwrap();
+        let form = pdf
+            .new_object_from_str(
+                "<< /Fields [
+                    << /T (approval) /FT /Sig /V << /Type /Sig /Name (Jane Doe) \
+                        /M (D:20240131120000+01'00') /Reason (Approved) >> >>
+                    << /T (part3) /Kids [<< /T (witness) /FT /Sig /Kids [<< /Subtype /Widget >>] >>] >>
+                    << /T (name) /FT /Tx >>
+                ] >>",
+            )
+            .unwrap();
+        pdf.catalog().unwrap().dict_put("AcroForm", form).unwrap();
+        let mut bytes = vec![];
+        pdf.write_to(&mut bytes).unwrap();
+        let doc = mupdf::Document::from_bytes(&bytes, "application/pdf").unwrap();
+
+        let signatures = signatures(&doc);
+        assert_eq!(
+            signatures
+                .iter()
+                .map(|s| (s.field.as_str(), s.summary()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "approval",
+                    "Signed by Jane Doe on 2024-01-31 12:00 (Approved)".to_string()
+                ),
+                ("part3.witness", "Not signed".to_string()),
+            ]
+        );
+        assert_eq!(format_date("yesterday"), "yesterday");
+
+        let unsigned = mupdf::Document::open("assets/links.pdf").unwrap();
+        assert!(super::signatures(&unsigned).is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::{Result, anyhow};
+use directories::ProjectDirs;
+
+const PORTABLE_DIR_NAME: &str = "miro-pdf";
+
+static LOCATIONS: OnceLock<Locations> = OnceLock::new();
+
+/// Where miro reads and writes its files. Decided once at startup from the command line flags.
+#[derive(Debug)]
+struct Locations {
+    config_file: Option<PathBuf>,
+    portable: bool,
+    /// Nothing is written at all, see [write]
+    read_only: bool,
+}
+
+/// Must be called before any of the path functions, later calls are ignored.
+pub fn init(config_file: Option<PathBuf>, portable: bool, read_only: bool) {
+    let _ = LOCATIONS.set(Locations {
+        config_file,
+        portable,
+        read_only,
+    });
+}
+
+fn locations() -> &'static Locations {
+    LOCATIONS.get_or_init(|| Locations {
+        config_file: None,
+        portable: false,
+        read_only: false,
+    })
+}
+
+/// Set by `--read-only`. Bookmarks, sessions, annotations and everything else that would be
+/// saved are left as they are.
+pub fn read_only() -> bool {
+    locations().read_only
+}
+
+/// Writes one of miro's own files, unless running read-only in which case nothing happens.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    if read_only() {
+        return Ok(());
+    }
+    std::fs::write(path, contents).map_err(|e| anyhow!("{}", e))
+}
+
+/// Fails while running read-only. Used by writes the user asked for, which shouldn't silently do
+/// nothing like [write] does.
+pub fn ensure_writable() -> Result<()> {
+    if read_only() {
+        return Err(anyhow!("miro is running with --read-only"));
+    }
+    Ok(())
+}
+
+/// Replaces the file at `path` without ever leaving it half written, which a crash, a full disk
+/// or being killed in the middle of [std::fs::write] would. The contents are written to a file
+/// next to it first and renamed over it once they are on disk.
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The path has no file name",
+        )
+    })?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(name);
+    tmp_name.push(".miro-tmp");
+    let tmp = path.with_file_name(tmp_name);
+    let written = (|| {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    })();
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written
+}
+
+fn portable_dir() -> Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    let dir = exe
+        .parent()
+        .ok_or(anyhow!("The executable has no parent directory"))?;
+    Ok(dir.join(PORTABLE_DIR_NAME))
+}
+
+fn project_dirs() -> Result<ProjectDirs> {
+    ProjectDirs::from("dev", "uden", "miro-pdf")
+        .ok_or(anyhow!("No home directory could be determined"))
+}
+
+/// The directory used before the XDG base directories were respected. Files found here are still
+/// read so upgrading doesn't lose any bookmarks or settings.
+fn legacy_dir() -> Result<PathBuf> {
+    Ok(home::home_dir()
+        .ok_or(anyhow!("No home directory could be determined"))?
+        .join(".config/miro-pdf"))
+}
+
+pub fn config_dir() -> Result<PathBuf> {
+    if locations().portable {
+        return portable_dir();
+    }
+    Ok(project_dirs()?.config_dir().to_path_buf())
+}
+
+/// Bookmarks and other data the user would miss if it was deleted.
+pub fn data_dir() -> Result<PathBuf> {
+    if locations().portable {
+        return portable_dir();
+    }
+    Ok(project_dirs()?.data_dir().to_path_buf())
+}
+
+/// Recent files and other state that can be thrown away without much loss.
+pub fn state_dir() -> Result<PathBuf> {
+    if locations().portable {
+        return portable_dir();
+    }
+    let dirs = project_dirs()?;
+    // Only Linux has a dedicated state directory
+    Ok(dirs
+        .state_dir()
+        .unwrap_or_else(|| dirs.data_local_dir())
+        .to_path_buf())
+}
+
+/// Downloaded documents and other files that can be fetched again.
+pub fn cache_dir() -> Result<PathBuf> {
+    if locations().portable {
+        return Ok(portable_dir()?.join("cache"));
+    }
+    Ok(project_dirs()?.cache_dir().to_path_buf())
+}
+
+pub fn config_file() -> Result<PathBuf> {
+    if let Some(path) = &locations().config_file {
+        return Ok(path.clone());
+    }
+    with_legacy_fallback(&config_dir()?, "miro.conf")
+}
+
+pub fn data_file(name: &str) -> Result<PathBuf> {
+    with_legacy_fallback(&data_dir()?, name)
+}
+
+pub fn state_file(name: &str) -> Result<PathBuf> {
+    with_legacy_fallback(&state_dir()?, name)
+}
+
+/// Creates every directory miro might write to.
+pub fn create_dirs() -> Result<()> {
+    if read_only() {
+        return Ok(());
+    }
+    for dir in [config_dir()?, data_dir()?, state_dir()?, cache_dir()?] {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+fn with_legacy_fallback(dir: &Path, name: &str) -> Result<PathBuf> {
+    let path = dir.join(name);
+    if !path.exists()
+        && !locations().portable
+        && let Ok(legacy) = legacy_dir()
+        && legacy.join(name).exists()
+    {
+        return Ok(legacy.join(name));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atomic_writes_replace_the_file() {
+        let dir = std::env::temp_dir().join(format!("miro-atomic-write-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("paper.pdf");
+        write_atomically(&path, b"first").unwrap();
+        write_atomically(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        // Nothing but the file itself is left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
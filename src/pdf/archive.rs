@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use anyhow::{Result, anyhow};
+
+/// Extensions of the files the open dialog offers
+pub const OPENABLE_EXTENSIONS: &[&str] = &["pdf", "cbz", "zip"];
+/// Archives of images, shown with one image per page in natural order of their names
+const ARCHIVE_EXTENSIONS: &[&str] = &["cbz", "zip"];
+
+/// What kind of document a file is, judged by its extension. Mupdf opens all of them, but archives
+/// have no text, outline or links to go through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Pdf,
+    ImageArchive,
+    Other,
+}
+
+impl DocumentKind {
+    pub fn of(path: &Path) -> Self {
+        let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+            return DocumentKind::Other;
+        };
+        if ext.eq_ignore_ascii_case("pdf") {
+            DocumentKind::Pdf
+        } else if ARCHIVE_EXTENSIONS
+            .iter()
+            .any(|archive| ext.eq_ignore_ascii_case(archive))
+        {
+            DocumentKind::ImageArchive
+        } else {
+            DocumentKind::Other
+        }
+    }
+}
+
+/// Opens the document at `path`. Mupdf happily opens archives without a single image in them as
+/// documents with no pages, which would leave nothing to show.
+pub fn open(path: &Path) -> Result<mupdf::Document> {
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| anyhow!("The path {path:?} is not valid UTF-8"))?;
+    let doc = mupdf::Document::open(path_str)?;
+    if DocumentKind::of(path) == DocumentKind::ImageArchive && doc.page_count()? == 0 {
+        return Err(anyhow!("The archive doesn't contain any images"));
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archives_are_opened_with_one_page_per_image() -> Result<()> {
+        let path = Path::new("assets/pages.cbz");
+        assert_eq!(DocumentKind::of(path), DocumentKind::ImageArchive);
+        assert_eq!(DocumentKind::of(Path::new("Paper.PDF")), DocumentKind::Pdf);
+        assert_eq!(
+            DocumentKind::of(Path::new("book.epub")),
+            DocumentKind::Other
+        );
+
+        let doc = open(path)?;
+        // The xml file isn't an image and page-9 comes before page-10
+        assert_eq!(doc.page_count()?, 2);
+        let first = doc.load_page(0)?.bounds()?;
+        let second = doc.load_page(1)?.bounds()?;
+        assert!(first.height() > first.width());
+        assert!(second.width() > second.height());
+        Ok(())
+    }
+}
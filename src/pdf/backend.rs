@@ -0,0 +1,263 @@
+use std::path::Path;
+
+use anyhow::{Result, anyhow};
+use mupdf::{Colorspace, Device, Matrix, Pixmap, TextPageFlags};
+
+use crate::{
+    geometry::{Rect, Vector},
+    pdf::{archive, layers, recolor, text_layout, widget::OutlineItem},
+};
+
+/// A clickable area of a page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageLink {
+    /// In page space
+    pub bounds: Rect<f32>,
+    pub uri: String,
+    /// The page an internal link leads to, if the backend could resolve it
+    pub page: Option<usize>,
+}
+
+/// A character of the text of a page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextChar {
+    pub c: char,
+    /// In page space
+    pub bounds: Rect<f32>,
+}
+
+/// A rendered page with four bytes (rgba) per pixel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    fn from_pixmap(pix: &Pixmap) -> Self {
+        RgbaImage {
+            width: pix.width(),
+            height: pix.height(),
+            pixels: pix.samples().to_vec(),
+        }
+    }
+}
+
+/// An rgba pixmap covering all of `page` at `scale`.
+fn page_pixmap(page: &mupdf::Page, scale: f32) -> Result<Pixmap> {
+    let bounds = page.bounds()?;
+    let w = ((bounds.x1 - bounds.x0) * scale).ceil().max(1.0) as i32;
+    let h = ((bounds.y1 - bounds.y0) * scale).ceil().max(1.0) as i32;
+    Ok(Pixmap::new_with_w_h(&Colorspace::device_rgb(), w, h, true)?)
+}
+
+/// Everything the viewer needs from a document format. New formats are added by implementing this
+/// and picking the implementation in [open], the widget and the app only see pages, text, links
+/// and the outline.
+///
+/// Mupdf covers every format opened so far. The viewer still draws pdfs through mupdf display
+/// lists directly, since the pixmap pooling and partial redraws depend on them, and annotations and
+/// forms only exist in pdfs. It reads the text, links and outline while it has the pages loaded
+/// for that, with the same conversions as [MupdfBackend].
+pub trait DocumentBackend: std::fmt::Debug {
+    fn page_count(&self) -> Result<usize>;
+
+    /// Renders an entire page on a white background at `scale` pixels per unit of page space, where
+    /// one unit is 1/72 of an inch.
+    fn render_page(&self, page_idx: usize, scale: f32) -> Result<RgbaImage>;
+
+    /// Renders a page with its text and vector graphics in the dark mode `colors`, see
+    /// [recolor]. `None` for scans, which are left to the shader.
+    fn render_page_recolored(
+        &self,
+        page_idx: usize,
+        scale: f32,
+        colors: &[[u8; 4]; 256],
+    ) -> Result<Option<RgbaImage>>;
+
+    /// The characters of a page in reading order, empty for pages without text like scans.
+    fn page_text(&self, page_idx: usize) -> Result<Vec<TextChar>>;
+
+    /// The bounds of the paragraphs and images of a page in page space, in the order the document
+    /// lists them. See [text_layout::reading_order] for the order they are read in.
+    fn page_blocks(&self, page_idx: usize) -> Result<Vec<Rect<f32>>>;
+
+    fn page_links(&self, page_idx: usize) -> Result<Vec<PageLink>>;
+
+    fn outline(&self) -> Result<Vec<OutlineItem>>;
+}
+
+/// Opens the document at `path` with the backend for its format.
+pub fn open(path: &Path) -> Result<Box<dyn DocumentBackend>> {
+    Ok(Box::new(MupdfBackend::new(archive::open(path)?)))
+}
+
+/// Like [open], with the layers named in `hidden` hidden and all others shown.
+pub fn open_with_hidden_layers(path: &Path, hidden: &[String]) -> Result<Box<dyn DocumentBackend>> {
+    let doc = archive::open(path)?;
+    layers::hide(&doc, hidden)?;
+    Ok(Box::new(MupdfBackend::new(doc)))
+}
+
+/// Pdfs, image archives and everything else mupdf can open.
+#[derive(Debug, Clone)]
+pub struct MupdfBackend {
+    doc: mupdf::Document,
+}
+
+impl MupdfBackend {
+    pub fn new(doc: mupdf::Document) -> Self {
+        Self { doc }
+    }
+
+    fn load_page(&self, page_idx: usize) -> Result<mupdf::Page> {
+        let page_idx = i32::try_from(page_idx).map_err(|_| anyhow!("Page index out of range"))?;
+        Ok(self.doc.load_page(page_idx)?)
+    }
+}
+
+/// The characters of `text_page` in reading order.
+pub(crate) fn text_chars(text_page: &mupdf::TextPage) -> Vec<TextChar> {
+    let mut chars = vec![];
+    for block in text_page.blocks() {
+        for line in block.lines() {
+            for char in line.chars() {
+                if let Some(c) = char.char() {
+                    let quad = char.quad();
+                    chars.push(TextChar {
+                        c,
+                        bounds: Rect {
+                            x0: Vector::new(quad.ul.x, quad.ul.y),
+                            x1: Vector::new(quad.lr.x, quad.lr.y),
+                        },
+                    });
+                }
+            }
+        }
+    }
+    chars
+}
+
+pub(crate) fn links(page: &mupdf::Page) -> Result<Vec<PageLink>> {
+    Ok(page
+        .links()?
+        .map(|link| PageLink {
+            bounds: link.bounds.into(),
+            uri: link.uri,
+            page: link.dest.map(|dest| dest.loc.page_number as usize),
+        })
+        .collect())
+}
+
+pub(crate) fn outline(doc: &mupdf::Document) -> Result<Vec<OutlineItem>> {
+    Ok(doc
+        .outlines()?
+        .iter()
+        .map(|outline| convert_outline(outline, 0))
+        .collect())
+}
+
+fn convert_outline(outline: &mupdf::Outline, level: u32) -> OutlineItem {
+    OutlineItem {
+        title: outline.title.clone(),
+        page: outline.dest.map(|d| d.loc.page_number),
+        level,
+        children: outline
+            .down
+            .iter()
+            .map(|child| convert_outline(child, level + 1))
+            .collect(),
+    }
+}
+
+impl DocumentBackend for MupdfBackend {
+    fn page_count(&self) -> Result<usize> {
+        Ok(self.doc.page_count()?.max(0) as usize)
+    }
+
+    fn render_page(&self, page_idx: usize, scale: f32) -> Result<RgbaImage> {
+        let page = self.load_page(page_idx)?;
+        let mut pix = page_pixmap(&page, scale)?;
+        pix.samples_mut().fill(255);
+        {
+            // The device has to be closed (dropped) before the pixmap contents are complete
+            let device = Device::from_pixmap(&pix)?;
+            page.run(&device, &Matrix::new_scale(scale, scale))?;
+        }
+        Ok(RgbaImage::from_pixmap(&pix))
+    }
+
+    fn render_page_recolored(
+        &self,
+        page_idx: usize,
+        scale: f32,
+        colors: &[[u8; 4]; 256],
+    ) -> Result<Option<RgbaImage>> {
+        let page = self.load_page(page_idx)?;
+        if recolor::is_scan(page.bounds()?, |device| page.run(device, &Matrix::IDENTITY))? {
+            return Ok(None);
+        }
+        let mut pix = page_pixmap(&page, scale)?;
+        recolor::draw_recolored(&mut pix, colors, |device| {
+            page.run(device, &Matrix::new_scale(scale, scale))
+        })?;
+        Ok(Some(RgbaImage::from_pixmap(&pix)))
+    }
+
+    fn page_text(&self, page_idx: usize) -> Result<Vec<TextChar>> {
+        let text_page = self
+            .load_page(page_idx)?
+            .to_text_page(TextPageFlags::empty())?;
+        Ok(text_chars(&text_page))
+    }
+
+    fn page_blocks(&self, page_idx: usize) -> Result<Vec<Rect<f32>>> {
+        let text_page = self
+            .load_page(page_idx)?
+            .to_text_page(TextPageFlags::PRESERVE_IMAGES)?;
+        Ok(text_page
+            .blocks()
+            .map(|block| Rect::from(block.bounds()))
+            .filter(|bounds| bounds.width() > 0.0 && bounds.height() > 0.0)
+            .collect())
+    }
+
+    fn page_links(&self, page_idx: usize) -> Result<Vec<PageLink>> {
+        links(&self.load_page(page_idx)?)
+    }
+
+    fn outline(&self) -> Result<Vec<OutlineItem>> {
+        outline(&self.doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mupdf_backend_reads_pages_text_and_links() -> Result<()> {
+        let backend = open(Path::new("assets/links.pdf"))?;
+        assert_eq!(backend.page_count()?, 3);
+
+        let image = backend.render_page(0, 0.5)?;
+        let full_size = backend.render_page(0, 1.0)?;
+        assert!(image.width.abs_diff(full_size.width / 2) <= 1);
+        assert_eq!(
+            image.pixels.len(),
+            (image.width * image.height * 4) as usize
+        );
+
+        let text: String = backend.page_text(0)?.iter().map(|c| c.c).collect();
+        assert!(!text.trim().is_empty());
+        assert!(!backend.page_blocks(0)?.is_empty());
+        assert!(
+            (0..3)
+                .flat_map(|page_idx| backend.page_links(page_idx).unwrap())
+                .any(|link| link.page.is_some())
+        );
+        assert!(backend.render_page(3, 1.0).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use strum::EnumString;
+
+use crate::geometry::Rect;
+
+/// Every character of a document as (page_index, byte_offset, bounding_box), see
+/// [super::widget::PdfViewer::char_bboxes]
+type Chars = [(usize, usize, Rect<f32>)];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, EnumString, Default, PartialEq, Eq)]
+pub enum CaretMotion {
+    #[default]
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// A text cursor placed on a character of the document, moved with the keyboard in caret mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Caret {
+    /// Index of the character the caret is on
+    pub position: usize,
+    /// Where the selection started, if text is being selected
+    pub anchor: Option<usize>,
+}
+
+impl Caret {
+    pub fn new(position: usize) -> Self {
+        Self {
+            position,
+            anchor: None,
+        }
+    }
+
+    /// Moves the caret, either dropping the selection or extending it when `extend` is set.
+    pub fn apply(&mut self, chars: &Chars, motion: CaretMotion, extend: bool) {
+        if extend {
+            self.anchor.get_or_insert(self.position);
+        } else {
+            self.anchor = None;
+        }
+        self.position = move_caret(chars, self.position, motion);
+    }
+
+    /// The selected characters, including the ones under both ends of the selection.
+    pub fn selection(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        let anchor = self.anchor?;
+        Some(anchor.min(self.position)..=anchor.max(self.position))
+    }
+}
+
+fn same_line(a: &(usize, usize, Rect<f32>), b: &(usize, usize, Rect<f32>)) -> bool {
+    a.0 == b.0 && a.2.x0.y < b.2.x1.y && a.2.x1.y > b.2.x0.y
+}
+
+/// The range of characters on the same line as the character at `idx`.
+fn line_range(chars: &Chars, idx: usize) -> std::ops::Range<usize> {
+    let mut start = idx;
+    while start > 0 && same_line(&chars[start - 1], &chars[idx]) {
+        start -= 1;
+    }
+    let mut end = idx + 1;
+    while end < chars.len() && same_line(&chars[end], &chars[idx]) {
+        end += 1;
+    }
+    start..end
+}
+
+/// The character of `line` horizontally closest to `x`.
+fn closest_in_line(chars: &Chars, line: std::ops::Range<usize>, x: f32) -> usize {
+    line.min_by(|&a, &b| {
+        let da = (chars[a].2.center().x - x).abs();
+        let db = (chars[b].2.center().x - x).abs();
+        da.total_cmp(&db)
+    })
+    .unwrap_or(0)
+}
+
+/// Where the caret at `position` ends up after `motion`. Lines follow the order of the text
+/// page, so moving down from the last line of a page continues on the next page.
+pub fn move_caret(chars: &Chars, position: usize, motion: CaretMotion) -> usize {
+    if chars.is_empty() {
+        return 0;
+    }
+    let position = position.min(chars.len() - 1);
+    let x = chars[position].2.center().x;
+    match motion {
+        CaretMotion::Left => position.saturating_sub(1),
+        CaretMotion::Right => (position + 1).min(chars.len() - 1),
+        CaretMotion::Up => {
+            let line = line_range(chars, position);
+            if line.start == 0 {
+                return position;
+            }
+            closest_in_line(chars, line_range(chars, line.start - 1), x)
+        }
+        CaretMotion::Down => {
+            let line = line_range(chars, position);
+            if line.end == chars.len() {
+                return position;
+            }
+            closest_in_line(chars, line_range(chars, line.end), x)
+        }
+    }
+}
+
+/// The text of the characters in `range`, with a line break wherever a new line starts.
+pub fn text_in_range(text: &str, chars: &Chars, range: std::ops::RangeInclusive<usize>) -> String {
+    let mut result = String::new();
+    let mut previous: Option<&(usize, usize, Rect<f32>)> = None;
+    for entry in chars[range].iter() {
+        if let Some(previous) = previous
+            && !same_line(previous, entry)
+        {
+            result.push('\n');
+        }
+        if let Some(c) = text[entry.1..].chars().next() {
+            result.push(c);
+        }
+        previous = Some(entry);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Vector;
+
+    /// Two lines of three characters on page 0, followed by one line on page 1
+    fn chars() -> (String, Vec<(usize, usize, Rect<f32>)>) {
+        let mut text = String::new();
+        let mut chars = vec![];
+        for (page, y, line) in [(0, 0.0, "abc"), (0, 20.0, "def"), (1, 0.0, "ghi")] {
+            for (i, c) in line.chars().enumerate() {
+                let x = i as f32 * 10.0;
+                chars.push((
+                    page,
+                    text.len(),
+                    Rect::from_points(Vector::new(x, y), Vector::new(x + 10.0, y + 12.0)),
+                ));
+                text.push(c);
+            }
+        }
+        (text, chars)
+    }
+
+    #[test]
+    fn vertical_motion_keeps_the_column() {
+        let (_, chars) = chars();
+        assert_eq!(move_caret(&chars, 1, CaretMotion::Down), 4);
+        assert_eq!(move_caret(&chars, 4, CaretMotion::Down), 7);
+        assert_eq!(move_caret(&chars, 7, CaretMotion::Down), 7);
+        assert_eq!(move_caret(&chars, 5, CaretMotion::Up), 2);
+        assert_eq!(move_caret(&chars, 0, CaretMotion::Left), 0);
+        assert_eq!(move_caret(&chars, 8, CaretMotion::Right), 8);
+    }
+
+    #[test]
+    fn extending_a_selection_across_lines() {
+        let (text, chars) = chars();
+        let mut caret = Caret::new(1);
+        caret.apply(&chars, CaretMotion::Down, true);
+        caret.apply(&chars, CaretMotion::Right, true);
+        assert_eq!(caret.selection(), Some(1..=5));
+        assert_eq!(text_in_range(&text, &chars, 1..=5), "bc\ndef");
+
+        caret.apply(&chars, CaretMotion::Left, false);
+        assert_eq!(caret.selection(), None);
+    }
+}
@@ -0,0 +1,85 @@
+/// Undoes the layout of justified text in copied text when `CopyCleanup` is set. Lines are joined
+/// into paragraphs, words hyphenated at the end of a line are put back together and typographic
+/// quotes, dashes and ligatures become plain characters. Blank lines still separate paragraphs.
+pub fn clean(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    // Whether the last line ended a paragraph, so the next one starts on a new line
+    let mut paragraph_ended = true;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            if !out.is_empty() && !paragraph_ended {
+                out.push_str("\n\n");
+            }
+            paragraph_ended = true;
+            continue;
+        }
+        if !paragraph_ended {
+            match hyphenated_prefix(&out) {
+                Some(len) if line.starts_with(char::is_lowercase) => out.truncate(len),
+                // Compounds like "Miller-Rabin" keep their hyphen
+                _ if out.ends_with(HYPHENS) => {}
+                _ => out.push(' '),
+            }
+        }
+        out.push_str(line);
+        paragraph_ended = false;
+    }
+    normalize_characters(&out)
+}
+
+/// Characters a word can be broken with at the end of a line, the last one is a soft hyphen
+const HYPHENS: [char; 3] = ['-', '\u{2010}', '\u{00ad}'];
+
+/// The length of `text` without a hyphen it ends in, if that hyphen splits a word.
+fn hyphenated_prefix(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().rev();
+    let (idx, hyphen) = chars.next()?;
+    let (_, before) = chars.next()?;
+    (HYPHENS.contains(&hyphen) && before.is_alphabetic()).then_some(idx)
+}
+
+fn normalize_characters(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' | '\u{2032}' => out.push('\''),
+            '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{201f}' | '\u{2033}' => out.push('"'),
+            '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2212}' => out.push('-'),
+            '\u{2014}' | '\u{2015}' => out.push_str("--"),
+            '\u{2026}' => out.push_str("..."),
+            '\u{00a0}' => out.push(' '),
+            // Soft hyphens only say where a word may be broken
+            '\u{00ad}' => {}
+            '\u{fb00}' => out.push_str("ff"),
+            '\u{fb01}' => out.push_str("fi"),
+            '\u{fb02}' => out.push_str("fl"),
+            '\u{fb03}' => out.push_str("ffi"),
+            '\u{fb04}' => out.push_str("ffl"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_lines_are_joined_and_hyphenation_removed() {
+        assert_eq!(
+            clean("The quick brown fox jum-\nped over the lazy\ndog.\n\nA second para-\ngraph"),
+            "The quick brown fox jumped over the lazy dog.\n\nA second paragraph"
+        );
+        // Names and numbers keep their hyphen
+        assert_eq!(clean("Miller-\nRabin and 1-\n2"), "Miller-Rabin and 1-2");
+    }
+
+    #[test]
+    fn typography_becomes_plain_characters() {
+        assert_eq!(
+            clean("\u{201c}It\u{2019}s \u{fb01}ne\u{201d} \u{2014} pages 3\u{2013}5\u{2026}"),
+            "\"It's fine\" -- pages 3-5..."
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+
+use mupdf::pdf::{PdfDocument, PdfObject};
+
+/// Name trees are balanced by the writer, anything deeper than this is a cycle
+const MAX_TREE_DEPTH: usize = 32;
+
+/// The named destinations of a document, places links and other documents can jump to by name
+/// (like `#nameddest=conclusion`) instead of by page. Spec-style documents use them for every
+/// section and definition.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Destinations {
+    /// Name to zero-based page
+    pages: BTreeMap<String, usize>,
+}
+
+impl Destinations {
+    /// Reads both the `/Dests` name tree and the older `/Dests` dictionary of the catalog.
+    /// Documents that aren't pdfs have no named destinations.
+    pub fn load(doc: &mupdf::Document) -> Self {
+        let mut destinations = Self::default();
+        let Ok(pdf) = PdfDocument::try_from(doc.clone()) else {
+            return destinations;
+        };
+        let Ok(catalog) = pdf.catalog() else {
+            return destinations;
+        };
+        if let Ok(Some(tree)) = catalog.get_dict("Names").and_then(|names| match names {
+            Some(names) => names.get_dict("Dests"),
+            None => Ok(None),
+        }) {
+            destinations.read_tree(&pdf, &tree, 0);
+        }
+        if let Ok(Some(dests)) = catalog.get_dict("Dests") {
+            for i in 0..dests.dict_len().unwrap_or(0) as i32 {
+                if let (Ok(Some(key)), Ok(Some(value))) =
+                    (dests.get_dict_key(i), dests.get_dict_val(i))
+                    && let Ok(name) = key.as_name()
+                {
+                    destinations.insert(&pdf, String::from_utf8_lossy(name).to_string(), &value);
+                }
+            }
+        }
+        destinations
+    }
+
+    fn read_tree(&mut self, pdf: &PdfDocument, node: &PdfObject, depth: usize) {
+        if depth > MAX_TREE_DEPTH {
+            return;
+        }
+        if let Ok(Some(kids)) = node.get_dict("Kids") {
+            for i in 0..kids.len().unwrap_or(0) as i32 {
+                if let Ok(Some(kid)) = kids.get_array(i) {
+                    self.read_tree(pdf, &kid, depth + 1);
+                }
+            }
+        }
+        if let Ok(Some(names)) = node.get_dict("Names") {
+            let len = names.len().unwrap_or(0) as i32;
+            for i in (0..len - 1).step_by(2) {
+                if let (Ok(Some(key)), Ok(Some(value))) =
+                    (names.get_array(i), names.get_array(i + 1))
+                    && let Ok(name) = key.as_bytes()
+                {
+                    self.insert(pdf, decode_text_string(name), &value);
+                }
+            }
+        }
+    }
+
+    /// `dest` is either an explicit destination like `[page /XYZ left top zoom]` or a dictionary
+    /// holding one under `/D`.
+    fn insert(&mut self, pdf: &PdfDocument, name: String, dest: &PdfObject) {
+        let explicit = match dest.get_dict("D") {
+            Ok(Some(explicit)) => explicit,
+            _ => match dest.try_clone() {
+                Ok(dest) => dest,
+                Err(_) => return,
+            },
+        };
+        let Ok(Some(page)) = explicit.get_array(0) else {
+            return;
+        };
+        // Destinations pointing into other documents use page numbers instead of page objects
+        let page = if page.is_int().unwrap_or(false) {
+            page.as_int()
+        } else {
+            pdf.lookup_page_number(&page)
+        };
+        if let Ok(page) = page
+            && page >= 0
+        {
+            self.pages.insert(name, page as usize);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.pages.get(name).copied()
+    }
+
+    /// The page an internal link like `#nameddest=intro` or `#intro` points at.
+    pub fn resolve_uri(&self, uri: &str) -> Option<usize> {
+        let fragment = uri.strip_prefix('#')?;
+        let name = match fragment.find("nameddest=") {
+            Some(start) => {
+                let name = &fragment[start + "nameddest=".len()..];
+                name.split('&').next().unwrap_or(name)
+            }
+            None => fragment,
+        };
+        self.get(name).or_else(|| self.get(&percent_decode(name)))
+    }
+
+    /// Destinations whose name contains `query`, ignoring case, sorted by name. The destination
+    /// named exactly `query` comes first.
+    pub fn matching<'a>(&'a self, query: &str) -> impl Iterator<Item = (&'a str, usize)> {
+        let exact = self.pages.get_key_value(query.trim());
+        let query = query.trim().to_lowercase();
+        exact
+            .into_iter()
+            .chain(self.pages.iter().filter(move |(name, _)| {
+                exact.is_none_or(|(exact, _)| exact != *name)
+                    && name.to_lowercase().contains(&query)
+            }))
+            .map(|(name, page)| (name.as_str(), *page))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+}
+
+/// Pdf strings are UTF-16 when they start with a byte order mark, PDFDocEncoding otherwise,
+/// which matches latin-1 for everything that shows up in names.
+pub(crate) fn decode_text_string(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xfe, 0xff]) {
+        Some(utf16) => char::decode_utf16(
+            utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]])),
+        )
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect(),
+        None => match std::str::from_utf8(bytes) {
+            Ok(text) => text.to_string(),
+            Err(_) => bytes.iter().map(|b| *b as char).collect(),
+        },
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = text
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destinations_are_read_from_the_name_tree() {
+        let doc = mupdf::Document::open("assets/links.pdf").unwrap();
+        let destinations = Destinations::load(&doc);
+        assert_eq!(destinations.len(), 3);
+        assert_eq!(destinations.get("section-text"), Some(1));
+        assert_eq!(destinations.get("conclusion"), Some(2));
+        assert_eq!(
+            destinations.resolve_uri("#nameddest=links-in-tables"),
+            Some(1)
+        );
+        assert_eq!(destinations.resolve_uri("#conclusion"), Some(2));
+        assert_eq!(destinations.resolve_uri("https://example.com"), None);
+        assert_eq!(
+            destinations.matching("TEXT").collect::<Vec<_>>(),
+            [("section-text", 1)]
+        );
+    }
+
+    #[test]
+    fn exact_names_match_first() {
+        let destinations = Destinations {
+            pages: BTreeMap::from([
+                ("fig1".to_string(), 0),
+                ("fig10".to_string(), 4),
+                ("Fig1".to_string(), 2),
+            ]),
+        };
+        assert_eq!(
+            destinations.matching("fig1").collect::<Vec<_>>(),
+            [("fig1", 0), ("Fig1", 2), ("fig10", 4)]
+        );
+        assert_eq!(
+            destinations.matching("fig10 ").collect::<Vec<_>>(),
+            [("fig10", 4)]
+        );
+    }
+
+    #[test]
+    fn names_are_decoded() {
+        assert_eq!(
+            decode_text_string(&[0xfe, 0xff, 0x00, 0x41, 0x00, 0xe9]),
+            "Aé"
+        );
+        assert_eq!(decode_text_string(b"caf\xe9"), "café");
+        assert_eq!(
+            percent_decode("Name%20With%2FSlash%zz"),
+            "Name With/Slash%zz"
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    io::Write,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use mupdf::{
+    ColorParams, Colorspace, Device, DisplayList, DocumentWriter, Image, ImageFormat, Matrix,
+    Pixmap,
+    pdf::{PdfDocument, PdfWriteOptions},
+};
+
+use crate::{
+    paths,
+    pdf::widget::{OutlineItem, cpu_pdf_dark_mode_shader},
+};
+
+/// Resolution of shared pages relative to the native 72 DPI of the document
+pub const SHARE_SCALE: f32 = 2.0;
+/// Resolution of the pages of a dark copy, high enough to keep small print readable when zooming
+/// in on other devices
+pub const DARK_COPY_SCALE: f32 = 2.0;
+
+/// Renders `region` of a page to a PNG file at `scale` times its native resolution. The region is
+/// given in page space and defaults to the entire page.
+pub fn page_to_png(
+    list: &DisplayList,
+    region: Option<mupdf::Rect>,
+    scale: f32,
+    path: &Path,
+) -> Result<()> {
+    let region = region.unwrap_or_else(|| list.bounds());
+    let w = ((region.x1 - region.x0) * scale).ceil() as i32;
+    let h = ((region.y1 - region.y0) * scale).ceil() as i32;
+    if w <= 0 || h <= 0 {
+        return Err(anyhow!("Can't export an empty region"));
+    }
+
+    let mut pix = Pixmap::new_with_w_h(&Colorspace::device_rgb(), w, h, false)?;
+    pix.clear_with(255)?;
+    let mut matrix = Matrix::new_translate(-region.x0, -region.y0);
+    matrix.concat(Matrix::new_scale(scale, scale));
+    {
+        // The device has to be closed (dropped) before the pixmap contents are complete
+        let device = Device::from_pixmap(&pix)?;
+        list.run(
+            &device,
+            &matrix,
+            mupdf::Rect::new(0.0, 0.0, w as f32, h as f32),
+        )?;
+    }
+
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| anyhow!("The path {path:?} is not valid UTF-8"))?;
+    paths::ensure_writable()?;
+    pix.save_as(path_str, ImageFormat::PNG)?;
+    Ok(())
+}
+
+/// A fresh path in the temporary directory for sharing page `page_idx` of the document `name`.
+pub fn share_path(name: &str, page_idx: usize) -> PathBuf {
+    let stem = Path::new(name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "page".to_string());
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    std::env::temp_dir().join(format!("miro-{stem}-p{}-{timestamp}.png", page_idx + 1))
+}
+
+/// Writes a copy of the pdf in `bytes` to `path` with every page replaced by an image of it in the
+/// colors of the dark mode, so it can be read that way without miro. The text of the copy can't be
+/// selected or searched anymore.
+pub fn dark_copy(
+    bytes: &[u8],
+    gradient_cache: &[[u8; 4]; 256],
+    scale: f32,
+    path: &Path,
+) -> Result<()> {
+    paths::ensure_writable()?;
+    let doc = PdfDocument::from_bytes(bytes)?;
+    let mut writer = DocumentWriter::new(
+        path.to_str()
+            .ok_or_else(|| anyhow!("The path {path:?} is not valid UTF-8"))?,
+        "pdf",
+        "compress-images",
+    )?;
+    for page_idx in 0..doc.page_count()? {
+        let page = doc.load_page(page_idx)?;
+        let bounds = page.bounds()?;
+        let w = ((bounds.x1 - bounds.x0) * scale).ceil().max(1.0) as i32;
+        let h = ((bounds.y1 - bounds.y0) * scale).ceil().max(1.0) as i32;
+        let mut pix = Pixmap::new_with_w_h(&Colorspace::device_rgb(), w, h, true)?;
+        pix.samples_mut().fill(255);
+        {
+            let device = Device::from_pixmap(&pix)?;
+            let mut matrix = Matrix::new_translate(-bounds.x0, -bounds.y0);
+            matrix.concat(Matrix::new_scale(scale, scale));
+            page.run(&device, &matrix)?;
+        }
+        cpu_pdf_dark_mode_shader(pix.samples_mut(), gradient_cache);
+
+        // Images are drawn into the unit square, which is stretched over the entire page
+        let media_box = mupdf::Rect::new(0.0, 0.0, bounds.x1 - bounds.x0, bounds.y1 - bounds.y0);
+        let device = writer.begin_page(media_box)?;
+        device.fill_image(
+            &Image::from_pixmap(&pix)?,
+            &Matrix::new_scale(media_box.x1, media_box.y1),
+            1.0,
+            ColorParams::default(),
+        )?;
+        writer.end_page(device)?;
+    }
+    Ok(())
+}
+
+/// Pages of the document belonging to one top-level entry of the outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub title: String,
+    pub pages: Range<usize>,
+}
+
+/// Splits the pages of a document into chapters at the top-level entries of its outline. Pages
+/// before the first entry become a chapter of their own and entries pointing at the same page as
+/// the next one are left out.
+pub fn chapters(outline: &[OutlineItem], page_count: usize) -> Vec<Chapter> {
+    let mut starts: Vec<(usize, &str)> = outline
+        .iter()
+        .filter_map(|item| Some((item.page? as usize, item.title.as_str())))
+        .filter(|(page, _)| *page < page_count)
+        .collect();
+    starts.sort_by_key(|(page, _)| *page);
+    if starts.first().is_some_and(|(page, _)| *page > 0) {
+        starts.insert(0, (0, "Front matter"));
+    }
+
+    let mut chapters = vec![];
+    for (i, (start, title)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(page_count, |(page, _)| *page);
+        if *start < end {
+            chapters.push(Chapter {
+                title: title.to_string(),
+                pages: *start..end,
+            });
+        }
+    }
+    chapters
+}
+
+/// A file name for the `n`-th chapter, numbered so the files sort in reading order. Characters
+/// that aren't allowed in file names on some platforms are replaced.
+pub fn chapter_file_name(n: usize, title: &str) -> String {
+    let mut name: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    // Long titles are shortened to stay well below the file name limit of most file systems
+    if let Some((idx, _)) = name.char_indices().nth(80) {
+        name.truncate(idx);
+    }
+    let name = name.trim_end_matches(['.', ' ']);
+    if name.is_empty() {
+        format!("{:02} Chapter {}.pdf", n + 1, n + 1)
+    } else {
+        format!("{:02} {name}.pdf", n + 1)
+    }
+}
+
+/// Writes every chapter of the pdf in `bytes` to its own file in `directory` and returns the
+/// paths of the files. Nothing is written if any of the files already exists.
+pub fn split_by_chapter(
+    bytes: &[u8],
+    chapters: &[Chapter],
+    directory: &Path,
+) -> Result<Vec<PathBuf>> {
+    paths::ensure_writable()?;
+    let targets: Vec<PathBuf> = chapters
+        .iter()
+        .enumerate()
+        .map(|(n, chapter)| directory.join(chapter_file_name(n, &chapter.title)))
+        .collect();
+    if let Some(existing) = targets.iter().find(|path| path.exists()) {
+        return Err(anyhow!(
+            "{} already exists, split into an empty directory instead",
+            existing.display()
+        ));
+    }
+    let mut written = vec![];
+    for (chapter, path) in chapters.iter().zip(targets) {
+        let mut doc = PdfDocument::from_bytes(bytes)?;
+        let page_count = doc.page_count()? as usize;
+        // Back to front so the indices of the pages still to be deleted don't shift
+        for page in (0..page_count).rev() {
+            if !chapter.pages.contains(&page) {
+                doc.delete_page(page as i32)?;
+            }
+        }
+        // The outline would point at pages that aren't part of the chapter anymore
+        doc.delete_outlines()?;
+
+        let mut options = PdfWriteOptions::default();
+        options.set_garbage(true);
+        let mut out = vec![];
+        doc.write_to_with_options(&mut out, options)?;
+        // Still refuses to replace a file which showed up since the check above
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?
+            .write_all(&out)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use mupdf::TextPageFlags;
+
+    use super::*;
+
+    #[test]
+    fn test_region_is_rendered_at_requested_scale() -> Result<()> {
+        let doc = mupdf::Document::open("assets/links.pdf")?;
+        let list = doc.load_page(0)?.to_display_list(false)?;
+        let path = share_path("links.pdf", 0);
+
+        page_to_png(
+            &list,
+            Some(mupdf::Rect::new(10.0, 20.0, 110.0, 70.0)),
+            SHARE_SCALE,
+            &path,
+        )?;
+        let png = std::fs::read(&path)?;
+        std::fs::remove_file(&path)?;
+
+        // Width and height are the first fields of the IHDR chunk
+        assert_eq!(&png[1..4], b"PNG");
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into()?), 200);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into()?), 100);
+        Ok(())
+    }
+
+    #[test]
+    fn dark_copies_keep_the_pages_in_the_dark_colors() -> Result<()> {
+        let bytes = std::fs::read("assets/links.pdf")?;
+        let original = PdfDocument::from_bytes(&bytes)?;
+        // Every brightness maps to the same color, which the copy has to be made of
+        let gradient_cache = [[30, 40, 50, 255]; 256];
+        let path = std::env::temp_dir().join("miro-dark-copy-test.pdf");
+
+        dark_copy(&bytes, &gradient_cache, 1.0, &path)?;
+        let copy = mupdf::Document::open(path.to_str().unwrap())?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(copy.page_count()?, original.page_count()?);
+        let page = copy.load_page(0)?;
+        let (bounds, original_bounds) = (page.bounds()?, original.load_page(0)?.bounds()?);
+        assert_eq!(bounds.width(), original_bounds.width());
+        assert_eq!(bounds.height(), original_bounds.height());
+        let text = page.to_text_page(TextPageFlags::empty())?.to_text()?;
+        assert!(text.trim().is_empty());
+        let pix = page.to_pixmap(&Matrix::IDENTITY, &Colorspace::device_rgb(), false, false)?;
+        let center = (pix.height() / 2 * pix.width() + pix.width() / 2) as usize * 3;
+        let rgb = &pix.samples()[center..center + 3];
+        assert!(
+            rgb.iter()
+                .zip([30, 40, 50])
+                .all(|(a, b)| a.abs_diff(b) <= 2)
+        );
+        Ok(())
+    }
+
+    fn item(title: &str, page: Option<u32>) -> OutlineItem {
+        OutlineItem {
+            title: title.to_string(),
+            page,
+            level: 0,
+            children: vec![],
+        }
+    }
+
+    #[test]
+    fn chapters_span_until_the_next_top_level_entry() {
+        let outline = [
+            item("Introduction", Some(2)),
+            item("Unresolved", None),
+            item("Methods", Some(5)),
+            item("Methods again", Some(5)),
+            item("Results", Some(9)),
+        ];
+        let titles_and_pages: Vec<_> = chapters(&outline, 12)
+            .into_iter()
+            .map(|c| (c.title, c.pages))
+            .collect();
+        assert_eq!(
+            titles_and_pages,
+            [
+                ("Front matter".to_string(), 0..2),
+                ("Introduction".to_string(), 2..5),
+                ("Methods again".to_string(), 5..9),
+                ("Results".to_string(), 9..12),
+            ]
+        );
+    }
+
+    #[test]
+    fn splitting_never_replaces_files() -> Result<()> {
+        let bytes = std::fs::read("assets/links.pdf")?;
+        let directory = std::env::temp_dir().join("miro-split-test");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory)?;
+        let chapters = [Chapter {
+            title: "Links".to_string(),
+            pages: 0..1,
+        }];
+
+        let written = split_by_chapter(&bytes, &chapters, &directory)?;
+        assert_eq!(written, [directory.join("01 Links.pdf")]);
+        std::fs::write(&written[0], b"notes")?;
+        assert!(split_by_chapter(&bytes, &chapters, &directory).is_err());
+        let kept = std::fs::read(&written[0])?;
+        std::fs::remove_dir_all(&directory)?;
+        assert_eq!(kept, b"notes");
+        Ok(())
+    }
+
+    #[test]
+    fn chapter_file_names_are_sanitized() {
+        assert_eq!(
+            chapter_file_name(0, "1. What/Why: an\nIntro?"),
+            "01 1. What_Why_ an Intro_.pdf"
+        );
+        assert_eq!(chapter_file_name(11, " ... "), "12 Chapter 12.pdf");
+    }
+}
@@ -0,0 +1,186 @@
+//! Interactive forms. Miro doesn't run the JavaScript some forms use to fill in totals and format
+//! values, which is only worth a warning when a form actually relies on it.
+
+use mupdf::pdf::{PdfDocument, PdfObject};
+
+use crate::pdf::destinations::decode_text_string;
+
+/// Deeper field hierarchies are assumed to be cyclic
+const MAX_FIELD_DEPTH: usize = 32;
+/// The additional actions of a field which compute or rewrite its value: calculate, format,
+/// keystroke and validate
+const VALUE_ACTIONS: [&str; 4] = ["C", "F", "K", "V"];
+
+/// A field of a form along with what it inherits from the fields above it.
+pub struct FormField {
+    pub obj: PdfObject,
+    /// The full name of the field, like "part3.signature"
+    pub name: String,
+    /// Like `Sig` or `Tx`
+    pub field_type: Option<Vec<u8>>,
+    /// Whether some of its kids are fields of their own. The others are its widgets.
+    pub has_field_kids: bool,
+    /// The kids without a name of their own, which are the widgets of this field, not fields
+    pub widgets: Vec<PdfObject>,
+}
+
+/// Walks the field hierarchy of a form, every field before its kids and in the order of the
+/// form.
+pub struct Fields {
+    /// (field, name of the parent, type of the parent, depth), the next field last
+    stack: Vec<(PdfObject, Option<String>, Option<Vec<u8>>, usize)>,
+}
+
+impl Fields {
+    /// The fields of the form of `doc`, none if it has no form.
+    pub fn of(doc: &mupdf::Document) -> Self {
+        let mut stack = vec![];
+        if let Some(fields) = acro_form(doc).and_then(|form| form.get_dict("Fields").ok().flatten())
+        {
+            for i in (0..fields.len().unwrap_or(0) as i32).rev() {
+                if let Ok(Some(field)) = fields.get_array(i) {
+                    stack.push((field, None, None, 0));
+                }
+            }
+        }
+        Self { stack }
+    }
+}
+
+impl Iterator for Fields {
+    type Item = FormField;
+
+    fn next(&mut self) -> Option<FormField> {
+        let (obj, parent, inherited_type, depth) = self.stack.pop()?;
+        let name = match (parent, text_entry(&obj, "T")) {
+            (Some(parent), Some(own)) => format!("{parent}.{own}"),
+            (None, Some(own)) => own,
+            (Some(parent), None) => parent,
+            (None, None) => String::new(),
+        };
+        let field_type = obj
+            .get_dict("FT")
+            .ok()
+            .flatten()
+            .and_then(|ft| ft.as_name().ok().map(<[u8]>::to_vec))
+            .or(inherited_type);
+
+        let (mut kids, mut widgets) = (vec![], vec![]);
+        if let Ok(Some(all_kids)) = obj.get_dict("Kids") {
+            for i in 0..all_kids.len().unwrap_or(0) as i32 {
+                let Ok(Some(kid)) = all_kids.get_array(i) else {
+                    continue;
+                };
+                if kid.get_dict("T").ok().flatten().is_some() {
+                    kids.push(kid);
+                } else {
+                    widgets.push(kid);
+                }
+            }
+        }
+        let has_field_kids = !kids.is_empty();
+        if depth < MAX_FIELD_DEPTH {
+            for kid in kids.into_iter().rev() {
+                self.stack
+                    .push((kid, Some(name.clone()), field_type.clone(), depth + 1));
+            }
+        }
+        Some(FormField {
+            obj,
+            name,
+            field_type,
+            has_field_kids,
+            widgets,
+        })
+    }
+}
+
+fn acro_form(doc: &mupdf::Document) -> Option<PdfObject> {
+    let pdf = PdfDocument::try_from(doc.clone()).ok()?;
+    pdf.catalog().ok()?.get_dict("AcroForm").ok()?
+}
+
+/// The text string `key` of `obj`, unless it is blank.
+pub fn text_entry(obj: &PdfObject, key: &str) -> Option<String> {
+    let text = decode_text_string(obj.get_dict(key).ok()??.as_bytes().ok()?);
+    (!text.trim().is_empty()).then_some(text)
+}
+
+fn is_javascript(action: &PdfObject) -> bool {
+    action
+        .get_dict("S")
+        .ok()
+        .flatten()
+        .is_some_and(|kind| kind.as_name().is_ok_and(|name| name == b"JavaScript"))
+}
+
+/// Whether the field or widget `obj` computes or rewrites its value with JavaScript.
+fn has_value_script(obj: &PdfObject) -> bool {
+    let Ok(Some(actions)) = obj.get_dict("AA") else {
+        return false;
+    };
+    VALUE_ACTIONS.iter().any(|key| {
+        actions
+            .get_dict(*key)
+            .ok()
+            .flatten()
+            .is_some_and(|action| is_javascript(&action))
+    })
+}
+
+/// Whether `doc` has a form which computes some of its values with JavaScript, such as the totals
+/// of a tax form. Those stay as they are when the fields they depend on change.
+pub fn calculates_with_javascript(doc: &mupdf::Document) -> bool {
+    let Some(form) = acro_form(doc) else {
+        return false;
+    };
+    // The calculation order lists every field with a calculate action
+    if let Ok(Some(order)) = form.get_dict("CO")
+        && order.len().unwrap_or(0) > 0
+    {
+        return true;
+    }
+    Fields::of(doc)
+        .any(|field| has_value_script(&field.obj) || field.widgets.iter().any(has_value_script))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document_with_form(form: &str) -> mupdf::Document {
+        let mut pdf = PdfDocument::new();
+        pdf.new_page((100.0, 100.0)).unwrap();
+        let form = pdf.new_object_from_str(form).unwrap();
+        pdf.catalog().unwrap().dict_put("AcroForm", form).unwrap();
+        let mut bytes = vec![];
+        pdf.write_to(&mut bytes).unwrap();
+        mupdf::Document::from_bytes(&bytes, "application/pdf").unwrap()
+    }
+
+    #[test]
+    fn javascript_calculations_are_detected() {
+        let total = "<< /T (total) /AA << /C << /S /JavaScript /JS (AFSimple_Calculate) >> >> >>";
+        assert!(calculates_with_javascript(&document_with_form(&format!(
+            "<< /Fields [<< /T (income) >> {total}] >>"
+        ))));
+        // Nested in the kids of a field
+        assert!(calculates_with_javascript(&document_with_form(&format!(
+            "<< /Fields [<< /T (part1) /Kids [{total}] >>] >>"
+        ))));
+        assert!(calculates_with_javascript(&document_with_form(
+            "<< /Fields [] /CO [<< /T (total) >>] >>"
+        )));
+
+        // Scripts which don't touch any values are harmless
+        assert!(!calculates_with_javascript(&document_with_form(
+            "<< /Fields [<< /T (name) /AA << /Fo << /S /JavaScript /JS (app.alert(1)) >> >> >>] >>"
+        )));
+        assert!(!calculates_with_javascript(&document_with_form(
+            "<< /Fields [<< /T (name) >>] >>"
+        )));
+        assert!(!calculates_with_javascript(
+            &mupdf::Document::open("assets/links.pdf").unwrap()
+        ));
+    }
+}
@@ -0,0 +1,185 @@
+//! Draws the test documents the way the viewer does and compares them with the images in
+//! `assets/golden`, so changes to the rendering, the transforms and the dark mode can't change the
+//! output unnoticed. After an intended change to the output, or to add a new image, run the tests
+//! with `UPDATE_GOLDEN=1` to record them and commit the new images.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use mupdf::{Colorspace, Image, ImageFormat, Pixmap};
+
+use super::{export, widget::PdfViewer};
+use crate::geometry::Vector;
+
+const GOLDEN_DIR: &str = "assets/golden";
+/// How much a channel may differ, anti-aliasing varies slightly between builds of mupdf
+const CHANNEL_TOLERANCE: u8 = 8;
+/// Fraction of the pixels that may differ by more than [CHANNEL_TOLERANCE]
+const MAX_DIFFERING: f64 = 0.001;
+
+/// An image with three bytes per pixel.
+struct Rgb {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Rgb {
+    fn from_rgba(width: u32, height: u32, rgba: &[u8]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: rgba
+                .chunks(4)
+                .flat_map(|pixel| &pixel[..3])
+                .copied()
+                .collect(),
+        }
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let path = path
+            .to_str()
+            .ok_or_else(|| anyhow!("{path:?} isn't UTF-8"))?;
+        let pix = Image::from_file(path)?.to_pixmap()?;
+        let (n, stride) = (pix.n() as usize, pix.stride() as usize);
+        let mut pixels = Vec::with_capacity(pix.width() as usize * pix.height() as usize * 3);
+        for row in pix.samples().chunks(stride).take(pix.height() as usize) {
+            for pixel in row.chunks(n).take(pix.width() as usize) {
+                pixels.extend_from_slice(&pixel[..3]);
+            }
+        }
+        Ok(Self {
+            width: pix.width(),
+            height: pix.height(),
+            pixels,
+        })
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let mut pix = Pixmap::new_with_w_h(
+            &Colorspace::device_rgb(),
+            self.width as i32,
+            self.height as i32,
+            false,
+        )?;
+        pix.samples_mut().copy_from_slice(&self.pixels);
+        let path = path
+            .to_str()
+            .ok_or_else(|| anyhow!("{path:?} isn't UTF-8"))?;
+        pix.save_as(path, ImageFormat::PNG)?;
+        Ok(())
+    }
+}
+
+fn assert_matches_golden(name: &str, actual: &Rgb) {
+    let path = Path::new(GOLDEN_DIR).join(format!("{name}.png"));
+    if std::env::var("UPDATE_GOLDEN").is_ok_and(|update| update == "1") {
+        std::fs::create_dir_all(GOLDEN_DIR).unwrap();
+        actual.save(&path).unwrap();
+        eprintln!("Recorded {path:?}");
+        return;
+    }
+    assert!(
+        path.exists(),
+        "{path:?} is missing, record it with UPDATE_GOLDEN=1"
+    );
+    let golden = Rgb::load(&path).unwrap();
+    assert_eq!(
+        (actual.width, actual.height),
+        (golden.width, golden.height),
+        "{name} changed size"
+    );
+    let differing = actual
+        .pixels
+        .chunks(3)
+        .zip(golden.pixels.chunks(3))
+        .filter(|(a, b)| {
+            a.iter()
+                .zip(b.iter())
+                .any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE)
+        })
+        .count();
+    let allowed = (MAX_DIFFERING * (actual.width * actual.height) as f64) as usize;
+    assert!(
+        differing <= allowed,
+        "{differing} pixels of {name} differ from {path:?}, record it again with \
+        UPDATE_GOLDEN=1 if the change is intended"
+    );
+}
+
+/// Draws the first page of `file` at `scale`, moved by `translation` from where it starts, in a
+/// viewport of `size`.
+fn draw(
+    file: &str,
+    scale: f32,
+    translation: Vector<f32>,
+    size: iced::Size,
+    dark_mode: bool,
+) -> Rgb {
+    let mut viewer = PdfViewer::from_path(PathBuf::from("assets").join(file)).unwrap();
+    viewer.set_viewport(size);
+    viewer.set_pdf_dark_mode(dark_mode);
+    viewer.scale = scale;
+    viewer.translation += translation;
+    let (width, height, rgba) = viewer.draw_visible_page(0, size).unwrap();
+    Rgb::from_rgba(width, height, &rgba)
+}
+
+#[test]
+fn pages_render_like_before() {
+    let size = iced::Size::new(1600.0, 2400.0);
+    for (file, scale) in [
+        ("links.pdf", 1.0),
+        ("links.pdf", 2.0),
+        ("text-copy-test.pdf", 1.5),
+    ] {
+        let name = format!("{}-{scale}", file.trim_end_matches(".pdf"));
+        let drawn = draw(file, scale, Vector::zero(), size, false);
+        assert_matches_golden(&name, &drawn);
+    }
+}
+
+#[test]
+fn partly_visible_pages_render_like_before() {
+    // The page reaches past the viewport, so only the part on screen is drawn
+    let drawn = draw(
+        "text-copy-test.pdf",
+        2.0,
+        Vector::new(150.0, 200.0),
+        iced::Size::new(400.0, 300.0),
+        false,
+    );
+    assert_matches_golden("text-copy-test-partial", &drawn);
+}
+
+#[test]
+fn dark_mode_renders_like_before() {
+    let drawn = draw(
+        "links.pdf",
+        1.0,
+        Vector::zero(),
+        iced::Size::new(1600.0, 2400.0),
+        true,
+    );
+    assert_matches_golden("links-dark", &drawn);
+}
+
+#[test]
+fn exported_regions_render_like_before() {
+    let doc = mupdf::Document::open("assets/text-copy-test.pdf").unwrap();
+    let list = doc.load_page(0).unwrap().to_display_list(false).unwrap();
+    let bounds = list.bounds();
+    // The upper left quarter, which takes a translation as well as a scale to render
+    let region = mupdf::Rect::new(
+        bounds.x0,
+        bounds.y0,
+        (bounds.x0 + bounds.x1) / 2.0,
+        (bounds.y0 + bounds.y1) / 2.0,
+    );
+    let path = std::env::temp_dir().join(format!("miro-golden-{}.png", std::process::id()));
+    export::page_to_png(&list, Some(region), 2.0, &path).unwrap();
+    let exported = Rgb::load(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_matches_golden("text-copy-test-region", &exported);
+}
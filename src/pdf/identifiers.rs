@@ -0,0 +1,99 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// How many pages from the start are searched for an identifier, papers print theirs on the first
+/// page or in the header of the second
+pub const SEARCHED_PAGES: usize = 2;
+
+static DOI: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b10\.\d{4,9}/[-._;()/:<>A-Za-z0-9]+").unwrap());
+/// New style ids like `arXiv:2101.00001v2` and old style ones like `arXiv:hep-th/9901001`
+static ARXIV: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\barxiv:\s*(\d{4}\.\d{4,5}(?:v\d+)?|[a-z-]+(?:\.[a-z]{2})?/\d{7}(?:v\d+)?)")
+        .unwrap()
+});
+
+/// A persistent identifier of a paper, found in its text without going online.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identifier {
+    Doi(String),
+    Arxiv(String),
+}
+
+impl Identifier {
+    /// Where the paper can be looked up.
+    pub fn url(&self) -> String {
+        match self {
+            Identifier::Doi(doi) => format!("https://doi.org/{doi}"),
+            Identifier::Arxiv(id) => format!("https://arxiv.org/abs/{id}"),
+        }
+    }
+
+    /// A short reference to the paper, led by its title if the document has one.
+    pub fn citation(&self, title: Option<&str>) -> String {
+        let reference = match self {
+            Identifier::Doi(_) => self.url(),
+            Identifier::Arxiv(id) => format!("arXiv:{id}"),
+        };
+        match title.map(str::trim).filter(|title| !title.is_empty()) {
+            Some(title) => format!("{title}. {reference}"),
+            None => reference,
+        }
+    }
+}
+
+/// The first DOI in `text`, or the first arXiv id if there is no DOI. Papers on arXiv that were
+/// published since usually print both and the DOI is the one to cite.
+pub fn find(text: &str) -> Option<Identifier> {
+    if let Some(doi) = DOI.find(text) {
+        // Punctuation after a DOI at the end of a sentence isn't part of it
+        let doi = doi.as_str().trim_end_matches(['.', ',', ';', ':']);
+        let doi = if doi.ends_with(')') && doi.matches('(').count() < doi.matches(')').count() {
+            &doi[..doi.len() - 1]
+        } else {
+            doi
+        };
+        return Some(Identifier::Doi(doi.to_string()));
+    }
+    ARXIV
+        .captures(text)
+        .map(|captures| Identifier::Arxiv(captures[1].to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dois_are_preferred_and_trailing_punctuation_dropped() {
+        assert_eq!(
+            find("arXiv:2101.00001v2 [cs.LG]. Published as doi:10.1145/3442188.3445922."),
+            Some(Identifier::Doi("10.1145/3442188.3445922".to_string()))
+        );
+        assert_eq!(
+            find("(see https://doi.org/10.1016/S0140-6736(20)30183-5)"),
+            Some(Identifier::Doi("10.1016/S0140-6736(20)30183-5".to_string()))
+        );
+        assert_eq!(find("Version 10.2 of the manual"), None);
+    }
+
+    #[test]
+    fn arxiv_ids_of_both_styles_are_found() {
+        let new_style = find("Preprint. arXiv: 1706.03762v5 [cs.CL] 6 Dec 2017").unwrap();
+        assert_eq!(new_style, Identifier::Arxiv("1706.03762v5".to_string()));
+        assert_eq!(new_style.url(), "https://arxiv.org/abs/1706.03762v5");
+        assert_eq!(
+            new_style.citation(Some("Attention Is All You Need")),
+            "Attention Is All You Need. arXiv:1706.03762v5"
+        );
+        assert_eq!(
+            find("ARXIV:hep-th/9711200"),
+            Some(Identifier::Arxiv("hep-th/9711200".to_string()))
+        );
+        assert_eq!(
+            Identifier::Doi("10.1000/182".to_string()).citation(Some(" ")),
+            "https://doi.org/10.1000/182"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Optional content groups, the layers of CAD drawings and maps which can be shown and hidden.
+//!
+//! Mupdf reads which layers are visible from the default configuration of the document the first
+//! time a page is drawn and never looks at it again. Layers are hidden by rewriting that
+//! configuration right after opening the document, so changing them means opening it again.
+
+use anyhow::{Result, anyhow};
+use mupdf::pdf::{PdfDocument, PdfObject};
+
+use super::destinations::decode_text_string;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+}
+
+fn oc_properties(doc: &mupdf::Document) -> Result<Option<(PdfDocument, PdfObject)>> {
+    let Ok(pdf) = PdfDocument::try_from(doc.clone()) else {
+        return Ok(None);
+    };
+    let properties = pdf.catalog()?.get_dict("OCProperties")?;
+    Ok(properties.map(|properties| (pdf, properties)))
+}
+
+fn layer_name(ocg: &PdfObject, idx: usize) -> String {
+    match ocg.get_dict("Name") {
+        Ok(Some(name)) => match name.as_bytes() {
+            Ok(bytes) => decode_text_string(bytes),
+            Err(_) => format!("Layer {}", idx + 1),
+        },
+        _ => format!("Layer {}", idx + 1),
+    }
+}
+
+/// The object numbers of the groups listed under `key` in a configuration dictionary.
+fn listed_groups(config: Option<&PdfObject>, key: &str) -> Result<Vec<i32>> {
+    let Some(list) = config.map(|c| c.get_dict(key)).transpose()?.flatten() else {
+        return Ok(vec![]);
+    };
+    let mut groups = vec![];
+    for i in 0..list.len()? as i32 {
+        if let Some(ocg) = list.get_array(i)? {
+            groups.push(ocg.as_indirect()?);
+        }
+    }
+    Ok(groups)
+}
+
+/// The layers of `doc` in the order the document lists them, visible as the document shows them
+/// by default. Documents without layers and formats other than pdf have none.
+pub fn read(doc: &mupdf::Document) -> Result<Vec<Layer>> {
+    let Some((_, properties)) = oc_properties(doc)? else {
+        return Ok(vec![]);
+    };
+    let Some(groups) = properties.get_dict("OCGs")? else {
+        return Ok(vec![]);
+    };
+    let config = properties.get_dict("D")?;
+    let base_off = match config.as_ref().map(|c| c.get_dict("BaseState")) {
+        Some(Ok(Some(state))) => state.as_name()? == b"OFF",
+        _ => false,
+    };
+    let on = listed_groups(config.as_ref(), "ON")?;
+    let off = listed_groups(config.as_ref(), "OFF")?;
+
+    let mut layers = vec![];
+    for i in 0..groups.len()? {
+        let Some(ocg) = groups.get_array(i as i32)? else {
+            continue;
+        };
+        let num = ocg.as_indirect()?;
+        layers.push(Layer {
+            name: layer_name(&ocg, i),
+            visible: if off.contains(&num) {
+                false
+            } else if on.contains(&num) {
+                true
+            } else {
+                !base_off
+            },
+        });
+    }
+    Ok(layers)
+}
+
+/// Shows every layer of `doc` except those named in `hidden`. Has to be called before any page
+/// of the document is loaded, see the module documentation.
+pub fn hide(doc: &mupdf::Document, hidden: &[String]) -> Result<()> {
+    let Some((pdf, mut properties)) = oc_properties(doc)? else {
+        return Ok(());
+    };
+    let Some(groups) = properties.get_dict("OCGs")? else {
+        return Ok(());
+    };
+    let mut off = pdf.new_array()?;
+    for i in 0..groups.len()? {
+        if let Some(ocg) = groups.get_array(i as i32)?
+            && hidden.contains(&layer_name(&ocg, i))
+        {
+            off.array_push(ocg)?;
+        }
+    }
+    if properties.get_dict("D")?.is_none() {
+        properties.dict_put("D", pdf.new_dict()?)?;
+    }
+    let mut config = properties
+        .get_dict("D")?
+        .ok_or_else(|| anyhow!("The layer configuration couldn't be created"))?;
+    config.dict_put("BaseState", pdf.new_name("ON")?)?;
+    config.dict_delete("ON")?;
+    config.dict_put("OFF", off)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::backend::{DocumentBackend, MupdfBackend};
+
+    /// A page covered by a red square in the layer "Roads", which is hidden by default, and a
+    /// blue one in "Labels".
+    fn document_with_layers() -> mupdf::Document {
+        let mut pdf = PdfDocument::new();
+        let roads = pdf
+            .new_object_from_str("<< /Type /OCG /Name (Roads) >>")
+            .unwrap();
+        let roads = pdf.add_object(&roads).unwrap();
+        let labels = pdf
+            .new_object_from_str("<< /Type /OCG /Name (Labels) >>")
+            .unwrap();
+        let labels = pdf.add_object(&labels).unwrap();
+        let mut ocgs = pdf.new_array().unwrap();
+        ocgs.array_push(roads.try_clone().unwrap()).unwrap();
+        ocgs.array_push(labels.try_clone().unwrap()).unwrap();
+        let mut off = pdf.new_array().unwrap();
+        off.array_push(roads.try_clone().unwrap()).unwrap();
+        let mut config = pdf.new_dict().unwrap();
+        config.dict_put("OFF", off).unwrap();
+        let mut properties = pdf.new_dict().unwrap();
+        properties.dict_put("OCGs", ocgs).unwrap();
+        properties.dict_put("D", config).unwrap();
+        pdf.catalog()
+            .unwrap()
+            .dict_put("OCProperties", properties)
+            .unwrap();
+
+        let page = pdf.new_page((100.0, 100.0)).unwrap();
+        let mut groups = pdf.new_dict().unwrap();
+        groups.dict_put("R", roads).unwrap();
+        groups.dict_put("L", labels).unwrap();
+        page.object()
+            .get_dict("Resources")
+            .unwrap()
+            .unwrap()
+            .dict_put("Properties", groups)
+            .unwrap();
+        page.object()
+            .get_dict("Contents")
+            .unwrap()
+            .unwrap()
+            .write_stream_string(
+                "/OC /R BDC 1 0 0 rg 0 0 100 100 re f EMC \
+                /OC /L BDC 0 0 1 rg 0 0 50 50 re f EMC",
+            )
+            .unwrap();
+
+        let mut bytes = vec![];
+        pdf.write_to(&mut bytes).unwrap();
+        mupdf::Document::from_bytes(&bytes, "application/pdf").unwrap()
+    }
+
+    #[test]
+    fn layers_are_read_and_hidden() {
+        let layer = |name: &str, visible| Layer {
+            name: name.to_string(),
+            visible,
+        };
+        let doc = document_with_layers();
+        assert_eq!(
+            read(&doc).unwrap(),
+            [layer("Roads", false), layer("Labels", true)]
+        );
+
+        hide(&doc, &["Labels".to_string()]).unwrap();
+        assert_eq!(
+            read(&doc).unwrap(),
+            [layer("Roads", true), layer("Labels", false)]
+        );
+
+        // Only the red square of "Roads" covers the upper right corner
+        let corner = |doc: mupdf::Document| {
+            let image = MupdfBackend::new(doc).render_page(0, 1.0).unwrap();
+            let i = ((25 * image.width + 75) * 4) as usize;
+            image.pixels[i..i + 3].to_vec()
+        };
+        assert_eq!(corner(document_with_layers()), [255, 255, 255]);
+        let doc = document_with_layers();
+        hide(&doc, &[]).unwrap();
+        assert_eq!(corner(doc), [255, 0, 0]);
+
+        let no_layers = mupdf::Document::open("assets/links.pdf").unwrap();
+        assert!(read(&no_layers).unwrap().is_empty());
+        hide(&no_layers, &["Labels".to_string()]).unwrap();
+    }
+}
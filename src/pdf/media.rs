@@ -0,0 +1,305 @@
+//! Video, sound and 3D annotations. Mupdf can't play any of them and draws at most their poster
+//! image, so they get a placeholder and their files can be opened with another program.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use mupdf::pdf::{PdfDocument, PdfObject, PdfPage};
+
+use super::destinations::decode_text_string;
+use crate::paths;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Video,
+    Sound,
+    /// A 3D model (U3D or PRC)
+    Model,
+    /// Rich media, usually Flash
+    Interactive,
+}
+
+impl MediaKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            MediaKind::Video => "Video",
+            MediaKind::Sound => "Sound",
+            MediaKind::Model => "3D model",
+            MediaKind::Interactive => "Interactive content",
+        }
+    }
+
+    /// Drawn in the middle of the placeholder
+    pub fn glyph(self) -> &'static str {
+        match self {
+            MediaKind::Video | MediaKind::Interactive => "▶",
+            MediaKind::Sound => "♪",
+            MediaKind::Model => "◆",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaSource {
+    /// A file stored in the document, `object` is the number of its stream
+    Embedded { file_name: String, object: i32 },
+    /// A web address or a path, which is relative to the document unless absolute
+    External(String),
+}
+
+/// A multimedia annotation, shown as a placeholder on top of its page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Media {
+    pub page_idx: usize,
+    /// In page space
+    pub bounds: mupdf::Rect,
+    pub kind: MediaKind,
+    /// The description the document gives, if any
+    pub title: Option<String>,
+    /// Where the media can be opened from. Sounds store raw samples no other program can play
+    pub source: Option<MediaSource>,
+}
+
+impl Media {
+    pub fn tooltip(&self) -> String {
+        let mut tooltip = self.kind.label().to_string();
+        if let Some(title) = &self.title {
+            tooltip.push_str(": ");
+            tooltip.push_str(title);
+        }
+        match &self.source {
+            Some(MediaSource::Embedded { file_name, .. }) => {
+                tooltip.push_str(&format!("\nClick to open {file_name} externally"));
+            }
+            Some(MediaSource::External(target)) => {
+                tooltip.push_str(&format!("\nClick to open {target}"));
+            }
+            None => tooltip.push_str("\nCan't be played in miro"),
+        }
+        tooltip
+    }
+}
+
+fn text(obj: &PdfObject, key: &str) -> Option<String> {
+    let value = obj.get_dict(key).ok()??;
+    let text = decode_text_string(value.as_bytes().ok()?);
+    (!text.trim().is_empty()).then_some(text)
+}
+
+fn name(obj: &PdfObject, key: &str) -> Option<Vec<u8>> {
+    Some(obj.get_dict(key).ok()??.as_name().ok()?.to_vec())
+}
+
+/// The embedded or external file a file specification points to.
+fn file_spec(spec: &PdfObject) -> Option<MediaSource> {
+    if spec.is_string().ok()? {
+        return Some(MediaSource::External(decode_text_string(
+            spec.as_bytes().ok()?,
+        )));
+    }
+    let file_name = text(spec, "UF").or_else(|| text(spec, "F"))?;
+    if let Some(embedded) = spec.get_dict("EF").ok().flatten() {
+        let stream = match embedded.get_dict("UF").ok().flatten() {
+            Some(stream) => stream,
+            None => embedded.get_dict("F").ok()??,
+        };
+        return Some(MediaSource::Embedded {
+            file_name,
+            object: stream.as_indirect().ok()?,
+        });
+    }
+    Some(MediaSource::External(file_name))
+}
+
+/// The media clip of a rendition action, with whether it is a sound going by its mime type.
+fn rendition_clip(action: &PdfObject) -> Option<(Option<MediaSource>, bool)> {
+    let mut rendition = action.get_dict("R").ok()??;
+    // A selector rendition lists alternatives, the first one is the preferred one
+    if name(&rendition, "S").as_deref() == Some(b"SR") {
+        rendition = rendition.get_dict("R").ok()??.get_array(0).ok()??;
+    }
+    let clip = rendition.get_dict("C").ok()??;
+    let is_sound = text(&clip, "CT").is_some_and(|mime| mime.starts_with("audio/"));
+    let source = clip.get_dict("D").ok()?.as_ref().and_then(file_spec);
+    Some((source, is_sound))
+}
+
+fn read_annotation(annot: &PdfObject) -> Option<(MediaKind, Option<MediaSource>)> {
+    match name(annot, "Subtype")?.as_slice() {
+        b"Screen" => {
+            let clip = annot
+                .get_dict("A")
+                .ok()
+                .flatten()
+                .filter(|action| name(action, "S").as_deref() == Some(b"Rendition"))
+                .and_then(|action| rendition_clip(&action));
+            Some(match clip {
+                Some((source, true)) => (MediaKind::Sound, source),
+                Some((source, false)) => (MediaKind::Video, source),
+                None => (MediaKind::Video, None),
+            })
+        }
+        b"Movie" => {
+            let movie = annot.get_dict("Movie").ok().flatten();
+            let source = movie
+                .and_then(|movie| movie.get_dict("F").ok().flatten())
+                .and_then(|spec| file_spec(&spec));
+            Some((MediaKind::Video, source))
+        }
+        b"Sound" => Some((MediaKind::Sound, None)),
+        b"RichMedia" => {
+            let assets = annot
+                .get_dict("RichMediaContent")
+                .ok()??
+                .get_dict("Assets")
+                .ok()
+                .flatten()
+                .and_then(|assets| assets.get_dict("Names").ok().flatten());
+            // Alternating names and file specifications
+            let source = assets
+                .and_then(|names| names.get_array(1).ok().flatten())
+                .and_then(|spec| file_spec(&spec));
+            Some((MediaKind::Interactive, source))
+        }
+        b"3D" => {
+            let source = annot.get_dict("3DD").ok().flatten().and_then(|stream| {
+                let extension = match name(&stream, "Subtype").as_deref() {
+                    Some(b"PRC") => "prc",
+                    _ => "u3d",
+                };
+                Some(MediaSource::Embedded {
+                    file_name: format!("model.{extension}"),
+                    object: stream.as_indirect().ok()?,
+                })
+            });
+            Some((MediaKind::Model, source))
+        }
+        _ => None,
+    }
+}
+
+/// Every multimedia annotation of `doc` in page order. Formats other than pdf have none.
+pub fn read(doc: &mupdf::Document) -> Result<Vec<Media>> {
+    if PdfDocument::try_from(doc.clone()).is_err() {
+        return Ok(vec![]);
+    }
+    let mut media = vec![];
+    for (page_idx, page) in doc.pages()?.enumerate() {
+        let Ok(page) = PdfPage::try_from(page?) else {
+            continue;
+        };
+        let Some(annots) = page.object().get_dict("Annots")? else {
+            continue;
+        };
+        let ctm = page.ctm()?;
+        for i in 0..annots.len()? as i32 {
+            let Some(annot) = annots.get_array(i)? else {
+                continue;
+            };
+            let Some((kind, source)) = read_annotation(&annot) else {
+                continue;
+            };
+            let Some(rect) = annot.get_dict("Rect")? else {
+                continue;
+            };
+            let coord = |i| -> Result<f32> {
+                Ok(rect
+                    .get_array(i)?
+                    .ok_or_else(|| anyhow!("Incomplete annotation rectangle"))?
+                    .as_float()?)
+            };
+            let (x0, y0, x1, y1) = (coord(0)?, coord(1)?, coord(2)?, coord(3)?);
+            let bounds = mupdf::Rect::new(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1));
+            media.push(Media {
+                page_idx,
+                bounds: bounds.transform(&ctm),
+                kind,
+                title: text(&annot, "Contents").or_else(|| text(&annot, "T")),
+                source,
+            });
+        }
+    }
+    Ok(media)
+}
+
+/// Writes the embedded file `object` of `doc` to the temporary directory so it can be opened with
+/// another program.
+pub fn extract(doc: &mupdf::Document, object: i32, file_name: &str) -> Result<PathBuf> {
+    paths::ensure_writable()?;
+    let pdf = PdfDocument::try_from(doc.clone())?;
+    let bytes = pdf.new_indirect(object, 0)?.read_stream()?;
+    // The name comes from the document, only its last component is trusted
+    let file_name = Path::new(file_name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "media".to_string());
+    let path = std::env::temp_dir().join(format!("miro-{file_name}"));
+    std::fs::write(&path, bytes)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A page with an embedded video, a linked sound and a movie without any file.
+    fn document_with_media() -> mupdf::Document {
+        let mut pdf = PdfDocument::new();
+        let video = pdf.new_dict().unwrap();
+        let mut video = pdf.add_object(&video).unwrap();
+        video.write_stream_string("not really a video").unwrap();
+        let video = video.as_indirect().unwrap();
+
+        let mut page = pdf.new_page((200.0, 200.0)).unwrap();
+        let annots = pdf
+            .new_object_from_str(&format!(
+                "[<< /Type /Annot /Subtype /Screen /Rect [10 150 60 190] /T (Intro) \
+                    /A << /S /Rendition /R << /S /MR /C << /S /MCD /CT (video/mp4) \
+                        /D << /Type /Filespec /F (intro.mp4) /EF << /F {video} 0 R >> >> >> >> >> >>
+                  << /Type /Annot /Subtype /Screen /Rect [10 10 60 50] \
+                    /A << /S /Rendition /R << /S /MR /C << /S /MCD /CT (audio/mpeg) \
+                        /D (https://example.com/theme.mp3) >> >> >> >>
+                  << /Type /Annot /Subtype /Movie /Rect [100 10 150 50] >>
+                  << /Type /Annot /Subtype /Text /Rect [100 100 120 120] >>]"
+            ))
+            .unwrap();
+        page.object().dict_put("Annots", annots).unwrap();
+        page.update().unwrap();
+
+        let mut bytes = vec![];
+        pdf.write_to(&mut bytes).unwrap();
+        mupdf::Document::from_bytes(&bytes, "application/pdf").unwrap()
+    }
+
+    #[test]
+    fn media_annotations_are_found() {
+        let doc = document_with_media();
+        let media = read(&doc).unwrap();
+        assert_eq!(
+            media.iter().map(|m| m.kind).collect::<Vec<_>>(),
+            [MediaKind::Video, MediaKind::Sound, MediaKind::Video]
+        );
+        // Page space has its origin at the top left corner
+        assert_eq!(media[0].bounds, mupdf::Rect::new(10.0, 10.0, 60.0, 50.0));
+        assert_eq!(media[0].title.as_deref(), Some("Intro"));
+        assert_eq!(
+            media[1].source,
+            Some(MediaSource::External(
+                "https://example.com/theme.mp3".to_string()
+            ))
+        );
+        assert_eq!(media[2].source, None);
+        assert!(media[2].tooltip().contains("Can't be played"));
+
+        let Some(MediaSource::Embedded { file_name, object }) = &media[0].source else {
+            panic!("The video should be embedded, got {:?}", media[0].source);
+        };
+        assert_eq!(file_name, "intro.mp4");
+        let path = extract(&doc, *object, "../../intro.mp4").unwrap();
+        assert_eq!(path, std::env::temp_dir().join("miro-intro.mp4"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"not really a video");
+
+        let no_media = mupdf::Document::open("assets/links.pdf").unwrap();
+        assert!(read(&no_media).unwrap().is_empty());
+    }
+}
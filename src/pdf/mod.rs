@@ -1,13 +1,34 @@
 use crate::{
     app::AppMessage,
     config::MouseAction,
+    error::MiroError,
     geometry::{Rect, Vector},
-    pdf::page_layout::PageLayout,
+    pdf::{caret::CaretMotion, page_layout::PageLayout, text_layout::TableFormat},
 };
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use strum::EnumString;
 
+pub mod archive;
+pub mod backend;
+pub mod caret;
+pub mod copy_cleanup;
+pub mod destinations;
+pub mod export;
+pub mod forms;
+#[cfg(test)]
+mod golden;
+pub mod identifiers;
+pub mod layers;
+pub mod media;
+pub mod overview;
 pub mod page_layout;
+pub mod pointer;
+pub mod recolor;
+pub mod rect_index;
+pub mod scheduler;
+pub mod scrollbar;
+pub mod text_layout;
 pub mod widget;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, EnumString, Default, PartialEq, Eq)]
@@ -17,6 +38,111 @@ pub enum SearchMethod {
     Regex,
 }
 
+/// Options applied on top of the [SearchMethod], toggled in the search bar or given as prefixes
+/// of the needle.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct SearchFlags {
+    pub ignore_case: bool,
+    /// Only match where the needle isn't part of a longer word
+    pub whole_word: bool,
+}
+
+/// A zoom level cycled through with [PdfMessage::CycleZoomPreset], set with `ZoomPresets` in the
+/// config.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoomPreset {
+    /// 1.0 is 100%
+    Scale(f32),
+    /// The whole current page fits on screen
+    Fit,
+    /// The current page is as wide as the screen
+    FitWidth,
+    /// The column of text in the middle of the screen is as wide as the screen, see
+    /// [text_layout::columns]
+    FitColumn,
+}
+
+impl FromStr for ZoomPreset {
+    type Err = String;
+
+    /// Either `Fit`, `FitWidth`, `FitColumn` or a percentage like `150%`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Fit" => Ok(ZoomPreset::Fit),
+            "FitWidth" => Ok(ZoomPreset::FitWidth),
+            "FitColumn" => Ok(ZoomPreset::FitColumn),
+            _ => s
+                .strip_suffix('%')
+                .and_then(|percent| percent.parse::<f32>().ok())
+                .filter(|percent| *percent > 0.0 && percent.is_finite())
+                .map(|percent| ZoomPreset::Scale(percent / 100.0))
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid zoom preset: '{s}'. Use Fit, FitWidth, FitColumn or a \
+                        percentage like 150%"
+                    )
+                }),
+        }
+    }
+}
+
+const SEARCH_PREFIXES: [&str; 5] = ["re:", "lit:", "i:", "c:", "w:"];
+
+/// Strips search prefixes from the start of `needle` and applies them to `method` and `flags`.
+/// `re:` and `lit:` pick the method, `i:` and `c:` turn ignoring case on and off and `w:` only
+/// matches whole words. They can be combined, like `i:w:miro`. In a plain text search a
+/// backslash in front of a prefix searches for it literally, `\i:` finds `i:`. Regular
+/// expressions keep their backslashes, `[i]:` finds `i:` there.
+pub fn parse_search_prefixes(
+    mut needle: &str,
+    mut method: SearchMethod,
+    mut flags: SearchFlags,
+) -> (&str, SearchMethod, SearchFlags) {
+    loop {
+        if method == SearchMethod::PlainText
+            && let Some(rest) = needle.strip_prefix('\\')
+            && SEARCH_PREFIXES
+                .iter()
+                .any(|prefix| rest.starts_with(prefix))
+        {
+            return (rest, method, flags);
+        } else if let Some(rest) = needle.strip_prefix("re:") {
+            method = SearchMethod::Regex;
+            needle = rest;
+        } else if let Some(rest) = needle.strip_prefix("lit:") {
+            method = SearchMethod::PlainText;
+            needle = rest;
+        } else if let Some(rest) = needle.strip_prefix("i:") {
+            flags.ignore_case = true;
+            needle = rest;
+        } else if let Some(rest) = needle.strip_prefix("c:") {
+            flags.ignore_case = false;
+            needle = rest;
+        } else if let Some(rest) = needle.strip_prefix("w:") {
+            flags.whole_word = true;
+            needle = rest;
+        } else {
+            return (needle, method, flags);
+        }
+    }
+}
+
+/// The regex matching `needle`, or `None` if it is an invalid regular expression.
+fn search_regex(needle: &str, method: SearchMethod, flags: SearchFlags) -> Option<regex::Regex> {
+    let mut pattern = match method {
+        SearchMethod::PlainText => regex::escape(needle),
+        SearchMethod::Regex => needle.to_string(),
+    };
+    if flags.whole_word {
+        // Unlike \b these also work for needles starting or ending with punctuation
+        pattern = format!(r"\b{{start-half}}(?:{pattern})\b{{end-half}}");
+    }
+    regex::RegexBuilder::new(&pattern)
+        .case_insensitive(flags.ignore_case)
+        .build()
+        .ok()
+}
+
 /// A single search result, potentially spanning multiple pages.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SearchMatch {
@@ -32,6 +158,20 @@ pub fn find_search_matches(
     haystack: &str,
     needle: &str,
     method: SearchMethod,
+    flags: SearchFlags,
+    char_bboxes: &[(usize, usize, Rect<f32>)],
+) -> Vec<SearchMatch> {
+    let (needle, method, flags) = parse_search_prefixes(needle, method, flags);
+    find_matches(haystack, needle, method, flags, char_bboxes)
+}
+
+/// Like [find_search_matches], but `needle` is searched for as it is without looking for search
+/// prefixes. For text which wasn't typed as a query, like the selection.
+pub fn find_matches(
+    haystack: &str,
+    needle: &str,
+    method: SearchMethod,
+    flags: SearchFlags,
     char_bboxes: &[(usize, usize, Rect<f32>)],
 ) -> Vec<SearchMatch> {
     if needle.is_empty() {
@@ -40,21 +180,15 @@ pub fn find_search_matches(
 
     // Collect (start_byte, end_byte) for each match.
     let mut byte_ranges = vec![];
-    match method {
-        SearchMethod::PlainText => {
-            let _span = tracy_client::span!("Plain text search");
-            for (start, matched) in haystack.match_indices(needle) {
-                byte_ranges.push((start, start + matched.len()));
-            }
+    if method == SearchMethod::PlainText && flags == SearchFlags::default() {
+        let _span = tracy_client::span!("Plain text search");
+        for (start, matched) in haystack.match_indices(needle) {
+            byte_ranges.push((start, start + matched.len()));
         }
-        SearchMethod::Regex => {
-            let _span = tracy_client::span!("Regex search");
-            if let Ok(re) = regex::Regex::new(needle) {
-                for capture in re.captures_iter(haystack) {
-                    let m = capture.get_match();
-                    byte_ranges.push((m.start(), m.end()));
-                }
-            }
+    } else if let Some(re) = search_regex(needle, method, flags) {
+        let _span = tracy_client::span!("Regex search");
+        for m in re.find_iter(haystack) {
+            byte_ranges.push((m.start(), m.end()));
         }
     }
 
@@ -113,6 +247,12 @@ pub fn merge_search_rects(char_rects: &[(usize, Rect<f32>)]) -> Vec<(usize, Rect
 pub enum PdfMessage {
     NextPage,
     PreviousPage,
+    FirstPage,
+    LastPage,
+    /// Moves the view to the next paragraph or figure in reading order, see
+    /// [text_layout::reading_order]
+    NextBlock,
+    PreviousBlock,
     PageUp,
     PageDown,
     HalfPageUp,
@@ -126,6 +266,27 @@ pub enum PdfMessage {
     ZoomOut,
     ZoomHome,
     ZoomFit,
+    /// Zoom to the column of text in the middle of the screen, see [ZoomPreset::FitColumn]
+    ZoomFitColumn,
+    /// Zoom and pan so the last mouse selection fills the screen
+    ZoomToSelection,
+    /// Zooms around the mouse for a trackpad pinch of the given size in pixels, reported as
+    /// scrolling with Ctrl held
+    Pinch(f32),
+    /// Outline the media, crop, bleed, trim and art boxes of every page
+    TogglePageBoxes,
+    /// Step through screen-sized segments of pages too large for the screen with NextPage and
+    /// PreviousPage, down each column before moving right
+    TogglePageSlicing,
+    /// Show a grid of every page instead of the document, or go back from it
+    ToggleOverview,
+    CloseOverview,
+    /// Leave the page grid on the n-th page
+    OpenOverviewPage(usize),
+    /// Zoom to the n-th of the configured `ZoomPresets`
+    ZoomPreset(usize),
+    /// Zoom to the preset after the one used last
+    CycleZoomPreset,
     /// Move some distance in Document space
     Move(Vector<f32>),
     MouseMoved(Vector<f32>),
@@ -136,8 +297,40 @@ pub enum PdfMessage {
     ActivateLink(usize),
     /// Close/hide link hitboxes
     CloseLinkHitboxes,
+    /// Highlight visible words starting with a typed prefix and jump to one by its hint
+    ToggleWordHints,
+    CloseWordHints,
+    /// Scroll to the n-th word of the document
+    JumpToWord(usize),
+    /// Show a text cursor on the current page which is moved with the arrow keys
+    ToggleCaretMode,
+    CloseCaret,
+    MoveCaret(CaretMotion),
+    /// Move the caret while selecting the text it passes over
+    ExtendSelection(CaretMotion),
+    /// Copy the selected text to the clipboard
+    CopySelection,
+    /// Copy all text of the current page to the clipboard and flash the page
+    SelectAllPage,
+    /// Copy the text of a table covered by the last mouse selection, one line per row
+    CopyTable(TableFormat),
+    /// Copy the DOI or arXiv id of the document along with its title
+    CopyCitation,
+    /// Look up the DOI or arXiv id of the document in the browser
+    OpenIdentifier,
+    /// Copy the file name, the current page and the section it is in
+    CopyPageReference,
+    /// Look up the selected text with the configured `LookupCommand`
+    LookupSelection,
+    /// Copy the formula covered by the last mouse selection as LaTeX, read by the configured
+    /// `FormulaCommand`
+    CopyFormula,
+    /// Highlight every occurrence of the selected text, or hide them again if they are shown
+    HighlightOccurrences,
     FileChanged,
     PrintPdf,
+    /// Render the last selection, or the current page without one, to a PNG and open it
+    SharePage,
     HighlightSearchResults,
     HideSearchResults,
     JumpToSearchResult(usize),
@@ -145,18 +338,184 @@ pub enum PdfMessage {
     PreviousSearchResult,
     UpdateSearchNeedle(String),
     SetSearchMethod(SearchMethod),
+    SetSearchFlags(SearchFlags),
     ToggleSearchMethod,
     /// Close the comment popup
     CloseComment,
+    /// Removes the n-th entry of [widget::PdfViewer::annotations] from the document
+    DeleteAnnotation(usize),
+    /// Collapses or expands the annotations of a page in the sidebar
+    ToggleAnnotationPage(usize),
+    /// Shows or hides the n-th entry of [widget::PdfViewer::layers]
+    ToggleLayer(usize),
+    /// Hides the notice that the form's calculations aren't run
+    DismissScriptNotice,
     #[strum(disabled)]
     #[serde(skip)]
     SearchResultsReady(Vec<SearchMatch>, u64),
+    /// Searches for a needle and scrolls to one of its matches, used by the search across all
+    /// tabs
+    #[strum(disabled)]
+    #[serde(skip)]
+    ShowSearchMatch(String, SearchMethod, SearchFlags, SearchMatch),
+    #[strum(disabled)]
+    #[serde(skip)]
+    OccurrencesReady(Vec<SearchMatch>, u64),
+    /// Hides the start/end of document indicator unless it has been shown again since
+    #[strum(disabled)]
+    #[serde(skip)]
+    HideEdgeIndicator(u64),
+    /// Ends the flash of a page copied with [PdfMessage::SelectAllPage] unless another started
+    #[strum(disabled)]
+    #[serde(skip)]
+    HidePageFlash(u64),
+    /// Hides the scrollbars unless the view has moved since
+    #[strum(disabled)]
+    #[serde(skip)]
+    HideScrollbars(u64),
+    /// Handled by the app, which shows it as a notification
+    #[strum(disabled)]
+    #[serde(skip)]
+    Error(MiroError),
     #[default]
     None,
 }
 
+impl PdfMessage {
+    /// Turns the outcome of a task into [PdfMessage::Error] if it failed.
+    pub fn from_result(result: Result<(), MiroError>) -> Self {
+        match result {
+            Ok(()) => PdfMessage::None,
+            Err(e) => PdfMessage::Error(e),
+        }
+    }
+}
+
 impl From<PdfMessage> for AppMessage {
     fn from(value: PdfMessage) -> Self {
         AppMessage::PdfMessage(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every character gets its own box on a single line
+    fn char_bboxes(haystack: &str) -> Vec<(usize, usize, Rect<f32>)> {
+        haystack
+            .char_indices()
+            .enumerate()
+            .map(|(i, (byte, _))| {
+                let x = i as f32;
+                (
+                    0,
+                    byte,
+                    Rect::from_points(Vector::new(x, 0.0), Vector::new(x + 1.0, 1.0)),
+                )
+            })
+            .collect()
+    }
+
+    fn search(haystack: &str, needle: &str, method: SearchMethod, flags: SearchFlags) -> Vec<&str> {
+        find_search_matches(haystack, needle, method, flags, &char_bboxes(haystack))
+            .into_iter()
+            .map(|m| &haystack[m.start_byte..m.end_byte])
+            .collect()
+    }
+
+    #[test]
+    fn zoom_presets_are_percentages_or_fits() {
+        assert_eq!("150%".parse(), Ok(ZoomPreset::Scale(1.5)));
+        assert_eq!("FitWidth".parse(), Ok(ZoomPreset::FitWidth));
+        assert!("150".parse::<ZoomPreset>().is_err());
+        assert!("0%".parse::<ZoomPreset>().is_err());
+    }
+
+    #[test]
+    fn selected_text_is_searched_as_it_is() {
+        let haystack = "re: see w:page 3, re: see w:page 4";
+        let matches = find_matches(
+            haystack,
+            "re: see w:",
+            SearchMethod::PlainText,
+            SearchFlags::default(),
+            &char_bboxes(haystack),
+        );
+        assert_eq!(matches.len(), 2);
+        assert_eq!(
+            &haystack[matches[1].start_byte..matches[1].end_byte],
+            "re: see w:"
+        );
+    }
+
+    #[test]
+    fn prefixes_override_the_search_bar() {
+        let flags = SearchFlags {
+            ignore_case: true,
+            whole_word: false,
+        };
+        assert_eq!(
+            parse_search_prefixes("c:w:re:a+", SearchMethod::PlainText, flags),
+            (
+                "a+",
+                SearchMethod::Regex,
+                SearchFlags {
+                    ignore_case: false,
+                    whole_word: true,
+                }
+            )
+        );
+        assert_eq!(
+            parse_search_prefixes("http://x", SearchMethod::Regex, flags),
+            ("http://x", SearchMethod::Regex, flags)
+        );
+        assert_eq!(
+            parse_search_prefixes(r"w:\i: am", SearchMethod::PlainText, flags),
+            (
+                "i: am",
+                SearchMethod::PlainText,
+                SearchFlags {
+                    ignore_case: true,
+                    whole_word: true,
+                }
+            )
+        );
+        // The backslash is part of the regex
+        assert_eq!(
+            parse_search_prefixes(r"re:\w:", SearchMethod::PlainText, flags),
+            (r"\w:", SearchMethod::Regex, flags)
+        );
+        assert_eq!(
+            parse_search_prefixes(r"\d:", SearchMethod::PlainText, flags),
+            (r"\d:", SearchMethod::PlainText, flags)
+        );
+    }
+
+    #[test]
+    fn search_modes() {
+        let haystack = "Cat catalog cat. (cat)";
+        let plain = SearchFlags::default();
+        assert_eq!(
+            search(haystack, "cat", SearchMethod::PlainText, plain),
+            ["cat", "cat", "cat"]
+        );
+        assert_eq!(
+            search(haystack, "i:w:cat", SearchMethod::PlainText, plain),
+            ["Cat", "cat", "cat"]
+        );
+        assert_eq!(
+            search(haystack, "w:(cat)", SearchMethod::PlainText, plain),
+            ["(cat)"]
+        );
+        assert_eq!(
+            search(haystack, "cat.", SearchMethod::PlainText, plain),
+            ["cat."]
+        );
+        assert_eq!(
+            search(haystack, "c[a-z]{4,}", SearchMethod::Regex, plain),
+            ["catalog"]
+        );
+        assert!(search(haystack, "re:(", SearchMethod::PlainText, plain).is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+use iced::{
+    Border, Color, Element, Length,
+    advanced::image,
+    alignment::Horizontal,
+    widget::{self, button, operation, scrollable},
+};
+
+use crate::pdf::PdfMessage;
+
+const OVERVIEW_SCROLLABLE: &str = "overview_scrollable";
+/// Width of the thumbnails in pixels, they are scaled to the size of the grid cells
+pub const THUMBNAIL_WIDTH: f32 = 320.0;
+const DEFAULT_COLUMNS: usize = 4;
+const MAX_COLUMNS: usize = 12;
+const SPACING: f32 = 12.0;
+/// Space around a thumbnail inside its cell, where the outline of the current page goes
+const CELL_PADDING: f32 = 4.0;
+/// Height of the page number under each thumbnail
+const LABEL_HEIGHT: f32 = 20.0;
+
+/// A grid of every page of the document, zoomed by changing how many pages fit on a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overview {
+    pub columns: usize,
+}
+
+impl Default for Overview {
+    fn default() -> Self {
+        Self {
+            columns: DEFAULT_COLUMNS,
+        }
+    }
+}
+
+/// What the grid knows about a page.
+#[derive(Debug, Clone)]
+pub struct PageThumbnail {
+    /// `None` until the page has been rendered
+    pub handle: Option<image::Handle>,
+    /// Height divided by width
+    pub aspect_ratio: f32,
+}
+
+impl Overview {
+    pub fn zoom_in(&mut self) {
+        self.columns = self.columns.saturating_sub(1).max(1);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.columns = (self.columns + 1).min(MAX_COLUMNS);
+    }
+
+    /// Scrolls the grid so the row of `page` is on screen.
+    pub fn scroll_to<T: Send + 'static>(&self, page: usize, page_count: usize) -> iced::Task<T> {
+        let rows = page_count.div_ceil(self.columns);
+        let y = if rows > 1 {
+            (page / self.columns) as f32 / (rows - 1) as f32
+        } else {
+            0.0
+        };
+        operation::snap_to(
+            widget::Id::new(OVERVIEW_SCROLLABLE),
+            scrollable::RelativeOffset { x: 0.0, y },
+        )
+    }
+
+    pub fn view<'a>(
+        &self,
+        pages: Vec<PageThumbnail>,
+        current_page: usize,
+    ) -> Element<'a, PdfMessage> {
+        let columns = self.columns;
+        widget::responsive(move |size| {
+            let cell_width = ((size.width - SPACING * (columns + 1) as f32) / columns as f32
+                - 2.0 * CELL_PADDING)
+                .max(16.0);
+            let mut grid = widget::column![].spacing(SPACING).padding(SPACING);
+            for (row_idx, row) in pages.chunks(columns).enumerate() {
+                let mut cells = widget::row![].spacing(SPACING);
+                for (col_idx, page) in row.iter().enumerate() {
+                    let page_idx = row_idx * columns + col_idx;
+                    cells = cells.push(cell(page, page_idx, page_idx == current_page, cell_width));
+                }
+                grid = grid.push(cells);
+            }
+            widget::container(
+                widget::scrollable(grid)
+                    .id(widget::Id::new(OVERVIEW_SCROLLABLE))
+                    .width(Length::Fill)
+                    .height(Length::Fill),
+            )
+            .style(|theme: &iced::Theme| widget::container::Style {
+                background: Some(theme.extended_palette().background.base.color.into()),
+                ..Default::default()
+            })
+            .into()
+        })
+        .into()
+    }
+}
+
+fn cell<'a>(
+    page: &PageThumbnail,
+    page_idx: usize,
+    is_current: bool,
+    width: f32,
+) -> Element<'a, PdfMessage> {
+    let height = width * page.aspect_ratio;
+    let thumbnail: Element<'a, PdfMessage> = match &page.handle {
+        Some(handle) => widget::image(handle.clone())
+            .width(Length::Fixed(width))
+            .height(Length::Fixed(height))
+            .into(),
+        None => widget::container(widget::space::horizontal())
+            .width(Length::Fixed(width))
+            .height(Length::Fixed(height))
+            .style(|theme: &iced::Theme| widget::container::Style {
+                background: Some(theme.extended_palette().background.weak.color.into()),
+                ..Default::default()
+            })
+            .into(),
+    };
+    button(
+        widget::column![
+            thumbnail,
+            widget::text((page_idx + 1).to_string())
+                .size(12.0)
+                .height(Length::Fixed(LABEL_HEIGHT)),
+        ]
+        .align_x(Horizontal::Center)
+        .spacing(4.0),
+    )
+    .padding(CELL_PADDING)
+    .on_press(PdfMessage::OpenOverviewPage(page_idx))
+    .style(move |theme: &iced::Theme, status| {
+        let palette = theme.extended_palette();
+        button::Style {
+            background: match status {
+                button::Status::Hovered | button::Status::Pressed => {
+                    Some(palette.background.strong.color.into())
+                }
+                _ => None,
+            },
+            text_color: palette.background.base.text,
+            border: Border {
+                color: if is_current {
+                    palette.primary.base.color
+                } else {
+                    Color::TRANSPARENT
+                },
+                width: 2.0,
+                radius: 4.0.into(),
+            },
+            ..Default::default()
+        }
+    })
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zooming_changes_the_number_of_columns_within_limits() {
+        let mut overview = Overview { columns: 2 };
+        overview.zoom_in();
+        overview.zoom_in();
+        assert_eq!(overview.columns, 1);
+        for _ in 0..20 {
+            overview.zoom_out();
+        }
+        assert_eq!(overview.columns, MAX_COLUMNS);
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use anyhow::{anyhow, Result};
 use iced::Size;
 use mupdf::Document;
@@ -7,7 +9,7 @@ use strum::EnumString;
 
 use crate::geometry::{Rect, Vector};
 
-#[derive(Debug, Clone, Serialize, Deserialize, EnumString, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Default)]
 pub enum PageLayout {
     #[default]
     /// One page per row, many rows
@@ -20,6 +22,194 @@ pub enum PageLayout {
     Presentation,
 }
 
+/// Groups the pages of a document into spreads, the pages shown next to each other on one row of a
+/// [PageLayout]. All page based navigation goes through this so the layouts agree on what the
+/// next and previous page is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpreadLayout {
+    page_count: usize,
+    pages_per_spread: usize,
+    /// The first page is shown on its own
+    cover_page: bool,
+}
+
+impl SpreadLayout {
+    pub fn new(layout: &PageLayout, page_count: usize) -> Self {
+        let (pages_per_spread, cover_page) = match layout {
+            PageLayout::SinglePage | PageLayout::Presentation => (1, false),
+            PageLayout::DoublePage => (2, false),
+            PageLayout::DoublePageTitlePage => (2, true),
+        };
+        Self {
+            page_count,
+            pages_per_spread,
+            cover_page,
+        }
+    }
+
+    pub fn spread_count(&self) -> usize {
+        if self.cover_page && self.page_count > 0 {
+            1 + (self.page_count - 1).div_ceil(self.pages_per_spread)
+        } else {
+            self.page_count.div_ceil(self.pages_per_spread)
+        }
+    }
+
+    /// The spread containing `page`. Pages past the end belong to the last spread.
+    pub fn spread_of(&self, page: usize) -> usize {
+        let page = page.min(self.page_count.saturating_sub(1));
+        if self.cover_page {
+            if page == 0 {
+                0
+            } else {
+                1 + (page - 1) / self.pages_per_spread
+            }
+        } else {
+            page / self.pages_per_spread
+        }
+    }
+
+    /// The pages shown in `spread`. The last spread can contain fewer pages than the others.
+    pub fn pages_in(&self, spread: usize) -> Range<usize> {
+        let start = if self.cover_page && spread > 0 {
+            1 + (spread - 1) * self.pages_per_spread
+        } else if self.cover_page {
+            0
+        } else {
+            spread * self.pages_per_spread
+        };
+        let len = if self.cover_page && spread == 0 {
+            1
+        } else {
+            self.pages_per_spread
+        };
+        start.min(self.page_count)..(start + len).min(self.page_count)
+    }
+
+    /// First page of the spread after the one containing `page`, or the first page of the last
+    /// spread if there is none.
+    pub fn next_page(&self, page: usize) -> usize {
+        let last = self.spread_count().saturating_sub(1);
+        self.pages_in((self.spread_of(page) + 1).min(last)).start
+    }
+
+    /// First page of the spread before the one containing `page`.
+    pub fn previous_page(&self, page: usize) -> usize {
+        self.pages_in(self.spread_of(page).saturating_sub(1)).start
+    }
+}
+
+/// Where the viewport's top left corner goes, relative to the top left of a page, to show each
+/// screen-sized segment of the page in turn. Segments go down the page before moving right to the
+/// next column, like reading a poster or a drawing. Neighbouring segments overlap so the last one
+/// lines up with the edge of the page, and an axis the page fits on is kept centered.
+pub fn page_segments(page: Vector<f32>, viewport: Vector<f32>) -> Vec<Vector<f32>> {
+    fn offsets(page: f32, viewport: f32) -> Vec<f32> {
+        if page <= viewport || viewport <= 0.0 {
+            return vec![(page - viewport) / 2.0];
+        }
+        let count = (page / viewport).ceil() as usize;
+        let step = (page - viewport) / (count - 1) as f32;
+        (0..count).map(|i| i as f32 * step).collect()
+    }
+    let rows = offsets(page.y, viewport.y);
+    offsets(page.x, viewport.x)
+        .into_iter()
+        .flat_map(|x| rows.iter().map(move |&y| Vector::new(x, y)))
+        .collect()
+}
+
+/// Maps between screen space and the page space of each page for one arrangement of the pages.
+/// Every page has its own offset, so points on the right hand page of a spread or below a cover
+/// page map to that page rather than the leftmost one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageMapping {
+    /// Where each page is on screen
+    screen_rects: Vec<Rect<f32>>,
+    /// The bounds of each page in page space
+    page_bounds: Vec<Rect<f32>>,
+}
+
+impl PageMapping {
+    /// `screen_rects` come from [PageLayout::pages_rects] and `page_bounds` are the bounds of the
+    /// same pages.
+    pub fn new(screen_rects: Vec<Rect<f32>>, page_bounds: Vec<Rect<f32>>) -> Self {
+        Self {
+            screen_rects,
+            page_bounds,
+        }
+    }
+
+    /// Where the page at `page_idx` is on screen.
+    pub fn screen_rect(&self, page_idx: usize) -> Option<Rect<f32>> {
+        self.screen_rects.get(page_idx).copied()
+    }
+
+    /// The screen rect, page bounds and screen pixels per unit of page space along each axis of
+    /// a page, `None` for empty pages.
+    fn scale(&self, page_idx: usize) -> Option<(Rect<f32>, Rect<f32>, Vector<f32>)> {
+        let screen = *self.screen_rects.get(page_idx)?;
+        let bounds = *self.page_bounds.get(page_idx)?;
+        if bounds.width() <= 0.0 || bounds.height() <= 0.0 {
+            return None;
+        }
+        let scale = Vector::new(
+            screen.width() / bounds.width(),
+            screen.height() / bounds.height(),
+        );
+        Some((screen, bounds, scale))
+    }
+
+    pub fn page_point_to_screen(&self, page_idx: usize, point: Vector<f32>) -> Option<Vector<f32>> {
+        let (screen, bounds, scale) = self.scale(page_idx)?;
+        Some(Vector::new(
+            screen.x0.x + (point.x - bounds.x0.x) * scale.x,
+            screen.x0.y + (point.y - bounds.x0.y) * scale.y,
+        ))
+    }
+
+    pub fn page_to_screen(&self, page_idx: usize, rect: Rect<f32>) -> Option<Rect<f32>> {
+        Some(Rect::from_points(
+            self.page_point_to_screen(page_idx, rect.x0)?,
+            self.page_point_to_screen(page_idx, rect.x1)?,
+        ))
+    }
+
+    /// `point` in the space of the page at `page_idx`, whether or not it is on that page.
+    pub fn screen_point_to_page(&self, page_idx: usize, point: Vector<f32>) -> Option<Vector<f32>> {
+        let (screen, bounds, scale) = self.scale(page_idx)?;
+        Some(Vector::new(
+            bounds.x0.x + (point.x - screen.x0.x) / scale.x,
+            bounds.x0.y + (point.y - screen.x0.y) / scale.y,
+        ))
+    }
+
+    /// The page under `point` along with the point in that page's space. Points in the gaps
+    /// between pages aren't on any page.
+    pub fn screen_to_page(&self, point: Vector<f32>) -> Option<(usize, Vector<f32>)> {
+        let page_idx = self.screen_rects.iter().position(|r| r.contains(point))?;
+        Some((page_idx, self.screen_point_to_page(page_idx, point)?))
+    }
+
+    /// Splits `rect` in screen space into the parts covering each page, converted to page space.
+    pub fn screen_rect_to_pages(&self, rect: Rect<f32>) -> Vec<(usize, Rect<f32>)> {
+        let mut out = vec![];
+        for (page_idx, screen) in self.screen_rects.iter().enumerate() {
+            let intersect = rect.intersect(screen);
+            if intersect.width() <= 0.0 || intersect.height() <= 0.0 {
+                continue;
+            }
+            if let (Some(x0), Some(x1)) = (
+                self.screen_point_to_page(page_idx, intersect.x0),
+                self.screen_point_to_page(page_idx, intersect.x1),
+            ) {
+                out.push((page_idx, Rect::from_points(x0, x1)));
+            }
+        }
+        out
+    }
+}
+
 impl PageLayout {
     const GAP: f32 = 10.0;
 
@@ -198,6 +388,36 @@ impl PageLayout {
         Ok((rect.center() - viewport_center).scaled(1.0 / (scale * fractional_scale)))
     }
 
+    /// Like [PageLayout::translation_for_page] but centers every page in the spread containing
+    /// `page_idx` rather than just the one page.
+    pub fn translation_for_spread(
+        &self,
+        doc: &Document,
+        scale: f32,
+        fractional_scale: f32,
+        page_idx: usize,
+        viewport: Size<f32>,
+    ) -> Result<Vector<f32>> {
+        let rects = self.pages_rects(
+            doc.pages()?,
+            Vector::zero(),
+            scale,
+            fractional_scale,
+            viewport,
+        )?;
+        let spreads = SpreadLayout::new(self, rects.len());
+        let spread = rects
+            .get(spreads.pages_in(spreads.spread_of(page_idx)))
+            .filter(|rects| !rects.is_empty())
+            .ok_or(anyhow!("Page index {page_idx} out of bounds"))?;
+        let center = spread
+            .iter()
+            .fold(Vector::zero(), |acc, rect| acc + rect.center())
+            .scaled(1.0 / spread.len() as f32);
+        let viewport_center = Vector::new(viewport.width, viewport.height).scaled(0.5);
+        Ok((center - viewport_center).scaled(1.0 / (scale * fractional_scale)))
+    }
+
     pub fn current_page_index(
         &self,
         doc: &Document,
@@ -238,14 +458,8 @@ impl PageLayout {
         viewport: Size<f32>,
     ) -> Result<Rect<f32>> {
         let rects = self.pages_rects(doc.pages()?, translation, 1.0, 1.0, viewport)?;
-        let mut idx = self.current_page_index(doc, translation, viewport)?;
-        idx = (match self {
-            PageLayout::SinglePage => idx.saturating_sub(1),
-            PageLayout::DoublePage => idx.saturating_sub(2),
-            PageLayout::DoublePageTitlePage => idx.saturating_sub(2),
-            PageLayout::Presentation => idx.saturating_sub(1),
-        })
-        .clamp(0, rects.len() - 1);
+        let idx = self.current_page_index(doc, translation, viewport)?;
+        let idx = SpreadLayout::new(self, rects.len()).previous_page(idx);
         Ok(rects[idx])
     }
 
@@ -256,20 +470,8 @@ impl PageLayout {
         viewport: Size<f32>,
     ) -> Result<Rect<f32>> {
         let rects = self.pages_rects(doc.pages()?, translation, 1.0, 1.0, viewport)?;
-        let mut idx = self.current_page_index(doc, translation, viewport)?;
-        idx = (match self {
-            PageLayout::SinglePage => idx + 1,
-            PageLayout::DoublePage => idx + 2,
-            PageLayout::DoublePageTitlePage => {
-                if idx == 0 {
-                    idx + 1
-                } else {
-                    idx + 2
-                }
-            }
-            PageLayout::Presentation => idx + 1,
-        })
-        .clamp(0, rects.len() - 1);
+        let idx = self.current_page_index(doc, translation, viewport)?;
+        let idx = SpreadLayout::new(self, rects.len()).next_page(idx);
         Ok(rects[idx])
     }
 }
@@ -279,6 +481,141 @@ mod tests {
     use super::*;
     use mupdf::Document;
 
+    #[test]
+    fn spreads_in_double_page_layout() {
+        let spreads = SpreadLayout::new(&PageLayout::DoublePage, 5);
+        assert_eq!(spreads.spread_count(), 3);
+        assert_eq!(spreads.pages_in(0), 0..2);
+        assert_eq!(spreads.pages_in(2), 4..5);
+        // From the right hand page of the second spread
+        assert_eq!(spreads.previous_page(3), 0);
+        assert_eq!(spreads.previous_page(1), 0);
+        assert_eq!(spreads.next_page(1), 2);
+        assert_eq!(spreads.next_page(4), 4);
+    }
+
+    #[test]
+    fn segments_go_down_each_column() {
+        let segments = page_segments(Vector::new(250.0, 150.0), Vector::new(100.0, 100.0));
+        assert_eq!(
+            segments,
+            vec![
+                Vector::new(0.0, 0.0),
+                Vector::new(0.0, 50.0),
+                Vector::new(75.0, 0.0),
+                Vector::new(75.0, 50.0),
+                Vector::new(150.0, 0.0),
+                Vector::new(150.0, 50.0),
+            ]
+        );
+        // A page that fits is a single centered segment
+        assert_eq!(
+            page_segments(Vector::new(80.0, 60.0), Vector::new(100.0, 100.0)),
+            vec![Vector::new(-10.0, -20.0)]
+        );
+    }
+
+    #[test]
+    fn spreads_in_cover_page_layout() {
+        let spreads = SpreadLayout::new(&PageLayout::DoublePageTitlePage, 6);
+        assert_eq!(spreads.spread_count(), 4);
+        assert_eq!(spreads.pages_in(0), 0..1);
+        assert_eq!(spreads.pages_in(1), 1..3);
+        assert_eq!(spreads.pages_in(3), 5..6);
+        assert_eq!(spreads.next_page(0), 1);
+        assert_eq!(spreads.next_page(2), 3);
+        assert_eq!(spreads.previous_page(2), 0);
+        assert_eq!(spreads.previous_page(5), 3);
+    }
+
+    #[test]
+    fn last_spread_of_cover_page_layout() {
+        // An odd page count means the last spread is full in cover page mode
+        let spreads = SpreadLayout::new(&PageLayout::DoublePageTitlePage, 5);
+        assert_eq!(spreads.spread_count(), 3);
+        assert_eq!(spreads.pages_in(2), 3..5);
+        assert_eq!(spreads.spread_of(4), 2);
+        assert_eq!(spreads.next_page(3), 3);
+        assert_eq!(spreads.next_page(4), 3);
+        assert_eq!(spreads.spread_of(10), 2);
+
+        let single = SpreadLayout::new(&PageLayout::DoublePageTitlePage, 1);
+        assert_eq!(single.spread_count(), 1);
+        assert_eq!(single.next_page(0), 0);
+        assert_eq!(single.previous_page(0), 0);
+    }
+
+    #[test]
+    fn single_page_spreads() {
+        let spreads = SpreadLayout::new(&PageLayout::SinglePage, 3);
+        assert_eq!(spreads.spread_count(), 3);
+        assert_eq!(spreads.next_page(0), 1);
+        assert_eq!(spreads.next_page(2), 2);
+        assert_eq!(spreads.previous_page(0), 0);
+    }
+
+    #[test]
+    fn mapping_across_a_spread() {
+        let rect = |x0, y0, x1, y1| Rect::from_points(Vector::new(x0, y0), Vector::new(x1, y1));
+        // Two pages of 100x200 points drawn at half size next to each other with a 10px seam
+        let mapping = PageMapping::new(
+            vec![rect(0.0, 0.0, 50.0, 100.0), rect(60.0, 0.0, 110.0, 100.0)],
+            vec![rect(0.0, 0.0, 100.0, 200.0); 2],
+        );
+        assert_eq!(
+            mapping.screen_to_page(Vector::new(25.0, 50.0)),
+            Some((0, Vector::new(50.0, 100.0)))
+        );
+        // Beyond the seam the offset of the right hand page is used
+        assert_eq!(
+            mapping.screen_to_page(Vector::new(85.0, 50.0)),
+            Some((1, Vector::new(50.0, 100.0)))
+        );
+        assert_eq!(mapping.screen_to_page(Vector::new(55.0, 50.0)), None);
+        assert_eq!(
+            mapping.page_to_screen(1, rect(0.0, 0.0, 20.0, 20.0)),
+            Some(rect(60.0, 0.0, 70.0, 10.0))
+        );
+        assert_eq!(
+            mapping.screen_rect_to_pages(rect(40.0, 10.0, 70.0, 20.0)),
+            vec![
+                (0, rect(80.0, 20.0, 100.0, 40.0)),
+                (1, rect(0.0, 20.0, 20.0, 40.0))
+            ]
+        );
+    }
+
+    #[test]
+    fn mapping_round_trips_in_every_layout() -> Result<()> {
+        let doc = Document::open("assets/links.pdf")?;
+        let viewport = Size::new(800.0, 600.0);
+        let bounds: Vec<Rect<f32>> = doc
+            .pages()?
+            .flatten()
+            .map(|page| page.bounds().unwrap().into())
+            .collect();
+        for layout in [
+            PageLayout::SinglePage,
+            PageLayout::DoublePage,
+            PageLayout::DoublePageTitlePage,
+            PageLayout::Presentation,
+        ] {
+            let rects =
+                layout.pages_rects(doc.pages()?, Vector::new(30.0, 200.0), 0.8, 1.5, viewport)?;
+            let mapping = PageMapping::new(rects.clone(), bounds.clone());
+            for (page_idx, rect) in rects.iter().enumerate() {
+                let (found, point) = mapping.screen_to_page(rect.center()).unwrap();
+                assert_eq!(found, page_idx, "{layout:?}");
+                let back = mapping.page_point_to_screen(page_idx, point).unwrap();
+                assert!(
+                    (back - rect.center()).norm_squared() < 1e-3,
+                    "{layout:?} page {page_idx} maps back to {back:?}"
+                );
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_translation_for_page() -> Result<()> {
         let doc = Document::open("assets/links.pdf")?;
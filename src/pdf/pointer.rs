@@ -0,0 +1,144 @@
+use iced::{
+    Point, Renderer,
+    widget::canvas::{self, Path, fill},
+};
+
+use crate::pdf::PdfMessage;
+
+const LASER_RADIUS: f32 = 7.0;
+/// The faint halo around the laser dot which keeps it visible on red figures
+const LASER_GLOW_RADIUS: f32 = 14.0;
+const SPOTLIGHT_RADIUS: f32 = 140.0;
+/// Opacity of the shade over everything outside the spotlight
+const SPOTLIGHT_SHADE: f32 = 0.65;
+
+/// What the mouse is drawn as in presentation mode, switched with `CyclePresentationPointer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PresentationPointer {
+    #[default]
+    Off,
+    /// A large red dot in place of the cursor
+    Laser,
+    /// Everything but a circle around the cursor is dimmed
+    Spotlight,
+}
+
+impl PresentationPointer {
+    pub fn next(self) -> Self {
+        match self {
+            PresentationPointer::Off => PresentationPointer::Laser,
+            PresentationPointer::Laser => PresentationPointer::Spotlight,
+            PresentationPointer::Spotlight => PresentationPointer::Off,
+        }
+    }
+}
+
+/// Draws the [PresentationPointer] at the cursor, on top of the pages.
+#[derive(Debug)]
+pub struct PointerOverlay {
+    pub pointer: PresentationPointer,
+}
+
+impl canvas::Program<PdfMessage> for PointerOverlay {
+    type State = ();
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: &canvas::Event,
+        _bounds: iced::Rectangle,
+        _cursor: iced::advanced::mouse::Cursor,
+    ) -> Option<canvas::Action<PdfMessage>> {
+        // The pointer follows the cursor even when nothing else changes, the event is left to the
+        // pages underneath
+        match event {
+            canvas::Event::Mouse(
+                iced::mouse::Event::CursorMoved { .. } | iced::mouse::Event::CursorLeft,
+            ) => Some(canvas::Action::request_redraw()),
+            _ => None,
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &iced::Theme,
+        bounds: iced::Rectangle,
+        cursor: iced::advanced::mouse::Cursor,
+    ) -> Vec<canvas::Geometry<Renderer>> {
+        let Some(position) = cursor.position_in(bounds) else {
+            return Vec::new();
+        };
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        match self.pointer {
+            PresentationPointer::Off => return Vec::new(),
+            PresentationPointer::Laser => {
+                frame.fill(
+                    &Path::circle(position, LASER_GLOW_RADIUS),
+                    iced::Color::from_rgba(1.0, 0.1, 0.1, 0.3),
+                );
+                frame.fill(
+                    &Path::circle(position, LASER_RADIUS),
+                    iced::Color::from_rgb(1.0, 0.1, 0.1),
+                );
+            }
+            PresentationPointer::Spotlight => {
+                // The circle is cut out of the shade by filling both with the even-odd rule
+                let shade = Path::new(|builder| {
+                    builder.rectangle(Point::ORIGIN, bounds.size());
+                    builder.circle(position, SPOTLIGHT_RADIUS);
+                });
+                frame.fill(
+                    &shade,
+                    canvas::Fill {
+                        style: canvas::Style::Solid(iced::Color::from_rgba(
+                            0.0,
+                            0.0,
+                            0.0,
+                            SPOTLIGHT_SHADE,
+                        )),
+                        rule: fill::Rule::EvenOdd,
+                    },
+                );
+            }
+        }
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &Self::State,
+        bounds: iced::Rectangle,
+        cursor: iced::advanced::mouse::Cursor,
+    ) -> iced::advanced::mouse::Interaction {
+        if self.pointer == PresentationPointer::Laser && cursor.is_over(bounds) {
+            iced::advanced::mouse::Interaction::Hidden
+        } else {
+            iced::advanced::mouse::Interaction::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pointers_cycle_back_to_the_cursor() {
+        let mut pointer = PresentationPointer::default();
+        let mut seen = vec![];
+        for _ in 0..3 {
+            pointer = pointer.next();
+            seen.push(pointer);
+        }
+        assert_eq!(
+            seen,
+            [
+                PresentationPointer::Laser,
+                PresentationPointer::Spotlight,
+                PresentationPointer::Off
+            ]
+        );
+    }
+}
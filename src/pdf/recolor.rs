@@ -0,0 +1,384 @@
+//! The recoloring dark mode. Rather than recoloring the rendered pixels like
+//! [super::widget::cpu_pdf_dark_mode_shader], the colors of text and vector graphics are swapped
+//! while the page is drawn, which leaves photos and figures as they are and keeps the edges of
+//! text crisp. A scan is a single image with nothing to recolor, so those pages go through the
+//! shader instead.
+
+use std::{cell::RefCell, num::NonZero, rc::Rc};
+
+use anyhow::Result;
+use mupdf::{
+    BlendMode, ColorParams, Colorspace, Device, Function, Image, Matrix, NativeDevice, Path,
+    Pixmap, Rect, Shade, StrokeState, Text,
+};
+
+/// A page with a single image covering at least this fraction of it is treated as a scan
+const SCAN_COVERAGE: f32 = 0.6;
+
+/// The dark mode color of `color`. The gradient only covers grays, so the difference to the gray
+/// of the same brightness is added back on top to keep the hue of colored text and lines.
+fn recolor(rgb: [f32; 3], colors: &[[u8; 4]; 256]) -> [f32; 3] {
+    let brightness = (rgb.iter().sum::<f32>() / 3.0).clamp(0.0, 1.0);
+    let gray = colors[(brightness * 255.0).round() as usize];
+    std::array::from_fn(|i| (gray[i] as f32 / 255.0 + rgb[i] - brightness).clamp(0.0, 1.0))
+}
+
+/// Forwards everything drawn to `target` with the colors of paths, text and image masks replaced.
+/// Images and shadings are drawn as they are.
+struct RecolorDevice {
+    target: Device,
+    rgb: Colorspace,
+    colors: [[u8; 4]; 256],
+    /// The first call the target failed, the device interface has no way of returning it
+    error: Rc<RefCell<Option<mupdf::Error>>>,
+}
+
+impl RecolorDevice {
+    fn color(&self, cs: &Colorspace, color: &[f32], cp: ColorParams) -> [f32; 3] {
+        let rgb = match cs.convert_color(color, &self.rgb, None, cp) {
+            Ok(rgb) => [rgb[0], rgb[1], rgb[2]],
+            Err(_) => [0.0; 3],
+        };
+        recolor(rgb, &self.colors)
+    }
+
+    fn forward<T>(&self, result: Result<T, mupdf::Error>) -> Option<T> {
+        result
+            .map_err(|e| {
+                self.error.borrow_mut().get_or_insert(e);
+            })
+            .ok()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+impl NativeDevice for RecolorDevice {
+    fn fill_path(
+        &mut self,
+        path: &Path,
+        even_odd: bool,
+        cmt: Matrix,
+        cs: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        cp: ColorParams,
+    ) {
+        let color = self.color(cs, color, cp);
+        self.forward(
+            self.target
+                .fill_path(path, even_odd, &cmt, &self.rgb, &color, alpha, cp),
+        );
+    }
+
+    fn stroke_path(
+        &mut self,
+        path: &Path,
+        stroke_state: &StrokeState,
+        cmt: Matrix,
+        cs: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        cp: ColorParams,
+    ) {
+        let color = self.color(cs, color, cp);
+        self.forward(self.target.stroke_path(
+            path,
+            stroke_state,
+            &cmt,
+            &self.rgb,
+            &color,
+            alpha,
+            cp,
+        ));
+    }
+
+    fn clip_path(&mut self, path: &Path, even_odd: bool, cmt: Matrix, _scissor: Rect) {
+        self.forward(self.target.clip_path(path, even_odd, &cmt));
+    }
+
+    fn clip_stroke_path(
+        &mut self,
+        path: &Path,
+        stroke_state: &StrokeState,
+        cmt: Matrix,
+        _scissor: Rect,
+    ) {
+        self.forward(self.target.clip_stroke_path(path, stroke_state, &cmt));
+    }
+
+    fn fill_text(
+        &mut self,
+        text: &Text,
+        cmt: Matrix,
+        cs: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        cp: ColorParams,
+    ) {
+        let color = self.color(cs, color, cp);
+        self.forward(
+            self.target
+                .fill_text(text, &cmt, &self.rgb, &color, alpha, cp),
+        );
+    }
+
+    fn stroke_text(
+        &mut self,
+        text: &Text,
+        stroke_state: &StrokeState,
+        cmt: Matrix,
+        cs: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        cp: ColorParams,
+    ) {
+        let color = self.color(cs, color, cp);
+        self.forward(self.target.stroke_text(
+            text,
+            stroke_state,
+            &cmt,
+            &self.rgb,
+            &color,
+            alpha,
+            cp,
+        ));
+    }
+
+    fn clip_text(&mut self, text: &Text, cmt: Matrix, _scissor: Rect) {
+        self.forward(self.target.clip_text(text, &cmt));
+    }
+
+    fn clip_stroke_text(
+        &mut self,
+        text: &Text,
+        stroke_state: &StrokeState,
+        cmt: Matrix,
+        _scissor: Rect,
+    ) {
+        self.forward(self.target.clip_stroke_text(text, stroke_state, &cmt));
+    }
+
+    fn ignore_text(&mut self, text: &Text, cmt: Matrix) {
+        self.forward(self.target.ignore_text(text, &cmt));
+    }
+
+    fn fill_shade(&mut self, shade: &Shade, cmt: Matrix, alpha: f32, cp: ColorParams) {
+        self.forward(self.target.fill_shade(shade, &cmt, alpha, cp));
+    }
+
+    fn fill_image(&mut self, img: &Image, cmt: Matrix, alpha: f32, cp: ColorParams) {
+        self.forward(self.target.fill_image(img, &cmt, alpha, cp));
+    }
+
+    /// Image masks are a single color, mostly used for glyphs of bitmap fonts
+    fn fill_image_mask(
+        &mut self,
+        img: &Image,
+        cmt: Matrix,
+        cs: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        cp: ColorParams,
+    ) {
+        let color = self.color(cs, color, cp);
+        self.forward(
+            self.target
+                .fill_image_mask(img, &cmt, &self.rgb, &color, alpha, cp),
+        );
+    }
+
+    fn clip_image_mask(&mut self, img: &Image, cmt: Matrix, _scissor: Rect) {
+        self.forward(self.target.clip_image_mask(img, &cmt));
+    }
+
+    fn pop_clip(&mut self) {
+        self.forward(self.target.pop_clip());
+    }
+
+    /// Soft masks keep their colors, recoloring them would turn what they hide inside out
+    fn begin_mask(
+        &mut self,
+        area: Rect,
+        luminosity: bool,
+        cs: &Colorspace,
+        color: &[f32],
+        cp: ColorParams,
+    ) {
+        self.forward(self.target.begin_mask(area, luminosity, cs, color, cp));
+    }
+
+    fn end_mask(&mut self, f: &Function) {
+        self.forward(self.target.end_mask(Some(f)));
+    }
+
+    fn begin_group(
+        &mut self,
+        area: Rect,
+        cs: &Colorspace,
+        isolated: bool,
+        knockout: bool,
+        blendmode: BlendMode,
+        alpha: f32,
+    ) {
+        self.forward(
+            self.target
+                .begin_group(area, cs, isolated, knockout, blendmode, alpha),
+        );
+    }
+
+    fn end_group(&mut self) {
+        self.forward(self.target.end_group());
+    }
+
+    fn begin_tile(
+        &mut self,
+        area: Rect,
+        view: Rect,
+        x_step: f32,
+        y_step: f32,
+        ctm: Matrix,
+        id: Option<NonZero<i32>>,
+        doc_id: Option<NonZero<i32>>,
+    ) -> Option<NonZero<i32>> {
+        self.forward(
+            self.target
+                .begin_tile(area, view, x_step, y_step, &ctm, id, doc_id),
+        )
+        .flatten()
+    }
+
+    fn end_tile(&mut self) {
+        self.forward(self.target.end_tile());
+    }
+
+    fn begin_layer(&mut self, name: &str) {
+        self.forward(self.target.begin_layer(name));
+    }
+
+    fn end_layer(&mut self) {
+        self.forward(self.target.end_layer());
+    }
+}
+
+/// Looks for an image covering most of the page, see [SCAN_COVERAGE].
+struct ScanDetector {
+    page: Rect,
+    found: Rc<RefCell<bool>>,
+}
+
+impl NativeDevice for ScanDetector {
+    fn fill_image(&mut self, _img: &Image, cmt: Matrix, _alpha: f32, _cp: ColorParams) {
+        // Images are drawn into the unit square
+        let covered = Rect::new(0.0, 0.0, 1.0, 1.0)
+            .transform(&cmt)
+            .intersect(&self.page);
+        let area = |rect: &Rect| rect.width().max(0.0) * rect.height().max(0.0);
+        if area(&covered) >= SCAN_COVERAGE * area(&self.page) {
+            *self.found.borrow_mut() = true;
+        }
+    }
+}
+
+/// Whether the page drawn by `run` in page space within `bounds` is a scan, which
+/// [draw_recolored] has no use for.
+pub fn is_scan(
+    bounds: Rect,
+    run: impl FnOnce(&Device) -> Result<(), mupdf::Error>,
+) -> Result<bool> {
+    let found = Rc::new(RefCell::new(false));
+    run(&Device::from_native(ScanDetector {
+        page: bounds,
+        found: found.clone(),
+    })?)?;
+    Ok(*found.borrow())
+}
+
+/// Draws the page `run` draws onto `pix` with the dark mode `colors`, on the background of the
+/// darkest one.
+pub fn draw_recolored(
+    pix: &mut Pixmap,
+    colors: &[[u8; 4]; 256],
+    run: impl FnOnce(&Device) -> Result<(), mupdf::Error>,
+) -> Result<()> {
+    for pixel in pix.samples_mut().chunks_exact_mut(4) {
+        pixel.copy_from_slice(&colors[255]);
+    }
+    let error = Rc::new(RefCell::new(None));
+    {
+        // The device has to be closed (dropped) before the pixmap contents are complete
+        let device = Device::from_native(RecolorDevice {
+            target: Device::from_pixmap(pix)?,
+            rgb: Colorspace::device_rgb(),
+            colors: *colors,
+            error: error.clone(),
+        })?;
+        run(&device)?;
+    }
+    match error.take() {
+        Some(e) => Err(e.into()),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::{
+        backend::{DocumentBackend, MupdfBackend},
+        widget::dark_mode_gradient,
+    };
+
+    /// A page drawn with `contents`, which is 100 by 100 points.
+    fn document_with_page(contents: &str) -> mupdf::Document {
+        let mut pdf = mupdf::pdf::PdfDocument::new();
+        let page = pdf.new_page((100.0, 100.0)).unwrap();
+        page.object()
+            .get_dict("Contents")
+            .unwrap()
+            .unwrap()
+            .write_stream_string(contents)
+            .unwrap();
+        let mut bytes = vec![];
+        pdf.write_to(&mut bytes).unwrap();
+        mupdf::Document::from_bytes(&bytes, "application/pdf").unwrap()
+    }
+
+    #[test]
+    fn colors_are_swapped_but_images_kept() {
+        let colors = dark_mode_gradient();
+        // A black square in the top left corner and a red image in the bottom right one
+        let doc = document_with_page(
+            "0 0 0 rg 0 50 50 50 re f \
+            q 50 0 0 50 50 0 cm BI /W 1 /H 1 /CS /RGB /BPC 8 /F /AHx ID ff0000> EI Q",
+        );
+        let backend = MupdfBackend::new(doc);
+        let image = backend
+            .render_page_recolored(0, 1.0, &colors)
+            .unwrap()
+            .expect("The page isn't a scan");
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * image.width + x) * 4) as usize;
+            image.pixels[i..i + 4].to_vec()
+        };
+        assert_eq!(pixel(25, 25), colors[0]);
+        assert_eq!(pixel(75, 25), colors[255]);
+        assert_eq!(pixel(75, 75), [255, 0, 0, 255]);
+
+        assert_eq!(recolor([1.0, 0.0, 0.0], &colors)[0], 1.0);
+        assert!(recolor([1.0, 0.0, 0.0], &colors)[1] < 0.5);
+    }
+
+    #[test]
+    fn scans_are_left_to_the_shader() {
+        let scan = document_with_page(
+            "q 100 0 0 100 0 0 cm BI /W 1 /H 1 /CS /G /BPC 8 /F /AHx ID 80> EI Q",
+        );
+        let backend = MupdfBackend::new(scan);
+        assert!(
+            backend
+                .render_page_recolored(0, 1.0, &dark_mode_gradient())
+                .unwrap()
+                .is_none()
+        );
+    }
+}
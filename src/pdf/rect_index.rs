@@ -0,0 +1,92 @@
+use crate::geometry::{Rect, Vector};
+
+/// More rows than this don't make lookups any faster, even on pages with thousands of links
+const MAX_ROWS: usize = 256;
+
+/// Finds the rectangle under a point without testing every rectangle, for hit testing pages with
+/// many links. The area covered by the rectangles is split into rows of equal height and a point
+/// only tests the rectangles overlapping its row.
+#[derive(Debug, Clone, Default)]
+pub struct RectIndex {
+    rects: Vec<Rect<f32>>,
+    top: f32,
+    row_height: f32,
+    /// The indices of the rectangles overlapping each row, in ascending order
+    rows: Vec<Vec<usize>>,
+}
+
+impl RectIndex {
+    pub fn new(rects: Vec<Rect<f32>>) -> Self {
+        if rects.is_empty() {
+            return Self::default();
+        }
+        let top = rects.iter().map(|r| r.x0.y).fold(f32::INFINITY, f32::min);
+        let bottom = rects
+            .iter()
+            .map(|r| r.x1.y)
+            .fold(f32::NEG_INFINITY, f32::max);
+        // With evenly spread rectangles each row holds about as many as there are rows
+        let row_count = (rects.len() as f32).sqrt().ceil() as usize;
+        let row_count = row_count.clamp(1, MAX_ROWS);
+        let mut index = Self {
+            top,
+            row_height: ((bottom - top) / row_count as f32).max(f32::EPSILON),
+            rows: vec![vec![]; row_count],
+            rects: vec![],
+        };
+        for (i, rect) in rects.iter().enumerate() {
+            for row in index.row(rect.x0.y)..=index.row(rect.x1.y) {
+                index.rows[row].push(i);
+            }
+        }
+        index.rects = rects;
+        index
+    }
+
+    fn row(&self, y: f32) -> usize {
+        (((y - self.top) / self.row_height).max(0.0) as usize).min(self.rows.len() - 1)
+    }
+
+    /// The index of the first rectangle containing `point`, the same one a linear search finds.
+    pub fn find(&self, point: Vector<f32>) -> Option<usize> {
+        if self.rows.is_empty() || point.y < self.top {
+            return None;
+        }
+        self.rows[self.row(point.y)]
+            .iter()
+            .copied()
+            .find(|&i| self.rects[i].contains(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_same_rectangle_as_a_linear_search() {
+        // A column of overlapping lines like a table of contents, and one tall link next to it
+        let mut rects: Vec<Rect<f32>> = (0..200)
+            .map(|i| {
+                let y = i as f32 * 12.0;
+                Rect::from_points(Vector::new(50.0, y), Vector::new(400.0, y + 14.0))
+            })
+            .collect();
+        rects.push(Rect::from_points(
+            Vector::new(0.0, 0.0),
+            Vector::new(60.0, 2400.0),
+        ));
+        let index = RectIndex::new(rects.clone());
+        for x in (-10..420).step_by(7) {
+            for y in (-10..2420).step_by(5) {
+                let point = Vector::new(x as f32 + 0.5, y as f32 + 0.5);
+                assert_eq!(
+                    index.find(point),
+                    rects.iter().position(|r| r.contains(point)),
+                    "{point:?}"
+                );
+            }
+        }
+        assert_eq!(RectIndex::new(vec![]).find(Vector::zero()), None);
+    }
+}
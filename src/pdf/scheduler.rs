@@ -0,0 +1,319 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex, mpsc},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use anyhow::Result;
+use tracing::error;
+
+use crate::pdf::{
+    backend::{self, DocumentBackend, RgbaImage},
+    widget::cpu_pdf_dark_mode_shader,
+};
+
+const MAX_WORKERS: usize = 4;
+/// How long a blocking render waits for the workers before the caller renders by itself instead
+const RENDER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Jobs with a higher priority are always picked up first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RenderPriority {
+    /// Small previews of every page for the page grid
+    Thumbnail,
+    /// Pages next to the visible ones, rendered ahead of time so flipping to them is instant
+    Adjacent,
+    /// Pages on screen which the ui is waiting for
+    Visible,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderJob {
+    pub page_idx: usize,
+    /// Scale from document space to pixels, including fractional scaling
+    pub scale: f32,
+    pub priority: RenderPriority,
+    /// Recolors the page with the dark mode shader if set
+    pub dark_mode: Option<[[u8; 4]; 256]>,
+    /// Draws text and vector graphics in the dark mode colors instead of running the shader, see
+    /// [super::recolor]
+    pub recolor: bool,
+}
+
+impl RenderJob {
+    fn is_same_render(&self, other: &RenderJob) -> bool {
+        self.page_idx == other.page_idx && self.scale.to_bits() == other.scale.to_bits()
+    }
+}
+
+#[derive(Debug)]
+pub struct RenderedPage {
+    pub page_idx: usize,
+    pub scale: f32,
+    pub priority: RenderPriority,
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    generation: u64,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    jobs: Vec<RenderJob>,
+    /// The file the workers render from, no jobs are run without one
+    path: Option<PathBuf>,
+    /// The layers to hide, the document decides which are shown when this isn't set
+    hidden_layers: Option<Vec<String>>,
+    /// Incremented whenever the document changes so outdated results can be recognized
+    generation: u64,
+    shutdown: bool,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    wakeup: Condvar,
+}
+
+/// Renders whole pages on a small pool of worker threads. MuPDF documents can't be shared between
+/// threads, so every worker opens the file on its own and renders through its own (thread local)
+/// MuPDF context.
+#[derive(Debug)]
+pub struct RenderScheduler {
+    shared: Arc<Shared>,
+    results: mpsc::Receiver<RenderedPage>,
+    /// Results received while waiting for something else
+    finished: Vec<RenderedPage>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl RenderScheduler {
+    /// Nothing is rendered without a `path` until [RenderScheduler::reset] provides one.
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let shared = Arc::new(Shared::default());
+        shared.queue.lock().unwrap().path = path;
+        let (sender, results) = mpsc::channel();
+        let worker_count = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(MAX_WORKERS);
+        let workers = (0..worker_count)
+            .filter_map(|i| {
+                let shared = shared.clone();
+                let sender = sender.clone();
+                std::thread::Builder::new()
+                    .name(format!("miro-render-{i}"))
+                    .spawn(move || worker(shared, sender))
+                    .inspect_err(|e| error!("Couldn't start render worker: {e}"))
+                    .ok()
+            })
+            .collect();
+        Self {
+            shared,
+            results,
+            finished: vec![],
+            workers,
+        }
+    }
+
+    /// Drops all queued jobs and makes the workers render from `path` from now on. Without a path
+    /// nothing is rendered, which is used when the document in memory no longer matches the file.
+    pub fn reset(&mut self, path: Option<PathBuf>) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.jobs.clear();
+        queue.path = path;
+        queue.generation += 1;
+        self.finished.clear();
+    }
+
+    /// Drops all queued and finished jobs while rendering from the same file, for when the pages
+    /// would look different, such as after toggling the dark mode.
+    pub fn invalidate(&mut self) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.jobs.clear();
+        queue.generation += 1;
+        self.finished.clear();
+    }
+
+    /// Makes the workers render with the layers in `hidden` hidden and every other layer shown.
+    pub fn set_hidden_layers(&mut self, hidden: Vec<String>) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.jobs.clear();
+        queue.hidden_layers = Some(hidden);
+        queue.generation += 1;
+        self.finished.clear();
+    }
+
+    /// Replaces every queued job of `priority` with `jobs`. The most recent view of the document
+    /// knows best what will be needed next, older wishes are dropped.
+    pub fn schedule(&self, priority: RenderPriority, jobs: Vec<RenderJob>) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        if queue.path.is_none() {
+            return;
+        }
+        queue.jobs.retain(|job| job.priority != priority);
+        queue.jobs.extend(jobs);
+        self.shared.wakeup.notify_all();
+    }
+
+    /// Queues `jobs` along with the ones already queued, for jobs that are only ever requested once.
+    pub fn add(&self, jobs: Vec<RenderJob>) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        if queue.path.is_none() {
+            return;
+        }
+        queue.jobs.extend(jobs);
+        self.shared.wakeup.notify_all();
+    }
+
+    /// Renders `jobs` in parallel and waits for them. Jobs that fail or take too long are left out
+    /// of the result.
+    pub fn render_now(&mut self, jobs: Vec<RenderJob>) -> Vec<RenderedPage> {
+        let generation = {
+            let mut queue = self.shared.queue.lock().unwrap();
+            if queue.path.is_none() {
+                return vec![];
+            }
+            queue
+                .jobs
+                .retain(|queued| !jobs.iter().any(|j| j.is_same_render(queued)));
+            queue.jobs.extend(jobs.iter().cloned());
+            self.shared.wakeup.notify_all();
+            queue.generation
+        };
+
+        let mut pending = jobs;
+        let mut done = vec![];
+        while !pending.is_empty() {
+            let Ok(page) = self.results.recv_timeout(RENDER_TIMEOUT) else {
+                break;
+            };
+            if page.generation != generation {
+                continue;
+            }
+            match pending.iter().position(|job| {
+                job.page_idx == page.page_idx && job.scale.to_bits() == page.scale.to_bits()
+            }) {
+                Some(i) => {
+                    pending.remove(i);
+                    done.push(page);
+                }
+                None => self.finished.push(page),
+            }
+        }
+        done.retain(|page| !page.rgba.is_empty());
+        done
+    }
+
+    /// Whether there is a file to render from, see [RenderScheduler::reset].
+    pub fn can_render(&self) -> bool {
+        self.shared.queue.lock().unwrap().path.is_some()
+    }
+
+    /// Pages rendered in the background since the last call. Pages that couldn't be rendered are
+    /// included without any pixels, so whoever waits for them can stop.
+    pub fn take_finished(&mut self) -> Vec<RenderedPage> {
+        let generation = self.shared.queue.lock().unwrap().generation;
+        self.finished.extend(self.results.try_iter());
+        let mut finished = std::mem::take(&mut self.finished);
+        finished.retain(|page| page.generation == generation);
+        finished
+    }
+}
+
+impl Drop for RenderScheduler {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().shutdown = true;
+        self.shared.wakeup.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn worker(shared: Arc<Shared>, results: mpsc::Sender<RenderedPage>) {
+    let mut doc: Option<(u64, Box<dyn DocumentBackend>)> = None;
+    loop {
+        let (job, path, hidden_layers, generation) = {
+            let mut queue = shared.queue.lock().unwrap();
+            loop {
+                if queue.shutdown {
+                    return;
+                }
+                if !queue.jobs.is_empty() && queue.path.is_some() {
+                    break;
+                }
+                queue = shared.wakeup.wait(queue).unwrap();
+            }
+            let next = queue
+                .jobs
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, job)| job.priority)
+                .map(|(i, _)| i)
+                .unwrap();
+            let job = queue.jobs.remove(next);
+            (
+                job,
+                queue.path.clone().unwrap(),
+                queue.hidden_layers.clone(),
+                queue.generation,
+            )
+        };
+
+        if doc.as_ref().is_none_or(|(g, _)| *g != generation) {
+            doc = match &hidden_layers {
+                Some(hidden) => backend::open_with_hidden_layers(&path, hidden),
+                None => backend::open(&path),
+            }
+            .ok()
+            .map(|d| (generation, d));
+        }
+        let (width, height, rgba) = match doc.as_ref() {
+            Some((_, doc)) => render_page(doc.as_ref(), &job).unwrap_or_else(|e| {
+                error!(
+                    "Couldn't render page {} in the background: {e}",
+                    job.page_idx
+                );
+                (0, 0, vec![])
+            }),
+            None => (0, 0, vec![]),
+        };
+        let page = RenderedPage {
+            page_idx: job.page_idx,
+            scale: job.scale,
+            priority: job.priority,
+            width,
+            height,
+            rgba,
+            generation,
+        };
+        if results.send(page).is_err() {
+            return;
+        }
+    }
+}
+
+/// Renders an entire page the same way [super::widget::PdfViewer] does when it fits on screen.
+pub(crate) fn render_page(
+    doc: &dyn DocumentBackend,
+    job: &RenderJob,
+) -> Result<(u32, u32, Vec<u8>)> {
+    let _span = tracy_client::span!("Background render");
+    if job.recolor
+        && let Some(colors) = &job.dark_mode
+        && let Some(image) = doc.render_page_recolored(job.page_idx, job.scale, colors)?
+    {
+        return Ok((image.width, image.height, image.pixels));
+    }
+    let RgbaImage {
+        width,
+        height,
+        mut pixels,
+    } = doc.render_page(job.page_idx, job.scale)?;
+    if let Some(gradient) = &job.dark_mode {
+        cpu_pdf_dark_mode_shader(&mut pixels, gradient);
+    }
+    Ok((width, height, pixels))
+}
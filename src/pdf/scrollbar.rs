@@ -0,0 +1,248 @@
+use std::{ops::Range, time::Duration};
+
+use iced::{
+    Renderer, Size,
+    widget::canvas::{self, Path},
+};
+
+use crate::{
+    geometry::{Rect, Vector},
+    pdf::PdfMessage,
+};
+
+/// Thickness of a scrollbar in pixels
+const WIDTH: f32 = 8.0;
+/// Space between a scrollbar and the edge of the view
+const MARGIN: f32 = 2.0;
+/// How far from a scrollbar the mouse can be while still grabbing it
+const GRAB_DISTANCE: f32 = 4.0;
+const MIN_THUMB_LENGTH: f32 = 24.0;
+/// How long the scrollbars stay on screen after the view stopped moving
+pub const HIDE_DELAY: Duration = Duration::from_millis(1200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    /// The component of `v` along the axis.
+    pub fn of(self, v: Vector<f32>) -> f32 {
+        match self {
+            Axis::Horizontal => v.x,
+            Axis::Vertical => v.y,
+        }
+    }
+
+    /// A vector of length `len` along the axis.
+    pub fn vector(self, len: f32) -> Vector<f32> {
+        match self {
+            Axis::Horizontal => Vector::new(len, 0.0),
+            Axis::Vertical => Vector::new(0.0, len),
+        }
+    }
+}
+
+/// The part of a track covered by the thumb of a scrollbar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thumb {
+    /// Distance from the start of the track
+    pub start: f32,
+    pub length: f32,
+    /// How many pixels the pages move when the thumb moves by one
+    pub content_per_pixel: f32,
+}
+
+/// Places the thumb on a track of length `track` for pages covering `content` on screen along one
+/// axis, while the view covers `0..viewport`. `None` when all the pages fit in the view.
+fn thumb(content: Range<f32>, viewport: f32, track: f32) -> Option<Thumb> {
+    // Scrolling past the pages leaves empty space in the view, which is part of what is scrolled
+    let start = content.start.min(0.0);
+    let end = content.end.max(viewport);
+    let scrollable = end - start - viewport;
+    if scrollable < 1.0 || track <= MIN_THUMB_LENGTH {
+        return None;
+    }
+    let length = (track * viewport / (end - start)).clamp(MIN_THUMB_LENGTH, track);
+    let free = track - length;
+    if free <= 0.0 {
+        return None;
+    }
+    Some(Thumb {
+        start: -start / scrollable * free,
+        length,
+        content_per_pixel: scrollable / free,
+    })
+}
+
+/// Scrollbars along the right and top edge of the view showing where it is within the pages. The
+/// tab bar floats over the bottom of the view, so the horizontal one goes along the top.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scrollbars {
+    viewport: Size<f32>,
+    pub vertical: Option<Thumb>,
+    pub horizontal: Option<Thumb>,
+}
+
+impl Scrollbars {
+    /// `page_rects` are where every page of the document is on screen.
+    pub fn new(page_rects: &[Rect<f32>], viewport: Size<f32>) -> Self {
+        let mut scrollbars = Self {
+            viewport,
+            vertical: None,
+            horizontal: None,
+        };
+        if page_rects.is_empty() {
+            return scrollbars;
+        }
+        let min = |f: fn(&Rect<f32>) -> f32| page_rects.iter().map(f).fold(f32::INFINITY, f32::min);
+        let max =
+            |f: fn(&Rect<f32>) -> f32| page_rects.iter().map(f).fold(f32::NEG_INFINITY, f32::max);
+        let (left, right) = (min(|r| r.x0.x), max(|r| r.x1.x));
+        let (top, bottom) = (min(|r| r.x0.y), max(|r| r.x1.y));
+        scrollbars.vertical = thumb(
+            top..bottom,
+            viewport.height,
+            scrollbars.track(Axis::Vertical).height(),
+        );
+        scrollbars.horizontal = thumb(
+            left..right,
+            viewport.width,
+            scrollbars.track(Axis::Horizontal).width(),
+        );
+        scrollbars
+    }
+
+    pub fn thumb(&self, axis: Axis) -> Option<Thumb> {
+        match axis {
+            Axis::Horizontal => self.horizontal,
+            Axis::Vertical => self.vertical,
+        }
+    }
+
+    /// Where the thumb can move along `axis`, in screen space. The horizontal track stops short of
+    /// the vertical one so they don't overlap in the corner.
+    fn track(&self, axis: Axis) -> Rect<f32> {
+        let Size { width, height } = self.viewport;
+        match axis {
+            Axis::Horizontal => Rect::from_points(
+                Vector::new(MARGIN, MARGIN),
+                Vector::new(width - 2.0 * MARGIN - WIDTH, MARGIN + WIDTH),
+            ),
+            Axis::Vertical => Rect::from_points(
+                Vector::new(width - MARGIN - WIDTH, MARGIN),
+                Vector::new(width - MARGIN, height - MARGIN),
+            ),
+        }
+    }
+
+    pub fn thumb_rect(&self, axis: Axis) -> Option<Rect<f32>> {
+        let thumb = self.thumb(axis)?;
+        let track = self.track(axis);
+        let start = track.x0 + axis.vector(thumb.start);
+        let size = match axis {
+            Axis::Horizontal => Vector::new(thumb.length, WIDTH),
+            Axis::Vertical => Vector::new(WIDTH, thumb.length),
+        };
+        Some(Rect::from_pos_size(start, size))
+    }
+
+    /// The scrollbar under `point`, which can be a bit outside of it to make it easier to grab.
+    pub fn hit(&self, point: Vector<f32>) -> Option<Axis> {
+        [Axis::Vertical, Axis::Horizontal]
+            .into_iter()
+            .filter(|axis| self.thumb(*axis).is_some())
+            .find(|axis| {
+                let track = self.track(*axis);
+                let grab = Vector::new(GRAB_DISTANCE, GRAB_DISTANCE);
+                Rect::from_points(track.x0 - grab, track.x1 + grab).contains(point)
+            })
+    }
+
+    /// Whether `point` is close enough to the right or top edge of `viewport` to possibly be over
+    /// a scrollbar, cheaper than building the scrollbars to check.
+    pub fn near_edge(point: Vector<f32>, viewport: Size<f32>) -> bool {
+        let reach = MARGIN + WIDTH + GRAB_DISTANCE;
+        point.x >= viewport.width - reach || point.y <= reach
+    }
+
+    /// How far the thumb along `axis` has to move for its center to end up at `point`.
+    pub fn distance_to_center(&self, axis: Axis, point: Vector<f32>) -> f32 {
+        self.thumb_rect(axis)
+            .map(|rect| axis.of(point) - axis.of(rect.center()))
+            .unwrap_or(0.0)
+    }
+}
+
+/// Draws the scrollbars over the pages, highlighting the one being dragged or hovered.
+#[derive(Debug)]
+pub struct ScrollbarOverlay {
+    pub scrollbars: Scrollbars,
+    pub active: Option<Axis>,
+}
+
+impl canvas::Program<PdfMessage> for ScrollbarOverlay {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &iced::Theme,
+        bounds: iced::Rectangle,
+        _cursor: iced::advanced::mouse::Cursor,
+    ) -> Vec<canvas::Geometry<Renderer>> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let palette = theme.extended_palette();
+        for axis in [Axis::Vertical, Axis::Horizontal] {
+            let Some(rect) = self.scrollbars.thumb_rect(axis) else {
+                continue;
+            };
+            let mut color = if self.active == Some(axis) {
+                palette.primary.base.color
+            } else {
+                palette.background.strong.color
+            };
+            color.a = 0.8;
+            frame.fill(
+                &Path::rounded_rectangle(rect.x0.into(), rect.size().into(), (WIDTH / 2.0).into()),
+                color,
+            );
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thumb_follows_the_view_through_the_pages() {
+        // Pages ten times as long as the view, scrolled a quarter of the way down
+        let thumb = thumb(-250.0..900.0, 100.0, 100.0).unwrap();
+        assert_eq!(thumb.length, MIN_THUMB_LENGTH.max(100.0 * 100.0 / 1150.0));
+        let free = 100.0 - thumb.length;
+        assert!((thumb.start - 250.0 / 1050.0 * free).abs() < 1e-4);
+        assert!((thumb.content_per_pixel * free - 1050.0).abs() < 1e-3);
+
+        assert_eq!(super::thumb(10.0..90.0, 100.0, 100.0), None);
+    }
+
+    #[test]
+    fn scrollbars_only_show_up_where_the_pages_overflow() {
+        let viewport = Size::new(400.0, 300.0);
+        let page = |y: f32| Rect::from_pos_size(Vector::new(50.0, y), Vector::new(300.0, 400.0));
+        let scrollbars = Scrollbars::new(&[page(-100.0), page(310.0)], viewport);
+        assert!(scrollbars.horizontal.is_none());
+        assert!(scrollbars.vertical.is_some());
+        assert_eq!(
+            scrollbars.hit(Vector::new(395.0, 150.0)),
+            Some(Axis::Vertical)
+        );
+        assert_eq!(scrollbars.hit(Vector::new(200.0, 5.0)), None);
+        assert!(Scrollbars::near_edge(Vector::new(395.0, 150.0), viewport));
+        assert!(!Scrollbars::near_edge(Vector::new(200.0, 150.0), viewport));
+    }
+}
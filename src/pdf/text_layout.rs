@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use strum::EnumString;
+
+use crate::geometry::{Rect, Vector};
+
+/// A gap between two characters wider than this many line heights separates table cells
+const CELL_GAP: f32 = 1.0;
+/// A gap wider than this many line heights separates words even without a space character
+const WORD_GAP: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, EnumString, Default, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Tab separated, what spreadsheets expect when pasting
+    #[default]
+    Tsv,
+    Csv,
+}
+
+/// A run of characters on one line with no wide gap inside it.
+#[derive(Debug)]
+struct Cell {
+    text: String,
+    rect: Rect<f32>,
+}
+
+/// Splits `chars` into cells, ignoring the order of the text page since it often lists a table
+/// column by column.
+fn cells(text: &str, chars: &[(usize, usize, Rect<f32>)]) -> Vec<Cell> {
+    let mut cells: Vec<Cell> = vec![];
+    let mut space_pending = false;
+    for &(_, byte, rect) in chars {
+        let Some(c) = text[byte..].chars().next() else {
+            continue;
+        };
+        if c.is_whitespace() {
+            space_pending = true;
+            continue;
+        }
+        let height = rect.height().max(1.0);
+        let continues = cells.last().is_some_and(|cell| {
+            let gap = rect.x0.x - cell.rect.x1.x;
+            let center_y = rect.center().y;
+            center_y > cell.rect.x0.y
+                && center_y < cell.rect.x1.y
+                && gap > -height * WORD_GAP
+                && gap < height * CELL_GAP
+        });
+        match cells.last_mut() {
+            Some(cell) if continues => {
+                if space_pending || rect.x0.x - cell.rect.x1.x > height * WORD_GAP {
+                    cell.text.push(' ');
+                }
+                cell.text.push(c);
+                cell.rect = Rect::from_points(
+                    Vector::new(cell.rect.x0.x.min(rect.x0.x), cell.rect.x0.y.min(rect.x0.y)),
+                    Vector::new(cell.rect.x1.x.max(rect.x1.x), cell.rect.x1.y.max(rect.x1.y)),
+                );
+            }
+            _ => cells.push(Cell {
+                text: c.to_string(),
+                rect,
+            }),
+        }
+        space_pending = false;
+    }
+    cells
+}
+
+/// The characters of a selection on one page arranged as a table, or `None` if they don't form
+/// at least two columns. Cells are grouped into rows by their vertical position and into columns
+/// by the horizontal ranges they cover, so a cell spanning several columns merges them.
+pub fn table(text: &str, chars: &[(usize, usize, Rect<f32>)]) -> Option<Vec<Vec<String>>> {
+    let mut cells = cells(text, chars);
+    cells.sort_by(|a, b| a.rect.center().y.total_cmp(&b.rect.center().y));
+
+    // Columns are the ranges of x covered by some cell
+    let mut columns: Vec<(f32, f32)> = cells.iter().map(|c| (c.rect.x0.x, c.rect.x1.x)).collect();
+    columns.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut merged: Vec<(f32, f32)> = vec![];
+    for (start, end) in columns {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    if merged.len() < 2 {
+        return None;
+    }
+
+    let mut rows: Vec<Vec<String>> = vec![];
+    let mut row_bottom = f32::NEG_INFINITY;
+    for cell in cells {
+        if cell.rect.center().y > row_bottom {
+            rows.push(vec![String::new(); merged.len()]);
+            row_bottom = cell.rect.x1.y;
+        }
+        let column = merged
+            .iter()
+            .position(|(_, end)| cell.rect.x0.x <= *end)
+            .unwrap_or(merged.len() - 1);
+        let slot = &mut rows.last_mut().unwrap()[column];
+        if !slot.is_empty() {
+            slot.push(' ');
+        }
+        slot.push_str(&cell.text);
+    }
+    Some(rows)
+}
+
+/// How far, as a fraction of the page width, a block of a column may reach past the middle of
+/// the page
+const GUTTER_TOLERANCE: f32 = 0.02;
+/// The part of the text of a page each side of it needs to hold to count as a column
+const MIN_COLUMN_SHARE: f32 = 0.2;
+
+/// Which column of `page` a block belongs to, `None` when it crosses the middle.
+fn block_column(block: &Rect<f32>, page: Rect<f32>) -> Option<usize> {
+    let middle = page.center().x;
+    let tolerance = page.width() * GUTTER_TOLERANCE;
+    if block.x1.x <= middle + tolerance {
+        Some(0)
+    } else if block.x0.x >= middle - tolerance {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// The horizontal ranges of the columns of text on `page`, left to right. Titles and figures
+/// across both columns are left out, and pages with no more than a few lines on either side of
+/// the middle have a single column spanning all of their text.
+pub fn columns(blocks: &[Rect<f32>], page: Rect<f32>) -> Vec<(f32, f32)> {
+    let span = |column: Option<usize>| {
+        blocks
+            .iter()
+            .filter(|b| column.is_none() || block_column(b, page) == column)
+            .fold(None, |span: Option<(f32, f32, f32)>, b| {
+                let (x0, x1, height) = span.unwrap_or((b.x0.x, b.x1.x, 0.0));
+                Some((x0.min(b.x0.x), x1.max(b.x1.x), height + b.height()))
+            })
+    };
+    let Some((x0, x1, height)) = span(None) else {
+        return vec![];
+    };
+    match (span(Some(0)), span(Some(1))) {
+        (Some(left), Some(right))
+            if left.2 >= height * MIN_COLUMN_SHARE && right.2 >= height * MIN_COLUMN_SHARE =>
+        {
+            vec![(left.0, left.1), (right.0, right.1)]
+        }
+        _ => vec![(x0, x1)],
+    }
+}
+
+/// Sorts the blocks of `page` into the order they are read in. Blocks lying on one side of the
+/// middle of the page belong to a column, anything crossing it, like a title or a figure across
+/// both columns, starts a new band. Bands are read top to bottom, each one left column first.
+pub fn reading_order(mut blocks: Vec<Rect<f32>>, page: Rect<f32>) -> Vec<Rect<f32>> {
+    let column = |block: &Rect<f32>| block_column(block, page);
+    blocks.sort_by(|a, b| a.x0.y.total_cmp(&b.x0.y));
+    let mut ordered = Vec::with_capacity(blocks.len());
+    let mut band = vec![];
+    for block in blocks {
+        if column(&block).is_some() {
+            band.push(block);
+        } else {
+            band.sort_by_key(column);
+            ordered.append(&mut band);
+            ordered.push(block);
+        }
+    }
+    band.sort_by_key(column);
+    ordered.append(&mut band);
+    ordered
+}
+
+/// Writes `rows` in `format`, one row per line.
+pub fn to_delimited(rows: &[Vec<String>], format: TableFormat) -> String {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| match format {
+                    // Tabs and line breaks can't be escaped in TSV
+                    TableFormat::Tsv => cell.replace(['\t', '\n'], " "),
+                    TableFormat::Csv if cell.contains([',', '"', '\n']) => {
+                        format!("\"{}\"", cell.replace('"', "\"\""))
+                    }
+                    TableFormat::Csv => cell.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(match format {
+                    TableFormat::Tsv => "\t",
+                    TableFormat::Csv => ",",
+                })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lays out columns of lines starting at the given x, 10 units per character and 20 per line.
+    /// The text page lists the table column by column, like mupdf does for many tables.
+    fn layout(columns: &[(f32, &[&str])]) -> (String, Vec<(usize, usize, Rect<f32>)>) {
+        let mut text = String::new();
+        let mut chars = vec![];
+        for &(x, lines) in columns {
+            for (row, line) in lines.iter().enumerate() {
+                let y = row as f32 * 20.0;
+                for (i, c) in line.chars().enumerate() {
+                    let x = x + i as f32 * 10.0;
+                    chars.push((
+                        0,
+                        text.len(),
+                        Rect::from_points(Vector::new(x, y), Vector::new(x + 10.0, y + 12.0)),
+                    ));
+                    text.push(c);
+                }
+                text.push('\n');
+            }
+        }
+        (text, chars)
+    }
+
+    #[test]
+    fn columns_listed_one_after_another_become_rows() {
+        let (text, chars) = layout(&[
+            (0.0, &["Name", "Ada, L.", "Alan"]),
+            (100.0, &["Born", "1815", "1912"]),
+        ]);
+        let rows = table(&text, &chars).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["Name", "Born"],
+                vec!["Ada, L.", "1815"],
+                vec!["Alan", "1912"]
+            ]
+        );
+        assert_eq!(
+            to_delimited(&rows, TableFormat::Tsv),
+            "Name\tBorn\nAda, L.\t1815\nAlan\t1912"
+        );
+        assert_eq!(
+            to_delimited(&rows, TableFormat::Csv),
+            "Name,Born\n\"Ada, L.\",1815\nAlan,1912"
+        );
+    }
+
+    #[test]
+    fn empty_cells_are_kept_and_prose_is_not_a_table() {
+        let (text, chars) = layout(&[(0.0, &["a", "b"]), (100.0, &["1"])]);
+        assert_eq!(
+            table(&text, &chars).unwrap(),
+            vec![vec!["a", "1"], vec!["b", ""]]
+        );
+        let (text, chars) = layout(&[(0.0, &["Just a sentence", "wrapped over lines"])]);
+        assert_eq!(table(&text, &chars), None);
+    }
+
+    #[test]
+    fn two_columns_are_read_one_after_the_other() {
+        let rect = |x0, y0, x1, y1| Rect::from_points(Vector::new(x0, y0), Vector::new(x1, y1));
+        let page = rect(0.0, 0.0, 600.0, 800.0);
+        let title = rect(100.0, 40.0, 500.0, 80.0);
+        let left = [
+            rect(50.0, 100.0, 290.0, 300.0),
+            rect(50.0, 320.0, 295.0, 400.0),
+        ];
+        let right = [rect(310.0, 100.0, 550.0, 400.0)];
+        let figure = rect(50.0, 420.0, 550.0, 600.0);
+        let below = [
+            rect(50.0, 620.0, 290.0, 750.0),
+            rect(310.0, 620.0, 550.0, 750.0),
+        ];
+        // Listed right column first, like documents often do
+        let blocks = vec![
+            right[0], below[1], figure, left[1], title, below[0], left[0],
+        ];
+        assert_eq!(
+            reading_order(blocks, page),
+            [
+                title, left[0], left[1], right[0], figure, below[0], below[1]
+            ]
+        );
+    }
+
+    #[test]
+    fn columns_are_found_beside_titles_and_figures() {
+        let rect = |x0, y0, x1, y1| Rect::from_points(Vector::new(x0, y0), Vector::new(x1, y1));
+        let page = rect(0.0, 0.0, 600.0, 800.0);
+        let title = rect(100.0, 40.0, 500.0, 80.0);
+        let left = rect(50.0, 100.0, 290.0, 700.0);
+        let right = rect(310.0, 100.0, 550.0, 700.0);
+        assert_eq!(
+            columns(&[title, left, right], page),
+            [(50.0, 290.0), (310.0, 550.0)]
+        );
+        // A page number in the corner doesn't make a second column
+        let number = rect(520.0, 760.0, 540.0, 770.0);
+        let text = rect(50.0, 100.0, 290.0, 700.0);
+        assert_eq!(columns(&[text, number], page), [(50.0, 540.0)]);
+        assert!(columns(&[], page).is_empty());
+    }
+
+    #[test]
+    fn pages_of_a_single_column_document_have_one_column() {
+        use crate::pdf::backend::{DocumentBackend, MupdfBackend};
+
+        let doc = mupdf::Document::open("assets/text-copy-test.pdf").unwrap();
+        let page_bounds: Vec<Rect<f32>> = (0..doc.page_count().unwrap())
+            .map(|idx| doc.load_page(idx).unwrap().bounds().unwrap().into())
+            .collect();
+        let backend = MupdfBackend::new(doc);
+        for (page_idx, page) in page_bounds.into_iter().enumerate() {
+            let blocks = backend.page_blocks(page_idx).unwrap();
+            if blocks.is_empty() {
+                continue;
+            }
+            assert_eq!(columns(&blocks, page).len(), 1, "page {page_idx}");
+            let ordered = reading_order(blocks.clone(), page);
+            assert_eq!(ordered.len(), blocks.len());
+            assert!(blocks.iter().all(|b| ordered.contains(b)));
+        }
+    }
+}
@@ -3,9 +3,10 @@ use std::{
     collections::{HashMap, HashSet},
     path::PathBuf,
     sync::{Arc, Mutex, Weak},
+    time::Duration,
 };
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use colorgrad::{Gradient as _, GradientBuilder, LinearGradient};
 use iced::{
     Renderer, Size,
@@ -19,24 +20,50 @@ use iced::advanced::image::Renderer as _;
 use bytes::Bytes;
 
 use mupdf::{
-    Colorspace, Device, Matrix, Pixmap, TextPageFlags,
+    Colorspace, Device, Matrix, MetadataName, Pixmap, TextPageFlags,
     pdf::{PdfAnnotationType, PdfPage},
 };
 use serde::{Deserialize, Serialize};
-use tracing::{error};
+use tracing::error;
 
 use crate::{
     CONFIG, DARK_THEME,
-    config::{MOVE_STEP, MouseAction},
+    config::{MAIL_HANDLER_COPY, MOVE_STEP, MouseAction, PdfDarkModeStyle},
+    error::{MiroError, copy_to_clipboard, run_blocking},
+    formula,
     geometry::{Rect, Vector},
-    pdf::{PdfMessage, SearchMatch, SearchMethod, find_search_matches, page_layout::PageLayout},
+    lookup,
+    metadata::{self, PageBox, Signature},
+    paths,
+    pdf::{
+        PdfMessage, SearchFlags, SearchMatch, SearchMethod, ZoomPreset, archive,
+        backend::{self, DocumentBackend, MupdfBackend, PageLink},
+        caret::{self, Caret, CaretMotion},
+        copy_cleanup,
+        identifiers::{self, Identifier},
+        layers::{self, Layer},
+        media::{self, Media, MediaSource},
+        text_layout,
+        destinations::Destinations,
+        export, find_matches, find_search_matches, forms, merge_search_rects,
+        overview::{self, Overview, PageThumbnail},
+        page_layout::{self, PageLayout, PageMapping, SpreadLayout},
+        pointer::{PointerOverlay, PresentationPointer},
+        recolor,
+        rect_index::RectIndex,
+        scheduler::{self, RenderJob, RenderPriority, RenderScheduler},
+        scrollbar::{self, Axis, ScrollbarOverlay, Scrollbars},
+    },
 };
 
+/// A page of the page grid.
 #[derive(Debug, Clone)]
-struct PageLink {
-    bounds: mupdf::Rect,
-    uri: String,
-    dest: Option<mupdf::link::LinkDestination>,
+enum Thumbnail {
+    /// Waiting for the render workers
+    Queued,
+    Rendered(image::Handle),
+    /// Shown as a blank page rather than trying again
+    Failed,
 }
 
 #[derive(Debug, Clone)]
@@ -47,6 +74,44 @@ struct Comment {
     author: Option<String>,
 }
 
+/// A word of the document, used by word hint mode.
+#[derive(Debug, Clone, PartialEq)]
+struct Word {
+    page_idx: usize,
+    /// Lowercase
+    text: String,
+    /// In page space
+    bounds: Rect<f32>,
+}
+
+/// A comment or markup annotation, as listed in the annotations sidebar tab.
+#[derive(Debug, Clone)]
+pub struct AnnotationEntry {
+    pub page_idx: usize,
+    /// Position in the page's list of annotations, used to find it again when deleting it
+    annot_idx: usize,
+    pub kind: PdfAnnotationType,
+    pub content: Option<String>,
+    pub author: Option<String>,
+    bounds: mupdf::Rect,
+}
+
+impl AnnotationEntry {
+    pub fn kind_label(&self) -> &'static str {
+        match self.kind {
+            PdfAnnotationType::Text => "Comment",
+            PdfAnnotationType::FreeText => "Text box",
+            PdfAnnotationType::Highlight => "Highlight",
+            PdfAnnotationType::Underline => "Underline",
+            PdfAnnotationType::Squiggly => "Squiggly",
+            PdfAnnotationType::StrikeOut => "Strike out",
+            PdfAnnotationType::Ink => "Drawing",
+            PdfAnnotationType::Stamp => "Stamp",
+            _ => "Annotation",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutlineItem {
     pub title: String,
@@ -57,6 +122,15 @@ pub struct OutlineItem {
 
 const MIN_SELECTION: f32 = 5.0;
 const MIN_CLICK_DISTANCE: f32 = 5.0;
+/// Pages larger than this at the current zoom level aren't rendered ahead of time
+const MAX_PREFETCH_PIXELS: f32 = 4096.0 * 4096.0;
+/// Thumbnails rendered on the ui thread per frame when the workers can't render the document
+const SYNC_THUMBNAILS_PER_FRAME: usize = 4;
+/// Pages without text, like figures, that [PdfMessage::NextBlock] looks past before it turns the
+/// page instead
+const BLOCK_SEARCH_PAGES: usize = 4;
+/// Space left above a block moved to the top of the screen, in pixels
+const BLOCK_MARGIN: f32 = 16.0;
 
 /// A pixel buffer that returns itself to a shared pool when dropped.
 ///
@@ -105,12 +179,130 @@ enum RenderKey {
     Partial(usize, u32, i32, i32),
 }
 
+/// How a page is drawn into a pixmap.
+#[derive(Debug, Clone, Copy)]
+struct DrawTarget {
+    key: RenderKey,
+    /// Where the pixmap is drawn on the screen
+    rect: Rect<f32>,
+    width: i32,
+    height: i32,
+    /// From page space into the pixmap
+    ctm: Matrix,
+    /// In pixmap space
+    scissor: mupdf::Rect,
+}
+
+impl DrawTarget {
+    /// Page `i` covers `rect_ss` on the screen. Pages that fit in the viewport are drawn entirely,
+    /// independent of the translation, and only the visible part of the others.
+    fn new(
+        i: usize,
+        rect_ss: Rect<f32>,
+        viewport_rect: &Rect<f32>,
+        page_bounds: Rect<f32>,
+        effective_scale: f32,
+    ) -> Self {
+        if is_fully_visible(&rect_ss, viewport_rect) {
+            return Self {
+                key: RenderKey::Full(i, effective_scale.to_bits()),
+                rect: rect_ss,
+                width: rect_ss.width().ceil().max(1.0) as i32,
+                height: rect_ss.height().ceil().max(1.0) as i32,
+                ctm: Matrix::new(effective_scale, 0.0, 0.0, effective_scale, 0.0, 0.0),
+                scissor: mupdf::Rect::new(0.0, 0.0, page_bounds.width(), page_bounds.height()),
+            };
+        }
+        let vis = rect_ss.intersect(viewport_rect);
+        let vw = vis.width().ceil().max(1.0) as i32;
+        let vh = vis.height().ceil().max(1.0) as i32;
+
+        let render_offset_x = rect_ss.x0.x - vis.x0.x;
+        let render_offset_y = rect_ss.x0.y - vis.x0.y;
+
+        let raster_tx = render_offset_x - page_bounds.x0.x * effective_scale;
+        let raster_ty = render_offset_y - page_bounds.x0.y * effective_scale;
+
+        Self {
+            key: RenderKey::Partial(i, effective_scale.to_bits(), vw, vh),
+            rect: vis,
+            width: vw,
+            height: vh,
+            ctm: Matrix::new(
+                effective_scale,
+                0.0,
+                0.0,
+                effective_scale,
+                raster_tx.round(),
+                raster_ty.round(),
+            ),
+            // NOTE: Controls what part of the pdf page is rendered, in what coordinates? It
+            // "moves" along when I pan, thus it is NOT anchored to the document but rather to the
+            // pixmap itself. The units are pixels, even though they are floating point numbers.
+            // Thus the scissor area is expressed entirely in pixmap coordinates.
+            //
+            // NOTE: What makes this more confusing is that a scissored render can still draw
+            // outside of the scissored region. Any object in the pdf that is within the scissored
+            // region will be rendered in its entirety. Its like a crude frustrum cull
+            //
+            // NOTE: We want to draw the entire pixmap everytime, thus this is just the pixmaps
+            // size.
+            scissor: mupdf::Rect::new(0.0, 0.0, vw as f32, vh as f32),
+        }
+    }
+}
+
+/// How the edges of pages are decorated while page borders are shown.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PageStyle {
+    pub border_color: iced::Color,
+    pub border_width: f32,
+    pub shadow: bool,
+}
+
+impl PageStyle {
+    pub(crate) fn from_config(pdf_dark_mode: bool) -> Self {
+        let cfg = CONFIG.read().unwrap();
+        Self {
+            border_color: if pdf_dark_mode {
+                cfg.page_border_color_dark
+            } else {
+                cfg.page_border_color
+            },
+            border_width: cfg.page_border_width,
+            shadow: cfg.page_shadow,
+        }
+    }
+}
+
+/// How long the start/end of document indicator stays on screen
+const EDGE_INDICATOR_DURATION: Duration = Duration::from_millis(900);
+/// How long a page stays tinted after its text was copied
+const PAGE_FLASH_DURATION: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DocumentEdge {
+    Start,
+    End,
+}
+
+/// Offset of the drop shadow from the page and how far it fades out
+pub(crate) const PAGE_SHADOW_OFFSET: iced::Vector = iced::Vector::new(2.0, 3.0);
+pub(crate) const PAGE_SHADOW_BLUR: f32 = 6.0;
+/// How many rectangles the shadow is approximated with
+pub(crate) const PAGE_SHADOW_STEPS: u32 = 4;
+/// Longer render errors are cut off in the placeholder of the page
+const RENDER_ERROR_MAX_CHARS: usize = 120;
+
 struct Document<'a> {
     cache: Cache,
     pages: Vec<(image::Handle, Rect<f32>)>,
+    /// Pages that failed to render and why, covered by a placeholder
+    failed: Vec<(Rect<f32>, String)>,
     allocation_cache: &'a RefCell<HashMap<image::Id, image::Allocation>>,
     draw_page_borders: bool,
     pdf_dark_mode: bool,
+    page_style: PageStyle,
 }
 
 impl<'a> std::fmt::Debug for Document<'a> {
@@ -126,17 +318,85 @@ impl<'a> Document<'a> {
     pub fn new(
         allocation_cache: &'a RefCell<HashMap<image::Id, image::Allocation>>,
         pages: Vec<(image::Handle, Rect<f32>)>,
+        failed: Vec<(Rect<f32>, String)>,
         draw_page_borders: bool,
         pdf_dark_mode: bool,
     ) -> Self {
         Self {
             cache: Cache::default(),
             pages,
+            failed,
             allocation_cache,
             draw_page_borders,
             pdf_dark_mode,
+            page_style: PageStyle::from_config(pdf_dark_mode),
         }
     }
+
+    /// Approximates a blurred shadow with a few translucent rectangles of growing size, which is
+    /// cheap enough to redraw for every page.
+    fn draw_shadow(&self, frame: &mut canvas::Frame<Renderer>, page: iced::Rectangle) {
+        let alpha = page_shadow_alpha(self.pdf_dark_mode);
+        for i in (1..=PAGE_SHADOW_STEPS).rev() {
+            let spread = PAGE_SHADOW_BLUR * i as f32 / PAGE_SHADOW_STEPS as f32;
+            let shadow = iced::Rectangle {
+                x: page.x + PAGE_SHADOW_OFFSET.x - spread / 2.0,
+                y: page.y + PAGE_SHADOW_OFFSET.y - spread / 2.0,
+                width: page.width + spread,
+                height: page.height + spread,
+            };
+            frame.fill(
+                &canvas::Path::rounded_rectangle(
+                    shadow.position(),
+                    shadow.size(),
+                    iced::border::Radius::from(spread),
+                ),
+                iced::Color::BLACK.scale_alpha(alpha),
+            );
+        }
+    }
+
+    fn draw_border(&self, frame: &mut canvas::Frame<Renderer>, page: iced::Rectangle) {
+        let width = self.page_style.border_width;
+        // Stroked just outside the page so no content is covered
+        frame.stroke(
+            &canvas::Path::rectangle(
+                iced::Point::new(page.x - width / 2.0, page.y - width / 2.0),
+                iced::Size::new(page.width + width, page.height + width),
+            ),
+            Stroke::default()
+                .with_color(self.page_style.border_color)
+                .with_width(width),
+        );
+    }
+
+    /// Covers a page that couldn't be drawn with the reason, so it doesn't look like a blank page.
+    fn draw_render_error(
+        &self,
+        frame: &mut canvas::Frame<Renderer>,
+        theme: &iced::Theme,
+        page: iced::Rectangle,
+        error: &str,
+    ) {
+        let palette = theme.extended_palette();
+        frame.fill_rectangle(page.position(), page.size(), palette.background.weak.color);
+        let error = match error.char_indices().nth(RENDER_ERROR_MAX_CHARS) {
+            Some((end, _)) => format!("{}…", &error[..end]),
+            None => error.to_string(),
+        };
+        frame.fill_text(geometry::Text {
+            content: format!("Page failed to render ({error})"),
+            position: page.center(),
+            max_width: page.width * 0.8,
+            color: palette.danger.base.color,
+            size: 14.0.into(),
+            line_height: widget::text::LineHeight::default(),
+            font: iced::Font::default(),
+            align_x: iced::alignment::Horizontal::Center.into(),
+            align_y: iced::alignment::Vertical::Center,
+            shaping: widget::text::Shaping::Advanced,
+        });
+    }
 }
 
 impl<'a> widget::canvas::Program<PdfMessage> for Document<'a> {
@@ -146,7 +406,7 @@ impl<'a> widget::canvas::Program<PdfMessage> for Document<'a> {
         &self,
         _state: &Self::State,
         renderer: &Renderer,
-        _theme: &iced::Theme,
+        theme: &iced::Theme,
         bounds: iced::Rectangle,
         _cursor: iced::advanced::mouse::Cursor,
     ) -> Vec<canvas::Geometry<Renderer>> {
@@ -155,6 +415,12 @@ impl<'a> widget::canvas::Program<PdfMessage> for Document<'a> {
             let bg_color = get_pdf_background_color(self.pdf_dark_mode, self.draw_page_borders);
             frame.fill_rectangle(iced::Point::new(0.0, 0.0), bounds.size(), bg_color);
 
+            if self.draw_page_borders && self.page_style.shadow {
+                for (_, rect) in &self.pages {
+                    self.draw_shadow(frame, (*rect).into());
+                }
+            }
+
             for (handle, rect) in &self.pages {
                 let bounds: iced::Rectangle = (*rect).into();
 
@@ -177,6 +443,16 @@ impl<'a> widget::canvas::Program<PdfMessage> for Document<'a> {
 
                 frame.draw_image(bounds, img);
             }
+
+            for (rect, error) in &self.failed {
+                self.draw_render_error(frame, theme, (*rect).into(), error);
+            }
+
+            if self.draw_page_borders && self.page_style.border_width > 0.0 {
+                for (_, rect) in &self.pages {
+                    self.draw_border(frame, (*rect).into());
+                }
+            }
         });
         vec![bg]
     }
@@ -200,21 +476,46 @@ impl<'a> widget::canvas::Program<PdfMessage> for SelectionOverlay<'a> {
         &self,
         _state: &Self::State,
         renderer: &Renderer,
-        _theme: &iced::Theme,
+        theme: &iced::Theme,
         bounds: iced::Rectangle,
         _cursor: iced::advanced::mouse::Cursor,
     ) -> Vec<canvas::Geometry<Renderer>> {
-        let Some(selection) = self.viewer.selection_rect() else {
-            return Vec::new();
-        };
-
         let viewport = bounds.size();
+        let flashed = self
+            .viewer
+            .flashed_page
+            .and_then(|page_idx| self.viewer.page_mapping(viewport)?.screen_rect(page_idx));
+        let selection = self.viewer.selection_rect();
+        if flashed.is_none() && selection.is_none() {
+            return Vec::new();
+        }
 
         let mut frame = canvas::Frame::new(renderer, viewport);
 
+        if let Some(page) = flashed {
+            let color = theme
+                .extended_palette()
+                .primary
+                .base
+                .color
+                .scale_alpha(0.25);
+            frame.fill_rectangle(page.x0.into(), page.size().into(), color);
+        }
+        let Some(selection) = selection else {
+            return vec![frame.into_geometry()];
+        };
+
+        // The rubber band stays faint so the text that will be copied stands out
         let mut color = iced::Color::from_rgb(0.0, 0.4, 0.8);
-        color.a = 0.25;
+        color.a = 0.1;
         frame.fill_rectangle(selection.x0.into(), selection.size().into(), color);
+        color.a = 0.35;
+        for line in self
+            .viewer
+            .visible_text_rects(viewport, &self.viewer.selection_preview)
+        {
+            frame.fill_rectangle(line.x0.into(), line.size().into(), color);
+        }
 
         vec![frame.into_geometry()]
     }
@@ -226,8 +527,32 @@ struct InteractiveOverlayState {
     pending_key: String,
     /// Keeps track for toggle link hitboxes events
     was_active: bool,
+    /// The start of the words to show hints for in word hint mode
+    word_prefix: String,
+    /// Keeps track of toggling word hint mode, like [InteractiveOverlayState::was_active]
+    word_hints_were_active: bool,
 }
 
+/// Number of characters typed in word hint mode before hints are shown
+const WORD_HINT_PREFIX_LEN: usize = 2;
+/// How many pixels of pinching double the zoom with `PinchSensitivity` at 1.0
+const PINCH_PIXELS_PER_DOUBLING: f32 = 200.0;
+/// Size of the hint labels at 100% zoom
+const HINT_TEXT_SIZE: f32 = 14.0;
+const MIN_HINT_TEXT_SIZE: f32 = 10.0;
+const MAX_HINT_TEXT_SIZE: f32 = 22.0;
+/// Links narrower or shorter than this many pixels on screen don't get a hint
+const MIN_HINTED_LINK_SIZE: f32 = 3.0;
+const PAGE_BOX_LABEL_SIZE: f32 = 12.0;
+/// Colors of the page boxes in the order they are listed, from the media box inwards
+const PAGE_BOX_COLORS: [iced::Color; 5] = [
+    iced::Color::from_rgb(0.8, 0.1, 0.6),
+    iced::Color::from_rgb(0.1, 0.6, 0.8),
+    iced::Color::from_rgb(0.9, 0.5, 0.1),
+    iced::Color::from_rgb(0.2, 0.7, 0.2),
+    iced::Color::from_rgb(0.5, 0.3, 0.9),
+];
+
 #[derive(Debug)]
 struct InteractiveOverlay<'a> {
     viewer: &'a PdfViewer,
@@ -239,6 +564,116 @@ impl<'a> InteractiveOverlay<'a> {
     }
 }
 
+impl InteractiveOverlay<'_> {
+    /// First the prefix is typed, then the hint of one of the matching words.
+    fn update_word_hints(
+        &self,
+        state: &mut InteractiveOverlayState,
+        event: canvas::Event,
+    ) -> Option<canvas::Action<PdfMessage>> {
+        if !state.word_hints_were_active {
+            state.word_prefix.clear();
+            state.pending_key.clear();
+        }
+        state.word_hints_were_active = true;
+
+        let canvas::Event::Keyboard(iced::keyboard::Event::KeyPressed { key, modifiers, .. }) =
+            event
+        else {
+            return None;
+        };
+        if modifiers.control() || modifiers.alt() || modifiers.logo() {
+            return None;
+        }
+
+        match key {
+            iced::keyboard::Key::Named(iced::keyboard::key::Named::Escape) => {
+                Some(canvas::Action::publish(PdfMessage::CloseWordHints).and_capture())
+            }
+            iced::keyboard::Key::Named(iced::keyboard::key::Named::Backspace) => {
+                if state.pending_key.pop().is_none() {
+                    state.word_prefix.pop();
+                }
+                Some(canvas::Action::request_redraw().and_capture())
+            }
+            iced::keyboard::Key::Character(c) => {
+                let ch = c.to_lowercase();
+                let viewport = *self.viewer.viewport.borrow();
+                if state.word_prefix.chars().count() < WORD_HINT_PREFIX_LEN {
+                    state.word_prefix.push_str(&ch);
+                    let matches = self.viewer.visible_words(viewport, &state.word_prefix);
+                    if state.word_prefix.chars().count() >= WORD_HINT_PREFIX_LEN
+                        && let [(word_idx, _)] = matches.as_slice()
+                    {
+                        return Some(
+                            canvas::Action::publish(PdfMessage::JumpToWord(*word_idx))
+                                .and_capture(),
+                        );
+                    }
+                    return Some(canvas::Action::request_redraw().and_capture());
+                }
+
+                state.pending_key.push_str(&ch);
+                let matches = self.viewer.visible_words(viewport, &state.word_prefix);
+                let keys = generate_key_combinations(matches.len(), &hint_chars());
+                if let Some(idx) = keys.iter().position(|k| k == &state.pending_key) {
+                    state.pending_key.clear();
+                    return Some(
+                        canvas::Action::publish(PdfMessage::JumpToWord(matches[idx].0))
+                            .and_capture(),
+                    );
+                }
+                if !keys.iter().any(|k| k.starts_with(&state.pending_key)) {
+                    state.pending_key.clear();
+                }
+                Some(canvas::Action::capture())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl InteractiveOverlay<'_> {
+    /// Arrow keys (or hjkl) move the caret and extend the selection while Shift is held. Every
+    /// other key is left to the keybindings so copying still works.
+    fn update_caret(&self, event: &canvas::Event) -> Option<canvas::Action<PdfMessage>> {
+        use iced::keyboard::{Key, key::Named};
+
+        let canvas::Event::Keyboard(iced::keyboard::Event::KeyPressed { key, modifiers, .. }) =
+            event
+        else {
+            return None;
+        };
+        if modifiers.control() || modifiers.alt() || modifiers.logo() {
+            return None;
+        }
+
+        let motion = match key.as_ref() {
+            Key::Named(Named::Escape) => {
+                return Some(canvas::Action::publish(PdfMessage::CloseCaret).and_capture());
+            }
+            Key::Named(Named::ArrowLeft) => CaretMotion::Left,
+            Key::Named(Named::ArrowRight) => CaretMotion::Right,
+            Key::Named(Named::ArrowUp) => CaretMotion::Up,
+            Key::Named(Named::ArrowDown) => CaretMotion::Down,
+            Key::Character(c) => match c.to_lowercase().as_str() {
+                "h" => CaretMotion::Left,
+                "l" => CaretMotion::Right,
+                "k" => CaretMotion::Up,
+                "j" => CaretMotion::Down,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let message = if modifiers.shift() {
+            PdfMessage::ExtendSelection(motion)
+        } else {
+            PdfMessage::MoveCaret(motion)
+        };
+        Some(canvas::Action::publish(message).and_capture())
+    }
+}
+
 impl<'a> widget::canvas::Program<PdfMessage> for InteractiveOverlay<'a> {
     type State = InteractiveOverlayState;
 
@@ -262,6 +697,16 @@ impl<'a> widget::canvas::Program<PdfMessage> for InteractiveOverlay<'a> {
             return Some(canvas::Action::publish(PdfMessage::CloseComment).and_capture());
         }
 
+        if self.viewer.show_word_hints {
+            return self.update_word_hints(state, event);
+        }
+        if self.viewer.caret.is_some()
+            && let Some(action) = self.update_caret(&event)
+        {
+            return Some(action);
+        }
+        state.word_hints_were_active = false;
+
         if !self.viewer.show_link_hitboxes {
             state.was_active = false;
             return None;
@@ -289,8 +734,8 @@ impl<'a> widget::canvas::Program<PdfMessage> for InteractiveOverlay<'a> {
                 state.pending_key.push_str(&ch);
 
                 let viewport = *self.viewer.viewport.borrow();
-                let link_visible = self.viewer.visible_links(viewport);
-                let keys = generate_key_combinations(link_visible.len());
+                let link_visible = self.viewer.hinted_links(viewport);
+                let keys = generate_key_combinations(link_visible.len(), &hint_chars());
 
                 if let Some(idx) = keys.iter().position(|k| k == &state.pending_key) {
                     state.pending_key.clear();
@@ -314,7 +759,7 @@ impl<'a> widget::canvas::Program<PdfMessage> for InteractiveOverlay<'a> {
 
     fn draw(
         &self,
-        _state: &Self::State,
+        state: &Self::State,
         renderer: &Renderer,
         _theme: &iced::Theme,
         bounds: iced::Rectangle,
@@ -324,20 +769,43 @@ impl<'a> widget::canvas::Program<PdfMessage> for InteractiveOverlay<'a> {
         let viewport = bounds.size();
         let link_visible = self.viewer.visible_links(viewport);
         let search_visible = self.viewer.visible_search_results(viewport);
+        let occurrences_visible = self
+            .viewer
+            .visible_matches(&self.viewer.occurrences, viewport);
         let comment_visible = self.viewer.visible_comments(viewport);
+        let media_visible = self.viewer.visible_media(viewport);
+        let word_visible = if self.viewer.show_word_hints && !state.word_prefix.is_empty() {
+            self.viewer.visible_words(viewport, &state.word_prefix)
+        } else {
+            vec![]
+        };
+        let caret_visible = self.viewer.visible_caret(viewport);
         if link_visible.is_empty()
+            && caret_visible.is_none()
             && search_visible.is_empty()
+            && occurrences_visible.is_empty()
             && comment_visible.is_empty()
+            && media_visible.is_empty()
+            && word_visible.is_empty()
             && self.viewer.hovered_link.is_none()
             && self.viewer.hovered_search_result.is_none()
             && self.viewer.hovered_comment.is_none()
+            && self.viewer.page_boxes.is_none()
         {
             return Vec::new();
         }
 
         let mut frame = canvas::Frame::new(renderer, viewport);
 
-        // Draw search results first (behind links).
+        // Draw occurrences of the selection first, search results go on top of them and links on
+        // top of both.
+        for (_, rect) in &occurrences_visible {
+            let mut color = iced::Color::from_rgb(0.3, 0.8, 1.0);
+            color.a = 0.25;
+            frame.fill_rectangle(rect.x0.into(), rect.size().into(), color);
+        }
+
+        // Draw search results (behind links).
         for (match_idx, rect) in &search_visible {
             let is_hovered = self
                 .viewer
@@ -364,10 +832,13 @@ impl<'a> widget::canvas::Program<PdfMessage> for InteractiveOverlay<'a> {
             frame.fill_rectangle(rect.x0.into(), rect.size().into(), color);
         }
 
+        let hint_size = hint_text_size(self.viewer.scale * self.viewer.fractional_scaling);
+
         // Draw link hitbox mode.
         if self.viewer.show_link_hitboxes {
-            let keys = generate_key_combinations(link_visible.len());
-            for (((_page_idx, _link_idx), rect), key) in link_visible.iter().zip(keys.iter()) {
+            let hinted = self.viewer.hinted_links(viewport);
+            let keys = generate_key_combinations(hinted.len(), &hint_chars());
+            for (((_page_idx, _link_idx), rect), key) in hinted.iter().zip(keys.iter()) {
                 let mut fill_color = iced::Color::from_rgb(0.9, 0.3, 0.1);
                 fill_color.a = 0.2;
                 frame.fill_rectangle(rect.x0.into(), rect.size().into(), fill_color);
@@ -379,31 +850,103 @@ impl<'a> widget::canvas::Program<PdfMessage> for InteractiveOverlay<'a> {
                     Stroke::default().with_color(stroke_color).with_width(1.5),
                 );
 
-                let text_size = 16.0;
-                let padding = 3.0;
-                let approx_char_w = text_size * 0.6;
-                let bg_w = approx_char_w * key.len() as f32 + padding * 2.0;
-                let bg_h = text_size + padding;
-                let bg_x = rect.x1.x + 2.0;
-                let bg_y = rect.center().y - bg_h / 2.0;
+                draw_hint_label(&mut frame, rect, key, hint_size, viewport);
+            }
+        }
+
+        // Draw word hint mode.
+        if !word_visible.is_empty() {
+            let show_keys = state.word_prefix.chars().count() >= WORD_HINT_PREFIX_LEN;
+            let keys = generate_key_combinations(word_visible.len(), &hint_chars());
+            for ((_, rect), key) in word_visible.iter().zip(keys.iter()) {
+                let mut fill_color = iced::Color::from_rgb(0.2, 0.7, 0.3);
+                fill_color.a = 0.3;
+                frame.fill_rectangle(rect.x0.into(), rect.size().into(), fill_color);
+                if show_keys && key.starts_with(&state.pending_key) {
+                    draw_hint_label(&mut frame, rect, key, hint_size, viewport);
+                }
+            }
+        }
+
+        if let Some((cursor, selection)) = &caret_visible {
+            let mut selection_color = iced::Color::from_rgb(0.0, 0.4, 0.8);
+            selection_color.a = 0.25;
+            for rect in selection {
+                frame.fill_rectangle(rect.x0.into(), rect.size().into(), selection_color);
+            }
+            // A thin bar at the start of the character, like a text input's cursor
+            let cursor_color = if self.viewer.pdf_dark_mode {
+                iced::Color::WHITE
+            } else {
+                iced::Color::BLACK
+            };
+            for rect in cursor {
                 frame.fill_rectangle(
-                    iced::Point::new(bg_x, bg_y),
-                    iced::Size::new(bg_w, bg_h),
-                    iced::Color::from_rgb(0.1, 0.1, 0.1),
+                    iced::Point::new(rect.x0.x - 1.0, rect.x0.y),
+                    iced::Size::new(2.0, rect.height()),
+                    cursor_color,
                 );
+            }
+        }
 
-                frame.fill_text(geometry::Text {
-                    content: key.clone(),
-                    position: iced::Point::new(bg_x + bg_w / 2.0, bg_y + bg_h / 2.0),
-                    max_width: bg_w,
-                    color: iced::Color::WHITE,
-                    size: text_size.into(),
-                    line_height: widget::text::LineHeight::Relative(1.0),
-                    font: iced::Font::default(),
-                    align_x: iced::alignment::Horizontal::Center.into(),
-                    align_y: iced::alignment::Vertical::Center,
-                    shaping: widget::text::Shaping::Basic,
-                });
+        for (rect, names, kind) in self.viewer.visible_page_boxes(viewport) {
+            let color = PAGE_BOX_COLORS[kind % PAGE_BOX_COLORS.len()];
+            frame.stroke_rectangle(
+                rect.x0.into(),
+                rect.size().into(),
+                Stroke::default().with_color(color).with_width(1.0),
+            );
+            frame.fill_text(geometry::Text {
+                content: names.to_string(),
+                // Labels of nested boxes are stacked so they don't cover each other
+                position: iced::Point::new(
+                    rect.x0.x + 4.0,
+                    rect.x0.y + 4.0 + kind as f32 * PAGE_BOX_LABEL_SIZE,
+                ),
+                max_width: rect.width(),
+                color,
+                size: PAGE_BOX_LABEL_SIZE.into(),
+                line_height: widget::text::LineHeight::Relative(1.0),
+                font: iced::Font::default(),
+                align_x: iced::alignment::Horizontal::Left.into(),
+                align_y: iced::alignment::Vertical::Top,
+                shaping: widget::text::Shaping::Basic,
+            });
+        }
+
+        // Draw placeholders for media mupdf can't play, with a tooltip while hovered.
+        for (media_idx, rect) in &media_visible {
+            let media = &self.viewer.media[*media_idx];
+            let hovered = self.viewer.hovered_media == Some(*media_idx);
+            frame.fill_rectangle(
+                rect.x0.into(),
+                rect.size().into(),
+                iced::Color::from_rgba(0.1, 0.1, 0.1, if hovered { 0.35 } else { 0.2 }),
+            );
+            frame.stroke_rectangle(
+                rect.x0.into(),
+                rect.size().into(),
+                Stroke::default()
+                    .with_color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+                    .with_width(1.5),
+            );
+            frame.fill_text(geometry::Text {
+                content: media.kind.glyph().to_string(),
+                position: iced::Point::new(
+                    (rect.x0.x + rect.x1.x) / 2.0,
+                    (rect.x0.y + rect.x1.y) / 2.0,
+                ),
+                max_width: rect.width(),
+                color: iced::Color::WHITE,
+                size: (rect.height() / 2.0).clamp(8.0, 48.0).into(),
+                line_height: widget::text::LineHeight::Relative(1.0),
+                font: iced::Font::default(),
+                align_x: iced::alignment::Horizontal::Center.into(),
+                align_y: iced::alignment::Vertical::Center,
+                shaping: widget::text::Shaping::Advanced,
+            });
+            if hovered {
+                draw_media_tooltip(&mut frame, rect, &media.tooltip(), viewport);
             }
         }
 
@@ -434,6 +977,10 @@ impl<'a> widget::canvas::Program<PdfMessage> for InteractiveOverlay<'a> {
         if self.viewer.hovered_link.is_some()
             || self.viewer.hovered_search_result.is_some()
             || self.viewer.hovered_comment.is_some()
+            || self
+                .viewer
+                .hovered_media
+                .is_some_and(|idx| self.viewer.media[idx].source.is_some())
         {
             iced::advanced::mouse::Interaction::Pointer
         } else {
@@ -442,11 +989,32 @@ impl<'a> widget::canvas::Program<PdfMessage> for InteractiveOverlay<'a> {
     }
 }
 
+/// Everything that decides where pages end up on screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LayoutKey {
+    layout: PageLayout,
+    translation: Vector<f32>,
+    scale: f32,
+    fractional_scaling: f32,
+    viewport: Size<f32>,
+}
+
+/// The screen space hitboxes of the visible links for one arrangement of the pages. They are only
+/// ever built by [PdfViewer::visible_links], which rebuilds them as soon as the arrangement
+/// changes, so they can't fall behind what is rendered.
+#[derive(Debug, Clone)]
+struct LinkHitboxes {
+    key: LayoutKey,
+    /// ((page_idx, link_idx), screen rect) of every link at least partially on screen
+    visible: Vec<((usize, usize), Rect<f32>)>,
+}
+
 #[derive(Debug)]
 pub enum MouseInteraction {
     None,
     Panning,
     Selecting,
+    DraggingScrollbar(Axis),
 }
 
 /// A pixmap is cached by its page number and the zoom level at which it was generated.
@@ -455,13 +1023,32 @@ pub enum MouseInteraction {
 pub struct PdfViewer {
     pub name: String,
     pub path: PathBuf,
+    /// The document was read from memory (such as stdin) and `path` doesn't point to a real file.
+    /// Such documents can't be watched or reloaded until they are saved somewhere.
+    pub in_memory: bool,
+    /// Set when the file was reloaded while its tab wasn't the active one
+    pub unseen_changes: bool,
+    /// The document was edited since it was opened or last saved, such as by deleting an
+    /// annotation. The edits only exist in memory until then.
+    pub modified: bool,
 
     pdf_dark_mode: bool,
+    /// Recolor text and vector graphics rather than inverting whole pages in the dark mode, see
+    /// [recolor]
+    recolor_pdf: bool,
     interface_dark_mode: bool,
     pub draw_page_borders: bool,
+    /// Drawn in place of the cursor, only set in presentation mode
+    pub presentation_pointer: PresentationPointer,
 
     doc: mupdf::Document,
+    /// Pages, text, links and the outline of [PdfViewer::doc]
+    backend: Box<dyn DocumentBackend>,
     display_lists: Vec<mupdf::DisplayList>,
+    /// The blocks of each page in reading order, cleared whenever the display lists change
+    reading_blocks: RefCell<HashMap<usize, Vec<Rect<f32>>>>,
+    /// Pages mupdf couldn't draw and the error it gave, they get a placeholder instead of crashing
+    render_errors: RefCell<HashMap<usize, String>>,
     /// Final iced image handles cached by render key. Kept separately so iced can reuse the
     /// GPU texture without re-uploading when the widget redraws for non-visual reasons.
     render_cache: RefCell<HashMap<RenderKey, image::Handle>>,
@@ -475,6 +1062,14 @@ pub struct PdfViewer {
     /// thread-safe, but iced may render on any thread, so we must copy into a Vec<u8> and pool
     /// it to avoid allocating multi-megabyte buffers on every frame during zoom or pan.
     buffer_pool: BufferPool,
+    /// Renders whole pages on other threads, see [RenderScheduler]
+    scheduler: RefCell<RenderScheduler>,
+    /// Whole pages rendered by the scheduler which haven't been shown yet
+    prefetched: RefCell<HashMap<RenderKey, image::Handle>>,
+    /// Small renders of every page for the page grid, see [overview::THUMBNAIL_WIDTH]
+    thumbnails: RefCell<HashMap<usize, Thumbnail>>,
+    /// Shown instead of the document while set
+    overview: Option<Overview>,
 
     pub translation: Vector<f32>,
     pub scale: f32,
@@ -488,14 +1083,37 @@ pub struct PdfViewer {
 
     selection_start: Option<Vector<f32>>,
     selection_end: Option<Vector<f32>>,
+    /// The lines of text a mouse selection covers while it is being dragged, in page space. This
+    /// is what gets copied once the mouse is released.
+    selection_preview: Vec<(usize, Rect<f32>)>,
     selected_text: String,
+    /// The most recent selection in page space. Only the part on the first selected page is kept
+    last_selection: Option<(usize, mupdf::Rect)>,
 
     layout: PageLayout,
 
     gradient_cache: [[u8; 4]; 256],
 
     show_link_hitboxes: bool,
+    show_word_hints: bool,
+    /// The boxes of every page while they are shown, see [metadata::page_boxes]
+    page_boxes: Option<Vec<Vec<PageBox>>>,
+    /// Whether NextPage and PreviousPage step through screen-sized segments of pages that don't
+    /// fit on screen
+    page_slicing: bool,
+    /// The block [PdfMessage::NextBlock] or [PdfMessage::PreviousBlock] moved to last, as (page,
+    /// index in reading order, translation after moving). The next step continues from it unless
+    /// the view has moved since
+    current_block: Option<(usize, usize, Vector<f32>)>,
+    words: Vec<Word>,
+    /// The text cursor of caret mode, indexes into [PdfViewer::char_bboxes]
+    caret: Option<Caret>,
     links: Vec<Vec<PageLink>>,
+    /// Looks up the links of each page in page space, built along with [PdfViewer::links] so
+    /// zooming and scrolling don't rebuild it
+    link_index: Vec<RectIndex>,
+    /// Cached by [PdfViewer::visible_links]
+    link_hitboxes: RefCell<Option<LinkHitboxes>>,
     hovered_link: Option<(usize, usize)>,
 
     show_search_results: bool,
@@ -503,24 +1121,62 @@ pub struct PdfViewer {
     current_search_result: Option<usize>,
 
     outline: Vec<OutlineItem>,
+    destinations: Destinations,
 
     /// The entire textual contents of the document. Used to search through text
-    text_contents: String,
+    text_contents: Arc<str>,
     /// Bounding boxes of every character in the document. Used to highlight searched text
     /// Each entry is (page_index, byte_offset_in_text_contents, bounding_box)
-    char_bboxes: Vec<(usize, usize, Rect<f32>)>,
+    char_bboxes: Arc<[(usize, usize, Rect<f32>)]>,
     /// The search matches found in the document
     search_matches: Vec<SearchMatch>,
     pub(crate) search_method: SearchMethod,
+    pub(crate) search_flags: SearchFlags,
     /// The thing to search for
     pub(crate) needle: String,
     /// Monotonically incremented to cancel stale async search tasks.
     search_generation: u64,
+    /// Every occurrence of [PdfViewer::occurrences_needle], highlighted as a lighter alternative to
+    /// searching
+    occurrences: Vec<SearchMatch>,
+    occurrences_needle: String,
+    occurrences_generation: u64,
 
     /// All text annotations (sticky notes / comments) extracted from the document.
     comments: Vec<Comment>,
+    /// Every annotation worth listing in the sidebar, in page order
+    annotations: Vec<AnnotationEntry>,
+    /// Pages whose annotations are collapsed in the sidebar
+    collapsed_annotation_pages: HashSet<usize>,
+    /// The optional content layers and whether they are shown right now
+    layers: Vec<Layer>,
+    /// The layers hidden through [PdfMessage::ToggleLayer], the document decides while unset
+    hidden_layers: Option<Vec<String>>,
+    /// Video, sound and 3D annotations, drawn as placeholders
+    media: Vec<Media>,
+    hovered_media: Option<usize>,
+    /// Shown until dismissed when the document's form computes values with JavaScript, which
+    /// isn't run
+    script_notice: bool,
+    /// The signature fields of the form, see [metadata::signatures]
+    signatures: Vec<Signature>,
     hovered_comment: Option<usize>,
     active_comment: Option<usize>,
+    /// Shown for a moment after trying to page past either end of the document
+    edge_indicator: Option<DocumentEdge>,
+    /// Incremented whenever the edge indicator is shown so only the latest timeout hides it
+    edge_indicator_generation: u64,
+    /// Page tinted for a moment after its text was copied with [PdfMessage::SelectAllPage]
+    flashed_page: Option<usize>,
+    /// Like [PdfViewer::edge_indicator_generation] for the flash
+    page_flash_generation: u64,
+    /// The last of the configured zoom presets that was used
+    zoom_preset: Option<usize>,
+    /// Shown while the view moves and for [scrollbar::HIDE_DELAY] after
+    scrollbars_visible: bool,
+    /// The scrollbar under the mouse
+    hovered_scrollbar: Option<Axis>,
+    scrollbars_generation: u64,
 
     /// The widget's position in window coordinates, updated each frame by the overlay draw.
     widget_position: RefCell<iced::Point>,
@@ -535,32 +1191,69 @@ impl PdfViewer {
         Vec<Vec<PageLink>>,
         Vec<OutlineItem>,
         Vec<Comment>,
+        Vec<AnnotationEntry>,
+        HashMap<usize, String>,
     )> {
         let mut display_lists = vec![];
+        let mut render_errors = HashMap::new();
         let mut links = vec![];
         let mut comments = vec![];
-        for (page_idx, page) in doc.pages()?.flatten().enumerate() {
-            let dl = mupdf::DisplayList::new(page.bounds()?)?;
+        let mut annotations = vec![];
+        for (page_idx, page) in doc.pages()?.enumerate() {
+            let (bounds, page) = match page.and_then(|page| Ok((page.bounds()?, page))) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    error!("Couldn't load page {page_idx}: {e}");
+                    render_errors.insert(page_idx, e.to_string());
+                    // Drawn the size of the page before it, which is the most likely size
+                    let bounds = display_lists
+                        .last()
+                        .map(mupdf::DisplayList::bounds)
+                        .unwrap_or(mupdf::Rect::new(0.0, 0.0, 612.0, 792.0));
+                    display_lists.push(mupdf::DisplayList::new(bounds)?);
+                    links.push(vec![]);
+                    continue;
+                }
+            };
+            let dl = mupdf::DisplayList::new(bounds)?;
             let dummy_device = Device::from_display_list(&dl)?;
             let ctm = Matrix::IDENTITY;
-            page.run(&dummy_device, &ctm)?;
+            // A broken content stream only takes its own page down, what was recorded before the
+            // error is still drawn underneath the placeholder
+            if let Err(e) = page.run(&dummy_device, &ctm) {
+                render_errors.insert(page_idx, e.to_string());
+            }
             display_lists.push(dl);
-
-            let page_links: Vec<PageLink> = page
-                .links()?
-                .map(|link| PageLink {
-                    bounds: link.bounds,
-                    uri: link.uri,
-                    dest: link.dest,
-                })
-                .collect();
-            links.push(page_links);
+            links.push(backend::links(&page).unwrap_or_else(|e| {
+                error!("Couldn't read the links of page {page_idx}: {e}");
+                render_errors
+                    .entry(page_idx)
+                    .or_insert_with(|| e.to_string());
+                vec![]
+            }));
 
             if let Ok(pdf_page) = PdfPage::try_from(page) {
-                for ann in pdf_page.annotations() {
-                    let Ok(PdfAnnotationType::Text) = ann.r#type() else {
+                for (annot_idx, ann) in pdf_page.annotations().enumerate() {
+                    let Ok(kind) = ann.r#type() else { continue };
+                    if !matches!(
+                        kind,
+                        PdfAnnotationType::Link
+                            | PdfAnnotationType::Popup
+                            | PdfAnnotationType::Widget
+                    ) && let Ok(bounds) = ann.rect()
+                    {
+                        annotations.push(AnnotationEntry {
+                            page_idx,
+                            annot_idx,
+                            kind,
+                            content: ann.contents().ok().flatten().map(str::to_string),
+                            author: ann.author().ok().flatten().map(str::to_string),
+                            bounds,
+                        });
+                    }
+                    if kind != PdfAnnotationType::Text {
                         continue;
-                    };
+                    }
                     let Ok(Some(content)) = ann.contents() else {
                         continue;
                     };
@@ -575,8 +1268,15 @@ impl PdfViewer {
                 }
             }
         }
-        let outline = Self::extract_outline(doc).unwrap_or_default();
-        Ok((display_lists, links, outline, comments))
+        let outline = backend::outline(doc).unwrap_or_default();
+        Ok((
+            display_lists,
+            links,
+            outline,
+            comments,
+            annotations,
+            render_errors,
+        ))
     }
 
     pub fn from_path(path: PathBuf) -> Result<Self> {
@@ -585,31 +1285,55 @@ impl PdfViewer {
             .expect("The pdf must have a file name")
             .to_string_lossy()
             .to_string();
-        let doc = mupdf::Document::open(&path.to_str().unwrap())?;
-        let (display_lists, links, outline, comments) = Self::build_document_data(&doc)?;
-        let (all_text, bboxes) = Self::extract_search_data(&display_lists)?;
-
-        let bg_color = DARK_THEME
-            .extended_palette()
-            .background
-            .base
-            .color
-            .into_rgba8();
-        let mut gradient_cache = [[0; 4]; 256];
-        generate_gradient_cache(&mut gradient_cache, &bg_color);
+        let doc = archive::open(&path)?;
+        Self::from_document(name, path, doc, false)
+    }
+
+    /// Opens a pdf which only exists in memory, such as one piped through stdin. `name` is shown
+    /// in place of a file name.
+    pub fn from_bytes(name: String, bytes: &[u8]) -> Result<Self> {
+        let doc = mupdf::Document::from_bytes(bytes, "application/pdf")?;
+        Self::from_document(name.clone(), PathBuf::from(name), doc, true)
+    }
+
+    fn from_document(
+        name: String,
+        path: PathBuf,
+        doc: mupdf::Document,
+        in_memory: bool,
+    ) -> Result<Self> {
+        let backend = MupdfBackend::new(doc.clone());
+        let (display_lists, links, outline, comments, annotations, mut render_errors) =
+            Self::build_document_data(&doc)?;
+        let (all_text, bboxes) = Self::extract_search_data(&display_lists, &mut render_errors);
+        let destinations = Destinations::load(&doc);
+
+        let gradient_cache = dark_mode_gradient();
 
         Ok(PdfViewer {
             name,
+            scheduler: RefCell::new(RenderScheduler::new((!in_memory).then(|| path.clone()))),
             path,
+            in_memory,
+            unseen_changes: false,
+            modified: false,
             pdf_dark_mode: false,
+            recolor_pdf: CONFIG.read().unwrap().pdf_dark_mode_style == PdfDarkModeStyle::Recolor,
             interface_dark_mode: false,
             draw_page_borders: true,
+            presentation_pointer: PresentationPointer::Off,
             doc,
+            backend: Box::new(backend),
             display_lists,
+            reading_blocks: RefCell::default(),
+            render_errors: RefCell::new(render_errors),
             render_cache: RefCell::default(),
             allocation_cache: RefCell::default(),
             pixmap_pool: RefCell::default(),
             buffer_pool: Arc::new(Mutex::new(HashMap::new())),
+            prefetched: RefCell::default(),
+            thumbnails: RefCell::default(),
+            overview: None,
             translation: Vector::zero(),
             scale: 1.0,
             fractional_scaling: 1.0,
@@ -621,24 +1345,55 @@ impl PdfViewer {
             mouse_interaction: MouseInteraction::None,
             selection_start: None,
             selection_end: None,
+            selection_preview: vec![],
             selected_text: String::new(),
+            last_selection: None,
             show_link_hitboxes: false,
+            show_word_hints: false,
+            page_slicing: false,
+            current_block: None,
+            page_boxes: None,
+            words: extract_words(&all_text, &bboxes),
+            caret: None,
+            link_index: index_links(&links),
             links,
+            link_hitboxes: RefCell::new(None),
             hovered_link: None,
             show_search_results: false,
             hovered_search_result: None,
             current_search_result: None,
             outline,
+            destinations,
             widget_position: RefCell::new(iced::Point::new(0.0, 0.0)),
-            text_contents: all_text,
-            char_bboxes: bboxes,
+            text_contents: all_text.into(),
+            char_bboxes: bboxes.into(),
             search_matches: vec![],
             search_method: CONFIG.read().unwrap().default_search_method,
+            search_flags: SearchFlags::default(),
             needle: String::new(),
             search_generation: 0,
+            occurrences: vec![],
+            occurrences_needle: String::new(),
+            occurrences_generation: 0,
             comments,
+            annotations,
+            collapsed_annotation_pages: HashSet::new(),
+            layers: layers::read(&doc).unwrap_or_default(),
+            hidden_layers: None,
+            media: media::read(&doc).unwrap_or_default(),
+            hovered_media: None,
+            script_notice: forms::calculates_with_javascript(&doc),
+            signatures: metadata::signatures(&doc),
             hovered_comment: None,
             active_comment: None,
+            edge_indicator: None,
+            edge_indicator_generation: 0,
+            flashed_page: None,
+            page_flash_generation: 0,
+            zoom_preset: None,
+            scrollbars_visible: false,
+            hovered_scrollbar: None,
+            scrollbars_generation: 0,
         })
     }
 }
@@ -646,35 +1401,82 @@ impl PdfViewer {
 impl PdfViewer {
     pub fn update(&mut self, msg: PdfMessage) -> iced::Task<PdfMessage> {
         let mut out = iced::Task::none();
-        let page_count = self.doc.page_count().unwrap() as usize;
+        let page_count = self.doc.page_count().unwrap_or(0) as usize;
+        let layout_before = self.layout_key(*self.viewport.borrow());
         match msg {
+            PdfMessage::NextPage if self.page_slicing && self.step_page_segment(true) => {}
             PdfMessage::NextPage => {
-                let current = self
-                    .layout
-                    .center_of_page(&self.doc, self.translation, *self.viewport.borrow())
-                    .unwrap();
-                let next = self
-                    .layout
-                    .center_of_page_below(&self.doc, self.translation, *self.viewport.borrow())
-                    .unwrap();
+                let viewport = *self.viewport.borrow();
+                let (Ok(current), Ok(next)) = (
+                    self.layout
+                        .center_of_page(&self.doc, self.translation, viewport),
+                    self.layout
+                        .center_of_page_below(&self.doc, self.translation, viewport),
+                ) else {
+                    return out;
+                };
 
-                self.translation.y += next.center().y - current.center().y;
+                let delta = next.center().y - current.center().y;
+                if delta.abs() < f32::EPSILON {
+                    out = self.show_edge_indicator(DocumentEdge::End);
+                }
+                self.translation.y += delta;
+                if self.page_slicing {
+                    self.show_page_segment(true);
+                }
             }
+            PdfMessage::PreviousPage if self.page_slicing && self.step_page_segment(false) => {}
             PdfMessage::PreviousPage => {
-                let current = self
-                    .layout
-                    .center_of_page(&self.doc, self.translation, *self.viewport.borrow())
-                    .unwrap();
-                let prev = self
-                    .layout
-                    .center_of_page_above(&self.doc, self.translation, *self.viewport.borrow())
-                    .unwrap();
+                let viewport = *self.viewport.borrow();
+                let (Ok(current), Ok(prev)) = (
+                    self.layout
+                        .center_of_page(&self.doc, self.translation, viewport),
+                    self.layout
+                        .center_of_page_above(&self.doc, self.translation, viewport),
+                ) else {
+                    return out;
+                };
 
-                self.translation.y += prev.center().y - current.center().y;
+                let delta = prev.center().y - current.center().y;
+                if delta.abs() < f32::EPSILON {
+                    out = self.show_edge_indicator(DocumentEdge::Start);
+                }
+                self.translation.y += delta;
+                if self.page_slicing {
+                    self.show_page_segment(false);
+                }
+            }
+            PdfMessage::NextBlock => {
+                if !self.step_block(true) {
+                    out = self.update(PdfMessage::NextPage);
+                }
+            }
+            PdfMessage::PreviousBlock => {
+                if !self.step_block(false) {
+                    out = self.update(PdfMessage::PreviousPage);
+                }
+            }
+            PdfMessage::TogglePageBoxes => {
+                if self.page_boxes.take().is_none() {
+                    let boxes = self.load_page_boxes();
+                    if boxes.iter().all(Vec::is_empty) {
+                        out = iced::Task::done(PdfMessage::Error(MiroError::document(
+                            "Only pdf documents have page boxes",
+                        )));
+                    } else {
+                        self.page_boxes = Some(boxes);
+                    }
+                }
+            }
+            PdfMessage::TogglePageSlicing => {
+                self.page_slicing = !self.page_slicing;
+                if self.page_slicing {
+                    self.show_page_segment(true);
+                }
             }
             PdfMessage::SetPage(idx) => {
                 if idx < page_count
-                    && let Ok(translation) = self.layout.translation_for_page(
+                    && let Ok(translation) = self.layout.translation_for_spread(
                         &self.doc,
                         self.scale,
                         self.fractional_scaling,
@@ -685,6 +1487,12 @@ impl PdfViewer {
                     self.translation = translation;
                 }
             }
+            // SetPage puts the page where it belongs in its spread, so the cover of a book stays
+            // alone and the last page of a double page layout is on the correct side
+            PdfMessage::FirstPage => out = self.update(PdfMessage::SetPage(0)),
+            PdfMessage::LastPage => {
+                out = self.update(PdfMessage::SetPage(page_count.saturating_sub(1)));
+            }
             PdfMessage::SetTranslation(vector) => {
                 self.translation = vector;
             }
@@ -695,38 +1503,61 @@ impl PdfViewer {
             PdfMessage::SetLayout(page_layout) => {
                 self.layout = page_layout;
             }
-            PdfMessage::ZoomIn => {
-                self.scale *= 1.2;
+            PdfMessage::ZoomIn => match &mut self.overview {
+                Some(overview) => overview.zoom_in(),
+                None => self.scale *= 1.2,
+            },
+            PdfMessage::ZoomOut => match &mut self.overview {
+                Some(overview) => overview.zoom_out(),
+                None => self.scale /= 1.2,
+            },
+            PdfMessage::Pinch(_) if self.overview.is_some() => {}
+            PdfMessage::Pinch(pixels) => {
+                let sensitivity = CONFIG.read().unwrap().pinch_sensitivity;
+                self.zoom_around(
+                    pinch_zoom_factor(pixels, sensitivity),
+                    self.local_mouse_pos(),
+                );
+            }
+            PdfMessage::ToggleOverview => match self.overview {
+                Some(_) => self.overview = None,
+                None => {
+                    let overview = Overview::default();
+                    out = overview.scroll_to(self.current_page(), self.display_lists.len());
+                    self.overview = Some(overview);
+                }
+            },
+            PdfMessage::CloseOverview => {
+                self.overview = None;
             }
-            PdfMessage::ZoomOut => {
-                self.scale /= 1.2;
+            PdfMessage::OpenOverviewPage(page_idx) => {
+                self.overview = None;
+                out = iced::Task::done(PdfMessage::SetPage(page_idx));
             }
             PdfMessage::ZoomHome => {
                 self.scale = 1.0;
             }
-            PdfMessage::ZoomFit => {
-                let page_idx = self.current_page();
-                if let Some(display_list) = self.display_lists.get(page_idx) {
-                    let page_bounds = display_list.bounds();
-                    let page_width = page_bounds.x1 - page_bounds.x0;
-                    let page_height = page_bounds.y1 - page_bounds.y0;
-                    if page_width > 0.0 && page_height > 0.0 {
-                        let viewport = *self.viewport.borrow();
-                        if viewport.width > 0.0 && viewport.height > 0.0 {
-                            let scale_x = viewport.width / page_width;
-                            let scale_y = viewport.height / page_height;
-                            self.scale = scale_x.min(scale_y) / self.fractional_scaling;
-                            if let Ok(translation) = self.layout.translation_for_page(
-                                &self.doc,
-                                self.scale,
-                                self.fractional_scaling,
-                                page_idx,
-                                viewport,
-                            ) {
-                                self.translation = translation;
-                            }
-                        }
-                    }
+            PdfMessage::ZoomFit => self.zoom_to_preset(ZoomPreset::Fit),
+            PdfMessage::ZoomFitColumn => self.zoom_to_preset(ZoomPreset::FitColumn),
+            PdfMessage::ZoomToSelection => match self.last_selection {
+                Some((page_idx, region)) => self.zoom_to_region(page_idx, region.into()),
+                None => {
+                    out = iced::Task::done(PdfMessage::Error(MiroError::document(
+                        "Select a region with the mouse to zoom to it",
+                    )));
+                }
+            },
+            PdfMessage::ZoomPreset(idx) => {
+                if let Some(preset) = CONFIG.read().unwrap().zoom_presets.get(idx).copied() {
+                    self.zoom_preset = Some(idx);
+                    self.zoom_to_preset(preset);
+                }
+            }
+            PdfMessage::CycleZoomPreset => {
+                let count = CONFIG.read().unwrap().zoom_presets.len();
+                if count > 0 {
+                    let next = self.zoom_preset.map_or(0, |idx| (idx + 1) % count);
+                    out = iced::Task::done(PdfMessage::ZoomPreset(next));
                 }
             }
             PdfMessage::Move(vector) => {
@@ -746,12 +1577,35 @@ impl PdfViewer {
                     }
                     MouseInteraction::Selecting => {
                         self.selection_end = Some(new_local);
+                        self.update_selection_preview();
+                    }
+                    MouseInteraction::DraggingScrollbar(axis) => {
+                        out = iced::Task::done(PdfMessage::Move(
+                            self.scrollbar_drag(axis, axis.of(new_local - old_local)),
+                        ));
                     }
                 }
+                let hovered = self.scrollbar_at(new_local);
+                if hovered.is_some() {
+                    out = iced::Task::batch([out, self.show_scrollbars()]);
+                }
+                self.hovered_scrollbar = hovered;
                 self.update_hover_state();
             }
+            // The page grid handles clicks by itself
+            PdfMessage::MouseAction(_, _) if self.overview.is_some() => {}
             PdfMessage::MouseAction(mouse_action, pressed) => {
-                if pressed {
+                let local = self.local_mouse_pos();
+                let grabs_scrollbar = pressed
+                    && matches!(mouse_action, MouseAction::Panning | MouseAction::Selection);
+                if let Some(axis) = grabs_scrollbar.then(|| self.scrollbar_at(local)).flatten() {
+                    // Clicking the track next to the thumb jumps there first
+                    let viewport = *self.viewport.borrow();
+                    let jump = self.scrollbars(viewport).distance_to_center(axis, local);
+                    self.translation += self.scrollbar_drag(axis, jump);
+                    self.mouse_interaction = MouseInteraction::DraggingScrollbar(axis);
+                    self.mouse_pressed_at = self.mouse_pos;
+                } else if pressed {
                     match mouse_action {
                         MouseAction::Panning => {
                             self.mouse_interaction = MouseInteraction::Panning;
@@ -765,7 +1619,9 @@ impl PdfViewer {
                             let local = self.local_mouse_pos();
                             self.selection_start = Some(local);
                             self.selection_end = Some(local);
+                            self.selection_preview.clear();
                             self.selected_text.clear();
+                            self.last_selection = None;
                         }
                         MouseAction::NextPage => {
                             out = iced::Task::done(PdfMessage::NextPage);
@@ -774,10 +1630,10 @@ impl PdfViewer {
                             out = iced::Task::done(PdfMessage::PreviousPage);
                         }
                         MouseAction::ZoomIn => {
-                            out = iced::Task::done(PdfMessage::ZoomIn);
+                            self.zoom_around(1.2, self.local_mouse_pos());
                         }
                         MouseAction::ZoomOut => {
-                            out = iced::Task::done(PdfMessage::ZoomOut);
+                            self.zoom_around(1.0 / 1.2, self.local_mouse_pos());
                         }
                         MouseAction::MoveUp => {
                             out = iced::Task::done(PdfMessage::Move(Vector::new(0.0, -MOVE_STEP)));
@@ -803,19 +1659,7 @@ impl PdfViewer {
                                     if let Some(m) = self.search_matches.get(match_idx) {
                                         let text = self.text_contents[m.start_byte..m.end_byte]
                                             .to_string();
-                                        out = iced::Task::perform(
-                                            async move {
-                                                if let Ok(mut clipboard) = arboard::Clipboard::new()
-                                                    && let Err(e) = clipboard.set_text(text)
-                                                {
-                                                    error!(
-                                                        "Failed to copy search result to clipboard: {}",
-                                                        e
-                                                    );
-                                                }
-                                            },
-                                            |_| PdfMessage::None,
-                                        );
+                                        out = copy_selected_text(text);
                                     }
                                 } else if let Some(comment_idx) = self.hovered_comment {
                                     if self.active_comment == Some(comment_idx) {
@@ -823,11 +1667,15 @@ impl PdfViewer {
                                     } else {
                                         self.active_comment = Some(comment_idx);
                                     }
+                                } else if let Some(media_idx) = self.hovered_media {
+                                    self.active_comment = None;
+                                    out = self.open_media(media_idx);
                                 } else {
                                     self.active_comment = None;
                                 }
                             }
                         }
+                        MouseInteraction::DraggingScrollbar(_) => {}
                         MouseInteraction::Selecting => {
                             if let (Some(start), Some(end)) =
                                 (self.selection_start, self.selection_end)
@@ -838,12 +1686,20 @@ impl PdfViewer {
                                     let selection_rect = Rect::from_points(min, max);
                                     self.selected_text =
                                         self.extract_text_from_rect(selection_rect);
+                                    self.last_selection = self
+                                        .screen_rect_to_page_regions(selection_rect)
+                                        .into_iter()
+                                        .next();
+                                    if CONFIG.read().unwrap().highlight_occurrences {
+                                        out = self.highlight_occurrences();
+                                    }
                                 }
                             }
                         }
                     }
                     self.selection_start = None;
                     self.selection_end = None;
+                    self.selection_preview.clear();
                     self.mouse_interaction = MouseInteraction::None;
                 }
             }
@@ -852,7 +1708,7 @@ impl PdfViewer {
             }
             PdfMessage::ActivateLink(idx) => {
                 let viewport = *self.viewport.borrow();
-                let visible = self.visible_links(viewport);
+                let visible = self.hinted_links(viewport);
                 if let Some(((page_idx, link_idx), _)) = visible.get(idx) {
                     out = self.activate_link(*page_idx, *link_idx);
                 }
@@ -860,41 +1716,267 @@ impl PdfViewer {
             PdfMessage::CloseLinkHitboxes => {
                 self.show_link_hitboxes = false;
             }
-            PdfMessage::CloseComment => {
-                self.active_comment = None;
+            PdfMessage::ToggleWordHints => {
+                self.show_word_hints = !self.show_word_hints;
+                self.show_link_hitboxes = false;
             }
-            PdfMessage::FileChanged => {
-                self.render_cache.borrow_mut().clear();
-                self.allocation_cache.borrow_mut().clear();
-                self.pixmap_pool.borrow_mut().clear();
-
-                if let Some(path_str) = self.path.to_str()
-                    && let Ok(new_doc) = mupdf::Document::open(path_str)
-                    && let Ok((display_lists, links, outline, comments)) =
-                        Self::build_document_data(&new_doc)
-                {
-                    self.doc = new_doc;
-                    self.display_lists = display_lists;
-                    self.links = links;
-                    self.outline = outline;
-                    self.comments = comments;
-                    self.active_comment = None;
-                    self.hovered_comment = None;
+            PdfMessage::CloseWordHints => {
+                self.show_word_hints = false;
+            }
+            PdfMessage::JumpToWord(idx) => {
+                self.show_word_hints = false;
+                if let Some(word) = self.words.get(idx) {
+                    self.scroll_to_rect(word.page_idx, word.bounds);
                 }
             }
-            PdfMessage::PrintPdf => {
-                let path = self.path.clone();
-                out = iced::Task::perform(
-                    async move {
-                        let file_url = format!("file://{}", path.to_string_lossy());
-                        if let Err(e) = webbrowser::open(&file_url) {
-                            error!("Failed to open PDF in default browser: {}", e);
-                        }
-                    },
-                    |_| PdfMessage::None,
-                );
+            PdfMessage::ToggleCaretMode => {
+                let page_idx = self.current_page();
+                self.caret = match self.caret {
+                    Some(_) => None,
+                    None => self
+                        .char_bboxes
+                        .iter()
+                        .position(|(idx, _, _)| *idx == page_idx)
+                        .map(Caret::new),
+                };
             }
-            PdfMessage::PageUp => {
+            PdfMessage::CloseCaret => {
+                self.caret = None;
+            }
+            PdfMessage::MoveCaret(motion) => self.move_caret(motion, false),
+            PdfMessage::ExtendSelection(motion) => {
+                self.move_caret(motion, true);
+                if CONFIG.read().unwrap().highlight_occurrences {
+                    out = self.highlight_occurrences();
+                }
+            }
+            PdfMessage::HighlightOccurrences => {
+                if self.selection_text().trim() == self.occurrences_needle {
+                    self.clear_occurrences();
+                } else {
+                    out = self.highlight_occurrences();
+                }
+            }
+            PdfMessage::CopySelection => {
+                let text = self.selection_text();
+                if !text.is_empty() {
+                    out = copy_selected_text(text);
+                }
+            }
+            PdfMessage::CopyTable(format) => {
+                out = match self.selected_table() {
+                    Ok(rows) => iced::Task::perform(
+                        copy_to_clipboard(text_layout::to_delimited(&rows, format)),
+                        PdfMessage::from_result,
+                    ),
+                    Err(e) => iced::Task::done(PdfMessage::Error(e)),
+                };
+            }
+            PdfMessage::CopyCitation => {
+                out = match self.identifier() {
+                    Some(identifier) => {
+                        let title = self.doc.metadata(MetadataName::Title).ok();
+                        iced::Task::perform(
+                            copy_to_clipboard(identifier.citation(title.as_deref())),
+                            PdfMessage::from_result,
+                        )
+                    }
+                    None => iced::Task::done(no_identifier_error()),
+                };
+            }
+            PdfMessage::CopyPageReference => {
+                out = iced::Task::perform(
+                    copy_to_clipboard(self.page_reference(self.current_page())),
+                    PdfMessage::from_result,
+                );
+            }
+            PdfMessage::OpenIdentifier => {
+                out = match self.identifier() {
+                    Some(identifier) => open_external(&identifier.url()),
+                    None => iced::Task::done(no_identifier_error()),
+                };
+            }
+            PdfMessage::SelectAllPage => {
+                let page_idx = self.current_page();
+                let text = self.page_text(page_idx);
+                out = if text.trim().is_empty() {
+                    iced::Task::done(PdfMessage::Error(MiroError::document(format!(
+                        "Page {} has no text to copy",
+                        page_idx + 1
+                    ))))
+                } else {
+                    iced::Task::batch([copy_selected_text(text), self.flash_page(page_idx)])
+                };
+            }
+            PdfMessage::LookupSelection => {
+                let text = self.selection_text();
+                let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !text.is_empty() {
+                    let target =
+                        lookup::lookup_target(&CONFIG.read().unwrap().lookup_command, &text);
+                    out = iced::Task::perform(lookup::run(target), |_| PdfMessage::None);
+                }
+            }
+            PdfMessage::CopyFormula => {
+                let template = CONFIG.read().unwrap().formula_command.clone();
+                out = match self.last_selection {
+                    None => iced::Task::done(PdfMessage::Error(MiroError::document(
+                        "Select the formula with the mouse first",
+                    ))),
+                    Some(_) if template.trim().is_empty() => {
+                        iced::Task::done(PdfMessage::Error(MiroError::external(
+                            "Copying formulas requires a FormulaCommand such as pix2tex in the \
+                            config file",
+                        )))
+                    }
+                    Some((page_idx, region)) => {
+                        let path = export::share_path(&self.name, page_idx);
+                        match export::page_to_png(
+                            &self.display_lists[page_idx],
+                            Some(region),
+                            export::SHARE_SCALE,
+                            &path,
+                        ) {
+                            Ok(()) => iced::Task::perform(
+                                formula::copy_as_latex(template, path),
+                                PdfMessage::from_result,
+                            ),
+                            Err(e) => iced::Task::done(PdfMessage::Error(MiroError::document(
+                                format!("Couldn't render the formula: {e}"),
+                            ))),
+                        }
+                    }
+                };
+            }
+            PdfMessage::CloseComment => {
+                self.active_comment = None;
+            }
+            PdfMessage::DeleteAnnotation(idx) => match self.delete_annotation(idx) {
+                Ok(()) => self.modified = true,
+                Err(e) => {
+                    out = iced::Task::done(PdfMessage::Error(MiroError::document(format!(
+                        "Couldn't delete the annotation: {e}"
+                    ))));
+                }
+            },
+            PdfMessage::DismissScriptNotice => {
+                self.script_notice = false;
+            }
+            PdfMessage::ToggleLayer(idx) => {
+                if let Some(layer) = self.layers.get(idx) {
+                    let name = layer.name.clone();
+                    let mut hidden: Vec<String> = self
+                        .layers
+                        .iter()
+                        .filter(|l| !l.visible && l.name != name)
+                        .map(|l| l.name.clone())
+                        .collect();
+                    if layer.visible {
+                        hidden.push(name);
+                    }
+                    if let Err(e) = self.set_hidden_layers(hidden) {
+                        out = iced::Task::done(PdfMessage::Error(MiroError::document(format!(
+                            "Couldn't change the layers: {e}"
+                        ))));
+                    }
+                }
+            }
+            PdfMessage::ToggleAnnotationPage(page_idx) => {
+                if !self.collapsed_annotation_pages.remove(&page_idx) {
+                    self.collapsed_annotation_pages.insert(page_idx);
+                }
+            }
+            PdfMessage::FileChanged if self.in_memory => {}
+            PdfMessage::FileChanged => {
+                self.render_cache.borrow_mut().clear();
+                self.thumbnails.borrow_mut().clear();
+                self.prefetched.borrow_mut().clear();
+                self.scheduler.get_mut().reset(Some(self.path.clone()));
+                self.allocation_cache.borrow_mut().clear();
+                self.pixmap_pool.borrow_mut().clear();
+
+                if let Some(path_str) = self.path.to_str()
+                    && let Ok(new_doc) = mupdf::Document::open(path_str)
+                    && self
+                        .hidden_layers
+                        .as_ref()
+                        .is_none_or(|hidden| layers::hide(&new_doc, hidden).is_ok())
+                    && let Ok((display_lists, links, outline, comments, annotations, render_errors)) =
+                        Self::build_document_data(&new_doc)
+                {
+                    self.destinations = Destinations::load(&new_doc);
+                    // Edits made in miro are lost along with the old document
+                    self.modified = false;
+                    self.layers = layers::read(&new_doc).unwrap_or_default();
+                    self.media = media::read(&new_doc).unwrap_or_default();
+                    self.signatures = metadata::signatures(&new_doc);
+                    self.current_block = None;
+                    self.hovered_media = None;
+                    self.backend = Box::new(MupdfBackend::new(new_doc.clone()));
+                    self.doc = new_doc;
+                    self.display_lists = display_lists;
+                    self.reading_blocks.borrow_mut().clear();
+                    self.render_errors.replace(render_errors);
+                    self.link_index = index_links(&links);
+                    self.links = links;
+                    self.link_hitboxes.replace(None);
+                    self.outline = outline;
+                    self.comments = comments;
+                    self.annotations = annotations;
+                    self.last_selection = None;
+                    self.caret = None;
+                    self.clear_occurrences();
+                    self.active_comment = None;
+                    self.hovered_comment = None;
+                }
+            }
+            PdfMessage::PrintPdf => {
+                let path = self.path.clone();
+                out = iced::Task::perform(
+                    run_blocking(move || {
+                        let file_url = format!("file://{}", path.to_string_lossy());
+                        webbrowser::open(&file_url).map_err(|e| {
+                            MiroError::external(format!(
+                                "Couldn't open the pdf in the default browser: {e}"
+                            ))
+                        })
+                    }),
+                    PdfMessage::from_result,
+                );
+            }
+            PdfMessage::SharePage => {
+                let (page_idx, region) = match self.last_selection {
+                    Some((page_idx, region)) => (page_idx, Some(region)),
+                    None => (self.current_page(), None),
+                };
+                let path = export::share_path(&self.name, page_idx);
+                match export::page_to_png(
+                    &self.display_lists[page_idx],
+                    region,
+                    export::SHARE_SCALE,
+                    &path,
+                ) {
+                    Ok(()) => {
+                        out = iced::Task::perform(
+                            async move {
+                                copy_to_clipboard(path.to_string_lossy().to_string()).await?;
+                                run_blocking(move || {
+                                    open::that(&path).map_err(|e| MiroError::file(&path, e))
+                                })
+                                .await
+                            },
+                            PdfMessage::from_result,
+                        );
+                    }
+                    Err(e) => {
+                        out = iced::Task::done(PdfMessage::Error(MiroError::document(format!(
+                            "Couldn't share page {}: {e}",
+                            page_idx + 1
+                        ))));
+                    }
+                }
+            }
+            PdfMessage::PageUp => {
                 let vp = self.viewport.borrow();
                 out = iced::Task::done(PdfMessage::Move(Vector::new(
                     0.0,
@@ -931,35 +2013,7 @@ impl PdfViewer {
             PdfMessage::JumpToSearchResult(idx) => {
                 if let Some(m) = self.search_matches.get(idx) {
                     self.current_search_result = Some(idx);
-                    let page_idx = m.pages.start;
-                    if let Ok(base_translation) = self.layout.translation_for_page(
-                        &self.doc,
-                        self.scale,
-                        self.fractional_scaling,
-                        page_idx,
-                        *self.viewport.borrow(),
-                    ) {
-                        let page_bounds: Rect<f32> = self.display_lists[page_idx].bounds().into();
-                        let page_center = page_bounds.center();
-                        let match_rect = m.rects[0].1;
-                        let match_center = match_rect.center();
-                        // Center vertically.
-                        self.translation.y = base_translation.y + (match_center.y - page_center.y);
-                        // Horizontal: adjust minimally from current pan to keep match visible.
-                        let viewport = *self.viewport.borrow();
-                        let effective_scale = self.scale * self.fractional_scaling;
-                        let half_viewport = viewport.width / (2.0 * effective_scale);
-                        let lower_bound = match_rect.x1.x - page_center.x - half_viewport;
-                        let upper_bound = match_rect.x0.x - page_center.x + half_viewport;
-                        if lower_bound > upper_bound {
-                            // Wider than viewport: center horizontally.
-                            self.translation.x = match_center.x - page_center.x;
-                        } else if self.translation.x < lower_bound {
-                            self.translation.x = lower_bound;
-                        } else if self.translation.x > upper_bound {
-                            self.translation.x = upper_bound;
-                        }
-                    }
+                    self.scroll_to_rect(m.pages.start, m.rects[0].1);
                 }
             }
             PdfMessage::NextSearchResult => {
@@ -990,6 +2044,10 @@ impl PdfViewer {
                 self.search_method = search_method;
                 out = iced::Task::done(PdfMessage::UpdateSearchNeedle(self.needle.clone()))
             }
+            PdfMessage::SetSearchFlags(flags) => {
+                self.search_flags = flags;
+                out = iced::Task::done(PdfMessage::UpdateSearchNeedle(self.needle.clone()))
+            }
             PdfMessage::ToggleSearchMethod => {
                 self.search_method = match self.search_method {
                     SearchMethod::PlainText => SearchMethod::Regex,
@@ -1004,12 +2062,61 @@ impl PdfViewer {
                     self.current_search_result = None;
                 }
             }
-            PdfMessage::None => {}
+            PdfMessage::ShowSearchMatch(needle, method, flags, found) => {
+                self.needle = needle;
+                self.search_method = method;
+                self.search_flags = flags;
+                self.search_generation = self.search_generation.wrapping_add(1);
+                self.show_search_results = true;
+                if let Some(&(page_idx, rect)) = found.rects.first() {
+                    self.scroll_to_rect(page_idx, rect);
+                }
+                out = self.spawn_search_task();
+            }
+            PdfMessage::OccurrencesReady(matches, generation) => {
+                if generation == self.occurrences_generation {
+                    self.occurrences = matches;
+                }
+            }
+            PdfMessage::HideEdgeIndicator(generation) => {
+                if generation == self.edge_indicator_generation {
+                    self.edge_indicator = None;
+                }
+            }
+            PdfMessage::HidePageFlash(generation) => {
+                if generation == self.page_flash_generation {
+                    self.flashed_page = None;
+                }
+            }
+            PdfMessage::HideScrollbars(generation) => {
+                if generation == self.scrollbars_generation {
+                    if self.hovered_scrollbar.is_some()
+                        || matches!(
+                            self.mouse_interaction,
+                            MouseInteraction::DraggingScrollbar(_)
+                        )
+                    {
+                        out = self.show_scrollbars();
+                    } else {
+                        self.scrollbars_visible = false;
+                    }
+                }
+            }
+            PdfMessage::None | PdfMessage::Error(_) => {}
+        }
+        // The hovered link is otherwise only updated when the mouse moves, so it would point at
+        // where a link used to be before the pages were rearranged underneath the cursor
+        if self.layout_key(*self.viewport.borrow()) != layout_before {
+            self.update_hover_state();
+            out = iced::Task::batch([out, self.show_scrollbars()]);
         }
         out
     }
 
     pub fn view(&self) -> iced::Element<'_, PdfMessage> {
+        if let Some(overview) = &self.overview {
+            return overview.view(self.page_thumbnails(), self.current_page());
+        }
         widget::responsive(|size| {
             {
                 let mut viewport = self.viewport.borrow_mut();
@@ -1027,6 +2134,7 @@ impl PdfViewer {
                 .unwrap();
             let viewport_rect =
                 Rect::from_pos_size(Vector::zero(), Vector::new(size.width, size.height));
+            let scrollbars = Scrollbars::new(&rects, size);
 
             let effective_scale = self.scale * self.fractional_scaling;
 
@@ -1045,106 +2153,75 @@ impl PdfViewer {
                 .unwrap()
                 .retain(|idx, _| visible_indices.contains(idx));
 
+            self.collect_prefetched(effective_scale);
+            // Several pages fitting on screen at once, such as in the double page layouts, are
+            // rendered in parallel. Each of them would otherwise be rendered one after another
+            // below.
+            let missing: Vec<RenderJob> = rects
+                .iter()
+                .enumerate()
+                .filter(|(i, r)| {
+                    is_fully_visible(r, &viewport_rect)
+                        && !self.has_full_render(*i, effective_scale)
+                })
+                .map(|(i, _)| self.render_job(i, effective_scale, RenderPriority::Visible))
+                .collect();
+            if missing.len() > 1 {
+                let rendered = self.scheduler.borrow_mut().render_now(missing);
+                let mut prefetched = self.prefetched.borrow_mut();
+                for page in rendered {
+                    prefetched.insert(
+                        RenderKey::Full(page.page_idx, page.scale.to_bits()),
+                        image::Handle::from_rgba(page.width, page.height, page.rgba),
+                    );
+                }
+            }
+
+            // NOTE: Pages which weren't rendered by the scheduler are rendered synchronously right
+            // here, only at the current scale and translation. Partial renders are never queued,
+            // so there is nothing to cancel when zooming quickly. Stale results are dropped from
+            // the render cache below once their keys go unused.
             let mut used_keys = vec![];
             let with_handles: Vec<_> = rects
-                .into_iter()
-                .zip(self.doc.pages().unwrap())
+                .iter()
+                .copied()
                 .enumerate()
-                .filter(|(_, (r, _page))| viewport_rect.intersects(r))
-                .map(|(i, (rect_ss, page))| {
+                .filter(|(_, r)| viewport_rect.intersects(r))
+                .map(|(i, rect_ss)| {
                     // rect_ss = A pages bounding box in screen coordinates (relative to the widgets origin)
-                    let page = page.unwrap();
-                    let page_bounds: Rect<f32> = page.bounds().unwrap().into();
-
-                    let fully_visible = rect_ss.x0.x >= 0.0
-                        && rect_ss.x1.x <= viewport_rect.x1.x
-                        && rect_ss.x0.y >= 0.0
-                        && rect_ss.x1.y <= viewport_rect.x1.y;
-
-                    let (key, draw_rect, w, h, matrix, scissor) = if fully_visible {
-                        let key = RenderKey::Full(i, effective_scale.to_bits());
-                        let w = rect_ss.width().ceil().max(1.0) as i32;
-                        let h = rect_ss.height().ceil().max(1.0) as i32;
-                        let matrix =
-                            Matrix::new(effective_scale, 0.0, 0.0, effective_scale, 0.0, 0.0);
-                        let scissor =
-                            mupdf::Rect::new(0.0, 0.0, page_bounds.width(), page_bounds.height());
-                        (key, rect_ss, w, h, matrix, scissor)
-                    } else {
-                        let vis = rect_ss.intersect(&viewport_rect);
-                        let vw = vis.width().ceil().max(1.0) as i32;
-                        let vh = vis.height().ceil().max(1.0) as i32;
-
-                        let render_offset_x = rect_ss.x0.x - vis.x0.x;
-                        let render_offset_y = rect_ss.x0.y - vis.x0.y;
-
-                        let key = RenderKey::Partial(i, effective_scale.to_bits(), vw, vh);
+                    let page_bounds: Rect<f32> = self.display_lists[i].bounds().into();
 
-                        let raster_tx = render_offset_x - page_bounds.x0.x * effective_scale;
-                        let raster_ty = render_offset_y - page_bounds.x0.y * effective_scale;
-
-                        let matrix = Matrix::new(
-                            effective_scale,
-                            0.0,
-                            0.0,
-                            effective_scale,
-                            raster_tx.round(),
-                            raster_ty.round(),
-                        );
-
-                        // NOTE: Controls what part of the pdf page is rendered, in what
-                        // coordinates? It "moves" along when I pan, thus it is NOT anchored to the
-                        // document but rather to the pixmap itself. The units are pixels, even
-                        // though they are floating point numbers. Thus the scissor area is
-                        // expressed entirely in pixmap coordinates.
-                        //
-                        // NOTE: What makes this more confusing is that a scissored render can still
-                        // draw outside of the scissored region. Any object in the pdf that is
-                        // within the scissored region will be rendered in its entirety. Its like a
-                        // crude frustrum cull
-                        //
-                        // NOTE: We want to draw the entire pixmap everytime, thus this is just the
-                        // pixmaps size.
-                        let scissor = mupdf::Rect::new(0.0, 0.0, vw as f32, vh as f32);
-
-                        (key, vis, vw, vh, matrix, scissor)
-                    };
+                    let target =
+                        DrawTarget::new(i, rect_ss, &viewport_rect, page_bounds, effective_scale);
+                    let key = target.key;
 
-                    // Try to reuse a pixmap allocation for this page.
-                    let mut pix = {
-                        let mut pool = self.pixmap_pool.borrow_mut();
-                        pool.remove(&i)
-                    };
-                    match pix {
-                        Some(_) => {}
-                        None => {
-                            let mut new_pix =
-                                Pixmap::new_with_w_h(&Colorspace::device_rgb(), w, h, true)
-                                    .unwrap();
-                            self.run(&mut new_pix, i, &matrix, scissor, key);
-                            pix = Some(new_pix);
+                    if matches!(key, RenderKey::Full(_, _)) {
+                        let cached = self.render_cache.borrow().get(&key).cloned();
+                        if let Some(handle) =
+                            cached.or_else(|| self.prefetched.borrow_mut().remove(&key))
+                        {
+                            self.render_cache.borrow_mut().insert(key, handle.clone());
+                            used_keys.push(key);
+                            return (handle, target.rect);
                         }
                     }
-                    let mut pix = pix.unwrap();
-
-                    // If the pooled pixmap has the wrong size, allocate a new one.
-                    if pix.width() as i32 != w || pix.height() as i32 != h {
-                        let _span = tracy_client::span!("Pixmap bounds mismatch");
-                        pix = Pixmap::new_with_w_h(&Colorspace::device_rgb(), w, h, true).unwrap();
 
-                        if matches!(key, RenderKey::Full(_, _)) {
-                            self.run(&mut pix, i, &matrix, scissor, key);
+                    // Reuse the pixmap allocation of this page if it is large enough. Only its
+                    // top left w x h pixels are drawn to, so resizing the window doesn't
+                    // reallocate it on every frame.
+                    let pooled = self.pixmap_pool.borrow_mut().remove(&i);
+                    let (w, h) = (target.width, target.height);
+                    let mut pix = match pooled {
+                        Some(pix) if pixmap_fits(&pix, w, h) => pix,
+                        pooled => {
+                            let _span = tracy_client::span!("Pixmap allocation");
+                            let (pw, ph) = pixmap_size(pooled.as_ref(), w, h);
+                            Pixmap::new_with_w_h(&Colorspace::device_rgb(), pw, ph, true).unwrap()
                         }
-                    }
-                    if matches!(key, RenderKey::Partial(_, _, _, _)) {
-                        self.run(&mut pix, i, &matrix, scissor, key);
-                    }
-                    // NOTE: I am not 100% sure how the key can be missing at this point, but it can
-                    // happen. This is a fallback filling of the cache in case that happens.
-                    let is_cache_missing = { !self.render_cache.borrow_mut().contains_key(&key) };
-                    if is_cache_missing {
-                        self.run(&mut pix, i, &matrix, scissor, key);
-                    }
+                    };
+                    // Full renders that are cached returned above and partial renders depend
+                    // on the translation which isn't part of their key, so both are drawn here
+                    self.run(&mut pix, i, &target);
 
                     {
                         let mut pool = self.pixmap_pool.borrow_mut();
@@ -1153,14 +2230,22 @@ impl PdfViewer {
 
                     used_keys.push(key);
                     let cache = self.render_cache.borrow_mut();
-                    (cache[&key].clone(), draw_rect)
+                    (cache[&key].clone(), target.rect)
                 })
                 .collect();
+            let failed: Vec<_> = {
+                let render_errors = self.render_errors.borrow();
+                visible_indices
+                    .iter()
+                    .filter_map(|i| Some((rects[*i], render_errors.get(i)?.clone())))
+                    .collect()
+            };
 
             {
                 let mut cache = self.render_cache.borrow_mut();
                 cache.retain(|key, _| used_keys.contains(key));
             }
+            self.prefetch_adjacent(&visible_indices, effective_scale);
 
             {
                 let render_cache = self.render_cache.borrow();
@@ -1173,6 +2258,7 @@ impl PdfViewer {
             let pages_canvas = widget::canvas(Document::new(
                 &self.allocation_cache,
                 with_handles,
+                failed,
                 self.draw_page_borders,
                 self.pdf_dark_mode,
             ))
@@ -1196,6 +2282,34 @@ impl PdfViewer {
             if let Some(popup) = self.build_comment_popup(size) {
                 stack_children.push(popup);
             }
+            if self.script_notice {
+                stack_children.push(script_notice_view());
+            }
+            if let Some(edge) = self.edge_indicator {
+                stack_children.push(edge_indicator_view(edge));
+            }
+            if self.scrollbars_visible {
+                let active = match self.mouse_interaction {
+                    MouseInteraction::DraggingScrollbar(axis) => Some(axis),
+                    _ => self.hovered_scrollbar,
+                };
+                stack_children.push(
+                    widget::canvas(ScrollbarOverlay { scrollbars, active })
+                        .width(iced::Length::Fill)
+                        .height(iced::Length::Fill)
+                        .into(),
+                );
+            }
+            if self.presentation_pointer != PresentationPointer::Off {
+                stack_children.push(
+                    widget::canvas(PointerOverlay {
+                        pointer: self.presentation_pointer,
+                    })
+                    .width(iced::Length::Fill)
+                    .height(iced::Length::Fill)
+                    .into(),
+                );
+            }
 
             widget::Stack::with_children(stack_children)
                 .width(iced::Length::Fill)
@@ -1205,197 +2319,655 @@ impl PdfViewer {
         .into()
     }
 
-    fn run(
-        &self,
-        pix: &mut Pixmap,
-        i: usize,
-        matrix: &Matrix,
-        scissor: mupdf::Rect,
-        key: RenderKey,
-    ) -> image::Handle {
+    /// Draws page `i` into the top left pixels of `pix` and caches them under the key of `target`.
+    fn run(&self, pix: &mut Pixmap, i: usize, target: &DrawTarget) -> image::Handle {
         let _span = tracy_client::span!("run");
-        pix.samples_mut().fill(255);
-        let device = Device::from_pixmap(pix).unwrap();
-        self.display_lists[i].run(&device, matrix, scissor).unwrap();
-        if self.pdf_dark_mode {
-            cpu_pdf_dark_mode_shader(pix, &self.gradient_cache);
-        }
-        let mut cache = self.render_cache.borrow_mut();
-        let samples = pix.samples();
-
-        // NOTE: We have to copy the data at least once since the mupdf structures
-        // NOTE: and their associated data aren't thread safe. Iced could render
-        // NOTE: them on any thread without my control
-
         // Try to reuse a CPU buffer from the shared pool.
-        let mut buf = self
+        let buf = self
             .buffer_pool
             .lock()
             .unwrap()
             .remove(&i)
             .and_then(|mut v| v.pop())
-            .unwrap_or_else(|| Vec::with_capacity(samples.len()));
-        buf.clear();
-        buf.extend_from_slice(samples);
+            .unwrap_or_default();
+        let buf = self.draw_pdf_to_pixmap(pix, i, target, buf);
 
         let handle = image::Handle::from_rgba(
-            pix.width(),
-            pix.height(),
+            target.width as u32,
+            target.height as u32,
             Bytes::from_owner(PooledBuffer {
                 buf: Some(buf),
                 pool: Arc::downgrade(&self.buffer_pool),
                 page_idx: i,
             }),
         );
-        cache.insert(key, handle.clone());
+        self.render_cache
+            .borrow_mut()
+            .insert(target.key, handle.clone());
 
         handle
     }
 
-    fn build_comment_popup(
+    /// Draws page `i` into the top left pixels of `pix` as described by `target` and copies them
+    /// into `buf` as rgba, in the colors of the dark mode if it is on. Failures are recorded in
+    /// [PdfViewer::render_errors].
+    fn draw_pdf_to_pixmap(
         &self,
-        viewport_size: iced::Size,
-    ) -> Option<iced::Element<'_, PdfMessage>> {
-        let active_idx = self.active_comment?;
-        let comment_visible = self.visible_comments(viewport_size);
-        let (_, comment_rect) = comment_visible.iter().find(|(idx, _)| *idx == active_idx)?;
-
-        let popup_width = 280.0_f32.min(viewport_size.width - 16.0).max(120.0);
-        let popup_x = comment_rect.x1.x + 8.0;
-        let popup_y = comment_rect.x0.y;
-        let clamped_x = popup_x
-            .min(viewport_size.width - popup_width - 8.0)
-            .max(8.0);
-        let clamped_y = popup_y.min(viewport_size.height - 100.0).max(8.0);
-
-        let author_font = iced::Font {
-            style: iced::font::Style::Italic,
-            ..Default::default()
+        pix: &mut Pixmap,
+        i: usize,
+        target: &DrawTarget,
+        mut buf: Vec<u8>,
+    ) -> Vec<u8> {
+        let list = &self.display_lists[i];
+        let recolored = self.pdf_dark_mode
+            && self.recolor_pdf
+            && !recolor::is_scan(list.bounds(), |device| {
+                list.run(device, &Matrix::IDENTITY, mupdf::Rect::INF)
+            })
+            .unwrap_or(true);
+        let rendered = if recolored {
+            recolor::draw_recolored(pix, &self.gradient_cache, |device| {
+                list.run(device, &target.ctm, target.scissor)
+            })
+        } else {
+            pix.samples_mut().fill(255);
+            Device::from_pixmap(pix)
+                .and_then(|device| list.run(&device, &target.ctm, target.scissor))
+                .map_err(Into::into)
         };
+        if let Err(e) = rendered {
+            self.render_errors.borrow_mut().insert(i, e.to_string());
+        }
+        let row_len = target.width as usize * 4;
+        let samples = pix.samples();
+        let stride = pix.stride() as usize;
 
-        let popup = widget::container(
-            widget::column![
-                widget::row![
-                    widget::text(self.comments[active_idx].author.clone().unwrap_or_default())
-                        .font(author_font)
-                        .style(|theme: &iced::Theme| {
-                            let palette = theme.extended_palette();
-                            iced::widget::text::Style {
-                                color: Some(palette.primary.base.color),
-                            }
-                        }),
-                    widget::space::horizontal().width(iced::Length::Fill),
-                    widget::button(
-                        widget::text("×")
-                            .align_y(iced::alignment::Vertical::Bottom)
-                            .size(24.0),
-                    )
-                    .padding(0.0)
-                    .style(|theme: &iced::Theme, status: widget::button::Status| {
-                        let palette = theme.extended_palette();
-                        let base = widget::button::Style {
-                            background: Some(iced::Background::Color(
-                                palette.background.strong.color,
-                            )),
-                            text_color: palette.background.strong.text,
-                            border: iced::border::rounded(2),
-                            ..widget::button::Style::default()
-                        };
-                        match status {
-                            widget::button::Status::Active
-                            | widget::button::Status::Pressed
-                            | widget::button::Status::Hovered => widget::button::Style {
-                                background: None,
-                                text_color: palette.background.base.text,
-                                ..base
-                            },
-                            widget::button::Status::Disabled => widget::button::Style {
-                                background: base.background.map(|bg| bg.scale_alpha(0.5)),
-                                text_color: base.text_color.scale_alpha(0.5),
-                                ..base
-                            },
-                        }
-                    })
-                    .on_press(PdfMessage::CloseComment),
-                ]
-                .align_y(iced::alignment::Vertical::Center),
-                widget::text(&self.comments[active_idx].content)
-                    .size(14.0)
-                    .wrapping(widget::text::Wrapping::Word),
-            ]
-            .spacing(8.0),
-        )
-        .width(popup_width)
-        .padding(12.0)
-        .style(|theme: &iced::Theme| widget::container::Style {
-            background: Some(theme.extended_palette().background.weak.color.into()),
-            border: iced::Border {
-                color: theme.extended_palette().primary.base.color,
-                width: 2.0,
-                radius: iced::border::Radius::from(8.0),
-            },
-            shadow: iced::Shadow {
-                color: theme.extended_palette().primary.base.color,
-                offset: iced::Vector { x: 0.0, y: 2.0 },
-                blur_radius: 4.0,
-            },
-            ..Default::default()
-        });
-
-        let positioned = widget::container(widget::mouse_area(popup).on_press(PdfMessage::None))
-            .width(iced::Length::Fill)
-            .height(iced::Length::Fill)
-            .padding(iced::Padding::new(0.0).top(clamped_y).left(clamped_x))
-            .align_x(iced::alignment::Horizontal::Left)
-            .align_y(iced::alignment::Vertical::Top);
-
-        Some(positioned.into())
+        // NOTE: We have to copy the data at least once since the mupdf structures
+        // NOTE: and their associated data aren't thread safe. Iced could render
+        // NOTE: them on any thread without my control
+        buf.clear();
+        buf.reserve(row_len * target.height as usize);
+        for row in samples.chunks(stride).take(target.height as usize) {
+            buf.extend_from_slice(&row[..row_len]);
+        }
+        if self.pdf_dark_mode && !recolored {
+            cpu_pdf_dark_mode_shader(&mut buf, &self.gradient_cache);
+        }
+        buf
     }
 
-    #[allow(clippy::type_complexity)]
-    /// Returns (search haystack, Vec<(page number, byte offset, bounding box)>)
-    fn extract_search_data(
+    /// Draws page `page_idx` like [PdfViewer::view] does in a viewport of `size`, returning the
+    /// size and rgba pixels of the part of it on screen.
+    #[cfg(any(test, feature = "bench"))]
+    pub(crate) fn draw_visible_page(
+        &self,
+        page_idx: usize,
+        size: iced::Size,
+    ) -> Result<(u32, u32, Vec<u8>)> {
+        let rects = self.layout.pages_rects(
+            self.doc.pages()?,
+            self.translation.scaled(-1.0),
+            self.scale,
+            self.fractional_scaling,
+            size,
+        )?;
+        let viewport_rect =
+            Rect::from_pos_size(Vector::zero(), Vector::new(size.width, size.height));
+        let rect_ss = rects
+            .get(page_idx)
+            .filter(|rect| viewport_rect.intersects(rect))
+            .ok_or_else(|| anyhow!("Page {} isn't on screen", page_idx + 1))?;
+        let target = DrawTarget::new(
+            page_idx,
+            *rect_ss,
+            &viewport_rect,
+            self.display_lists[page_idx].bounds().into(),
+            self.scale * self.fractional_scaling,
+        );
+        let mut pix =
+            Pixmap::new_with_w_h(&Colorspace::device_rgb(), target.width, target.height, true)?;
+        let rgba = self.draw_pdf_to_pixmap(&mut pix, page_idx, &target, vec![]);
+        Ok((target.width as u32, target.height as u32, rgba))
+    }
+
+    /// Drops every rendered page and its GPU texture. Hidden tabs keep theirs so switching back
+    /// to them shows the pages right away, this is for the ones that haven't been looked at in a
+    /// while.
+    pub fn release_render_caches(&mut self) {
+        self.render_cache.get_mut().clear();
+        self.thumbnails.get_mut().clear();
+        self.prefetched.get_mut().clear();
+        self.allocation_cache.get_mut().clear();
+        self.pixmap_pool.get_mut().clear();
+        self.buffer_pool.lock().unwrap().clear();
+    }
+
+    fn has_full_render(&self, page_idx: usize, scale: f32) -> bool {
+        let key = RenderKey::Full(page_idx, scale.to_bits());
+        self.render_cache.borrow().contains_key(&key) || self.prefetched.borrow().contains_key(&key)
+    }
+
+    fn render_job(&self, page_idx: usize, scale: f32, priority: RenderPriority) -> RenderJob {
+        RenderJob {
+            page_idx,
+            scale,
+            priority,
+            dark_mode: self.pdf_dark_mode.then_some(self.gradient_cache),
+            recolor: self.recolor_pdf,
+        }
+    }
+
+    /// Moves pages finished by the scheduler into [PdfViewer::prefetched], forgetting the ones
+    /// rendered at another scale.
+    fn collect_prefetched(&self, scale: f32) {
+        let finished = self.scheduler.borrow_mut().take_finished();
+        let mut prefetched = self.prefetched.borrow_mut();
+        for page in finished {
+            let failed = page.rgba.is_empty();
+            let handle = image::Handle::from_rgba(page.width, page.height, page.rgba);
+            if page.priority == RenderPriority::Thumbnail {
+                // Thumbnails that were cleared while they were rendered are dropped
+                if let Some(thumbnail) = self.thumbnails.borrow_mut().get_mut(&page.page_idx) {
+                    *thumbnail = if failed {
+                        Thumbnail::Failed
+                    } else {
+                        Thumbnail::Rendered(handle)
+                    };
+                }
+            } else if !failed {
+                prefetched.insert(RenderKey::Full(page.page_idx, page.scale.to_bits()), handle);
+            }
+        }
+        prefetched.retain(|key, _| matches!(key, RenderKey::Full(_, s) if *s == scale.to_bits()));
+    }
+
+    /// Every page for the page grid, with the thumbnails rendered so far. The missing ones are
+    /// queued for the workers, or rendered right here a few at a time if they can't render this
+    /// document.
+    fn page_thumbnails(&self) -> Vec<PageThumbnail> {
+        self.collect_prefetched(self.scale * self.fractional_scaling);
+        let thumbnail_scale =
+            |bounds: mupdf::Rect| overview::THUMBNAIL_WIDTH / (bounds.x1 - bounds.x0);
+        let missing: Vec<usize> = {
+            let thumbnails = self.thumbnails.borrow();
+            (0..self.display_lists.len())
+                .filter(|i| !thumbnails.contains_key(i))
+                .collect()
+        };
+        let scheduler = self.scheduler.borrow();
+        if scheduler.can_render() {
+            let jobs = missing
+                .iter()
+                .map(|&i| {
+                    self.thumbnails.borrow_mut().insert(i, Thumbnail::Queued);
+                    let scale = thumbnail_scale(self.display_lists[i].bounds());
+                    self.render_job(i, scale, RenderPriority::Thumbnail)
+                })
+                .collect();
+            scheduler.add(jobs);
+        } else {
+            for &i in missing.iter().take(SYNC_THUMBNAILS_PER_FRAME) {
+                let scale = thumbnail_scale(self.display_lists[i].bounds());
+                let thumbnail = match self.render_thumbnail(i, scale) {
+                    Ok(handle) => Thumbnail::Rendered(handle),
+                    Err(_) => Thumbnail::Failed,
+                };
+                self.thumbnails.borrow_mut().insert(i, thumbnail);
+            }
+        }
+
+        let thumbnails = self.thumbnails.borrow();
+        self.display_lists
+            .iter()
+            .enumerate()
+            .map(|(i, list)| {
+                let bounds = list.bounds();
+                PageThumbnail {
+                    handle: match thumbnails.get(&i) {
+                        Some(Thumbnail::Rendered(handle)) => Some(handle.clone()),
+                        _ => None,
+                    },
+                    aspect_ratio: (bounds.y1 - bounds.y0) / (bounds.x1 - bounds.x0).max(1.0),
+                }
+            })
+            .collect()
+    }
+
+    fn render_thumbnail(&self, page_idx: usize, scale: f32) -> Result<image::Handle> {
+        let job = self.render_job(page_idx, scale, RenderPriority::Thumbnail);
+        let (width, height, pixels) = scheduler::render_page(self.backend.as_ref(), &job)?;
+        Ok(image::Handle::from_rgba(width, height, pixels))
+    }
+
+    /// The page grid is open and still waiting for some of its thumbnails.
+    pub fn is_loading_thumbnails(&self) -> bool {
+        let thumbnails = self.thumbnails.borrow();
+        self.overview.is_some()
+            && (thumbnails.len() < self.display_lists.len()
+                || (self.scheduler.borrow().can_render()
+                    && thumbnails
+                        .values()
+                        .any(|thumbnail| matches!(thumbnail, Thumbnail::Queued))))
+    }
+
+    /// Queues the spreads before and after the visible pages for rendering in the background, so
+    /// flipping to them doesn't have to wait for a render.
+    fn prefetch_adjacent(&self, visible: &[usize], scale: f32) {
+        let (Some(&first), Some(&last)) = (visible.first(), visible.last()) else {
+            return;
+        };
+        let spreads = SpreadLayout::new(&self.layout, self.display_lists.len());
+        let before = match spreads.spread_of(first) {
+            0 => 0..0,
+            spread => spreads.pages_in(spread - 1),
+        };
+        let after = spreads.pages_in(spreads.spread_of(last) + 1);
+        let jobs = before
+            .chain(after)
+            .filter(|i| !visible.contains(i) && !self.has_full_render(*i, scale))
+            .filter(|i| {
+                let bounds = self.display_lists[*i].bounds();
+                let pixels = (bounds.x1 - bounds.x0) * (bounds.y1 - bounds.y0) * scale * scale;
+                pixels <= MAX_PREFETCH_PIXELS
+            })
+            .map(|i| self.render_job(i, scale, RenderPriority::Adjacent))
+            .collect();
+        self.scheduler
+            .borrow()
+            .schedule(RenderPriority::Adjacent, jobs);
+    }
+
+    fn show_edge_indicator(&mut self, edge: DocumentEdge) -> iced::Task<PdfMessage> {
+        self.edge_indicator = Some(edge);
+        self.edge_indicator_generation = self.edge_indicator_generation.wrapping_add(1);
+        let generation = self.edge_indicator_generation;
+        iced::Task::perform(tokio::time::sleep(EDGE_INDICATOR_DURATION), move |_| {
+            PdfMessage::HideEdgeIndicator(generation)
+        })
+    }
+
+    fn flash_page(&mut self, page_idx: usize) -> iced::Task<PdfMessage> {
+        self.flashed_page = Some(page_idx);
+        self.page_flash_generation = self.page_flash_generation.wrapping_add(1);
+        let generation = self.page_flash_generation;
+        iced::Task::perform(tokio::time::sleep(PAGE_FLASH_DURATION), move |_| {
+            PdfMessage::HidePageFlash(generation)
+        })
+    }
+
+    /// The text of a page with a line break wherever a new line starts.
+    pub fn page_text(&self, page_idx: usize) -> String {
+        let start = self
+            .char_bboxes
+            .partition_point(|(page, _, _)| *page < page_idx);
+        let end = self
+            .char_bboxes
+            .partition_point(|(page, _, _)| *page <= page_idx);
+        if start == end {
+            return String::new();
+        }
+        caret::text_in_range(&self.text_contents, &self.char_bboxes, start..=end - 1)
+    }
+
+    /// The DOI or arXiv id printed on the first pages, see [identifiers::find].
+    pub fn identifier(&self) -> Option<Identifier> {
+        let text: String = (0..identifiers::SEARCHED_PAGES)
+            .map(|page_idx| self.page_text(page_idx))
+            .collect::<Vec<_>>()
+            .join("\n");
+        identifiers::find(&text)
+    }
+
+    /// The text of every page, separated by form feeds like `pdftotext` does.
+    pub fn document_text(&self) -> String {
+        (0..self.display_lists.len())
+            .map(|page_idx| self.page_text(page_idx))
+            .collect::<Vec<_>>()
+            .join("\n\x0c")
+    }
+
+    fn zoom_to_preset(&mut self, preset: ZoomPreset) {
+        let page_idx = self.current_page();
+        let viewport = *self.viewport.borrow();
+        let Some(display_list) = self.display_lists.get(page_idx) else {
+            return;
+        };
+        let page_bounds = display_list.bounds();
+        let page_width = page_bounds.x1 - page_bounds.x0;
+        let page_height = page_bounds.y1 - page_bounds.y0;
+        if page_width <= 0.0 || page_height <= 0.0 || viewport.width <= 0.0 {
+            return;
+        }
+        let scale_x = viewport.width / page_width / self.fractional_scaling;
+        let scale_y = viewport.height / page_height / self.fractional_scaling;
+        // Pages without text are fit to their width instead
+        let column = match preset {
+            ZoomPreset::FitColumn => self.column_in_view(page_idx),
+            _ => None,
+        };
+        self.scale = match (preset, column) {
+            (ZoomPreset::Scale(scale), _) => scale,
+            (ZoomPreset::Fit, _) if viewport.height > 0.0 => scale_x.min(scale_y),
+            (ZoomPreset::Fit, _) => return,
+            (ZoomPreset::FitColumn, Some((x0, x1))) => {
+                (viewport.width - 2.0 * BLOCK_MARGIN).max(1.0)
+                    / (x1 - x0).max(1.0)
+                    / self.fractional_scaling
+            }
+            (ZoomPreset::FitWidth | ZoomPreset::FitColumn, _) => scale_x,
+        };
+        let Ok(translation) = self.layout.translation_for_page(
+            &self.doc,
+            self.scale,
+            self.fractional_scaling,
+            page_idx,
+            viewport,
+        ) else {
+            return;
+        };
+        match (preset, column) {
+            (ZoomPreset::Fit, _) => self.translation = translation,
+            (ZoomPreset::FitColumn, Some((x0, x1))) => {
+                let page_center = (page_bounds.x0 + page_bounds.x1) / 2.0;
+                self.translation.x = translation.x + (x0 + x1) / 2.0 - page_center;
+            }
+            // Zooming keeps the center of the screen in place, so only the page has to be centered
+            // horizontally to keep reading where the view was
+            (ZoomPreset::FitWidth | ZoomPreset::FitColumn, _) => self.translation.x = translation.x,
+            (ZoomPreset::Scale(_), _) => {}
+        }
+    }
+
+    /// The column of text of the page at `page_idx` closest to the middle of the screen, as its
+    /// horizontal range in page space. See [text_layout::columns].
+    fn column_in_view(&self, page_idx: usize) -> Option<(f32, f32)> {
+        let viewport = *self.viewport.borrow();
+        let page: Rect<f32> = self.display_lists.get(page_idx)?.bounds().into();
+        let blocks = self.backend.page_blocks(page_idx).ok()?;
+        let middle = self
+            .page_mapping(viewport)
+            .and_then(|mapping| {
+                mapping.screen_point_to_page(
+                    page_idx,
+                    Vector::new(viewport.width / 2.0, viewport.height / 2.0),
+                )
+            })
+            .map_or(page.center().x, |point| point.x);
+        let distance = |(x0, x1): (f32, f32)| (x0 - middle).max(middle - x1).max(0.0);
+        text_layout::columns(&blocks, page)
+            .into_iter()
+            .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+    }
+
+    /// Zooms and pans so `region` of the page at `page_idx`, in page space, fills the viewport.
+    fn zoom_to_region(&mut self, page_idx: usize, region: Rect<f32>) {
+        let viewport = *self.viewport.borrow();
+        let Some(display_list) = self.display_lists.get(page_idx) else {
+            return;
+        };
+        if region.width() <= 0.0 || region.height() <= 0.0 || viewport.width <= 0.0 {
+            return;
+        }
+        let page_bounds: Rect<f32> = display_list.bounds().into();
+        self.scale = (viewport.width / region.width()).min(viewport.height / region.height())
+            / self.fractional_scaling;
+        let Ok(translation) = self.layout.translation_for_page(
+            &self.doc,
+            self.scale,
+            self.fractional_scaling,
+            page_idx,
+            viewport,
+        ) else {
+            return;
+        };
+        // The translation is in page units, so the region is centered by moving from the center of
+        // the page to the center of the region
+        self.translation = translation + (region.center() - page_bounds.center());
+    }
+
+    fn show_scrollbars(&mut self) -> iced::Task<PdfMessage> {
+        if !CONFIG.read().unwrap().scrollbars {
+            return iced::Task::none();
+        }
+        self.scrollbars_visible = true;
+        self.scrollbars_generation = self.scrollbars_generation.wrapping_add(1);
+        let generation = self.scrollbars_generation;
+        iced::Task::perform(tokio::time::sleep(scrollbar::HIDE_DELAY), move |_| {
+            PdfMessage::HideScrollbars(generation)
+        })
+    }
+
+    fn scrollbars(&self, viewport: Size<f32>) -> Scrollbars {
+        let rects = self.doc.pages().ok().and_then(|pages| {
+            self.layout
+                .pages_rects(
+                    pages,
+                    self.translation.scaled(-1.0),
+                    self.scale,
+                    self.fractional_scaling,
+                    viewport,
+                )
+                .ok()
+        });
+        Scrollbars::new(&rects.unwrap_or_default(), viewport)
+    }
+
+    /// The scrollbar under `point` in widget space, if the scrollbars are enabled.
+    fn scrollbar_at(&self, point: Vector<f32>) -> Option<Axis> {
+        let viewport = *self.viewport.borrow();
+        if !CONFIG.read().unwrap().scrollbars || !Scrollbars::near_edge(point, viewport) {
+            return None;
+        }
+        self.scrollbars(viewport).hit(point)
+    }
+
+    /// The translation moving the thumb of the scrollbar along `axis` by `distance` pixels.
+    fn scrollbar_drag(&self, axis: Axis, distance: f32) -> Vector<f32> {
+        let viewport = *self.viewport.borrow();
+        let Some(thumb) = self.scrollbars(viewport).thumb(axis) else {
+            return Vector::zero();
+        };
+        axis.vector(distance * thumb.content_per_pixel / (self.scale * self.fractional_scaling))
+    }
+
+    fn build_comment_popup(
+        &self,
+        viewport_size: iced::Size,
+    ) -> Option<iced::Element<'_, PdfMessage>> {
+        let active_idx = self.active_comment?;
+        let comment_visible = self.visible_comments(viewport_size);
+        let (_, comment_rect) = comment_visible.iter().find(|(idx, _)| *idx == active_idx)?;
+
+        let popup_width = 280.0_f32.min(viewport_size.width - 16.0).max(120.0);
+        let popup_x = comment_rect.x1.x + 8.0;
+        let popup_y = comment_rect.x0.y;
+        let clamped_x = popup_x
+            .min(viewport_size.width - popup_width - 8.0)
+            .max(8.0);
+        let clamped_y = popup_y.min(viewport_size.height - 100.0).max(8.0);
+
+        let author_font = iced::Font {
+            style: iced::font::Style::Italic,
+            ..Default::default()
+        };
+
+        let popup = widget::container(
+            widget::column![
+                widget::row![
+                    widget::text(self.comments[active_idx].author.clone().unwrap_or_default())
+                        .font(author_font)
+                        .style(|theme: &iced::Theme| {
+                            let palette = theme.extended_palette();
+                            iced::widget::text::Style {
+                                color: Some(palette.primary.base.color),
+                            }
+                        }),
+                    widget::space::horizontal().width(iced::Length::Fill),
+                    widget::button(
+                        widget::text("×")
+                            .align_y(iced::alignment::Vertical::Bottom)
+                            .size(24.0),
+                    )
+                    .padding(0.0)
+                    .style(|theme: &iced::Theme, status: widget::button::Status| {
+                        let palette = theme.extended_palette();
+                        let base = widget::button::Style {
+                            background: Some(iced::Background::Color(
+                                palette.background.strong.color,
+                            )),
+                            text_color: palette.background.strong.text,
+                            border: iced::border::rounded(2),
+                            ..widget::button::Style::default()
+                        };
+                        match status {
+                            widget::button::Status::Active
+                            | widget::button::Status::Pressed
+                            | widget::button::Status::Hovered => widget::button::Style {
+                                background: None,
+                                text_color: palette.background.base.text,
+                                ..base
+                            },
+                            widget::button::Status::Disabled => widget::button::Style {
+                                background: base.background.map(|bg| bg.scale_alpha(0.5)),
+                                text_color: base.text_color.scale_alpha(0.5),
+                                ..base
+                            },
+                        }
+                    })
+                    .on_press(PdfMessage::CloseComment),
+                ]
+                .align_y(iced::alignment::Vertical::Center),
+                widget::text(&self.comments[active_idx].content)
+                    .size(14.0)
+                    .wrapping(widget::text::Wrapping::Word),
+            ]
+            .spacing(8.0),
+        )
+        .width(popup_width)
+        .padding(12.0)
+        .style(|theme: &iced::Theme| widget::container::Style {
+            background: Some(theme.extended_palette().background.weak.color.into()),
+            border: iced::Border {
+                color: theme.extended_palette().primary.base.color,
+                width: 2.0,
+                radius: iced::border::Radius::from(8.0),
+            },
+            shadow: iced::Shadow {
+                color: theme.extended_palette().primary.base.color,
+                offset: iced::Vector { x: 0.0, y: 2.0 },
+                blur_radius: 4.0,
+            },
+            ..Default::default()
+        });
+
+        let positioned = widget::container(widget::mouse_area(popup).on_press(PdfMessage::None))
+            .width(iced::Length::Fill)
+            .height(iced::Length::Fill)
+            .padding(iced::Padding::new(0.0).top(clamped_y).left(clamped_x))
+            .align_x(iced::alignment::Horizontal::Left)
+            .align_y(iced::alignment::Vertical::Top);
+
+        Some(positioned.into())
+    }
+
+    #[allow(clippy::type_complexity)]
+    /// Returns (search haystack, Vec<(page number, byte offset, bounding box)>). Pages whose text
+    /// can't be read are left out and added to `render_errors`.
+    fn extract_search_data(
         display_lists: &[mupdf::DisplayList],
-    ) -> Result<(String, Vec<(usize, usize, Rect<f32>)>)> {
+        render_errors: &mut HashMap<usize, String>,
+    ) -> (String, Vec<(usize, usize, Rect<f32>)>) {
         let _span = tracy_client::span!("Preparing search data");
         let mut all_text = String::new();
         let mut bounding_boxes = vec![];
-        for (page_idx, dl) in display_lists.iter().enumerate() {
-            let tp = dl.to_text_page(TextPageFlags::empty())?;
-            for block in tp.blocks() {
-                for line in block.lines() {
-                    for char in line.chars() {
-                        if let Some(c) = char.char() {
-                            let byte_offset = all_text.len();
-                            all_text.push(c);
-                            let quad = char.quad();
-                            bounding_boxes.push((
-                                page_idx,
-                                byte_offset,
-                                Rect {
-                                    x0: Vector::new(quad.ul.x, quad.ul.y),
-                                    x1: Vector::new(quad.lr.x, quad.lr.y),
-                                },
-                            ));
-                        }
-                    }
+        for (page_idx, list) in display_lists.iter().enumerate() {
+            let text_page = match list.to_text_page(TextPageFlags::empty()) {
+                Ok(text_page) => text_page,
+                Err(e) => {
+                    error!("Couldn't read the text of page {page_idx}: {e}");
+                    render_errors
+                        .entry(page_idx)
+                        .or_insert_with(|| e.to_string());
+                    continue;
                 }
+            };
+            for char in backend::text_chars(&text_page) {
+                bounding_boxes.push((page_idx, all_text.len(), char.bounds));
+                all_text.push(char.c);
             }
         }
-        Ok((all_text, bounding_boxes))
+        (all_text, bounding_boxes)
+    }
+
+    /// The text of the document and where each of its characters is, shared with the tasks
+    /// searching through it.
+    pub fn search_source(&self) -> (Arc<str>, Arc<[(usize, usize, Rect<f32>)]>) {
+        (self.text_contents.clone(), self.char_bboxes.clone())
+    }
+
+    /// Finds every occurrence of the selected text, or hides them if nothing is selected.
+    fn highlight_occurrences(&mut self) -> iced::Task<PdfMessage> {
+        let needle = self.selection_text().trim().to_string();
+        self.clear_occurrences();
+        if needle.is_empty() {
+            return iced::Task::none();
+        }
+        self.occurrences_needle = needle.clone();
+        let text_contents = self.text_contents.clone();
+        let char_bboxes = self.char_bboxes.clone();
+        let generation = self.occurrences_generation;
+
+        iced::Task::perform(
+            tokio::task::spawn_blocking(move || {
+                find_matches(
+                    &text_contents,
+                    &needle,
+                    SearchMethod::PlainText,
+                    SearchFlags::default(),
+                    &char_bboxes,
+                )
+            }),
+            move |result| match result {
+                Ok(matches) => PdfMessage::OccurrencesReady(matches, generation),
+                Err(_) => PdfMessage::None,
+            },
+        )
+    }
+
+    fn clear_occurrences(&mut self) {
+        self.occurrences.clear();
+        self.occurrences_needle.clear();
+        self.occurrences_generation = self.occurrences_generation.wrapping_add(1);
+    }
+
+    /// How many occurrences of the selected text are on screen and in the whole document, while
+    /// they are highlighted.
+    pub fn occurrence_counts(&self) -> Option<(usize, usize)> {
+        if self.occurrences_needle.is_empty() {
+            return None;
+        }
+        let viewport = *self.viewport.borrow();
+        let mut on_screen: Vec<usize> = self
+            .visible_matches(&self.occurrences, viewport)
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect();
+        on_screen.dedup();
+        Some((on_screen.len(), self.occurrences.len()))
     }
 
     fn spawn_search_task(&self) -> iced::Task<PdfMessage> {
         let text_contents = self.text_contents.clone();
         let needle = self.needle.clone();
         let method = self.search_method;
+        let flags = self.search_flags;
         let char_bboxes = self.char_bboxes.clone();
         let generation = self.search_generation;
 
         iced::Task::perform(
             async move {
                 tokio::task::spawn_blocking(move || {
-                    find_search_matches(&text_contents, &needle, method, &char_bboxes)
+                    find_search_matches(&text_contents, &needle, method, flags, &char_bboxes)
                 })
                 .await
             },
@@ -1406,42 +2978,245 @@ impl PdfViewer {
         )
     }
 
-    pub fn extract_text_from_rect(&self, screen_rect: Rect<f32>) -> String {
-        use mupdf::TextPageFlags;
+    /// Where every page is on screen with the current layout, translation and zoom.
+    fn page_mapping(&self, viewport: iced::Size<f32>) -> Option<PageMapping> {
+        let pages = self.doc.pages().ok()?;
+        let rects = self
+            .layout
+            .pages_rects(
+                pages,
+                self.translation.scaled(-1.0),
+                self.scale,
+                self.fractional_scaling,
+                viewport,
+            )
+            .ok()?;
+        let bounds: Vec<Rect<f32>> = self
+            .display_lists
+            .iter()
+            .map(|list| list.bounds().into())
+            .collect();
+        Some(PageMapping::new(rects, bounds))
+    }
 
-        let effective_scale = self.scale * self.fractional_scaling;
+    /// The segments of the current page, see [page_layout::page_segments], along with where the
+    /// viewport is relative to the page.
+    fn current_page_segments(&self) -> Option<(Vec<Vector<f32>>, Vector<f32>)> {
         let viewport = *self.viewport.borrow();
+        let page = self
+            .page_mapping(viewport)?
+            .screen_rect(self.current_page())?;
+        let segments = page_layout::page_segments(page.size(), viewport.into());
+        Some((segments, page.x0.scaled(-1.0)))
+    }
 
-        let Ok(pages) = self.doc.pages() else {
-            return String::new();
+    /// Moves the view to the next or previous segment of the current page. Returns false when the
+    /// page fits on screen or there is no segment in that direction, then the page changes.
+    fn step_page_segment(&mut self, forward: bool) -> bool {
+        let Some((segments, offset)) = self.current_page_segments() else {
+            return false;
         };
-        let Ok(rects) = self.layout.pages_rects(
-            pages,
-            self.translation.scaled(-1.0),
-            self.scale,
-            self.fractional_scaling,
-            viewport,
+        let distance = |segment: &Vector<f32>| (*segment - offset).norm_squared();
+        let Some(current) = (0..segments.len())
+            .min_by(|&a, &b| distance(&segments[a]).total_cmp(&distance(&segments[b])))
+        else {
+            return false;
+        };
+        let target = if forward {
+            segments.get(current + 1)
+        } else {
+            current.checked_sub(1).and_then(|i| segments.get(i))
+        };
+        let Some(target) = target else {
+            return false;
+        };
+        self.translation += (*target - offset).scaled(1.0 / (self.scale * self.fractional_scaling));
+        true
+    }
+
+    /// The paragraphs and images of a page in reading order.
+    fn reading_blocks(&self, page_idx: usize) -> Vec<Rect<f32>> {
+        if let Some(blocks) = self.reading_blocks.borrow().get(&page_idx) {
+            return blocks.clone();
+        }
+        let (Some(list), Ok(blocks)) = (
+            self.display_lists.get(page_idx),
+            self.backend.page_blocks(page_idx),
         ) else {
-            return String::new();
+            return vec![];
         };
+        let blocks = text_layout::reading_order(blocks, list.bounds().into());
+        self.reading_blocks
+            .borrow_mut()
+            .insert(page_idx, blocks.clone());
+        blocks
+    }
 
-        let mut result = String::new();
+    /// The first block after `block_idx` on `page_idx`, or on the pages after it, as (page, index
+    /// in reading order). `None` starts from the top of the page.
+    fn block_after(&self, page_idx: usize, block_idx: Option<usize>) -> Option<(usize, usize)> {
+        let next = block_idx.map_or(0, |idx| idx + 1);
+        if next < self.reading_blocks(page_idx).len() {
+            return Some((page_idx, next));
+        }
+        (page_idx + 1..self.display_lists.len())
+            .take(BLOCK_SEARCH_PAGES)
+            .find(|&page| !self.reading_blocks(page).is_empty())
+            .map(|page| (page, 0))
+    }
 
-        for (i, page_rect) in rects.iter().enumerate() {
-            let intersect = screen_rect.intersect(page_rect);
-            if intersect.width() <= 0.0 || intersect.height() <= 0.0 {
-                continue;
+    /// Like [PdfViewer::block_after] in the other direction, `None` starts from the bottom.
+    fn block_before(&self, page_idx: usize, block_idx: Option<usize>) -> Option<(usize, usize)> {
+        let blocks = self.reading_blocks(page_idx).len();
+        match block_idx {
+            Some(idx) if idx > 0 => return Some((page_idx, idx - 1)),
+            None if blocks > 0 => return Some((page_idx, blocks - 1)),
+            _ => {}
+        }
+        (0..page_idx)
+            .rev()
+            .take(BLOCK_SEARCH_PAGES)
+            .find_map(|page| Some((page, self.reading_blocks(page).len().checked_sub(1)?)))
+    }
+
+    /// Moves the view to the next or previous block of text or image in reading order, with its
+    /// top at the top of the screen. Blocks taller than the screen are scrolled through a screen at
+    /// a time first. Returns false when there is no block to move to, then the page changes.
+    fn step_block(&mut self, forward: bool) -> bool {
+        let viewport = *self.viewport.borrow();
+        let effective_scale = self.scale * self.fractional_scaling;
+        let Some(mapping) = self.page_mapping(viewport) else {
+            return false;
+        };
+        let continued = self
+            .current_block
+            .filter(|(_, _, translation)| *translation == self.translation);
+        let target = match continued {
+            Some((page_idx, block_idx, _)) => {
+                if forward
+                    && let Some(block) = self.reading_blocks(page_idx).get(block_idx)
+                    && let Some(screen) = mapping.page_to_screen(page_idx, *block)
+                    && screen.x1.y > viewport.height
+                {
+                    let remaining =
+                        (screen.x1.y - viewport.height + BLOCK_MARGIN) / effective_scale;
+                    self.translation.y +=
+                        remaining.min((viewport.height - 2.0 * BLOCK_MARGIN) / effective_scale);
+                    self.current_block = Some((page_idx, block_idx, self.translation));
+                    return true;
+                }
+                if forward {
+                    self.block_after(page_idx, Some(block_idx))
+                } else {
+                    self.block_before(page_idx, Some(block_idx))
+                }
             }
+            None => {
+                let page_idx = self.current_page();
+                let Some(top) =
+                    mapping.screen_point_to_page(page_idx, Vector::new(viewport.width / 2.0, 0.0))
+                else {
+                    return false;
+                };
+                let blocks = self.reading_blocks(page_idx);
+                // Blocks already at the top of the screen have been reached
+                let top = top.y + BLOCK_MARGIN / effective_scale;
+                if forward {
+                    match blocks.iter().position(|b| b.x0.y > top + 1.0) {
+                        Some(idx) => Some((page_idx, idx)),
+                        None => self.block_after(page_idx, blocks.len().checked_sub(1)),
+                    }
+                } else {
+                    match blocks.iter().rposition(|b| b.x0.y < top - 1.0) {
+                        Some(idx) => Some((page_idx, idx)),
+                        None => self.block_before(page_idx, Some(0)),
+                    }
+                }
+            }
+        };
+        let Some((page_idx, block_idx)) = target else {
+            return false;
+        };
+        let Some(&block) = self.reading_blocks(page_idx).get(block_idx) else {
+            return false;
+        };
+        // Panning to the whole column when it fits keeps a view fit to one column on the column
+        // being read, moving on to the next one together with the text
+        let column = self.display_lists.get(page_idx).and_then(|list| {
+            let blocks = self.backend.page_blocks(page_idx).ok()?;
+            text_layout::columns(&blocks, list.bounds().into())
+                .into_iter()
+                .find(|&(x0, x1)| x0 <= block.x0.x && block.x1.x <= x1)
+        });
+        match column {
+            Some((x0, x1)) if (x1 - x0) * effective_scale <= viewport.width => self.scroll_to_rect(
+                page_idx,
+                Rect::from_points(Vector::new(x0, block.x0.y), Vector::new(x1, block.x1.y)),
+            ),
+            _ => self.scroll_to_rect(page_idx, block),
+        }
+        // Centered by scroll_to_rect, blocks are read from their top
+        self.translation.y += (block.x0.y - block.center().y)
+            + (viewport.height / 2.0 - BLOCK_MARGIN) / effective_scale;
+        self.current_block = Some((page_idx, block_idx, self.translation));
+        true
+    }
 
-            let page_bounds = self.display_lists[i].bounds();
+    /// Moves the view to the first or last segment of the current page if it doesn't fit on
+    /// screen.
+    fn show_page_segment(&mut self, first: bool) {
+        let Some((segments, offset)) = self.current_page_segments() else {
+            return;
+        };
+        if segments.len() < 2 {
+            return;
+        }
+        let target = if first {
+            segments[0]
+        } else {
+            segments[segments.len() - 1]
+        };
+        self.translation += (target - offset).scaled(1.0 / (self.scale * self.fractional_scaling));
+    }
 
-            let pdf_rect = mupdf::Rect::new(
-                (intersect.x0.x - page_rect.x0.x) / effective_scale + page_bounds.x0,
-                (intersect.x0.y - page_rect.x0.y) / effective_scale + page_bounds.y0,
-                (intersect.x1.x - page_rect.x0.x) / effective_scale + page_bounds.x0,
-                (intersect.x1.y - page_rect.x0.y) / effective_scale + page_bounds.y0,
-            );
+    /// Zooms by `factor` while keeping the part of the page under `point` (in screen space) in
+    /// place. Outside of the pages, like in the seam of a spread, it zooms around the center of
+    /// the screen like [PdfMessage::ZoomIn] does.
+    fn zoom_around(&mut self, factor: f32, point: Vector<f32>) {
+        let viewport = *self.viewport.borrow();
+        let anchor = self
+            .page_mapping(viewport)
+            .and_then(|mapping| mapping.screen_to_page(point));
+        self.scale *= factor;
+        let Some((page_idx, page_point)) = anchor else {
+            return;
+        };
+        if let Some(moved_to) = self
+            .page_mapping(viewport)
+            .and_then(|mapping| mapping.page_point_to_screen(page_idx, page_point))
+        {
+            self.translation +=
+                (moved_to - point).scaled(1.0 / (self.scale * self.fractional_scaling));
+        }
+    }
+
+    /// Splits a rectangle in screen space into the parts covering each page, converted to page
+    /// space.
+    fn screen_rect_to_page_regions(&self, screen_rect: Rect<f32>) -> Vec<(usize, mupdf::Rect)> {
+        let Some(mapping) = self.page_mapping(*self.viewport.borrow()) else {
+            return vec![];
+        };
+        mapping
+            .screen_rect_to_pages(screen_rect)
+            .into_iter()
+            .map(|(i, rect)| (i, rect.into()))
+            .collect()
+    }
 
+    pub fn extract_text_from_rect(&self, screen_rect: Rect<f32>) -> String {
+        let mut result = String::new();
+
+        for (i, pdf_rect) in self.screen_rect_to_page_regions(screen_rect) {
             let Ok(text_page) = self.display_lists[i].to_text_page(TextPageFlags::empty()) else {
                 continue;
             };
@@ -1474,6 +3249,60 @@ impl PdfViewer {
         &self.selected_text
     }
 
+    /// The table covered by the current selection, see [text_layout::table]. Like
+    /// [PdfViewer::selection_text] the caret selection comes before the mouse selection.
+    fn selected_table(&self) -> Result<Vec<Vec<String>>, MiroError> {
+        let chars: Vec<_> = match (
+            self.caret.and_then(|caret| caret.selection()),
+            self.last_selection,
+        ) {
+            (Some(range), _) => self.char_bboxes[range].to_vec(),
+            (None, Some((page_idx, region))) if !self.selected_text.is_empty() => {
+                let region: Rect<f32> = region.into();
+                let start = self
+                    .char_bboxes
+                    .partition_point(|(page, _, _)| *page < page_idx);
+                let end = self
+                    .char_bboxes
+                    .partition_point(|(page, _, _)| *page <= page_idx);
+                self.char_bboxes[start..end]
+                    .iter()
+                    .filter(|(_, _, rect)| region.contains(rect.center()))
+                    .copied()
+                    .collect()
+            }
+            _ => {
+                return Err(MiroError::document("Select a table before copying it"));
+            }
+        };
+        text_layout::table(&self.text_contents, &chars).ok_or_else(|| {
+            MiroError::document("The selection doesn't have columns to copy as a table")
+        })
+    }
+
+    /// The text selected with the caret, or with the mouse if there is no caret selection.
+    pub fn selection_text(&self) -> String {
+        match self.caret.and_then(|caret| caret.selection()) {
+            Some(range) => caret::text_in_range(&self.text_contents, &self.char_bboxes, range),
+            None => self.selected_text.clone(),
+        }
+    }
+
+    fn update_selection_preview(&mut self) {
+        self.selection_preview.clear();
+        let Some(selection) = self.selection_rect() else {
+            return;
+        };
+        if selection.size().norm_squared() < MIN_SELECTION * MIN_SELECTION {
+            return;
+        }
+        let Some(mapping) = self.page_mapping(*self.viewport.borrow()) else {
+            return;
+        };
+        let regions = mapping.screen_rect_to_pages(selection);
+        self.selection_preview = selected_lines(&self.char_bboxes, &regions);
+    }
+
     fn selection_rect(&self) -> Option<Rect<f32>> {
         let (start, end) = (self.selection_start?, self.selection_end?);
         Some(Rect::from_points(
@@ -1482,47 +3311,65 @@ impl PdfViewer {
         ))
     }
 
+    fn layout_key(&self, viewport: iced::Size<f32>) -> LayoutKey {
+        LayoutKey {
+            layout: self.layout,
+            translation: self.translation,
+            scale: self.scale,
+            fractional_scaling: self.fractional_scaling,
+            viewport,
+        }
+    }
+
+    fn link_hitboxes(&self, viewport: iced::Size<f32>) -> std::cell::RefMut<'_, LinkHitboxes> {
+        let key = self.layout_key(viewport);
+        let mut cache = self.link_hitboxes.borrow_mut();
+        if cache.as_ref().is_none_or(|hitboxes| hitboxes.key != key) {
+            let visible = self.compute_visible_links(viewport);
+            *cache = Some(LinkHitboxes { key, visible });
+        }
+        std::cell::RefMut::map(cache, |cache| cache.as_mut().unwrap())
+    }
+
     fn visible_links(&self, viewport: iced::Size<f32>) -> Vec<((usize, usize), Rect<f32>)> {
+        self.link_hitboxes(viewport).visible.clone()
+    }
+
+    /// The link under `point` in screen space.
+    fn link_at(&self, viewport: iced::Size<f32>, point: Vector<f32>) -> Option<(usize, usize)> {
+        let (page_idx, point) = self.page_mapping(viewport)?.screen_to_page(point)?;
+        let link_idx = self.link_index.get(page_idx)?.find(point)?;
+        Some((page_idx, link_idx))
+    }
+
+    /// The visible links that get a hint in link hitbox mode, leaving out the ones too small to
+    /// see or click. [PdfMessage::ActivateLink] indexes into these.
+    fn hinted_links(&self, viewport: iced::Size<f32>) -> Vec<((usize, usize), Rect<f32>)> {
+        let mut links = self.visible_links(viewport);
+        links.retain(|(_, rect)| {
+            rect.width() >= MIN_HINTED_LINK_SIZE && rect.height() >= MIN_HINTED_LINK_SIZE
+        });
+        links
+    }
+
+    fn compute_visible_links(&self, viewport: iced::Size<f32>) -> Vec<((usize, usize), Rect<f32>)> {
         let mut result = Vec::new();
-        let Ok(pages) = self.doc.pages() else {
-            return result;
-        };
-        let Ok(page_rects) = self.layout.pages_rects(
-            pages,
-            self.translation.scaled(-1.0),
-            self.scale,
-            self.fractional_scaling,
-            viewport,
-        ) else {
+        let Some(mapping) = self.page_mapping(viewport) else {
             return result;
         };
-
         let viewport_rect = Rect::from_pos_size(Vector::zero(), viewport.into());
 
-        for (page_idx, page_rect) in page_rects.iter().enumerate() {
-            if !viewport_rect.intersects(page_rect) {
-                continue;
-            }
-            let page_bounds = self.display_lists[page_idx].bounds();
-            let page_width = page_bounds.x1 - page_bounds.x0;
-            let page_height = page_bounds.y1 - page_bounds.y0;
-            if page_width <= 0.0 || page_height <= 0.0 {
+        for (page_idx, links) in self.links.iter().enumerate() {
+            if !mapping
+                .screen_rect(page_idx)
+                .is_some_and(|r| viewport_rect.intersects(&r))
+            {
                 continue;
             }
-            let scale_x = page_rect.width() / page_width;
-            let scale_y = page_rect.height() / page_height;
-
-            for (link_idx, link) in self.links[page_idx].iter().enumerate() {
-                let screen_rect = Rect::from_points(
-                    Vector::new(
-                        page_rect.x0.x + (link.bounds.x0 - page_bounds.x0) * scale_x,
-                        page_rect.x0.y + (link.bounds.y0 - page_bounds.y0) * scale_y,
-                    ),
-                    Vector::new(
-                        page_rect.x0.x + (link.bounds.x1 - page_bounds.x0) * scale_x,
-                        page_rect.x0.y + (link.bounds.y1 - page_bounds.y0) * scale_y,
-                    ),
-                );
+            for (link_idx, link) in links.iter().enumerate() {
+                let Some(screen_rect) = mapping.page_to_screen(page_idx, link.bounds) else {
+                    continue;
+                };
                 if viewport_rect.intersects(&screen_rect) {
                     result.push(((page_idx, link_idx), screen_rect));
                 }
@@ -1532,112 +3379,242 @@ impl PdfViewer {
     }
 
     fn visible_search_results(&self, viewport: iced::Size<f32>) -> Vec<(usize, Rect<f32>)> {
-        let mut result = Vec::new();
         if !self.show_search_results {
-            return result;
+            return Vec::new();
         }
-        let Ok(pages) = self.doc.pages() else {
+        self.visible_matches(&self.search_matches, viewport)
+    }
+
+    /// Screen space rectangles of every line of `matches` that is on screen, together with the
+    /// index of the match it belongs to.
+    fn visible_matches(
+        &self,
+        matches: &[SearchMatch],
+        viewport: iced::Size<f32>,
+    ) -> Vec<(usize, Rect<f32>)> {
+        let mut result = Vec::new();
+        let Some(mapping) = self.page_mapping(viewport) else {
             return result;
         };
-        let Ok(page_rects) = self.layout.pages_rects(
-            pages,
-            self.translation.scaled(-1.0),
+        let viewport_rect = Rect::from_pos_size(Vector::zero(), viewport.into());
+
+        for (match_idx, m) in matches.iter().enumerate() {
+            for &(page_idx, rect) in &m.rects {
+                let Some(screen_rect) = mapping.page_to_screen(page_idx, rect) else {
+                    continue;
+                };
+                if viewport_rect.intersects(&screen_rect) {
+                    result.push((match_idx, screen_rect));
+                }
+            }
+        }
+        result
+    }
+
+    /// Centers `rect` (in page space) vertically while panning horizontally only as much as needed
+    /// to keep it visible.
+    fn scroll_to_rect(&mut self, page_idx: usize, rect: Rect<f32>) {
+        if let Ok(base_translation) = self.layout.translation_for_page(
+            &self.doc,
             self.scale,
             self.fractional_scaling,
-            viewport,
-        ) else {
+            page_idx,
+            *self.viewport.borrow(),
+        ) {
+            let page_bounds: Rect<f32> = self.display_lists[page_idx].bounds().into();
+            let page_center = page_bounds.center();
+            let match_center = rect.center();
+            // Center vertically.
+            self.translation.y = base_translation.y + (match_center.y - page_center.y);
+            // Horizontal: adjust minimally from current pan to keep match visible.
+            let viewport = *self.viewport.borrow();
+            let effective_scale = self.scale * self.fractional_scaling;
+            let half_viewport = viewport.width / (2.0 * effective_scale);
+            let lower_bound = rect.x1.x - page_center.x - half_viewport;
+            let upper_bound = rect.x0.x - page_center.x + half_viewport;
+            if lower_bound > upper_bound {
+                // Wider than viewport: center horizontally.
+                self.translation.x = match_center.x - page_center.x;
+            } else if self.translation.x < lower_bound {
+                self.translation.x = lower_bound;
+            } else if self.translation.x > upper_bound {
+                self.translation.x = upper_bound;
+            }
+        }
+    }
+
+    /// Screen space rectangles of the visible words starting with `prefix`, along with their index
+    /// in [PdfViewer::words].
+    fn visible_words(&self, viewport: iced::Size<f32>, prefix: &str) -> Vec<(usize, Rect<f32>)> {
+        let mut result = Vec::new();
+        let Some(mapping) = self.page_mapping(viewport) else {
             return result;
         };
-
         let viewport_rect = Rect::from_pos_size(Vector::zero(), viewport.into());
 
-        for (page_idx, page_rect) in page_rects.iter().enumerate() {
-            if !viewport_rect.intersects(page_rect) {
+        for (word_idx, word) in self.words.iter().enumerate() {
+            if !word.text.starts_with(prefix) {
                 continue;
             }
-            let page_bounds = self.display_lists[page_idx].bounds();
-            let page_width = page_bounds.x1 - page_bounds.x0;
-            let page_height = page_bounds.y1 - page_bounds.y0;
-            if page_width <= 0.0 || page_height <= 0.0 {
+            let Some(screen_rect) = mapping.page_to_screen(word.page_idx, word.bounds) else {
                 continue;
+            };
+            if viewport_rect.intersects(&screen_rect) {
+                result.push((word_idx, screen_rect));
             }
-            let scale_x = page_rect.width() / page_width;
-            let scale_y = page_rect.height() / page_height;
+        }
+        result
+    }
 
-            for (match_idx, m) in self.search_matches.iter().enumerate() {
-                for &(rect_page_idx, rect) in &m.rects {
-                    if rect_page_idx != page_idx {
-                        continue;
-                    }
-                    let screen_rect = Rect::from_points(
-                        Vector::new(
-                            page_rect.x0.x + (rect.x0.x - page_bounds.x0) * scale_x,
-                            page_rect.x0.y + (rect.x0.y - page_bounds.y0) * scale_y,
-                        ),
-                        Vector::new(
-                            page_rect.x0.x + (rect.x1.x - page_bounds.x0) * scale_x,
-                            page_rect.x0.y + (rect.x1.y - page_bounds.y0) * scale_y,
-                        ),
-                    );
-                    if viewport_rect.intersects(&screen_rect) {
-                        result.push((match_idx, screen_rect));
-                    }
+    /// Moves the caret and scrolls to it once it leaves the screen.
+    fn move_caret(&mut self, motion: CaretMotion, extend: bool) {
+        let Some(caret) = &mut self.caret else {
+            return;
+        };
+        caret.apply(&self.char_bboxes, motion, extend);
+        let Some(&(page_idx, _, rect)) = self.char_bboxes.get(caret.position) else {
+            return;
+        };
+        let viewport = *self.viewport.borrow();
+        if self
+            .visible_text_rects(viewport, &[(page_idx, rect)])
+            .is_empty()
+        {
+            self.scroll_to_rect(page_idx, rect);
+        }
+    }
+
+    /// Converts `rects` given in page space to screen space, leaving out the ones that are off
+    /// screen.
+    fn visible_text_rects(
+        &self,
+        viewport: iced::Size<f32>,
+        rects: &[(usize, Rect<f32>)],
+    ) -> Vec<Rect<f32>> {
+        let Some(mapping) = self.page_mapping(viewport) else {
+            return vec![];
+        };
+        let viewport_rect = Rect::from_pos_size(Vector::zero(), viewport.into());
+        rects
+            .iter()
+            .filter_map(|&(page_idx, rect)| mapping.page_to_screen(page_idx, rect))
+            .filter(|screen_rect| viewport_rect.intersects(screen_rect))
+            .collect()
+    }
+
+    fn load_page_boxes(&self) -> Vec<Vec<PageBox>> {
+        (0..self.display_lists.len())
+            .map(|page_idx| {
+                self.doc
+                    .load_page(page_idx as i32)
+                    .ok()
+                    .and_then(|page| PdfPage::try_from(page).ok())
+                    .map(|page| metadata::page_boxes(&page))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Screen space rectangles of the shown page boxes along with their names and their position
+    /// among the boxes of their page.
+    fn visible_page_boxes(&self, viewport: iced::Size<f32>) -> Vec<(Rect<f32>, &str, usize)> {
+        let (Some(boxes), Some(mapping)) = (&self.page_boxes, self.page_mapping(viewport)) else {
+            return vec![];
+        };
+        let viewport_rect = Rect::from_pos_size(Vector::zero(), viewport.into());
+        let mut out = vec![];
+        for (page_idx, page_boxes) in boxes.iter().enumerate() {
+            for (kind, page_box) in page_boxes.iter().enumerate() {
+                if let Some(rect) = mapping.page_to_screen(page_idx, page_box.rect.into())
+                    && viewport_rect.intersects(&rect)
+                {
+                    out.push((rect, page_box.names.as_str(), kind));
                 }
             }
         }
-        result
+        out
+    }
+
+    /// Screen space rectangles of the caret and of the text selected with it.
+    fn visible_caret(&self, viewport: iced::Size<f32>) -> Option<(Vec<Rect<f32>>, Vec<Rect<f32>>)> {
+        let caret = self.caret?;
+        let (page_idx, _, rect) = *self.char_bboxes.get(caret.position)?;
+        let cursor = self.visible_text_rects(viewport, &[(page_idx, rect)]);
+        let selection = match caret.selection() {
+            Some(range) => {
+                let chars: Vec<_> = self.char_bboxes[range]
+                    .iter()
+                    .map(|&(page_idx, _, rect)| (page_idx, rect))
+                    .collect();
+                self.visible_text_rects(viewport, &merge_search_rects(&chars))
+            }
+            None => vec![],
+        };
+        Some((cursor, selection))
     }
 
     fn visible_comments(&self, viewport: iced::Size<f32>) -> Vec<(usize, Rect<f32>)> {
         let mut result = Vec::new();
-        let Ok(pages) = self.doc.pages() else {
-            return result;
-        };
-        let Ok(page_rects) = self.layout.pages_rects(
-            pages,
-            self.translation.scaled(-1.0),
-            self.scale,
-            self.fractional_scaling,
-            viewport,
-        ) else {
+        let Some(mapping) = self.page_mapping(viewport) else {
             return result;
         };
-
         let viewport_rect = Rect::from_pos_size(Vector::zero(), viewport.into());
 
-        for (page_idx, page_rect) in page_rects.iter().enumerate() {
-            if !viewport_rect.intersects(page_rect) {
-                continue;
-            }
-            let page_bounds = self.display_lists[page_idx].bounds();
-            let page_width = page_bounds.x1 - page_bounds.x0;
-            let page_height = page_bounds.y1 - page_bounds.y0;
-            if page_width <= 0.0 || page_height <= 0.0 {
+        for (comment_idx, comment) in self.comments.iter().enumerate() {
+            let Some(screen_rect) = mapping.page_to_screen(comment.page_idx, comment.bounds.into())
+            else {
                 continue;
+            };
+            if viewport_rect.intersects(&screen_rect) {
+                result.push((comment_idx, screen_rect));
             }
-            let scale_x = page_rect.width() / page_width;
-            let scale_y = page_rect.height() / page_height;
+        }
+        result
+    }
 
-            for (comment_idx, comment) in self.comments.iter().enumerate() {
-                if comment.page_idx != page_idx {
-                    continue;
-                }
-                let screen_rect = Rect::from_points(
-                    Vector::new(
-                        page_rect.x0.x + (comment.bounds.x0 - page_bounds.x0) * scale_x,
-                        page_rect.x0.y + (comment.bounds.y0 - page_bounds.y0) * scale_y,
-                    ),
-                    Vector::new(
-                        page_rect.x0.x + (comment.bounds.x1 - page_bounds.x0) * scale_x,
-                        page_rect.x0.y + (comment.bounds.y1 - page_bounds.y0) * scale_y,
-                    ),
-                );
-                if viewport_rect.intersects(&screen_rect) {
-                    result.push((comment_idx, screen_rect));
+    fn visible_media(&self, viewport: iced::Size<f32>) -> Vec<(usize, Rect<f32>)> {
+        let Some(mapping) = self.page_mapping(viewport) else {
+            return vec![];
+        };
+        let viewport_rect = Rect::from_pos_size(Vector::zero(), viewport.into());
+        self.media
+            .iter()
+            .enumerate()
+            .filter_map(|(media_idx, media)| {
+                let rect = mapping.page_to_screen(media.page_idx, media.bounds.into())?;
+                viewport_rect.intersects(&rect).then_some((media_idx, rect))
+            })
+            .collect()
+    }
+
+    /// Opens the file of a video or sound annotation with the program the system picks for it.
+    fn open_media(&self, media_idx: usize) -> iced::Task<PdfMessage> {
+        let Some(source) = self.media.get(media_idx).and_then(|m| m.source.clone()) else {
+            return iced::Task::none();
+        };
+        let path = match source {
+            MediaSource::Embedded { file_name, object } => {
+                match media::extract(&self.doc, object, &file_name) {
+                    Ok(path) => path,
+                    Err(e) => {
+                        return iced::Task::done(PdfMessage::Error(MiroError::document(format!(
+                            "Couldn't extract {file_name}: {e}"
+                        ))));
+                    }
                 }
             }
-        }
-        result
+            MediaSource::External(target) if target.contains("://") => {
+                return open_external(&target);
+            }
+            MediaSource::External(target) => match self.path.parent() {
+                Some(dir) if !self.in_memory => dir.join(target),
+                _ => PathBuf::from(target),
+            },
+        };
+        iced::Task::perform(
+            run_blocking(move || open::that(&path).map_err(|e| MiroError::file(&path, e))),
+            PdfMessage::from_result,
+        )
     }
 
     fn local_mouse_pos(&self) -> Vector<f32> {
@@ -1649,14 +3626,11 @@ impl PdfViewer {
         let local_mouse = self.local_mouse_pos();
         let viewport = *self.viewport.borrow();
 
-        let visible_links = self.visible_links(viewport);
-        self.hovered_link = visible_links
-            .iter()
-            .find(|(_, rect)| rect.contains(local_mouse))
-            .map(|((page_idx, link_idx), _)| (*page_idx, *link_idx));
+        self.hovered_link = self.link_at(viewport, local_mouse);
         if self.hovered_link.is_some() {
             self.hovered_search_result = None;
             self.hovered_comment = None;
+            self.hovered_media = None;
             return;
         }
 
@@ -1667,6 +3641,7 @@ impl PdfViewer {
             .map(|(match_idx, _)| *match_idx);
         if self.hovered_search_result.is_some() {
             self.hovered_comment = None;
+            self.hovered_media = None;
             return;
         }
 
@@ -1675,6 +3650,16 @@ impl PdfViewer {
             .iter()
             .find(|(_, rect)| rect.contains(local_mouse))
             .map(|(comment_idx, _)| *comment_idx);
+        if self.hovered_comment.is_some() {
+            self.hovered_media = None;
+            return;
+        }
+
+        self.hovered_media = self
+            .visible_media(viewport)
+            .iter()
+            .find(|(_, rect)| rect.contains(local_mouse))
+            .map(|(media_idx, _)| *media_idx);
     }
 
     fn activate_link(&mut self, page_idx: usize, link_idx: usize) -> iced::Task<PdfMessage> {
@@ -1688,12 +3673,16 @@ impl PdfViewer {
             || link.uri.starts_with("https://")
             || link.uri.starts_with("mailto:")
         {
-            let _ = open::that(&link.uri);
-        } else if let Some(dest) = link.dest {
-            let page_num = dest.loc.page_number as usize;
-            if page_num < self.doc.page_count().unwrap() as usize {
+            return open_external(&link.uri);
+        } else if let Some(page_num) = link.page {
+            if self
+                .page_count()
+                .is_ok_and(|count| page_num < count as usize)
+            {
                 return iced::Task::done(PdfMessage::SetPage(page_num));
             }
+        } else if let Some(page_num) = self.destinations.resolve_uri(&link.uri) {
+            return iced::Task::done(PdfMessage::SetPage(page_num));
         } else if link.uri.starts_with("#page=")
             && let Some(page_str) = link.uri.strip_prefix("#page=")
             && let Ok(page_num) = page_str.parse::<usize>()
@@ -1715,8 +3704,90 @@ impl PdfViewer {
         Ok(self.doc.page_count()?)
     }
 
-    #[cfg(test)]
-    pub fn set_viewport_for_test(&mut self, size: iced::Size) {
+    pub fn destinations(&self) -> &Destinations {
+        &self.destinations
+    }
+
+    /// The metadata set in the document, like its title and author.
+    pub fn document_info(&self) -> Vec<(&'static str, String)> {
+        [
+            ("Title", MetadataName::Title),
+            ("Author", MetadataName::Author),
+            ("Subject", MetadataName::Subject),
+            ("Creator", MetadataName::Creator),
+            ("Producer", MetadataName::Producer),
+            ("Format", MetadataName::Format),
+        ]
+        .into_iter()
+        .filter_map(|(label, name)| {
+            let value = self.doc.metadata(name).ok()?;
+            (!value.trim().is_empty()).then_some((label, value))
+        })
+        .collect()
+    }
+
+    /// The colors pages are drawn in by the dark mode, indexed by brightness.
+    pub fn dark_mode_colors(&self) -> [[u8; 4]; 256] {
+        self.gradient_cache
+    }
+
+    /// The pdf as it is right now, including the edits that haven't been saved.
+    pub fn pdf_bytes(&self) -> Result<Vec<u8>> {
+        let pdf = mupdf::pdf::PdfDocument::try_from(self.doc.clone())
+            .map_err(|_| anyhow!("{} is not a pdf", self.name))?;
+        let mut bytes = vec![];
+        pdf.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Writes the edits back to the file the document was opened from. The document keeps reading
+    /// pages from that file, so this is only meant for documents that are closed right after.
+    pub fn save_before_close(&self) -> Result<()> {
+        if self.in_memory {
+            return Err(anyhow!("{} has no file to save to", self.name));
+        }
+        paths::ensure_writable()?;
+        // The file is replaced rather than overwritten, a failed save must not leave the
+        // original half written
+        paths::write_atomically(&self.path, self.pdf_bytes()?)?;
+        Ok(())
+    }
+
+    /// Writes the document as it currently is in memory to `path` and makes the viewer track the
+    /// new file from then on.
+    pub fn save_as(&mut self, path: PathBuf) -> Result<()> {
+        paths::ensure_writable()?;
+        if self.doc.is_pdf() {
+            // The dialog suggests the file the document is read from, which mupdf would truncate
+            // while still reading it
+            paths::write_atomically(&path, self.pdf_bytes()?)?;
+        } else if self.in_memory {
+            return Err(anyhow!(
+                "Only pdfs can be saved when they were opened from memory"
+            ));
+        } else {
+            // Copying a file onto itself truncates it
+            if std::fs::canonicalize(&path).is_ok_and(|path| path == self.path) {
+                return Ok(());
+            }
+            std::fs::copy(&self.path, &path)?;
+        }
+        self.path = std::fs::canonicalize(path)?;
+        self.in_memory = false;
+        self.modified = false;
+        self.scheduler.get_mut().reset(Some(self.path.clone()));
+        self.thumbnails.get_mut().clear();
+        self.name = self
+            .path
+            .file_name()
+            .expect("The pdf must have a file name")
+            .to_string_lossy()
+            .to_string();
+        Ok(())
+    }
+
+    #[cfg(any(test, feature = "bench"))]
+    pub fn set_viewport(&mut self, size: iced::Size) {
         *self.viewport.borrow_mut() = size;
     }
 
@@ -1727,17 +3798,36 @@ impl PdfViewer {
     pub fn set_pdf_dark_mode(&mut self, dark_mode_enabled: bool) {
         if self.pdf_dark_mode != dark_mode_enabled {
             self.pdf_dark_mode = dark_mode_enabled;
-            self.render_cache.borrow_mut().clear();
-            self.allocation_cache.borrow_mut().clear();
-            self.buffer_pool.lock().unwrap().clear();
-            self.pixmap_pool.borrow_mut().clear();
+            self.forget_page_colors();
+        }
+    }
+
+    /// Follows the dark mode style of the config, which changes when the preferences are saved.
+    pub fn set_pdf_dark_mode_style(&mut self, style: PdfDarkModeStyle) {
+        let recolor = style == PdfDarkModeStyle::Recolor;
+        if self.recolor_pdf != recolor {
+            self.recolor_pdf = recolor;
+            self.forget_page_colors();
         }
     }
 
+    /// Drops every rendered page after the colors pages are drawn in have changed.
+    fn forget_page_colors(&mut self) {
+        // Pages queued with the old colors would otherwise end up in the caches again
+        self.scheduler.get_mut().invalidate();
+        self.render_cache.borrow_mut().clear();
+        self.thumbnails.borrow_mut().clear();
+        self.prefetched.borrow_mut().clear();
+        self.allocation_cache.borrow_mut().clear();
+        self.buffer_pool.lock().unwrap().clear();
+        self.pixmap_pool.borrow_mut().clear();
+    }
+
     pub fn set_interface_dark_mode(&mut self, dark_mode_enabled: bool) {
         if self.interface_dark_mode != dark_mode_enabled {
             self.interface_dark_mode = dark_mode_enabled;
             self.render_cache.borrow_mut().clear();
+            self.prefetched.borrow_mut().clear();
             self.allocation_cache.borrow_mut().clear();
         }
     }
@@ -1755,6 +3845,10 @@ impl PdfViewer {
                     false
                 }
             }
+            PdfMessage::JumpToWord(_)
+            | PdfMessage::ShowSearchMatch(..)
+            | PdfMessage::FirstPage
+            | PdfMessage::LastPage => true,
             _ => false,
         }
     }
@@ -1763,26 +3857,164 @@ impl PdfViewer {
         &self.outline
     }
 
-    fn extract_outline(doc: &mupdf::Document) -> Result<Vec<OutlineItem>> {
-        let outlines = doc.outlines()?;
-        let mut items = Vec::new();
-        for outline in &outlines {
-            items.push(Self::convert_outline(outline, 0)?);
+    pub fn annotations(&self) -> &[AnnotationEntry] {
+        &self.annotations
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn signatures(&self) -> &[Signature] {
+        &self.signatures
+    }
+
+    /// The layers hidden by the user, `None` while the document decides which are shown.
+    pub fn hidden_layers(&self) -> Option<&[String]> {
+        self.hidden_layers.as_deref()
+    }
+
+    /// Shows every layer except those named in `hidden`. The document is opened again since
+    /// mupdf only reads which layers are shown once, see [layers].
+    pub fn set_hidden_layers(&mut self, hidden: Vec<String>) -> Result<()> {
+        if self.layers.is_empty() {
+            return Ok(());
+        }
+        // Edits only exist in memory, so the document is opened from there when it has any
+        let from_file = !self.in_memory && !self.modified;
+        let doc = if from_file {
+            archive::open(&self.path)?
+        } else {
+            mupdf::Document::from_bytes(&self.pdf_bytes()?, "application/pdf")?
+        };
+        layers::hide(&doc, &hidden)?;
+        let (display_lists, links, outline, comments, annotations, render_errors) =
+            Self::build_document_data(&doc)?;
+        self.layers = layers::read(&doc)?;
+        self.media = media::read(&doc)?;
+        self.hovered_media = None;
+        self.backend = Box::new(MupdfBackend::new(doc.clone()));
+        self.doc = doc;
+        self.display_lists = display_lists;
+        self.reading_blocks.borrow_mut().clear();
+        self.render_errors.replace(render_errors);
+        self.link_index = index_links(&links);
+        self.links = links;
+        self.link_hitboxes.replace(None);
+        self.outline = outline;
+        self.comments = comments;
+        self.annotations = annotations;
+        self.active_comment = None;
+        self.hovered_comment = None;
+        self.render_cache.borrow_mut().clear();
+        self.allocation_cache.borrow_mut().clear();
+        self.pixmap_pool.borrow_mut().clear();
+        self.prefetched.borrow_mut().clear();
+        self.thumbnails.borrow_mut().clear();
+        if from_file {
+            self.scheduler.get_mut().set_hidden_layers(hidden.clone());
+        } else {
+            self.scheduler.get_mut().reset(None);
         }
-        Ok(items)
+        self.hidden_layers = Some(hidden);
+        Ok(())
     }
 
-    fn convert_outline(outline: &mupdf::Outline, level: u32) -> Result<OutlineItem> {
-        let mut children = Vec::new();
-        for child in &outline.down {
-            children.push(Self::convert_outline(child, level + 1)?);
+    pub fn is_annotation_page_collapsed(&self, page_idx: usize) -> bool {
+        self.collapsed_annotation_pages.contains(&page_idx)
+    }
+
+    /// The annotation's own text, or the text it covers for highlights and the like.
+    pub fn annotation_snippet(&self, entry: &AnnotationEntry) -> String {
+        const MAX_CHARS: usize = 80;
+        let text = match &entry.content {
+            Some(content) if !content.trim().is_empty() => content.trim().to_string(),
+            _ => {
+                let bounds: Rect<f32> = entry.bounds.into();
+                let mut covered = String::new();
+                for &(page_idx, byte_offset, rect) in self.char_bboxes.iter() {
+                    if page_idx == entry.page_idx
+                        && bounds.contains(rect.center())
+                        && let Some(c) = self.text_contents[byte_offset..].chars().next()
+                    {
+                        covered.push(c);
+                    }
+                }
+                covered.trim().to_string()
+            }
+        };
+        if text.chars().count() > MAX_CHARS {
+            let mut short: String = text.chars().take(MAX_CHARS).collect();
+            short.push('…');
+            short
+        } else {
+            text
+        }
+    }
+
+    /// Removes the annotation from the document in memory. The file on disk is only changed when
+    /// the document is saved.
+    fn delete_annotation(&mut self, idx: usize) -> Result<()> {
+        let entry = self
+            .annotations
+            .get(idx)
+            .ok_or(anyhow!("Annotation index {idx} out of bounds"))?
+            .clone();
+        let mut pdf_page = PdfPage::try_from(self.doc.load_page(entry.page_idx as i32)?)?;
+        let annotation = pdf_page
+            .annotations()
+            .nth(entry.annot_idx)
+            .ok_or(anyhow!("The annotation no longer exists"))?;
+        pdf_page.delete_annotation(annotation)?;
+        pdf_page.update()?;
+
+        let (display_lists, links, outline, comments, annotations, render_errors) =
+            Self::build_document_data(&self.doc)?;
+        self.media = media::read(&self.doc)?;
+        self.hovered_media = None;
+        self.display_lists = display_lists;
+        self.reading_blocks.borrow_mut().clear();
+        self.render_errors.replace(render_errors);
+        self.link_index = index_links(&links);
+        self.links = links;
+        self.link_hitboxes.replace(None);
+        self.outline = outline;
+        self.comments = comments;
+        self.annotations = annotations;
+        self.active_comment = None;
+        self.hovered_comment = None;
+        self.render_cache.borrow_mut().clear();
+        self.allocation_cache.borrow_mut().clear();
+        self.pixmap_pool.borrow_mut().clear();
+        self.prefetched.borrow_mut().clear();
+        self.thumbnails.borrow_mut().clear();
+        // The workers render from the file, which still contains the annotation until it's saved
+        self.scheduler.get_mut().reset(None);
+        Ok(())
+    }
+
+    /// Title of the deepest outline entry starting at or before `page`.
+    pub fn section_title(&self, page: usize) -> Option<String> {
+        fn visit<'a>(items: &'a [OutlineItem], page: usize, found: &mut Option<&'a OutlineItem>) {
+            for item in items {
+                if item.page.is_some_and(|p| p as usize <= page) {
+                    *found = Some(item);
+                }
+                visit(&item.children, page, found);
+            }
+        }
+        let mut found = None;
+        visit(&self.outline, page, &mut found);
+        found.map(|item| item.title.clone())
+    }
+
+    /// Points someone at `page`, like "paper.pdf — page 42 (3.1 Results)".
+    pub fn page_reference(&self, page: usize) -> String {
+        let reference = format!("{} — page {}", self.name, page + 1);
+        match self.section_title(page) {
+            Some(section) => format!("{reference} ({})", section.trim()),
+            None => reference,
         }
-        Ok(OutlineItem {
-            title: outline.title.clone(),
-            page: outline.dest.map(|d| d.loc.page_number),
-            level,
-            children,
-        })
     }
 
     pub fn page_progress(&self) -> String {
@@ -1808,6 +4040,19 @@ impl PdfViewer {
     }
 }
 
+/// The colors the dark mode shader maps each brightness to, from white to the dark background.
+pub(crate) fn dark_mode_gradient() -> [[u8; 4]; 256] {
+    let bg_color = DARK_THEME
+        .extended_palette()
+        .background
+        .base
+        .color
+        .into_rgba8();
+    let mut gradient_cache = [[0; 4]; 256];
+    generate_gradient_cache(&mut gradient_cache, &bg_color);
+    gradient_cache
+}
+
 fn generate_gradient_cache(cache: &mut [[u8; 4]; 256], bg_color: &[u8; 4]) {
     let gradient = GradientBuilder::new()
         .colors(&[
@@ -1821,10 +4066,36 @@ fn generate_gradient_cache(cache: &mut [[u8; 4]; 256], bg_color: &[u8; 4]) {
     }
 }
 
-fn cpu_pdf_dark_mode_shader(pixmap: &mut mupdf::Pixmap, gradient_cache: &[[u8; 4]; 256]) {
+/// A pooled pixmap more than this many times larger than needed in either direction is replaced,
+/// so a page that shrank on screen doesn't hold on to its largest allocation
+const PIXMAP_MAX_SLACK: i32 = 2;
+/// A pixmap that is too small is replaced by one at least this many times larger, so the size
+/// only has to be grown a few times while a window is resized by dragging its edge
+const PIXMAP_GROWTH: f32 = 1.5;
+
+fn pixmap_fits(pix: &Pixmap, w: i32, h: i32) -> bool {
+    let (pw, ph) = (pix.width() as i32, pix.height() as i32);
+    (w..=w * PIXMAP_MAX_SLACK).contains(&pw) && (h..=h * PIXMAP_MAX_SLACK).contains(&ph)
+}
+
+/// The size of a new pixmap for `w` x `h` pixels replacing the `pooled` one.
+fn pixmap_size(pooled: Option<&Pixmap>, w: i32, h: i32) -> (i32, i32) {
+    let grow = |needed: i32, old: i32| {
+        if needed > old {
+            needed.max((old as f32 * PIXMAP_GROWTH).ceil() as i32)
+        } else {
+            needed
+        }
+    };
+    match pooled {
+        Some(pix) => (grow(w, pix.width() as i32), grow(h, pix.height() as i32)),
+        None => (w, h),
+    }
+}
+
+pub(crate) fn cpu_pdf_dark_mode_shader(samples: &mut [u8], gradient_cache: &[[u8; 4]; 256]) {
     // PERF: Slow in debug builds but more than fast enough in release builds.
     let _span = tracy_client::span!("Cpu dark mode shader");
-    let samples = pixmap.samples_mut();
     for pixel in samples.chunks_exact_mut(4) {
         let r: u16 = pixel[0] as u16;
         let g: u16 = pixel[1] as u16;
@@ -1835,28 +4106,263 @@ fn cpu_pdf_dark_mode_shader(pixmap: &mut mupdf::Pixmap, gradient_cache: &[[u8; 4
     }
 }
 
+/// The lines of the characters in `char_bboxes` touching one of the page space `regions`, the
+/// same characters [PdfViewer::extract_text_from_rect] copies. The boxes are sorted by page, so
+/// only the pages of the regions are looked through.
+fn selected_lines(
+    char_bboxes: &[(usize, usize, Rect<f32>)],
+    regions: &[(usize, Rect<f32>)],
+) -> Vec<(usize, Rect<f32>)> {
+    let mut chars = vec![];
+    for &(page_idx, region) in regions {
+        let start = char_bboxes.partition_point(|(page, _, _)| *page < page_idx);
+        let end = char_bboxes.partition_point(|(page, _, _)| *page <= page_idx);
+        chars.extend(
+            char_bboxes[start..end]
+                .iter()
+                .filter(|(_, _, rect)| rect.intersects(&region))
+                .map(|&(page, _, rect)| (page, rect)),
+        );
+    }
+    merge_search_rects(&chars)
+}
+
+fn index_links(links: &[Vec<PageLink>]) -> Vec<RectIndex> {
+    links
+        .iter()
+        .map(|page| RectIndex::new(page.iter().map(|link| link.bounds).collect()))
+        .collect()
+}
+
 fn rectangles_intersect(a: mupdf::Rect, b: mupdf::Rect) -> bool {
     a.x0 < b.x1 && a.x1 > b.x0 && a.y0 < b.y1 && a.y1 > b.y0
 }
 
-fn generate_key_combinations(count: usize) -> Vec<String> {
-    // Use easily distinguishable characters (excluding confusing ones like 'I', 'l', 'O', '0')
-    const CHARS: &[char] = &[
-        'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'j', 'k', 'm', 'n', 'p', 'q', 'r', 's', 't', 'u',
-        'v', 'w', 'x', 'y', 'z',
-    ];
+fn is_fully_visible(rect: &Rect<f32>, viewport: &Rect<f32>) -> bool {
+    rect.x0.x >= 0.0 && rect.x1.x <= viewport.x1.x && rect.x0.y >= 0.0 && rect.x1.y <= viewport.x1.y
+}
+
+/// Where a hint label of `size` goes next to `rect`. Hints are placed to the right of what they
+/// belong to, unless they'd end up outside the viewport. Then they are moved to the left or on
+/// top of it.
+fn hint_label_position(rect: &Rect<f32>, size: iced::Size, viewport: iced::Size) -> iced::Point {
+    let gap = 2.0;
+    let y = rect.center().y - size.height / 2.0;
+    if rect.x1.x + gap + size.width <= viewport.width {
+        iced::Point::new(rect.x1.x + gap, y)
+    } else if rect.x0.x - gap - size.width >= 0.0 {
+        iced::Point::new(rect.x0.x - gap - size.width, y)
+    } else {
+        let x = rect.x0.x.clamp(0.0, (viewport.width - size.width).max(0.0));
+        let above = rect.x0.y - gap - size.height;
+        iced::Point::new(x, if above >= 0.0 { above } else { rect.x0.y })
+    }
+}
+
+/// How far a pinch zooms, 1.0 keeps the scale. Opposite pinches of the same size cancel out.
+fn pinch_zoom_factor(pixels: f32, sensitivity: f32) -> f32 {
+    2f32.powf(pixels * sensitivity / PINCH_PIXELS_PER_DOUBLING)
+}
+
+fn no_identifier_error() -> PdfMessage {
+    PdfMessage::Error(MiroError::document(format!(
+        "No DOI or arXiv id found on the first {} pages",
+        identifiers::SEARCHED_PAGES
+    )))
+}
+
+/// Opens a web or `mailto:` link with the configured `UrlHandler` or `MailHandler`, or the default
+/// browser or mail client.
+fn open_external(uri: &str) -> iced::Task<PdfMessage> {
+    let is_mail = uri.starts_with("mailto:");
+    let handler = if is_mail {
+        CONFIG.read().unwrap().mail_handler.clone()
+    } else {
+        CONFIG.read().unwrap().url_handler.clone()
+    };
+    if is_mail && handler == MAIL_HANDLER_COPY {
+        iced::Task::perform(
+            copy_to_clipboard(mail_address(uri).to_string()),
+            PdfMessage::from_result,
+        )
+    } else if !handler.is_empty() {
+        let target = lookup::lookup_target(&handler, uri);
+        iced::Task::perform(lookup::open(target), PdfMessage::from_result)
+    } else {
+        let uri = uri.to_string();
+        iced::Task::perform(
+            run_blocking(move || {
+                open::that(&uri)
+                    .map_err(|e| MiroError::external(format!("Couldn't open {uri}: {e}")))
+            }),
+            PdfMessage::from_result,
+        )
+    }
+}
+
+/// Copies text taken from the document, joining its lines first if `CopyCleanup` is set.
+fn copy_selected_text(mut text: String) -> iced::Task<PdfMessage> {
+    if CONFIG.read().unwrap().copy_cleanup {
+        text = copy_cleanup::clean(&text);
+    }
+    iced::Task::perform(copy_to_clipboard(text), PdfMessage::from_result)
+}
+
+/// The address of a `mailto:` link, without the subject or other fields after it.
+fn mail_address(uri: &str) -> &str {
+    let address = uri.strip_prefix("mailto:").unwrap_or(uri);
+    address.split('?').next().unwrap_or(address)
+}
+
+/// Hint labels grow and shrink with the pages so they stay about as large as the text they sit
+/// next to, but never so small they can't be read or so large they cover what they point at.
+fn hint_text_size(effective_scale: f32) -> f32 {
+    (HINT_TEXT_SIZE * effective_scale).clamp(MIN_HINT_TEXT_SIZE, MAX_HINT_TEXT_SIZE)
+}
+
+/// A few lines of text below `rect`, or above it when there is no room below.
+fn draw_media_tooltip(
+    frame: &mut canvas::Frame<Renderer>,
+    rect: &Rect<f32>,
+    tooltip: &str,
+    viewport: iced::Size,
+) {
+    const SIZE: f32 = 13.0;
+    const PADDING: f32 = 6.0;
+    let lines = tooltip.lines().count() as f32;
+    let longest = tooltip
+        .lines()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or(0) as f32;
+    let size = iced::Size::new(
+        (longest * SIZE * 0.6 + PADDING * 2.0).min(viewport.width),
+        lines * SIZE * 1.3 + PADDING * 2.0,
+    );
+    let y = if rect.x1.y + 4.0 + size.height <= viewport.height {
+        rect.x1.y + 4.0
+    } else {
+        rect.x0.y - 4.0 - size.height
+    };
+    let x = rect.x0.x.min(viewport.width - size.width).max(0.0);
+    frame.fill_rectangle(
+        iced::Point::new(x, y),
+        size,
+        iced::Color::from_rgb(0.1, 0.1, 0.1),
+    );
+    frame.fill_text(geometry::Text {
+        content: tooltip.to_string(),
+        position: iced::Point::new(x + PADDING, y + PADDING),
+        max_width: size.width - PADDING * 2.0,
+        color: iced::Color::WHITE,
+        size: SIZE.into(),
+        line_height: widget::text::LineHeight::Relative(1.3),
+        font: iced::Font::default(),
+        align_x: iced::alignment::Horizontal::Left.into(),
+        align_y: iced::alignment::Vertical::Top,
+        shaping: widget::text::Shaping::Advanced,
+    });
+}
+
+/// Draws the keys that activate a hint next to its target.
+fn draw_hint_label(
+    frame: &mut canvas::Frame<Renderer>,
+    rect: &Rect<f32>,
+    key: &str,
+    text_size: f32,
+    viewport: iced::Size,
+) {
+    let padding = (text_size * 0.2).round();
+    let approx_char_w = text_size * 0.6;
+    let bg_w = approx_char_w * key.len() as f32 + padding * 2.0;
+    let bg_h = text_size + padding;
+    let iced::Point { x: bg_x, y: bg_y } =
+        hint_label_position(rect, iced::Size::new(bg_w, bg_h), viewport);
+    frame.fill_rectangle(
+        iced::Point::new(bg_x, bg_y),
+        iced::Size::new(bg_w, bg_h),
+        iced::Color::from_rgb(0.1, 0.1, 0.1),
+    );
+
+    frame.fill_text(geometry::Text {
+        content: key.to_string(),
+        position: iced::Point::new(bg_x + bg_w / 2.0, bg_y + bg_h / 2.0),
+        max_width: bg_w,
+        color: iced::Color::WHITE,
+        size: text_size.into(),
+        line_height: widget::text::LineHeight::Relative(1.0),
+        font: iced::Font::default(),
+        align_x: iced::alignment::Horizontal::Center.into(),
+        align_y: iced::alignment::Vertical::Center,
+        shaping: widget::text::Shaping::Basic,
+    });
+}
+
+/// Splits the text of a document into words. Characters only belong to the same word if they are
+/// next to each other on the same line.
+fn extract_words(text: &str, char_bboxes: &[(usize, usize, Rect<f32>)]) -> Vec<Word> {
+    let mut words: Vec<Word> = vec![];
+    let mut current: Option<Word> = None;
+    let mut last_rect: Option<Rect<f32>> = None;
+    for &(page_idx, byte_offset, rect) in char_bboxes {
+        let Some(c) = text[byte_offset..].chars().next() else {
+            continue;
+        };
+        if !c.is_alphanumeric() {
+            words.extend(current.take());
+            last_rect = None;
+            continue;
+        }
+        let same_line = last_rect.is_some_and(|last| {
+            (rect.center().y - last.center().y).abs() < last.height() / 2.0
+                && rect.x0.x >= last.x0.x
+        });
+        match current.as_mut() {
+            Some(word) if word.page_idx == page_idx && same_line => {
+                word.text.extend(c.to_lowercase());
+                word.bounds = Rect::from_points(
+                    Vector::new(
+                        word.bounds.x0.x.min(rect.x0.x),
+                        word.bounds.x0.y.min(rect.x0.y),
+                    ),
+                    Vector::new(
+                        word.bounds.x1.x.max(rect.x1.x),
+                        word.bounds.x1.y.max(rect.x1.y),
+                    ),
+                );
+            }
+            _ => {
+                words.extend(current.take());
+                current = Some(Word {
+                    page_idx,
+                    text: c.to_lowercase().collect(),
+                    bounds: rect,
+                });
+            }
+        }
+        last_rect = Some(rect);
+    }
+    words.extend(current);
+    words
+}
 
+/// The characters hints are made of, see `Set HintChars`
+fn hint_chars() -> Vec<char> {
+    CONFIG.read().unwrap().hint_chars.clone()
+}
+
+fn generate_key_combinations(count: usize, chars: &[char]) -> Vec<String> {
     let mut keys = Vec::new();
 
-    for &c in CHARS.iter().take(count.min(CHARS.len())) {
+    for &c in chars.iter().take(count.min(chars.len())) {
         keys.push(c.to_string());
     }
 
-    if count > CHARS.len() {
-        let remaining = count - CHARS.len();
+    if count > chars.len() {
+        let remaining = count - chars.len();
         let mut added = 0;
-        'outer: for &c1 in CHARS {
-            for &c2 in CHARS {
+        'outer: for &c1 in chars {
+            for &c2 in chars {
                 if added >= remaining {
                     break 'outer;
                 }
@@ -1870,7 +4376,12 @@ fn generate_key_combinations(count: usize) -> Vec<String> {
 }
 
 /// Returns the pdf background color
-fn get_pdf_background_color(pdf_dark_mode: bool, show_borders: bool) -> iced::Color {
+/// A dark page on a dark background needs a stronger shadow to stand out at all
+pub(crate) fn page_shadow_alpha(pdf_dark_mode: bool) -> f32 {
+    if pdf_dark_mode { 0.12 } else { 0.05 }
+}
+
+pub(crate) fn get_pdf_background_color(pdf_dark_mode: bool, show_borders: bool) -> iced::Color {
     if show_borders {
         if pdf_dark_mode {
             iced::Color::from_rgb8(21, 22, 32)
@@ -1886,17 +4397,114 @@ fn get_pdf_background_color(pdf_dark_mode: bool, show_borders: bool) -> iced::Co
     }
 }
 
+/// The banner warning that the form's scripts aren't run, shown until it is dismissed.
+fn script_notice_view<'a>() -> iced::Element<'a, PdfMessage> {
+    let notice = widget::container(
+        widget::row![
+            widget::text(
+                "This form fills in some fields with JavaScript, which miro doesn't run. \
+                Totals and other calculated values won't update as you type."
+            )
+            .size(14.0)
+            .width(iced::Length::Fill),
+            widget::button(widget::text("Dismiss").size(14.0))
+                .style(widget::button::text)
+                .on_press(PdfMessage::DismissScriptNotice),
+        ]
+        .spacing(8.0)
+        .align_y(iced::alignment::Vertical::Center),
+    )
+    .max_width(640.0)
+    .padding(8.0)
+    .style(|theme: &iced::Theme| widget::container::Style {
+        background: Some(theme.extended_palette().background.weak.color.into()),
+        border: iced::Border {
+            color: theme.extended_palette().warning.base.color,
+            width: 2.0,
+            radius: iced::border::Radius::from(8.0),
+        },
+        ..Default::default()
+    });
+
+    widget::container(widget::mouse_area(notice).on_press(PdfMessage::None))
+        .width(iced::Length::Fill)
+        .align_x(iced::alignment::Horizontal::Center)
+        .padding(8.0)
+        .into()
+}
+
+/// A bar along the edge of the view that was paged into, with an optional toast next to it.
+fn edge_indicator_view<'a>(edge: DocumentEdge) -> iced::Element<'a, PdfMessage> {
+    let bar = widget::container(widget::space::horizontal())
+        .width(iced::Length::Fill)
+        .height(4.0)
+        .style(|theme: &iced::Theme| widget::container::Style {
+            background: Some(theme.extended_palette().primary.base.color.into()),
+            ..Default::default()
+        });
+    let (label, align) = match edge {
+        DocumentEdge::Start => ("Start of document", iced::alignment::Vertical::Top),
+        DocumentEdge::End => ("End of document", iced::alignment::Vertical::Bottom),
+    };
+
+    let mut content = widget::column![].align_x(iced::alignment::Horizontal::Center);
+    if CONFIG.read().unwrap().end_of_document_toast {
+        let toast = widget::container(widget::text(label).size(14.0))
+            .padding(8.0)
+            .style(|theme: &iced::Theme| widget::container::Style {
+                background: Some(theme.extended_palette().background.weak.color.into()),
+                border: iced::Border {
+                    color: theme.extended_palette().primary.base.color,
+                    width: 2.0,
+                    radius: iced::border::Radius::from(8.0),
+                },
+                ..Default::default()
+            });
+        content = match edge {
+            DocumentEdge::Start => content
+                .push(bar)
+                .push(widget::space::vertical().height(8.0))
+                .push(toast),
+            // The tab bar is drawn over the bottom of the document
+            DocumentEdge::End => content
+                .push(toast)
+                .push(widget::space::vertical().height(56.0))
+                .push(bar),
+        };
+    } else {
+        content = content.push(bar);
+    }
+
+    widget::container(content)
+        .width(iced::Length::Fill)
+        .height(iced::Length::Fill)
+        .align_y(align)
+        .into()
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
     use crate::pdf::find_search_matches;
     use super::*;
 
+    #[test]
+    fn mail_links_are_copied_without_their_fields() {
+        assert_eq!(
+            mail_address("mailto:someone@example.com"),
+            "someone@example.com"
+        );
+        assert_eq!(
+            mail_address("mailto:someone@example.com?subject=Hello"),
+            "someone@example.com"
+        );
+    }
+
     #[test]
     fn test_zoom_fit_scales_current_page_to_viewport() -> Result<()> {
         let mut viewer = PdfViewer::from_path(PathBuf::from("assets/links.pdf"))?;
         let viewport = iced::Size::new(800.0, 600.0);
-        viewer.set_viewport_for_test(viewport);
+        viewer.set_viewport(viewport);
         viewer.layout = PageLayout::SinglePage;
 
         // Start on page 0
@@ -1976,6 +4584,20 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn page_text_only_contains_that_page() -> Result<()> {
+        let viewer = PdfViewer::from_path(PathBuf::from("assets/links.pdf"))?;
+        let first = viewer.page_text(0);
+        assert!(first.contains("Link Extraction"));
+        assert!(!viewer.page_text(1).contains("Link Extraction"));
+        assert_eq!(viewer.page_text(100), "");
+
+        let document = viewer.document_text();
+        assert_eq!(document.split('\x0c').count(), 3);
+        assert!(document.starts_with(&first));
+        Ok(())
+    }
+
     #[test]
     fn test_plaintext_search_link_extraction_on_page_0() -> Result<()> {
         let viewer = PdfViewer::from_path(PathBuf::from("assets/links.pdf"))?;
@@ -1983,6 +4605,7 @@ mod tests {
             &viewer.text_contents,
             "Link Extraction",
             SearchMethod::PlainText,
+            SearchFlags::default(),
             &viewer.char_bboxes,
         );
         assert_eq!(result.len(), 1, "should find exactly one 'Link Extraction'");
@@ -2008,6 +4631,7 @@ mod tests {
             &viewer.text_contents,
             "Code Blocks",
             SearchMethod::PlainText,
+            SearchFlags::default(),
             &viewer.char_bboxes,
         );
         assert_eq!(result.len(), 1, "should find exactly one 'Code Blocks'");
@@ -2027,6 +4651,7 @@ mod tests {
             &viewer.text_contents,
             "•",
             SearchMethod::PlainText,
+            SearchFlags::default(),
             &viewer.char_bboxes,
         );
         assert!(!result.is_empty(), "should find bullet characters");
@@ -2048,6 +4673,7 @@ mod tests {
             &viewer.text_contents,
             "Link Extraction",
             SearchMethod::Regex,
+            SearchFlags::default(),
             &viewer.char_bboxes,
         );
         assert_eq!(
@@ -2072,6 +4698,7 @@ mod tests {
             &viewer.text_contents,
             "Code Blocks",
             SearchMethod::Regex,
+            SearchFlags::default(),
             &viewer.char_bboxes,
         );
         assert_eq!(
@@ -2091,12 +4718,14 @@ mod tests {
             &viewer.text_contents,
             "Link Extraction",
             SearchMethod::PlainText,
+            SearchFlags::default(),
             &viewer.char_bboxes,
         );
         let regex = find_search_matches(
             &viewer.text_contents,
             "Link Extraction",
             SearchMethod::Regex,
+            SearchFlags::default(),
             &viewer.char_bboxes,
         );
         assert_eq!(
@@ -2120,12 +4749,14 @@ mod tests {
             &viewer.text_contents,
             "Code Blocks",
             SearchMethod::PlainText,
+            SearchFlags::default(),
             &viewer.char_bboxes,
         );
         let regex = find_search_matches(
             &viewer.text_contents,
             "Code Blocks",
             SearchMethod::Regex,
+            SearchFlags::default(),
             &viewer.char_bboxes,
         );
         assert_eq!(
@@ -2142,6 +4773,7 @@ mod tests {
             &viewer.text_contents,
             "XYZ_NONEXISTENT",
             SearchMethod::PlainText,
+            SearchFlags::default(),
             &viewer.char_bboxes,
         );
         assert!(result.is_empty(), "should not find nonexistent text");
@@ -2168,6 +4800,45 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_words_are_split_on_whitespace_and_lines() {
+        let text = "ab cd";
+        let char_rect =
+            |x: f32, y: f32| Rect::from_points(Vector::new(x, y), Vector::new(x + 5.0, y + 10.0));
+        let bboxes = vec![
+            (0, 0, char_rect(0.0, 0.0)),
+            (0, 1, char_rect(5.0, 0.0)),
+            (0, 2, char_rect(10.0, 0.0)),
+            (0, 3, char_rect(15.0, 0.0)),
+            // "d" starts the next line
+            (0, 4, char_rect(0.0, 20.0)),
+        ];
+        let words: Vec<String> = extract_words(text, &bboxes)
+            .into_iter()
+            .map(|w| w.text)
+            .collect();
+        assert_eq!(words, vec!["ab", "c", "d"]);
+    }
+
+    #[test]
+    fn test_deleting_an_annotation() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/links_commented.pdf"))?;
+        let count = viewer.annotations().len();
+        assert!(count > 0, "links_commented.pdf should list its comments");
+        assert!(
+            viewer
+                .annotations()
+                .iter()
+                .all(|a| a.kind != PdfAnnotationType::Link)
+        );
+
+        assert!(!viewer.modified);
+        let _ = viewer.update(PdfMessage::DeleteAnnotation(0));
+        assert_eq!(viewer.annotations().len(), count - 1);
+        assert!(viewer.modified);
+        Ok(())
+    }
+
     #[test]
     fn test_no_comments_on_plain_pdf() -> Result<()> {
         let viewer = PdfViewer::from_path(PathBuf::from("assets/links.pdf"))?;
@@ -2177,4 +4848,344 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_key_combinations_use_the_hint_chars() {
+        let keys = generate_key_combinations(5, &['a', 's', 'd']);
+        assert_eq!(keys, vec!["a", "s", "d", "aa", "as"]);
+    }
+
+    #[test]
+    fn test_selection_preview_covers_the_lines_that_get_copied() {
+        let rect = |x0, y0, x1, y1| Rect::from_points(Vector::new(x0, y0), Vector::new(x1, y1));
+        let chars = [
+            (0, 0, rect(10.0, 10.0, 20.0, 20.0)),
+            (0, 1, rect(20.0, 10.0, 30.0, 20.0)),
+            (0, 2, rect(10.0, 30.0, 20.0, 40.0)),
+            (1, 3, rect(10.0, 10.0, 20.0, 20.0)),
+        ];
+        // The first line of page 0 and nothing of page 1, which isn't selected
+        let lines = selected_lines(&chars, &[(0, rect(15.0, 5.0, 40.0, 25.0))]);
+        assert_eq!(lines, vec![(0, rect(10.0, 10.0, 30.0, 20.0))]);
+
+        let lines = selected_lines(
+            &chars,
+            &[
+                (0, rect(0.0, 35.0, 50.0, 50.0)),
+                (1, rect(0.0, 0.0, 50.0, 15.0)),
+            ],
+        );
+        assert_eq!(
+            lines,
+            vec![
+                (0, rect(10.0, 30.0, 20.0, 40.0)),
+                (1, rect(10.0, 10.0, 20.0, 20.0))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pinches_zoom_smoothly_and_cancel_out() {
+        assert_eq!(pinch_zoom_factor(0.0, 1.0), 1.0);
+        assert_eq!(pinch_zoom_factor(PINCH_PIXELS_PER_DOUBLING, 1.0), 2.0);
+        assert_eq!(
+            pinch_zoom_factor(PINCH_PIXELS_PER_DOUBLING, 0.5),
+            2f32.sqrt()
+        );
+        let there_and_back = pinch_zoom_factor(37.0, 1.3) * pinch_zoom_factor(-37.0, 1.3);
+        assert!((there_and_back - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_hint_text_follows_the_zoom_within_limits() {
+        assert_eq!(hint_text_size(1.0), HINT_TEXT_SIZE);
+        assert_eq!(hint_text_size(0.1), MIN_HINT_TEXT_SIZE);
+        assert_eq!(hint_text_size(8.0), MAX_HINT_TEXT_SIZE);
+    }
+
+    #[test]
+    fn test_hint_labels_stay_inside_the_viewport() {
+        let viewport = iced::Size::new(100.0, 100.0);
+        let size = iced::Size::new(20.0, 10.0);
+        let rect = |x0, y0, x1, y1| Rect::from_points(Vector::new(x0, y0), Vector::new(x1, y1));
+
+        // Right of the link when there is room
+        let pos = hint_label_position(&rect(10.0, 40.0, 30.0, 50.0), size, viewport);
+        assert_eq!(pos, iced::Point::new(32.0, 40.0));
+        // Left of a link at the right edge
+        let pos = hint_label_position(&rect(70.0, 40.0, 95.0, 50.0), size, viewport);
+        assert_eq!(pos, iced::Point::new(48.0, 40.0));
+        // Above a link spanning the whole width
+        let pos = hint_label_position(&rect(0.0, 40.0, 100.0, 50.0), size, viewport);
+        assert_eq!(pos, iced::Point::new(0.0, 28.0));
+    }
+
+    #[test]
+    fn test_link_hitboxes_follow_layout_changes() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/links.pdf"))?;
+        let viewport = iced::Size::new(1200.0, 800.0);
+        viewer.set_viewport(viewport);
+        let _ = viewer.update(PdfMessage::SetLayout(PageLayout::DoublePage));
+
+        let double = viewer.visible_links(viewport);
+        assert!(!double.is_empty(), "links.pdf should have visible links");
+        for layout in [
+            PageLayout::SinglePage,
+            PageLayout::DoublePage,
+            PageLayout::SinglePage,
+        ] {
+            let _ = viewer.update(PdfMessage::SetLayout(layout));
+            assert_eq!(
+                viewer.visible_links(viewport),
+                viewer.compute_visible_links(viewport),
+                "Cached hitboxes should match the {layout:?} layout"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_links_under_the_mouse_survive_zooming() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/links.pdf"))?;
+        let viewport = iced::Size::new(1200.0, 800.0);
+        viewer.set_viewport(viewport);
+        let index = viewer.link_index.clone();
+        for message in [PdfMessage::ZoomIn, PdfMessage::ZoomIn, PdfMessage::ZoomOut] {
+            let _ = viewer.update(message);
+            for (link, rect) in viewer.compute_visible_links(viewport) {
+                assert_eq!(viewer.link_at(viewport, rect.center()), Some(link));
+            }
+        }
+        // The page space index is built once per document, not per zoom level
+        assert_eq!(format!("{index:?}"), format!("{:?}", viewer.link_index));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hovered_link_is_updated_when_the_layout_changes() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/links.pdf"))?;
+        let viewport = iced::Size::new(1200.0, 800.0);
+        viewer.set_viewport(viewport);
+        let _ = viewer.update(PdfMessage::SetLayout(PageLayout::DoublePage));
+
+        let (link, rect) = viewer.visible_links(viewport)[0];
+        let _ = viewer.update(PdfMessage::MouseMoved(rect.center()));
+        assert_eq!(viewer.hovered_link, Some(link));
+
+        let _ = viewer.update(PdfMessage::SetLayout(PageLayout::SinglePage));
+        let under_mouse = viewer
+            .compute_visible_links(viewport)
+            .into_iter()
+            .find(|(_, rect)| rect.contains(viewer.local_mouse_pos()))
+            .map(|(link, _)| link);
+        assert_eq!(viewer.hovered_link, under_mouse);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mouse_zoom_keeps_the_right_hand_page_under_the_cursor() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/links.pdf"))?;
+        let viewport = iced::Size::new(1200.0, 800.0);
+        viewer.set_viewport(viewport);
+        let _ = viewer.update(PdfMessage::SetLayout(PageLayout::DoublePage));
+
+        let mapping = viewer.page_mapping(viewport).unwrap();
+        let right_page = mapping.screen_rect(1).unwrap();
+        let cursor = Vector::new(
+            right_page.x0.x + right_page.width() * 0.75,
+            right_page.center().y,
+        );
+        let (page_idx, point) = mapping.screen_to_page(cursor).unwrap();
+        assert_eq!(page_idx, 1);
+
+        let _ = viewer.update(PdfMessage::MouseMoved(cursor));
+        let _ = viewer.update(PdfMessage::MouseAction(MouseAction::ZoomIn, true));
+        let moved_to = viewer
+            .page_mapping(viewport)
+            .unwrap()
+            .page_point_to_screen(1, point)
+            .unwrap();
+        assert!(
+            (moved_to - cursor).norm_squared() < 1e-2,
+            "The point under the cursor moved from {cursor:?} to {moved_to:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn a_corrupt_page_doesnt_stop_the_others_from_opening() -> Result<()> {
+        let viewer = PdfViewer::from_path(PathBuf::from("assets/corrupt-page.pdf"))?;
+        assert_eq!(viewer.display_lists.len(), 3);
+        assert_eq!(viewer.links.len(), 3);
+        assert!(viewer.page_text(0).contains("First page"));
+        assert!(viewer.page_text(2).contains("Last page"));
+        assert!(
+            viewer.links[0]
+                .iter()
+                .any(|link| link.uri == "https://example.com")
+        );
+        assert!(viewer.links[1].is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn thumbnails_that_fail_stop_the_loading() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/links.pdf"))?;
+        let _ = viewer.update(PdfMessage::ToggleOverview);
+        // Every worker fails to open the document
+        viewer
+            .scheduler
+            .get_mut()
+            .reset(Some(PathBuf::from("assets/missing.pdf")));
+        let _ = viewer.page_thumbnails();
+        assert!(viewer.is_loading_thumbnails());
+        for _ in 0..200 {
+            if !viewer.is_loading_thumbnails() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            let _ = viewer.page_thumbnails();
+        }
+        assert!(!viewer.is_loading_thumbnails());
+        Ok(())
+    }
+
+    #[test]
+    fn resizing_the_window_rarely_reallocates_pixmaps() {
+        let mut pooled: Option<Pixmap> = None;
+        let mut allocations = 0;
+        // Dragging the edge of the window one pixel per frame
+        for width in 400..800 {
+            if !pooled
+                .as_ref()
+                .is_some_and(|pix| pixmap_fits(pix, width, 600))
+            {
+                let (w, h) = pixmap_size(pooled.as_ref(), width, 600);
+                assert!(w >= width && h >= 600);
+                pooled = Some(Pixmap::new_with_w_h(&Colorspace::device_rgb(), w, h, true).unwrap());
+                allocations += 1;
+            }
+        }
+        assert!(allocations <= 3, "{allocations} allocations");
+        // A page that became much smaller doesn't keep the large pixmap
+        assert!(!pixmap_fits(pooled.as_ref().unwrap(), 100, 100));
+    }
+
+    #[test]
+    fn page_references_name_the_section() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/links.pdf"))?;
+        let item = |title: &str, page, children| OutlineItem {
+            title: title.to_string(),
+            page: Some(page),
+            level: 0,
+            children,
+        };
+        viewer.outline = vec![item(
+            "1 Introduction",
+            1,
+            vec![item(" 1.1 Scope ", 2, vec![])],
+        )];
+        assert_eq!(viewer.page_reference(0), "links.pdf — page 1");
+        assert_eq!(
+            viewer.page_reference(1),
+            "links.pdf — page 2 (1 Introduction)"
+        );
+        assert_eq!(viewer.page_reference(3), "links.pdf — page 4 (1.1 Scope)");
+        Ok(())
+    }
+
+    #[test]
+    fn first_and_last_page_are_jumps() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/multiple-page-layouts.pdf"))?;
+        viewer.set_viewport(iced::Size::new(800.0, 600.0));
+        viewer.layout = PageLayout::SinglePage;
+        let last = viewer.page_count()? as usize - 1;
+        assert!(viewer.is_jumpable_action(&PdfMessage::LastPage));
+
+        let _ = viewer.update(PdfMessage::LastPage);
+        assert_eq!(viewer.current_page(), last);
+        let _ = viewer.update(PdfMessage::FirstPage);
+        assert_eq!(viewer.current_page(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn blocks_are_stepped_through_in_reading_order() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/text-copy-test.pdf"))?;
+        viewer.set_viewport(iced::Size::new(800.0, 1200.0));
+        viewer.layout = PageLayout::SinglePage;
+        assert!(viewer.reading_blocks(0).len() >= 3);
+        let block = |viewer: &PdfViewer| viewer.current_block.map(|(page, idx, _)| (page, idx));
+
+        let _ = viewer.update(PdfMessage::NextBlock);
+        let (page, first) = block(&viewer).expect("The first page has text");
+        let _ = viewer.update(PdfMessage::NextBlock);
+        assert_eq!(block(&viewer), viewer.block_after(page, Some(first)));
+        let _ = viewer.update(PdfMessage::PreviousBlock);
+        assert_eq!(block(&viewer), Some((page, first)));
+        Ok(())
+    }
+
+    #[test]
+    fn saving_over_the_open_file_keeps_it_intact() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("miro-save-as-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        for asset in ["text-copy-test.pdf", "pages.cbz"] {
+            let path = dir.join(asset);
+            std::fs::copy(PathBuf::from("assets").join(asset), &path)?;
+            let mut viewer = PdfViewer::from_path(path.clone())?;
+            let pages = viewer.doc.page_count()?;
+            viewer.save_as(path.clone())?;
+            assert_eq!(archive::open(&path)?.page_count()?, pages, "{asset}");
+        }
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn dark_mode_style_changes_apply_to_open_documents() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/text-copy-test.pdf"))?;
+        let size = iced::Size::new(800.0, 1000.0);
+        viewer.set_viewport(size);
+        viewer.set_pdf_dark_mode(true);
+        viewer.set_pdf_dark_mode_style(PdfDarkModeStyle::Invert);
+        let inverted = viewer.draw_visible_page(0, size)?;
+        assert!(!viewer.render_job(0, 1.0, RenderPriority::Visible).recolor);
+
+        viewer.set_pdf_dark_mode_style(PdfDarkModeStyle::Recolor);
+        assert!(viewer.render_job(0, 1.0, RenderPriority::Visible).recolor);
+        assert_ne!(viewer.draw_visible_page(0, size)?, inverted);
+        Ok(())
+    }
+
+    #[test]
+    fn reading_order_is_cached_until_the_pages_change() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/text-copy-test.pdf"))?;
+        let blocks = viewer.reading_blocks(0);
+        assert_eq!(viewer.reading_blocks.borrow().get(&0), Some(&blocks));
+        assert_eq!(viewer.reading_blocks(0), blocks);
+
+        let _ = viewer.update(PdfMessage::FileChanged);
+        assert!(viewer.reading_blocks.borrow().is_empty());
+        assert_eq!(viewer.reading_blocks(0), blocks);
+        Ok(())
+    }
+
+    #[test]
+    fn fitting_a_column_makes_it_as_wide_as_the_screen() -> Result<()> {
+        let mut viewer = PdfViewer::from_path(PathBuf::from("assets/text-copy-test.pdf"))?;
+        viewer.set_viewport(iced::Size::new(800.0, 600.0));
+        viewer.layout = PageLayout::SinglePage;
+        let (x0, x1) = viewer.column_in_view(0).expect("The page has text");
+
+        let _ = viewer.update(PdfMessage::ZoomFitColumn);
+        let effective_scale = viewer.scale * viewer.fractional_scaling;
+        assert!(((x1 - x0) * effective_scale - (800.0 - 2.0 * BLOCK_MARGIN)).abs() < 1.0);
+        let mapping = viewer.page_mapping(iced::Size::new(800.0, 600.0)).unwrap();
+        let left = mapping
+            .page_point_to_screen(0, Vector::new(x0, 0.0))
+            .unwrap();
+        assert!((left.x - BLOCK_MARGIN).abs() < 1.0);
+        Ok(())
+    }
 }
@@ -4,16 +4,22 @@ use iced::{
 };
 use iced_aw::menu::{self, primary, DrawPath};
 use keybinds2::{KeySeq, Keybind};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::{
     CONFIG,
     app::{self, AppMessage},
+    closed_tabs::ClosedTab,
     common_menu::{self, CommonMenuItem},
     config::BindableMessage,
 };
 
-pub fn create_menu_bar(_pdf_idx: usize, recent_files: &[PathBuf]) -> Element<'static, AppMessage> {
+pub fn create_menu_bar(
+    _pdf_idx: usize,
+    recent_files: &[PathBuf],
+    closed_tabs: &[ClosedTab],
+    workspaces: &[String],
+) -> Element<'static, AppMessage> {
     let cfg = CONFIG.read().unwrap();
     let mut bar_items = Vec::new();
 
@@ -33,8 +39,32 @@ pub fn create_menu_bar(_pdf_idx: usize, recent_files: &[PathBuf]) -> Element<'st
                         }
                     }
                 }
+                CommonMenuItem::RecentlyClosed => {
+                    if !closed_tabs.is_empty() {
+                        descs.push(ItemDesc::Label("Recently Closed".to_string()));
+                        for (i, tab) in closed_tabs.iter().enumerate() {
+                            descs.push(ItemDesc::ClosedTab(i, tab.path.clone()));
+                        }
+                    }
+                }
+                CommonMenuItem::Workspaces => {
+                    if !workspaces.is_empty() {
+                        descs.push(ItemDesc::Label("Workspaces".to_string()));
+                        for name in workspaces {
+                            descs.push(ItemDesc::Workspace(name.clone()));
+                        }
+                    }
+                }
+                CommonMenuItem::Tools => {
+                    for (i, (name, _)) in cfg.tools.iter().enumerate() {
+                        descs.push(ItemDesc::Tool(i, name.clone()));
+                    }
+                }
                 CommonMenuItem::Separator => {
-                    descs.push(ItemDesc::Separator);
+                    // Not needed above the first item, such as when no tools are configured
+                    if !descs.is_empty() {
+                        descs.push(ItemDesc::Separator);
+                    }
                 }
             }
         }
@@ -61,6 +91,21 @@ pub fn create_menu_bar(_pdf_idx: usize, recent_files: &[PathBuf]) -> Element<'st
                 ItemDesc::RecentFile(path) => {
                     menu_items.push(menu::Item::new(create_recent_file_button(path)));
                 }
+                ItemDesc::ClosedTab(i, path) => {
+                    menu_items.push(menu::Item::new(create_file_button(
+                        &path,
+                        AppMessage::ReopenClosedTab(i),
+                    )));
+                }
+                ItemDesc::Workspace(name) => {
+                    menu_items.push(menu::Item::new(create_file_button(
+                        Path::new(&name),
+                        AppMessage::OpenWorkspace(name.clone()),
+                    )));
+                }
+                ItemDesc::Tool(i, name) => {
+                    menu_items.push(menu::Item::new(create_tool_button(i, name)));
+                }
                 ItemDesc::Separator => {
                     menu_items.push(menu::Item::new(menu_separator()));
                 }
@@ -116,6 +161,11 @@ enum ItemDesc {
     Button(BindableMessage),
     Label(String),
     RecentFile(PathBuf),
+    /// Index into the recently closed tabs and the path of that tab
+    ClosedTab(usize, PathBuf),
+    Workspace(String),
+    /// Index into the configured tools and their name
+    Tool(usize, String),
     Separator,
 }
 
@@ -192,6 +242,13 @@ fn menu_separator() -> Element<'static, AppMessage> {
 
 fn create_recent_file_button(
     path: PathBuf,
+) -> button::Button<'static, AppMessage, Theme, iced::Renderer> {
+    create_file_button(&path, AppMessage::OpenFile(path.clone()))
+}
+
+fn create_file_button(
+    path: &Path,
+    on_press: AppMessage,
 ) -> button::Button<'static, AppMessage, Theme, iced::Renderer> {
     let file_name = path
         .file_name()
@@ -208,7 +265,7 @@ fn create_recent_file_button(
                 }
             })
         ],
-        AppMessage::OpenFile(path),
+        on_press,
     )
     .width(Length::Fill)
     .style(move |theme, status| {
@@ -231,6 +288,32 @@ fn create_recent_file_button(
     })
 }
 
+fn create_tool_button(
+    idx: usize,
+    name: String,
+) -> button::Button<'static, AppMessage, Theme, iced::Renderer> {
+    app::base_button(text(name), AppMessage::RunTool(idx))
+        .width(Length::Fill)
+        .style(move |theme, status| {
+            let palette = theme.extended_palette();
+            let pair = match status {
+                button::Status::Active => palette.background.weak,
+                button::Status::Hovered => palette.background.base,
+                button::Status::Pressed => palette.background.strong,
+                button::Status::Disabled => palette.secondary.weak,
+            };
+            button::Style {
+                text_color: pair.text,
+                background: Some(Background::Color(pair.color)),
+                border: Border {
+                    radius: border::Radius::default(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        })
+}
+
 fn menu_button(
     label: String,
     msg: BindableMessage,
@@ -1,6 +1,7 @@
 use muda::accelerator::{KeyAccelerator};
 use muda::AcceleratorParseError;
 use crate::CONFIG;
+use crate::closed_tabs::ClosedTab;
 use crate::common_menu::CommonMenuItem;
 use keybinds2::{Keybind};
 use crate::config::BindableMessage;
@@ -12,12 +13,14 @@ use std::str::FromStr;
 pub struct Menu {
     menu: muda::Menu,
     recent_files_submenu: muda::Submenu,
+    closed_tabs_submenu: muda::Submenu,
+    workspaces_submenu: muda::Submenu,
     // These are special in the macos menu bar
     window_submenu: muda::Submenu,
     help_submenu: muda::Submenu,
 }
 impl Menu {
-    pub fn new(recent_files: &[PathBuf]) -> Self {
+    pub fn new(recent_files: &[PathBuf], workspaces: &[String]) -> Self {
         let menu = muda::Menu::new();
 
         let app_submenu = muda::Submenu::new("App", true);
@@ -38,6 +41,8 @@ impl Menu {
 
         let skeleton = crate::common_menu::items();
         let recent_files_submenu = muda::Submenu::new("Recent Files", true);
+        let closed_tabs_submenu = muda::Submenu::new("Recently Closed", true);
+        let workspaces_submenu = muda::Submenu::new("Workspaces", true);
 
         for tuple in skeleton {
             let submenu = muda::Submenu::new(format!("&{}", tuple.0), true);
@@ -59,10 +64,29 @@ impl Menu {
                         }
                         submenu.append(&recent_files_submenu).unwrap();
                     }
+                    CommonMenuItem::RecentlyClosed => {
+                        submenu.append(&closed_tabs_submenu).unwrap();
+                    }
+                    CommonMenuItem::Workspaces => {
+                        for name in workspaces {
+                            workspaces_submenu
+                                .append(&new_workspace_menu_item(name))
+                                .unwrap();
+                        }
+                        submenu.append(&workspaces_submenu).unwrap();
+                    }
+                    CommonMenuItem::Tools => {
+                        let tools = CONFIG.read().unwrap().tools.clone();
+                        for (i, (name, _)) in tools.iter().enumerate() {
+                            submenu.append(&new_tool_menu_item(i, name)).unwrap();
+                        }
+                    }
                     CommonMenuItem::Separator => {
-                        submenu
-                            .append(&muda::PredefinedMenuItem::separator())
-                            .unwrap();
+                        if !submenu.items().is_empty() {
+                            submenu
+                                .append(&muda::PredefinedMenuItem::separator())
+                                .unwrap();
+                        }
                     }
                 }
             }
@@ -88,6 +112,8 @@ impl Menu {
         Self {
             menu,
             recent_files_submenu,
+            closed_tabs_submenu,
+            workspaces_submenu,
             window_submenu,
             help_submenu,
         }
@@ -104,6 +130,28 @@ impl Menu {
         }
     }
 
+    pub fn update_closed_tabs(&self, closed_tabs: &[ClosedTab]) {
+        for _ in 0..(self.closed_tabs_submenu.items().len()) {
+            self.closed_tabs_submenu.remove_at(0).unwrap();
+        }
+        for (i, tab) in closed_tabs.iter().enumerate() {
+            self.closed_tabs_submenu
+                .append(&new_closed_tab_menu_item(i, tab))
+                .unwrap();
+        }
+    }
+
+    pub fn update_workspaces(&self, workspaces: &[String]) {
+        for _ in 0..(self.workspaces_submenu.items().len()) {
+            self.workspaces_submenu.remove_at(0).unwrap();
+        }
+        for name in workspaces {
+            self.workspaces_submenu
+                .append(&new_workspace_menu_item(name))
+                .unwrap();
+        }
+    }
+
     pub fn init(&self) {
         #[cfg(target_os = "macos")]
         {
@@ -123,6 +171,34 @@ pub fn new_recent_file_menu_item(path: &PathBuf) -> muda::MenuItem {
     return menu_item;
 }
 
+const CLOSED_TAB_ID_PREFIX: &str = "closed-tab:";
+
+pub fn new_closed_tab_menu_item(idx: usize, tab: &ClosedTab) -> muda::MenuItem {
+    let file_name = tab
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| tab.path.to_string_lossy().to_string());
+    muda::MenuItem::with_id(
+        format!("{CLOSED_TAB_ID_PREFIX}{idx}"),
+        file_name,
+        true,
+        None,
+    )
+}
+
+const WORKSPACE_ID_PREFIX: &str = "workspace:";
+
+pub fn new_workspace_menu_item(name: &str) -> muda::MenuItem {
+    muda::MenuItem::with_id(format!("{WORKSPACE_ID_PREFIX}{name}"), name, true, None)
+}
+
+const TOOL_ID_PREFIX: &str = "tool:";
+
+pub fn new_tool_menu_item(idx: usize, name: &str) -> muda::MenuItem {
+    muda::MenuItem::with_id(format!("{TOOL_ID_PREFIX}{idx}"), name, true, None)
+}
+
 pub fn new_menu_item(label: &str, msg: BindableMessage) -> muda::MenuItem {
     let cfg = CONFIG.read().unwrap();
     let menu_id = msg.to_string();
@@ -143,7 +219,24 @@ pub fn keybind_to_keyaccelerator(
     keybind: Keybind<BindableMessage>,
 ) -> Result<KeyAccelerator, AcceleratorParseError> {
     let keybind_as_string = keybind.seq.as_slice()[0].to_string();
-    return KeyAccelerator::from_str(&keybind_as_string);
+    return KeyAccelerator::from_str(&accelerator_string(&keybind_as_string));
+}
+
+// keybinds2 writes Ctrl+Shift+t as "Ctrl+T" while muda ignores the case of letters, so the Shift
+// has to be spelled out or the menu would show (and trigger on) Ctrl+t.
+fn accelerator_string(key_input: &str) -> String {
+    let (mods, key) = match key_input.rsplit_once('+') {
+        Some((mods, key)) if !key.is_empty() => (Some(mods), key),
+        _ => (None, key_input),
+    };
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_uppercase() => match mods {
+            Some(mods) => format!("{mods}+Shift+{}", c.to_lowercase()),
+            None => format!("Shift+{}", c.to_lowercase()),
+        },
+        _ => key_input.to_string(),
+    }
 }
 
 // Dummy debug for now (muda doesn't implement debug for some reason?)
@@ -162,6 +255,20 @@ pub fn menu_listener() -> impl iced::futures::Stream<Item = AppMessage> {
                     Some(msg) => {
                         let _ = sender.try_send(msg.into());
                     }
+                    None if id.starts_with(CLOSED_TAB_ID_PREFIX) => {
+                        if let Ok(idx) = id[CLOSED_TAB_ID_PREFIX.len()..].parse::<usize>() {
+                            let _ = sender.try_send(AppMessage::ReopenClosedTab(idx));
+                        }
+                    }
+                    None if id.starts_with(TOOL_ID_PREFIX) => {
+                        if let Ok(idx) = id[TOOL_ID_PREFIX.len()..].parse::<usize>() {
+                            let _ = sender.try_send(AppMessage::RunTool(idx));
+                        }
+                    }
+                    None if id.starts_with(WORKSPACE_ID_PREFIX) => {
+                        let name = id[WORKSPACE_ID_PREFIX.len()..].to_string();
+                        let _ = sender.try_send(AppMessage::OpenWorkspace(name));
+                    }
                     None => {
                         let _ = sender.try_send(AppMessage::OpenFile(PathBuf::from(id)));
                     }
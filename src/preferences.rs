@@ -0,0 +1,318 @@
+use std::path::PathBuf;
+
+use anyhow::{Result, anyhow};
+
+use crate::{config::Config, paths};
+
+/// Every setting and binding along with its default value
+const DEFAULT_CONFIG: &str = include_str!("../assets/default.conf");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Set,
+    Bind,
+}
+
+/// A setting or key binding in the preferences window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub kind: EntryKind,
+    /// The name of the setting, or the action of the binding
+    pub name: String,
+    /// The rest of the `Set` line, or the key of the binding
+    pub value: String,
+    /// What the value was when the window was opened
+    original: String,
+    /// Whether the config file has a line for this, otherwise it comes from the defaults
+    in_file: bool,
+    pub error: Option<String>,
+}
+
+impl Entry {
+    fn line(&self, value: &str) -> String {
+        match self.kind {
+            EntryKind::Set => format!("Set {} {value}", self.name),
+            // Key sequences like "Z Z" are quoted to stay one part of the line
+            EntryKind::Bind if value.contains(' ') && !value.starts_with('"') => {
+                format!("Bind \"{value}\" {}", self.name)
+            }
+            EntryKind::Bind => format!("Bind {value} {}", self.name),
+        }
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.value != self.original
+    }
+
+    /// Whether `line` of a config file is the one this entry was read from.
+    fn is_line(&self, line: &str) -> bool {
+        let Ok(parts) = Config::parse_line_parts(line.trim()) else {
+            return false;
+        };
+        match (self.kind, parts.as_slice()) {
+            (EntryKind::Set, [command, name, ..]) => command == "Set" && *name == self.name,
+            (EntryKind::Bind, [command, key, action]) => {
+                command == "Bind" && *key == self.original && *action == self.name
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The `Set` and `Bind` lines of a config file in order. Everything else is only kept as text.
+fn read_entries(config: &str, in_file: bool) -> Vec<Entry> {
+    let mut entries = vec![];
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        let Ok(parts) = Config::parse_line_parts(line) else {
+            continue;
+        };
+        let (kind, name, value) = match parts.as_slice() {
+            [command, name, ..] if command == "Set" => {
+                // Keeps the value as it was written, quotes included
+                let value = line[3..].trim_start()[name.len()..].trim();
+                (EntryKind::Set, name.clone(), value.to_string())
+            }
+            [command, key, action] if command == "Bind" => {
+                (EntryKind::Bind, action.clone(), key.clone())
+            }
+            _ => continue,
+        };
+        entries.push(Entry {
+            kind,
+            name,
+            original: value.clone(),
+            value,
+            in_file,
+            error: None,
+        });
+    }
+    entries
+}
+
+/// The settings and key bindings of the config file, edited in a window. Every setting is listed
+/// with its default value unless the config file sets it. Keys of default bindings are bound in
+/// addition to the default, since the config file can't remove bindings.
+#[derive(Debug)]
+pub struct Preferences {
+    pub path: PathBuf,
+    /// The config file as it was when the window opened, edits replace single lines of it so the
+    /// comments stay where they are
+    original: String,
+    pub entries: Vec<Entry>,
+    /// Only entries containing this are shown
+    pub filter: String,
+}
+
+impl Preferences {
+    pub fn load() -> Result<Self> {
+        let path = paths::config_file()?;
+        let original = match std::fs::read_to_string(&path) {
+            Ok(original) => original,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self::new(path, original))
+    }
+
+    pub(crate) fn new(path: PathBuf, original: String) -> Self {
+        let mut entries = read_entries(&original, true);
+        // Later lines win, only the last line of a setting is listed
+        let mut seen = std::collections::HashSet::new();
+        for i in (0..entries.len()).rev() {
+            if entries[i].kind == EntryKind::Set && !seen.insert(entries[i].name.clone()) {
+                entries.remove(i);
+            }
+        }
+        for default in read_entries(DEFAULT_CONFIG, false) {
+            let overridden = entries.iter().any(|entry| {
+                entry.kind == default.kind
+                    && entry.name == default.name
+                    && (entry.kind == EntryKind::Set || entry.value == default.value)
+            });
+            if !overridden {
+                entries.push(default);
+            }
+        }
+        // Settings first, both in the order of the default config with the user's own after
+        entries.sort_by_key(|entry| entry.kind == EntryKind::Bind);
+        Self {
+            path,
+            original,
+            entries,
+            filter: String::new(),
+        }
+    }
+
+    /// Changes the value of entry `idx` and checks it the same way the config file is checked.
+    pub fn edit(&mut self, idx: usize, value: String) {
+        let Some(entry) = self.entries.get_mut(idx) else {
+            return;
+        };
+        let result = Config::parse_with_errors(&entry.line(value.trim()));
+        entry.error = result.errors.first().map(|e| e.message.clone());
+        if value.trim().is_empty() {
+            entry.error = Some("A value is required".to_string());
+        }
+        entry.value = value;
+    }
+
+    /// The indices of the entries matching [Preferences::filter].
+    pub fn visible(&self) -> impl Iterator<Item = (usize, &Entry)> {
+        let filter = self.filter.to_lowercase();
+        self.entries.iter().enumerate().filter(move |(_, entry)| {
+            entry.name.to_lowercase().contains(&filter)
+                || entry.value.to_lowercase().contains(&filter)
+        })
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.entries.iter().any(|entry| entry.error.is_some())
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.entries.iter().any(Entry::is_changed)
+    }
+
+    /// The config file with the edits. Lines of changed entries are replaced where they are and
+    /// entries that only had a default value are added at the end.
+    fn config_file(&self) -> String {
+        let mut lines: Vec<String> = self.original.lines().map(str::to_string).collect();
+        let mut added = vec![];
+        for entry in self.entries.iter().filter(|entry| entry.is_changed()) {
+            let line = entry.line(entry.value.trim());
+            let existing = entry
+                .in_file
+                .then(|| lines.iter().rposition(|l| entry.is_line(l)))
+                .flatten();
+            match existing {
+                Some(idx) => lines[idx] = line,
+                None => added.push(line),
+            }
+        }
+        if !added.is_empty() {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push("# Changed in the preferences window".to_string());
+            lines.extend(added);
+        }
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        contents
+    }
+
+    /// Writes the edits to the config file, refusing to while some value is invalid.
+    pub fn save(&self) -> Result<()> {
+        if self.has_errors() {
+            return Err(anyhow!("Fix the invalid values before saving"));
+        }
+        paths::ensure_writable()?;
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        paths::write(&self.path, self.config_file())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_CONFIG: &str = "# My settings\n\
+        Set DarkModeUi False\n\
+        Bind Ctrl+q CloseTab\n\
+        \n\
+        # Looking things up\n\
+        Set LookupCommand \"dict %s\"\n";
+
+    fn preferences() -> Preferences {
+        Preferences::new(PathBuf::from("miro.conf"), USER_CONFIG.to_string())
+    }
+
+    fn find(preferences: &Preferences, kind: EntryKind, name: &str) -> usize {
+        preferences
+            .entries
+            .iter()
+            .position(|entry| entry.kind == kind && entry.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn settings_are_listed_with_the_value_from_the_file_or_the_default() {
+        let preferences = preferences();
+        let dark_mode = &preferences.entries[find(&preferences, EntryKind::Set, "DarkModeUi")];
+        assert_eq!(dark_mode.value, "False");
+        assert!(dark_mode.in_file);
+        let lookup = &preferences.entries[find(&preferences, EntryKind::Set, "LookupCommand")];
+        assert_eq!(lookup.value, "\"dict %s\"");
+        let borders = &preferences.entries[find(&preferences, EntryKind::Set, "PageBorders")];
+        assert_eq!(borders.value, "True");
+        assert!(!borders.in_file);
+        assert!(
+            preferences
+                .entries
+                .iter()
+                .any(|e| e.kind == EntryKind::Bind && e.name == "CloseTab" && e.value == "Ctrl+q")
+        );
+        // Settings come before bindings
+        let first_bind = preferences
+            .entries
+            .iter()
+            .position(|e| e.kind == EntryKind::Bind)
+            .unwrap();
+        assert!(
+            preferences.entries[first_bind..]
+                .iter()
+                .all(|e| e.kind == EntryKind::Bind)
+        );
+    }
+
+    #[test]
+    fn edits_are_validated_like_the_config_file() {
+        let mut preferences = preferences();
+        let idx = find(&preferences, EntryKind::Set, "TrackpadSensitivity");
+        preferences.edit(idx, "fast".to_string());
+        assert!(
+            preferences.entries[idx]
+                .error
+                .as_ref()
+                .is_some_and(|e| e.contains("TrackpadSensitivity"))
+        );
+        assert!(preferences.has_errors());
+        assert!(preferences.save().is_err());
+        preferences.edit(idx, "2.5".to_string());
+        assert_eq!(preferences.entries[idx].error, None);
+
+        let idx = find(&preferences, EntryKind::Bind, "CloseTab");
+        preferences.edit(idx, "".to_string());
+        assert!(preferences.entries[idx].error.is_some());
+    }
+
+    #[test]
+    fn saving_keeps_comments_and_replaces_lines_in_place() {
+        let mut preferences = preferences();
+        let idx = find(&preferences, EntryKind::Set, "DarkModeUi");
+        preferences.edit(idx, "True".to_string());
+        let idx = find(&preferences, EntryKind::Bind, "CloseTab");
+        preferences.edit(idx, "Ctrl+w".to_string());
+        let idx = find(&preferences, EntryKind::Set, "PageBorders");
+        preferences.edit(idx, "False".to_string());
+
+        assert_eq!(
+            preferences.config_file(),
+            "# My settings\n\
+            Set DarkModeUi True\n\
+            Bind Ctrl+w CloseTab\n\
+            \n\
+            # Looking things up\n\
+            Set LookupCommand \"dict %s\"\n\
+            \n\
+            # Changed in the preferences window\n\
+            Set PageBorders False\n"
+        );
+        assert!(!Config::parse_with_errors(&preferences.config_file()).has_errors());
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::PathBuf;
+
+/// Recent files offered besides the open tabs
+const MAX_RECENT: usize = 20;
+
+/// What choosing an entry of the quick switcher does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwitchTarget {
+    /// Switches to the n-th tab
+    Tab(usize),
+    /// Opens a recent file which isn't open
+    Recent(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    /// The file name, which is what the query is matched against
+    pub name: String,
+    /// The directory of the file, shown next to the name
+    pub location: String,
+    pub target: SwitchTarget,
+}
+
+impl Candidate {
+    fn new(path: PathBuf, name: String, target: SwitchTarget) -> Self {
+        Self {
+            name,
+            location: path
+                .parent()
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            target,
+        }
+    }
+}
+
+/// Switches between the open tabs and recent files by typing part of their name.
+#[derive(Debug, Default)]
+pub struct QuickSwitcher {
+    pub query: String,
+    /// Index into [QuickSwitcher::matches]
+    pub selected: usize,
+    candidates: Vec<Candidate>,
+}
+
+impl QuickSwitcher {
+    /// `tabs` are the path and name of every open tab, `recent` the recent files from most to
+    /// least recent. Tabs are listed first and recent files which are already open are left out.
+    pub fn new(tabs: Vec<(PathBuf, String)>, recent: &[PathBuf]) -> Self {
+        let recent: Vec<Candidate> = recent
+            .iter()
+            .filter(|path| !tabs.iter().any(|(tab, _)| tab == *path))
+            .take(MAX_RECENT)
+            .map(|path| {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.to_string_lossy().into_owned());
+                Candidate::new(path.clone(), name, SwitchTarget::Recent(path.clone()))
+            })
+            .collect();
+        let mut candidates: Vec<Candidate> = tabs
+            .into_iter()
+            .enumerate()
+            .map(|(i, (path, name))| Candidate::new(path, name, SwitchTarget::Tab(i)))
+            .collect();
+        candidates.extend(recent);
+        Self {
+            candidates,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.selected = 0;
+    }
+
+    /// The candidates matching the query, best match first. Equally good matches keep the order
+    /// of [QuickSwitcher::new].
+    pub fn matches(&self) -> Vec<&Candidate> {
+        let mut scored: Vec<(i32, &Candidate)> = self
+            .candidates
+            .iter()
+            .filter_map(|c| fuzzy_score(&self.query, &c.name).map(|score| (score, c)))
+            .collect();
+        scored.sort_by_key(|(score, _)| -score);
+        scored.into_iter().map(|(_, c)| c).collect()
+    }
+
+    pub fn move_selection(&mut self, delta: i32) {
+        let listed = self.matches().len();
+        if listed == 0 {
+            return;
+        }
+        self.selected = (self.selected as i64 + delta as i64).clamp(0, listed as i64 - 1) as usize;
+    }
+
+    /// The target of the `idx`-th match.
+    pub fn get(&self, idx: usize) -> Option<SwitchTarget> {
+        self.matches().get(idx).map(|c| c.target.clone())
+    }
+}
+
+/// How well `name` matches `query` when the characters of the query appear in it in order,
+/// ignoring case. Characters directly after the previous match and at the start of words count
+/// more, so "lp" prefers "lecture_physics.pdf" over "lapel.pdf".
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+    let mut query = query.chars().filter(|c| !c.is_whitespace()).peekable();
+    for (i, c) in name.chars().enumerate() {
+        let Some(&wanted) = query.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(wanted.to_lowercase()) {
+            score += 1;
+            if last_match.is_some_and(|last| last + 1 == i) {
+                score += 5;
+            }
+            let word_start = match previous {
+                None => true,
+                Some(p) => !p.is_alphanumeric() || (p.is_lowercase() && c.is_uppercase()),
+            };
+            if word_start {
+                score += 8;
+            }
+            last_match = Some(i);
+            query.next();
+        }
+        previous = Some(c);
+    }
+    query.peek().is_none().then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_match_in_order_ignoring_case() {
+        assert!(fuzzy_score("", "paper.pdf").is_some());
+        assert!(fuzzy_score("PPR", "paper.pdf").is_some());
+        assert!(fuzzy_score("rp", "paper.pdf").is_none());
+        assert!(
+            fuzzy_score("lp", "lecture_physics.pdf").unwrap()
+                > fuzzy_score("lp", "lapel.pdf").unwrap()
+        );
+        assert!(
+            fuzzy_score("thesis", "thesis-draft.pdf").unwrap()
+                > fuzzy_score("thesis", "the-synthesis.pdf").unwrap()
+        );
+    }
+
+    #[test]
+    fn open_tabs_come_before_recent_files() {
+        let switcher = QuickSwitcher::new(
+            vec![
+                (PathBuf::from("/papers/notes.pdf"), "notes.pdf".to_string()),
+                (
+                    PathBuf::from("/papers/attention.pdf"),
+                    "attention.pdf".to_string(),
+                ),
+            ],
+            &[
+                PathBuf::from("/papers/attention.pdf"),
+                PathBuf::from("/books/analysis.pdf"),
+            ],
+        );
+        let targets: Vec<_> = switcher
+            .matches()
+            .iter()
+            .map(|c| c.target.clone())
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                SwitchTarget::Tab(0),
+                SwitchTarget::Tab(1),
+                SwitchTarget::Recent(PathBuf::from("/books/analysis.pdf")),
+            ]
+        );
+        assert_eq!(switcher.matches()[2].location, "/books");
+
+        let mut switcher = switcher;
+        switcher.set_query("an".to_string());
+        assert_eq!(
+            switcher.get(0),
+            Some(SwitchTarget::Recent(PathBuf::from("/books/analysis.pdf")))
+        );
+        switcher.move_selection(5);
+        assert_eq!(switcher.selected, switcher.matches().len() - 1);
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Result, anyhow};
+use iced::{
+    Length, Theme,
+    widget::{self, container, text},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::paths;
+
+/// Accumulated reading statistics for one document.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DocumentStats {
+    pub total_seconds: u64,
+    pub pages_read: u64,
+    pub sessions: u64,
+    /// Seconds since the unix epoch
+    pub last_read: u64,
+}
+
+impl DocumentStats {
+    /// Seconds spent per page read
+    pub fn average_pace(&self) -> Option<f64> {
+        if self.pages_read == 0 {
+            None
+        } else {
+            Some(self.total_seconds as f64 / self.pages_read as f64)
+        }
+    }
+}
+
+/// The document currently being read and since when.
+#[derive(Debug)]
+struct ActiveSession {
+    path: PathBuf,
+    since: Instant,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ReadingStats {
+    documents: HashMap<PathBuf, DocumentStats>,
+    #[serde(skip)]
+    active: Option<ActiveSession>,
+    /// Documents which have been focused at least once since the program started
+    #[serde(skip)]
+    counted_sessions: HashSet<PathBuf>,
+    /// The pages of each document seen since the program started, so returning to a document
+    /// doesn't count its pages again
+    #[serde(skip)]
+    pages_seen: HashMap<PathBuf, HashSet<usize>>,
+}
+
+impl ReadingStats {
+    pub fn system_store() -> Result<Self> {
+        let path = Self::system_store_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(&fs::read_to_string(path)?).map_err(|e| anyhow!("{}", e))
+    }
+
+    fn system_store_path() -> Result<PathBuf> {
+        paths::data_file("reading_stats.json")
+    }
+
+    /// Flushes the running timer before writing to disk.
+    pub fn save(&mut self) -> Result<()> {
+        self.finish_active();
+        paths::write(
+            &Self::system_store_path()?,
+            serde_json::to_string(self).map_err(|e| anyhow!("{}", e))?,
+        )
+    }
+
+    /// Called whenever the focused document or its page might have changed. `None` means no
+    /// document is being read, because none is shown or the window is in the background, and
+    /// the timer is paused.
+    pub fn observe(&mut self, focused: Option<(&Path, usize)>) {
+        match focused {
+            Some((path, page)) => {
+                if self.active.as_ref().is_none_or(|a| a.path != path) {
+                    self.finish_active();
+                    if self.counted_sessions.insert(path.to_path_buf()) {
+                        self.documents
+                            .entry(path.to_path_buf())
+                            .or_default()
+                            .sessions += 1;
+                    }
+                    self.active = Some(ActiveSession {
+                        path: path.to_path_buf(),
+                        since: Instant::now(),
+                    });
+                }
+                if self
+                    .pages_seen
+                    .entry(path.to_path_buf())
+                    .or_default()
+                    .insert(page)
+                {
+                    self.documents
+                        .entry(path.to_path_buf())
+                        .or_default()
+                        .pages_read += 1;
+                }
+            }
+            None => self.finish_active(),
+        }
+    }
+
+    fn finish_active(&mut self) {
+        if let Some(active) = self.active.take() {
+            let stats = self.documents.entry(active.path).or_default();
+            stats.total_seconds += active.since.elapsed().as_secs();
+            stats.last_read = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+        }
+    }
+
+    /// Whether the timer is running for `path`.
+    #[cfg(test)]
+    pub fn is_reading(&self, path: &Path) -> bool {
+        self.active.as_ref().is_some_and(|a| a.path == path)
+    }
+
+    /// Statistics for `path` including the time spent in the running session.
+    pub fn stats_for(&self, path: &Path) -> DocumentStats {
+        let mut stats = self.documents.get(path).cloned().unwrap_or_default();
+        if let Some(active) = self.active.as_ref().filter(|a| a.path == path) {
+            stats.total_seconds += active.since.elapsed().as_secs();
+        }
+        stats
+    }
+
+    pub fn view<'a, Message: 'a>(&self, path: Option<&Path>) -> iced::Element<'a, Message> {
+        let mut col = widget::column![
+            text("Reading Statistics").size(18.0),
+            widget::space::vertical().height(8.0),
+        ];
+
+        match path {
+            Some(path) => {
+                let stats = self.stats_for(path);
+                col = col
+                    .push(stat_row(
+                        "Total time",
+                        format_duration(Duration::from_secs(stats.total_seconds)),
+                    ))
+                    .push(stat_row("Pages read", stats.pages_read.to_string()))
+                    .push(stat_row(
+                        "Average pace",
+                        stats.average_pace().map_or(String::from("-"), |pace| {
+                            format!("{} / page", format_duration(Duration::from_secs_f64(pace)))
+                        }),
+                    ))
+                    .push(stat_row("Sessions", stats.sessions.to_string()))
+                    .push(stat_row("Last read", format_last_read(stats.last_read)));
+            }
+            None => {
+                col = col.push(text("No document loaded").style(|theme: &Theme| {
+                    let palette = theme.extended_palette();
+                    text::Style {
+                        color: Some(palette.background.weak.color),
+                    }
+                }));
+            }
+        }
+
+        container(col.spacing(4.0)).height(Length::Fill).into()
+    }
+}
+
+fn stat_row<'a, Message: 'a>(label: &'a str, value: String) -> iced::Element<'a, Message> {
+    widget::row![text(label).width(Length::Fill), text(value)].into()
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+fn format_last_read(last_read: u64) -> String {
+    if last_read == 0 {
+        return String::from("Now");
+    }
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    match now.saturating_sub(last_read) / (60 * 60 * 24) {
+        0 => String::from("Today"),
+        1 => String::from("Yesterday"),
+        days => format!("{days} days ago"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_are_counted_once_per_session() {
+        let mut stats = ReadingStats::default();
+        let path = Path::new("/tmp/a.pdf");
+        stats.observe(Some((path, 0)));
+        stats.observe(Some((path, 1)));
+        stats.observe(Some((path, 1)));
+        stats.observe(None);
+        assert_eq!(stats.stats_for(path).pages_read, 2);
+        assert_eq!(stats.stats_for(path).sessions, 1);
+    }
+
+    #[test]
+    fn switching_documents_only_counts_one_session_each() {
+        let mut stats = ReadingStats::default();
+        let a = Path::new("/tmp/a.pdf");
+        let b = Path::new("/tmp/b.pdf");
+        stats.observe(Some((a, 0)));
+        stats.observe(Some((b, 0)));
+        stats.observe(Some((a, 3)));
+        stats.observe(Some((b, 0)));
+        stats.observe(Some((a, 0)));
+        stats.observe(None);
+        assert_eq!(stats.stats_for(a).sessions, 1);
+        // Coming back to a document doesn't count the pages seen before again
+        assert_eq!(stats.stats_for(a).pages_read, 2);
+        assert_eq!(stats.stats_for(b).pages_read, 1);
+    }
+
+    #[test]
+    fn durations_are_formatted_compactly() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(
+            format_duration(Duration::from_secs(3 * 3600 + 120)),
+            "3h 2m"
+        );
+    }
+}
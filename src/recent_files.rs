@@ -3,6 +3,8 @@ use std::{fs, path::PathBuf};
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 
+use crate::paths;
+
 const MAX_RECENT_FILES: usize = 5;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -24,17 +26,14 @@ impl RecentFiles {
     }
 
     fn system_store_path() -> Result<PathBuf> {
-        Ok(home::home_dir()
-            .ok_or(anyhow!("No home directory could be determined"))?
-            .join("./.config/miro-pdf/recent_files.json"))
+        paths::state_file("recent_files.json")
     }
 
     pub fn save(&self) -> Result<()> {
-        fs::write(
-            Self::system_store_path()?,
+        paths::write(
+            &Self::system_store_path()?,
             serde_json::to_string(self).map_err(|e| anyhow!("{}", e))?,
         )
-        .map_err(|e| anyhow!("{}", e))
     }
 
     pub fn add_recent(&mut self, path: PathBuf) {
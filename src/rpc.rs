@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 
-use axum::{Json, Router, extract::State, routing::post};
+use anyhow::{Result, anyhow};
+use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
+use clap::Subcommand;
 use iced::{
     futures::{SinkExt, Stream, channel::mpsc::Sender},
     stream,
@@ -8,7 +10,12 @@ use iced::{
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 
-use crate::{CONFIG, app::AppMessage};
+use crate::{
+    CONFIG,
+    app::AppMessage,
+    bookmarks::{BookmarkMessage, BookmarkStore},
+    paths,
+};
 
 #[derive(Clone)]
 struct AppState {
@@ -18,10 +25,28 @@ struct AppState {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type", content = "data")]
 enum RpcMessage {
-    OpenFile { path: PathBuf },
-    CloseFile { path: PathBuf },
+    OpenFile {
+        path: PathBuf,
+    },
+    CloseFile {
+        path: PathBuf,
+    },
     ToggleDarkModeUi,
     ToggleDarkModePdf,
+    /// `page` starts at 1 like the page numbers shown in the viewer
+    AddBookmark {
+        path: PathBuf,
+        page: usize,
+        name: String,
+    },
+    /// Answers with the bookmarks of `path`, or of every file without one
+    ListBookmarks {
+        path: Option<PathBuf>,
+    },
+    /// Jumps to a named destination of the active document
+    GoToDestination {
+        name: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -29,6 +54,37 @@ struct RpcRequest {
     pub message: RpcMessage,
 }
 
+/// A bookmark as it is listed over RPC
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BookmarkEntry {
+    pub path: PathBuf,
+    pub page: usize,
+    pub name: String,
+}
+
+/// Talks to the RPC server of a running instance, see `miro ctl --help`
+#[derive(Subcommand, Debug)]
+pub enum CtlCommand {
+    /// Create and list bookmarks
+    #[command(subcommand)]
+    Bookmark(BookmarkCommand),
+    /// Jump to a named destination of the active document, like `#nameddest=NAME` links do
+    GotoDest { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BookmarkCommand {
+    /// Add a bookmark to the pdf at PATH
+    Add {
+        path: PathBuf,
+        /// Starts at 1
+        page: usize,
+        name: String,
+    },
+    /// List the bookmarks of the pdf at PATH, or of every pdf without one
+    List { path: Option<PathBuf> },
+}
+
 pub fn rpc_server() -> impl Stream<Item = AppMessage> {
     stream::channel(100, |output| async move {
         let (port, allow_lan) = {
@@ -39,33 +95,134 @@ pub fn rpc_server() -> impl Stream<Item = AppMessage> {
         let app = Router::new()
             .route("/", post(root_handler))
             .with_state(AppState { tx: output });
-        let listener = tokio::net::TcpListener::bind(if allow_lan {
+        let listener = match tokio::net::TcpListener::bind(if allow_lan {
             format!("0.0.0.0:{port}")
         } else {
             format!("127.0.0.1:{port}")
         })
         .await
-        .unwrap();
-        axum::serve(listener, app).await.unwrap();
+        {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Couldn't start the RPC server on port {port}: {e}");
+                return;
+            }
+        };
+        if let Err(e) = axum::serve(listener, app).await {
+            error!("The RPC server stopped: {e}");
+        }
     })
 }
 
 async fn root_handler(
     State(mut state): State<AppState>,
     Json(payload): Json<RpcRequest>,
-) -> String {
+) -> Result<String, (StatusCode, String)> {
     let message = match payload.message {
         RpcMessage::OpenFile { path } => AppMessage::OpenFile(path),
         RpcMessage::CloseFile { path } => AppMessage::CloseFile(path),
         RpcMessage::ToggleDarkModeUi => AppMessage::ToggleDarkModeUi,
         RpcMessage::ToggleDarkModePdf => AppMessage::ToggleDarkModePdf,
+        RpcMessage::AddBookmark { .. } if paths::read_only() => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "miro is running with --read-only".to_string(),
+            ));
+        }
+        RpcMessage::AddBookmark { path, .. } | RpcMessage::ListBookmarks { path: Some(path) }
+            if path.is_relative() =>
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("{} has to be an absolute path", path.display()),
+            ));
+        }
+        RpcMessage::AddBookmark { path, page, name } => {
+            let path = path
+                .canonicalize()
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("{}: {e}", path.display())))?;
+            if page == 0 {
+                return Err((StatusCode::BAD_REQUEST, "Pages start at 1".to_string()));
+            }
+            AppMessage::BookmarkMessage(BookmarkMessage::CreateBookmark {
+                path,
+                name,
+                page: page - 1,
+            })
+        }
+        RpcMessage::GoToDestination { name } => AppMessage::GoToDestination(name),
+        RpcMessage::ListBookmarks { path } => {
+            return list_bookmarks(path)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+        }
     };
 
     if let Err(e) = state.tx.send(message).await {
         error!("Failed to send message: {}", e);
     }
 
-    "OK".to_string()
+    Ok("OK".to_string())
+}
+
+/// Bookmarks are saved whenever they change, so the store on disk is what the viewer shows.
+fn list_bookmarks(path: Option<PathBuf>) -> Result<String> {
+    let path = path.map(|p| p.canonicalize()).transpose()?;
+    let store = BookmarkStore::system_store().unwrap_or_default();
+    let entries: Vec<BookmarkEntry> = store
+        .bookmarks()
+        .filter(|(p, _)| path.as_deref().is_none_or(|path| path == *p))
+        .map(|(p, mark)| BookmarkEntry {
+            path: p.to_path_buf(),
+            page: mark.page + 1,
+            name: mark.name.clone(),
+        })
+        .collect();
+    Ok(serde_json::to_string(&entries)?)
+}
+
+/// `path` as the server has to see it, relative paths are relative to where `miro ctl` runs and
+/// not to the directory the viewer was started in.
+fn absolute(path: PathBuf) -> Result<PathBuf> {
+    path.canonicalize()
+        .map_err(|e| anyhow!("{}: {e}", path.display()))
+}
+
+/// Sends `command` to the RPC server of a running instance and prints the answer.
+pub fn ctl(command: CtlCommand) -> Result<()> {
+    let message = match command {
+        CtlCommand::Bookmark(BookmarkCommand::Add { path, page, name }) => {
+            RpcMessage::AddBookmark {
+                path: absolute(path)?,
+                page,
+                name,
+            }
+        }
+        CtlCommand::Bookmark(BookmarkCommand::List { path }) => RpcMessage::ListBookmarks {
+            path: path.map(absolute).transpose()?,
+        },
+        CtlCommand::GotoDest { name } => RpcMessage::GoToDestination { name },
+    };
+    let list = matches!(message, RpcMessage::ListBookmarks { .. });
+    let port = CONFIG.read().unwrap().rpc_port;
+    let response = reqwest::blocking::Client::new()
+        .post(format!("http://127.0.0.1:{port}/"))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&RpcRequest { message })?)
+        .send()
+        .map_err(|e| {
+            anyhow!("Couldn't reach miro on port {port}, is it running with `Set Rpc True`? ({e})")
+        })?;
+    let status = response.status();
+    let body = response.text()?;
+    if !status.is_success() {
+        return Err(anyhow!("{body}"));
+    }
+    if list {
+        for entry in serde_json::from_str::<Vec<BookmarkEntry>>(&body)? {
+            println!("{}\t{}\t{}", entry.path.display(), entry.page, entry.name);
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -83,4 +240,30 @@ mod tests {
                 }
         );
     }
+
+    #[test]
+    pub fn json_parsing_of_bookmark_rpc() {
+        let input = r#"{"message": {"type": "AddBookmark", "data": { "path": "a.pdf", "page": 3, "name": "Lemma 2"}}}"#;
+        let output: RpcRequest = serde_json::from_str(input).unwrap();
+        assert_eq!(
+            output.message,
+            RpcMessage::AddBookmark {
+                path: "a.pdf".into(),
+                page: 3,
+                name: "Lemma 2".to_string(),
+            }
+        );
+
+        let input = r#"{"message": {"type": "ListBookmarks", "data": {}}}"#;
+        let output: RpcRequest = serde_json::from_str(input).unwrap();
+        assert_eq!(output.message, RpcMessage::ListBookmarks { path: None });
+    }
+
+    #[test]
+    fn ctl_sends_absolute_paths() {
+        let path = absolute(PathBuf::from("assets/links.pdf")).unwrap();
+        assert!(path.is_absolute());
+        assert!(path.ends_with("assets/links.pdf"));
+        assert!(absolute(PathBuf::from("assets/missing.pdf")).is_err());
+    }
 }
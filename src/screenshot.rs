@@ -0,0 +1,170 @@
+use std::path::Path;
+
+use anyhow::{Result, anyhow};
+use mupdf::{Colorspace, ImageFormat, Pixmap};
+
+use crate::{
+    CONFIG,
+    config::PdfDarkModeStyle,
+    geometry::{Rect, Vector},
+    pdf::{
+        backend::{self, RgbaImage},
+        scheduler::{self, RenderJob, RenderPriority},
+        widget::{
+            PAGE_SHADOW_BLUR, PAGE_SHADOW_OFFSET, PAGE_SHADOW_STEPS, PageStyle, dark_mode_gradient,
+            get_pdf_background_color, page_shadow_alpha,
+        },
+    },
+};
+
+/// Background shown around the page on every side, in pixels
+const MARGIN: u32 = 32;
+
+/// Renders page `page_idx` the way the viewer shows it at `scale`, with the background, shadow and
+/// border around it and recolored like the dark mode of pdfs if `dark_mode` is set. The page
+/// decorations follow the config just like in the viewer.
+pub fn render(path: &Path, page_idx: usize, scale: f32, dark_mode: bool) -> Result<RgbaImage> {
+    let doc = backend::open(path)?;
+    let page_count = doc.page_count()?;
+    if page_idx >= page_count {
+        return Err(anyhow!(
+            "Page {} doesn't exist, the document has {page_count} pages",
+            page_idx + 1
+        ));
+    }
+    let job = RenderJob {
+        page_idx,
+        scale,
+        priority: RenderPriority::Visible,
+        dark_mode: dark_mode.then(dark_mode_gradient),
+        recolor: CONFIG.read().unwrap().pdf_dark_mode_style == PdfDarkModeStyle::Recolor,
+    };
+    let (width, height, pixels) = scheduler::render_page(doc.as_ref(), &job)?;
+
+    let borders = CONFIG.read().unwrap().page_borders;
+    let style = PageStyle::from_config(dark_mode);
+    let mut shot = RgbaImage {
+        width: width + 2 * MARGIN,
+        height: height + 2 * MARGIN,
+        pixels: vec![],
+    };
+    shot.pixels = get_pdf_background_color(dark_mode, borders)
+        .into_rgba8()
+        .repeat((shot.width * shot.height) as usize);
+    let page = Rect::from_pos_size(
+        Vector::new(MARGIN as f32, MARGIN as f32),
+        Vector::new(width as f32, height as f32),
+    );
+
+    if borders && style.shadow {
+        let shadow = iced::Color::BLACK.scale_alpha(page_shadow_alpha(dark_mode));
+        for i in (1..=PAGE_SHADOW_STEPS).rev() {
+            let spread = PAGE_SHADOW_BLUR * i as f32 / PAGE_SHADOW_STEPS as f32;
+            let offset = Vector::from(PAGE_SHADOW_OFFSET) - Vector::new(spread, spread).scaled(0.5);
+            let rect =
+                Rect::from_pos_size(page.x0 + offset, page.size() + Vector::new(spread, spread));
+            fill(&mut shot, rect, shadow);
+        }
+    }
+
+    for (row, line) in pixels.chunks_exact(width as usize * 4).enumerate() {
+        let start = (((row as u32 + MARGIN) * shot.width + MARGIN) * 4) as usize;
+        shot.pixels[start..start + line.len()].copy_from_slice(line);
+    }
+
+    if borders && style.border_width > 0.0 {
+        // Stroked just outside the page, like the viewer does
+        let w = style.border_width;
+        let outer = Rect::from_points(page.x0 - Vector::new(w, w), page.x1 + Vector::new(w, w));
+        for side in [
+            Rect::from_points(outer.x0, Vector::new(outer.x1.x, page.x0.y)),
+            Rect::from_points(Vector::new(outer.x0.x, page.x1.y), outer.x1),
+            Rect::from_points(
+                Vector::new(outer.x0.x, page.x0.y),
+                Vector::new(page.x0.x, page.x1.y),
+            ),
+            Rect::from_points(
+                Vector::new(page.x1.x, page.x0.y),
+                Vector::new(outer.x1.x, page.x1.y),
+            ),
+        ] {
+            fill(&mut shot, side, style.border_color);
+        }
+    }
+    Ok(shot)
+}
+
+/// Blends `color` over the pixels of `image` covered by `rect`.
+fn fill(image: &mut RgbaImage, rect: Rect<f32>, color: iced::Color) {
+    let [r, g, b, _] = color.into_rgba8();
+    let clamp = |v: f32, max: u32| (v.round().max(0.0) as u32).min(max);
+    for y in clamp(rect.x0.y, image.height)..clamp(rect.x1.y, image.height) {
+        for x in clamp(rect.x0.x, image.width)..clamp(rect.x1.x, image.width) {
+            let i = ((y * image.width + x) * 4) as usize;
+            for (channel, value) in image.pixels[i..i + 3].iter_mut().zip([r, g, b]) {
+                *channel =
+                    (*channel as f32 * (1.0 - color.a) + value as f32 * color.a).round() as u8;
+            }
+        }
+    }
+}
+
+/// `miro screenshot`, writes page `page` (counted from 1) of `path` to the PNG file `out`.
+pub fn cli(path: &Path, page: usize, out: &Path, scale: f32, dark_mode: bool) -> Result<()> {
+    if page == 0 {
+        return Err(anyhow!("Pages are counted from 1"));
+    }
+    let shot = render(path, page - 1, scale, dark_mode)?;
+    let mut pix = Pixmap::new_with_w_h(
+        &Colorspace::device_rgb(),
+        shot.width as i32,
+        shot.height as i32,
+        false,
+    )?;
+    for (rgb, rgba) in pix
+        .samples_mut()
+        .chunks_exact_mut(3)
+        .zip(shot.pixels.chunks_exact(4))
+    {
+        rgb.copy_from_slice(&rgba[..3]);
+    }
+    let out_str = out
+        .to_str()
+        .ok_or_else(|| anyhow!("The path {out:?} is not valid UTF-8"))?;
+    pix.save_as(out_str, ImageFormat::PNG)?;
+    println!(
+        "Wrote page {page} of {} to {}",
+        path.display(),
+        out.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_are_framed_by_the_viewer_background() {
+        let path = Path::new("assets/links.pdf");
+        let light = render(path, 0, 1.0, false).unwrap();
+        let dark = render(path, 0, 1.0, true).unwrap();
+        assert_eq!((light.width, light.height), (dark.width, dark.height));
+
+        let pixel = |image: &RgbaImage, x: u32, y: u32| {
+            let i = ((y * image.width + x) * 4) as usize;
+            image.pixels[i..i + 4].to_vec()
+        };
+        let borders = CONFIG.read().unwrap().page_borders;
+        assert_eq!(
+            pixel(&light, 0, 0),
+            get_pdf_background_color(false, borders).into_rgba8()
+        );
+        // The blank margin of the page is white, or the dark background once recolored
+        let (x, y) = (MARGIN + 2, MARGIN + 2);
+        assert_eq!(pixel(&light, x, y), [255, 255, 255, 255]);
+        assert!(pixel(&dark, x, y)[..3].iter().all(|c| *c < 128));
+
+        assert!(render(path, 1000, 1.0, false).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use iced::{
+    Point, Rectangle, Renderer, Size,
+    advanced::mouse,
+    widget::canvas::{self, Path},
+};
+
+use crate::app::AppMessage;
+
+/// Height of the bar under the active tab
+pub const HEIGHT: f32 = 6.0;
+
+/// A thin progress bar through the document. Clicking or dragging along it previews the page
+/// under the mouse in the tab, which is jumped to once the mouse is released.
+#[derive(Debug)]
+pub struct PageScrubber {
+    pub current_page: usize,
+    pub page_count: usize,
+    /// The page being dragged to, shown instead of the current one
+    pub preview: Option<usize>,
+}
+
+/// The page at `x` on a scrubber `width` pixels wide, the bar is split evenly between the pages.
+fn page_at(x: f32, width: f32, page_count: usize) -> usize {
+    if page_count == 0 || width <= 0.0 {
+        return 0;
+    }
+    let fraction = (x / width).clamp(0.0, 1.0);
+    ((fraction * page_count as f32) as usize).min(page_count - 1)
+}
+
+impl canvas::Program<AppMessage> for PageScrubber {
+    /// Whether the scrubber is being dragged
+    type State = bool;
+
+    fn update(
+        &self,
+        dragging: &mut Self::State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<AppMessage>> {
+        let page_under =
+            |position: Point| page_at(position.x - bounds.x, bounds.width, self.page_count);
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let position = cursor.position_over(bounds)?;
+                *dragging = true;
+                Some(
+                    canvas::Action::publish(AppMessage::ScrubPage(page_under(position)))
+                        .and_capture(),
+                )
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { position }) if *dragging => {
+                let page = page_under(*position);
+                (self.preview != Some(page))
+                    .then(|| canvas::Action::publish(AppMessage::ScrubPage(page)).and_capture())
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if *dragging =>
+            {
+                *dragging = false;
+                Some(canvas::Action::publish(AppMessage::FinishScrub).and_capture())
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(
+        &self,
+        _dragging: &Self::State,
+        renderer: &Renderer,
+        theme: &iced::Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry<Renderer>> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let palette = theme.extended_palette();
+        let radius = (bounds.height / 2.0).into();
+        frame.fill(
+            &Path::rounded_rectangle(Point::ORIGIN, bounds.size(), radius),
+            palette.background.strong.color,
+        );
+        if self.page_count > 0 {
+            let page = self
+                .preview
+                .unwrap_or(self.current_page)
+                .min(self.page_count - 1);
+            let progress = (page + 1) as f32 / self.page_count as f32;
+            frame.fill(
+                &Path::rounded_rectangle(
+                    Point::ORIGIN,
+                    Size::new(bounds.width * progress, bounds.height),
+                    radius,
+                ),
+                palette.primary.base.color,
+            );
+        }
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        dragging: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if *dragging {
+            mouse::Interaction::Grabbing
+        } else if cursor.is_over(bounds) {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_bar_is_split_evenly_between_the_pages() {
+        assert_eq!(page_at(0.0, 100.0, 4), 0);
+        assert_eq!(page_at(24.9, 100.0, 4), 0);
+        assert_eq!(page_at(25.0, 100.0, 4), 1);
+        assert_eq!(page_at(100.0, 100.0, 4), 3);
+        // Dragging past the ends keeps the first or last page
+        assert_eq!(page_at(-30.0, 100.0, 4), 0);
+        assert_eq!(page_at(300.0, 100.0, 4), 3);
+        assert_eq!(page_at(50.0, 100.0, 0), 0);
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::{jumplist::Jumplist, paths, tab_color::TabColor};
+
+const MAX_SESSIONS: usize = 20;
+
+/// State belonging to one set of open documents.
+#[derive(Debug, Serialize, Deserialize)]
+struct Session {
+    /// Sorted so the order the documents were opened in doesn't matter
+    documents: Vec<PathBuf>,
+    jumplist: Jumplist,
+}
+
+/// Remembers per session state, such as the jumplist, between launches. A session is identified
+/// by the set of documents which were open when the program was closed.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SessionStore {
+    /// Most recently saved first
+    sessions: Vec<Session>,
+    /// Fraction of the window taken up by the sidebar the last time it was resized
+    #[serde(default)]
+    sidebar_ratio: Option<f32>,
+    /// Named sets of documents saved by the user, in the order their tabs were in
+    #[serde(default)]
+    workspaces: BTreeMap<String, Vec<PathBuf>>,
+    /// The layers hidden in each document whose layers were changed
+    #[serde(default)]
+    hidden_layers: BTreeMap<PathBuf, Vec<String>>,
+    /// The color labels given to the tabs of documents
+    #[serde(default)]
+    tab_colors: BTreeMap<PathBuf, TabColor>,
+}
+
+fn session_key<'a>(documents: impl IntoIterator<Item = &'a Path>) -> Vec<PathBuf> {
+    let mut key: Vec<PathBuf> = documents.into_iter().map(Path::to_path_buf).collect();
+    key.sort();
+    key.dedup();
+    key
+}
+
+impl SessionStore {
+    pub fn system_store() -> Result<Self> {
+        let path = Self::system_store_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(&fs::read_to_string(path)?).map_err(|e| anyhow!("{}", e))
+    }
+
+    fn system_store_path() -> Result<PathBuf> {
+        paths::state_file("sessions.json")
+    }
+
+    pub fn save(&self) -> Result<()> {
+        paths::write(
+            &Self::system_store_path()?,
+            serde_json::to_string(self).map_err(|e| anyhow!("{}", e))?,
+        )
+    }
+
+    pub fn sidebar_ratio(&self) -> Option<f32> {
+        self.sidebar_ratio
+    }
+
+    pub fn set_sidebar_ratio(&mut self, ratio: f32) {
+        self.sidebar_ratio = Some(ratio);
+    }
+
+    /// Stores the jumplist of the session made up of `documents`, replacing any older entry for
+    /// the same documents.
+    pub fn store_jumplist<'a>(
+        &mut self,
+        documents: impl IntoIterator<Item = &'a Path>,
+        jumplist: Jumplist,
+    ) {
+        let documents = session_key(documents);
+        if documents.is_empty() || jumplist.is_empty() {
+            return;
+        }
+        self.sessions.retain(|s| s.documents != documents);
+        self.sessions.insert(
+            0,
+            Session {
+                documents,
+                jumplist,
+            },
+        );
+        self.sessions.truncate(MAX_SESSIONS);
+    }
+
+    /// Saves `documents` as the workspace `name`, replacing any workspace with the same name.
+    pub fn save_workspace<'a>(
+        &mut self,
+        name: &str,
+        documents: impl IntoIterator<Item = &'a Path>,
+    ) {
+        let documents: Vec<PathBuf> = documents.into_iter().map(Path::to_path_buf).collect();
+        if name.is_empty() || documents.is_empty() {
+            return;
+        }
+        self.workspaces.insert(name.to_string(), documents);
+    }
+
+    pub fn workspace(&self, name: &str) -> Option<&[PathBuf]> {
+        self.workspaces.get(name).map(Vec::as_slice)
+    }
+
+    /// The names of all saved workspaces in alphabetical order.
+    pub fn workspace_names(&self) -> Vec<String> {
+        self.workspaces.keys().cloned().collect()
+    }
+
+    pub fn hidden_layers(&self, document: &Path) -> Option<&[String]> {
+        self.hidden_layers.get(document).map(Vec::as_slice)
+    }
+
+    pub fn set_hidden_layers(&mut self, document: &Path, hidden: Vec<String>) {
+        self.hidden_layers.insert(document.to_path_buf(), hidden);
+    }
+
+    pub fn tab_color(&self, document: &Path) -> Option<TabColor> {
+        self.tab_colors.get(document).copied()
+    }
+
+    /// Labels the tab of `document` with `color`, or removes its label.
+    pub fn set_tab_color(&mut self, document: &Path, color: Option<TabColor>) {
+        match color {
+            Some(color) => self.tab_colors.insert(document.to_path_buf(), color),
+            None => self.tab_colors.remove(document),
+        };
+    }
+
+    /// Removes and returns the jumplist saved for exactly this set of documents.
+    pub fn take_jumplist<'a>(
+        &mut self,
+        documents: impl IntoIterator<Item = &'a Path>,
+    ) -> Option<Jumplist> {
+        let documents = session_key(documents);
+        let idx = self
+            .sessions
+            .iter()
+            .position(|s| s.documents == documents)?;
+        Some(self.sessions.remove(idx).jumplist)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{geometry::Vector, jumplist::JumpLocation};
+
+    fn jumplist_in(path: &str) -> Jumplist {
+        let mut jumplist = Jumplist::new();
+        for y in [0.0, 500.0] {
+            jumplist.push(JumpLocation {
+                pdf_path: PathBuf::from(path),
+                translation: Vector::new(0.0, y),
+                scale: 1.0,
+                ..Default::default()
+            });
+        }
+        jumplist
+    }
+
+    #[test]
+    fn sessions_are_matched_regardless_of_tab_order() {
+        let mut store = SessionStore::default();
+        store.store_jumplist(
+            [Path::new("/b.pdf"), Path::new("/a.pdf")],
+            jumplist_in("/a.pdf"),
+        );
+
+        assert!(store.take_jumplist([Path::new("/a.pdf")]).is_none());
+        let restored = store
+            .take_jumplist([Path::new("/a.pdf"), Path::new("/b.pdf")])
+            .unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.current_index, 1);
+    }
+
+    #[test]
+    fn storing_the_same_session_replaces_it() {
+        let mut store = SessionStore::default();
+        store.store_jumplist([Path::new("/a.pdf")], jumplist_in("/a.pdf"));
+        store.store_jumplist([Path::new("/a.pdf")], jumplist_in("/a.pdf"));
+        assert_eq!(store.sessions.len(), 1);
+    }
+
+    #[test]
+    fn tab_colors_survive_a_restart() {
+        let mut store = SessionStore::default();
+        store.set_tab_color(Path::new("/a.pdf"), Some(TabColor::Green));
+        store.set_tab_color(Path::new("/b.pdf"), Some(TabColor::Red));
+        store.set_tab_color(Path::new("/b.pdf"), None);
+
+        let store: SessionStore =
+            serde_json::from_str(&serde_json::to_string(&store).unwrap()).unwrap();
+        assert_eq!(store.tab_color(Path::new("/a.pdf")), Some(TabColor::Green));
+        assert_eq!(store.tab_color(Path::new("/b.pdf")), None);
+    }
+
+    #[test]
+    fn workspaces_keep_the_tab_order() {
+        let mut store = SessionStore::default();
+        store.save_workspace("thesis", [Path::new("/b.pdf"), Path::new("/a.pdf")]);
+        store.save_workspace("empty", []);
+        assert_eq!(
+            store.workspace("thesis").unwrap(),
+            [PathBuf::from("/b.pdf"), PathBuf::from("/a.pdf")]
+        );
+        assert_eq!(store.workspace_names(), vec!["thesis".to_string()]);
+
+        store.save_workspace("thesis", [Path::new("/c.pdf")]);
+        assert_eq!(
+            store.workspace("thesis").unwrap(),
+            [PathBuf::from("/c.pdf")]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+/// A color label given to a tab from the menu opened by right clicking it. Remembered per
+/// document in the session store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TabColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl TabColor {
+    /// In the order the groups of [grouped_order] are shown in
+    pub const ALL: [TabColor; 6] = [
+        TabColor::Red,
+        TabColor::Orange,
+        TabColor::Yellow,
+        TabColor::Green,
+        TabColor::Blue,
+        TabColor::Purple,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TabColor::Red => "Red",
+            TabColor::Orange => "Orange",
+            TabColor::Yellow => "Yellow",
+            TabColor::Green => "Green",
+            TabColor::Blue => "Blue",
+            TabColor::Purple => "Purple",
+        }
+    }
+
+    /// Readable on both the light and the dark theme
+    pub fn color(self) -> iced::Color {
+        match self {
+            TabColor::Red => iced::Color::from_rgb8(0xe0, 0x5a, 0x5a),
+            TabColor::Orange => iced::Color::from_rgb8(0xe8, 0x8a, 0x3c),
+            TabColor::Yellow => iced::Color::from_rgb8(0xd9, 0xb8, 0x2e),
+            TabColor::Green => iced::Color::from_rgb8(0x4f, 0xb0, 0x6a),
+            TabColor::Blue => iced::Color::from_rgb8(0x4a, 0x8f, 0xe0),
+            TabColor::Purple => iced::Color::from_rgb8(0x9b, 0x6b, 0xd6),
+        }
+    }
+}
+
+/// The order tabs with `colors` are shown in by `GroupTabsByColor`: grouped by color in the order
+/// of [TabColor::ALL] and tabs without a color last. Tabs of a group keep the order they were
+/// opened in.
+pub fn grouped_order(colors: &[Option<TabColor>]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..colors.len()).collect();
+    order.sort_by_key(|&i| colors[i].map_or(TabColor::ALL.len(), |color| color as usize));
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabs_are_grouped_by_color() {
+        let colors = [
+            None,
+            Some(TabColor::Blue),
+            Some(TabColor::Red),
+            None,
+            Some(TabColor::Blue),
+        ];
+        assert_eq!(grouped_order(&colors), [2, 1, 4, 0, 3]);
+        assert_eq!(grouped_order(&[None, None]), [0, 1]);
+    }
+}
@@ -0,0 +1,128 @@
+/// The order in which tabs were last activated, used to switch between tabs Ctrl+Tab style.
+#[derive(Debug, Default)]
+pub struct TabHistory {
+    /// Tab indices, most recently activated first
+    order: Vec<usize>,
+    /// Position in `order` highlighted by an ongoing switch
+    switching: Option<usize>,
+}
+
+impl TabHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves `idx` to the front. Ignored while a switch is ongoing so that stepping through the
+    /// history doesn't reorder it.
+    pub fn activate(&mut self, idx: usize) {
+        if self.switching.is_some() {
+            return;
+        }
+        self.order.retain(|&i| i != idx);
+        self.order.insert(0, idx);
+    }
+
+    /// Forgets the closed tab `idx` and shifts the indices of the tabs after it.
+    pub fn remove(&mut self, idx: usize) {
+        self.order.retain(|&i| i != idx);
+        for i in &mut self.order {
+            if *i > idx {
+                *i -= 1;
+            }
+        }
+        self.switching = None;
+    }
+
+    /// Steps the ongoing switch `steps` entries further back in the history, wrapping around,
+    /// and starts one if needed. Returns the tab that is now highlighted.
+    pub fn step(&mut self, steps: isize) -> Option<usize> {
+        if self.order.len() < 2 {
+            return None;
+        }
+        let len = self.order.len() as isize;
+        let pos = self.switching.unwrap_or(0) as isize;
+        let pos = (pos + steps).rem_euclid(len) as usize;
+        self.switching = Some(pos);
+        Some(self.order[pos])
+    }
+
+    /// Ends the ongoing switch and returns the tab that was chosen.
+    pub fn finish(&mut self) -> Option<usize> {
+        let pos = self.switching.take()?;
+        let idx = self.order[pos];
+        self.activate(idx);
+        Some(idx)
+    }
+
+    /// Whether `idx` is among the `count` most recently activated tabs.
+    pub fn is_recent(&self, idx: usize, count: usize) -> bool {
+        self.order.iter().take(count).any(|&i| i == idx)
+    }
+
+    pub fn cancel(&mut self) {
+        self.switching = None;
+    }
+
+    pub fn is_switching(&self) -> bool {
+        self.switching.is_some()
+    }
+
+    /// The tab indices from most to least recently activated, together with whether the entry
+    /// is highlighted by the ongoing switch.
+    pub fn entries(&self) -> impl Iterator<Item = (usize, bool)> + '_ {
+        self.order
+            .iter()
+            .enumerate()
+            .map(|(pos, &idx)| (idx, Some(pos) == self.switching))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(activated: &[usize]) -> TabHistory {
+        let mut history = TabHistory::new();
+        for &idx in activated {
+            history.activate(idx);
+        }
+        history
+    }
+
+    #[test]
+    fn stepping_walks_from_most_recent_and_wraps() {
+        let mut history = history(&[0, 1, 2]);
+        assert_eq!(history.step(1), Some(1));
+        assert_eq!(history.step(1), Some(0));
+        assert_eq!(history.step(1), Some(2));
+        assert_eq!(history.step(-1), Some(0));
+        assert_eq!(history.finish(), Some(0));
+        assert_eq!(history.entries().next(), Some((0, false)));
+    }
+
+    #[test]
+    fn switching_twice_returns_to_the_original_tab() {
+        let mut history = history(&[0, 1]);
+        history.step(1);
+        history.finish();
+        history.step(1);
+        assert_eq!(history.finish(), Some(1));
+    }
+
+    #[test]
+    fn only_the_latest_tabs_are_recent() {
+        let history = history(&[0, 1, 2, 3]);
+        assert!(history.is_recent(3, 2));
+        assert!(history.is_recent(2, 2));
+        assert!(!history.is_recent(1, 2));
+        assert!(!history.is_recent(4, 2));
+    }
+
+    #[test]
+    fn closing_a_tab_shifts_later_indices() {
+        let mut history = history(&[0, 1, 2, 3]);
+        history.remove(1);
+        let order: Vec<usize> = history.entries().map(|(idx, _)| idx).collect();
+        assert_eq!(order, vec![2, 1, 0]);
+    }
+}
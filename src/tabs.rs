@@ -0,0 +1,229 @@
+use std::path::Path;
+
+use crate::{
+    jumplist::JumpLocation,
+    pdf::{PdfMessage, widget::PdfViewer},
+    tab_history::TabHistory,
+};
+
+/// What [Tabs] and the [crate::app::App] holding them need to know about the document in a tab.
+pub trait Tab {
+    fn path(&self) -> &Path;
+    /// Documents opened from memory have no file to come back to
+    fn in_memory(&self) -> bool;
+    /// Where the document is scrolled to, for the jumplist
+    fn location(&self) -> JumpLocation;
+    /// Scrolls to `location`, which must be in this document
+    fn set_location(&mut self, location: &JumpLocation) -> iced::Task<PdfMessage>;
+}
+
+impl Tab for PdfViewer {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn in_memory(&self) -> bool {
+        self.in_memory
+    }
+
+    fn location(&self) -> JumpLocation {
+        let page = self.current_page();
+        JumpLocation {
+            pdf_path: self.path.clone(),
+            translation: self.translation,
+            scale: self.scale,
+            page,
+            section: self.section_title(page),
+        }
+    }
+
+    fn set_location(&mut self, location: &JumpLocation) -> iced::Task<PdfMessage> {
+        self.update(PdfMessage::SetLocation(
+            location.translation,
+            location.scale,
+        ))
+    }
+}
+
+/// The open tabs, which one of them is active and the order they were last activated in.
+#[derive(Debug)]
+pub struct Tabs<T> {
+    pub pdfs: Vec<T>,
+    /// Index into `pdfs`, 0 while no tabs are open
+    pub active: usize,
+    pub history: TabHistory,
+}
+
+impl<T: Tab> Tabs<T> {
+    pub fn new() -> Self {
+        Self {
+            pdfs: vec![],
+            active: 0,
+            history: TabHistory::new(),
+        }
+    }
+
+    /// Adds `tab` after the others and returns its index.
+    pub fn push(&mut self, tab: T) -> usize {
+        self.pdfs.push(tab);
+        self.pdfs.len() - 1
+    }
+
+    /// Switches to tab `idx`, tabs that don't exist are ignored.
+    pub fn open(&mut self, idx: usize) {
+        if idx < self.pdfs.len() {
+            self.active = idx;
+        }
+    }
+
+    /// Moves `step` tabs to the right in `order`, the order the tabs are shown in, stopping at
+    /// the ends.
+    pub fn step(&mut self, order: &[usize], step: isize) {
+        if let Some(pos) = order.iter().position(|&i| i == self.active) {
+            let pos = pos.saturating_add_signed(step).min(order.len() - 1);
+            self.active = order[pos];
+        }
+    }
+
+    /// Closes tab `idx`. The active tab keeps showing the same document when a tab before it
+    /// closes.
+    pub fn remove(&mut self, idx: usize) -> T {
+        let closed = self.pdfs.remove(idx);
+        self.history.remove(idx);
+        if idx < self.active {
+            self.active -= 1;
+        }
+        self.active = self.active.min(self.pdfs.len().saturating_sub(1));
+        closed
+    }
+
+    /// The tab showing the document at `path`.
+    pub fn position(&self, path: &Path) -> Option<usize> {
+        self.pdfs.iter().position(|tab| tab.path() == path)
+    }
+
+    /// Ends an ongoing Ctrl+Tab style switch by opening the highlighted tab.
+    pub fn finish_switch(&mut self) {
+        if let Some(idx) = self.history.finish() {
+            self.open(idx);
+        }
+    }
+}
+
+/// A document that is only a path and a location, for testing how tabs are handled without
+/// opening real documents.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct FakeTab {
+    pub location: JumpLocation,
+}
+
+#[cfg(test)]
+impl FakeTab {
+    pub fn new(path: &str) -> Self {
+        Self {
+            location: JumpLocation {
+                pdf_path: std::path::PathBuf::from(path),
+                scale: 1.0,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+impl Tab for FakeTab {
+    fn path(&self) -> &Path {
+        &self.location.pdf_path
+    }
+
+    fn in_memory(&self) -> bool {
+        false
+    }
+
+    fn location(&self) -> JumpLocation {
+        self.location.clone()
+    }
+
+    fn set_location(&mut self, location: &JumpLocation) -> iced::Task<PdfMessage> {
+        self.location.translation = location.translation;
+        self.location.scale = location.scale;
+        iced::Task::none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tabs showing `paths`, the last one active like after opening them one by one.
+    fn tabs_with(paths: &[&str]) -> Tabs<FakeTab> {
+        let mut tabs = Tabs::new();
+        for path in paths {
+            let idx = tabs.push(FakeTab::new(path));
+            tabs.open(idx);
+            tabs.history.activate(idx);
+        }
+        tabs
+    }
+
+    fn active(tabs: &Tabs<FakeTab>) -> &Path {
+        tabs.pdfs[tabs.active].path()
+    }
+
+    #[test]
+    fn opened_documents_become_the_active_tab() {
+        let tabs = tabs_with(&["a.pdf", "b.pdf"]);
+        assert_eq!(tabs.pdfs.len(), 2);
+        assert_eq!(active(&tabs), Path::new("b.pdf"));
+    }
+
+    #[test]
+    fn closing_tabs_keeps_the_active_document() {
+        let mut tabs = tabs_with(&["a.pdf", "b.pdf", "c.pdf"]);
+        let closed = tabs.remove(0);
+        assert_eq!(closed.path(), Path::new("a.pdf"));
+        assert_eq!(active(&tabs), Path::new("c.pdf"));
+        tabs.remove(tabs.active);
+        assert_eq!(tabs.active, 0);
+        assert_eq!(active(&tabs), Path::new("b.pdf"));
+        tabs.remove(0);
+        assert_eq!(tabs.active, 0);
+        assert!(tabs.pdfs.is_empty());
+    }
+
+    #[test]
+    fn switching_tabs_stops_at_either_end() {
+        let mut tabs = tabs_with(&["a.pdf", "b.pdf", "c.pdf"]);
+        tabs.step(&[0, 1, 2], 1);
+        assert_eq!(tabs.active, 2);
+        tabs.step(&[0, 1, 2], -5);
+        assert_eq!(tabs.active, 0);
+        // Steps follow the order the tabs are shown in
+        tabs.step(&[0, 2, 1], 1);
+        assert_eq!(tabs.active, 2);
+        tabs.open(7);
+        assert_eq!(tabs.active, 2);
+    }
+
+    #[test]
+    fn switching_to_the_previous_tab() {
+        let mut tabs = tabs_with(&["a.pdf", "b.pdf", "c.pdf"]);
+        tabs.history.step(1);
+        tabs.finish_switch();
+        assert_eq!(active(&tabs), Path::new("b.pdf"));
+        // Closing a tab shifts the history along with the tabs
+        tabs.remove(0);
+        tabs.history.activate(tabs.active);
+        tabs.history.step(1);
+        tabs.finish_switch();
+        assert_eq!(active(&tabs), Path::new("c.pdf"));
+    }
+
+    #[test]
+    fn documents_are_found_by_path() {
+        let tabs = tabs_with(&["a.pdf", "b.pdf"]);
+        assert_eq!(tabs.position(Path::new("a.pdf")), Some(0));
+        assert_eq!(tabs.position(Path::new("c.pdf")), None);
+    }
+}
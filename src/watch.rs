@@ -1,24 +1,180 @@
-use std::{fs, path::PathBuf, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use async_watcher::{AsyncDebouncer, notify::RecursiveMode};
+use async_watcher::{
+    AsyncDebouncer, DebouncedEvent,
+    notify::{
+        self, EventKind, RecommendedWatcher, RecursiveMode,
+        event::{AccessKind, AccessMode, ModifyKind, RenameMode},
+    },
+};
 use iced::{
     futures::{SinkExt, Stream, channel::mpsc},
     stream,
 };
-use tokio::sync::mpsc as tokio_mpsc;
+use tokio::{sync::mpsc as tokio_mpsc, time::Instant};
+use tracing::error;
+
+use crate::error::MiroError;
+
+const DEBOUNCE: Duration = Duration::from_millis(200);
+/// How long to wait before retrying something that failed once, doubled after every failure
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Failed attempts to watch a file before giving up on it and telling the user
+const MAX_ATTEMPTS: u32 = 10;
+/// How long the size of a new pdf has to stay the same before it is reported, so files that are
+/// written in several steps aren't opened half done
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+type FileEvents = Result<Vec<DebouncedEvent>, Vec<notify::Error>>;
 
 #[derive(Debug)]
 pub enum WatchMessage {
     StartWatch(PathBuf),
     StopWatch(PathBuf),
+    /// Report pdfs showing up directly inside the directory
+    StartWatchDirectory(PathBuf),
 }
 
 #[derive(Debug, Clone)]
 pub enum WatchNotification {
     Ready(tokio_mpsc::Sender<WatchMessage>),
     Changed(PathBuf),
+    /// A pdf was added to a watched directory
+    Created(PathBuf),
+    Error(MiroError),
+}
+
+/// When to try again after `attempts` failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Backoff {
+    attempts: u32,
+    at: Instant,
+}
+
+impl Backoff {
+    fn now(now: Instant) -> Self {
+        Self {
+            attempts: 0,
+            at: now,
+        }
+    }
+
+    fn failed(&mut self, now: Instant) {
+        self.attempts += 1;
+        self.at = now + backoff_delay(self.attempts);
+    }
+}
+
+/// A pdf that showed up in a watched directory and might still be written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NewPdf {
+    len: u64,
+    check_at: Instant,
+}
+
+impl NewPdf {
+    fn new(path: &Path, now: Instant) -> Self {
+        Self {
+            len: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            check_at: now + SETTLE_DELAY,
+        }
+    }
+}
+
+/// Removes the pdfs whose size hasn't changed since they were last checked and returns them.
+/// Pdfs that are gone are forgotten, the others are checked again later.
+fn settled_pdfs(new_pdfs: &mut HashMap<PathBuf, NewPdf>, now: Instant) -> Vec<PathBuf> {
+    let mut settled = vec![];
+    new_pdfs.retain(|path, pdf| {
+        if pdf.check_at > now {
+            return true;
+        }
+        match fs::metadata(path) {
+            Ok(m) if m.len() == pdf.len => {
+                settled.push(path.clone());
+                false
+            }
+            Ok(m) => {
+                *pdf = NewPdf {
+                    len: m.len(),
+                    check_at: now + SETTLE_DELAY,
+                };
+                true
+            }
+            Err(_) => false,
+        }
+    });
+    settled
 }
 
+fn backoff_delay(attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    INITIAL_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+struct Watcher {
+    debouncer: AsyncDebouncer<RecommendedWatcher>,
+    events: tokio_mpsc::Receiver<FileEvents>,
+}
+
+impl Watcher {
+    async fn new() -> Result<Self, MiroError> {
+        let (debouncer, events) = AsyncDebouncer::new_with_channel(DEBOUNCE, Some(DEBOUNCE))
+            .await
+            .map_err(|e| MiroError::Watch(format!("Couldn't start the file watcher: {e}")))?;
+        Ok(Self { debouncer, events })
+    }
+
+    fn watch(&mut self, path: &Path, mode: RecursiveMode) -> Result<(), MiroError> {
+        let canonical = fs::canonicalize(path).map_err(|e| MiroError::file(path, e))?;
+        self.debouncer
+            .watcher()
+            .watch(&canonical, mode)
+            .map_err(|e| MiroError::file(path, e))
+    }
+}
+
+/// Whether `kind` means a pdf showed up at `path` in one of the `directories`. Browsers often
+/// create an empty file first and move the download over it once it's done, so empty files don't
+/// count yet. Others write the file in place, see [settled_pdfs].
+fn is_new_pdf(kind: EventKind, path: &Path, directories: &HashSet<PathBuf>) -> bool {
+    let created = matches!(
+        kind,
+        EventKind::Create(_)
+            | EventKind::Modify(ModifyKind::Name(
+                RenameMode::To | RenameMode::Both | RenameMode::Any
+            ))
+    );
+    created
+        && path.parent().is_some_and(|dir| directories.contains(dir))
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+        && fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() > 0)
+}
+
+async fn next_file_events(watcher: &mut Option<Watcher>) -> Option<FileEvents> {
+    match watcher {
+        Some(watcher) => watcher.events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+enum Input {
+    App(Option<WatchMessage>),
+    Files(Option<FileEvents>),
+    Retry,
+}
+
+/// Watches the open files for changes and the directories for new pdfs. Files that can't be
+/// watched, for example because they are being replaced right now, are retried with a growing
+/// delay. The stream only ends once the app drops its sender.
 pub fn file_watcher() -> impl Stream<Item = WatchNotification> {
     stream::channel(
         100,
@@ -26,44 +182,244 @@ pub fn file_watcher() -> impl Stream<Item = WatchNotification> {
             let (sender, mut receiver) = tokio_mpsc::channel(100);
             let _ = output.send(WatchNotification::Ready(sender)).await;
 
-            let (mut debouncer, mut file_events) = AsyncDebouncer::new_with_channel(
-                Duration::from_millis(200),
-                Some(Duration::from_millis(200)),
-            )
-            .await
-            .unwrap();
+            let mut watcher: Option<Watcher> = None;
+            // Set while there is no watcher
+            let mut watcher_retry = Some(Backoff::now(Instant::now()));
+            // Every file the app wants watched, with a retry while it isn't. Files given up on
+            // have neither.
+            let mut files: HashMap<PathBuf, Option<Backoff>> = HashMap::new();
+            // The entries of `files` that are directories, with the canonical paths events use
+            let mut directories: HashSet<PathBuf> = HashSet::new();
+            // Pdfs added to the directories which are reported once they are written
+            let mut new_pdfs: HashMap<PathBuf, NewPdf> = HashMap::new();
 
             loop {
-                tokio::select! {
-                    Some(msg) = receiver.recv() => {
-                        match msg {
-                            WatchMessage::StartWatch(path_buf) => {
-                                let canonical = fs::canonicalize(path_buf).unwrap();
-                                debouncer.watcher().watch(&canonical, RecursiveMode::Recursive).unwrap()
+                let now = Instant::now();
+                if let Some(retry) = watcher_retry.as_mut()
+                    && retry.at <= now
+                {
+                    match Watcher::new().await {
+                        Ok(new) => {
+                            watcher = Some(new);
+                            watcher_retry = None;
+                            for retry in files.values_mut() {
+                                *retry = Some(Backoff::now(now));
                             }
-                            WatchMessage::StopWatch(path_buf) => {
-                                let canonical = fs::canonicalize(path_buf).unwrap();
-                                debouncer.watcher().unwatch(&canonical).unwrap()
+                        }
+                        Err(e) => {
+                            error!("{e}");
+                            if retry.attempts == 0 {
+                                let _ = output.send(WatchNotification::Error(e)).await;
                             }
+                            retry.failed(now);
                         }
                     }
-                    Some(file_event) = file_events.recv() => {
-                        match file_event {
-                            Ok(events) => {
-                                for e in &events {
-                                    if let async_watcher::notify::EventKind::Modify(_) = e.event.kind {
-                                        let _ = output.send(WatchNotification::Changed(e.event.paths[0].clone())).await;
+                }
+
+                if let Some(watcher) = watcher.as_mut() {
+                    for (path, retry) in files.iter_mut() {
+                        let Some(backoff) = retry.as_mut().filter(|b| b.at <= now) else {
+                            continue;
+                        };
+                        // Files in subdirectories of watched directories aren't reported
+                        let mode = if directories.contains(path) {
+                            RecursiveMode::NonRecursive
+                        } else {
+                            RecursiveMode::Recursive
+                        };
+                        match watcher.watch(path, mode) {
+                            Ok(()) => {
+                                // The file was replaced while it wasn't watched
+                                if backoff.attempts > 0 {
+                                    let _ =
+                                        output.send(WatchNotification::Changed(path.clone())).await;
+                                }
+                                *retry = None;
+                            }
+                            Err(e) => {
+                                backoff.failed(now);
+                                if backoff.attempts >= MAX_ATTEMPTS {
+                                    error!("Gave up watching {path:?}: {e}");
+                                    *retry = None;
+                                    let _ = output.send(WatchNotification::Error(e)).await;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                for path in settled_pdfs(&mut new_pdfs, now) {
+                    let _ = output.send(WatchNotification::Created(path)).await;
+                }
+
+                let wakeup = watcher_retry
+                    .iter()
+                    .chain(files.values().flatten().filter(|_| watcher.is_some()))
+                    .map(|b| b.at)
+                    .chain(new_pdfs.values().map(|pdf| pdf.check_at))
+                    .min();
+                let input = tokio::select! {
+                    msg = receiver.recv() => Input::App(msg),
+                    events = next_file_events(&mut watcher) => Input::Files(events),
+                    _ = tokio::time::sleep_until(wakeup.unwrap_or(now)), if wakeup.is_some() => {
+                        Input::Retry
+                    }
+                };
+
+                match input {
+                    // The app is shutting down
+                    Input::App(None) => return,
+                    Input::App(Some(WatchMessage::StartWatch(path))) => {
+                        files.insert(path, Some(Backoff::now(Instant::now())));
+                    }
+                    Input::App(Some(WatchMessage::StartWatchDirectory(path))) => {
+                        match fs::canonicalize(&path) {
+                            Ok(canonical) => {
+                                directories.insert(canonical.clone());
+                                files.insert(canonical, Some(Backoff::now(Instant::now())));
+                            }
+                            Err(e) => {
+                                let _ = output
+                                    .send(WatchNotification::Error(MiroError::file(path, e)))
+                                    .await;
+                            }
+                        }
+                    }
+                    Input::App(Some(WatchMessage::StopWatch(path))) => {
+                        directories.remove(&path);
+                        if let Some(None) = files.remove(&path)
+                            && let Some(watcher) = watcher.as_mut()
+                            && let Err(e) = watcher.debouncer.watcher().unwatch(&path)
+                        {
+                            error!("Couldn't stop watching {path:?}: {e}");
+                        }
+                    }
+                    Input::Files(None) => {
+                        error!("The file watcher stopped, restarting it");
+                        watcher = None;
+                        watcher_retry = Some(Backoff::now(Instant::now()));
+                    }
+                    Input::Files(Some(Ok(events))) => {
+                        for e in &events {
+                            // Renames list the old path first and the new one last
+                            if let Some(path) = e.event.paths.last()
+                                && is_new_pdf(e.event.kind, path, &directories)
+                                // Replacing a pdf that is already open shows up as a new one
+                                && !files.keys().any(|open| {
+                                    fs::canonicalize(open).is_ok_and(|open| open == *path)
+                                })
+                            {
+                                new_pdfs
+                                    .entry(path.clone())
+                                    .or_insert_with(|| NewPdf::new(path, Instant::now()));
+                            }
+                            // Only some platforms report when a file is closed after writing it
+                            if let EventKind::Access(AccessKind::Close(AccessMode::Write)) =
+                                e.event.kind
+                                && let Some(path) = e.event.paths.first()
+                                && new_pdfs.remove(path).is_some()
+                            {
+                                let _ = output.send(WatchNotification::Created(path.clone())).await;
+                            }
+                            let Some(path) = e.event.paths.first() else {
+                                continue;
+                            };
+                            match e.event.kind {
+                                EventKind::Modify(_) => {
+                                    let _ =
+                                        output.send(WatchNotification::Changed(path.clone())).await;
+                                }
+                                // Editors and build tools often replace the file instead of
+                                // writing to it, which ends the watch on the old one
+                                EventKind::Remove(_) => {
+                                    if let Some(retry) = files.get_mut(path) {
+                                        let mut backoff = Backoff::now(Instant::now());
+                                        backoff.failed(Instant::now());
+                                        *retry = Some(backoff);
                                     }
                                 }
+                                _ => {}
                             }
-                            Err(_) => todo!(),
                         }
                     }
-                    else => {
-                        panic!("File watcher channels closed, this shouldn't happen.");
+                    Input::Files(Some(Err(errors))) => {
+                        for e in errors {
+                            error!("File watcher error: {e}");
+                        }
                     }
+                    Input::Retry => {}
                 }
             }
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        assert_eq!(backoff_delay(1), INITIAL_BACKOFF);
+        assert_eq!(backoff_delay(2), INITIAL_BACKOFF * 2);
+        assert_eq!(backoff_delay(4), INITIAL_BACKOFF * 8);
+        assert_eq!(backoff_delay(MAX_ATTEMPTS), MAX_BACKOFF);
+        assert_eq!(backoff_delay(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn only_finished_pdfs_in_watched_directories_are_new() -> std::io::Result<()> {
+        let dir = fs::canonicalize(std::env::temp_dir())?.join("miro-watch-dir-test");
+        fs::create_dir_all(dir.join("nested"))?;
+        let directories = HashSet::from([dir.clone()]);
+        let created = EventKind::Create(notify::event::CreateKind::File);
+        fs::write(dir.join("paper.PDF"), b"%PDF-1.7")?;
+        fs::write(dir.join("empty.pdf"), b"")?;
+        fs::write(dir.join("notes.txt"), b"notes")?;
+        fs::write(dir.join("nested/paper.pdf"), b"%PDF-1.7")?;
+
+        assert!(is_new_pdf(created, &dir.join("paper.PDF"), &directories));
+        let renamed = EventKind::Modify(ModifyKind::Name(RenameMode::To));
+        assert!(is_new_pdf(renamed, &dir.join("paper.PDF"), &directories));
+        assert!(!is_new_pdf(created, &dir.join("empty.pdf"), &directories));
+        assert!(!is_new_pdf(created, &dir.join("notes.txt"), &directories));
+        assert!(!is_new_pdf(
+            created,
+            &dir.join("nested/paper.pdf"),
+            &directories
+        ));
+        let written = EventKind::Modify(ModifyKind::Data(notify::event::DataChange::Any));
+        assert!(!is_new_pdf(written, &dir.join("paper.PDF"), &directories));
+
+        fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn new_pdfs_are_reported_once_their_size_settles() -> std::io::Result<()> {
+        let dir = fs::canonicalize(std::env::temp_dir())?.join("miro-watch-settle-test");
+        fs::create_dir_all(&dir)?;
+        let (done, growing, gone) = (
+            dir.join("done.pdf"),
+            dir.join("growing.pdf"),
+            dir.join("gone.pdf"),
+        );
+        fs::write(&done, b"%PDF-1.7")?;
+        fs::write(&growing, b"%PDF")?;
+        let now = Instant::now();
+        let mut new_pdfs = HashMap::from([
+            (done.clone(), NewPdf::new(&done, now)),
+            (growing.clone(), NewPdf::new(&growing, now)),
+            (gone.clone(), NewPdf::new(&gone, now)),
+        ]);
+
+        assert!(settled_pdfs(&mut new_pdfs, now).is_empty());
+        fs::write(&growing, b"%PDF-1.7")?;
+        let later = now + SETTLE_DELAY;
+        assert_eq!(settled_pdfs(&mut new_pdfs, later), [done]);
+        assert_eq!(new_pdfs.len(), 1);
+        assert_eq!(settled_pdfs(&mut new_pdfs, later + SETTLE_DELAY), [growing]);
+        assert!(new_pdfs.is_empty());
+
+        fs::remove_dir_all(&dir)
+    }
+}